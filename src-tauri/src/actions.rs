@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+pub(crate) static ACTIONS_MUTATION_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+/// Where an action is offered in the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionContext {
+    File,
+    Directory,
+    Connection,
+}
+
+/// A user-defined command template such as `du -sh {path}` or `ssh-copy-id {user}@{host}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomAction {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub contexts: Vec<ActionContext>,
+    /// Limit the action to one connection; `None` offers it everywhere.
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    /// Ask the user before running.
+    #[serde(default)]
+    pub confirm: bool,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionsData {
+    pub actions: Vec<CustomAction>,
+}
+
+/// Values substituted into a template. Every value is shell-quoted on the way in.
+#[derive(Debug, Clone, Default)]
+pub struct ActionVars {
+    pub path: Option<String>,
+    pub host: String,
+    pub user: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quoting {
+    Posix,
+    PowerShell,
+}
+
+impl Quoting {
+    fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Posix => crate::ai::tool_exec_support::shell_quote(value),
+            Self::PowerShell => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+const PLACEHOLDERS: &[&str] = &["path", "name", "dir", "host", "user", "port"];
+const FILE_PLACEHOLDERS: &[&str] = &["path", "name", "dir"];
+
+/// Splits a template into literal text and `{placeholder}` names. `{{` and `}}`
+/// are literal braces, so shell constructs like `${HOME}` can be written `${{HOME}}`.
+fn tokenize(template: &str) -> Result<Vec<(bool, String)>, String> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => name.push(ch),
+                        None => return Err("Unclosed '{' in action command".to_string()),
+                    }
+                }
+                if !literal.is_empty() {
+                    tokens.push((false, std::mem::take(&mut literal)));
+                }
+                tokens.push((true, name.trim().to_string()));
+            }
+            '}' => {
+                return Err(
+                    "Unmatched '}' in action command (use '}}' for a literal brace)".to_string(),
+                )
+            }
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push((false, literal));
+    }
+    Ok(tokens)
+}
+
+pub fn validate(action: &CustomAction) -> Result<(), String> {
+    if action.name.trim().is_empty() {
+        return Err("Action name must not be empty.".to_string());
+    }
+    if action.contexts.is_empty() {
+        return Err("Pick at least one place to show the action.".to_string());
+    }
+    crate::ai::tool_exec_support::validate_command(&action.command)?;
+    let only_connection = action
+        .contexts
+        .iter()
+        .all(|context| *context == ActionContext::Connection);
+    for (is_placeholder, name) in tokenize(&action.command)? {
+        if !is_placeholder {
+            continue;
+        }
+        if !PLACEHOLDERS.contains(&name.as_str()) {
+            return Err(format!(
+                "Unknown placeholder '{{{}}}'. Available: {}",
+                name,
+                PLACEHOLDERS
+                    .iter()
+                    .map(|p| format!("{{{}}}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        if only_connection && FILE_PLACEHOLDERS.contains(&name.as_str()) {
+            return Err(format!(
+                "'{{{}}}' needs a file; connection-only actions can't use it.",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn split_path(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind(['/', '\\']) {
+        Some(0) => ("/".to_string(), trimmed[1..].to_string()),
+        Some(idx) => (trimmed[..idx].to_string(), trimmed[idx + 1..].to_string()),
+        None => (".".to_string(), trimmed.to_string()),
+    }
+}
+
+/// Expands the template with quoted values and rejects commands that trip the
+/// same dangerous-command guard used for AI tool execution. The guard looks at
+/// the unquoted expansion too, so `rm -rf {dir}` with `/` selected is caught.
+pub fn render(template: &str, vars: &ActionVars, quoting: Quoting) -> Result<String, String> {
+    let mut out = String::new();
+    let mut unquoted = String::new();
+    for (is_placeholder, token) in tokenize(template)? {
+        if !is_placeholder {
+            out.push_str(&token);
+            unquoted.push_str(&token);
+            continue;
+        }
+        let value = match token.as_str() {
+            "host" => vars.host.clone(),
+            "user" => vars.user.clone(),
+            "port" => vars.port.to_string(),
+            "path" | "name" | "dir" => {
+                let path = vars
+                    .path
+                    .as_deref()
+                    .ok_or_else(|| format!("'{{{}}}' needs a selected file", token))?;
+                let (dir, name) = split_path(path);
+                match token.as_str() {
+                    "path" => path.to_string(),
+                    "name" => name,
+                    _ => dir,
+                }
+            }
+            other => return Err(format!("Unknown placeholder '{{{}}}'", other)),
+        };
+        out.push_str(&quoting.quote(&value));
+        unquoted.push_str(&value);
+    }
+    if crate::ai::tool_exec_support::is_dangerous_command(&out)
+        || crate::ai::tool_exec_support::is_dangerous_command(&unquoted)
+    {
+        return Err("Blocked: the expanded command matches a destructive pattern.".to_string());
+    }
+    Ok(out)
+}
+
+pub struct ActionsManager {
+    file_path: PathBuf,
+}
+
+impl ActionsManager {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let file_path = app_data_dir.join("actions.json");
+        Self { file_path }
+    }
+
+    pub async fn list(&self) -> Result<Vec<CustomAction>, String> {
+        let _guard = ACTIONS_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        self.list_from_disk()
+    }
+
+    pub async fn get(&self, id: &str) -> Result<CustomAction, String> {
+        self.list()
+            .await?
+            .into_iter()
+            .find(|action| action.id == id)
+            .ok_or_else(|| format!("Action '{}' not found", id))
+    }
+
+    fn list_from_disk(&self) -> Result<Vec<CustomAction>, String> {
+        if !self.file_path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.file_path).map_err(|e| e.to_string())?;
+        let data: ActionsData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        Ok(data.actions)
+    }
+
+    pub async fn save(&self, action: CustomAction) -> Result<(), String> {
+        validate(&action)?;
+        let _guard = ACTIONS_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        let mut actions = self.list_from_disk()?;
+        let now = current_unix_millis();
+
+        if let Some(pos) = actions.iter().position(|a| a.id == action.id) {
+            let created_at = actions[pos].created_at.or(action.created_at).or(Some(now));
+            actions[pos] = CustomAction {
+                created_at,
+                updated_at: Some(now),
+                ..action
+            };
+        } else {
+            actions.push(CustomAction {
+                created_at: action.created_at.or(Some(now)),
+                updated_at: Some(now),
+                ..action
+            });
+        }
+
+        write_actions_atomic(&self.file_path, &ActionsData { actions })
+    }
+
+    pub async fn delete(&self, id: String) -> Result<(), String> {
+        let _guard = ACTIONS_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        let mut actions = self.list_from_disk()?;
+        actions.retain(|a| a.id != id);
+        write_actions_atomic(&self.file_path, &ActionsData { actions })
+    }
+}
+
+fn write_actions_atomic(path: &Path, data: &ActionsData) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+    crate::atomic_io::durable_replace(path, json.as_bytes())
+        .map_err(|e| format!("Failed to write actions file: {e}"))
+}
+
+fn current_unix_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(path: Option<&str>) -> ActionVars {
+        ActionVars {
+            path: path.map(str::to_string),
+            host: "db.internal".to_string(),
+            user: "deploy".to_string(),
+            port: 2222,
+        }
+    }
+
+    fn action(command: &str, contexts: Vec<ActionContext>) -> CustomAction {
+        CustomAction {
+            id: "a1".to_string(),
+            name: "Test".to_string(),
+            command: command.to_string(),
+            contexts,
+            connection_id: None,
+            confirm: false,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn render_quotes_every_value() {
+        let rendered = render(
+            "du -sh {path} && echo {name} in {dir}",
+            &vars(Some("/srv/it's here/app")),
+            Quoting::Posix,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "du -sh '/srv/it'\\''s here/app' && echo 'app' in '/srv/it'\\''s here'"
+        );
+    }
+
+    #[test]
+    fn render_keeps_escaped_braces_literal() {
+        let rendered = render(
+            "echo ${{HOME}} {user}@{host}:{port}",
+            &vars(None),
+            Quoting::Posix,
+        )
+        .unwrap();
+        assert_eq!(rendered, "echo ${HOME} 'deploy'@'db.internal':'2222'");
+    }
+
+    #[test]
+    fn render_requires_a_path_for_file_placeholders() {
+        assert!(render("cat {path}", &vars(None), Quoting::Posix).is_err());
+    }
+
+    #[test]
+    fn render_blocks_dangerous_expansions() {
+        assert!(render("rm -rf {path}", &vars(Some("/")), Quoting::Posix).is_err());
+        assert!(render("rm -rf / {name}", &vars(Some("/x")), Quoting::Posix).is_err());
+        assert!(render("rm -rf {path}", &vars(Some("/tmp/x")), Quoting::Posix).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_and_misplaced_placeholders() {
+        assert!(validate(&action("ls {folder}", vec![ActionContext::Directory])).is_err());
+        assert!(validate(&action("cat {path}", vec![ActionContext::Connection])).is_err());
+        assert!(validate(&action("uptime", vec![])).is_err());
+        assert!(validate(&action("cat {path}", vec![ActionContext::File])).is_ok());
+    }
+}
@@ -4,13 +4,14 @@ pub(crate) mod brain;
 mod agent_loop_support;
 mod config;
 mod context;
+pub(crate) mod embeddings;
 mod agent_planning;
 mod model_catalog;
-mod policy;
+pub(crate) mod policy;
 mod providers;
 mod prompts;
 mod tool_command_exec;
-mod tool_exec_support;
+pub(crate) mod tool_exec_support;
 mod tool_file_ops;
 mod tool_schemas;
 mod translate;
@@ -143,7 +143,7 @@ async fn run_inner(
                         summary,
                         &actions,
                     ) {
-                        eprintln!("[brain] failed to finalize session: {}", e);
+                        crate::log_error!("[brain] failed to finalize session: {}", e);
                     }
                 }
                 Some(dir.to_string_lossy().to_string())
@@ -67,7 +67,7 @@ pub fn init_session(
     let session_dir = brain_dir.join(&conn_folder).join(&session_name);
 
     if let Err(e) = std::fs::create_dir_all(&session_dir) {
-        eprintln!("[brain] failed to create session dir {:?}: {}", session_dir, e);
+        crate::log_error!("[brain] failed to create session dir {:?}: {}", session_dir, e);
         return None;
     }
 
@@ -97,7 +97,7 @@ pub fn finalize_session(
 pub fn save_artifact(session_dir: &Path, tool_call_id: &str, content: &str) -> Option<String> {
     let artifacts_dir = session_dir.join("artifacts");
     if let Err(e) = std::fs::create_dir_all(&artifacts_dir) {
-        eprintln!("[brain] failed to create artifacts dir {:?}: {}", artifacts_dir, e);
+        crate::log_error!("[brain] failed to create artifacts dir {:?}: {}", artifacts_dir, e);
         return None;
     }
 
@@ -109,14 +109,14 @@ pub fn save_artifact(session_dir: &Path, tool_call_id: &str, content: &str) -> O
         .collect();
 
     if safe_id.is_empty() {
-        eprintln!("[brain] failed to save artifact: invalid tool_call_id '{}'", tool_call_id);
+        crate::log_error!("[brain] failed to save artifact: invalid tool_call_id '{}'", tool_call_id);
         return None;
     }
 
     // Write full output
     let file_path = artifacts_dir.join(format!("{}.txt", safe_id));
     if let Err(e) = std::fs::write(&file_path, content) {
-        eprintln!("[brain] failed to write artifact {:?}: {}", file_path, e);
+        crate::log_error!("[brain] failed to write artifact {:?}: {}", file_path, e);
         return None;
     }
     
@@ -42,7 +42,7 @@ pub fn read_ai_config(app: &AppHandle) -> AiConfig {
                         // Soft-parse: fill missing fields from defaults instead of
                         // throwing away a valid provider selection (e.g. mistral without `enabled`).
                         #[cfg(debug_assertions)]
-                        eprintln!(
+                        crate::log_error!(
                             "[zync/ai] Partial AI config parse failed ({e}); merging with defaults"
                         );
                         let defaults = serde_json::to_value(AiConfig::default())
@@ -64,16 +64,16 @@ pub fn read_ai_config(app: &AppHandle) -> AiConfig {
                             return merge_secret_keys(app, config);
                         }
                         #[cfg(debug_assertions)]
-                        eprintln!("[zync/ai] Failed to recover AI config after merge with defaults");
+                        crate::log_error!("[zync/ai] Failed to recover AI config after merge with defaults");
                     }
                 }
             } else {
                 #[cfg(debug_assertions)]
-                eprintln!("[zync/ai] effective settings has no 'ai' key, using defaults");
+                crate::log_error!("[zync/ai] effective settings has no 'ai' key, using defaults");
             }
         }
         #[cfg(debug_assertions)]
-        Err(e) => eprintln!("[zync/ai] Failed to read effective settings: {e}"),
+        Err(e) => crate::log_error!("[zync/ai] Failed to read effective settings: {e}"),
         #[cfg(not(debug_assertions))]
         Err(_) => {}
     }
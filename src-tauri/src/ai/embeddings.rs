@@ -0,0 +1,191 @@
+//! Text embeddings through the configured AI provider, with a built-in
+//! fallback.
+//!
+//! OpenAI, Mistral, Gemini and Ollama have embedding endpoints; Claude and
+//! Groq do not, so with those providers (or AI turned off, or
+//! `embeddingModel: "local"`) texts are embedded locally by hashing their
+//! words and character trigrams into a fixed-size vector. Local vectors only
+//! match on shared word pieces, not meaning, but need no network.
+
+use super::{providers, AiConfig};
+
+/// Texts sent per embeddings request.
+const BATCH_SIZE: usize = 64;
+/// Dimensions of the local hashed vectors.
+const LOCAL_DIMENSIONS: usize = 512;
+pub(crate) const LOCAL_MODEL: &str = "local";
+
+/// Where vectors come from. `id()` names the vector space, so vectors from
+/// different backends are never compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Embedder {
+    OpenAiCompat {
+        provider: &'static str,
+        base_url: &'static str,
+        model: String,
+    },
+    Gemini {
+        model: String,
+    },
+    Ollama {
+        model: String,
+    },
+    Local,
+}
+
+impl Embedder {
+    pub(crate) fn for_config(config: &AiConfig) -> Self {
+        let configured = config
+            .embedding_model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty());
+        if !config.enabled || configured == Some(LOCAL_MODEL) {
+            return Self::Local;
+        }
+        let model = |default: &str| configured.unwrap_or(default).to_string();
+        match config.provider.as_str() {
+            "openai" => Self::OpenAiCompat {
+                provider: "OpenAI",
+                base_url: "https://api.openai.com/v1",
+                model: model("text-embedding-3-small"),
+            },
+            "mistral" => Self::OpenAiCompat {
+                provider: "Mistral",
+                base_url: "https://api.mistral.ai/v1",
+                model: model("mistral-embed"),
+            },
+            "gemini" => Self::Gemini {
+                model: model("text-embedding-004"),
+            },
+            "ollama" => Self::Ollama {
+                model: model("nomic-embed-text"),
+            },
+            _ => Self::Local,
+        }
+    }
+
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Self::OpenAiCompat {
+                provider, model, ..
+            } => format!("{}:{}", provider.to_lowercase(), model),
+            Self::Gemini { model } => format!("gemini:{}", model),
+            Self::Ollama { model } => format!("ollama:{}", model),
+            Self::Local => format!("{}:hash{}", LOCAL_MODEL, LOCAL_DIMENSIONS),
+        }
+    }
+
+    /// One vector per input, in order.
+    pub(crate) async fn embed(
+        &self,
+        config: &AiConfig,
+        inputs: &[String],
+    ) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(inputs.len());
+        for batch in inputs.chunks(BATCH_SIZE) {
+            let embedded = match self {
+                Self::OpenAiCompat {
+                    provider,
+                    base_url,
+                    model,
+                } => {
+                    providers::openai_compat::embed(provider, base_url, model, config, batch)
+                        .await?
+                }
+                Self::Gemini { model } => providers::gemini::embed(config, model, batch).await?,
+                Self::Ollama { model } => providers::ollama::embed(config, model, batch).await?,
+                Self::Local => batch.iter().map(|text| local_embed(text)).collect(),
+            };
+            if embedded.len() != batch.len() {
+                return Err(format!(
+                    "Embedding provider returned {} vectors for {} texts",
+                    embedded.len(),
+                    batch.len()
+                ));
+            }
+            vectors.extend(embedded);
+        }
+        Ok(vectors)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Signed feature hashing of lowercase words (weight 2) and their
+/// character trigrams (weight 1), L2-normalized.
+pub(crate) fn local_embed(text: &str) -> Vec<f32> {
+    let mut vector = vec![0f32; LOCAL_DIMENSIONS];
+    let mut add = |feature: &str, weight: f32| {
+        let hash = fnv1a(feature.as_bytes());
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % LOCAL_DIMENSIONS as u64) as usize] += sign * weight;
+    };
+    let lower = text.to_lowercase();
+    for word in lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        add(word, 2.0);
+        let padded: Vec<char> = format!("^{}$", word).chars().collect();
+        for trigram in padded.windows(3) {
+            add(&trigram.iter().collect::<String>(), 1.0);
+        }
+    }
+    normalize(&mut vector);
+    vector
+}
+
+pub(crate) fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: &str, embedding_model: Option<&str>) -> AiConfig {
+        AiConfig {
+            provider: provider.to_string(),
+            embedding_model: embedding_model.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn picks_the_provider_embedding_model() {
+        assert_eq!(
+            Embedder::for_config(&config("openai", None)).id(),
+            "openai:text-embedding-3-small"
+        );
+        assert_eq!(
+            Embedder::for_config(&config("ollama", Some("mxbai-embed-large"))).id(),
+            "ollama:mxbai-embed-large"
+        );
+        assert_eq!(
+            Embedder::for_config(&config("claude", None)),
+            Embedder::Local
+        );
+        assert_eq!(
+            Embedder::for_config(&config("gemini", Some("local"))),
+            Embedder::Local
+        );
+    }
+
+    #[test]
+    fn local_vectors_share_word_pieces() {
+        let cosine = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        let query = local_embed("drop page caches");
+        let related = local_embed("sync; echo 3 > /proc/sys/vm/drop_caches");
+        let unrelated = local_embed("git log --oneline");
+        assert!(cosine(&query, &related) > cosine(&query, &unrelated));
+        assert!((cosine(&query, &query) - 1.0).abs() < 1e-5);
+    }
+}
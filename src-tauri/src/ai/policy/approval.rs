@@ -0,0 +1,194 @@
+//! Second-factor approval for dangerous commands written to terminals.
+//!
+//! Writes from AI runs, `terminal_write`, plugins and triggers ask
+//! [`submits_dangerous_command`] about the data before it reaches the PTY, and
+//! AI runs the model rated dangerous need approval regardless. Macro replay and
+//! a connection's startup commands are written as the user recorded or
+//! configured them and are not checked. A gated write goes through only with a
+//! token from [`ApprovalStore::confirm`], which is issued once the user has
+//! re-typed the target host into the dialog opened by the challenge event.
+//! Tokens are bound to one terminal and the exact data, expire quickly and are
+//! consumed on use, so a stray click on "Run" can never reach a shell on its own.
+
+use crate::ai::tool_exec_support::is_dangerous_command;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+const CHALLENGE_TTL: Duration = Duration::from_secs(120);
+const TOKEN_TTL: Duration = Duration::from_secs(60);
+/// Wrong hostnames before a challenge is thrown away.
+const MAX_ATTEMPTS: u32 = 3;
+
+pub static APPROVALS: LazyLock<ApprovalStore> = LazyLock::new(ApprovalStore::default);
+
+/// Whether writing `data` to a terminal submits a dangerous command line. Only
+/// lines ended by Enter count: keys typed one by one arrive without the line
+/// they end, so this catches pastes and programmatic writes, not typing.
+pub fn submits_dangerous_command(data: &str) -> bool {
+    let mut lines: Vec<&str> = data.split(['\r', '\n']).collect();
+    // The unterminated tail is still being edited.
+    lines.pop();
+    lines.into_iter().any(is_dangerous_command)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DangerousChallenge {
+    pub challenge_id: String,
+    pub term_id: String,
+    pub command: String,
+    /// What the user has to type.
+    pub host: String,
+    pub expires_in_ms: u64,
+}
+
+struct Pending {
+    term_id: String,
+    command: String,
+    host: String,
+    attempts: u32,
+    expires: Instant,
+}
+
+struct Grant {
+    term_id: String,
+    command: String,
+    expires: Instant,
+}
+
+#[derive(Default)]
+pub struct ApprovalStore {
+    challenges: Mutex<HashMap<String, Pending>>,
+    tokens: Mutex<HashMap<String, Grant>>,
+}
+
+impl ApprovalStore {
+    pub fn challenge(&self, term_id: &str, command: &str, host: &str) -> DangerousChallenge {
+        let challenge_id = uuid::Uuid::new_v4().to_string();
+        if let Ok(mut challenges) = self.challenges.lock() {
+            let now = Instant::now();
+            challenges.retain(|_, pending| pending.expires > now);
+            challenges.insert(
+                challenge_id.clone(),
+                Pending {
+                    term_id: term_id.to_string(),
+                    command: command.to_string(),
+                    host: host.to_string(),
+                    attempts: 0,
+                    expires: now + CHALLENGE_TTL,
+                },
+            );
+        }
+        DangerousChallenge {
+            challenge_id,
+            term_id: term_id.to_string(),
+            command: command.to_string(),
+            host: host.to_string(),
+            expires_in_ms: CHALLENGE_TTL.as_millis() as u64,
+        }
+    }
+
+    /// Exchanges a correctly re-typed hostname for a single-use token.
+    pub fn confirm(&self, challenge_id: &str, typed_host: &str) -> Result<String, String> {
+        let mut challenges = self
+            .challenges
+            .lock()
+            .map_err(|_| "Approval state poisoned".to_string())?;
+        let pending = challenges
+            .get_mut(challenge_id)
+            .filter(|pending| pending.expires > Instant::now())
+            .ok_or_else(|| "Approval request expired; run the command again".to_string())?;
+        if !typed_host.trim().eq_ignore_ascii_case(&pending.host) {
+            pending.attempts += 1;
+            if pending.attempts >= MAX_ATTEMPTS {
+                challenges.remove(challenge_id);
+                return Err("Hostname did not match; approval cancelled".to_string());
+            }
+            return Err(format!(
+                "Hostname did not match; type {} to confirm",
+                pending.host
+            ));
+        }
+        let Some(pending) = challenges.remove(challenge_id) else {
+            return Err("Approval request expired; run the command again".to_string());
+        };
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut tokens = self
+            .tokens
+            .lock()
+            .map_err(|_| "Approval state poisoned".to_string())?;
+        let now = Instant::now();
+        tokens.retain(|_, grant| grant.expires > now);
+        tokens.insert(
+            token.clone(),
+            Grant {
+                term_id: pending.term_id,
+                command: pending.command,
+                expires: now + TOKEN_TTL,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Spends `token` on running `command` in `term_id`.
+    pub fn consume(&self, token: &str, term_id: &str, command: &str) -> Result<(), String> {
+        let mut tokens = self
+            .tokens
+            .lock()
+            .map_err(|_| "Approval state poisoned".to_string())?;
+        let grant = tokens
+            .remove(token)
+            .filter(|grant| grant.expires > Instant::now())
+            .ok_or_else(|| "Approval token is invalid or expired".to_string())?;
+        if grant.term_id != term_id || grant.command != command {
+            return Err("Approval token was issued for a different command".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_requires_hostname_and_is_single_use() {
+        let store = ApprovalStore::default();
+        let challenge = store.challenge("t1", "rm -rf /srv/app", "prod-db-1");
+        assert!(store.confirm(&challenge.challenge_id, "prod-db-2").is_err());
+        let token = store
+            .confirm(&challenge.challenge_id, " PROD-DB-1 ")
+            .unwrap();
+        // The challenge is spent once answered.
+        assert!(store.confirm(&challenge.challenge_id, "prod-db-1").is_err());
+        assert!(store.consume(&token, "t1", "rm -rf /srv").is_err());
+
+        let challenge = store.challenge("t1", "rm -rf /srv/app", "prod-db-1");
+        let token = store.confirm(&challenge.challenge_id, "prod-db-1").unwrap();
+        assert!(store.consume(&token, "t1", "rm -rf /srv/app").is_ok());
+        assert!(store.consume(&token, "t1", "rm -rf /srv/app").is_err());
+    }
+
+    #[test]
+    fn only_submitted_lines_are_checked() {
+        assert!(submits_dangerous_command("rm -rf /srv/app\r"));
+        assert!(submits_dangerous_command(
+            "ls\nmkfs.ext4 /dev/sdb\nuptime\n"
+        ));
+        assert!(!submits_dangerous_command("rm -rf /srv/app"));
+        assert!(!submits_dangerous_command("\r"));
+        assert!(!submits_dangerous_command("ls -la\r"));
+    }
+
+    #[test]
+    fn too_many_wrong_hostnames_cancel_the_challenge() {
+        let store = ApprovalStore::default();
+        let challenge = store.challenge("t1", "mkfs.ext4 /dev/sda", "nas");
+        for _ in 0..MAX_ATTEMPTS {
+            assert!(store.confirm(&challenge.challenge_id, "nope").is_err());
+        }
+        assert!(store.confirm(&challenge.challenge_id, "nas").is_err());
+    }
+}
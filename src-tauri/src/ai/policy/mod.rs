@@ -1 +1,2 @@
+pub mod approval;
 pub mod errors;
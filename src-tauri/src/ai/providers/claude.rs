@@ -313,7 +313,9 @@ pub async fn get_models(config: &AiConfig) -> Result<Vec<String>, String> {
         .api_key()
         .ok_or_else(|| "No API key configured".to_string())?;
 
-    let client = reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+
+    let client = crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()
         .map_err(|e| e.to_string())?;
@@ -326,7 +326,9 @@ pub async fn get_models(config: &AiConfig) -> Result<Vec<String>, String> {
         .api_key()
         .ok_or_else(|| "No API key configured".to_string())?;
 
-    let client = reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+
+    let client = crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()
         .map_err(|e| e.to_string())?;
@@ -388,3 +390,57 @@ pub async fn get_models(config: &AiConfig) -> Result<Vec<String>, String> {
     models.sort_by(|a, b| b.cmp(a));
     Ok(models)
 }
+
+/// Embeddings through `batchEmbedContents`, in input order.
+pub async fn embed(
+    config: &AiConfig,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let api_key = config
+        .api_key()
+        .ok_or_else(|| "Gemini API key not configured. Go to Settings -> AI.".to_string())?;
+    let client = make_client().await?;
+    let requests: Vec<serde_json::Value> = inputs
+        .iter()
+        .map(|text| {
+            serde_json::json!({
+                "model": format!("models/{}", model),
+                "content": { "parts": [{ "text": text }] }
+            })
+        })
+        .collect();
+    let url = format!(
+        "https://generativelanguage.googleapis.com/v1beta/models/{}:batchEmbedContents?key={}",
+        model, api_key
+    );
+    let response = client
+        .post(&url)
+        .json(&serde_json::json!({ "requests": requests }))
+        .send()
+        .await
+        .map_err(|e| sanitize_error(&e.to_string()))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err("Invalid Gemini API key. Check Settings -> AI.".to_string());
+    }
+    if !status.is_success() {
+        let detail = read_error_body(response).await;
+        return Err(format!("Gemini embeddings ({}): {}", model, detail));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Embedding {
+        values: Vec<f32>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Body {
+        embeddings: Vec<Embedding>,
+    }
+    let body: Body = response
+        .json()
+        .await
+        .map_err(|e| sanitize_error(&e.to_string()))?;
+    Ok(body.embeddings.into_iter().map(|e| e.values).collect())
+}
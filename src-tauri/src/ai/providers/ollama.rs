@@ -284,7 +284,8 @@ pub async fn get_models(config: &AiConfig) -> Result<Vec<String>, String> {
         .ollama_url
         .as_deref()
         .unwrap_or("http://localhost:11434");
-    let client = reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+    let client = crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(5))
         .build()
         .map_err(|e| e.to_string())?;
@@ -320,3 +321,39 @@ pub async fn get_models(config: &AiConfig) -> Result<Vec<String>, String> {
     models.dedup();
     Ok(models)
 }
+
+/// Embeddings through `/api/embed`, in input order.
+pub async fn embed(
+    config: &AiConfig,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let base_url = config
+        .ollama_url
+        .as_deref()
+        .unwrap_or("http://localhost:11434");
+    let client = make_client().await?;
+    let response = client
+        .post(format!("{}/api/embed", base_url))
+        .json(&serde_json::json!({ "model": model, "input": inputs }))
+        .send()
+        .await
+        .map_err(|e| {
+            format!(
+                "Ollama not running. Install from ollama.com or run 'ollama serve'. ({})",
+                e
+            )
+        })?;
+
+    if !response.status().is_success() {
+        let detail = read_error_body(response).await;
+        return Err(format!("Ollama embeddings ({}): {}", model, detail));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Body {
+        embeddings: Vec<Vec<f32>>,
+    }
+    let body: Body = response.json().await.map_err(|e| e.to_string())?;
+    Ok(body.embeddings)
+}
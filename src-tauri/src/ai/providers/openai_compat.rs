@@ -363,7 +363,9 @@ pub async fn get_models(
         .api_key()
         .ok_or_else(|| format!("No {provider_name} API key configured"))?;
 
-    let client = reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+
+    let client = crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()
         .map_err(|e| e.to_string())?;
@@ -392,3 +394,48 @@ pub async fn get_models(
     models.dedup();
     Ok(models)
 }
+
+/// Embeddings through an OpenAI-compatible `/embeddings` endpoint, in input order.
+pub async fn embed(
+    provider_name: &str,
+    base_url: &str,
+    model: &str,
+    config: &AiConfig,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let api_key = config
+        .api_key()
+        .ok_or_else(|| format!("{provider_name} API key not configured. Go to Settings -> AI."))?;
+    let client = make_client().await?;
+    let response = client
+        .post(format!("{base_url}/embeddings"))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .json(&serde_json::json!({ "model": model, "input": inputs }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(format!(
+            "Invalid {provider_name} API key. Check Settings -> AI."
+        ));
+    }
+    if !status.is_success() {
+        let detail = read_error_body(response).await;
+        return Err(format!("{provider_name} embeddings ({model}): {detail}"));
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Item {
+        index: usize,
+        embedding: Vec<f32>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Body {
+        data: Vec<Item>,
+    }
+    let mut body: Body = response.json().await.map_err(|e| e.to_string())?;
+    body.data.sort_by_key(|item| item.index);
+    Ok(body.data.into_iter().map(|item| item.embedding).collect())
+}
@@ -7,6 +7,7 @@ pub(crate) async fn exec_command(
     cmd: &str,
     tool_call_id: &str,
 ) -> Result<String, String> {
+    crate::commands::audit_command(ctx.app, ctx.connection_id.unwrap_or("local"), cmd, "ai").await;
     if let Some(conn_id) = ctx.connection_id {
         let session_arc = {
             let conns = ctx.connections.lock().await;
@@ -20,7 +21,7 @@ pub(crate) async fn exec_command(
                 .clone()
         };
         let session = session_arc.lock().await;
-        return exec_ssh(ctx.app, ctx.session_dir.as_deref(), &session, cmd, ctx.run_id, tool_call_id).await;
+        return exec_ssh(conn_id, ctx.app, ctx.session_dir.as_deref(), &session, cmd, ctx.run_id, tool_call_id).await;
     }
 
     exec_local(ctx.app, ctx.session_dir.as_deref(), cmd, ctx.run_id, tool_call_id).await
@@ -40,13 +41,14 @@ pub(crate) async fn exec_silent(ctx: &ToolContext<'_>, cmd: &str) -> Result<Stri
                 .clone()
         };
         let session = session_arc.lock().await;
-        return exec_ssh_silent(&session, cmd).await;
+        return exec_ssh_silent(conn_id, &session, cmd).await;
     }
 
     exec_local_silent(cmd).await
 }
 
 async fn exec_ssh(
+    connection_id: &str,
     app: &tauri::AppHandle,
     session_dir: Option<&std::path::Path>,
     session: &russh::client::Handle<crate::ssh::Client>,
@@ -54,8 +56,7 @@ async fn exec_ssh(
     run_id: &str,
     tool_call_id: &str,
 ) -> Result<String, String> {
-    let mut channel = session
-        .channel_open_session()
+    let mut channel = crate::channel_budget::open_session(connection_id, session)
         .await
         .map_err(|e| format!("SSH channel error: {}", e))?;
 
@@ -105,11 +106,11 @@ async fn exec_ssh(
 }
 
 pub(crate) async fn exec_ssh_silent(
+    connection_id: &str,
     session: &russh::client::Handle<crate::ssh::Client>,
     cmd: &str,
 ) -> Result<String, String> {
-    let mut channel = session
-        .channel_open_session()
+    let mut channel = crate::channel_budget::open_session(connection_id, session)
         .await
         .map_err(|e| format!("SSH channel error: {}", e))?;
 
@@ -122,12 +123,12 @@ pub(crate) async fn exec_ssh_silent(
 }
 
 pub(crate) async fn exec_ssh_silent_with_stdin(
+    connection_id: &str,
     session: &russh::client::Handle<crate::ssh::Client>,
     cmd: &str,
     input: &[u8],
 ) -> Result<String, String> {
-    let mut channel = session
-        .channel_open_session()
+    let mut channel = crate::channel_budget::open_session(connection_id, session)
         .await
         .map_err(|e| format!("SSH channel error: {}", e))?;
 
@@ -111,7 +111,7 @@ async fn do_write_file(
                 if let Some(session_arc) = &handle.session {
                     let mkdir_cmd = format!("mkdir -p {}", shell_quote(parent_str));
                     let session = session_arc.lock().await;
-                    let _ = exec_ssh_silent(&session, &mkdir_cmd).await;
+                    let _ = exec_ssh_silent(conn_id, &session, &mkdir_cmd).await;
                 }
             }
         }
@@ -144,14 +144,14 @@ async fn do_write_file(
         let session = session_arc.lock().await;
         if should_stream_remote_write(content.len()) {
             let cmd = build_streaming_write_command(path);
-            exec_ssh_silent_with_stdin(&session, &cmd, encoded.as_bytes()).await?;
+            exec_ssh_silent_with_stdin(conn_id, &session, &cmd, encoded.as_bytes()).await?;
         } else {
             let cmd = format!(
                 "printf '%s' '{}' | base64 -d > {}",
                 encoded,
                 shell_quote(path)
             );
-            exec_ssh_silent(&session, &cmd).await?;
+            exec_ssh_silent(conn_id, &session, &cmd).await?;
         }
         return Ok(());
     }
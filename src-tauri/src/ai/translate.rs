@@ -243,14 +243,17 @@ pub async fn translate_stream(
 }
 
 pub async fn check_ollama(ollama_url: &str) -> bool {
-    let client = match reqwest::Client::builder()
+    if crate::kill_switch::is_engaged() {
+        return false;
+    }
+    let client = match crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(3))
         .build()
     {
         Ok(client) => client,
         Err(error) => {
             #[cfg(debug_assertions)]
-            eprintln!("[zync/ai] failed to build Ollama healthcheck client: {error}");
+            crate::log_error!("[zync/ai] failed to build Ollama healthcheck client: {error}");
             return false;
         }
     };
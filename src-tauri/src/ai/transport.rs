@@ -3,14 +3,16 @@ use tauri::{AppHandle, Emitter};
 use crate::ai::AiStreamChunk;
 
 pub async fn make_client() -> Result<reqwest::Client, String> {
-    reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+    crate::http_client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()
         .map_err(|e| e.to_string())
 }
 
 pub async fn make_stream_client() -> Result<reqwest::Client, String> {
-    reqwest::Client::builder()
+    crate::kill_switch::ensure_online()?;
+    crate::http_client::builder()
         .connect_timeout(std::time::Duration::from_secs(15))
         .build()
         .map_err(|e| e.to_string())
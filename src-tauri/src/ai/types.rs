@@ -33,6 +33,10 @@ pub struct AiConfig {
     pub ollama_url: Option<String>,
     #[serde(default = "default_ai_enabled")]
     pub enabled: bool,
+    /// Model for semantic search embeddings; `local` forces the built-in
+    /// hashed vectors, unset uses the provider's default embedding model.
+    #[serde(default)]
+    pub embedding_model: Option<String>,
 }
 
 impl Default for AiConfig {
@@ -43,6 +47,7 @@ impl Default for AiConfig {
             model: None,
             ollama_url: default_ollama_url(),
             enabled: default_ai_enabled(),
+            embedding_model: None,
         }
     }
 }
@@ -67,6 +72,9 @@ pub struct TerminalContext {
     pub connection_type: String,
     pub attached_content: Option<String>,
     pub attached_label: Option<String>,
+    /// Terminal the request came from; its backend-tracked cwd replaces `cwd`.
+    #[serde(default)]
+    pub term_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -0,0 +1,690 @@
+//! Ansible inventories (INI or YAML) as importable connections.
+//!
+//! Hosts take their address, user, port and key from the usual behavioural
+//! variables (`ansible_host`, `ansible_user`, `ansible_port`,
+//! `ansible_ssh_private_key_file`), set on the host or inherited from its
+//! groups with Ansible's precedence: `all`, then parent groups, then child
+//! groups, then the host itself. Every group a host belongs to (directly or
+//! through `children`) becomes a tag; the most specific one, with its parents,
+//! becomes the folder. Hosts managed over something other than SSH
+//! (`ansible_connection=local`, `winrm`, ...) are left out.
+//!
+//! The YAML reader handles the block-mapping subset inventories are written
+//! in; anchors, flow collections and multi-line strings are not supported.
+
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, HashSet};
+
+const ALL: &str = "all";
+const UNGROUPED: &str = "ungrouped";
+/// `ansible_connection` values that still mean SSH.
+const SSH_CONNECTIONS: &[&str] = &["ssh", "smart", "paramiko", "paramiko_ssh"];
+/// Hosts a single `[start:end]` pattern may expand to.
+const MAX_RANGE: usize = 4096;
+
+#[derive(Debug, Default)]
+struct Group {
+    hosts: Vec<String>,
+    children: Vec<String>,
+    vars: HashMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+struct Inventory {
+    /// Hosts in first-seen order.
+    hosts: Vec<String>,
+    host_vars: HashMap<String, HashMap<String, String>>,
+    /// Groups in first-seen order.
+    group_order: Vec<String>,
+    groups: HashMap<String, Group>,
+}
+
+impl Inventory {
+    fn group(&mut self, name: &str) -> &mut Group {
+        if !self.groups.contains_key(name) {
+            self.group_order.push(name.to_string());
+        }
+        self.groups.entry(name.to_string()).or_default()
+    }
+
+    fn add_host(&mut self, group: &str, host: &str, vars: HashMap<String, String>) {
+        if !self.host_vars.contains_key(host) {
+            self.hosts.push(host.to_string());
+        }
+        self.host_vars
+            .entry(host.to_string())
+            .or_default()
+            .extend(vars);
+        let group = self.group(group);
+        if !group.hosts.iter().any(|h| h == host) {
+            group.hosts.push(host.to_string());
+        }
+    }
+
+    fn add_child(&mut self, parent: &str, child: &str) {
+        self.group(child);
+        let parent = self.group(parent);
+        if !parent.children.iter().any(|c| c == child) {
+            parent.children.push(child.to_string());
+        }
+    }
+}
+
+/// Expands `web[01:03].example.com` and `db-[a:c]`; other names pass through.
+fn expand_pattern(pattern: &str) -> Result<Vec<String>> {
+    let (Some(open), Some(close)) = (pattern.find('['), pattern.find(']')) else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    if close < open {
+        return Ok(vec![pattern.to_string()]);
+    }
+    let (prefix, range, suffix) = (
+        &pattern[..open],
+        &pattern[open + 1..close],
+        &pattern[close + 1..],
+    );
+    let mut parts = range.splitn(3, ':');
+    let (start, end) = match (parts.next(), parts.next()) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Ok(vec![pattern.to_string()]),
+    };
+    let step: usize = match parts.next() {
+        Some(step) => step
+            .parse()
+            .ok()
+            .filter(|s| *s > 0)
+            .ok_or_else(|| anyhow!("Invalid range step in {}", pattern))?,
+        None => 1,
+    };
+    let items: Vec<String> =
+        if let (Ok(first), Ok(last)) = (start.parse::<u64>(), end.parse::<u64>()) {
+            let width = if start.starts_with('0') {
+                start.len()
+            } else {
+                0
+            };
+            if last < first || (last - first) as usize / step >= MAX_RANGE {
+                bail!("Invalid host range in {}", pattern);
+            }
+            (first..=last)
+                .step_by(step)
+                .map(|n| format!("{:0width$}", n, width = width))
+                .collect()
+        } else if let ([a], [b]) = (start.as_bytes(), end.as_bytes()) {
+            if !a.is_ascii_alphabetic() || b < a {
+                bail!("Invalid host range in {}", pattern);
+            }
+            (*a..=*b)
+                .step_by(step)
+                .map(|c| (c as char).to_string())
+                .collect()
+        } else {
+            bail!("Invalid host range in {}", pattern);
+        };
+    let mut hosts = Vec::new();
+    for item in items {
+        // Later ranges in the same name, e.g. `r[1:2]-n[1:3]`.
+        hosts.extend(expand_pattern(&format!("{}{}{}", prefix, item, suffix))?);
+    }
+    Ok(hosts)
+}
+
+/// Splits a line on whitespace, keeping quoted parts together.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut in_word = false;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for q in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(q) && value.ends_with(q) {
+            return value[1..value.len() - 1].to_string();
+        }
+    }
+    value.to_string()
+}
+
+fn parse_ini(text: &str) -> Result<Inventory> {
+    enum Section {
+        Hosts(String),
+        Vars(String),
+        Children(String),
+    }
+    let mut inventory = Inventory::default();
+    let mut section = Section::Hosts(UNGROUPED.to_string());
+    for (number, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = match header.split_once(':') {
+                Some((group, "vars")) => Section::Vars(group.to_string()),
+                Some((group, "children")) => Section::Children(group.to_string()),
+                Some((_, kind)) => bail!("Line {}: unknown section type `{}`", number + 1, kind),
+                None => Section::Hosts(header.to_string()),
+            };
+            match &section {
+                Section::Hosts(group) | Section::Vars(group) | Section::Children(group) => {
+                    inventory.group(group);
+                }
+            }
+            continue;
+        }
+        match &section {
+            Section::Hosts(group) => {
+                let words = split_words(line);
+                let Some((pattern, vars)) = words.split_first() else {
+                    continue;
+                };
+                let vars: HashMap<String, String> = vars
+                    .iter()
+                    .filter_map(|word| word.split_once('='))
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                for host in expand_pattern(pattern)? {
+                    inventory.add_host(group, &host, vars.clone());
+                }
+            }
+            Section::Vars(group) => {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("Line {}: expected key=value", number + 1))?;
+                inventory
+                    .group(group)
+                    .vars
+                    .insert(key.trim().to_string(), unquote(value));
+            }
+            Section::Children(group) => inventory.add_child(group, line),
+        }
+    }
+    Ok(inventory)
+}
+
+/// A YAML block-mapping value.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Map(Vec<(String, Node)>),
+    Scalar(String),
+    Null,
+}
+
+impl Node {
+    fn entries(&self) -> &[(String, Node)] {
+        match self {
+            Node::Map(entries) => entries,
+            _ => &[],
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&Node> {
+        self.entries()
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    fn scalar(&self) -> Option<String> {
+        match self {
+            Node::Scalar(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Drops a trailing ` # comment` outside quotes.
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &line[..i],
+            None => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// Splits `key: value` at the first `:` followed by a space or the end,
+/// outside quotes.
+fn split_yaml_key(line: &str) -> Option<(String, &str)> {
+    let mut quote = None;
+    let bytes = line.as_bytes();
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ':' && bytes.get(i + 1).is_none_or(|b| *b == b' ') => {
+                return Some((unquote(&line[..i]), line[i + 1..].trim()));
+            }
+            None => {}
+        }
+    }
+    None
+}
+
+fn parse_yaml(text: &str) -> Result<Node> {
+    let mut lines = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = strip_yaml_comment(raw).trim_end();
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed == "---" || trimmed == "..." {
+            continue;
+        }
+        if content.starts_with('\t') {
+            bail!("Line {}: tabs are not allowed for indentation", number + 1);
+        }
+        if trimmed.starts_with("- ") || trimmed == "-" {
+            bail!(
+                "Line {}: lists are not supported in inventories",
+                number + 1
+            );
+        }
+        let (key, value) = split_yaml_key(trimmed)
+            .ok_or_else(|| anyhow!("Line {}: expected `key:` or `key: value`", number + 1))?;
+        let indent = content.len() - trimmed.len();
+        lines.push((indent, key, value.to_string(), number + 1));
+    }
+    let mut position = 0;
+    let root = parse_yaml_block(&lines, &mut position, 0)?;
+    if let Some((_, _, _, number)) = lines.get(position) {
+        bail!("Line {}: unexpected indentation", number);
+    }
+    Ok(root)
+}
+
+fn parse_yaml_block(
+    lines: &[(usize, String, String, usize)],
+    position: &mut usize,
+    indent: usize,
+) -> Result<Node> {
+    let mut entries = Vec::new();
+    while let Some((line_indent, key, value, number)) = lines.get(*position) {
+        if *line_indent < indent {
+            break;
+        }
+        if *line_indent > indent {
+            bail!("Line {}: unexpected indentation", number);
+        }
+        *position += 1;
+        let node = match value.as_str() {
+            "" => match lines.get(*position) {
+                Some((next_indent, ..)) if *next_indent > indent => {
+                    parse_yaml_block(lines, position, *next_indent)?
+                }
+                _ => Node::Null,
+            },
+            "~" | "null" | "{}" => Node::Null,
+            value if value.starts_with('{') || value.starts_with('[') => {
+                bail!("Line {}: flow collections are not supported", number)
+            }
+            value if value.starts_with('&') || value.starts_with('*') => {
+                bail!("Line {}: anchors and aliases are not supported", number)
+            }
+            value if value.starts_with('|') || value.starts_with('>') => {
+                bail!("Line {}: multi-line strings are not supported", number)
+            }
+            value => Node::Scalar(unquote(value)),
+        };
+        entries.push((key.clone(), node));
+    }
+    Ok(Node::Map(entries))
+}
+
+fn scalar_vars(node: Option<&Node>) -> HashMap<String, String> {
+    node.map(Node::entries)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(key, value)| Some((key.clone(), value.scalar()?)))
+        .collect()
+}
+
+fn read_yaml_group(inventory: &mut Inventory, name: &str, node: &Node) {
+    inventory
+        .group(name)
+        .vars
+        .extend(scalar_vars(node.get("vars")));
+    for (host, vars) in node.get("hosts").map(Node::entries).unwrap_or_default() {
+        let hosts = expand_pattern(host).unwrap_or_else(|_| vec![host.clone()]);
+        for host in hosts {
+            inventory.add_host(name, &host, scalar_vars(Some(vars)));
+        }
+    }
+    for (child, child_node) in node.get("children").map(Node::entries).unwrap_or_default() {
+        inventory.add_child(name, child);
+        read_yaml_group(inventory, child, child_node);
+    }
+}
+
+fn parse_yaml_inventory(text: &str) -> Result<Inventory> {
+    let root = parse_yaml(text)?;
+    let mut inventory = Inventory::default();
+    for (group, node) in root.entries() {
+        read_yaml_group(&mut inventory, group, node);
+    }
+    Ok(inventory)
+}
+
+/// YAML when the first meaningful line is a `key:` mapping entry, INI otherwise.
+fn looks_like_yaml(text: &str) -> bool {
+    text.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+        .is_some_and(|line| line == "---" || (!line.starts_with('[') && line.ends_with(':')))
+}
+
+/// Group depth below `all` (longest parent chain) and first parent of each group.
+fn group_ancestry(inventory: &Inventory) -> (HashMap<&str, usize>, HashMap<&str, &str>) {
+    let mut parent_of: HashMap<&str, &str> = HashMap::new();
+    for name in &inventory.group_order {
+        for child in &inventory.groups[name].children {
+            parent_of.entry(child.as_str()).or_insert(name.as_str());
+        }
+    }
+    let mut depth: HashMap<&str, usize> = HashMap::new();
+    // Relax parent/child edges; bounded so cycles cannot loop forever.
+    for _ in 0..=inventory.group_order.len() {
+        let mut changed = false;
+        for name in &inventory.group_order {
+            let parent_depth = depth.get(name.as_str()).copied().unwrap_or(1);
+            for child in &inventory.groups[name].children {
+                let entry = depth.entry(child.as_str()).or_insert(1);
+                if *entry < parent_depth + 1 {
+                    *entry = parent_depth + 1;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    (depth, parent_of)
+}
+
+fn to_connections(inventory: &Inventory) -> Vec<ParsedSshConnection> {
+    let (depth, parent_of) = group_ancestry(inventory);
+    let group_depth = |name: &str| match name {
+        ALL => 0,
+        _ => depth.get(name).copied().unwrap_or(1),
+    };
+    let default_user = crate::cloud_import::local_username().unwrap_or_else(|| "root".to_string());
+
+    let mut connections = Vec::new();
+    for host in &inventory.hosts {
+        // Groups holding the host directly, then all their ancestors.
+        let direct: Vec<&str> = inventory
+            .group_order
+            .iter()
+            .filter(|name| inventory.groups[*name].hosts.contains(host))
+            .map(String::as_str)
+            .collect();
+        let mut member_of: Vec<&str> = Vec::new();
+        let mut pending = direct.clone();
+        let mut seen = HashSet::new();
+        while let Some(group) = pending.pop() {
+            if !seen.insert(group) {
+                continue;
+            }
+            member_of.push(group);
+            for name in &inventory.group_order {
+                if inventory.groups[name].children.iter().any(|c| c == group) {
+                    pending.push(name.as_str());
+                }
+            }
+        }
+        member_of.sort_by_key(|group| {
+            (
+                group_depth(group),
+                inventory
+                    .group_order
+                    .iter()
+                    .position(|g| g.as_str() == *group),
+            )
+        });
+
+        let mut vars: HashMap<&str, &str> = HashMap::new();
+        if let Some(all) = inventory.groups.get(ALL) {
+            vars.extend(all.vars.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        }
+        for group in &member_of {
+            let group = &inventory.groups[*group];
+            vars.extend(group.vars.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        }
+        if let Some(host_vars) = inventory.host_vars.get(host) {
+            vars.extend(host_vars.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        }
+        let var = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| vars.get(key).map(|v| v.trim()).filter(|v| !v.is_empty()))
+        };
+
+        if var(&["ansible_connection"]).is_some_and(|c| !SSH_CONNECTIONS.contains(&c)) {
+            continue;
+        }
+        let tags: Vec<String> = member_of
+            .iter()
+            .filter(|group| **group != ALL && **group != UNGROUPED)
+            .map(|group| group.to_string())
+            .collect();
+        let folder = direct
+            .iter()
+            .filter(|group| **group != ALL && **group != UNGROUPED)
+            .max_by_key(|group| group_depth(group))
+            .map(|group| {
+                let mut path = vec![*group];
+                while let Some(parent) = parent_of.get(path[0]).filter(|p| **p != ALL) {
+                    if path.contains(parent) {
+                        break;
+                    }
+                    path.insert(0, *parent);
+                }
+                path.join("/")
+            });
+
+        connections.push(ParsedSshConnection {
+            id: format!("ansible_{}", host),
+            name: host.clone(),
+            host: var(&["ansible_host", "ansible_ssh_host"])
+                .unwrap_or(host)
+                .to_string(),
+            username: var(&["ansible_user", "ansible_ssh_user"])
+                .map(str::to_string)
+                .unwrap_or_else(|| default_user.clone()),
+            port: var(&["ansible_port", "ansible_ssh_port"])
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(22),
+            private_key_path: var(&["ansible_ssh_private_key_file", "ansible_private_key_file"])
+                .map(str::to_string),
+            jump_server_alias: var(&["ansible_ssh_common_args", "ansible_ssh_extra_args"])
+                .and_then(proxy_jump),
+            jump_server_id: None,
+            aliases: vec![host.clone()],
+            identities_only: None,
+            tags,
+            folder,
+        });
+    }
+    connections
+}
+
+/// Jump host from `-J host` or `-o ProxyJump=host` in extra SSH args.
+fn proxy_jump(args: &str) -> Option<String> {
+    let words = split_words(args);
+    let mut iter = words.iter();
+    while let Some(word) = iter.next() {
+        let option = match word.as_str() {
+            "-J" => return iter.next().cloned(),
+            "-o" => iter.next().map(String::as_str),
+            word => word.strip_prefix("-o"),
+        };
+        if let Some((key, value)) = option.and_then(|o| o.split_once('=')) {
+            if key.trim().eq_ignore_ascii_case("proxyjump") {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Parses an inventory file's contents, INI or YAML.
+pub fn parse_inventory(text: &str) -> Result<Vec<ParsedSshConnection>> {
+    let inventory = if looks_like_yaml(text) {
+        parse_yaml_inventory(text)?
+    } else {
+        parse_ini(text)?
+    };
+    Ok(to_connections(&inventory))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn by_name<'a>(connections: &'a [ParsedSshConnection], name: &str) -> &'a ParsedSshConnection {
+        connections.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn parses_ini_groups_vars_and_children() {
+        let connections = parse_inventory(
+            r#"
+bastion.example.com ansible_user=ops
+
+[web]
+web[01:02].example.com
+www ansible_host=10.0.0.9 ansible_port=2222 ansible_user="deploy"
+
+[db]
+db1 ansible_host=10.0.1.5 ansible_ssh_common_args='-o ProxyJump=bastion.example.com'
+
+[prod:children]
+web
+db
+
+[prod:vars]
+ansible_user=admin
+
+[web:vars]
+ansible_user=www-data
+
+[local]
+localhost ansible_connection=local
+"#,
+        )
+        .unwrap();
+        assert_eq!(connections.len(), 5);
+
+        let bastion = by_name(&connections, "bastion.example.com");
+        assert_eq!(bastion.username, "ops");
+        assert!(bastion.tags.is_empty());
+        assert_eq!(bastion.folder, None);
+
+        let web = by_name(&connections, "web02.example.com");
+        assert_eq!(web.host, "web02.example.com");
+        assert_eq!(web.username, "www-data");
+        assert_eq!(web.tags, ["prod", "web"]);
+        assert_eq!(web.folder.as_deref(), Some("prod/web"));
+
+        let www = by_name(&connections, "www");
+        assert_eq!((www.host.as_str(), www.port), ("10.0.0.9", 2222));
+        assert_eq!(www.username, "deploy");
+
+        let db = by_name(&connections, "db1");
+        assert_eq!(db.username, "admin");
+        assert_eq!(db.jump_server_alias.as_deref(), Some("bastion.example.com"));
+        assert_eq!(db.id, "ansible_db1");
+    }
+
+    #[test]
+    fn parses_yaml_inventories() {
+        let connections = parse_inventory(
+            r#"---
+all:
+  vars:
+    ansible_user: ubuntu   # default user
+  hosts:
+    mail.example.com:
+  children:
+    prod:
+      children:
+        app:
+          hosts:
+            app1:
+              ansible_host: 10.1.0.11
+              ansible_port: "2200"
+            app2: ~
+          vars:
+            ansible_ssh_private_key_file: ~/.ssh/app.pem
+    windows:
+      hosts:
+        win1:
+          ansible_connection: winrm
+"#,
+        )
+        .unwrap();
+        assert_eq!(connections.len(), 3);
+        let mail = by_name(&connections, "mail.example.com");
+        assert_eq!(mail.username, "ubuntu");
+        assert!(mail.tags.is_empty());
+
+        let app1 = by_name(&connections, "app1");
+        assert_eq!((app1.host.as_str(), app1.port), ("10.1.0.11", 2200));
+        assert_eq!(app1.private_key_path.as_deref(), Some("~/.ssh/app.pem"));
+        assert_eq!(app1.tags, ["prod", "app"]);
+        assert_eq!(app1.folder.as_deref(), Some("prod/app"));
+        assert_eq!(by_name(&connections, "app2").username, "ubuntu");
+    }
+
+    #[test]
+    fn expands_host_ranges() {
+        assert_eq!(
+            expand_pattern("db-[a:c].lan").unwrap(),
+            ["db-a.lan", "db-b.lan", "db-c.lan"]
+        );
+        assert_eq!(expand_pattern("n[1:5:2]").unwrap(), ["n1", "n3", "n5"]);
+        assert_eq!(expand_pattern("r[1:2]-[08:09]").unwrap().len(), 4);
+        assert!(expand_pattern("x[5:1]").is_err());
+        assert_eq!(expand_pattern("plain").unwrap(), ["plain"]);
+    }
+
+    #[test]
+    fn rejects_unsupported_yaml() {
+        assert!(parse_inventory("all:\n  hosts: [a, b]\n").is_err());
+        assert!(parse_inventory("all:\n  hosts:\n    - a\n").is_err());
+        assert!(parse_inventory("all:\n  hosts:\n     a:\n    b:\n").is_err());
+    }
+}
@@ -0,0 +1,363 @@
+//! Archive create/extract for the file manager.
+//!
+//! Remote archives are built server-side with `tar`/`zip` over an exec channel.
+//! When the server has no usable tools (or is Windows), zip archives fall back to
+//! staging files locally over SFTP, packing/unpacking them with the `zip` crate
+//! and uploading the result.
+
+use anyhow::{anyhow, Context, Result};
+use russh_sftp::client::SftpSession;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "zip" => Ok(Self::Zip),
+            "tar" => Ok(Self::Tar),
+            "tar.gz" | "tgz" | "targz" => Ok(Self::TarGz),
+            other => Err(anyhow!("Unsupported archive format '{}'", other)),
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if lower.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Splits a remote path into `(parent, name)`; the parent defaults to `.`.
+pub fn split_remote_path(path: &str) -> (String, String) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rfind('/') {
+        Some(0) => ("/".to_string(), trimmed[1..].to_string()),
+        Some(idx) => (trimmed[..idx].to_string(), trimmed[idx + 1..].to_string()),
+        None => (".".to_string(), trimmed.to_string()),
+    }
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Builds the server-side command that packs `paths` into `archive_path`.
+/// Each path is added relative to its own parent, so entries keep only their name.
+pub fn compress_command(format: ArchiveFormat, paths: &[String], archive_path: &str) -> String {
+    match format {
+        ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+            let flags = if format == ArchiveFormat::TarGz { "-czf" } else { "-cf" };
+            let mut cmd = format!("tar {} {}", flags, quote(archive_path));
+            for path in paths {
+                let (parent, name) = split_remote_path(path);
+                cmd.push_str(&format!(" -C {} {}", quote(&parent), quote(&name)));
+            }
+            cmd
+        }
+        ArchiveFormat::Zip => {
+            // zip has no -C; add each path from a subshell in its parent directory.
+            let mut parts = vec![format!("rm -f {}", quote(archive_path))];
+            for path in paths {
+                let (parent, name) = split_remote_path(path);
+                parts.push(format!(
+                    "(cd {} && zip -qry {} {})",
+                    quote(&parent),
+                    quote(archive_path),
+                    quote(&name)
+                ));
+            }
+            parts.join(" && ")
+        }
+    }
+}
+
+pub fn extract_command(format: ArchiveFormat, archive: &str, dest: &str) -> String {
+    let extract = match format {
+        ArchiveFormat::Zip => format!("unzip -oq {} -d {}", quote(archive), quote(dest)),
+        ArchiveFormat::Tar => format!("tar -xf {} -C {}", quote(archive), quote(dest)),
+        ArchiveFormat::TarGz => format!("tar -xzf {} -C {}", quote(archive), quote(dest)),
+    };
+    format!("mkdir -p {} && {}", quote(dest), extract)
+}
+
+// --- Local zip helpers ---
+
+fn add_to_zip<W: Write + std::io::Seek>(
+    writer: &mut zip::ZipWriter<W>,
+    path: &Path,
+    entry_name: &str,
+) -> Result<()> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let metadata = std::fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    if metadata.is_dir() {
+        writer.add_directory(format!("{}/", entry_name), options)?;
+        let mut children: Vec<_> = std::fs::read_dir(path)?.filter_map(|e| e.ok()).collect();
+        children.sort_by_key(|e| e.file_name());
+        for child in children {
+            let child_name = format!("{}/{}", entry_name, child.file_name().to_string_lossy());
+            add_to_zip(writer, &child.path(), &child_name)?;
+        }
+    } else if metadata.is_file() {
+        writer.start_file(entry_name, options)?;
+        let mut file = std::fs::File::open(path)?;
+        std::io::copy(&mut file, writer)?;
+    }
+    // Symlinks and special files are skipped; zip has no portable representation.
+    Ok(())
+}
+
+/// Packs local `paths` into a zip at `archive`, each entry named relative to its parent.
+pub fn zip_local_paths(paths: &[PathBuf], archive: &Path) -> Result<()> {
+    let file = std::fs::File::create(archive)
+        .with_context(|| format!("Failed to create {}", archive.display()))?;
+    let mut writer = zip::ZipWriter::new(file);
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Cannot archive '{}'", path.display()))?;
+        add_to_zip(&mut writer, path, &name)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Extracts a zip into `dest`. Entries that would escape `dest` are rejected by the zip crate.
+pub fn unzip_local(archive: &Path, dest: &Path) -> Result<()> {
+    let file = std::fs::File::open(archive)
+        .with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut zip = zip::ZipArchive::new(file).context("Invalid zip archive")?;
+    std::fs::create_dir_all(dest)?;
+    zip.extract(dest)?;
+    Ok(())
+}
+
+/// Runs the local `tar` binary (bundled with macOS, Linux and Windows 10+).
+pub async fn tar_local(args: &[String]) -> Result<()> {
+    let output = tokio::process::Command::new("tar")
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run tar")?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// --- SFTP fallback ---
+
+fn download_tree<'a>(
+    sftp: &'a SftpSession,
+    remote: &'a str,
+    local: &'a Path,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        let attrs = sftp
+            .symlink_metadata(remote)
+            .await
+            .map_err(|e| anyhow!("Failed to stat '{}': {}", remote, e))?;
+        let file_type = attrs.permissions.unwrap_or(0) & 0o170000;
+        if file_type == 0o040000 {
+            tokio::fs::create_dir_all(local).await?;
+            let entries = sftp
+                .read_dir(remote)
+                .await
+                .map_err(|e| anyhow!("Failed to list '{}': {}", remote, e))?;
+            for entry in entries {
+                let name = entry.file_name();
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let child = format!("{}/{}", remote.trim_end_matches('/'), name);
+                download_tree(sftp, &child, &local.join(&name)).await?;
+            }
+        } else if file_type == 0o100000 || file_type == 0 {
+            let mut source = sftp
+                .open(remote)
+                .await
+                .map_err(|e| anyhow!("Failed to open '{}': {}", remote, e))?;
+            let mut dest = tokio::fs::File::create(local).await?;
+            tokio::io::copy(&mut source, &mut dest).await?;
+        }
+        Ok(())
+    })
+}
+
+fn upload_tree<'a>(
+    sftp: &'a SftpSession,
+    local: &'a Path,
+    remote: &'a str,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+    Box::pin(async move {
+        let metadata = tokio::fs::symlink_metadata(local).await?;
+        if metadata.is_dir() {
+            let _ = sftp.create_dir(remote).await;
+            let mut entries = tokio::fs::read_dir(local).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let child = format!(
+                    "{}/{}",
+                    remote.trim_end_matches('/'),
+                    entry.file_name().to_string_lossy()
+                );
+                upload_tree(sftp, &entry.path(), &child).await?;
+            }
+        } else if metadata.is_file() {
+            upload_file(sftp, local, remote).await?;
+        }
+        Ok(())
+    })
+}
+
+async fn upload_file(sftp: &SftpSession, local: &Path, remote: &str) -> Result<()> {
+    use russh_sftp::protocol::OpenFlags;
+    use tokio::io::AsyncWriteExt;
+
+    let mut source = tokio::fs::File::open(local).await?;
+    let mut dest = sftp
+        .open_with_flags(
+            remote,
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+        )
+        .await
+        .map_err(|e| anyhow!("Failed to open '{}' for writing: {}", remote, e))?;
+    tokio::io::copy(&mut source, &mut dest).await?;
+    dest.shutdown().await?;
+    Ok(())
+}
+
+fn staging_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("zync-archive-{}", uuid::Uuid::new_v4()))
+}
+
+/// Zips remote `paths` into `archive_path` without any server-side tooling.
+pub async fn compress_via_sftp(sftp: &SftpSession, paths: &[String], archive_path: &str) -> Result<()> {
+    let staging = staging_dir();
+    let result = async {
+        let files_dir = staging.join("files");
+        tokio::fs::create_dir_all(&files_dir).await?;
+        let mut staged = Vec::new();
+        for path in paths {
+            let (_, name) = split_remote_path(path);
+            let local = files_dir.join(&name);
+            download_tree(sftp, path, &local).await?;
+            staged.push(local);
+        }
+
+        let local_archive = staging.join("archive.zip");
+        let archive_for_task = local_archive.clone();
+        tokio::task::spawn_blocking(move || zip_local_paths(&staged, &archive_for_task))
+            .await
+            .map_err(|e| anyhow!("Archive task failed: {}", e))??;
+
+        upload_file(sftp, &local_archive, archive_path).await
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    result
+}
+
+/// Extracts a remote zip into `dest` by unpacking it locally and uploading the tree.
+pub async fn extract_via_sftp(sftp: &SftpSession, archive: &str, dest: &str) -> Result<()> {
+    let staging = staging_dir();
+    let result = async {
+        tokio::fs::create_dir_all(&staging).await?;
+        let local_archive = staging.join("archive.zip");
+        download_tree(sftp, archive, &local_archive).await?;
+
+        let extracted = staging.join("extracted");
+        let (archive_for_task, extracted_for_task) = (local_archive.clone(), extracted.clone());
+        tokio::task::spawn_blocking(move || unzip_local(&archive_for_task, &extracted_for_task))
+            .await
+            .map_err(|e| anyhow!("Extract task failed: {}", e))??;
+
+        let _ = sftp.create_dir(dest).await;
+        let mut entries = tokio::fs::read_dir(&extracted).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let child = format!(
+                "{}/{}",
+                dest.trim_end_matches('/'),
+                entry.file_name().to_string_lossy()
+            );
+            upload_tree(sftp, &entry.path(), &child).await?;
+        }
+        Ok(())
+    }
+    .await;
+    let _ = tokio::fs::remove_dir_all(&staging).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_is_detected_from_extension() {
+        assert_eq!(ArchiveFormat::from_path("/tmp/a.ZIP"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_path("a.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path("a.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path("a.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(ArchiveFormat::from_path("a.rar"), None);
+        assert!(ArchiveFormat::parse("7z").is_err());
+    }
+
+    #[test]
+    fn tar_command_adds_each_path_from_its_parent() {
+        let cmd = compress_command(
+            ArchiveFormat::TarGz,
+            &["/var/log/app".to_string(), "/etc/app.conf".to_string()],
+            "/tmp/out.tar.gz",
+        );
+        assert_eq!(
+            cmd,
+            "tar -czf '/tmp/out.tar.gz' -C '/var/log' 'app' -C '/etc' 'app.conf'"
+        );
+    }
+
+    #[test]
+    fn extract_command_creates_destination() {
+        let cmd = extract_command(ArchiveFormat::Zip, "/tmp/it's.zip", "/srv/out");
+        assert_eq!(cmd, "mkdir -p '/srv/out' && unzip -oq '/tmp/it'\\''s.zip' -d '/srv/out'");
+    }
+
+    #[test]
+    fn local_zip_round_trips_directories() {
+        let root = staging_dir();
+        let src = root.join("project");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        std::fs::write(src.join("sub/readme.txt"), b"hello").unwrap();
+
+        let archive = root.join("out.zip");
+        zip_local_paths(&[src.clone()], &archive).unwrap();
+        let dest = root.join("dest");
+        unzip_local(&archive, &dest).unwrap();
+        assert_eq!(
+            std::fs::read(dest.join("project/sub/readme.txt")).unwrap(),
+            b"hello"
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,550 @@
+//! Audit trail and tamper-evident export bundles for auditors.
+//!
+//! With `audit.enabled` set, or while session logging covers a connection, opened
+//! connections, commands run outside a terminal (actions, `ssh_exec`, AI tool calls),
+//! finished transfers, deletions and tunnel starts are appended to
+//! `<data>/audit/trail.jsonl`. `audit_query` reads it back through an [`AuditFilter`];
+//! events older than `audit.retentionDays` are pruned at most once a day.
+//! `audit_export_bundle` packs the session logs, that trail and the transfer manifests
+//! for a time window into a zip whose `MANIFEST.json` lists the SHA-256 of every entry.
+//! The manifest's own hash is returned to the caller and, when a key is supplied, the
+//! manifest is signed with `ssh-keygen -Y sign` so it can be checked with
+//! `ssh-keygen -Y verify -n zync-audit`.
+
+use crate::hashing::{hash_bytes, HashAlgorithm};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const TRAIL_FILE: &str = "trail.jsonl";
+/// Namespace passed to `ssh-keygen -Y sign`; verifiers must use the same one.
+pub const SIGNATURE_NAMESPACE: &str = "zync-audit";
+/// Default for `audit.retentionDays`; 0 keeps events forever.
+pub const DEFAULT_RETENTION_DAYS: u64 = 90;
+/// Default cap on events returned by one query.
+pub const DEFAULT_QUERY_LIMIT: usize = 500;
+const DAY_MS: u64 = 86_400_000;
+
+static TRAIL_LOCK: Mutex<()> = Mutex::new(());
+static LAST_PRUNE_MS: AtomicU64 = AtomicU64::new(0);
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditKind {
+    Connect,
+    Command,
+    Transfer,
+    Delete,
+    Tunnel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub timestamp_ms: u64,
+    pub connection_id: String,
+    pub kind: AuditKind,
+    /// The command line, `source -> destination` for transfers, the deleted path,
+    /// `user@host:port` for connections or the forward spec for tunnels.
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+impl AuditEvent {
+    pub fn new(connection_id: &str, kind: AuditKind, summary: String, details: Value) -> Self {
+        Self {
+            timestamp_ms: now_ms(),
+            connection_id: connection_id.to_string(),
+            kind,
+            summary,
+            details,
+        }
+    }
+}
+
+/// Appends one event to `<dir>/trail.jsonl`.
+pub fn append(dir: &Path, event: &AuditEvent) -> Result<()> {
+    let line = serde_json::to_string(event)?;
+    let _guard = TRAIL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    std::fs::create_dir_all(dir)?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(TRAIL_FILE))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+/// Selection for `audit_query`; every field is optional and unset fields match all.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+    #[serde(default)]
+    pub connection_ids: Vec<String>,
+    #[serde(default)]
+    pub kinds: Vec<AuditKind>,
+    /// Case-insensitive substring of the summary.
+    pub text: Option<String>,
+    /// Newest events are kept; defaults to [`DEFAULT_QUERY_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, event: &AuditEvent, needle: Option<&str>) -> bool {
+        self.from_ms.is_none_or(|from| event.timestamp_ms >= from)
+            && self.to_ms.is_none_or(|to| event.timestamp_ms <= to)
+            && (self.connection_ids.is_empty()
+                || self.connection_ids.contains(&event.connection_id))
+            && (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+            && needle.is_none_or(|needle| event.summary.to_lowercase().contains(needle))
+    }
+}
+
+/// Events matching `filter`, newest first.
+pub fn query(dir: &Path, filter: &AuditFilter) -> Result<Vec<AuditEvent>> {
+    let needle = filter
+        .text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_lowercase);
+    let mut events = Vec::new();
+    for event in read_events(dir)? {
+        if filter.matches(&event, needle.as_deref()) {
+            events.push(event);
+        }
+    }
+    let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    let skip = events.len().saturating_sub(limit);
+    let mut events = events.split_off(skip);
+    events.reverse();
+    Ok(events)
+}
+
+/// Rewrites the trail without events older than `retention_days`. Returns how many
+/// were dropped; torn lines are dropped too.
+pub fn prune(dir: &Path, retention_days: u64) -> Result<usize> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+    let cutoff = now_ms().saturating_sub(retention_days.saturating_mul(DAY_MS));
+    let _guard = TRAIL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = dir.join(TRAIL_FILE);
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut kept = String::with_capacity(raw.len());
+    let mut dropped = 0;
+    for line in raw.lines() {
+        match serde_json::from_str::<AuditEvent>(line) {
+            Ok(event) if event.timestamp_ms >= cutoff => {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+            _ => dropped += 1,
+        }
+    }
+    if dropped > 0 {
+        crate::atomic_io::durable_replace(&path, kept.as_bytes())?;
+    }
+    Ok(dropped)
+}
+
+/// [`prune`], unless this process already pruned within the last day.
+pub fn prune_if_due(dir: &Path, retention_days: u64) -> Result<usize> {
+    let now = now_ms();
+    let last = LAST_PRUNE_MS.load(Ordering::Relaxed);
+    if now.saturating_sub(last) < DAY_MS
+        || LAST_PRUNE_MS
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+    {
+        return Ok(0);
+    }
+    prune(dir, retention_days)
+}
+
+/// Time window (inclusive, unix milliseconds) and connection set for an export.
+#[derive(Debug, Clone)]
+pub struct BundleScope {
+    pub from_ms: u64,
+    pub to_ms: u64,
+    /// Empty means every connection.
+    pub connection_ids: Vec<String>,
+    /// Session log file name prefixes (see `session_log::file_label`); empty means all logs.
+    pub log_labels: Vec<String>,
+}
+
+impl BundleScope {
+    fn includes_event(&self, event: &AuditEvent) -> bool {
+        (self.from_ms..=self.to_ms).contains(&event.timestamp_ms)
+            && (self.connection_ids.is_empty()
+                || self.connection_ids.contains(&event.connection_id))
+    }
+
+    fn includes_log(&self, name: &str) -> bool {
+        self.log_labels.is_empty()
+            || self
+                .log_labels
+                .iter()
+                .any(|label| name.starts_with(&format!("{}-", label)))
+    }
+}
+
+fn read_events(dir: &Path) -> Result<Vec<AuditEvent>> {
+    let file = match std::fs::File::open(dir.join(TRAIL_FILE)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut events = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        // A torn last line (crash mid-append) is skipped rather than failing the read.
+        if let Ok(event) = serde_json::from_str::<AuditEvent>(&line?) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+fn read_trail(dir: &Path, scope: &BundleScope) -> Result<Vec<AuditEvent>> {
+    let mut events = read_events(dir)?;
+    events.retain(|event| scope.includes_event(event));
+    Ok(events)
+}
+
+fn to_jsonl(events: &[&AuditEvent]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut out, event)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+fn millis(time: std::io::Result<SystemTime>) -> Option<u64> {
+    time.ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// Session logs whose lifetime overlaps the window: last written after `from`,
+/// started before `to` (when the platform reports a creation time).
+fn collect_logs(dir: &Path, scope: &BundleScope) -> Result<Vec<(String, PathBuf)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut logs = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".log") || !scope.includes_log(&name) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        if !meta.is_file() {
+            continue;
+        }
+        let modified = millis(meta.modified()).unwrap_or(u64::MAX);
+        let created = millis(meta.created()).unwrap_or(0);
+        if modified >= scope.from_ms && created <= scope.to_ms {
+            logs.push((name, entry.path()));
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+/// Everything that goes into the archive, read into memory up front so a log that
+/// is still being written cannot drift from the hash recorded for it.
+pub struct Bundle {
+    entries: Vec<(String, Vec<u8>)>,
+    manifest: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleResult {
+    pub path: String,
+    pub files: usize,
+    pub session_logs: usize,
+    pub commands: usize,
+    pub transfers: usize,
+    /// SHA-256 of `MANIFEST.json`; record it out of band to detect a swapped archive.
+    pub manifest_sha256: String,
+    pub signed: bool,
+}
+
+impl Bundle {
+    pub fn collect(scope: &BundleScope, log_dir: &Path, audit_dir: &Path) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (name, path) in collect_logs(log_dir, scope)? {
+            entries.push((format!("session-logs/{}", name), std::fs::read(path)?));
+        }
+
+        let events = read_trail(audit_dir, scope)?;
+        let of_kind = |kind| events.iter().filter(|e| e.kind == kind).collect::<Vec<_>>();
+        let commands = of_kind(AuditKind::Command);
+        let transfers = of_kind(AuditKind::Transfer);
+        entries.push(("audit/commands.jsonl".to_string(), to_jsonl(&commands)?));
+        entries.push(("audit/transfers.jsonl".to_string(), to_jsonl(&transfers)?));
+
+        let files: Vec<Value> = entries
+            .iter()
+            .map(|(name, data)| {
+                json!({
+                    "path": name,
+                    "size": data.len(),
+                    "sha256": hash_bytes(data, HashAlgorithm::Sha256),
+                })
+            })
+            .collect();
+        let manifest = serde_json::to_vec_pretty(&json!({
+            "version": 1,
+            "createdAtMs": now_ms(),
+            "fromMs": scope.from_ms,
+            "toMs": scope.to_ms,
+            "connectionIds": scope.connection_ids,
+            "counts": {
+                "sessionLogs": entries.len() - 2,
+                "commands": commands.len(),
+                "transfers": transfers.len(),
+            },
+            "files": files,
+        }))?;
+        Ok(Self { entries, manifest })
+    }
+
+    pub fn manifest_sha256(&self) -> String {
+        hash_bytes(&self.manifest, HashAlgorithm::Sha256)
+    }
+
+    fn count_lines(&self, name: &str) -> usize {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, data)| data.iter().filter(|b| **b == b'\n').count())
+            .unwrap_or(0)
+    }
+
+    /// Writes the zip; `signature` is the armored `ssh-keygen -Y sign` output, if any.
+    pub fn write(&self, dest: &Path, signature: Option<&[u8]>) -> Result<BundleResult> {
+        let file = std::fs::File::create(dest)
+            .map_err(|e| anyhow!("Failed to create {}: {}", dest.display(), e))?;
+        let mut writer = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in &self.entries {
+            writer.start_file(name.as_str(), options)?;
+            writer.write_all(data)?;
+        }
+        writer.start_file("MANIFEST.json", options)?;
+        writer.write_all(&self.manifest)?;
+        writer.start_file("MANIFEST.json.sha256", options)?;
+        writeln!(writer, "{}  MANIFEST.json", self.manifest_sha256())?;
+        if let Some(signature) = signature {
+            writer.start_file("MANIFEST.json.sig", options)?;
+            writer.write_all(signature)?;
+        }
+        writer.finish()?;
+
+        Ok(BundleResult {
+            path: dest.to_string_lossy().to_string(),
+            files: self.entries.len() + if signature.is_some() { 3 } else { 2 },
+            session_logs: self.entries.len() - 2,
+            commands: self.count_lines("audit/commands.jsonl"),
+            transfers: self.count_lines("audit/transfers.jsonl"),
+            manifest_sha256: self.manifest_sha256(),
+            signed: signature.is_some(),
+        })
+    }
+
+    /// Signs the manifest with an OpenSSH private key. The key must not need a passphrase
+    /// prompt (unencrypted, or loaded in the agent with its `.pub` next to it).
+    pub async fn sign(&self, key: &Path) -> Result<Vec<u8>> {
+        let dir = std::env::temp_dir().join(format!("zync-audit-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)?;
+        let manifest = dir.join("MANIFEST.json");
+        let result = async {
+            std::fs::write(&manifest, &self.manifest)?;
+            let key = crate::vault::at_rest::PlaintextKey::open(key)
+                .map_err(|e| anyhow!("Could not read {}: {}", key.display(), e))?;
+            let output = tokio::process::Command::new("ssh-keygen")
+                .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+                .arg(key.path())
+                .arg(&manifest)
+                .stdin(std::process::Stdio::null())
+                .output()
+                .await
+                .map_err(|e| anyhow!("Failed to run ssh-keygen: {}", e))?;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "Signing failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ));
+            }
+            Ok(std::fs::read(dir.join("MANIFEST.json.sig"))?)
+        }
+        .await;
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zync-audit-test-{}-{}", name, now_ms()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn event(ts: u64, conn: &str, kind: AuditKind) -> AuditEvent {
+        AuditEvent {
+            timestamp_ms: ts,
+            connection_id: conn.to_string(),
+            kind,
+            summary: format!("{}@{}", conn, ts),
+            details: Value::Null,
+        }
+    }
+
+    #[test]
+    fn trail_is_filtered_by_window_and_connection() {
+        let dir = scratch("trail");
+        for e in [
+            event(100, "a", AuditKind::Command),
+            event(200, "a", AuditKind::Transfer),
+            event(200, "b", AuditKind::Command),
+            event(900, "a", AuditKind::Command),
+        ] {
+            append(&dir, &e).unwrap();
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(TRAIL_FILE))
+            .unwrap()
+            .write_all(b"{\"torn")
+            .unwrap();
+
+        let scope = BundleScope {
+            from_ms: 150,
+            to_ms: 500,
+            connection_ids: vec!["a".to_string()],
+            log_labels: Vec::new(),
+        };
+        let events = read_trail(&dir, &scope).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].summary, "a@200");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn query_filters_and_returns_newest_first() {
+        let dir = scratch("query");
+        for e in [
+            event(100, "a", AuditKind::Connect),
+            event(200, "a", AuditKind::Command),
+            event(300, "b", AuditKind::Delete),
+            event(400, "a", AuditKind::Command),
+        ] {
+            append(&dir, &e).unwrap();
+        }
+
+        let filter = AuditFilter {
+            connection_ids: vec!["a".to_string()],
+            kinds: vec![AuditKind::Command],
+            ..Default::default()
+        };
+        let summaries: Vec<_> = query(&dir, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.summary)
+            .collect();
+        assert_eq!(summaries, ["a@400", "a@200"]);
+
+        let filter = AuditFilter {
+            text: Some("B@".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(query(&dir, &filter).unwrap()[0].summary, "b@300");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prune_drops_events_past_retention() {
+        let dir = scratch("prune");
+        let now = now_ms();
+        append(&dir, &event(now - 10 * DAY_MS, "a", AuditKind::Tunnel)).unwrap();
+        append(&dir, &event(now, "a", AuditKind::Tunnel)).unwrap();
+
+        assert_eq!(prune(&dir, 0).unwrap(), 0);
+        assert_eq!(prune(&dir, 7).unwrap(), 1);
+        let events = read_events(&dir).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].timestamp_ms, now);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn manifest_hashes_every_entry() {
+        let logs = scratch("logs");
+        let audit = scratch("bundle");
+        std::fs::write(logs.join("prod-20261016-120000-abcd1234.log"), b"hello\n").unwrap();
+        std::fs::write(logs.join("staging-20261016-120000-abcd1234.log"), b"x").unwrap();
+        append(&audit, &event(now_ms(), "p", AuditKind::Command)).unwrap();
+
+        let scope = BundleScope {
+            from_ms: 0,
+            to_ms: u64::MAX,
+            connection_ids: Vec::new(),
+            log_labels: vec!["prod".to_string()],
+        };
+        let bundle = Bundle::collect(&scope, &logs, &audit).unwrap();
+        let manifest: Value = serde_json::from_slice(&bundle.manifest).unwrap();
+        let files = manifest["files"].as_array().unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files[0]["path"],
+            "session-logs/prod-20261016-120000-abcd1234.log"
+        );
+        assert_eq!(
+            files[0]["sha256"],
+            hash_bytes(b"hello\n", HashAlgorithm::Sha256).as_str()
+        );
+
+        let dest = audit.join("bundle.zip");
+        let result = bundle.write(&dest, None).unwrap();
+        assert_eq!(
+            (result.session_logs, result.commands, result.transfers),
+            (1, 1, 0)
+        );
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        assert!(zip.by_name("MANIFEST.json").is_ok());
+        assert!(zip.by_name("MANIFEST.json.sig").is_err());
+        let _ = std::fs::remove_dir_all(&logs);
+        let _ = std::fs::remove_dir_all(&audit);
+    }
+}
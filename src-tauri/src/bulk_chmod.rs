@@ -0,0 +1,456 @@
+//! Bulk permission fixes under one directory, the classic "644 for files,
+//! 755 for directories".
+//!
+//! On servers with `find` the whole job runs server-side: one pass counts what
+//! would change (`! -perm`), and unless it is a dry run `-exec chmod {} +`
+//! applies it. Without `find` (or on Windows servers) the tree is walked over
+//! SFTP instead, and local paths are walked directly. Symlinks are never
+//! followed or changed. `glob` filters by entry name like `find -name`.
+
+use crate::fs::FileSystem;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Affected paths returned for the preview.
+pub const MAX_SAMPLES: usize = 50;
+/// Error messages kept in the report.
+const MAX_ERRORS: usize = 20;
+/// Exit status of the find script when `find` is missing.
+pub const FIND_MISSING: u32 = 127;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkChmod {
+    pub file_mode: Option<u32>,
+    pub dir_mode: Option<u32>,
+    pub glob: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    Find,
+    Sftp,
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeChange {
+    pub path: String,
+    pub is_dir: bool,
+    /// Current mode in octal; not known when `find` did the counting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
+/// Return value of `fs_chmod_bulk`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkChmodReport {
+    pub dry_run: bool,
+    pub method: Method,
+    /// Files whose mode differs from `fileMode` (changed unless a dry run).
+    pub files: usize,
+    pub dirs: usize,
+    /// The first [`MAX_SAMPLES`] affected entries.
+    pub samples: Vec<ModeChange>,
+    pub errors: Vec<String>,
+}
+
+impl BulkChmodReport {
+    fn new(dry_run: bool, method: Method) -> Self {
+        Self {
+            dry_run,
+            method,
+            files: 0,
+            dirs: 0,
+            samples: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn count(&mut self, change: ModeChange) {
+        if change.is_dir {
+            self.dirs += 1;
+        } else {
+            self.files += 1;
+        }
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(change);
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        if self.errors.len() < MAX_ERRORS {
+            self.errors.push(message);
+        }
+    }
+}
+
+/// `find -name` matching: `*`, `?`, `[...]` (with `!` or `^` negation and
+/// ranges) and `\` escapes.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    fn class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+        let mut i = 1;
+        let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+        if negated {
+            i += 1;
+        }
+        let mut matched = false;
+        let mut first = true;
+        while let Some(&start) = pattern.get(i) {
+            if start == ']' && !first {
+                return Some((matched != negated, i + 1));
+            }
+            first = false;
+            if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|e| *e != ']') {
+                matched |= start <= c && c <= pattern[i + 2];
+                i += 3;
+            } else {
+                matched |= start == c;
+                i += 1;
+            }
+        }
+        None // unterminated: `[` is literal
+    }
+
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.first() {
+            None => name.is_empty(),
+            Some('*') => (0..=name.len()).any(|skip| matches(&pattern[1..], &name[skip..])),
+            Some('?') => !name.is_empty() && matches(&pattern[1..], &name[1..]),
+            Some('[') => match (name.first(), class(pattern, *name.first().unwrap_or(&'\0'))) {
+                (Some(_), Some((true, used))) => matches(&pattern[used..], &name[1..]),
+                (Some(_), Some((false, _))) | (None, _) => false,
+                (Some(c), None) => *c == '[' && matches(&pattern[1..], &name[1..]),
+            },
+            Some('\\') if pattern.len() > 1 => {
+                name.first() == Some(&pattern[1]) && matches(&pattern[2..], &name[1..])
+            }
+            Some(p) => name.first() == Some(p) && matches(&pattern[1..], &name[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+fn base_name(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some((_, name)) => name,
+        None if trimmed.is_empty() => "/",
+        None => trimmed,
+    }
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.ends_with('/') {
+        format!("{}{}", parent, name)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+impl BulkChmod {
+    pub fn new(
+        file_mode: Option<&str>,
+        dir_mode: Option<&str>,
+        glob: Option<String>,
+    ) -> Result<Self, String> {
+        let parse = |mode: Option<&str>| {
+            mode.map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(FileSystem::parse_mode)
+                .transpose()
+                .map_err(|e| e.to_string())
+        };
+        let plan = Self {
+            file_mode: parse(file_mode)?,
+            dir_mode: parse(dir_mode)?,
+            glob: glob.map(|g| g.trim().to_string()).filter(|g| !g.is_empty()),
+        };
+        if plan.file_mode.is_none() && plan.dir_mode.is_none() {
+            return Err("Give a file mode, a directory mode or both".to_string());
+        }
+        Ok(plan)
+    }
+
+    /// The mode `path` should get, when it differs from `current`.
+    pub fn change_for(&self, path: &str, is_dir: bool, current: u32) -> Option<u32> {
+        let wanted = if is_dir {
+            self.dir_mode
+        } else {
+            self.file_mode
+        }?;
+        if let Some(glob) = &self.glob {
+            if !glob_match(glob, base_name(path)) {
+                return None;
+            }
+        }
+        (current & 0o7777 != wanted).then_some(wanted)
+    }
+
+    /// Shell script that reports `D <path>`/`F <path>` for the first
+    /// [`MAX_SAMPLES`] entries to change and `D# <n>`/`F# <n>` totals, then
+    /// applies the modes unless `dry_run`.
+    pub fn find_script(&self, root: &str, dry_run: bool) -> String {
+        let root = shell_quote(root);
+        let name = self
+            .glob
+            .as_deref()
+            .map(|glob| format!(" -name {}", shell_quote(glob)))
+            .unwrap_or_default();
+        let kinds = [("d", "D", self.dir_mode), ("f", "F", self.file_mode)];
+        let mut script = format!("command -v find >/dev/null 2>&1 || exit {}\n", FIND_MISSING);
+        for (kind, tag, mode) in kinds {
+            let Some(mode) = mode else { continue };
+            script.push_str(&format!(
+                "find {root} -type {kind}{name} ! -perm {mode:04o} -print | \
+                 awk 'NR<={max}{{print \"{tag} \" $0}} END{{print \"{tag}# \" NR}}'\n",
+                max = MAX_SAMPLES
+            ));
+        }
+        if !dry_run {
+            // Directories first, so newly searchable ones can be descended into.
+            for (kind, _, mode) in kinds {
+                let Some(mode) = mode else { continue };
+                script.push_str(&format!(
+                    "find {root} -type {kind}{name} ! -perm {mode:04o} \
+                     -exec chmod {mode:04o} {{}} +\n"
+                ));
+            }
+        }
+        script
+    }
+
+    /// Reads the output of [`Self::find_script`].
+    pub fn parse_find_output(&self, stdout: &str, stderr: &str, dry_run: bool) -> BulkChmodReport {
+        let mut report = BulkChmodReport::new(dry_run, Method::Find);
+        for line in stdout.lines() {
+            match line.split_once(' ') {
+                Some(("D#", n)) => report.dirs = n.trim().parse().unwrap_or(0),
+                Some(("F#", n)) => report.files = n.trim().parse().unwrap_or(0),
+                Some((tag @ ("D" | "F"), path)) if report.samples.len() < MAX_SAMPLES => {
+                    report.samples.push(ModeChange {
+                        path: path.to_string(),
+                        is_dir: tag == "D",
+                        from: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+        for line in stderr.lines().filter(|l| !l.trim().is_empty()) {
+            report.error(line.trim().to_string());
+        }
+        report
+    }
+}
+
+/// Walks `root` over SFTP, changing modes unless `dry_run`.
+pub async fn walk_sftp(
+    sftp: &russh_sftp::client::SftpSession,
+    root: &str,
+    plan: &BulkChmod,
+    dry_run: bool,
+) -> Result<BulkChmodReport> {
+    let mut report = BulkChmodReport::new(dry_run, Method::Sftp);
+    let attrs = sftp
+        .symlink_metadata(root)
+        .await
+        .map_err(|e| anyhow!("Failed to stat '{}': {}", root, e))?;
+    let mut queue = VecDeque::from([(root.to_string(), attrs)]);
+    while let Some((path, mut attrs)) = queue.pop_front() {
+        let current = attrs.permissions.unwrap_or(0);
+        let is_dir = current & 0o170000 == 0o040000;
+        if current & 0o170000 == 0o120000 {
+            continue;
+        }
+        if let Some(mode) = plan.change_for(&path, is_dir, current) {
+            report.count(ModeChange {
+                path: path.clone(),
+                is_dir,
+                from: Some(format!("{:04o}", current & 0o7777)),
+            });
+            if !dry_run {
+                attrs.size = None;
+                attrs.uid = None;
+                attrs.user = None;
+                attrs.gid = None;
+                attrs.group = None;
+                attrs.atime = None;
+                attrs.mtime = None;
+                attrs.permissions = Some(mode);
+                if let Err(e) = sftp.set_metadata(&path, attrs).await {
+                    report.error(format!("{}: {}", path, e));
+                }
+            }
+        }
+        if !is_dir {
+            continue;
+        }
+        match sftp.read_dir(&path).await {
+            Ok(entries) => {
+                for entry in entries {
+                    let name = entry.file_name();
+                    if name != "." && name != ".." {
+                        queue.push_back((join(&path, &name), entry.metadata()));
+                    }
+                }
+            }
+            Err(e) => report.error(format!("{}: {}", path, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Walks a local `root`, changing modes unless `dry_run`.
+#[cfg(unix)]
+pub fn walk_local(root: &str, plan: &BulkChmod, dry_run: bool) -> Result<BulkChmodReport> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut report = BulkChmodReport::new(dry_run, Method::Local);
+    std::fs::symlink_metadata(root).map_err(|e| anyhow!("Failed to stat '{}': {}", root, e))?;
+    let mut queue = VecDeque::from([root.to_string()]);
+    while let Some(path) = queue.pop_front() {
+        let metadata = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if !metadata.file_type().is_symlink() => metadata,
+            Ok(_) => continue,
+            Err(e) => {
+                report.error(format!("{}: {}", path, e));
+                continue;
+            }
+        };
+        let current = metadata.permissions().mode();
+        let is_dir = metadata.is_dir();
+        if let Some(mode) = plan.change_for(&path, is_dir, current) {
+            report.count(ModeChange {
+                path: path.clone(),
+                is_dir,
+                from: Some(format!("{:04o}", current & 0o7777)),
+            });
+            if !dry_run {
+                if let Err(e) =
+                    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                {
+                    report.error(format!("{}: {}", path, e));
+                }
+            }
+        }
+        if !is_dir {
+            continue;
+        }
+        match std::fs::read_dir(&path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    queue.push_back(join(&path, &entry.file_name().to_string_lossy()));
+                }
+            }
+            Err(e) => report.error(format!("{}: {}", path, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(not(unix))]
+pub fn walk_local(_root: &str, _plan: &BulkChmod, _dry_run: bool) -> Result<BulkChmodReport> {
+    Err(anyhow!(
+        "Bulk permission changes are not supported on Windows"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_like_find_name() {
+        assert!(glob_match("*.sh", "deploy.sh"));
+        assert!(!glob_match("*.sh", "deploy.shx"));
+        assert!(glob_match("file?.[ch]", "file1.c"));
+        assert!(!glob_match("file?.[!ch]", "file1.c"));
+        assert!(glob_match("[a-c]*", "beta"));
+        assert!(glob_match("\\*", "*"));
+        assert!(!glob_match("\\*", "x"));
+        assert!(glob_match("[", "["));
+    }
+
+    #[test]
+    fn plans_only_entries_that_differ() {
+        let plan = BulkChmod::new(Some("644"), Some("0755"), Some("*.conf".to_string())).unwrap();
+        assert_eq!(
+            plan.change_for("/etc/app/a.conf", false, 0o100600),
+            Some(0o644)
+        );
+        assert_eq!(plan.change_for("/etc/app/a.conf", false, 0o100644), None);
+        assert_eq!(plan.change_for("/etc/app/a.txt", false, 0o100600), None);
+        let dirs_only = BulkChmod::new(None, Some("755"), None).unwrap();
+        assert_eq!(dirs_only.change_for("/srv", true, 0o040700), Some(0o755));
+        assert_eq!(dirs_only.change_for("/srv/x", false, 0o100600), None);
+        assert!(BulkChmod::new(None, Some(" "), None).is_err());
+    }
+
+    #[test]
+    fn builds_and_reads_the_find_script() {
+        let plan = BulkChmod::new(Some("644"), Some("755"), Some("*.php".to_string())).unwrap();
+        let dry = plan.find_script("/var/www/it's", true);
+        assert!(dry.contains("find '/var/www/it'\\''s' -type d -name '*.php' ! -perm 0755 -print"));
+        assert!(!dry.contains("-exec"));
+        let apply = plan.find_script("/var/www", false);
+        assert!(apply.contains("-type d -name '*.php' ! -perm 0755 -exec chmod 0755 {} +"));
+        assert!(apply.contains("-type f -name '*.php' ! -perm 0644 -exec chmod 0644 {} +"));
+
+        let report = plan.parse_find_output(
+            "D /var/www\nD# 1\nF /var/www/index.php\nF# 120\n",
+            "find: '/var/www/private': Permission denied\n",
+            true,
+        );
+        assert_eq!((report.dirs, report.files), (1, 120));
+        assert_eq!(report.samples.len(), 2);
+        assert!(report.samples[0].is_dir);
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walks_local_trees_with_dry_run() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("zync-bulk-chmod-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/a.txt"), b"a").unwrap();
+        std::fs::set_permissions(
+            root.join("sub/a.txt"),
+            std::fs::Permissions::from_mode(0o600),
+        )
+        .unwrap();
+        std::fs::set_permissions(root.join("sub"), std::fs::Permissions::from_mode(0o700)).unwrap();
+        let root_str = root.to_string_lossy().to_string();
+        std::fs::set_permissions(&root, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let plan = BulkChmod::new(Some("644"), Some("755"), None).unwrap();
+        let preview = walk_local(&root_str, &plan, true).unwrap();
+        assert_eq!((preview.dirs, preview.files), (1, 1));
+        let mode = |p: &str| {
+            std::fs::metadata(root.join(p))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777
+        };
+        assert_eq!(mode("sub/a.txt"), 0o600);
+
+        let applied = walk_local(&root_str, &plan, false).unwrap();
+        assert_eq!((applied.dirs, applied.files), (1, 1));
+        assert_eq!(mode("sub/a.txt"), 0o644);
+        assert_eq!(mode("sub"), 0o755);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
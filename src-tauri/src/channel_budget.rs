@@ -0,0 +1,337 @@
+//! Per-connection budget for SSH session channels.
+//!
+//! Servers cap the session channels (shells, exec, SFTP) open at once on one
+//! SSH connection, OpenSSH through `MaxSessions` (10 by default), and refuse
+//! any beyond it. Zync opens them for terminals, file operations, probes and
+//! OS detection alike, so session channels go through [`open_session`], which
+//! waits for a free slot once the connection's budget is used up. A channel
+//! holds its slot until the server closes it.
+//!
+//! When the server refuses a channel anyway, the budget drops to the number
+//! that was open so later requests queue instead of failing, and the caller
+//! gets a `CHANNEL_LIMIT:` error with a JSON payload explaining what to change.
+//! Forwarded TCP channels are not sessions and are not counted.
+
+use crate::ssh::Client;
+use russh::client::{Handle, Msg};
+use russh::{Channel, ChannelId, ChannelOpenFailure};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+pub const CHANNEL_LIMIT: &str = "CHANNEL_LIMIT";
+/// OpenSSH's default `MaxSessions`.
+pub const DEFAULT_BUDGET: usize = 10;
+/// How long a request waits for a slot before giving up.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Budgets of the live connections, by connection id.
+static BUDGETS: LazyLock<Mutex<HashMap<String, Arc<ChannelBudget>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelLimitError {
+    pub connection_id: String,
+    pub open: usize,
+    pub limit: usize,
+    /// `true` when the server refused the channel, `false` when the request
+    /// gave up waiting for a slot.
+    pub refused_by_server: bool,
+    pub message: String,
+}
+
+impl ChannelLimitError {
+    pub fn to_error_string(&self) -> String {
+        format!(
+            "{}:{}",
+            CHANNEL_LIMIT,
+            serde_json::to_string(self).unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum ChannelOpenError {
+    Limit(ChannelLimitError),
+    Ssh(russh::Error),
+}
+
+impl ChannelOpenError {
+    pub fn is_limit(&self) -> bool {
+        matches!(self, Self::Limit(_))
+    }
+}
+
+impl std::fmt::Display for ChannelOpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Limit(error) => f.write_str(&error.to_error_string()),
+            Self::Ssh(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ChannelOpenError {}
+
+#[derive(Debug)]
+struct BudgetState<Id> {
+    limit: usize,
+    open: HashSet<Id>,
+    /// Slots taken by opens still waiting for the server's answer.
+    pending: usize,
+}
+
+/// Session channel slots of one SSH connection.
+#[derive(Debug)]
+pub struct ChannelBudget<Id = ChannelId> {
+    state: Mutex<BudgetState<Id>>,
+    released: Notify,
+}
+
+impl<Id: Eq + Hash> ChannelBudget<Id> {
+    /// `limit` is the connection's `channel_budget`; unset or zero means
+    /// [`DEFAULT_BUDGET`].
+    pub fn new(limit: Option<u32>) -> Arc<Self> {
+        let limit = limit
+            .map(|limit| limit as usize)
+            .filter(|limit| *limit > 0)
+            .unwrap_or(DEFAULT_BUDGET);
+        Arc::new(Self {
+            state: Mutex::new(BudgetState {
+                limit,
+                open: HashSet::new(),
+                pending: 0,
+            }),
+            released: Notify::new(),
+        })
+    }
+
+    /// `(open, limit)`.
+    pub fn usage(&self) -> (usize, usize) {
+        let state = self.state.lock().unwrap();
+        (state.open.len(), state.limit)
+    }
+
+    /// The server closed `id`; frees its slot if it held one.
+    pub fn closed(&self, id: Id) {
+        if self.state.lock().unwrap().open.remove(&id) {
+            self.released.notify_waiters();
+        }
+    }
+
+    /// Takes a slot, waiting up to `timeout` for one to free up.
+    async fn reserve(&self, timeout: Duration) -> Option<Reservation<'_, Id>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            // Register before checking, so a release in between is not missed.
+            released.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.open.len() + state.pending < state.limit {
+                    state.pending += 1;
+                    return Some(Reservation {
+                        budget: self,
+                        done: false,
+                    });
+                }
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    /// The server refused a channel: what is open now is all it allows.
+    fn refused(&self) -> (usize, usize) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = state.open.len().max(1);
+        (state.open.len(), state.limit)
+    }
+}
+
+struct Reservation<'a, Id: Eq + Hash> {
+    budget: &'a ChannelBudget<Id>,
+    done: bool,
+}
+
+impl<Id: Eq + Hash> Reservation<'_, Id> {
+    fn opened(mut self, id: Id) {
+        let mut state = self.budget.state.lock().unwrap();
+        state.pending -= 1;
+        state.open.insert(id);
+        self.done = true;
+    }
+}
+
+impl<Id: Eq + Hash> Drop for Reservation<'_, Id> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.budget.state.lock().unwrap().pending -= 1;
+            self.budget.released.notify_waiters();
+        }
+    }
+}
+
+/// Makes `budget` the one [`open_session`] uses for `connection_id`.
+pub fn register(connection_id: &str, budget: Arc<ChannelBudget>) {
+    BUDGETS
+        .lock()
+        .unwrap()
+        .insert(connection_id.to_string(), budget);
+}
+
+pub fn unregister(connection_id: &str) {
+    BUDGETS.lock().unwrap().remove(connection_id);
+}
+
+fn budget_for(connection_id: &str) -> Option<Arc<ChannelBudget>> {
+    BUDGETS.lock().unwrap().get(connection_id).cloned()
+}
+
+fn refused_by_server(error: &russh::Error) -> bool {
+    matches!(
+        error,
+        russh::Error::ChannelOpenFailure(
+            ChannelOpenFailure::AdministrativelyProhibited | ChannelOpenFailure::ResourceShortage
+        )
+    )
+}
+
+/// Opens a session channel on `connection_id`'s connection within its budget.
+/// Connections without a registered budget (tests, jump hops) open directly.
+pub async fn open_session(
+    connection_id: &str,
+    handle: &Handle<Client>,
+) -> Result<Channel<Msg>, ChannelOpenError> {
+    match budget_for(connection_id) {
+        Some(budget) => budget.open_session(connection_id, handle).await,
+        None => handle
+            .channel_open_session()
+            .await
+            .map_err(ChannelOpenError::Ssh),
+    }
+}
+
+impl ChannelBudget {
+    /// Opens a session channel once a slot is free.
+    pub async fn open_session(
+        &self,
+        connection_id: &str,
+        handle: &Handle<Client>,
+    ) -> Result<Channel<Msg>, ChannelOpenError> {
+        let Some(reservation) = self.reserve(QUEUE_TIMEOUT).await else {
+            let (open, limit) = self.usage();
+            return Err(ChannelOpenError::Limit(ChannelLimitError {
+                connection_id: connection_id.to_string(),
+                open,
+                limit,
+                refused_by_server: false,
+                message: format!(
+                    "All {} channels of this connection stayed in use for {}s. Close terminals \
+                     or transfers you no longer need, or raise the connection's channel budget \
+                     if the server allows more sessions.",
+                    limit,
+                    QUEUE_TIMEOUT.as_secs()
+                ),
+            }));
+        };
+        match handle.channel_open_session().await {
+            Ok(channel) => {
+                reservation.opened(channel.id());
+                Ok(channel)
+            }
+            Err(error) if refused_by_server(&error) => {
+                drop(reservation);
+                let (open, limit) = self.refused();
+                crate::log_error!(
+                    "[SSH] {} refused a channel with {} open; budget lowered to {}",
+                    connection_id,
+                    open,
+                    limit
+                );
+                Err(ChannelOpenError::Limit(ChannelLimitError {
+                    connection_id: connection_id.to_string(),
+                    open,
+                    limit,
+                    refused_by_server: true,
+                    message: format!(
+                        "The server refused a new channel with {} already open. Raise \
+                         `MaxSessions` in the server's sshd_config, or set this connection's \
+                         channel budget to {} so further requests wait for a free channel.",
+                        open, limit
+                    ),
+                }))
+            }
+            Err(error) => Err(ChannelOpenError::Ssh(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn queues_beyond_the_budget() {
+        let budget = ChannelBudget::<u32>::new(Some(2));
+        budget.reserve(QUEUE_TIMEOUT).await.unwrap().opened(1);
+        let pending = budget.reserve(QUEUE_TIMEOUT).await.unwrap();
+        assert!(budget.reserve(Duration::from_millis(20)).await.is_none());
+
+        // A failed open gives its slot back.
+        drop(pending);
+        budget.reserve(QUEUE_TIMEOUT).await.unwrap().opened(2);
+        assert_eq!(budget.usage(), (2, 2));
+
+        let waiter = {
+            let budget = budget.clone();
+            tokio::spawn(async move {
+                budget
+                    .reserve(QUEUE_TIMEOUT)
+                    .await
+                    .map(|slot| slot.opened(3))
+                    .is_some()
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        budget.closed(1);
+        assert!(waiter.await.unwrap());
+        assert_eq!(budget.usage(), (2, 2));
+    }
+
+    #[test]
+    fn refusals_lower_the_budget() {
+        let budget = ChannelBudget::<u32>::new(None);
+        assert_eq!(budget.usage(), (0, DEFAULT_BUDGET));
+        budget.state.lock().unwrap().open.extend([1, 2]);
+        assert_eq!(budget.refused(), (2, 2));
+        budget.closed(1);
+        budget.closed(2);
+        // Never below one, or nothing could open again.
+        assert_eq!(budget.refused(), (0, 1));
+        assert_eq!(ChannelBudget::<u32>::new(Some(0)).usage().1, DEFAULT_BUDGET);
+    }
+
+    #[test]
+    fn limit_errors_carry_a_json_payload() {
+        let error = ChannelOpenError::Limit(ChannelLimitError {
+            connection_id: "c1".to_string(),
+            open: 10,
+            limit: 10,
+            refused_by_server: true,
+            message: "m".to_string(),
+        });
+        assert!(error.is_limit());
+        let text = error.to_string();
+        let json: serde_json::Value =
+            serde_json::from_str(text.strip_prefix("CHANNEL_LIMIT:").unwrap()).unwrap();
+        assert_eq!(json["refusedByServer"], true);
+        assert_eq!(json["connectionId"], "c1");
+    }
+}
@@ -0,0 +1,286 @@
+//! EC2 instances via `aws ec2 describe-instances`.
+//!
+//! The AWS CLI resolves profiles, SSO sessions and assumed roles exactly as it
+//! does in the user's shell, which signing requests here could not match.
+
+use super::{key_value_filters, non_empty, CloudProvider};
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Login user for Amazon Linux; override per import or with a `ssh-user` tag.
+const DEFAULT_USERNAME: &str = "ec2-user";
+/// Instance tags naming the SSH login user.
+const USER_TAGS: [&str; 2] = ["ssh-user", "zync:user"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AwsQuery {
+    /// Named profile from `~/.aws/config`; the CLI default when unset.
+    pub profile: Option<String>,
+    pub region: Option<String>,
+    /// Only list instances in the `running` state.
+    pub running_only: bool,
+    /// Connect to the private address even when there is a public one
+    /// (VPN or bastion setups).
+    pub use_private_ip: bool,
+    pub username: Option<String>,
+    /// Directory holding `<KeyName>.pem` files for the instances' key pairs.
+    pub key_directory: Option<String>,
+    /// `Key=Value` tag filters; all must match.
+    pub tag_filters: Vec<String>,
+}
+
+impl Default for AwsQuery {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            region: None,
+            running_only: true,
+            use_private_ip: false,
+            username: None,
+            key_directory: None,
+            tag_filters: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DescribeInstances {
+    #[serde(default)]
+    reservations: Vec<Reservation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Reservation {
+    #[serde(default)]
+    instances: Vec<Instance>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Instance {
+    instance_id: String,
+    public_ip_address: Option<String>,
+    private_ip_address: Option<String>,
+    key_name: Option<String>,
+    platform: Option<String>,
+    #[serde(default)]
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Tag {
+    key: String,
+    value: String,
+}
+
+pub fn describe_args(query: &AwsQuery) -> Vec<String> {
+    let mut args: Vec<String> = ["ec2", "describe-instances", "--output", "json"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if let Some(profile) = non_empty(&query.profile) {
+        args.extend(["--profile".to_string(), profile.to_string()]);
+    }
+    if let Some(region) = non_empty(&query.region) {
+        args.extend(["--region".to_string(), region.to_string()]);
+    }
+    let mut filters = Vec::new();
+    if query.running_only {
+        filters.push("Name=instance-state-name,Values=running".to_string());
+    }
+    for (key, value) in key_value_filters(&query.tag_filters) {
+        filters.push(format!("Name=tag:{},Values={}", key, value));
+    }
+    if !filters.is_empty() {
+        args.push("--filters".to_string());
+        args.extend(filters);
+    }
+    args
+}
+
+/// Maps `describe-instances` JSON to connections. Windows instances and
+/// instances without a usable address are left out.
+pub fn parse_instances(json: &[u8], query: &AwsQuery) -> Result<Vec<ParsedSshConnection>> {
+    let output: DescribeInstances =
+        serde_json::from_slice(json).context("Unexpected `aws ec2 describe-instances` output")?;
+    let mut connections = Vec::new();
+    for instance in output.reservations.into_iter().flat_map(|r| r.instances) {
+        if instance
+            .platform
+            .as_deref()
+            .is_some_and(|p| p.eq_ignore_ascii_case("windows"))
+        {
+            continue;
+        }
+        let address = if query.use_private_ip {
+            non_empty(&instance.private_ip_address)
+        } else {
+            non_empty(&instance.public_ip_address).or(non_empty(&instance.private_ip_address))
+        };
+        let Some(address) = address else {
+            continue;
+        };
+        let tag = |key: &str| {
+            instance
+                .tags
+                .iter()
+                .find(|tag| tag.key.eq_ignore_ascii_case(key))
+                .map(|tag| tag.value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let name = tag("Name")
+            .unwrap_or(instance.instance_id.as_str())
+            .to_string();
+        let username = non_empty(&query.username)
+            .or_else(|| USER_TAGS.into_iter().find_map(|key| tag(key)))
+            .unwrap_or(DEFAULT_USERNAME)
+            .to_string();
+        let private_key_path = non_empty(&query.key_directory).and_then(|dir| {
+            let key = Path::new(dir).join(format!("{}.pem", non_empty(&instance.key_name)?));
+            key.is_file().then(|| key.to_string_lossy().to_string())
+        });
+        let mut tags = vec!["aws".to_string()];
+        tags.extend(non_empty(&query.region).map(str::to_string));
+        tags.extend(
+            instance
+                .tags
+                .iter()
+                .filter(|tag| tag.key != "Name" && !tag.key.starts_with("aws:"))
+                .filter(|tag| !USER_TAGS.contains(&tag.key.as_str()))
+                .map(|tag| format!("{}:{}", tag.key, tag.value)),
+        );
+        connections.push(ParsedSshConnection {
+            id: format!("aws_{}", instance.instance_id),
+            name: name.clone(),
+            host: address.to_string(),
+            username,
+            port: 22,
+            private_key_path,
+            jump_server_alias: None,
+            jump_server_id: None,
+            aliases: vec![name, instance.instance_id.clone()],
+            identities_only: None,
+            tags,
+            folder: None,
+        });
+    }
+    Ok(connections)
+}
+
+impl CloudProvider for AwsQuery {
+    fn program(&self) -> &'static str {
+        "aws"
+    }
+
+    fn list_args(&self) -> Vec<String> {
+        describe_args(self)
+    }
+
+    fn parse(&self, stdout: &[u8]) -> Result<Vec<ParsedSshConnection>> {
+        parse_instances(stdout, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"{
+      "Reservations": [{
+        "Instances": [
+          {
+            "InstanceId": "i-0abc",
+            "PublicIpAddress": "54.1.2.3",
+            "PrivateIpAddress": "10.0.0.5",
+            "KeyName": "deploy",
+            "Tags": [
+              {"Key": "Name", "Value": "web-1"},
+              {"Key": "env", "Value": "prod"},
+              {"Key": "ssh-user", "Value": "ubuntu"},
+              {"Key": "aws:autoscaling:groupName", "Value": "web"}
+            ]
+          },
+          {"InstanceId": "i-0def", "PrivateIpAddress": "10.0.0.6"},
+          {"InstanceId": "i-0win", "PublicIpAddress": "54.1.2.4", "Platform": "windows"},
+          {"InstanceId": "i-0none"}
+        ]
+      }]
+    }"#;
+
+    #[test]
+    fn builds_cli_arguments() {
+        let query = AwsQuery {
+            profile: Some("ops".to_string()),
+            region: Some("eu-west-1".to_string()),
+            tag_filters: vec!["env=prod".to_string(), "invalid".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            describe_args(&query).join(" "),
+            "ec2 describe-instances --output json --profile ops --region eu-west-1 \
+             --filters Name=instance-state-name,Values=running Name=tag:env,Values=prod"
+        );
+        let all = AwsQuery {
+            running_only: false,
+            ..Default::default()
+        };
+        assert_eq!(describe_args(&all).len(), 4);
+    }
+
+    #[test]
+    fn maps_instances_to_connections() {
+        let query = AwsQuery {
+            region: Some("eu-west-1".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_instances(OUTPUT.as_bytes(), &query).unwrap();
+        assert_eq!(connections.len(), 2);
+
+        let web = &connections[0];
+        assert_eq!(web.id, "aws_i-0abc");
+        assert_eq!(web.name, "web-1");
+        assert_eq!(web.host, "54.1.2.3");
+        assert_eq!(web.username, "ubuntu");
+        assert_eq!(web.tags, ["aws", "eu-west-1", "env:prod"]);
+        assert_eq!(web.aliases, ["web-1", "i-0abc"]);
+
+        let unnamed = &connections[1];
+        assert_eq!(unnamed.name, "i-0def");
+        assert_eq!(unnamed.host, "10.0.0.6");
+        assert_eq!(unnamed.username, DEFAULT_USERNAME);
+
+        let private = AwsQuery {
+            use_private_ip: true,
+            username: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_instances(OUTPUT.as_bytes(), &private).unwrap();
+        assert_eq!(connections[0].host, "10.0.0.5");
+        assert_eq!(connections[0].username, "admin");
+        assert!(parse_instances(b"not json", &query).is_err());
+    }
+
+    #[test]
+    fn finds_key_pairs_in_the_key_directory() {
+        let dir = std::env::temp_dir().join(format!("zync-aws-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("deploy.pem"), "key").unwrap();
+        let query = AwsQuery {
+            key_directory: Some(dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let connections = parse_instances(OUTPUT.as_bytes(), &query).unwrap();
+        assert!(connections[0]
+            .private_key_path
+            .as_deref()
+            .is_some_and(|path| path.ends_with("deploy.pem")));
+        assert_eq!(connections[1].private_key_path, None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,271 @@
+//! Azure virtual machines via `az vm list --show-details`.
+//!
+//! `az vm list` has no state or tag filters of its own, so both are applied
+//! to the listed machines here.
+
+use super::{key_value_filters, non_empty, CloudProvider};
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default admin user of Azure Linux images.
+const DEFAULT_USERNAME: &str = "azureuser";
+/// VM tag naming the SSH login user.
+const USER_TAG: &str = "ssh-user";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AzureQuery {
+    /// Subscription name or id; the `az` default when unset.
+    pub subscription: Option<String>,
+    pub resource_group: Option<String>,
+    /// Only list machines whose power state is `VM running`.
+    pub running_only: bool,
+    pub use_private_ip: bool,
+    pub username: Option<String>,
+    /// `Key=Value` tag filters; all must match.
+    pub tag_filters: Vec<String>,
+}
+
+impl Default for AzureQuery {
+    fn default() -> Self {
+        Self {
+            subscription: None,
+            resource_group: None,
+            running_only: true,
+            use_private_ip: false,
+            username: None,
+            tag_filters: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VirtualMachine {
+    name: String,
+    vm_id: Option<String>,
+    #[serde(default)]
+    resource_group: String,
+    #[serde(default)]
+    location: String,
+    power_state: Option<String>,
+    public_ips: Option<String>,
+    private_ips: Option<String>,
+    #[serde(default)]
+    tags: Option<BTreeMap<String, String>>,
+    os_profile: Option<OsProfile>,
+    storage_profile: Option<StorageProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OsProfile {
+    admin_username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageProfile {
+    os_disk: Option<OsDisk>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OsDisk {
+    os_type: Option<String>,
+}
+
+pub fn list_args(query: &AzureQuery) -> Vec<String> {
+    let mut args: Vec<String> = ["vm", "list", "--show-details", "--output", "json"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if let Some(subscription) = non_empty(&query.subscription) {
+        args.extend(["--subscription".to_string(), subscription.to_string()]);
+    }
+    if let Some(group) = non_empty(&query.resource_group) {
+        args.extend(["--resource-group".to_string(), group.to_string()]);
+    }
+    args
+}
+
+/// First address of a comma-separated `publicIps`/`privateIps` value.
+fn first_ip(ips: &Option<String>) -> Option<&str> {
+    non_empty(ips)?
+        .split(',')
+        .map(str::trim)
+        .find(|ip| !ip.is_empty())
+}
+
+/// Maps `vm list` JSON to connections. Windows machines, stopped machines
+/// (with `running_only`) and machines without a usable address are left out.
+pub fn parse_machines(json: &[u8], query: &AzureQuery) -> Result<Vec<ParsedSshConnection>> {
+    let machines: Vec<VirtualMachine> =
+        serde_json::from_slice(json).context("Unexpected `az vm list` output")?;
+    let filters = key_value_filters(&query.tag_filters);
+    let mut connections = Vec::new();
+    for machine in machines {
+        let os_type = machine
+            .storage_profile
+            .as_ref()
+            .and_then(|profile| profile.os_disk.as_ref())
+            .and_then(|disk| disk.os_type.as_deref());
+        if os_type.is_some_and(|os| os.eq_ignore_ascii_case("windows")) {
+            continue;
+        }
+        if query.running_only && machine.power_state.as_deref() != Some("VM running") {
+            continue;
+        }
+        let tags = machine.tags.unwrap_or_default();
+        if !filters
+            .iter()
+            .all(|(key, value)| tags.get(*key).is_some_and(|v| v == value))
+        {
+            continue;
+        }
+        let address = if query.use_private_ip {
+            first_ip(&machine.private_ips)
+        } else {
+            first_ip(&machine.public_ips).or(first_ip(&machine.private_ips))
+        };
+        let Some(address) = address else {
+            continue;
+        };
+        let username = non_empty(&query.username)
+            .or_else(|| tags.get(USER_TAG).map(String::as_str))
+            .or_else(|| {
+                machine
+                    .os_profile
+                    .as_ref()
+                    .and_then(|profile| non_empty(&profile.admin_username))
+            })
+            .unwrap_or(DEFAULT_USERNAME)
+            .to_string();
+        let mut connection_tags = vec!["azure".to_string()];
+        connection_tags.extend(
+            [&machine.location, &machine.resource_group]
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .cloned(),
+        );
+        connection_tags.extend(
+            tags.iter()
+                .filter(|(key, _)| key.as_str() != USER_TAG)
+                .map(|(key, value)| format!("{}:{}", key, value)),
+        );
+        let id = machine.vm_id.as_deref().unwrap_or(machine.name.as_str());
+        connections.push(ParsedSshConnection {
+            id: format!("azure_{}", id),
+            name: machine.name.clone(),
+            host: address.to_string(),
+            username,
+            port: 22,
+            private_key_path: None,
+            jump_server_alias: None,
+            jump_server_id: None,
+            aliases: vec![machine.name.clone()],
+            identities_only: None,
+            tags: connection_tags,
+            folder: None,
+        });
+    }
+    Ok(connections)
+}
+
+impl CloudProvider for AzureQuery {
+    fn program(&self) -> &'static str {
+        if cfg!(windows) {
+            "az.cmd"
+        } else {
+            "az"
+        }
+    }
+
+    fn list_args(&self) -> Vec<String> {
+        list_args(self)
+    }
+
+    fn parse(&self, stdout: &[u8]) -> Result<Vec<ParsedSshConnection>> {
+        parse_machines(stdout, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"[
+      {
+        "name": "web-1",
+        "vmId": "0f1e-22",
+        "resourceGroup": "shop-prod",
+        "location": "westeurope",
+        "powerState": "VM running",
+        "publicIps": "20.1.2.3",
+        "privateIps": "10.1.0.4,10.1.1.4",
+        "tags": {"env": "prod"},
+        "osProfile": {"adminUsername": "ops"},
+        "storageProfile": {"osDisk": {"osType": "Linux"}}
+      },
+      {
+        "name": "batch",
+        "resourceGroup": "shop-prod",
+        "location": "westeurope",
+        "powerState": "VM deallocated",
+        "privateIps": "10.1.0.5",
+        "tags": null
+      },
+      {
+        "name": "dc",
+        "powerState": "VM running",
+        "publicIps": "20.1.2.4",
+        "storageProfile": {"osDisk": {"osType": "Windows"}}
+      }
+    ]"#;
+
+    #[test]
+    fn builds_cli_arguments() {
+        let query = AzureQuery {
+            subscription: Some("prod".to_string()),
+            resource_group: Some("shop-prod".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            list_args(&query).join(" "),
+            "vm list --show-details --output json --subscription prod --resource-group shop-prod"
+        );
+    }
+
+    #[test]
+    fn maps_machines_to_connections() {
+        let connections = parse_machines(OUTPUT.as_bytes(), &AzureQuery::default()).unwrap();
+        assert_eq!(connections.len(), 1);
+        let web = &connections[0];
+        assert_eq!(web.id, "azure_0f1e-22");
+        assert_eq!(web.host, "20.1.2.3");
+        assert_eq!(web.username, "ops");
+        assert_eq!(web.tags, ["azure", "westeurope", "shop-prod", "env:prod"]);
+
+        let all = AzureQuery {
+            running_only: false,
+            use_private_ip: true,
+            ..Default::default()
+        };
+        let connections = parse_machines(OUTPUT.as_bytes(), &all).unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].host, "10.1.0.4");
+        assert_eq!(connections[1].id, "azure_batch");
+        assert_eq!(connections[1].username, DEFAULT_USERNAME);
+
+        let tagged = AzureQuery {
+            running_only: false,
+            tag_filters: vec!["env=staging".to_string()],
+            ..Default::default()
+        };
+        assert!(parse_machines(OUTPUT.as_bytes(), &tagged)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+//! Compute Engine instances via `gcloud compute instances list`.
+//!
+//! Login users and keys follow `gcloud compute ssh`: the local user name and
+//! `~/.ssh/google_compute_engine`, which gcloud pushes to project metadata the
+//! first time it is used.
+
+use super::{key_value_filters, local_username, non_empty, CloudProvider};
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Instance label naming the SSH login user (labels cannot contain `:`).
+const USER_LABEL: &str = "ssh-user";
+/// Key `gcloud compute ssh` generates and registers.
+const GCLOUD_KEY: &str = ".ssh/google_compute_engine";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GcpQuery {
+    /// The gcloud default project when unset.
+    pub project: Option<String>,
+    /// Only list instances in these zones; all zones when empty.
+    pub zones: Vec<String>,
+    /// Only list `RUNNING` instances.
+    pub running_only: bool,
+    pub use_private_ip: bool,
+    pub username: Option<String>,
+    /// `key=value` label filters; all must match.
+    pub label_filters: Vec<String>,
+}
+
+impl Default for GcpQuery {
+    fn default() -> Self {
+        Self {
+            project: None,
+            zones: Vec::new(),
+            running_only: true,
+            use_private_ip: false,
+            username: None,
+            label_filters: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Instance {
+    id: String,
+    name: String,
+    #[serde(default)]
+    zone: String,
+    #[serde(default)]
+    network_interfaces: Vec<NetworkInterface>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    #[serde(default)]
+    disks: Vec<Disk>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkInterface {
+    #[serde(rename = "networkIP")]
+    network_ip: Option<String>,
+    #[serde(default)]
+    access_configs: Vec<AccessConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessConfig {
+    #[serde(rename = "natIP")]
+    nat_ip: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Disk {
+    #[serde(default)]
+    licenses: Vec<String>,
+}
+
+pub fn list_args(query: &GcpQuery) -> Vec<String> {
+    let mut args: Vec<String> = ["compute", "instances", "list", "--format=json"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if let Some(project) = non_empty(&query.project) {
+        args.push(format!("--project={}", project));
+    }
+    let zones: Vec<&str> = query
+        .zones
+        .iter()
+        .map(|zone| zone.trim())
+        .filter(|zone| !zone.is_empty())
+        .collect();
+    if !zones.is_empty() {
+        args.push(format!("--zones={}", zones.join(",")));
+    }
+    let mut filters = Vec::new();
+    if query.running_only {
+        filters.push("status=RUNNING".to_string());
+    }
+    for (key, value) in key_value_filters(&query.label_filters) {
+        filters.push(format!("labels.{}={}", key, value));
+    }
+    if !filters.is_empty() {
+        args.push(format!("--filter={}", filters.join(" AND ")));
+    }
+    args
+}
+
+/// Last segment of a resource URL (`.../zones/europe-west1-b`).
+fn resource_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// Maps `instances list` JSON to connections. Windows instances and
+/// instances without a usable address are left out.
+pub fn parse_instances(json: &[u8], query: &GcpQuery) -> Result<Vec<ParsedSshConnection>> {
+    let instances: Vec<Instance> = serde_json::from_slice(json)
+        .context("Unexpected `gcloud compute instances list` output")?;
+    let gcloud_key = dirs::home_dir()
+        .map(|home| home.join(GCLOUD_KEY))
+        .filter(|key| key.is_file())
+        .map(|key| key.to_string_lossy().to_string());
+    let mut connections = Vec::new();
+    for instance in instances {
+        let windows = instance
+            .disks
+            .iter()
+            .flat_map(|disk| &disk.licenses)
+            .any(|license| license.contains("/windows-cloud/"));
+        if windows {
+            continue;
+        }
+        let interface = instance.network_interfaces.first();
+        let private = interface.and_then(|i| non_empty(&i.network_ip));
+        let public =
+            interface.and_then(|i| i.access_configs.iter().find_map(|c| non_empty(&c.nat_ip)));
+        let address = if query.use_private_ip {
+            private
+        } else {
+            public.or(private)
+        };
+        let Some(address) = address else {
+            continue;
+        };
+        let username = non_empty(&query.username)
+            .map(str::to_string)
+            .or_else(|| instance.labels.get(USER_LABEL).cloned())
+            .or_else(local_username)
+            .unwrap_or_else(|| "root".to_string());
+        let zone = resource_name(&instance.zone);
+        let mut tags = vec!["gcp".to_string()];
+        tags.extend(non_empty(&query.project).map(str::to_string));
+        if !zone.is_empty() {
+            tags.push(zone.to_string());
+        }
+        tags.extend(
+            instance
+                .labels
+                .iter()
+                .filter(|(key, _)| key.as_str() != USER_LABEL)
+                .map(|(key, value)| format!("{}:{}", key, value)),
+        );
+        connections.push(ParsedSshConnection {
+            id: format!("gcp_{}", instance.id),
+            name: instance.name.clone(),
+            host: address.to_string(),
+            username,
+            port: 22,
+            private_key_path: gcloud_key.clone(),
+            jump_server_alias: None,
+            jump_server_id: None,
+            aliases: vec![instance.name],
+            identities_only: None,
+            tags,
+            folder: None,
+        });
+    }
+    Ok(connections)
+}
+
+impl CloudProvider for GcpQuery {
+    fn program(&self) -> &'static str {
+        if cfg!(windows) {
+            "gcloud.cmd"
+        } else {
+            "gcloud"
+        }
+    }
+
+    fn list_args(&self) -> Vec<String> {
+        list_args(self)
+    }
+
+    fn parse(&self, stdout: &[u8]) -> Result<Vec<ParsedSshConnection>> {
+        parse_instances(stdout, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = r#"[
+      {
+        "id": "4211",
+        "name": "api-1",
+        "zone": "https://www.googleapis.com/compute/v1/projects/shop/zones/europe-west1-b",
+        "status": "RUNNING",
+        "networkInterfaces": [{
+          "networkIP": "10.132.0.2",
+          "accessConfigs": [{"name": "External NAT", "natIP": "34.76.1.2"}]
+        }],
+        "labels": {"env": "prod", "ssh-user": "deploy"},
+        "disks": [{"licenses": ["https://www.googleapis.com/compute/v1/projects/debian-cloud/global/licenses/debian-12-bookworm"]}]
+      },
+      {
+        "id": "4212",
+        "name": "worker",
+        "zone": "https://www.googleapis.com/compute/v1/projects/shop/zones/europe-west1-c",
+        "networkInterfaces": [{"networkIP": "10.132.0.3"}]
+      },
+      {
+        "id": "4213",
+        "name": "ad",
+        "networkInterfaces": [{"networkIP": "10.132.0.4"}],
+        "disks": [{"licenses": ["https://www.googleapis.com/compute/v1/projects/windows-cloud/global/licenses/windows-server-2022-dc"]}]
+      }
+    ]"#;
+
+    #[test]
+    fn builds_cli_arguments() {
+        let query = GcpQuery {
+            project: Some("shop".to_string()),
+            zones: vec!["europe-west1-b".to_string(), " ".to_string()],
+            label_filters: vec!["env=prod".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            list_args(&query),
+            [
+                "compute",
+                "instances",
+                "list",
+                "--format=json",
+                "--project=shop",
+                "--zones=europe-west1-b",
+                "--filter=status=RUNNING AND labels.env=prod"
+            ]
+        );
+        let all = GcpQuery {
+            running_only: false,
+            ..Default::default()
+        };
+        assert_eq!(list_args(&all).len(), 4);
+    }
+
+    #[test]
+    fn maps_instances_to_connections() {
+        let query = GcpQuery {
+            project: Some("shop".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_instances(OUTPUT.as_bytes(), &query).unwrap();
+        assert_eq!(connections.len(), 2);
+
+        let api = &connections[0];
+        assert_eq!(api.id, "gcp_4211");
+        assert_eq!(api.name, "api-1");
+        assert_eq!(api.host, "34.76.1.2");
+        assert_eq!(api.username, "deploy");
+        assert_eq!(api.tags, ["gcp", "shop", "europe-west1-b", "env:prod"]);
+        assert_eq!(connections[1].host, "10.132.0.3");
+
+        let private = GcpQuery {
+            use_private_ip: true,
+            username: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_instances(OUTPUT.as_bytes(), &private).unwrap();
+        assert_eq!(connections[0].host, "10.132.0.2");
+        assert_eq!(connections[0].username, "admin");
+        assert!(parse_instances(b"{}", &query).is_err());
+    }
+}
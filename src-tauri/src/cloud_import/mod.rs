@@ -0,0 +1,305 @@
+//! Connections discovered from cloud provider inventories.
+//!
+//! Each provider lists instances through its own CLI (already installed and
+//! authenticated on most machines that manage cloud servers, and the only
+//! place their credentials live) and maps them to [`ParsedSshConnection`]s
+//! with stable ids, so a re-import updates the same entries. Providers
+//! implement [`CloudProvider`]; [`CloudQuery`] picks one. Imports can be
+//! saved as sources in `cloud-sources.json`; the refresher re-runs due sources
+//! in the background and emits `cloud:refreshed` with the current instances.
+
+pub mod aws;
+pub mod azure;
+pub mod gcp;
+
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+const CLI_TIMEOUT: Duration = Duration::from_secs(60);
+const REFRESH_TICK: Duration = Duration::from_secs(60);
+const MIN_REFRESH_MINUTES: u32 = 5;
+const SOURCES_FILE: &str = "cloud-sources.json";
+
+/// An instance inventory listed through a provider CLI.
+pub trait CloudProvider: Send + Sync {
+    /// Executable to run. Windows installs of some CLIs are `.cmd` scripts,
+    /// which have to be named in full since only `.exe` is implied.
+    fn program(&self) -> &'static str;
+
+    /// Arguments that print the inventory as JSON.
+    fn list_args(&self) -> Vec<String>;
+
+    /// Maps the CLI output to connections.
+    fn parse(&self, stdout: &[u8]) -> Result<Vec<ParsedSshConnection>>;
+}
+
+/// One provider query; what a saved source re-runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum CloudQuery {
+    Aws(aws::AwsQuery),
+    Gcp(gcp::GcpQuery),
+    Azure(azure::AzureQuery),
+}
+
+impl CloudQuery {
+    pub fn provider(&self) -> &dyn CloudProvider {
+        match self {
+            Self::Aws(query) => query,
+            Self::Gcp(query) => query,
+            Self::Azure(query) => query,
+        }
+    }
+
+    pub async fn run(&self) -> Result<Vec<ParsedSshConnection>> {
+        let provider = self.provider();
+        let stdout = run_cli(provider.program(), &provider.list_args()).await?;
+        provider.parse(&stdout)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudSource {
+    pub id: String,
+    #[serde(flatten)]
+    pub query: CloudQuery,
+    pub refresh_minutes: u32,
+    #[serde(default)]
+    pub last_refreshed_at: Option<u64>,
+}
+
+/// Payload of `cloud:refreshed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudRefresh {
+    pub source_id: String,
+    pub connections: Vec<ParsedSshConnection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub(crate) fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// The local login name, which tools like `gcloud compute ssh` use remotely.
+pub(crate) fn local_username() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+/// `Key=Value` filters as trimmed pairs; entries without `=` are ignored.
+pub(crate) fn key_value_filters(filters: &[String]) -> Vec<(&str, &str)> {
+    filters
+        .iter()
+        .filter_map(|filter| filter.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// Runs a provider CLI and returns its stdout. The CLIs talk to their cloud
+/// APIs, so they are refused while the kill switch is engaged.
+pub(crate) async fn run_cli(program: &str, args: &[String]) -> Result<Vec<u8>> {
+    crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(CLI_TIMEOUT, command.output())
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "`{}` did not finish within {}s",
+                program,
+                CLI_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow!("`{}` is not installed or not on PATH", program)
+            }
+            _ => anyhow!("Failed to run `{}`: {}", program, e),
+        })?;
+    if !output.status.success() {
+        bail!(
+            "`{}` failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+pub fn sources_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(SOURCES_FILE)
+}
+
+pub fn load_sources(path: &Path) -> Result<Vec<CloudSource>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn save_sources(path: &Path, sources: &[CloudSource]) -> Result<()> {
+    let content = serde_json::to_vec_pretty(sources)?;
+    crate::atomic_io::durable_replace(path, &content)?;
+    Ok(())
+}
+
+/// Adds or replaces (same provider query) a refreshed source; returns its id.
+pub fn upsert_source(path: &Path, query: CloudQuery, refresh_minutes: u32) -> Result<String> {
+    let mut sources = load_sources(path)?;
+    let refresh_minutes = refresh_minutes.max(MIN_REFRESH_MINUTES);
+    let id = match sources.iter_mut().find(|source| source.query == query) {
+        Some(source) => {
+            source.refresh_minutes = refresh_minutes;
+            source.last_refreshed_at = Some(now_ms());
+            source.id.clone()
+        }
+        None => {
+            let id = format!("cloud_{}", uuid::Uuid::new_v4());
+            sources.push(CloudSource {
+                id: id.clone(),
+                query,
+                refresh_minutes,
+                last_refreshed_at: Some(now_ms()),
+            });
+            id
+        }
+    };
+    save_sources(path, &sources)?;
+    Ok(id)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn is_due(source: &CloudSource, now: u64) -> bool {
+    let interval = u64::from(source.refresh_minutes.max(MIN_REFRESH_MINUTES)) * 60_000;
+    source
+        .last_refreshed_at
+        .is_none_or(|last| now.saturating_sub(last) >= interval)
+}
+
+pub fn spawn_cloud_refresher(app: AppHandle, data_dir: PathBuf) {
+    tauri::async_runtime::spawn(async move {
+        let path = sources_path(&data_dir);
+        let mut ticker = tokio::time::interval(REFRESH_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Ok(sources) = load_sources(&path) else {
+                continue;
+            };
+            let now = now_ms();
+            let due: Vec<CloudSource> = sources.into_iter().filter(|s| is_due(s, now)).collect();
+            if due.is_empty() {
+                continue;
+            }
+            for source in &due {
+                let (connections, error) = match source.query.run().await {
+                    Ok(connections) => (connections, None),
+                    Err(e) => {
+                        crate::log_error!("[Cloud] Refreshing {} failed: {}", source.id, e);
+                        (Vec::new(), Some(e.to_string()))
+                    }
+                };
+                let _ = app.emit(
+                    "cloud:refreshed",
+                    CloudRefresh {
+                        source_id: source.id.clone(),
+                        connections,
+                        error,
+                    },
+                );
+            }
+            // Re-read so sources added or removed meanwhile are kept as they are.
+            if let Ok(mut sources) = load_sources(&path) {
+                for source in &mut sources {
+                    if due.iter().any(|d| d.id == source.id) {
+                        source.last_refreshed_at = Some(now);
+                    }
+                }
+                if let Err(e) = save_sources(&path, &sources) {
+                    crate::log_error!("[Cloud] Failed to save sources: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(refresh_minutes: u32, last_refreshed_at: Option<u64>) -> CloudSource {
+        CloudSource {
+            id: "s".to_string(),
+            query: CloudQuery::Aws(aws::AwsQuery::default()),
+            refresh_minutes,
+            last_refreshed_at,
+        }
+    }
+
+    #[test]
+    fn sources_come_due_after_their_interval() {
+        assert!(is_due(&source(10, None), 0));
+        assert!(!is_due(&source(10, Some(1_000)), 1_000 + 9 * 60_000));
+        assert!(is_due(&source(10, Some(1_000)), 1_000 + 10 * 60_000));
+        // Intervals below the minimum are raised to it.
+        assert!(!is_due(&source(1, Some(0)), 2 * 60_000));
+    }
+
+    #[test]
+    fn upserts_sources_by_query() {
+        let dir = std::env::temp_dir().join(format!("zync-cloud-{}", uuid::Uuid::new_v4()));
+        let path = sources_path(&dir);
+        let query = CloudQuery::Aws(aws::AwsQuery {
+            region: Some("eu-west-1".to_string()),
+            ..Default::default()
+        });
+        let first = upsert_source(&path, query.clone(), 15).unwrap();
+        let second = upsert_source(&path, query, 30).unwrap();
+        assert_eq!(first, second);
+
+        let sources = load_sources(&path).unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].refresh_minutes, 30);
+        let json = serde_json::to_value(&sources[0]).unwrap();
+        assert_eq!(json["provider"], "aws");
+        assert_eq!(json["region"], "eu-west-1");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn queries_select_their_provider() {
+        let query: CloudQuery =
+            serde_json::from_str(r#"{"provider": "azure", "resourceGroup": "web"}"#).unwrap();
+        assert!(matches!(&query, CloudQuery::Azure(q) if q.running_only));
+        assert!(query.provider().program().starts_with("az"));
+
+        let query: CloudQuery =
+            serde_json::from_str(r#"{"provider": "gcp", "project": "shop"}"#).unwrap();
+        assert!(query
+            .provider()
+            .list_args()
+            .contains(&"--project=shop".to_string()));
+    }
+}
@@ -0,0 +1,265 @@
+//! Executed-command history across terminals, searchable per connection.
+//!
+//! Commands are captured from the OSC 133 / 633 end-of-command marks that
+//! shell integration emits (see `crate::shell_integration`), together with the
+//! working directory and exit code. Repeats of a command on the same connection
+//! fold into one entry with a run count. The store lives in
+//! `<data>/command_history.json`; `PtyManager` records in memory and the flusher
+//! `AppState::new` starts writes changes out every few seconds.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept across all connections; the least recently run go first.
+const MAX_ENTRIES: usize = 10_000;
+/// Longer commands (pasted scripts, heredocs) are not kept.
+const MAX_COMMAND_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub command: String,
+    pub connection_id: String,
+    /// Times the command was run on this connection.
+    pub count: u32,
+    /// Unix milliseconds of the latest run.
+    pub last_run: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HistoryData {
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Default)]
+struct HistoryState {
+    path: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+    dirty: bool,
+}
+
+/// Shared handle; clones see the same history.
+#[derive(Clone, Default)]
+pub struct CommandHistory(Arc<Mutex<HistoryState>>);
+
+impl CommandHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads `path` and makes it the flush target. Earlier in-memory records are kept.
+    pub fn load(&self, path: &Path) -> Result<(), String> {
+        let loaded = if path.exists() {
+            let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+            serde_json::from_str::<HistoryData>(&content)
+                .map_err(|e| e.to_string())?
+                .entries
+        } else {
+            Vec::new()
+        };
+        let mut state = self.0.lock().map_err(|_| "History lock poisoned")?;
+        state.path = Some(path.to_path_buf());
+        let recorded = std::mem::replace(&mut state.entries, loaded);
+        for entry in recorded {
+            merge(&mut state.entries, entry);
+        }
+        Ok(())
+    }
+
+    /// Records one finished command. Blank commands, ones typed with a leading
+    /// space (`HISTCONTROL=ignorespace`) and likely secrets are skipped.
+    pub fn record(
+        &self,
+        connection_id: &str,
+        command: &str,
+        cwd: Option<String>,
+        exit_code: Option<i32>,
+    ) {
+        if command.starts_with(' ') {
+            return;
+        }
+        let command = command.trim();
+        if command.is_empty()
+            || command.len() > MAX_COMMAND_BYTES
+            || !crate::ghost::token::history_entry_safe_to_store(command)
+        {
+            return;
+        }
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        merge(
+            &mut state.entries,
+            HistoryEntry {
+                command: command.to_string(),
+                connection_id: connection_id.to_string(),
+                count: 1,
+                last_run: now_ms(),
+                last_exit_code: exit_code,
+                cwd,
+            },
+        );
+        state.dirty = true;
+    }
+
+    /// Most recent runs whose command contains every word of `query`
+    /// (case-insensitive), optionally limited to one connection.
+    pub fn search(
+        &self,
+        query: &str,
+        connection_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<HistoryEntry> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut matches = self.filtered(connection_id, |entry| {
+            let command = entry.command.to_lowercase();
+            words.iter().all(|word| command.contains(word.as_str()))
+        });
+        matches.sort_by(|a, b| b.last_run.cmp(&a.last_run));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Most frequently run commands, ties broken by recency.
+    pub fn top(&self, connection_id: Option<&str>, limit: usize) -> Vec<HistoryEntry> {
+        let mut entries = self.filtered(connection_id, |_| true);
+        entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| b.last_run.cmp(&a.last_run))
+        });
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Forgets one connection's history, or everything when `connection_id` is `None`.
+    pub fn clear(&self, connection_id: Option<&str>) {
+        if let Ok(mut state) = self.0.lock() {
+            match connection_id {
+                Some(id) => state.entries.retain(|entry| entry.connection_id != id),
+                None => state.entries.clear(),
+            }
+            state.dirty = true;
+        }
+    }
+
+    /// Writes pending changes to the loaded path.
+    pub fn flush(&self) -> Result<(), String> {
+        let (path, entries) = {
+            let mut state = self.0.lock().map_err(|_| "History lock poisoned")?;
+            let Some(path) = state.path.clone().filter(|_| state.dirty) else {
+                return Ok(());
+            };
+            state.dirty = false;
+            (path, state.entries.clone())
+        };
+        let json = serde_json::to_string(&HistoryData { entries }).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&path, json.as_bytes()).map_err(|e| {
+            if let Ok(mut state) = self.0.lock() {
+                state.dirty = true;
+            }
+            format!("Failed to write command history: {e}")
+        })
+    }
+
+    fn filtered(
+        &self,
+        connection_id: Option<&str>,
+        keep: impl Fn(&HistoryEntry) -> bool,
+    ) -> Vec<HistoryEntry> {
+        let Ok(state) = self.0.lock() else {
+            return Vec::new();
+        };
+        state
+            .entries
+            .iter()
+            .filter(|entry| connection_id.is_none_or(|id| entry.connection_id == id))
+            .filter(|entry| keep(entry))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Folds `entry` into an existing one for the same connection and command.
+fn merge(entries: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    if let Some(existing) = entries
+        .iter_mut()
+        .find(|e| e.connection_id == entry.connection_id && e.command == entry.command)
+    {
+        existing.count = existing.count.saturating_add(entry.count);
+        if entry.last_run >= existing.last_run {
+            existing.last_run = entry.last_run;
+            existing.last_exit_code = entry.last_exit_code;
+            existing.cwd = entry.cwd.or(existing.cwd.take());
+        }
+        return;
+    }
+    entries.push(entry);
+    if entries.len() > MAX_ENTRIES {
+        if let Some(oldest) = entries
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, e)| e.last_run)
+            .map(|(index, _)| index)
+        {
+            entries.swap_remove(oldest);
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeats_fold_into_one_entry_per_connection() {
+        let history = CommandHistory::new();
+        history.record("web", "git status", Some("/srv".to_string()), Some(0));
+        history.record("web", "git status", None, Some(1));
+        history.record("db", "git status", None, Some(0));
+        history.record("web", " secret-command", None, Some(0));
+        history.record("web", "   ", None, None);
+
+        let top = history.top(Some("web"), 10);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].count, 2);
+        assert_eq!(top[0].last_exit_code, Some(1));
+        assert_eq!(top[0].cwd.as_deref(), Some("/srv"));
+        assert_eq!(history.top(None, 10).len(), 2);
+    }
+
+    #[test]
+    fn search_matches_all_words_newest_first() {
+        let history = CommandHistory::new();
+        history.record("web", "docker compose up -d", None, Some(0));
+        history.record("web", "docker ps", None, Some(0));
+        history.record("web", "kubectl get pods", None, Some(0));
+        if let Ok(mut state) = history.0.lock() {
+            state.entries[1].last_run += 10;
+        }
+
+        let found = history.search("DOCKER", Some("web"), 10);
+        assert_eq!(
+            found.iter().map(|e| e.command.as_str()).collect::<Vec<_>>(),
+            vec!["docker ps", "docker compose up -d"]
+        );
+        assert_eq!(history.search("compose up", None, 10).len(), 1);
+        assert!(history.search("docker", Some("db"), 10).is_empty());
+
+        history.clear(Some("web"));
+        assert!(history.top(None, 10).is_empty());
+    }
+}
@@ -1,6 +1,8 @@
-use crate::fs::{FileEntry, FileSystem};
+use crate::fs::{FileEntry, FileSystem, SkipReason, Walk, WalkFlags};
 use crate::pty::PtyManager;
+use crate::remote_path::RemotePathStyle;
 use crate::ssh::{Client, SshManager};
+use crate::state_sync::{self, ChangeKind, StateKind};
 use crate::types::*;
 use anyhow::Result;
 use russh::client::{Handle, Msg};
@@ -36,7 +38,7 @@ fn warn_data_dir_fallback(custom_dir: &Path, error: &std::io::Error, default_dir
         return;
     }
     *warned = Some(key);
-    eprintln!(
+    crate::log_error!(
         "[DataDir] Could not create custom dataPath {:?} ({}). Using default {:?}.",
         custom_dir,
         error,
@@ -112,10 +114,10 @@ fn merge_json_values(base: Value, overlay: Value) -> Value {
 /// Resolve the native, user-scoped settings directory (OS config convention).
 /// Example: `%APPDATA%/Zync/User` on Windows, `~/.config/Zync/User` on Linux.
 fn get_native_settings_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
-    let config_root = app.path().config_dir().map_err(|e| e.to_string())?;
+    let config_root = app.path().config_dir().map_err(crate::redact::error)?;
     let dir = config_root.join("Zync").join("User");
     if !dir.exists() {
-        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&dir).map_err(crate::redact::error)?;
     }
     Ok(dir)
 }
@@ -144,10 +146,21 @@ fn get_legacy_settings_candidates(app: &AppHandle) -> Vec<std::path::PathBuf> {
 
 /// Write file content atomically via temporary file + rename.
 /// Prevents partial/corrupt settings writes on crashes/interruption.
+/// Reads a store such as `connections.json`, which may be sealed at rest
+/// (see [`crate::vault::at_rest`]).
+fn read_store_file(path: &std::path::Path) -> Result<String, String> {
+    crate::vault::at_rest::read_to_string(path).map_err(crate::redact::error)
+}
+
+/// Writes a store, sealing it when at-rest encryption is on.
+fn write_store_file(path: &std::path::Path, content: &str) -> Result<(), String> {
+    crate::vault::at_rest::write(path, content.as_bytes()).map_err(crate::redact::error)
+}
+
 fn write_atomic_file(path: &std::path::Path, content: &str) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         if !parent.exists() {
-            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(parent).map_err(crate::redact::error)?;
         }
     }
 
@@ -158,12 +171,12 @@ fn write_atomic_file(path: &std::path::Path, content: &str) -> Result<(), String
         .truncate(true)
         .write(true)
         .open(&tmp_path)
-        .map_err(|e| e.to_string())?;
+        .map_err(crate::redact::error)?;
     use std::io::Write;
     tmp_file
         .write_all(content.as_bytes())
-        .map_err(|e| e.to_string())?;
-    tmp_file.sync_all().map_err(|e| e.to_string())?;
+        .map_err(crate::redact::error)?;
+    tmp_file.sync_all().map_err(crate::redact::error)?;
     let replace_result = if path.exists() {
         #[cfg(target_os = "windows")]
         {
@@ -176,7 +189,7 @@ fn write_atomic_file(path: &std::path::Path, content: &str) -> Result<(), String
                     }
                     Err(rename_error) => {
                         if let Err(restore_error) = std::fs::rename(&backup_path, path) {
-                            eprintln!(
+                            crate::log_error!(
                                 "[settings] Failed to restore backup after rename error. backup_path={}, tmp_path={}, target_path={}, rename_error={}, restore_error={}",
                                 backup_path.display(),
                                 tmp_path.display(),
@@ -188,18 +201,18 @@ fn write_atomic_file(path: &std::path::Path, content: &str) -> Result<(), String
                         Err(rename_error.to_string())
                     }
                 },
-                Err(error) => Err(format!(
+                Err(error) => Err(crate::redact::error(format!(
                     "Failed to move existing file to backup before atomic replace: {}",
                     error
-                )),
+                ))),
             }
         }
         #[cfg(not(target_os = "windows"))]
         {
-            std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+            std::fs::rename(&tmp_path, path).map_err(crate::redact::error)
         }
     } else {
-        std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+        std::fs::rename(&tmp_path, path).map_err(crate::redact::error)
     };
 
     if let Err(error) = replace_result {
@@ -283,6 +296,14 @@ fn validate_settings_schema(settings: &Value) -> Result<(), String> {
             return Err("Invalid \"editor\": expected object.".to_string());
         }
     }
+    if let Some(proxy) = obj.get("proxy") {
+        crate::http_client::validate_settings(proxy)?;
+    }
+    if let Some(kill_switch) = obj.get(crate::kill_switch::SETTINGS_KEY) {
+        if !kill_switch.is_boolean() {
+            return Err("Invalid \"networkKillSwitch\": expected boolean.".to_string());
+        }
+    }
     Ok(())
 }
 
@@ -292,9 +313,9 @@ fn read_settings_from_path(path: &std::path::Path) -> Result<Value, String> {
         return Ok(Value::Object(serde_json::Map::new()));
     }
     let data = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read settings.json: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to read settings.json: {}", e)))?;
     let parsed = serde_json::from_str::<Value>(&data)
-        .map_err(|e| format!("Invalid JSON in settings.json: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Invalid JSON in settings.json: {}", e)))?;
     let object_settings = ensure_object_settings(parsed)?;
     validate_settings_schema(&object_settings)?;
     Ok(object_settings)
@@ -322,7 +343,7 @@ pub(crate) fn read_effective_settings(app: &AppHandle) -> Result<Value, String>
         if let Ok(_mutation_guard) = SETTINGS_MUTATION_LOCK.try_lock() {
             if !settings_path.exists() {
                 let json =
-                    serde_json::to_string_pretty(&legacy_settings).map_err(|e| e.to_string())?;
+                    serde_json::to_string_pretty(&legacy_settings).map_err(crate::redact::error)?;
                 write_atomic_file(&settings_path, &json)?;
             }
         }
@@ -340,7 +361,7 @@ fn persist_settings_json(app: &AppHandle, settings: &Value) -> Result<(), String
     let settings_path = get_native_settings_path(app)?;
     let backup_path = get_last_known_good_settings_path(app)?;
     if settings_path.exists() {
-        let existing = std::fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
+        let existing = std::fs::read_to_string(&settings_path).map_err(crate::redact::error)?;
         match serde_json::from_str::<Value>(&existing) {
             Ok(parsed) => match ensure_object_settings(parsed) {
                 Ok(valid_existing) => {
@@ -348,7 +369,7 @@ fn persist_settings_json(app: &AppHandle, settings: &Value) -> Result<(), String
                     write_atomic_file(&backup_path, &existing)?;
                 }
                 Err(error) => {
-                    eprintln!(
+                    crate::log_error!(
                         "[settings] Skipping last-known-good backup due to invalid existing settings at {}: {}",
                         settings_path.display(),
                         error
@@ -356,7 +377,7 @@ fn persist_settings_json(app: &AppHandle, settings: &Value) -> Result<(), String
                 }
             },
             Err(error) => {
-                eprintln!(
+                crate::log_error!(
                     "[settings] Skipping last-known-good backup due to invalid JSON at {}: {}",
                     settings_path.display(),
                     error
@@ -365,8 +386,10 @@ fn persist_settings_json(app: &AppHandle, settings: &Value) -> Result<(), String
         }
     }
 
-    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
-    write_atomic_file(&settings_path, &json)
+    let json = serde_json::to_string_pretty(settings).map_err(crate::redact::error)?;
+    write_atomic_file(&settings_path, &json)?;
+    crate::http_client::configure(settings);
+    Ok(())
 }
 
 pub fn get_data_dir(app: &AppHandle) -> std::path::PathBuf {
@@ -388,7 +411,7 @@ pub fn get_data_dir(app: &AppHandle) -> std::path::PathBuf {
         if !data_path.is_empty() {
             let custom_dir = std::path::PathBuf::from(data_path);
             if custom_dir.is_file() {
-                eprintln!(
+                crate::log_error!(
                     "[DataDir] Custom dataPath is a file, not a directory: {:?}. Using default.",
                     custom_dir
                 );
@@ -410,9 +433,10 @@ pub fn get_data_dir(app: &AppHandle) -> std::path::PathBuf {
     };
 
     if let Err(e) = std::fs::create_dir_all(&resolved) {
-        eprintln!(
+        crate::log_error!(
             "[DataDir] Warning: could not create data directory {:?}: {}",
-            resolved, e
+            resolved,
+            e
         );
     }
 
@@ -464,6 +488,8 @@ pub struct AppState {
     pub ssh_manager: Arc<SshManager>,
     pub tunnel_manager: Arc<TunnelManager>,
     pub snippets_manager: Arc<crate::snippets::SnippetsManager>,
+    pub actions_manager: Arc<crate::actions::ActionsManager>,
+    pub transfer_processors: Arc<crate::transfer_pipeline::ProcessorRegistry>,
     pub transfers: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
     // Agent v2: active run cancellation tokens
     pub agent_runs: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
@@ -473,6 +499,10 @@ pub struct AppState {
     pub command_whitelist: Arc<Mutex<HashMap<String, std::collections::HashSet<String>>>>,
     // Ghost suggestions: frecency-scored command history, persisted to disk.
     pub ghost_manager: Arc<crate::ghost::GhostManager>,
+    pub probes_manager: Arc<crate::probes::ProbesManager>,
+    pub inbox_manager: Arc<crate::inbox::InboxManager>,
+    pub snippet_scheduler: Arc<crate::snippet_schedule::SnippetScheduler>,
+    pub health_monitor: Arc<crate::connection_health::HealthMonitor>,
     pub shell_icon_cache: crate::shell_icons::IconCache,
     pub shell_icon_cache_path: std::path::PathBuf,
 }
@@ -481,20 +511,42 @@ impl AppState {
     pub fn new(data_dir: std::path::PathBuf, app_handle: tauri::AppHandle) -> Self {
         let (failure_tx, failure_rx) = session_failure_channel();
         spawn_session_failure_watcher(app_handle.clone(), failure_rx);
+        crate::tunnels::supervisor::spawn_tunnel_supervisor(app_handle.clone());
+        crate::probes::spawn_probe_scheduler(app_handle.clone());
+        crate::inbox::spawn_inbox_scheduler(app_handle.clone());
+        crate::snippet_schedule::spawn_snippet_scheduler(app_handle.clone());
+        crate::connection_health::spawn_health_monitor(app_handle.clone());
+        crate::cloud_import::spawn_cloud_refresher(app_handle.clone(), data_dir.clone());
+        let settings = read_effective_settings(&app_handle).unwrap_or(Value::Null);
+        crate::http_client::configure(&settings);
+        crate::kill_switch::set_engaged(crate::kill_switch::engaged_in_settings(&settings));
+        let pty_manager = Arc::new(PtyManager::new());
+        spawn_trigger_dispatcher(app_handle.clone(), &pty_manager, &data_dir);
+        spawn_history_flusher(&pty_manager, &data_dir);
 
         Self {
-            app_handle,
+            app_handle: app_handle.clone(),
             connections: Arc::new(Mutex::new(HashMap::new())),
-            pty_manager: Arc::new(PtyManager::new()),
+            pty_manager,
             file_system: Arc::new(FileSystem::new()),
-            ssh_manager: Arc::new(SshManager::new()),
+            ssh_manager: Arc::new(SshManager::new(app_handle.clone())),
             tunnel_manager: Arc::new(TunnelManager::new(failure_tx)),
             snippets_manager: Arc::new(crate::snippets::SnippetsManager::new(data_dir.clone())),
+            actions_manager: Arc::new(crate::actions::ActionsManager::new(data_dir.clone())),
+            transfer_processors: Arc::new(crate::transfer_pipeline::ProcessorRegistry::with_builtins()),
             transfers: Arc::new(Mutex::new(HashMap::new())),
             agent_runs: Arc::new(Mutex::new(HashMap::new())),
             agent_checkpoints: Arc::new(Mutex::new(HashMap::new())),
             command_whitelist: Arc::new(Mutex::new(HashMap::new())),
             ghost_manager: Arc::new(crate::ghost::GhostManager::new(&data_dir)),
+            probes_manager: Arc::new(crate::probes::ProbesManager::new(data_dir.clone())),
+            inbox_manager: Arc::new(crate::inbox::InboxManager::new(data_dir.clone())),
+            snippet_scheduler: Arc::new(crate::snippet_schedule::SnippetScheduler::new(
+                data_dir.clone(),
+            )),
+            health_monitor: Arc::new(crate::connection_health::HealthMonitor::new(
+                data_dir.clone(),
+            )),
             shell_icon_cache: crate::shell_icons::new_cache(),
             shell_icon_cache_path: data_dir.join("shell-icon-cache.json"),
         }
@@ -508,6 +560,10 @@ pub struct ConnectionHandle {
     pub sftp_session: Option<Arc<russh_sftp::client::SftpSession>>,
     pub detected_os: Option<String>,
     pub detected_shell: Option<String>,
+    /// The server refused the `sftp` subsystem; file operations fall back to SCP.
+    pub sftp_unavailable: bool,
+    /// Label of the fallback endpoint this session went through; `None` for the primary.
+    pub endpoint: Option<String>,
     pub uses_vault_auth: bool,
     /// Bumped on each new connect/reconnect; stale in-flight reconnects must match before replacing.
     pub reconnect_generation: u64,
@@ -517,42 +573,66 @@ pub struct ConnectionHandle {
 
 /// Internal helper: establishes a full SSH connection (session + SFTP + OS detection)
 /// and returns a fresh `ConnectionHandle`. Used for initial `ssh_connect` and reactive reconnection.
-async fn reconnect_connection(
+pub(crate) async fn reconnect_connection<R: tauri::Runtime>(
     config: &ConnectionConfig,
-    ssh_manager: &crate::ssh::SshManager,
+    ssh_manager: &crate::ssh::SshManager<R>,
     tunnel_manager: &crate::tunnels::TunnelManager,
 ) -> Result<ConnectionHandle, String> {
-    let session = ssh_manager
-        .connect(config.clone(), Arc::new(tunnel_manager.clone()))
+    let channel_budget = crate::channel_budget::ChannelBudget::new(config.channel_budget);
+    let (session, endpoint) = ssh_manager
+        .connect_with_fallbacks(
+            config,
+            Arc::new(tunnel_manager.clone()),
+            channel_budget.clone(),
+        )
         .await
-        .map_err(|e| format!("Failed to connect: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to connect: {}", e)))?;
+
+    // Gate the connection on its login steps before anything else uses it.
+    if let Some(steps) = config.login_steps.as_deref().filter(|steps| !steps.is_empty()) {
+        if let Err(failure) = crate::login_steps::run_login_steps(&session, steps).await {
+            crate::log_error!(
+                "[SSH] Login step '{}' failed ({}), aborting connection",
+                failure.name,
+                failure.reason
+            );
+            let _ = session
+                .disconnect(russh::Disconnect::ByApplication, "login step failed", "en")
+                .await;
+            return Err(failure.to_error_string());
+        }
+    }
 
     // Initialize SFTP session
-    let sftp_session = match session.channel_open_session().await {
+    let mut sftp_unavailable = false;
+    let sftp_session = match channel_budget.open_session(&config.id, &session).await {
         Ok(channel) => {
             if let Err(e) = channel.request_subsystem(true, "sftp").await {
-                eprintln!("[SSH] Failed to request SFTP subsystem: {}", e);
+                crate::log_error!("[SSH] Failed to request SFTP subsystem: {}", e);
+                sftp_unavailable = true;
                 None
             } else {
                 let stream = channel.into_stream();
                 match russh_sftp::client::SftpSession::new(stream).await {
                     Ok(sftp) => Some(Arc::new(sftp)),
                     Err(e) => {
-                        eprintln!("[SSH] Failed to initialize SFTP: {}", e);
+                        // A refused subsystem usually surfaces here as a closed channel.
+                        crate::log_error!("[SSH] Failed to initialize SFTP: {}", e);
+                        sftp_unavailable = true;
                         None
                     }
                 }
             }
         }
         Err(e) => {
-            eprintln!("[SSH] Failed to open channel for SFTP: {}", e);
+            crate::log_error!("[SSH] Failed to open channel for SFTP: {}", e);
             None
         }
     };
 
     // Detect OS (best-effort — reuse cached value if already known via caller)
     let mut detected_os = None;
-    if let Ok(mut channel) = session.channel_open_session().await {
+    if let Ok(mut channel) = channel_budget.open_session(&config.id, &session).await {
         if channel.exec(true, "cat /etc/os-release").await.is_ok() {
             let mut output = String::new();
             while let Some(msg) = channel.wait().await {
@@ -574,7 +654,7 @@ async fn reconnect_connection(
         }
     }
     if detected_os.is_none() {
-        if let Ok(mut channel) = session.channel_open_session().await {
+        if let Ok(mut channel) = channel_budget.open_session(&config.id, &session).await {
             if channel.exec(true, "uname -s").await.is_ok() {
                 let mut output = String::new();
                 while let Some(msg) = channel.wait().await {
@@ -596,7 +676,7 @@ async fn reconnect_connection(
         }
     }
     if detected_os.is_none() {
-        if let Ok(mut channel) = session.channel_open_session().await {
+        if let Ok(mut channel) = channel_budget.open_session(&config.id, &session).await {
             if channel.exec(true, "cmd /c ver").await.is_ok() {
                 let mut output = String::new();
                 while let Some(msg) = channel.wait().await {
@@ -623,7 +703,7 @@ async fn reconnect_connection(
         .unwrap_or(false)
     {
         detected_shell = Some("powershell".to_string());
-    } else if let Ok(mut channel) = session.channel_open_session().await {
+    } else if let Ok(mut channel) = channel_budget.open_session(&config.id, &session).await {
         if channel.exec(true, "basename \"${SHELL:-}\"").await.is_ok() {
             let mut output = String::new();
             while let Some(msg) = channel.wait().await {
@@ -642,12 +722,15 @@ async fn reconnect_connection(
         }
     }
 
+    crate::channel_budget::register(&config.id, channel_budget);
     Ok(ConnectionHandle {
         config: config.clone(),
         session: Some(Arc::new(Mutex::new(session))),
         sftp_session,
         detected_os,
         detected_shell,
+        sftp_unavailable,
+        endpoint,
         uses_vault_auth: config_uses_vault_auth(config),
         reconnect_generation: 0,
         reconnect_lock: Arc::new(tokio::sync::Mutex::new(())),
@@ -664,6 +747,12 @@ fn config_uses_vault_auth(config: &ConnectionConfig) -> bool {
             .as_ref()
             .map(|jump| config_uses_vault_auth(jump.as_ref()))
             .unwrap_or(false)
+        || config
+            .fallbacks
+            .iter()
+            .flatten()
+            .filter_map(|fallback| fallback.jump_host.as_deref())
+            .any(config_uses_vault_auth)
 }
 
 #[derive(Debug, Clone)]
@@ -730,15 +819,20 @@ fn resolve_vault_refs<'a>(
                     }
                 }
                 k => {
-                    return Err(format!(
+                    return Err(crate::redact::error(format!(
                         "Vault item kind '{k}' is not supported for SSH auth"
-                    ))
+                    )))
                 }
             };
         }
         if let Some(jump) = config.jump_host.as_mut() {
             relinked.extend(resolve_vault_refs(jump.as_mut(), vault).await?);
         }
+        for fallback in config.fallbacks.iter_mut().flatten() {
+            if let Some(jump) = fallback.jump_host.as_mut() {
+                relinked.extend(resolve_vault_refs(jump.as_mut(), vault).await?);
+            }
+        }
         Ok(relinked)
     })
 }
@@ -759,9 +853,9 @@ fn persist_relinked_vault_refs(
 
     let _connections_guard = CONNECTIONS_MUTATION_LOCK
         .lock()
-        .map_err(|e| e.to_string())?;
-    let data = std::fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
-    let mut saved_data: SavedData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+        .map_err(crate::redact::error)?;
+    let data = read_store_file(&file_path)?;
+    let mut saved_data: SavedData = serde_json::from_str(&data).map_err(crate::redact::error)?;
     let mut changed = false;
 
     for update in updates {
@@ -791,8 +885,8 @@ fn persist_relinked_vault_refs(
     }
 
     if changed {
-        let json = serde_json::to_string_pretty(&saved_data).map_err(|e| e.to_string())?;
-        write_atomic_file(&file_path, &json)?;
+        let json = serde_json::to_string_pretty(&saved_data).map_err(crate::redact::error)?;
+        write_store_file(&file_path, &json)?;
     }
 
     Ok(())
@@ -815,17 +909,22 @@ pub async fn ssh_connect(
                 .await;
         match persist_result {
             Ok(Ok(())) => {}
-            Ok(Err(error)) => return Err(format!("Failed to persist relinked vault refs: {error}")),
+            Ok(Err(error)) => {
+                return Err(crate::redact::error(format!(
+                    "Failed to persist relinked vault refs: {error}"
+                )))
+            }
             Err(join_error) => {
-                return Err(format!(
+                return Err(crate::redact::error(format!(
                     "Failed to persist relinked vault refs: task join error: {join_error}"
-                ))
+                )))
             }
         }
     }
     match reconnect_connection(&config, &state.ssh_manager, &state.tunnel_manager).await {
         Ok(mut handle) => {
             let detected_os = handle.detected_os.clone();
+            let endpoint = handle.endpoint.clone();
             // Do not keep decrypted vault secrets in the long-lived handle config.
             // The handle keeps the original VaultRef config so future reconnects
             // require the vault to be explicitly unlocked again.
@@ -837,16 +936,48 @@ pub async fn ssh_connect(
                 .map(|existing| existing.reconnect_generation.wrapping_add(1))
                 .unwrap_or(0);
             connections.insert(original_config.id.clone(), handle);
+            drop(connections);
+            state_sync::notify(
+                &app,
+                StateKind::Connection,
+                &original_config.id,
+                ChangeKind::Added,
+            );
+            crate::plugin_hooks::emit(
+                &app,
+                crate::plugin_hooks::Hook::ConnectionOpened,
+                crate::plugin_hooks::ConnectionOpened {
+                    connection_id: &original_config.id,
+                    name: &original_config.name,
+                    host: &original_config.host,
+                    port: original_config.port,
+                    username: &original_config.username,
+                },
+            );
+
+            let event = crate::audit::AuditEvent::new(
+                &original_config.id,
+                crate::audit::AuditKind::Connect,
+                format!(
+                    "{}@{}:{}",
+                    original_config.username, original_config.host, original_config.port
+                ),
+                serde_json::json!({ "name": original_config.name, "endpoint": endpoint }),
+            );
+            audit_record(&app, event).await;
+
+            crate::tunnels::start_auto_tunnels(&app, &state, &original_config.id).await;
 
             Ok(ConnectionResponse {
                 success: true,
                 message: "Connected".to_string(),
                 term_id: Some(original_config.id.clone()),
                 detected_os,
+                endpoint,
             })
         }
         Err(e) => {
-            eprintln!("[SSH] Connection failed: {}", e);
+            crate::log_error!("[SSH] Connection failed: {}", e);
             Err(e)
         }
     }
@@ -861,7 +992,11 @@ pub async fn ssh_test_connection(
     let _relinked = resolve_vault_refs(&mut config, &vault).await?;
     match state
         .ssh_manager
-        .connect(config.clone(), Arc::new((*state.tunnel_manager).clone()))
+        .connect(
+            config.clone(),
+            Arc::new((*state.tunnel_manager).clone()),
+            crate::channel_budget::ChannelBudget::new(config.channel_budget),
+        )
         .await
     {
         Ok(session) => {
@@ -887,11 +1022,14 @@ pub async fn ssh_test_connection(
                         Ok("Connected but failed to exec.".to_string())
                     }
                 }
-                Err(e) => Err(format!("Connected, but failed to open session: {}", e)),
+                Err(e) => Err(crate::redact::error(format!(
+                    "Connected, but failed to open session: {}",
+                    e
+                ))),
             };
             result
         }
-        Err(e) => Err(format!("Connection Failed: {}", e)),
+        Err(e) => Err(crate::redact::error(format!("Connection Failed: {}", e))),
     }
 }
 
@@ -929,15 +1067,15 @@ pub async fn get_system_info(app: AppHandle) -> Result<SystemInfo, String> {
 
 #[tauri::command]
 pub async fn save_secret(app: tauri::AppHandle, key: String, value: String) -> Result<(), String> {
-    let store = app.store("secrets.json").map_err(|e| e.to_string())?;
+    let store = app.store("secrets.json").map_err(crate::redact::error)?;
     store.set(key, serde_json::Value::String(value));
-    store.save().map_err(|e| e.to_string())?;
+    store.save().map_err(crate::redact::error)?;
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_secret(app: tauri::AppHandle, key: String) -> Result<Option<String>, String> {
-    let store = app.store("secrets.json").map_err(|e| e.to_string())?;
+    let store = app.store("secrets.json").map_err(crate::redact::error)?;
     Ok(store
         .get(key)
         .and_then(|v| v.as_str().map(|s| s.to_string())))
@@ -945,9 +1083,9 @@ pub async fn get_secret(app: tauri::AppHandle, key: String) -> Result<Option<Str
 
 #[tauri::command]
 pub async fn delete_secret(app: tauri::AppHandle, key: String) -> Result<(), String> {
-    let store = app.store("secrets.json").map_err(|e| e.to_string())?;
+    let store = app.store("secrets.json").map_err(crate::redact::error)?;
     store.delete(key);
-    store.save().map_err(|e| e.to_string())?;
+    store.save().map_err(crate::redact::error)?;
     Ok(())
 }
 
@@ -957,7 +1095,7 @@ pub async fn ssh_extract_pem(app_handle: tauri::AppHandle, path: String) -> Resu
     let keys_dir = data_dir.join("keys");
 
     if !keys_dir.exists() {
-        std::fs::create_dir_all(&keys_dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&keys_dir).map_err(crate::redact::error)?;
     }
 
     let src_path = std::path::Path::new(&path);
@@ -979,22 +1117,198 @@ pub async fn ssh_extract_pem(app_handle: tauri::AppHandle, path: String) -> Resu
         return Ok(path);
     }
 
-    std::fs::copy(src_path, &dest_path).map_err(|e| e.to_string())?;
+    crate::vault::at_rest::copy_in(src_path, &dest_path).map_err(crate::redact::error)?;
 
     // On Unix, set permissions to 600 for SSH keys
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
         let mut perms = std::fs::metadata(&dest_path)
-            .map_err(|e| e.to_string())?
+            .map_err(crate::redact::error)?
             .permissions();
         perms.set_mode(0o600);
-        std::fs::set_permissions(&dest_path, perms).map_err(|e| e.to_string())?;
+        std::fs::set_permissions(&dest_path, perms).map_err(crate::redact::error)?;
     }
 
     Ok(dest_path.to_string_lossy().to_string())
 }
 
+/// Replaces a connection's private key: generate, deploy, prove the new key logs in,
+/// optionally revoke the old one, then point the saved connection at the new file.
+/// Nothing is saved unless the new key has authenticated on its own.
+#[tauri::command]
+pub async fn ssh_rotate_key(
+    app: AppHandle,
+    connection_id: String,
+    remove_old_key: Option<bool>,
+    key_type: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::key_rotation::KeyRotationResult, String> {
+    use crate::key_rotation::{self as rotation, KeyType};
+
+    let key_type = match key_type {
+        Some(value) => KeyType::parse(&value).map_err(crate::redact::error)?,
+        None => KeyType::Ed25519,
+    };
+    let config = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&connection_id)
+            .map(|c| c.config.clone())
+            .ok_or("Connection not found")?
+    };
+    let (old_key_path, passphrase) = match &config.auth_method {
+        AuthMethod::PrivateKey {
+            key_path,
+            passphrase,
+        } => (rotation::expand_home(key_path), passphrase.clone()),
+        _ => {
+            return Err(
+                "Key rotation needs a connection that authenticates with a private key file"
+                    .to_string(),
+            )
+        }
+    };
+    if remote_path_style(&state, &connection_id).await == RemotePathStyle::Windows {
+        return Err("Key rotation is not supported for Windows servers yet".to_string());
+    }
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .to_string();
+    let new_key_path = rotation::rotated_key_path(&old_key_path, &config.name, &stamp);
+    let comment = format!("{}@zync-{}", config.username, stamp);
+    let public_key = rotation::generate(&new_key_path, key_type, passphrase.as_deref(), &comment)
+        .await
+        .map_err(crate::redact::error)?;
+    if let Err(e) = crate::vault::at_rest::seal_new_key(&get_data_dir(&app), &new_key_path) {
+        rotation::discard(&new_key_path);
+        return Err(crate::redact::error(e));
+    }
+
+    match exec_remote_capture(
+        &connection_id,
+        &rotation::install_command(&public_key),
+        &state,
+    )
+    .await
+    {
+        Ok((0, _, _)) => {}
+        Ok((code, _, stderr)) => {
+            rotation::discard(&new_key_path);
+            return Err(crate::redact::error(format!(
+                "Failed to install the new key (Exit {}): {}",
+                code,
+                String::from_utf8_lossy(&stderr).trim()
+            )));
+        }
+        Err(e) => {
+            rotation::discard(&new_key_path);
+            return Err(e);
+        }
+    }
+
+    let new_key_str = new_key_path.to_string_lossy().to_string();
+    let mut new_config = config.clone();
+    new_config.auth_method = AuthMethod::PrivateKey {
+        key_path: new_key_str.clone(),
+        passphrase: passphrase.clone(),
+    };
+    match state
+        .ssh_manager
+        .connect(
+            new_config.clone(),
+            Arc::new((*state.tunnel_manager).clone()),
+            crate::channel_budget::ChannelBudget::new(new_config.channel_budget),
+        )
+        .await
+    {
+        Ok(session) => {
+            let _ = session
+                .disconnect(russh::Disconnect::ByApplication, "key rotation check", "en")
+                .await;
+        }
+        Err(e) => {
+            if let Some(blob) = rotation::key_blob(&public_key) {
+                let _ =
+                    exec_remote_capture(&connection_id, &rotation::remove_command(blob), &state)
+                        .await;
+            }
+            rotation::discard(&new_key_path);
+            return Err(crate::redact::error(format!(
+                "The new key was installed but could not log in: {}",
+                e
+            )));
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let mut old_key_removed = false;
+    if remove_old_key.unwrap_or(false) {
+        match rotation::public_key_of(&old_key_path, passphrase.as_deref()).await {
+            Ok(old_public) => match rotation::key_blob(&old_public) {
+                Some(blob) if Some(blob) != rotation::key_blob(&public_key) => {
+                    match exec_remote_capture(
+                        &connection_id,
+                        &rotation::remove_command(blob),
+                        &state,
+                    )
+                    .await
+                    {
+                        Ok((0, _, _)) => old_key_removed = true,
+                        Ok((code, _, stderr)) => warnings.push(format!(
+                            "Old key was not removed (Exit {}): {}",
+                            code,
+                            String::from_utf8_lossy(&stderr).trim()
+                        )),
+                        Err(e) => warnings.push(format!("Old key was not removed: {}", e)),
+                    }
+                }
+                _ => warnings.push("Old public key could not be identified".to_string()),
+            },
+            Err(e) => warnings.push(e.to_string()),
+        }
+    }
+
+    let file_path = get_data_dir(&app).join("connections.json");
+    if file_path.exists() {
+        let _connections_guard = CONNECTIONS_MUTATION_LOCK
+            .lock()
+            .map_err(crate::redact::error)?;
+        let data = read_store_file(&file_path)?;
+        let mut saved_data: SavedData =
+            serde_json::from_str(&data).map_err(crate::redact::error)?;
+        if let Some(saved) = saved_data
+            .connections
+            .iter_mut()
+            .find(|saved| saved.id == connection_id)
+        {
+            saved.private_key_path = Some(new_key_str.clone());
+            let json = serde_json::to_string_pretty(&saved_data).map_err(crate::redact::error)?;
+            write_store_file(&file_path, &json)?;
+        } else {
+            warnings
+                .push("Connection is not saved; only this session uses the new key".to_string());
+        }
+    }
+    {
+        let mut connections = state.connections.lock().await;
+        if let Some(handle) = connections.get_mut(&connection_id) {
+            handle.config.auth_method = new_config.auth_method;
+        }
+    }
+
+    crate::log_info!("[SSH] Rotated key for {} to {}", connection_id, new_key_str);
+    Ok(crate::key_rotation::KeyRotationResult {
+        new_key_path: new_key_str,
+        public_key,
+        old_key_removed,
+        warnings,
+    })
+}
+
 #[tauri::command]
 pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize, String> {
     let data_dir = get_data_dir(&app_handle);
@@ -1006,16 +1320,16 @@ pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize,
 
     let _connections_guard = CONNECTIONS_MUTATION_LOCK
         .lock()
-        .map_err(|e| e.to_string())?;
-    let data = std::fs::read_to_string(&connections_path).map_err(|e| e.to_string())?;
+        .map_err(crate::redact::error)?;
+    let data = read_store_file(&connections_path)?;
     let mut saved_data: crate::types::SavedData =
-        serde_json::from_str(&data).map_err(|e| e.to_string())?;
+        serde_json::from_str(&data).map_err(crate::redact::error)?;
     let mut migrated_count = 0;
     let mut changed = false;
 
     let keys_dir = data_dir.join("keys");
     if !keys_dir.exists() {
-        std::fs::create_dir_all(&keys_dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&keys_dir).map_err(crate::redact::error)?;
     }
 
     for conn in &mut saved_data.connections {
@@ -1039,7 +1353,7 @@ pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize,
                 continue;
             } else {
                 #[cfg(debug_assertions)]
-                println!("[SSH Migration] Path {:?} (canonical: {:?}) does not start with data_dir {:?} (canonical: {:?}). Triggering migration check.", src_path, src_path_canonical, data_dir, data_dir_canonical);
+                crate::log_info!("[SSH Migration] Path {:?} (canonical: {:?}) does not start with data_dir {:?} (canonical: {:?}). Triggering migration check.", src_path, src_path_canonical, data_dir, data_dir_canonical);
             }
 
             if src_path.exists() && src_path.is_file() {
@@ -1062,11 +1376,11 @@ pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize,
                     conn.private_key_path = Some(dest_path.to_string_lossy().to_string());
                     changed = true;
                     #[cfg(debug_assertions)]
-                    println!("[SSH Migration] Key already exists at dest, updating config path only: {:?}", dest_path);
+                    crate::log_info!("[SSH Migration] Key already exists at dest, updating config path only: {:?}", dest_path);
                     continue;
                 }
 
-                match std::fs::copy(src_path, &dest_path) {
+                match crate::vault::at_rest::copy_in(src_path, &dest_path) {
                     Ok(_) => {
                         #[cfg(unix)]
                         {
@@ -1080,15 +1394,18 @@ pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize,
                         conn.private_key_path = Some(dest_path.to_string_lossy().to_string());
                         migrated_count += 1;
                         changed = true;
-                        println!(
+                        crate::log_info!(
                             "[SSH Migration] Migrated key for {} to {:?}",
-                            conn.name, dest_path
+                            conn.name,
+                            dest_path
                         );
                     }
                     Err(e) => {
-                        eprintln!(
+                        crate::log_error!(
                             "[SSH Migration] Failed to copy key for {} from {:?}: {}",
-                            conn.name, src_path, e
+                            conn.name,
+                            src_path,
+                            e
                         );
                     }
                 }
@@ -1097,24 +1414,11 @@ pub async fn ssh_migrate_all_keys(app_handle: tauri::AppHandle) -> Result<usize,
     }
 
     if changed {
-        let json = serde_json::to_string_pretty(&saved_data).map_err(|e| e.to_string())?;
-
-        // Use OpenOptions to truncate and write, then sync_all to ensure durability
-        use std::fs::OpenOptions;
-        use std::io::Write;
-
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&connections_path)
-            .map_err(|e| e.to_string())?;
-
-        file.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
-        file.sync_all().map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&saved_data).map_err(crate::redact::error)?;
+        write_store_file(&connections_path, &json)?;
 
         #[cfg(debug_assertions)]
-        println!(
+        crate::log_info!(
             "[SSH Migration] Successfully saved and synced updated connections.json to {:?}",
             connections_path
         );
@@ -1132,7 +1436,7 @@ pub async fn ssh_transport_lost(
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     if let Err(error) = crate::tunnels::stop_tunnels_for_connections(&app, &state, &[id.clone()]).await {
-        eprintln!("[TUNNEL] stop on transport lost for {id}: {error}");
+        crate::log_error!("[TUNNEL] stop on transport lost for {id}: {error}");
     }
 
     let mut connections = state.connections.lock().await;
@@ -1146,18 +1450,39 @@ pub async fn ssh_disconnect(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    state
+    crate::plugin_hooks::emit(
+        &app,
+        crate::plugin_hooks::Hook::BeforeDisconnect,
+        crate::plugin_hooks::BeforeDisconnect { connection_id: &id },
+    );
+    let closed_terminals = state
         .pty_manager
         .close_by_connection(&id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(crate::redact::error)?;
+    for term_id in &closed_terminals {
+        state_sync::notify(&app, StateKind::Terminal, term_id, ChangeKind::Removed);
+    }
 
     if let Err(error) = crate::tunnels::stop_tunnels_for_connections(&app, &state, &[id.clone()]).await {
-        eprintln!("[TUNNEL] stop on disconnect for {id}: {error}");
+        crate::log_error!("[TUNNEL] stop on disconnect for {id}: {error}");
+    }
+    if let Some(exit) = crate::tunnels::exit_node::stop_for_connection(
+        &state.tunnel_manager,
+        &get_data_dir(&app),
+        &id,
+    )
+    .await
+    {
+        crate::tunnels::commands::emit_exit_stopped(&app, &exit);
     }
 
     let mut connections = state.connections.lock().await;
     connections.remove(&id);
+    crate::listing_cache::forget_connection(&id);
+    crate::channel_budget::unregister(&id);
+    crate::devcontainer::forget_connection(&id);
+    state_sync::notify(&app, StateKind::Connection, &id, ChangeKind::Removed);
 
     Ok(())
 }
@@ -1177,8 +1502,18 @@ pub async fn ssh_disconnect_vault_backed(
 
     let mut errors = Vec::new();
     for id in &ids {
-        if let Err(error) = state.pty_manager.close_by_connection(id).await {
-            errors.push(format!("PTY close failed for {id}: {error}"));
+        crate::plugin_hooks::emit(
+            &app,
+            crate::plugin_hooks::Hook::BeforeDisconnect,
+            crate::plugin_hooks::BeforeDisconnect { connection_id: id },
+        );
+        match state.pty_manager.close_by_connection(id).await {
+            Ok(closed_terminals) => {
+                for term_id in &closed_terminals {
+                    state_sync::notify(&app, StateKind::Terminal, term_id, ChangeKind::Removed);
+                }
+            }
+            Err(error) => errors.push(format!("PTY close failed for {id}: {error}")),
         }
     }
 
@@ -1190,6 +1525,7 @@ pub async fn ssh_disconnect_vault_backed(
         let mut connections = state.connections.lock().await;
         for id in &ids {
             connections.remove(id);
+            state_sync::notify(&app, StateKind::Connection, id, ChangeKind::Removed);
         }
         Ok(ids)
     } else {
@@ -1197,17 +1533,137 @@ pub async fn ssh_disconnect_vault_backed(
     }
 }
 
+/// Large pastes are sent in paced chunks; very large ones report
+/// `terminal:paste-progress` as they go. Data that submits a dangerous command
+/// needs an `approval_token` (see [`approve_terminal_write`]).
 #[tauri::command]
 pub async fn terminal_write(
+    app: AppHandle,
+    term_id: String,
+    data: String,
+    approval_token: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    approve_terminal_write(&app, &state, &term_id, &data, approval_token.as_deref()).await?;
+    state
+        .pty_manager
+        .write_with_progress(&term_id, &data, |progress| {
+            let _ = app.emit("terminal:paste-progress", progress);
+        })
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Lets a terminal write through unless it submits a dangerous command. Then it
+/// spends `approval_token`, or, without one, opens the re-type-the-hostname
+/// dialog (`ai:dangerous-confirm`) and fails with `APPROVAL_REQUIRED`; the
+/// dialog repeats the write with the token once the user confirms.
+async fn approve_terminal_write(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    term_id: &str,
+    data: &str,
+    approval_token: Option<&str>,
+) -> Result<(), String> {
+    if !crate::ai::policy::approval::submits_dangerous_command(data) {
+        return Ok(());
+    }
+    require_approval(app, state, term_id, data, approval_token).await
+}
+
+/// Spends `approval_token` on `data`, or opens the confirmation dialog for it.
+async fn require_approval(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    term_id: &str,
+    data: &str,
+    approval_token: Option<&str>,
+) -> Result<(), String> {
+    use crate::ai::policy::approval::APPROVALS;
+
+    if let Some(token) = approval_token {
+        APPROVALS.consume(token, term_id, data)?;
+        crate::log_info!("[TERM] Writing approved dangerous command to {}", term_id);
+        return Ok(());
+    }
+    let host = terminal_host(state, term_id).await?;
+    let challenge = APPROVALS.challenge(term_id, data, &host);
+    app.emit("ai:dangerous-confirm", &challenge)
+        .map_err(crate::redact::error)?;
+    Err("APPROVAL_REQUIRED: confirm the target host first".to_string())
+}
+
+/// The hostname a user re-types to approve a command in `term_id`.
+async fn terminal_host(state: &State<'_, AppState>, term_id: &str) -> Result<String, String> {
+    let connection_id = state
+        .pty_manager
+        .connection_id(term_id)
+        .await
+        .ok_or_else(|| "Terminal session not found".to_string())?;
+    if connection_id == "local" {
+        return Ok(sysinfo::System::host_name().unwrap_or_else(|| "localhost".to_string()));
+    }
+    let connections = state.connections.lock().await;
+    connections
+        .get(&connection_id)
+        .map(|conn| conn.config.host.clone())
+        .ok_or_else(|| crate::redact::error(format!("Connection {} not found", connection_id)))
+}
+
+/// Opens the re-type-the-hostname dialog (`ai:dangerous-confirm`) for `data`
+/// about to be written to `term_id`.
+#[tauri::command]
+pub async fn ai_dangerous_command_challenge(
+    app: AppHandle,
     term_id: String,
     data: String,
     state: State<'_, AppState>,
+) -> Result<crate::ai::policy::approval::DangerousChallenge, String> {
+    let host = terminal_host(&state, &term_id).await?;
+    let challenge = crate::ai::policy::approval::APPROVALS.challenge(&term_id, &data, &host);
+    app.emit("ai:dangerous-confirm", &challenge)
+        .map_err(crate::redact::error)?;
+    Ok(challenge)
+}
+
+/// Answers a challenge with the hostname the user typed; returns a single-use token.
+#[tauri::command]
+pub async fn ai_dangerous_command_confirm(
+    challenge_id: String,
+    typed_host: String,
+) -> Result<String, String> {
+    crate::ai::policy::approval::APPROVALS.confirm(&challenge_id, &typed_host)
+}
+
+/// Writes an AI-suggested command to a terminal, submitting it when `execute`
+/// is set. Commands the model rated `safety: "dangerous"` always need the
+/// hostname approval; others need it when they match the dangerous patterns,
+/// as with [`terminal_write`].
+#[tauri::command]
+pub async fn ai_terminal_run(
+    app: AppHandle,
+    term_id: String,
+    command: String,
+    safety: Option<String>,
+    approval_token: Option<String>,
+    execute: bool,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let data = if execute {
+        format!("{}\r", command.trim_end_matches(['\r', '\n']))
+    } else {
+        command
+    };
+    if safety.as_deref() == Some("dangerous") {
+        require_approval(&app, &state, &term_id, &data, approval_token.as_deref()).await?;
+    } else {
+        approve_terminal_write(&app, &state, &term_id, &data, approval_token.as_deref()).await?;
+    }
     state
         .pty_manager
         .write(&term_id, &data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -1221,7 +1677,7 @@ pub async fn terminal_resize(
         .pty_manager
         .resize(&term_id, cols, rows)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -1234,7 +1690,7 @@ pub async fn terminal_navigate(
         .pty_manager
         .navigate_to_path(&term_id, &path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -1247,7 +1703,7 @@ pub async fn connections_get(
         let guard = vault.lock().await;
         if guard.vault_id().is_some() {
             crate::vault::commands::repair_connection_refs(&data_dir, &guard)
-                .map_err(|e| e.to_string())?;
+                .map_err(crate::redact::error)?;
         }
     }
     let file_path = data_dir.join("connections.json");
@@ -1259,12 +1715,33 @@ pub async fn connections_get(
         });
     }
 
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = read_store_file(&file_path)?;
+    let saved_data: SavedData = serde_json::from_str(&data).map_err(crate::redact::error)?;
 
     Ok(saved_data)
 }
 
+/// Filtered, sorted page of saved connections; see [`crate::connection_query`].
+#[tauri::command]
+pub async fn connections_query(
+    app: AppHandle,
+    filter: Option<crate::connection_query::ConnectionFilter>,
+) -> Result<crate::connection_query::ConnectionPage, String> {
+    let file_path = get_data_dir(&app).join("connections.json");
+    let connections = if file_path.exists() {
+        let data = read_store_file(&file_path)?;
+        serde_json::from_str::<SavedData>(&data)
+            .map_err(crate::redact::error)?
+            .connections
+    } else {
+        vec![]
+    };
+    Ok(crate::connection_query::query(
+        connections,
+        &filter.unwrap_or_default(),
+    ))
+}
+
 #[tauri::command]
 pub async fn connections_save(
     app: AppHandle,
@@ -1278,16 +1755,16 @@ pub async fn connections_save(
 
     let data_dir = get_data_dir(&app);
     if !data_dir.exists() {
-        std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&data_dir).map_err(crate::redact::error)?;
     }
 
     let file_path = data_dir.join("connections.json");
-    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&data).map_err(crate::redact::error)?;
 
     let _connections_guard = CONNECTIONS_MUTATION_LOCK
         .lock()
-        .map_err(|e| e.to_string())?;
-    write_atomic_file(&file_path, &json)?;
+        .map_err(crate::redact::error)?;
+    write_store_file(&file_path, &json)?;
 
     Ok(())
 }
@@ -1587,6 +2064,21 @@ fn parse_csv_connections(content: &str) -> Result<Vec<SavedConnection>, String>
                 Some(pinned_features)
             },
             auth_ref: None,
+            resolution: None,
+            login_steps: None,
+            session_logging: None,
+            persistent_session: None,
+            mosh: None,
+            scrollback_limit_mb: None,
+            quiet_login: None,
+            identities_only: None,
+            startup_commands: None,
+            env: None,
+            terminal: None,
+            tcp: None,
+            channel_budget: None,
+            color: None,
+            fallbacks: None,
         });
     }
 
@@ -1601,30 +2093,174 @@ fn build_ssh_config_export(connections: &[SavedConnection]) -> String {
 
     let mut output = String::new();
     for connection in connections {
-        let alias = alias_by_id
-            .get(&connection.id)
-            .cloned()
-            .unwrap_or_else(|| build_host_alias(connection));
+        output.push_str(&build_ssh_host_block(connection, &alias_by_id));
+        output.push('\n');
+    }
+    output
+}
+
+/// One `Host` block; `alias_by_id` names this connection and its jump server.
+fn build_ssh_host_block(
+    connection: &SavedConnection,
+    alias_by_id: &HashMap<String, String>,
+) -> String {
+    let alias = alias_by_id
+        .get(&connection.id)
+        .cloned()
+        .unwrap_or_else(|| build_host_alias(connection));
 
-        output.push_str(&format!("Host {}\n", alias));
-        output.push_str(&format!("  HostName {}\n", connection.host));
-        output.push_str(&format!("  User {}\n", connection.username));
-        output.push_str(&format!("  Port {}\n", connection.port));
-        if let Some(key_path) = &connection.private_key_path {
-            if !key_path.trim().is_empty() {
-                output.push_str(&format!("  IdentityFile {}\n", key_path));
+    let mut output = String::new();
+    output.push_str(&format!("Host {}\n", alias));
+    output.push_str(&format!("  HostName {}\n", connection.host));
+    output.push_str(&format!("  User {}\n", connection.username));
+    output.push_str(&format!("  Port {}\n", connection.port));
+    if let Some(key_path) = &connection.private_key_path {
+        if !key_path.trim().is_empty() {
+            output.push_str(&format!("  IdentityFile {}\n", key_path));
+            if connection.identities_only == Some(true) {
+                output.push_str("  IdentitiesOnly yes\n");
             }
         }
-        if let Some(jump_id) = &connection.jump_server_id {
-            if let Some(jump_alias) = alias_by_id.get(jump_id) {
-                output.push_str(&format!("  ProxyJump {}\n", jump_alias));
-            }
+    }
+    if let Some(jump_id) = &connection.jump_server_id {
+        if let Some(jump_alias) = alias_by_id.get(jump_id) {
+            output.push_str(&format!("  ProxyJump {}\n", jump_alias));
         }
-        output.push('\n');
     }
     output
 }
 
+/// Where `ssh_export_config` writes.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SshConfigExportTarget {
+    /// `~/.ssh/zync.config`, with an `Include` for it added to `~/.ssh/config`.
+    #[default]
+    Include,
+    /// A marked section at the end of `~/.ssh/config` itself.
+    Config,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigExportResult {
+    pub path: String,
+    pub hosts: Vec<String>,
+    /// `~/.ssh/config` gained an `Include` line for the exported file.
+    pub include_added: bool,
+}
+
+const SSH_CONFIG_INCLUDE_FILE: &str = "zync.config";
+
+/// Rewrites an ssh_config file, keeping its permissions (ssh refuses
+/// group-writable configs, and users often keep them at 0600).
+fn write_ssh_config_file(path: &std::path::Path, content: &str) -> Result<(), String> {
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path)
+            .map(|meta| meta.permissions().mode() & 0o7777)
+            .unwrap_or(0o600)
+    };
+    write_atomic_file(path, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .map_err(crate::redact::error)?;
+    }
+    Ok(())
+}
+
+/// Writes the selected connections (and the jump servers they go through) as
+/// `Host` blocks for the OpenSSH client. Hosts are kept in a marked section
+/// that later exports update in place; everything else in the file stays.
+#[tauri::command]
+pub async fn ssh_export_config(
+    app: AppHandle,
+    connection_ids: Vec<String>,
+    target: Option<SshConfigExportTarget>,
+    vault: State<'_, tokio::sync::Mutex<crate::vault::store::VaultService>>,
+) -> Result<SshConfigExportResult, String> {
+    if connection_ids.is_empty() {
+        return Err("Select at least one connection to export.".to_string());
+    }
+    let home = app.path().home_dir().map_err(crate::redact::error)?;
+    let ssh_dir = home.join(".ssh");
+    let config_path = ssh_dir.join("config");
+    let all_connections = connections_get(app, vault).await?.connections;
+
+    let mut wanted: HashSet<String> = connection_ids.into_iter().collect();
+    let mut pending: Vec<String> = wanted.iter().cloned().collect();
+    while let Some(id) = pending.pop() {
+        let jump = all_connections
+            .iter()
+            .find(|connection| connection.id == id)
+            .and_then(|connection| connection.jump_server_id.clone());
+        if let Some(jump) = jump.filter(|jump| wanted.insert(jump.clone())) {
+            pending.push(jump);
+        }
+    }
+    let selected: Vec<&SavedConnection> = all_connections
+        .iter()
+        .filter(|connection| wanted.contains(&connection.id))
+        .collect();
+    if selected.is_empty() {
+        return Err("None of the selected connections exist.".to_string());
+    }
+
+    let alias_by_id = selected
+        .iter()
+        .map(|connection| (connection.id.clone(), build_host_alias(connection)))
+        .collect::<HashMap<_, _>>();
+    let blocks: Vec<(String, String)> = selected
+        .iter()
+        .map(|connection| {
+            (
+                alias_by_id[&connection.id].clone(),
+                build_ssh_host_block(connection, &alias_by_id),
+            )
+        })
+        .collect();
+
+    let read_existing = |path: &std::path::Path| match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(crate::redact::error(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    };
+    let target = target.unwrap_or_default();
+    let path = match target {
+        SshConfigExportTarget::Include => ssh_dir.join(SSH_CONFIG_INCLUDE_FILE),
+        SshConfigExportTarget::Config => config_path.clone(),
+    };
+    let merged = crate::ssh_config::merge_managed_hosts(&read_existing(&path)?, &blocks);
+    write_ssh_config_file(&path, &merged)?;
+
+    let mut include_added = false;
+    if matches!(target, SshConfigExportTarget::Include) {
+        let config = read_existing(&config_path)?;
+        if let Some(updated) = crate::ssh_config::with_include(&config, SSH_CONFIG_INCLUDE_FILE) {
+            write_ssh_config_file(&config_path, &updated)?;
+            include_added = true;
+        }
+    }
+    crate::log_info!(
+        "[SSH] Exported {} hosts to {}",
+        blocks.len(),
+        path.display()
+    );
+
+    Ok(SshConfigExportResult {
+        path: path.to_string_lossy().to_string(),
+        hosts: blocks.into_iter().map(|(alias, _)| alias).collect(),
+        include_added,
+    })
+}
+
 #[tauri::command]
 pub async fn connections_export_to_file(
     app: AppHandle,
@@ -1677,10 +2313,11 @@ pub async fn connections_export_to_file(
                 connections: selected_connections,
                 folders,
             })
-            .map_err(|error| error.to_string())?
+            .map_err(crate::redact::error)?
+        }
+        "json" => {
+            serde_json::to_string_pretty(&selected_connections).map_err(crate::redact::error)?
         }
-        "json" => serde_json::to_string_pretty(&selected_connections)
-            .map_err(|error| error.to_string())?,
         "csv" => {
             let mut lines = vec![
                 "id,name,host,port,username,password,privateKeyPath,jumpServerId,folder,theme,tags,isFavorite,pinnedFeatures,createdAt,lastConnected".to_string(),
@@ -1693,7 +2330,7 @@ pub async fn connections_export_to_file(
     };
 
     std::fs::write(path, content)
-        .map_err(|error| format!("Failed to write export file: {}", error))?;
+        .map_err(|error| crate::redact::error(format!("Failed to write export file: {}", error)))?;
     Ok(path.to_string())
 }
 
@@ -1713,14 +2350,15 @@ pub async fn connections_import_from_file(
     if !file_path.is_file() {
         return Err("Import path is not a file.".to_string());
     }
-    let metadata = std::fs::metadata(file_path)
-        .map_err(|error| format!("Cannot read import file metadata: {}", error))?;
+    let metadata = std::fs::metadata(file_path).map_err(|error| {
+        crate::redact::error(format!("Cannot read import file metadata: {}", error))
+    })?;
     if metadata.len() > MAX_CONNECTION_IMPORT_BYTES {
         return Err("Import file is too large (max 5 MiB).".to_string());
     }
 
     let content = std::fs::read_to_string(file_path)
-        .map_err(|error| format!("Failed to read import file: {}", error))?;
+        .map_err(|error| crate::redact::error(format!("Failed to read import file: {}", error)))?;
     let requested_format = request
         .format
         .unwrap_or_else(|| "auto".to_string())
@@ -1769,6 +2407,92 @@ pub async fn connections_import_from_file(
     }
 }
 
+/// Writes every connection, folder and tunnel (and, with `include_keys`, the
+/// key files they use) to an encrypted bundle at `path`; see [`crate::bundle`].
+#[tauri::command]
+pub async fn connections_export(
+    app: AppHandle,
+    path: String,
+    include_keys: Option<bool>,
+    passphrase: String,
+    vault: State<'_, tokio::sync::Mutex<crate::vault::store::VaultService>>,
+) -> Result<String, String> {
+    let path = path.trim().to_string();
+    if path.is_empty() {
+        return Err("Export path is required.".to_string());
+    }
+    let tunnels_path = get_data_dir(&app).join("tunnels.json");
+    let SavedData {
+        connections,
+        folders,
+    } = connections_get(app, vault).await?;
+    let tunnels = crate::sync::domain_tunnels::load_saved_tunnels(&tunnels_path)
+        .map_err(crate::redact::error)?
+        .tunnels;
+    let keys = if include_keys.unwrap_or(false) {
+        crate::bundle::collect_keys(&connections)
+    } else {
+        Vec::new()
+    };
+    let contents = crate::bundle::BundleContents {
+        exported_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0),
+        connections,
+        folders,
+        tunnels,
+        keys,
+    };
+    let sealed = tokio::task::spawn_blocking(move || {
+        crate::bundle::seal(
+            &contents,
+            &passphrase,
+            &crate::vault::crypto::KdfParams::default_production(),
+        )
+    })
+    .await
+    .map_err(crate::redact::error)?
+    .map_err(crate::redact::error)?;
+    std::fs::write(&path, &sealed)
+        .map_err(|error| crate::redact::error(format!("Failed to write bundle: {}", error)))?;
+    Ok(path)
+}
+
+/// Decrypts a bundle written by `connections_export`, installs its key files
+/// and returns its contents for the frontend to merge.
+#[tauri::command]
+pub async fn connections_import(
+    app: AppHandle,
+    path: String,
+    passphrase: String,
+) -> Result<crate::bundle::BundleImport, String> {
+    let file_path = std::path::PathBuf::from(path.trim());
+    let metadata = std::fs::metadata(&file_path)
+        .map_err(|error| crate::redact::error(format!("Cannot read bundle: {}", error)))?;
+    if !metadata.is_file() {
+        return Err("Bundle path is not a file.".to_string());
+    }
+    if metadata.len() > crate::bundle::MAX_BUNDLE_BYTES {
+        return Err("Bundle is too large (max 50 MiB).".to_string());
+    }
+    let keys_dir = get_data_dir(&app).join("keys");
+    tokio::task::spawn_blocking(move || {
+        let bytes = std::fs::read(&file_path)?;
+        let mut contents = crate::bundle::open(&bytes, &passphrase)?;
+        let keys_imported = crate::bundle::install_keys(&mut contents, &keys_dir)?;
+        Ok::<_, anyhow::Error>(crate::bundle::BundleImport {
+            connections: contents.connections,
+            folders: contents.folders,
+            tunnels: contents.tunnels,
+            keys_imported,
+        })
+    })
+    .await
+    .map_err(crate::redact::error)?
+    .map_err(crate::redact::error)
+}
+
 #[tauri::command]
 pub async fn terminal_create(
     term_id: String,
@@ -1785,15 +2509,41 @@ pub async fn terminal_create(
     let generation = match generation {
         Some(value) => value,
         None => {
-            eprintln!(
+            crate::log_error!(
                 "[TERM] terminal_create called without generation for connection {} and term {}; defaulting to 0",
                 connection_id, term_id
             );
             0
         }
     };
-    // Check if this is a local or remote connection
+    let session_log = session_log_options(&app, &state, &connection_id).await;
+    let settings = read_effective_settings(&app).ok();
+    let shell_integration = settings
+        .as_ref()
+        .and_then(|settings| {
+            settings
+                .pointer("/terminal/shellIntegration")
+                .and_then(Value::as_bool)
+        })
+        .unwrap_or(false);
+    let (scrollback_limit, scrollback_total) =
+        scrollback_limits(&app, &state, &connection_id).await;
+    // Check if this is a local or remote connection
     if connection_id == "local" {
+        // Local shells take their extra variables from `terminal.localEnv`.
+        let local_env = settings
+            .as_ref()
+            .and_then(|settings| settings.pointer("/terminal/localEnv"))
+            .and_then(|value| {
+                serde_json::from_value::<HashMap<String, String>>(value.clone()).ok()
+            });
+        let local_options = settings
+            .as_ref()
+            .and_then(|settings| settings.pointer("/terminal/localOptions"))
+            .and_then(|value| {
+                serde_json::from_value::<crate::types::TerminalOptions>(value.clone()).ok()
+            })
+            .unwrap_or_default();
         // Use term_id (UUID) for the session, not connection_id
         state
             .pty_manager
@@ -1807,18 +2557,68 @@ pub async fn terminal_create(
                 output_channel,
                 shell,
                 cwd,
+                session_log,
+                shell_integration,
+                crate::pty::environment_pairs(local_env.as_ref()),
+                local_options,
             )
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(crate::redact::error)?;
+        state
+            .pty_manager
+            .configure_scrollback(&term_id, scrollback_limit, scrollback_total);
         Ok(term_id)
     } else {
-        let channel = open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
-        let remote_os = {
+        if let Some(client) = mosh_bootstrap(&state, &connection_id).await? {
+            state
+                .pty_manager
+                .create_mosh_session(
+                    term_id.clone(),
+                    connection_id,
+                    generation,
+                    cols,
+                    rows,
+                    app,
+                    output_channel,
+                    client,
+                    session_log,
+                )
+                .await
+                .map_err(crate::redact::error)?;
+            state
+                .pty_manager
+                .configure_scrollback(&term_id, scrollback_limit, scrollback_total);
+            return Ok(term_id);
+        }
+        let persistent_launch =
+            persistent_session_launch(&state, &connection_id, shell.as_deref()).await;
+        let (remote_os, quiet_login, startup_commands, env, terminal_options) = {
             let connections = state.connections.lock().await;
-            connections
-                .get(&connection_id)
-                .and_then(|c| c.detected_os.clone())
+            let handle = connections.get(&connection_id);
+            (
+                handle.and_then(|c| c.detected_os.clone()),
+                handle.and_then(|c| c.config.quiet_login).unwrap_or(false),
+                handle
+                    .and_then(|c| c.config.startup_commands.clone())
+                    .unwrap_or_default(),
+                crate::pty::environment_pairs(handle.and_then(|c| c.config.env.as_ref())),
+                handle
+                    .and_then(|c| c.config.terminal.clone())
+                    .unwrap_or_default(),
+            )
+        };
+        let integration_bootstrap = if shell_integration && persistent_launch.is_none() {
+            shell_integration_bootstrap(
+                &state,
+                &connection_id,
+                remote_os.as_deref(),
+                shell.as_deref(),
+            )
+            .await
+        } else {
+            None
         };
+        let channel = open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
 
         state
             .pty_manager
@@ -1834,14 +2634,585 @@ pub async fn terminal_create(
                 shell,
                 remote_os,
                 cwd,
+                session_log,
+                persistent_launch,
+                integration_bootstrap,
+                quiet_login,
+                startup_commands,
+                env,
+                terminal_options,
             )
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(crate::redact::error)?;
+        state
+            .pty_manager
+            .configure_scrollback(&term_id, scrollback_limit, scrollback_total);
 
         Ok(term_id)
     }
 }
 
+/// tmux and screen sessions running on the server, including ones zync did not start.
+#[tauri::command]
+pub async fn remote_sessions_list(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::persistent_session::RemoteSession>, String> {
+    if remote_path_style(&state, &connection_id).await == RemotePathStyle::Windows {
+        return Ok(Vec::new());
+    }
+    let (_, stdout, _) = exec_remote_capture(
+        &connection_id,
+        crate::persistent_session::LIST_COMMAND,
+        &state,
+    )
+    .await?;
+    Ok(crate::persistent_session::parse_sessions(
+        &String::from_utf8_lossy(&stdout),
+    ))
+}
+
+/// Opens a terminal already attached to an existing tmux/screen session
+/// (`id` from `remote_sessions_list`).
+#[tauri::command]
+pub async fn remote_sessions_attach(
+    term_id: String,
+    connection_id: String,
+    multiplexer: crate::persistent_session::Multiplexer,
+    id: String,
+    cols: u16,
+    rows: u16,
+    output_channel: tauri::ipc::Channel,
+    generation: Option<u32>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    if id.trim().is_empty() {
+        return Err("Session name is empty".to_string());
+    }
+    let session_log = session_log_options(&app, &state, &connection_id).await;
+    let (scrollback_limit, scrollback_total) =
+        scrollback_limits(&app, &state, &connection_id).await;
+    let (remote_os, terminal_options) = {
+        let connections = state.connections.lock().await;
+        let handle = connections.get(&connection_id);
+        (
+            handle.and_then(|c| c.detected_os.clone()),
+            handle
+                .and_then(|c| c.config.terminal.clone())
+                .unwrap_or_default(),
+        )
+    };
+    let attach = crate::persistent_session::attach_command(multiplexer, &id);
+    let channel = open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+    state
+        .pty_manager
+        .create_remote_session(
+            term_id.clone(),
+            connection_id,
+            generation.unwrap_or(0),
+            channel,
+            cols,
+            rows,
+            app,
+            output_channel,
+            None,
+            remote_os,
+            None,
+            session_log,
+            Some(attach),
+            None,
+            false,
+            Vec::new(),
+            Vec::new(),
+            terminal_options,
+        )
+        .await
+        .map_err(crate::redact::error)?;
+    state
+        .pty_manager
+        .configure_scrollback(&term_id, scrollback_limit, scrollback_total);
+    Ok(term_id)
+}
+
+/// Runs a docker step on the server; its stdout, or stderr as the error.
+async fn devcontainer_step(
+    connection_id: &str,
+    command: &str,
+    state: &State<'_, AppState>,
+) -> Result<String, String> {
+    let (status, stdout, stderr) = exec_remote_capture(connection_id, command, state).await?;
+    if status != 0 {
+        return Err(String::from_utf8_lossy(&stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Attaches to the dev container of the project at `path` on the server,
+/// starting or creating the container first (see [`crate::devcontainer`]).
+#[tauri::command]
+pub async fn devcontainer_attach(
+    connection_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<crate::devcontainer::DevcontainerSession, String> {
+    use crate::devcontainer as dc;
+
+    let project = match path.trim().trim_end_matches('/') {
+        "" if path.trim().starts_with('/') => "/".to_string(),
+        "" => return Err("Project path is empty".to_string()),
+        project => project.to_string(),
+    };
+    let (status, stdout, stderr) =
+        exec_remote_capture(&connection_id, &dc::detect_command(&project), &state).await?;
+    if status == dc::NO_CONFIG_STATUS {
+        return Err(crate::redact::error(format!(
+            "No .devcontainer configuration in {}",
+            project
+        )));
+    }
+    if status != 0 {
+        return Err(crate::redact::error(format!(
+            "Cannot read {}: {}",
+            project,
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    let (config_path, content) = dc::split_detected(&String::from_utf8_lossy(&stdout))
+        .ok_or_else(|| "Unexpected devcontainer detection output".to_string())?;
+    let config = dc::parse_config(&content).map_err(crate::redact::error)?;
+
+    let found = devcontainer_step(&connection_id, &dc::find_command(&project), &state)
+        .await
+        .map_err(|e| {
+            crate::redact::error(format!("Docker is not available on the server: {}", e))
+        })?;
+    let mut remote_user = config.user();
+    let mut workspace_folder = config.workspace_folder(&project);
+    let container_id = match dc::parse_found(&found) {
+        Some((id, true)) => id,
+        Some((id, false)) => {
+            crate::log_info!("[DEVCONTAINER] Starting {} for {}", id, project);
+            devcontainer_step(&connection_id, &format!("docker start {}", id), &state).await?;
+            id
+        }
+        None => {
+            crate::log_info!("[DEVCONTAINER] Creating container for {}", project);
+            let (status, stdout, stderr) =
+                exec_remote_capture(&connection_id, &dc::cli_up_command(&project), &state).await?;
+            if status == 127 {
+                // No devcontainer CLI: create it from the config directly.
+                let script = dc::create_script(&config, &project, &config_path)
+                    .map_err(crate::redact::error)?;
+                let stdout = devcontainer_step(&connection_id, &script, &state)
+                    .await
+                    .map_err(|e| {
+                        crate::redact::error(format!("Creating the dev container failed: {}", e))
+                    })?;
+                stdout
+                    .lines()
+                    .map(str::trim)
+                    .rfind(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .ok_or_else(|| "Docker did not report a container".to_string())?
+            } else {
+                let up = dc::parse_cli_up(&String::from_utf8_lossy(&stdout)).map_err(|e| {
+                    crate::redact::error(format!(
+                        "{} {}",
+                        e,
+                        String::from_utf8_lossy(&stderr).trim()
+                    ))
+                })?;
+                remote_user = up.remote_user.or(remote_user);
+                workspace_folder = up.workspace_folder.unwrap_or(workspace_folder);
+                up.container_id
+            }
+        }
+    };
+
+    let session = dc::DevcontainerSession {
+        id: dc::session_id(&connection_id, &container_id),
+        connection_id,
+        name: config.display_name(&project),
+        project_path: project,
+        config_path,
+        container_id,
+        workspace_folder,
+        remote_user,
+        remote_env: config.remote_env(),
+    };
+    dc::register(session.clone());
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn devcontainer_list() -> Result<Vec<crate::devcontainer::DevcontainerSession>, String> {
+    Ok(crate::devcontainer::list())
+}
+
+/// Forgets an attached container; it keeps running.
+#[tauri::command]
+pub async fn devcontainer_detach(session_id: String) -> Result<bool, String> {
+    Ok(crate::devcontainer::detach(&session_id))
+}
+
+/// Opens a terminal in an attached container (`docker exec -it`).
+#[tauri::command]
+pub async fn devcontainer_terminal(
+    term_id: String,
+    session_id: String,
+    cols: u16,
+    rows: u16,
+    output_channel: tauri::ipc::Channel,
+    generation: Option<u32>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    let connection_id = session.connection_id.clone();
+    let session_log = session_log_options(&app, &state, &connection_id).await;
+    let (scrollback_limit, scrollback_total) =
+        scrollback_limits(&app, &state, &connection_id).await;
+    let terminal_options = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&connection_id)
+            .and_then(|c| c.config.terminal.clone())
+            .unwrap_or_default()
+    };
+    let channel = open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+    state
+        .pty_manager
+        .create_remote_session(
+            term_id.clone(),
+            connection_id,
+            generation.unwrap_or(0),
+            channel,
+            cols,
+            rows,
+            app,
+            output_channel,
+            None,
+            Some("linux".to_string()),
+            None,
+            session_log,
+            Some(session.shell_command()),
+            None,
+            false,
+            Vec::new(),
+            Vec::new(),
+            terminal_options,
+        )
+        .await
+        .map_err(crate::redact::error)?;
+    state
+        .pty_manager
+        .configure_scrollback(&term_id, scrollback_limit, scrollback_total);
+    Ok(term_id)
+}
+
+/// Runs `command` in an attached container's workspace folder.
+#[tauri::command]
+pub async fn devcontainer_exec(
+    app: AppHandle,
+    session_id: String,
+    command: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    audit_command(&app, &session.connection_id, &command, "devcontainer_exec").await;
+    devcontainer_step(
+        &session.connection_id,
+        &session.exec_command(&command, false, false),
+        &state,
+    )
+    .await
+    .map_err(|e| crate::redact::error(format!("Command failed: {}", e)))
+}
+
+/// Lists a directory inside an attached container; relative paths start at
+/// the workspace folder.
+#[tauri::command]
+pub async fn devcontainer_list_dir(
+    session_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::fs::FileEntry>, String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    let stdout = devcontainer_step(&session.connection_id, &session.list_command(&path), &state)
+        .await
+        .map_err(|e| crate::redact::error(format!("Listing '{}' failed: {}", path, e)))?;
+    Ok(crate::fs::parse_ls_listing(
+        &stdout,
+        &session.container_path(&path),
+    ))
+}
+
+/// Reads a file out of an attached container with `docker cp`.
+#[tauri::command]
+pub async fn devcontainer_read_file(
+    session_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<crate::text_encoding::TextFile, String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    let (status, stdout, stderr) =
+        exec_remote_capture(&session.connection_id, &session.read_command(&path), &state).await?;
+    if status != 0 {
+        return Err(crate::redact::error(format!(
+            "Reading '{}' failed: {}",
+            path,
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    let bytes = crate::devcontainer::first_file_in_tar(&stdout)
+        .map_err(|e| crate::redact::error(format!("Reading '{}' failed: {}", path, e)))?;
+    Ok(crate::text_encoding::decode(&bytes))
+}
+
+/// Writes a file in an attached container as the container's user, keeping
+/// the owner of files that already exist.
+#[tauri::command]
+pub async fn devcontainer_write_file(
+    session_id: String,
+    path: String,
+    content: String,
+    format: Option<crate::text_encoding::TextFormat>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    let content = match format {
+        Some(format) => {
+            crate::text_encoding::encode(&content, &format).map_err(crate::redact::error)?
+        }
+        None => content.into_bytes(),
+    };
+    let (status, _, stderr) = exec_remote_with_input(
+        &session.connection_id,
+        &session.write_command(&path),
+        &content,
+        &state,
+    )
+    .await?;
+    if status != 0 {
+        return Err(crate::redact::error(format!(
+            "Writing '{}' failed: {}",
+            path,
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Copies between the server and an attached container with `docker cp`:
+/// `server_path` into `container_path` when `into_container`, the other way
+/// otherwise. Combined with the SFTP file manager this moves files between
+/// this machine and the container.
+#[tauri::command]
+pub async fn devcontainer_copy(
+    session_id: String,
+    server_path: String,
+    container_path: String,
+    into_container: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let session = crate::devcontainer::get(&session_id).map_err(crate::redact::error)?;
+    let command = session.copy_command(&server_path, &container_path, into_container);
+    devcontainer_step(&session.connection_id, &command, &state)
+        .await
+        .map(|_| ())
+        .map_err(|e| crate::redact::error(format!("docker cp failed: {}", e)))
+}
+
+/// Clipboard tool on the server, detected once per connection.
+async fn remote_clipboard_tool(
+    connection_id: &str,
+    state: &State<'_, AppState>,
+) -> Result<crate::remote_clipboard::ClipboardTool, String> {
+    use crate::remote_clipboard::{self as clipboard, ClipboardTool};
+
+    if remote_path_style(state, connection_id).await == RemotePathStyle::Windows {
+        return Ok(ClipboardTool::Windows);
+    }
+    if let Some(tool) = clipboard::cached_tool(connection_id) {
+        return Ok(tool);
+    }
+    let (_, stdout, _) =
+        exec_remote_capture(connection_id, clipboard::DETECT_COMMAND, state).await?;
+    let tool =
+        ClipboardTool::from_detect_output(&String::from_utf8_lossy(&stdout)).ok_or_else(|| {
+            "No clipboard tool (wl-copy, xclip, xsel or pbcopy) found on the server".to_string()
+        })?;
+    clipboard::remember_tool(connection_id, tool);
+    Ok(tool)
+}
+
+/// Puts `text` on the server's clipboard. Text that looks like it holds
+/// credentials is refused with `SECRETS_DETECTED:` unless `allow_secrets` is set.
+#[tauri::command]
+pub async fn remote_clipboard_set(
+    connection_id: String,
+    text: String,
+    allow_secrets: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::remote_clipboard::check_size(text.len())?;
+    let warnings = crate::remote_clipboard::secret_warnings(&text);
+    if !warnings.is_empty() && !allow_secrets.unwrap_or(false) {
+        return Err(crate::redact::error(format!(
+            "SECRETS_DETECTED: the text looks like it contains a {}",
+            warnings.join(", ")
+        )));
+    }
+    let tool = remote_clipboard_tool(&connection_id, &state).await?;
+    let (status, _, stderr) =
+        exec_remote_with_input(&connection_id, tool.set_command(), text.as_bytes(), &state).await?;
+    if status != 0 {
+        return Err(crate::redact::error(format!(
+            "{} failed: {}",
+            tool.set_command(),
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the server's clipboard, with warnings for credential-like content.
+#[tauri::command]
+pub async fn remote_clipboard_get(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::remote_clipboard::RemoteClipboard, String> {
+    use crate::remote_clipboard::{self as clipboard, ClipboardTool};
+
+    let tool = remote_clipboard_tool(&connection_id, &state).await?;
+    let command = match tool {
+        ClipboardTool::Windows => tool.get_command().to_string(),
+        // Stop reading one byte past the limit rather than pulling it all over.
+        _ => format!(
+            "{} | head -c {}",
+            tool.get_command(),
+            clipboard::MAX_CLIPBOARD_BYTES + 1
+        ),
+    };
+    let (status, stdout, stderr) = exec_remote_capture(&connection_id, &command, &state).await?;
+    if status != 0 {
+        return Err(crate::redact::error(format!(
+            "{} failed: {}",
+            tool.get_command(),
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    clipboard::check_size(stdout.len())?;
+    let text = String::from_utf8_lossy(&stdout).into_owned();
+    Ok(clipboard::RemoteClipboard {
+        warnings: clipboard::secret_warnings(&text),
+        text,
+        tool,
+    })
+}
+
+/// Negotiated key exchange, host key, cipher, MAC and compression for a connection,
+/// so legacy crypto is visible. Algorithms come from a fresh pre-auth probe of the
+/// server (see `crate::ssh_security`); connections behind a jump host only report
+/// their host key.
+#[tauri::command]
+pub async fn connection_security_info(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::ssh_security::ConnectionSecurityInfo, String> {
+    use crate::ssh_security::{self as security, ConnectionSecurityInfo};
+
+    let config = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&connection_id)
+            .map(|handle| handle.config.clone())
+            .ok_or_else(|| {
+                crate::redact::error(format!("Connection {} not found", connection_id))
+            })?
+    };
+    if config.jump_host.is_some() {
+        return Ok(ConnectionSecurityInfo::unavailable(
+            &connection_id,
+            "Algorithms are not probed through jump hosts".to_string(),
+        ));
+    }
+
+    let probe = async {
+        let resolved =
+            crate::dns::resolve_host(&config.host, config.port, config.resolution.as_ref()).await?;
+        let mut stream = tokio::net::TcpStream::connect(&resolved.addresses[..]).await?;
+        security::probe_kexinit(&mut stream).await
+    };
+    match tokio::time::timeout(std::time::Duration::from_secs(10), probe).await {
+        Ok(Ok((version, server))) => Ok(security::summarize(
+            &connection_id,
+            Some(version),
+            &security::ClientPreferences::russh_defaults(),
+            &server,
+        )),
+        Ok(Err(e)) => Ok(ConnectionSecurityInfo::unavailable(
+            &connection_id,
+            format!("Probe failed: {}", e),
+        )),
+        Err(_) => Ok(ConnectionSecurityInfo::unavailable(
+            &connection_id,
+            "Probe timed out".to_string(),
+        )),
+    }
+}
+
+/// Creates `~/.hushlogin` on the server so the login itself stays quiet.
+#[tauri::command]
+pub async fn connection_hushlogin(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (code, _, stderr) =
+        exec_remote_capture(&connection_id, "touch ~/.hushlogin", &state).await?;
+    if code != 0 {
+        return Err(crate::redact::error(format!(
+            "touch ~/.hushlogin failed: {}",
+            String::from_utf8_lossy(&stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Per-terminal and total scrollback caps in bytes: the connection's
+/// `scrollback_limit_mb`, else `terminal.scrollbackMemoryPerTerminalMB`, and
+/// `terminal.scrollbackMemoryTotalMB`.
+async fn scrollback_limits(
+    app: &AppHandle,
+    state: &AppState,
+    connection_id: &str,
+) -> (usize, usize) {
+    const MIB: u64 = 1024 * 1024;
+    let settings = read_effective_settings(app).unwrap_or_else(|_| serde_json::json!({}));
+    let setting_mb = |key: &str| {
+        settings
+            .pointer(&format!("/terminal/{}", key))
+            .and_then(Value::as_u64)
+            .filter(|mb| *mb > 0)
+    };
+    let connection_mb = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(connection_id)
+            .and_then(|conn| conn.config.scrollback_limit_mb)
+            .filter(|mb| *mb > 0)
+    };
+    let per_terminal = connection_mb
+        .or_else(|| setting_mb("scrollbackMemoryPerTerminalMB"))
+        .map(|mb| (mb * MIB) as usize)
+        .unwrap_or(crate::scrollback::DEFAULT_PER_TERMINAL_BYTES);
+    let total = setting_mb("scrollbackMemoryTotalMB")
+        .map(|mb| (mb * MIB) as usize)
+        .unwrap_or(crate::scrollback::DEFAULT_TOTAL_BYTES);
+    (per_terminal, total)
+}
+
 async fn reconnect_stored_connection(
     connection_id: &str,
     original_config: ConnectionConfig,
@@ -1853,7 +3224,11 @@ async fn reconnect_stored_connection(
         connections
             .get(connection_id)
             .map(|h| h.reconnect_lock.clone())
-            .ok_or_else(|| format!("Connection {connection_id} was disconnected during reconnect"))?
+            .ok_or_else(|| {
+                crate::redact::error(format!(
+                    "Connection {connection_id} was disconnected during reconnect"
+                ))
+            })?
     };
     let _reconnect_guard = reconnect_lock.clone().lock_owned().await;
 
@@ -1884,12 +3259,14 @@ async fn reconnect_stored_connection(
             match persist_result {
                 Ok(Ok(())) => {}
                 Ok(Err(error)) => {
-                    return Err(format!("Failed to persist relinked vault refs: {error}"))
+                    return Err(crate::redact::error(format!(
+                        "Failed to persist relinked vault refs: {error}"
+                    )))
                 }
                 Err(join_error) => {
-                    return Err(format!(
+                    return Err(crate::redact::error(format!(
                         "Failed to persist relinked vault refs: task join error: {join_error}"
-                    ))
+                    )))
                 }
             }
         }
@@ -1910,14 +3287,20 @@ async fn reconnect_stored_connection(
             // Preserve the *same* reconnect_lock Arc so any concurrent waiters on the old handle continue to serialize against this instance.
             new_handle.reconnect_lock = reconnect_lock.clone();
             connections.insert(connection_id.to_string(), new_handle);
+            state_sync::notify(
+                &state.app_handle,
+                StateKind::Connection,
+                connection_id,
+                ChangeKind::Updated,
+            );
             Ok(())
         }
-        Some(_) => Err(format!(
+        Some(_) => Err(crate::redact::error(format!(
             "Connection {connection_id} changed during reconnect"
-        )),
-        None => Err(format!(
+        ))),
+        None => Err(crate::redact::error(format!(
             "Connection {connection_id} was disconnected during reconnect"
-        )),
+        ))),
     }
 }
 
@@ -1963,45 +3346,491 @@ async fn open_ssh_channel_with_single_reconnect(
     state: &State<'_, AppState>,
 ) -> Result<Channel<Msg>, String> {
     let session = get_live_ssh_session(connection_id, state).await?;
+    open_channel_with_single_reconnect(connection_id, session, || async {
+        // First channel open failed; clear stale session and re-use centralized
+        // get_live_ssh_session() reconnect path.
+        {
+            let mut connections = state.connections.lock().await;
+            if let Some(conn) = connections.get_mut(connection_id) {
+                conn.session = None;
+            }
+        }
+        get_live_ssh_session(connection_id, state).await
+    })
+    .await
+}
+
+/// Opens a session channel on `session`. If that fails for any reason other
+/// than the server's channel limit, takes a fresh session from `reconnect` and
+/// tries once more.
+pub(crate) async fn open_channel_with_single_reconnect<F, Fut>(
+    connection_id: &str,
+    session: Arc<Mutex<russh::client::Handle<crate::ssh::Client>>>,
+    reconnect: F,
+) -> Result<Channel<Msg>, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<
+        Output = Result<Arc<Mutex<russh::client::Handle<crate::ssh::Client>>>, String>,
+    >,
+{
     let first_try = {
         let guard = session.lock().await;
-        guard.channel_open_session().await
+        crate::channel_budget::open_session(connection_id, &guard).await
     };
-    if let Ok(channel) = first_try {
-        return Ok(channel);
+    match first_try {
+        Ok(channel) => return Ok(channel),
+        // The session is fine, the server just will not take more channels.
+        Err(e) if e.is_limit() => return Err(e.to_string()),
+        Err(_) => {}
     }
 
-    // First channel open failed; clear stale session and re-use centralized
-    // get_live_ssh_session() reconnect path.
-    {
-        let mut connections = state.connections.lock().await;
-        if let Some(conn) = connections.get_mut(connection_id) {
-            conn.session = None;
-        }
-    }
-    let new_session = get_live_ssh_session(connection_id, state).await?;
+    let new_session = reconnect().await?;
     let guard = new_session.lock().await;
-    guard
-        .channel_open_session()
+    crate::channel_budget::open_session(connection_id, &guard)
         .await
-        .map_err(|e| format!("Channel open failed after reconnect: {}", e))
+        .map_err(|e| match e {
+            crate::channel_budget::ChannelOpenError::Limit(limit) => limit.to_error_string(),
+            e => format!("Channel open failed after reconnect: {}", e),
+        })
 }
 
-#[tauri::command]
-pub async fn terminal_close(term_id: String, state: State<'_, AppState>) -> Result<(), String> {
-    state
-        .pty_manager
-        .close(&term_id)
-        .await
-        .map_err(|e| e.to_string())
+/// Line that loads the OSC 133 snippet into a new remote shell, when the shell
+/// (the selected one, else the login shell from `$SHELL`) is bash, zsh or fish.
+async fn shell_integration_bootstrap(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    remote_os: Option<&str>,
+    shell: Option<&str>,
+) -> Option<String> {
+    use crate::shell_integration::{remote_bootstrap, IntegrationShell};
+    if remote_os.is_some_and(|os| os.eq_ignore_ascii_case("windows")) {
+        return None;
+    }
+    let shell = match shell
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "default")
+    {
+        Some(shell) => shell.to_string(),
+        None => {
+            let (_, stdout, _) = exec_remote_capture(connection_id, "printf %s \"$SHELL\"", state)
+                .await
+                .ok()?;
+            String::from_utf8_lossy(&stdout).trim().to_string()
+        }
+    };
+    IntegrationShell::detect(&shell).map(remote_bootstrap)
 }
 
-#[tauri::command]
-pub async fn terminal_has_active_processes(
-    term_id: String,
-    state: State<'_, AppState>,
-) -> Result<bool, String> {
-    Ok(state.pty_manager.has_active_child_processes(&term_id).await)
+/// For connections in mosh mode: starts `mosh-server` over the SSH session and
+/// locates the local client. `None` when the connection uses a plain SSH shell.
+async fn mosh_bootstrap(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+) -> Result<Option<crate::mosh::MoshClientCommand>, String> {
+    let config = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(connection_id)
+            .map(|handle| handle.config.clone())
+    };
+    let Some((config, options)) = config.and_then(|c| c.mosh.clone().map(|o| (c, o))) else {
+        return Ok(None);
+    };
+    if config.jump_host.is_some() {
+        return Err(
+            "Mosh needs direct UDP access to the server and cannot go through a jump host"
+                .to_string(),
+        );
+    }
+    let program = crate::mosh::client_program().map_err(crate::redact::error)?;
+    let (_, stdout, _) =
+        exec_remote_capture(connection_id, &crate::mosh::server_command(&options), state).await?;
+    let connect = crate::mosh::parse_connect(&String::from_utf8_lossy(&stdout))
+        .map_err(crate::redact::error)?;
+    // mosh-client only takes an IP; resolve the same way the SSH connection did.
+    let server = crate::dns::resolve_host(&config.host, connect.port, config.resolution.as_ref())
+        .await
+        .map_err(crate::redact::error)?
+        .addresses
+        .first()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| crate::redact::error(format!("No address found for {}", config.host)))?;
+    crate::log_info!(
+        "[SSH] mosh-server for {} listening on {}:{}",
+        connection_id,
+        server,
+        connect.port
+    );
+    Ok(Some(crate::mosh::MoshClientCommand::new(
+        program, server, connect,
+    )))
+}
+
+/// Attach-or-create command for connections with `persistent_session` set. Falls back
+/// to a plain shell (`None`) when the server has neither tmux nor screen.
+async fn persistent_session_launch(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    shell: Option<&str>,
+) -> Option<String> {
+    use crate::persistent_session as persistent;
+
+    let mode = {
+        let connections = state.connections.lock().await;
+        connections.get(connection_id)?.config.persistent_session?
+    };
+    if remote_path_style(state, connection_id).await == RemotePathStyle::Windows {
+        return None;
+    }
+    let detected = match exec_remote_capture(connection_id, persistent::DETECT_COMMAND, state).await
+    {
+        Ok((_, stdout, _)) => String::from_utf8_lossy(&stdout).to_string(),
+        Err(e) => {
+            crate::log_error!(
+                "[TERM] Multiplexer detection failed for {}: {}",
+                connection_id,
+                e
+            );
+            return None;
+        }
+    };
+    let Some(multiplexer) = persistent::choose(mode, &detected) else {
+        crate::log_error!(
+            "[TERM] {:?} persistent session requested for {} but not available; using a plain shell",
+            mode, connection_id
+        );
+        return None;
+    };
+    let shell = shell
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("default"));
+    Some(persistent::launch_command(
+        multiplexer,
+        &persistent::session_name(connection_id),
+        shell,
+    ))
+}
+
+/// Resolves `settings.terminal.sessionLogging` and the connection's own override.
+/// Logs go to `settings.logPath` when set, else `<data dir>/session-logs`.
+/// `logPath` from settings, else `<data>/session-logs`.
+fn session_log_dir(app: &AppHandle, settings: &Value) -> std::path::PathBuf {
+    settings
+        .get("logPath")
+        .and_then(Value::as_str)
+        .filter(|path| !path.trim().is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| get_data_dir(app).join("session-logs"))
+}
+
+async fn session_log_options(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    connection_id: &str,
+) -> Option<crate::session_log::SessionLogOptions> {
+    let settings = read_effective_settings(app).unwrap_or(Value::Null);
+    let (override_enabled, label) = if connection_id == "local" {
+        (None, "local".to_string())
+    } else {
+        let connections = state.connections.lock().await;
+        match connections.get(connection_id) {
+            Some(handle) => (handle.config.session_logging, handle.config.name.clone()),
+            None => (None, connection_id.to_string()),
+        }
+    };
+    let enabled = override_enabled.unwrap_or_else(|| {
+        settings
+            .pointer("/terminal/sessionLogging")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+    });
+    if !enabled {
+        return None;
+    }
+
+    let dir = session_log_dir(app, &settings);
+    let max_bytes = settings
+        .pointer("/terminal/sessionLogMaxMb")
+        .and_then(Value::as_u64)
+        .filter(|mb| *mb > 0)
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or(crate::session_log::DEFAULT_MAX_BYTES);
+    let max_files = settings
+        .pointer("/terminal/sessionLogMaxFiles")
+        .and_then(Value::as_u64)
+        .map(|n| n as usize)
+        .unwrap_or(crate::session_log::DEFAULT_MAX_FILES);
+    Some(crate::session_log::SessionLogOptions {
+        dir,
+        label,
+        max_bytes,
+        max_files,
+    })
+}
+
+/// Current log file of a terminal with session logging on, or `None`.
+#[tauri::command]
+pub async fn terminal_get_log_path(
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    Ok(state
+        .pty_manager
+        .session_log_path(&term_id)
+        .map(|path| path.to_string_lossy().to_string()))
+}
+
+/// Appends to the audit trail when `settings.audit.enabled` is on or session logging
+/// covers `event.connection_id`, pruning past `settings.audit.retentionDays` once a day.
+pub(crate) async fn audit_record(app: &AppHandle, event: crate::audit::AuditEvent) {
+    let settings = read_effective_settings(app).unwrap_or(Value::Null);
+    let enabled = settings
+        .pointer("/audit/enabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if !enabled {
+        let state = app.state::<AppState>();
+        if session_log_options(app, &state, &event.connection_id)
+            .await
+            .is_none()
+        {
+            return;
+        }
+    }
+    let dir = get_data_dir(app).join("audit");
+    if let Err(e) = crate::audit::append(&dir, &event) {
+        crate::log_error!("[AUDIT] Failed to record event: {}", e);
+    }
+    let retention_days = settings
+        .pointer("/audit/retentionDays")
+        .and_then(Value::as_u64)
+        .unwrap_or(crate::audit::DEFAULT_RETENTION_DAYS);
+    match crate::audit::prune_if_due(&dir, retention_days) {
+        Ok(0) => {}
+        Ok(dropped) => crate::log_info!("[AUDIT] Pruned {} events past retention", dropped),
+        Err(e) => crate::log_error!("[AUDIT] Failed to prune trail: {}", e),
+    }
+}
+
+/// Audit-trail events matching `filter`, newest first.
+#[tauri::command]
+pub async fn audit_query(
+    app: AppHandle,
+    filter: Option<crate::audit::AuditFilter>,
+) -> Result<Vec<crate::audit::AuditEvent>, String> {
+    let dir = get_data_dir(&app).join("audit");
+    let filter = filter.unwrap_or_default();
+    tokio::task::spawn_blocking(move || crate::audit::query(&dir, &filter))
+        .await
+        .map_err(crate::redact::error)?
+        .map_err(crate::redact::error)
+}
+
+/// Bundles session logs, the command trail and transfer manifests for
+/// `[from_ms, to_ms]` into a hashed (and optionally signed) zip at `destination`.
+#[tauri::command]
+pub async fn audit_export_bundle(
+    app: AppHandle,
+    from_ms: u64,
+    to_ms: u64,
+    connection_ids: Option<Vec<String>>,
+    destination: String,
+    signing_key_path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::audit::BundleResult, String> {
+    if from_ms > to_ms {
+        return Err("The start of the range is after its end".to_string());
+    }
+    let connection_ids = connection_ids.unwrap_or_default();
+    let mut log_labels = Vec::new();
+    if !connection_ids.is_empty() {
+        let saved = read_store_file(&get_data_dir(&app).join("connections.json"))
+            .ok()
+            .and_then(|data| serde_json::from_str::<SavedData>(&data).ok())
+            .map(|data| data.connections)
+            .unwrap_or_default();
+        let live = state.connections.lock().await;
+        for id in &connection_ids {
+            let name = if id == "local" {
+                Some("local".to_string())
+            } else {
+                live.get(id)
+                    .map(|handle| handle.config.name.clone())
+                    .or_else(|| saved.iter().find(|c| &c.id == id).map(|c| c.name.clone()))
+            };
+            // Unnamed connections log under their id (see `session_log_options`).
+            log_labels.push(crate::session_log::file_label(
+                name.as_deref().unwrap_or(id),
+            ));
+        }
+    }
+
+    let settings = read_effective_settings(&app)?;
+    let scope = crate::audit::BundleScope {
+        from_ms,
+        to_ms,
+        connection_ids,
+        log_labels,
+    };
+    let bundle = crate::audit::Bundle::collect(
+        &scope,
+        &session_log_dir(&app, &settings),
+        &get_data_dir(&app).join("audit"),
+    )
+    .map_err(crate::redact::error)?;
+    let signature = match signing_key_path {
+        Some(key) => Some(
+            bundle
+                .sign(&crate::key_rotation::expand_home(&key))
+                .await
+                .map_err(crate::redact::error)?,
+        ),
+        None => None,
+    };
+    let result = bundle
+        .write(std::path::Path::new(&destination), signature.as_deref())
+        .map_err(crate::redact::error)?;
+    crate::log_info!(
+        "[AUDIT] Exported {} files to {} (manifest {})",
+        result.files,
+        result.path,
+        result.manifest_sha256
+    );
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn terminal_close(
+    app: AppHandle,
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .pty_manager
+        .close(&term_id)
+        .await
+        .map_err(crate::redact::error)?;
+    state_sync::notify(&app, StateKind::Terminal, &term_id, ChangeKind::Removed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn terminal_has_active_processes(
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(state.pty_manager.has_active_child_processes(&term_id).await)
+}
+
+/// Working directory of a terminal (OSC 7 when the shell reports it, else the local
+/// shell process's cwd). `None` for remote shells that never reported one.
+#[tauri::command]
+pub async fn terminal_get_cwd(
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    state
+        .pty_manager
+        .cwd(&term_id)
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Lines recently entered in a terminal, newest first, for recall in the AI bar
+/// and command re-run. Input typed at password and passphrase prompts is never kept.
+#[tauri::command]
+pub async fn terminal_input_history(
+    term_id: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::input_history::InputLine>, String> {
+    state
+        .pty_manager
+        .input_history(&term_id, limit.unwrap_or(100))
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// The terminal's backend scrollback, newest `max_bytes` at most. With
+/// `include_spilled`, output evicted to the spill file is included as well.
+#[tauri::command]
+pub async fn terminal_get_scrollback(
+    term_id: String,
+    max_bytes: Option<usize>,
+    include_spilled: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::scrollback::ScrollbackSnapshot, String> {
+    state
+        .pty_manager
+        .scrollback(&term_id, max_bytes, include_spilled.unwrap_or(false))
+        .ok_or_else(|| "Terminal session not found".to_string())
+}
+
+#[tauri::command]
+pub async fn terminal_scrollback_stats(
+    state: State<'_, AppState>,
+) -> Result<crate::scrollback::ScrollbackStats, String> {
+    Ok(state.pty_manager.scrollback_stats())
+}
+
+/// Starts an asciicast v2 recording under `<data dir>/recordings`; returns where it is written.
+#[tauri::command]
+pub async fn terminal_record_start(
+    app: AppHandle,
+    term_id: String,
+    capture_input: Option<bool>,
+    title: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::recording::RecordingInfo, String> {
+    let dir = get_data_dir(&app).join("recordings");
+    state
+        .pty_manager
+        .start_recording(
+            &term_id,
+            &dir,
+            title.as_deref(),
+            capture_input.unwrap_or(false),
+        )
+        .await
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn terminal_record_stop(
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::recording::RecordingInfo, String> {
+    state
+        .pty_manager
+        .stop_recording(&term_id)
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn terminal_record_status(
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<crate::recording::RecordingInfo>, String> {
+    Ok(state.pty_manager.recording_status(&term_id))
+}
+
+/// Copies a finished recording to a user-chosen `.cast` destination.
+#[tauri::command]
+pub async fn terminal_record_export(
+    source_path: String,
+    destination: String,
+) -> Result<(), String> {
+    let destination = if destination.ends_with(".cast") {
+        destination
+    } else {
+        format!("{}.cast", destination)
+    };
+    crate::recording::export(
+        std::path::Path::new(&source_path),
+        std::path::Path::new(&destination),
+    )
+    .map_err(crate::redact::error)
 }
 
 // Helper to get SFTP session - reconnects automatically if session is dead.
@@ -2013,18 +3842,27 @@ async fn get_sftp_or_reconnect(
     // 1. Try to get existing SFTP session
     let config = {
         let connections = state.connections.lock().await;
-        let conn = connections
-            .get(id)
-            .ok_or_else(|| format!("Connection {} not found, cannot reconnect for SFTP", id))?;
+        let conn = connections.get(id).ok_or_else(|| {
+            crate::redact::error(format!(
+                "Connection {} not found, cannot reconnect for SFTP",
+                id
+            ))
+        })?;
 
         if let Some(sftp) = &conn.sftp_session {
             return Ok(sftp.clone());
         }
+        if conn.sftp_unavailable {
+            return Err(crate::redact::error(format!(
+                "SFTP_UNAVAILABLE: '{}' has no SFTP subsystem; only single-file operations work over SCP",
+                conn.config.name
+            )));
+        }
         conn.config.clone()
     };
 
     // 2. Session dropped — attempt full reconnect
-    println!(
+    crate::log_info!(
         "[SFTP] Session not found for '{}', attempting reconnect...",
         id
     );
@@ -2037,12 +3875,17 @@ async fn get_sftp_or_reconnect(
     .await
     {
         Ok(Ok(())) => {}
-        Ok(Err(e)) => return Err(format!("DISCONNECTED: Auto-reconnect failed: {}", e)),
+        Ok(Err(e)) => {
+            return Err(crate::redact::error(format!(
+                "DISCONNECTED: Auto-reconnect failed: {}",
+                e
+            )))
+        }
         Err(_) => {
-            return Err(format!(
+            return Err(crate::redact::error(format!(
                 "DISCONNECTED: Auto-reconnect timed out after {}s (Is the network down?)",
                 timeout_duration.as_secs()
-            ))
+            )))
         }
     };
     let sftp = {
@@ -2053,23 +3896,51 @@ async fn get_sftp_or_reconnect(
     }
     .ok_or_else(|| "Reconnection succeeded but SFTP initialization failed".to_string())?;
 
-    println!("[SFTP] Reconnected successfully for '{}'", id);
+    crate::log_info!("[SFTP] Reconnected successfully for '{}'", id);
     Ok(sftp)
 }
 
+/// True when `connection_id` is a live connection whose server has no SFTP
+/// subsystem, so file operations must go over SCP exec channels.
+async fn uses_scp_fallback(state: &AppState, connection_id: &str) -> bool {
+    let connections = state.connections.lock().await;
+    connections
+        .get(connection_id)
+        .map(|c| c.sftp_unavailable && c.session.is_some())
+        .unwrap_or(false)
+}
+
 #[tauri::command]
 pub async fn fs_list(
     connection_id: String,
     path: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<FileEntry>, String> {
+    let path = if connection_id == "local" {
+        path
+    } else {
+        remote_path_style(&state, &connection_id)
+            .await
+            .to_sftp(&path)
+    };
     if connection_id == "local" {
         state
             .file_system
             .list_local(&path)
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
+    } else if uses_scp_fallback(&state, &connection_id).await {
+        let channel = open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+        state
+            .file_system
+            .list_scp(channel, &path)
+            .await
+            .map_err(crate::redact::error)
     } else {
         let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+        if let Some(res) = crate::listing_cache::take(&sftp, &connection_id, &path).await {
+            crate::listing_cache::spawn_prefetch(sftp, &connection_id, &res);
+            return Ok(res);
+        }
 
         let timeout_duration = std::time::Duration::from_secs(10);
         match tokio::time::timeout(
@@ -2078,9 +3949,12 @@ pub async fn fs_list(
         )
         .await
         {
-            Ok(Ok(res)) => Ok(res),
+            Ok(Ok(res)) => {
+                crate::listing_cache::spawn_prefetch(sftp.clone(), &connection_id, &res);
+                Ok(res)
+            }
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during list, retrying...");
+                crate::log_info!("[FS] SFTP session closed during list, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -2096,10 +3970,10 @@ pub async fn fs_list(
                 {
                     Ok(Ok(res)) => Ok(res),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP listing timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
@@ -2110,10 +3984,10 @@ pub async fn fs_list(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP listing timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -2153,6 +4027,16 @@ pub(crate) async fn read_remote_connection_file(
     path: &str,
     timeout_secs: u64,
 ) -> Result<String, String> {
+    let content = read_remote_connection_bytes(state, connection_id, path, timeout_secs).await?;
+    Ok(String::from_utf8_lossy(&content).to_string())
+}
+
+async fn read_remote_connection_bytes(
+    state: &AppState,
+    connection_id: &str,
+    path: &str,
+    timeout_secs: u64,
+) -> Result<Vec<u8>, String> {
     let sftp = get_sftp_or_reconnect(state, connection_id).await?;
     let timeout_duration = std::time::Duration::from_secs(timeout_secs);
 
@@ -2164,7 +4048,7 @@ pub(crate) async fn read_remote_connection_file(
     {
         Ok(Ok(res)) => Ok(res),
         Ok(Err(e)) if sftp_error_is_dead_session(&e) => {
-            println!("[FS] SFTP session closed during read, retrying...");
+            crate::log_info!("[FS] SFTP session closed during read, retrying...");
             {
                 let mut connections = state.connections.lock().await;
                 if let Some(c) = connections.get_mut(connection_id) {
@@ -2180,17 +4064,17 @@ pub(crate) async fn read_remote_connection_file(
             {
                 Ok(Ok(res)) => Ok(res),
                 Ok(Err(e)) => Err(e.to_string()),
-                Err(_) => Err(format!(
+                Err(_) => Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP read timed out after {}s",
                     timeout_duration.as_secs()
-                )),
+                ))),
             }
         }
         Ok(Err(e)) => Err(e.to_string()),
-        Err(_) => Err(format!(
+        Err(_) => Err(crate::redact::error(format!(
             "DISCONNECTED: SFTP read timed out after {}s",
             timeout_duration.as_secs()
-        )),
+        ))),
     }
 }
 
@@ -2199,16 +4083,38 @@ pub async fn fs_read_file(
     connection_id: String,
     path: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    if connection_id == "local" {
+) -> Result<crate::text_encoding::TextFile, String> {
+    read_connection_text(&state, &connection_id, path).await
+}
+
+/// Reads and decodes a text file over SFTP (SCP where SFTP is unavailable), or locally.
+async fn read_connection_text(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: String,
+) -> Result<crate::text_encoding::TextFile, String> {
+    let path = if connection_id == "local" {
+        path
+    } else {
+        remote_path_style(state, connection_id).await.to_sftp(&path)
+    };
+    let content = if connection_id == "local" {
+        state
+            .file_system
+            .read_file_bytes(connection_id, &path)
+            .await
+            .map_err(crate::redact::error)?
+    } else if uses_scp_fallback(state, connection_id).await {
+        let channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
         state
             .file_system
-            .read_file(&connection_id, &path)
+            .read_scp(channel, &path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)?
     } else {
-        read_remote_connection_file(&state, &connection_id, &path, 10).await
-    }
+        read_remote_connection_bytes(state, connection_id, &path, 10).await?
+    };
+    Ok(crate::text_encoding::decode(&content))
 }
 
 #[tauri::command]
@@ -2216,98 +4122,273 @@ pub async fn fs_write_file(
     connection_id: String,
     path: String,
     content: String,
+    format: Option<crate::text_encoding::TextFormat>,
+    snapshot: Option<bool>,
     state: State<'_, AppState>,
+) -> Result<(), String> {
+    write_connection_text(&state, &connection_id, path, content, format, snapshot).await
+}
+
+/// Encodes and writes a text file on any connection, optionally keeping a version first.
+async fn write_connection_text(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: String,
+    content: String,
+    format: Option<crate::text_encoding::TextFormat>,
+    snapshot: Option<bool>,
+) -> Result<(), String> {
+    // Without a format (callers that never read the file) the text is written as UTF-8.
+    let content = match format {
+        Some(format) => {
+            crate::text_encoding::encode(&content, &format).map_err(crate::redact::error)?
+        }
+        None => content.into_bytes(),
+    };
+    let path = if connection_id == "local" {
+        path
+    } else {
+        remote_path_style(state, connection_id).await.to_sftp(&path)
+    };
+    if snapshot.unwrap_or(false) {
+        snapshot_before_write(state, connection_id, &path).await?;
+    }
+    write_connection_bytes(state, connection_id, &path, &content).await
+}
+
+/// Writes `content` to `path` (in SFTP form for remote connections).
+async fn write_connection_bytes(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: &str,
+    content: &[u8],
 ) -> Result<(), String> {
     if connection_id == "local" {
         state
             .file_system
-            .write_file(&connection_id, &path, &content)
+            .write_file(connection_id, path, content)
+            .await
+            .map_err(crate::redact::error)
+    } else if uses_scp_fallback(state, connection_id).await {
+        let channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+        state
+            .file_system
+            .write_scp(channel, path, content, 0o644)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
-        let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+        let sftp = get_sftp_or_reconnect(state, connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
 
         match tokio::time::timeout(
             timeout_duration,
-            state
-                .file_system
-                .write_remote(&sftp, &path, content.as_bytes()),
+            state.file_system.write_remote(&sftp, path, content),
         )
         .await
         {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during write, retrying...");
+                crate::log_info!("[FS] SFTP session closed during write, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
-                    if let Some(c) = connections.get_mut(&connection_id) {
+                    if let Some(c) = connections.get_mut(connection_id) {
                         c.sftp_session = None;
                     }
                 }
-                let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+                let sftp = get_sftp_or_reconnect(state, connection_id).await?;
                 match tokio::time::timeout(
                     timeout_duration,
-                    state
-                        .file_system
-                        .write_remote(&sftp, &path, content.as_bytes()),
+                    state.file_system.write_remote(&sftp, path, content),
                 )
                 .await
                 {
                     Ok(Ok(_)) => Ok(()),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP write timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
             Err(_) => {
                 {
                     let mut connections = state.connections.lock().await;
-                    if let Some(c) = connections.get_mut(&connection_id) {
+                    if let Some(c) = connections.get_mut(connection_id) {
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP write timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
 }
 
-#[tauri::command]
-pub async fn fs_cwd(connection_id: String, state: State<'_, AppState>) -> Result<String, String> {
+/// Current content of `path` (in SFTP form for remote connections), `None`
+/// when it does not exist.
+async fn read_existing_bytes(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: &str,
+) -> Result<Option<Vec<u8>>, String> {
     if connection_id == "local" {
-        state
+        if !std::path::Path::new(path).exists() {
+            return Ok(None);
+        }
+        return state
             .file_system
-            .get_home_dir(&connection_id)
-            .map_err(|e| e.to_string())
-    } else {
-        let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
-        let timeout_duration = std::time::Duration::from_secs(10);
-
-        match tokio::time::timeout(timeout_duration, sftp.canonicalize(".")).await {
-            Ok(Ok(path)) => Ok(path),
-            Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during cwd, retrying...");
-                {
-                    let mut connections = state.connections.lock().await;
-                    if let Some(c) = connections.get_mut(&connection_id) {
-                        c.sftp_session = None;
+            .read_file_bytes(connection_id, path)
+            .await
+            .map(Some)
+            .map_err(crate::redact::error);
+    }
+    if uses_scp_fallback(state, connection_id).await {
+        let channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+        return match state.file_system.read_scp(channel, path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.to_string().contains("No such file") => Ok(None),
+            Err(e) => Err(e.to_string()),
+        };
+    }
+    let sftp = get_sftp_or_reconnect(state, connection_id).await?;
+    let exists = tokio::time::timeout(
+        Duration::from_secs(10),
+        state.file_system.exists_remote(&sftp, path),
+    )
+    .await
+    .map_err(|_| "DISCONNECTED: SFTP session timeout".to_string())?
+    .map_err(crate::redact::error)?;
+    if !exists {
+        return Ok(None);
+    }
+    read_remote_connection_bytes(state, connection_id, path, 10)
+        .await
+        .map(Some)
+}
+
+/// Stores the current content of `path` as a version before it is
+/// overwritten; a file that does not exist yet has nothing to keep.
+async fn snapshot_before_write(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: &str,
+) -> Result<(), String> {
+    let Some(current) = read_existing_bytes(state, connection_id, path).await? else {
+        return Ok(());
+    };
+    let store = crate::file_versions::VersionStore::new(&get_data_dir(&state.app_handle));
+    store
+        .snapshot(connection_id, path, &current)
+        .map(|_| ())
+        .map_err(|e| crate::redact::error(format!("Snapshot failed, file not saved: {}", e)))
+}
+
+async fn version_path(state: &AppState, connection_id: &str, path: String) -> String {
+    if connection_id == "local" {
+        path
+    } else {
+        remote_path_style(state, connection_id).await.to_sftp(&path)
+    }
+}
+
+/// Snapshots of `path` taken by `fs_write_file` with `snapshot`, newest first.
+#[tauri::command]
+pub async fn fs_versions(
+    connection_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::file_versions::FileVersion>, String> {
+    let path = version_path(&state, &connection_id, path).await;
+    let store = crate::file_versions::VersionStore::new(&get_data_dir(&state.app_handle));
+    Ok(store.list(&connection_id, &path))
+}
+
+#[tauri::command]
+pub async fn fs_version_read(
+    connection_id: String,
+    path: String,
+    version_id: String,
+    state: State<'_, AppState>,
+) -> Result<crate::text_encoding::TextFile, String> {
+    let path = version_path(&state, &connection_id, path).await;
+    let store = crate::file_versions::VersionStore::new(&get_data_dir(&state.app_handle));
+    let content = store
+        .read(&connection_id, &path, &version_id)
+        .map_err(crate::redact::error)?;
+    Ok(crate::text_encoding::decode(&content))
+}
+
+/// Writes a snapshot back over `path`. The content being replaced is
+/// snapshotted first, so a restore can itself be undone. Returns the
+/// updated version list.
+#[tauri::command]
+pub async fn fs_version_restore(
+    connection_id: String,
+    path: String,
+    version_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::file_versions::FileVersion>, String> {
+    let path = version_path(&state, &connection_id, path).await;
+    let store = crate::file_versions::VersionStore::new(&get_data_dir(&state.app_handle));
+    let content = store
+        .read(&connection_id, &path, &version_id)
+        .map_err(crate::redact::error)?;
+    snapshot_before_write(&state, &connection_id, &path).await?;
+    write_connection_bytes(&state, &connection_id, &path, &content).await?;
+    Ok(store.list(&connection_id, &path))
+}
+
+/// The SFTP start directory in SFTP form. Windows servers that start outside any
+/// drive are asked for the user's profile directory instead.
+async fn remote_start_dir(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    reported: String,
+) -> String {
+    let style = remote_path_style(state, connection_id).await;
+    if !style.needs_home_lookup(&reported) {
+        return style.to_sftp(&reported);
+    }
+    match exec_remote_capture(connection_id, &style.home_command(), state).await {
+        Ok((0, stdout, _)) if !String::from_utf8_lossy(&stdout).trim().is_empty() => {
+            style.to_sftp(String::from_utf8_lossy(&stdout).trim())
+        }
+        _ => reported,
+    }
+}
+
+#[tauri::command]
+pub async fn fs_cwd(connection_id: String, state: State<'_, AppState>) -> Result<String, String> {
+    if connection_id == "local" {
+        state
+            .file_system
+            .get_home_dir(&connection_id)
+            .map_err(crate::redact::error)
+    } else {
+        let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+        let timeout_duration = std::time::Duration::from_secs(10);
+
+        match tokio::time::timeout(timeout_duration, sftp.canonicalize(".")).await {
+            Ok(Ok(path)) => Ok(remote_start_dir(&state, &connection_id, path).await),
+            Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
+                crate::log_info!("[FS] SFTP session closed during cwd, retrying...");
+                {
+                    let mut connections = state.connections.lock().await;
+                    if let Some(c) = connections.get_mut(&connection_id) {
+                        c.sftp_session = None;
                     }
                 }
                 let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
                 match tokio::time::timeout(timeout_duration, sftp.canonicalize(".")).await {
-                    Ok(Ok(path)) => Ok(path),
+                    Ok(Ok(path)) => Ok(remote_start_dir(&state, &connection_id, path).await),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP cwd timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
@@ -2318,10 +4399,10 @@ pub async fn fs_cwd(connection_id: String, state: State<'_, AppState>) -> Result
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP cwd timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -2359,11 +4440,16 @@ async fn read_wsl_zsh_init_files_impl(wsl_distro: Option<String>) -> Result<Stri
     let timeout_duration = std::time::Duration::from_secs(8);
     let child = cmd
         .spawn()
-        .map_err(|e| format!("Failed to run WSL probe: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to run WSL probe: {}", e)))?;
     let child_pid = child.id();
     let output = match tokio::time::timeout(timeout_duration, child.wait_with_output()).await {
         Ok(Ok(output)) => output,
-        Ok(Err(e)) => return Err(format!("Failed to run WSL probe: {}", e)),
+        Ok(Err(e)) => {
+            return Err(crate::redact::error(format!(
+                "Failed to run WSL probe: {}",
+                e
+            )))
+        }
         Err(_) => {
             if let Some(pid) = child_pid {
                 let _ = Command::new("taskkill")
@@ -2373,7 +4459,7 @@ async fn read_wsl_zsh_init_files_impl(wsl_distro: Option<String>) -> Result<Stri
                     .status()
                     .await;
             }
-            eprintln!(
+            crate::log_error!(
                 "[WSL] zsh init probe timed out after {}s",
                 timeout_duration.as_secs()
             );
@@ -2429,13 +4515,13 @@ async fn wsl_get_cwd_impl(wsl_distro: Option<String>) -> Result<String, String>
     let output = cmd
         .output()
         .await
-        .map_err(|e| format!("Failed to read WSL cwd: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to read WSL cwd: {}", e)))?;
 
     if !output.status.success() {
-        return Err(format!(
+        return Err(crate::redact::error(format!(
             "WSL cwd failed: {}",
             String::from_utf8_lossy(&output.stderr).trim()
-        ));
+        )));
     }
 
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
@@ -2485,13 +4571,15 @@ async fn fs_list_wsl_impl(wsl_distro: Option<String>, path: String) -> Result<Ve
     let output = cmd
         .output()
         .await
-        .map_err(|e| format!("Failed to list WSL directory: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to list WSL directory: {}", e)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
         let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
         let detail = if stderr.is_empty() { stdout } else { stderr };
-        return Err(format!("WSL list failed for {path:?}: {detail}"));
+        return Err(crate::redact::error(format!(
+            "WSL list failed for {path:?}: {detail}"
+        )));
     }
 
     Ok(parse_wsl_ls_listing(&String::from_utf8_lossy(&output.stdout)))
@@ -2512,7 +4600,7 @@ pub(crate) async fn ghost_fs_list(
         state
             .file_system
             .list_local(path)
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let sftp = get_sftp_or_reconnect(state, connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
@@ -2524,7 +4612,7 @@ pub(crate) async fn ghost_fs_list(
         {
             Ok(Ok(res)) => Ok(res),
             Ok(Err(e)) if sftp_error_is_dead_session(&e) => {
-                println!("[FS] SFTP session closed during list, retrying...");
+                crate::log_info!("[FS] SFTP session closed during list, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(connection_id) {
@@ -2540,17 +4628,17 @@ pub(crate) async fn ghost_fs_list(
                 {
                     Ok(Ok(res)) => Ok(res),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP listing timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
-            Err(_) => Err(format!(
+            Err(_) => Err(crate::redact::error(format!(
                 "DISCONNECTED: SFTP listing timed out after {}s",
                 timeout_duration.as_secs()
-            )),
+            ))),
         }
     }
 }
@@ -2560,17 +4648,17 @@ pub(crate) async fn ghost_fs_cwd(state: &AppState, connection_id: &str) -> Resul
         state
             .file_system
             .get_home_dir(connection_id)
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let sftp = get_sftp_or_reconnect(state, connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
         match tokio::time::timeout(timeout_duration, sftp.canonicalize(".")).await {
             Ok(Ok(path)) => Ok(path),
             Ok(Err(e)) => Err(e.to_string()),
-            Err(_) => Err(format!(
+            Err(_) => Err(crate::redact::error(format!(
                 "DISCONNECTED: SFTP cwd timed out after {}s",
                 timeout_duration.as_secs()
-            )),
+            ))),
         }
     }
 }
@@ -2614,6 +4702,10 @@ fn parse_wsl_ls_listing(stdout: &str) -> Vec<FileEntry> {
             size: 0,
             last_modified: 0,
             permissions: String::new(),
+            owner: None,
+            group: None,
+            link_target: None,
+            link_target_type: None,
         });
     }
 
@@ -2658,44 +4750,44 @@ pub async fn fs_touch(
 ) -> Result<(), String> {
     if connection_id == "local" {
         if let Ok(true) = state.file_system.exists(&connection_id, &path).await {
-            return Err(format!(
+            return Err(crate::redact::error(format!(
                 "An item with the name '{}' already exists in this directory.",
                 std::path::Path::new(&path)
                     .file_name()
                     .unwrap_or_default()
                     .to_string_lossy()
-            ));
+            )));
         }
         state
             .file_system
             .create_file(&connection_id, &path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let mut sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
 
         let touch_fut = async {
             if let Ok(true) = state.file_system.exists_remote(&sftp, &path).await {
-                return Err(format!(
+                return Err(crate::redact::error(format!(
                     "An item with the name '{}' already exists in this directory.",
                     std::path::Path::new(&path)
                         .file_name()
                         .unwrap_or_default()
                         .to_string_lossy()
-                ));
+                )));
             }
             state
                 .file_system
                 .create_file_remote(&sftp, &path)
                 .await
-                .map_err(|e| e.to_string())
+                .map_err(crate::redact::error)
         };
 
         match tokio::time::timeout(timeout_duration, touch_fut).await {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during touch, retrying...");
+                crate::log_info!("[FS] SFTP session closed during touch, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -2713,7 +4805,7 @@ pub async fn fs_touch(
                         .file_system
                         .create_file_remote(&sftp, &path)
                         .await
-                        .map_err(|e| e.to_string())
+                        .map_err(crate::redact::error)
                 };
 
                 match tokio::time::timeout(timeout_duration, retry_fut).await {
@@ -2726,10 +4818,10 @@ pub async fn fs_touch(
                                 c.sftp_session = None;
                             }
                         }
-                        Err(format!(
+                        Err(crate::redact::error(format!(
                             "DISCONNECTED: SFTP touch timed out after {}s",
                             timeout_duration.as_secs()
-                        ))
+                        )))
                     }
                 }
             }
@@ -2741,10 +4833,10 @@ pub async fn fs_touch(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP touch timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -2758,44 +4850,44 @@ pub async fn fs_mkdir(
 ) -> Result<(), String> {
     if connection_id == "local" {
         if let Ok(true) = state.file_system.exists(&connection_id, &path).await {
-            return Err(format!(
+            return Err(crate::redact::error(format!(
                 "An item with the name '{}' already exists in this directory.",
                 std::path::Path::new(&path)
                     .file_name()
                     .unwrap_or_default()
                     .to_string_lossy()
-            ));
+            )));
         }
         state
             .file_system
             .create_dir(&connection_id, &path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let mut sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
 
         let mkdir_fut = async {
             if let Ok(true) = state.file_system.exists_remote(&sftp, &path).await {
-                return Err(format!(
+                return Err(crate::redact::error(format!(
                     "An item with the name '{}' already exists in this directory.",
                     std::path::Path::new(&path)
                         .file_name()
                         .unwrap_or_default()
                         .to_string_lossy()
-                ));
+                )));
             }
             state
                 .file_system
                 .create_dir_remote(&sftp, &path)
                 .await
-                .map_err(|e| e.to_string())
+                .map_err(crate::redact::error)
         };
 
         match tokio::time::timeout(timeout_duration, mkdir_fut).await {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during mkdir, retrying...");
+                crate::log_info!("[FS] SFTP session closed during mkdir, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -2813,7 +4905,7 @@ pub async fn fs_mkdir(
                         .file_system
                         .create_dir_remote(&sftp, &path)
                         .await
-                        .map_err(|e| e.to_string())
+                        .map_err(crate::redact::error)
                 };
 
                 match tokio::time::timeout(timeout_duration, retry_fut).await {
@@ -2826,10 +4918,10 @@ pub async fn fs_mkdir(
                                 c.sftp_session = None;
                             }
                         }
-                        Err(format!(
+                        Err(crate::redact::error(format!(
                             "DISCONNECTED: SFTP mkdir timed out after {}s",
                             timeout_duration.as_secs()
-                        ))
+                        )))
                     }
                 }
             }
@@ -2841,10 +4933,10 @@ pub async fn fs_mkdir(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP mkdir timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -2856,6 +4948,7 @@ pub async fn fs_rename(
     old_path: String,
     mut new_path: String,
     auto_rename: Option<bool>,
+    overwrite: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     if connection_id == "local" {
@@ -2893,7 +4986,7 @@ pub async fn fs_rename(
             .file_system
             .rename(&connection_id, &old_path, &new_path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let mut sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
@@ -2910,19 +5003,23 @@ pub async fn fs_rename(
                     new_path = unique_path;
                 }
                 Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                    println!("[FS] SFTP session closed during name check, retrying...");
+                    crate::log_info!("[FS] SFTP session closed during name check, retrying...");
                     sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
                     new_path = tokio::time::timeout(
                         timeout_duration,
                         state.file_system.get_unique_path_remote(&sftp, &new_path),
                     )
                     .await
-                    .map_err(|e| format!("Timeout generating unique path: {}", e))?
-                    .map_err(|e| e.to_string())?;
+                    .map_err(|e| {
+                        crate::redact::error(format!("Timeout generating unique path: {}", e))
+                    })?
+                    .map_err(crate::redact::error)?;
                 }
                 Ok(Err(e)) => return Err(e.to_string()),
                 Err(_) => return Err("Timeout generating unique path".to_string()),
             }
+        } else if overwrite.unwrap_or(false) {
+            return remote_rename_overwrite(&state, &connection_id, &old_path, &new_path).await;
         }
 
         match tokio::time::timeout(
@@ -2933,7 +5030,7 @@ pub async fn fs_rename(
         {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during rename, retrying...");
+                crate::log_info!("[FS] SFTP session closed during rename, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -2949,10 +5046,10 @@ pub async fn fs_rename(
                 {
                     Ok(Ok(_)) => Ok(()),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP rename timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
@@ -2963,10 +5060,10 @@ pub async fn fs_rename(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP rename timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -2974,16 +5071,35 @@ pub async fn fs_rename(
 
 #[tauri::command]
 pub async fn fs_delete(
+    app: AppHandle,
     connection_id: String,
     path: String,
+    soft: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    delete_path(&app, connection_id.clone(), path.clone(), soft, &state).await?;
+    audit_delete(&app, &connection_id, std::slice::from_ref(&path), soft).await;
+    Ok(())
+}
+
+async fn delete_path(
+    app: &AppHandle,
+    connection_id: String,
+    path: String,
+    soft: Option<bool>,
+    state: &State<'_, AppState>,
+) -> Result<(), String> {
+    if soft.unwrap_or(false) {
+        return move_to_trash(app, state, &connection_id, std::slice::from_ref(&path))
+            .await
+            .map(|_| ());
+    }
     if connection_id == "local" {
         state
             .file_system
             .delete(&connection_id, &path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         // Optimization: Try server-side delete first (rm -rf) to avoid recursive SFTP calls
         let (session_opt, should_optimize) = {
@@ -2995,14 +5111,21 @@ pub async fn fs_delete(
             )
         };
 
+        let style = remote_path_style(state, &connection_id).await;
+        let path = style.to_sftp(&path);
         if should_optimize {
             if let Some(session) = session_opt {
-                let cmd = format!("rm -rf {}", shell_quote(&path));
-                println!("[FS] Attempting server-side delete: {}", cmd);
+                let cmd = style.remove_command(std::slice::from_ref(&path));
+                crate::log_info!("[FS] Attempting server-side delete: {}", cmd);
 
                 let timeout_duration = std::time::Duration::from_secs(10);
                 let optimize_fut = async {
-                    match session.lock().await.channel_open_session().await {
+                    match crate::channel_budget::open_session(
+                        &connection_id,
+                        &*session.lock().await,
+                    )
+                    .await
+                    {
                         Ok(mut channel) => {
                             if channel.exec(true, cmd).await.is_ok() {
                                 let mut success = false;
@@ -3035,10 +5158,10 @@ pub async fn fs_delete(
 
                 match tokio::time::timeout(timeout_duration, optimize_fut).await {
                     Ok(true) => {
-                        println!("[FS] Server-side delete successful.");
+                        crate::log_info!("[FS] Server-side delete successful.");
                         return Ok(());
                     }
-                    _ => println!(
+                    _ => crate::log_info!(
                         "[FS] Server-side delete failed or timed out. Checking SFTP fallback..."
                     ),
                 }
@@ -3046,8 +5169,8 @@ pub async fn fs_delete(
         }
 
         // Fallback to SFTP (recursive delete implemented there)
-        println!("[FS] Falling back to SFTP delete...");
-        let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+        crate::log_info!("[FS] Falling back to SFTP delete...");
+        let sftp = get_sftp_or_reconnect(state, &connection_id).await?;
         let timeout_duration = std::time::Duration::from_secs(10);
 
         match tokio::time::timeout(
@@ -3058,14 +5181,14 @@ pub async fn fs_delete(
         {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during delete, retrying...");
+                crate::log_info!("[FS] SFTP session closed during delete, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
                         c.sftp_session = None;
                     }
                 }
-                let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+                let sftp = get_sftp_or_reconnect(state, &connection_id).await?;
                 match tokio::time::timeout(
                     timeout_duration,
                     state.file_system.delete_remote(&sftp, &path),
@@ -3074,10 +5197,10 @@ pub async fn fs_delete(
                 {
                     Ok(Ok(_)) => Ok(()),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP delete timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
@@ -3088,13 +5211,145 @@ pub async fn fs_delete(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP delete timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
+            }
+        }
+    }
+}
+
+// ─── Trash ────────────────────────────────────────────────────────────────────
+
+/// Trash retention from `settings.fileManager.trashRetentionDays` (0 keeps items forever).
+fn trash_retention_secs(app: &AppHandle) -> Option<u64> {
+    let days = read_effective_settings(app)
+        .ok()
+        .and_then(|settings| {
+            settings
+                .pointer("/fileManager/trashRetentionDays")
+                .and_then(Value::as_u64)
+        })
+        .unwrap_or(crate::trash::DEFAULT_RETENTION_DAYS);
+    (days > 0).then_some(days * 86_400)
+}
+
+/// Soft-deletes `paths`, then purges anything past the retention window.
+async fn move_to_trash(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    paths: &[String],
+) -> Result<Vec<crate::trash::TrashEntry>, String> {
+    let retention = trash_retention_secs(app);
+
+    if connection_id == "local" {
+        let paths = paths.to_vec();
+        return tokio::task::spawn_blocking(move || {
+            let trash =
+                crate::trash::LocalTrash::for_current_user().map_err(crate::redact::error)?;
+            let mut entries = Vec::new();
+            for path in &paths {
+                entries.push(trash.trash(path).map_err(crate::redact::error)?);
+            }
+            if let Some(retention) = retention {
+                if let Err(e) = trash.purge_expired(retention) {
+                    crate::log_error!("[FS] Trash auto-purge failed: {}", e);
+                }
             }
+            Ok(entries)
+        })
+        .await
+        .map_err(crate::redact::error)?;
+    }
+
+    let sftp = get_sftp_or_reconnect(state, connection_id).await?;
+    let trash = crate::trash::RemoteTrash::open(&sftp)
+        .await
+        .map_err(crate::redact::error)?;
+    let mut entries = Vec::new();
+    for path in paths {
+        entries.push(trash.trash(path).await.map_err(crate::redact::error)?);
+    }
+    if let Some(retention) = retention {
+        if let Err(e) = trash.empty(Some(retention)).await {
+            crate::log_error!("[FS] Remote trash auto-purge failed: {}", e);
         }
     }
+    Ok(entries)
+}
+
+#[tauri::command]
+pub async fn fs_trash_list(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::trash::TrashEntry>, String> {
+    if connection_id == "local" {
+        return tokio::task::spawn_blocking(|| {
+            crate::trash::LocalTrash::for_current_user()?.list()
+        })
+        .await
+        .map_err(crate::redact::error)?
+        .map_err(crate::redact::error);
+    }
+
+    with_sftp_retry(&state, &connection_id, "trash list", |sftp| async move {
+        let trash = crate::trash::RemoteTrash::open(&sftp)
+            .await
+            .map_err(crate::redact::error)?;
+        trash.list().await.map_err(crate::redact::error)
+    })
+    .await
+}
+
+/// Restores a trashed item to its original location and returns that path.
+#[tauri::command]
+pub async fn fs_restore(
+    connection_id: String,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    if connection_id == "local" {
+        return tokio::task::spawn_blocking(move || {
+            crate::trash::LocalTrash::for_current_user()?.restore(&id)
+        })
+        .await
+        .map_err(crate::redact::error)?
+        .map_err(crate::redact::error);
+    }
+
+    let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+    let trash = crate::trash::RemoteTrash::open(&sftp)
+        .await
+        .map_err(crate::redact::error)?;
+    trash.restore(&id).await.map_err(crate::redact::error)
+}
+
+/// Permanently deletes trashed items (only those older than `older_than_days`
+/// when given). Returns how many were removed.
+#[tauri::command]
+pub async fn fs_empty_trash(
+    connection_id: String,
+    older_than_days: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let older_than = older_than_days.map(|days| days * 86_400);
+
+    if connection_id == "local" {
+        return tokio::task::spawn_blocking(move || {
+            crate::trash::LocalTrash::for_current_user()?.empty(older_than)
+        })
+        .await
+        .map_err(crate::redact::error)?
+        .map_err(crate::redact::error);
+    }
+
+    let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+    let trash = crate::trash::RemoteTrash::open(&sftp)
+        .await
+        .map_err(crate::redact::error)?;
+    trash.empty(older_than).await.map_err(crate::redact::error)
 }
 
 #[derive(Debug, Serialize)]
@@ -3105,16 +5360,48 @@ pub struct BatchDeleteError {
 
 #[tauri::command]
 pub async fn fs_delete_batch(
+    app: AppHandle,
     connection_id: String,
     paths: Vec<String>,
+    soft: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), BatchDeleteError> {
+    let result = delete_paths(&app, connection_id.clone(), paths.clone(), soft, &state).await;
+    let deleted: Vec<String> = match &result {
+        Ok(()) => paths,
+        Err(e) => paths
+            .into_iter()
+            .filter(|path| !e.failed_paths.contains(path))
+            .collect(),
+    };
+    if !deleted.is_empty() {
+        audit_delete(&app, &connection_id, &deleted, soft).await;
+    }
+    result
+}
+
+async fn delete_paths(
+    app: &AppHandle,
+    connection_id: String,
+    paths: Vec<String>,
+    soft: Option<bool>,
+    state: &State<'_, AppState>,
+) -> Result<(), BatchDeleteError> {
+    if soft.unwrap_or(false) {
+        return move_to_trash(app, state, &connection_id, &paths)
+            .await
+            .map(|_| ())
+            .map_err(|message| BatchDeleteError {
+                message,
+                failed_paths: paths.clone(),
+            });
+    }
     if connection_id == "local" {
         let mut failed_paths = Vec::new();
         for path in &paths {
             if let Err(e) = state.file_system.delete(&connection_id, path).await {
                 failed_paths.push(path.clone());
-                eprintln!("[FS] Local delete failed for {}: {}", path, e);
+                crate::log_error!("[FS] Local delete failed for {}: {}", path, e);
             }
         }
         if !failed_paths.is_empty() {
@@ -3134,32 +5421,28 @@ pub async fn fs_delete_batch(
                 conn.map(|c| c.detected_os.is_some()).unwrap_or(false),
             )
         };
+        let style = remote_path_style(state, &connection_id).await;
+        let paths: Vec<String> = paths.iter().map(|p| style.to_sftp(p)).collect();
 
         if should_optimize {
             if let Some(session) = session_opt {
                 let timeout_duration = std::time::Duration::from_secs(15);
 
                 let ssh_optimize_fut = async {
-                    let mut channel = session
-                        .lock()
-                        .await
-                        .channel_open_session()
-                        .await
-                        .map_err(|e| format!("Failed to open channel: {}", e))?;
-
-                    let paths_str = paths
-                        .iter()
-                        .map(|p| shell_quote(p))
-                        .collect::<Vec<_>>()
-                        .join(" ");
+                    let mut channel =
+                        crate::channel_budget::open_session(&connection_id, &*session.lock().await)
+                            .await
+                            .map_err(|e| {
+                                crate::redact::error(format!("Failed to open channel: {}", e))
+                            })?;
 
-                    let cmd = format!("rm -rf {}", paths_str);
-                    println!("[FS] Attempting batch server-side delete: {}", cmd);
+                    let cmd = style.remove_command(&paths);
+                    crate::log_info!("[FS] Attempting batch server-side delete: {}", cmd);
 
                     channel
                         .exec(true, cmd)
                         .await
-                        .map_err(|e| format!("Exec failed: {}", e))?;
+                        .map_err(|e| crate::redact::error(format!("Exec failed: {}", e)))?;
 
                     let mut success = false;
                     while let Some(msg) = channel.wait().await {
@@ -3175,18 +5458,18 @@ pub async fn fs_delete_batch(
 
                 match tokio::time::timeout(timeout_duration, ssh_optimize_fut).await {
                     Ok(Ok(true)) => {
-                        println!("[FS] Batch server-side delete successful.");
+                        crate::log_info!("[FS] Batch server-side delete successful.");
                         return Ok(());
                     }
-                    Ok(Err(e)) => println!(
+                    Ok(Err(e)) => crate::log_info!(
                         "[FS] Batch SSH delete error: {}. Falling back to SFTP...",
                         e
                     ),
-                    Err(_) => println!(
+                    Err(_) => crate::log_info!(
                         "[FS] Batch SSH delete timed out after {}s. Falling back to SFTP...",
                         timeout_duration.as_secs()
                     ),
-                    _ => println!("[FS] Batch SSH delete failed, falling back to SFTP..."),
+                    _ => crate::log_info!("[FS] Batch SSH delete failed, falling back to SFTP..."),
                 }
             }
         }
@@ -3201,13 +5484,13 @@ pub async fn fs_delete_batch(
             for path in paths {
                 if let Err(e) = fs.delete_remote(sftp, path).await {
                     failed.push(path.clone());
-                    eprintln!("[FS] SFTP delete failed for {}: {}", path, e);
+                    crate::log_error!("[FS] SFTP delete failed for {}: {}", path, e);
                 }
             }
             failed
         }
 
-        let sftp = match get_sftp_or_reconnect(&state, &connection_id).await {
+        let sftp = match get_sftp_or_reconnect(state, &connection_id).await {
             Ok(s) => s,
             Err(e) => {
                 return Err(BatchDeleteError {
@@ -3221,7 +5504,7 @@ pub async fn fs_delete_batch(
 
         // If some failed, maybe it was a session disconnect? Try reconnecting ONCE for the failures
         if !failed_paths.is_empty() {
-            println!(
+            crate::log_info!(
                 "[FS] Some batch deletes failed, attempting one-time reconnect for {} items...",
                 failed_paths.len()
             );
@@ -3231,7 +5514,7 @@ pub async fn fs_delete_batch(
                     c.sftp_session = None;
                 }
             }
-            if let Ok(retry_sftp) = get_sftp_or_reconnect(&state, &connection_id).await {
+            if let Ok(retry_sftp) = get_sftp_or_reconnect(state, &connection_id).await {
                 // Only retry the previously failed paths
                 let still_failed =
                     perform_sftp_batch_delete(&retry_sftp, &failed_paths, &state.file_system).await;
@@ -3250,19 +5533,45 @@ pub async fn fs_delete_batch(
     }
 }
 
+#[tauri::command]
+pub async fn fs_create_symlink(
+    connection_id: String,
+    target: String,
+    link_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if connection_id == "local" {
+        return FileSystem::create_symlink_local(&target, &link_path).map_err(crate::redact::error);
+    }
+
+    with_sftp_retry(&state, &connection_id, "symlink", |sftp| {
+        let fs = state.file_system.clone();
+        let target = target.clone();
+        let link_path = link_path.clone();
+        async move {
+            fs.create_symlink_remote(&sftp, &target, &link_path)
+                .await
+                .map_err(crate::redact::error)
+        }
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn fs_copy(
     connection_id: String,
     from: String,
     to: String,
+    preserve_symlinks: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let preserve_symlinks = preserve_symlinks.unwrap_or(false);
     if connection_id == "local" {
         state
             .file_system
-            .copy(&connection_id, &from, &to)
+            .copy(&connection_id, &from, &to, preserve_symlinks)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         // Optimization: Try server-side copy first (cp -r) to avoid download/upload
         let (session_opt, should_optimize) = {
@@ -3274,16 +5583,21 @@ pub async fn fs_copy(
             )
         };
 
+        let style = remote_path_style(&state, &connection_id).await;
+        let (from, to) = (style.to_sftp(&from), style.to_sftp(&to));
         if should_optimize {
             if let Some(session) = session_opt {
-                // Simple quoting for paths (Linux/Unix assumptions for now, robust enough for typical usage)
-                // We use standard "cp -r" which works on most Unix-likes.
-                // If it fails (e.g. Windows), we fall back to SFTP.
-                let cmd = format!("cp -r {} {}", shell_quote(&from), shell_quote(&to));
-                println!("[FS] Attempting server-side copy: {}", cmd);
+                // `cp -r` on Unix-likes, `Copy-Item` on Windows. If it fails we fall back to SFTP.
+                let cmd = style.copy_command(&from, &to, preserve_symlinks);
+                crate::log_info!("[FS] Attempting server-side copy: {}", cmd);
                 let timeout_duration = std::time::Duration::from_secs(10);
                 let optimize_fut = async {
-                    match session.lock().await.channel_open_session().await {
+                    match crate::channel_budget::open_session(
+                        &connection_id,
+                        &*session.lock().await,
+                    )
+                    .await
+                    {
                         Ok(mut channel) => {
                             if channel.exec(true, cmd).await.is_ok() {
                                 // Wait for exit status
@@ -3302,7 +5616,10 @@ pub async fn fs_copy(
                             }
                         }
                         Err(e) => {
-                            println!("[FS] Failed to open channel for copy optimization: {}", e);
+                            crate::log_info!(
+                                "[FS] Failed to open channel for copy optimization: {}",
+                                e
+                            );
                             Ok::<bool, String>(false)
                         }
                     }
@@ -3310,20 +5627,20 @@ pub async fn fs_copy(
 
                 match tokio::time::timeout(timeout_duration, optimize_fut).await {
                     Ok(Ok(true)) => {
-                        println!("[FS] Server-side copy successful");
+                        crate::log_info!("[FS] Server-side copy successful");
                         return Ok(());
                     }
                     Ok(Ok(false)) => {
-                        println!("[FS] Server-side copy failed (non-zero exit), checking SFTP fallback...");
+                        crate::log_info!("[FS] Server-side copy failed (non-zero exit), checking SFTP fallback...");
                     }
                     Ok(Err(e)) => {
-                        println!(
+                        crate::log_info!(
                             "[FS] Server-side copy failed (error), checking SFTP fallback: {}",
                             e
                         );
                     }
                     Err(_) => {
-                        println!("[FS] Server-side copy optimization timed out, checking SFTP fallback...");
+                        crate::log_info!("[FS] Server-side copy optimization timed out, checking SFTP fallback...");
                     }
                 }
             }
@@ -3335,13 +5652,15 @@ pub async fn fs_copy(
 
         match tokio::time::timeout(
             timeout_duration,
-            state.file_system.copy_remote(&sftp, &from, &to),
+            state
+                .file_system
+                .copy_remote(&sftp, &from, &to, preserve_symlinks),
         )
         .await
         {
             Ok(Ok(_)) => Ok(()),
             Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
-                println!("[FS] SFTP session closed during copy, retrying...");
+                crate::log_info!("[FS] SFTP session closed during copy, retrying...");
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -3351,16 +5670,18 @@ pub async fn fs_copy(
                 let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
                 match tokio::time::timeout(
                     timeout_duration,
-                    state.file_system.copy_remote(&sftp, &from, &to),
+                    state
+                        .file_system
+                        .copy_remote(&sftp, &from, &to, preserve_symlinks),
                 )
                 .await
                 {
                     Ok(Ok(_)) => Ok(()),
                     Ok(Err(e)) => Err(e.to_string()),
-                    Err(_) => Err(format!(
+                    Err(_) => Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP copy timed out after {}s",
                         timeout_duration.as_secs()
-                    )),
+                    ))),
                 }
             }
             Ok(Err(e)) => Err(e.to_string()),
@@ -3371,10 +5692,10 @@ pub async fn fs_copy(
                         c.sftp_session = None;
                     }
                 }
-                Err(format!(
+                Err(crate::redact::error(format!(
                     "DISCONNECTED: SFTP copy timed out after {}s",
                     timeout_duration.as_secs()
-                ))
+                )))
             }
         }
     }
@@ -3384,15 +5705,17 @@ pub async fn fs_copy(
 pub async fn fs_copy_batch(
     connection_id: String,
     operations: Vec<CopyOperation>,
+    preserve_symlinks: Option<bool>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let preserve_symlinks = preserve_symlinks.unwrap_or(false);
     if connection_id == "local" {
         for op in operations {
             state
                 .file_system
-                .copy(&connection_id, &op.from, &op.to)
+                .copy(&connection_id, &op.from, &op.to, preserve_symlinks)
                 .await
-                .map_err(|e| e.to_string())?;
+                .map_err(crate::redact::error)?;
         }
         Ok(())
     } else {
@@ -3406,28 +5729,37 @@ pub async fn fs_copy_batch(
             )
         };
 
+        let style = remote_path_style(&state, &connection_id).await;
+        let operations: Vec<CopyOperation> = operations
+            .into_iter()
+            .map(|op| CopyOperation {
+                from: style.to_sftp(&op.from),
+                to: style.to_sftp(&op.to),
+            })
+            .collect();
+
         if should_optimize && session_opt.is_some() {
             if let Some(session) = session_opt {
                 // Build a multi-command string: cp -r 'a' 'b' && cp -r 'c' 'd' ...
                 let cmd = operations
                     .iter()
-                    .map(|op| format!("cp -r {} {}", shell_quote(&op.from), shell_quote(&op.to)))
+                    .map(|op| style.copy_command(&op.from, &op.to, preserve_symlinks))
                     .collect::<Vec<_>>()
                     .join(" && ");
 
-                println!("[FS] Attempting batch server-side copy: {}", cmd);
+                crate::log_info!("[FS] Attempting batch server-side copy: {}", cmd);
                 let timeout_duration = std::time::Duration::from_secs(10);
                 let optimize_fut = async {
-                    let mut channel = session
-                        .lock()
-                        .await
-                        .channel_open_session()
-                        .await
-                        .map_err(|e| format!("Failed to open channel: {}", e))?;
+                    let mut channel =
+                        crate::channel_budget::open_session(&connection_id, &*session.lock().await)
+                            .await
+                            .map_err(|e| {
+                                crate::redact::error(format!("Failed to open channel: {}", e))
+                            })?;
                     channel
                         .exec(true, cmd)
                         .await
-                        .map_err(|e| format!("Exec failed: {}", e))?;
+                        .map_err(|e| crate::redact::error(format!("Exec failed: {}", e)))?;
 
                     let mut exit_code = None;
                     while let Some(msg) = channel.wait().await {
@@ -3441,17 +5773,17 @@ pub async fn fs_copy_batch(
 
                 match tokio::time::timeout(timeout_duration, optimize_fut).await {
                     Ok(Ok(Some(0))) => {
-                        println!("[FS] Batch server-side copy successful");
+                        crate::log_info!("[FS] Batch server-side copy successful");
                         return Ok(());
                     }
                     Ok(Ok(exit_code)) => {
-                        println!("[FS] Batch server-side copy failed with exit code {:?}, falling back to SFTP...", exit_code);
+                        crate::log_info!("[FS] Batch server-side copy failed with exit code {:?}, falling back to SFTP...", exit_code);
                     }
                     Ok(Err(e)) => {
-                        println!("[FS] Batch server-side copy optimization failed: {}. Falling back to SFTP...", e);
+                        crate::log_info!("[FS] Batch server-side copy optimization failed: {}. Falling back to SFTP...", e);
                     }
                     Err(_) => {
-                        println!("[FS] Batch server-side copy optimization timed out. Falling back to SFTP...");
+                        crate::log_info!("[FS] Batch server-side copy optimization timed out. Falling back to SFTP...");
                     }
                 }
             }
@@ -3469,7 +5801,7 @@ pub async fn fs_copy_batch(
                 timeout_duration,
                 state
                     .file_system
-                    .copy_remote(&current_sftp, &op.from, &op.to),
+                    .copy_remote(&current_sftp, &op.from, &op.to, preserve_symlinks),
             )
             .await
             {
@@ -3479,7 +5811,7 @@ pub async fn fs_copy_batch(
                 }
                 Ok(Err(e)) if e.to_string().to_lowercase().contains("session closed") => {
                     sftp_retry = sftp_retry.saturating_add(1);
-                    println!(
+                    crate::log_info!(
                         "[FS] SFTP session closed during batch item {}, retrying...",
                         idx
                     );
@@ -3491,10 +5823,10 @@ pub async fn fs_copy_batch(
                     }
                     current_sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
                     if sftp_retry > MAX_SFTP_RETRIES {
-                        return Err(format!(
+                        return Err(crate::redact::error(format!(
                             "DISCONNECTED: SFTP batch copy failed at item {} after {} reconnect retries",
                             idx, MAX_SFTP_RETRIES
-                        ));
+                        )));
                     }
                     // Don't increment idx, retry the same operation with new SFTP
                 }
@@ -3506,11 +5838,11 @@ pub async fn fs_copy_batch(
                             c.sftp_session = None;
                         }
                     }
-                    return Err(format!(
+                    return Err(crate::redact::error(format!(
                         "DISCONNECTED: SFTP batch copy timed out at item {} after {}s",
                         idx,
                         timeout_duration.as_secs()
-                    ));
+                    )));
                 }
             }
         }
@@ -3530,7 +5862,7 @@ pub async fn fs_rename_batch(
                 .file_system
                 .rename(&connection_id, &op.from, &op.to)
                 .await
-                .map_err(|e| e.to_string())?;
+                .map_err(crate::redact::error)?;
         }
         Ok(())
     } else {
@@ -3543,13 +5875,13 @@ pub async fn fs_rename_batch(
             .await;
 
             let final_res = match res {
-                Ok(inner) => inner.map_err(|e| e.to_string()),
+                Ok(inner) => inner.map_err(crate::redact::error),
                 Err(_) => Err("DISCONNECTED: SFTP session timeout".to_string()),
             };
 
             if let Err(e) = final_res {
                 if e.to_lowercase().contains("session closed") || e.contains("DISCONNECTED:") {
-                    println!(
+                    crate::log_info!(
                         "[FS] SFTP session closed or timed out during batch rename, retrying..."
                     );
                     {
@@ -3567,7 +5899,7 @@ pub async fn fs_rename_batch(
                         )
                         .await
                         .map_err(|_| "DISCONNECTED: SFTP session timeout".to_string())?
-                        .map_err(|e| e.to_string())?;
+                        .map_err(crate::redact::error)?;
 
                         let from_exists = tokio::time::timeout(
                             Duration::from_secs(10),
@@ -3575,16 +5907,16 @@ pub async fn fs_rename_batch(
                         )
                         .await
                         .map_err(|_| "DISCONNECTED: SFTP session timeout".to_string())?
-                        .map_err(|e| e.to_string())?;
+                        .map_err(crate::redact::error)?;
 
                         if !from_exists {
                             continue;
                         }
                         if to_exists && from_exists {
-                            return Err(format!(
+                            return Err(crate::redact::error(format!(
                                 "Batch rename conflict: both source and destination exist for '{}' -> '{}'",
                                 retry_op.from, retry_op.to
-                            ));
+                            )));
                         }
 
                         let retry_res = tokio::time::timeout(
@@ -3598,7 +5930,7 @@ pub async fn fs_rename_batch(
                         .await;
 
                         match retry_res {
-                            Ok(inner) => inner.map_err(|e| e.to_string())?,
+                            Ok(inner) => inner.map_err(crate::redact::error)?,
                             Err(_) => return Err("DISCONNECTED: SFTP session timeout".to_string()),
                         };
                     }
@@ -3622,7 +5954,7 @@ pub async fn fs_exists(
             .file_system
             .exists(&connection_id, &path)
             .await
-            .map_err(|e| e.to_string())
+            .map_err(crate::redact::error)
     } else {
         let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
 
@@ -3633,7 +5965,7 @@ pub async fn fs_exists(
         .await;
 
         let final_res = match res {
-            Ok(inner) => inner.map_err(|e| e.to_string()),
+            Ok(inner) => inner.map_err(crate::redact::error),
             Err(_) => Err("DISCONNECTED: SFTP session timeout".to_string()),
         };
 
@@ -3642,7 +5974,9 @@ pub async fn fs_exists(
             Err(e)
                 if e.to_lowercase().contains("session closed") || e.contains("DISCONNECTED:") =>
             {
-                println!("[FS] SFTP session closed or timed out during exists check, retrying...");
+                crate::log_info!(
+                    "[FS] SFTP session closed or timed out during exists check, retrying..."
+                );
                 {
                     let mut connections = state.connections.lock().await;
                     if let Some(c) = connections.get_mut(&connection_id) {
@@ -3658,7 +5992,7 @@ pub async fn fs_exists(
                 .await;
 
                 match retry_res {
-                    Ok(inner) => inner.map_err(|e| e.to_string()),
+                    Ok(inner) => inner.map_err(crate::redact::error),
                     Err(_) => Err("DISCONNECTED: SFTP session timeout".to_string()),
                 }
             }
@@ -3667,341 +6001,2692 @@ pub async fn fs_exists(
     }
 }
 
-#[tauri::command]
-pub async fn window_is_maximized(app: AppHandle) -> bool {
-    let Some(window) = app.get_webview_window("main") else {
-        return false;
-    };
-
-    let maximized = window.is_maximized().unwrap_or(false);
-    let fullscreen = window.is_fullscreen().unwrap_or(false);
-    maximized || fullscreen
-}
-
-#[tauri::command]
-pub async fn window_maximize(app: AppHandle) -> Result<(), String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
+/// Runs a one-shot command on the remote host and collects its output.
+/// Returns `(exit_status, stdout, stderr)`.
+pub(crate) async fn exec_remote_capture(
+    connection_id: &str,
+    command: &str,
+    state: &State<'_, AppState>,
+) -> Result<(u32, Vec<u8>, Vec<u8>), String> {
+    let mut channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(crate::redact::error)?;
 
-    #[cfg(target_os = "macos")]
-    {
-        let fullscreen = window.is_fullscreen().map_err(|e| e.to_string())?;
-        window
-            .set_fullscreen(!fullscreen)
-            .map_err(|e| e.to_string())?;
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = 0;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+            russh::ChannelMsg::ExtendedData { ref data, .. } => stderr.extend_from_slice(data),
+            russh::ChannelMsg::ExitStatus { exit_status: code } => exit_status = code,
+            _ => {}
+        }
     }
+    Ok((exit_status, stdout, stderr))
+}
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        if window.is_maximized().map_err(|e| e.to_string())? {
-            window.unmaximize().map_err(|e| e.to_string())?;
-        } else {
-            window.maximize().map_err(|e| e.to_string())?;
+/// Like `exec_remote_capture`, with `input` written to the command's stdin
+/// (followed by EOF).
+async fn exec_remote_with_input(
+    connection_id: &str,
+    command: &str,
+    input: &[u8],
+    state: &State<'_, AppState>,
+) -> Result<(u32, Vec<u8>, Vec<u8>), String> {
+    let mut channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(crate::redact::error)?;
+    channel.data(input).await.map_err(crate::redact::error)?;
+    channel.eof().await.map_err(crate::redact::error)?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = 0;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+            russh::ChannelMsg::ExtendedData { ref data, .. } => stderr.extend_from_slice(data),
+            russh::ChannelMsg::ExitStatus { exit_status: code } => exit_status = code,
+            _ => {}
         }
     }
+    Ok((exit_status, stdout, stderr))
+}
 
-    Ok(())
+/// Rename that replaces an existing target: `posix-rename@openssh.com` when the
+/// server offers it, otherwise the target is moved aside and restored on failure.
+async fn remote_rename_overwrite(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    old_path: &str,
+    new_path: &str,
+) -> Result<(), String> {
+    if crate::sftp_extensions::posix_rename_supported(connection_id) != Some(false) {
+        let channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+        let renamed = tokio::time::timeout(
+            Duration::from_secs(10),
+            crate::sftp_extensions::posix_rename(connection_id, channel, old_path, new_path),
+        )
+        .await
+        .map_err(|_| "DISCONNECTED: SFTP rename timed out after 10s".to_string())?
+        .map_err(crate::redact::error)?;
+        if renamed {
+            return Ok(());
+        }
+    }
+    with_sftp_retry(state, connection_id, "rename", |sftp| {
+        let fs = state.file_system.clone();
+        let old_path = old_path.to_string();
+        let new_path = new_path.to_string();
+        async move {
+            fs.rename_remote_replacing(&sftp, &old_path, &new_path)
+                .await
+                .map_err(crate::redact::error)
+        }
+    })
+    .await
 }
 
-#[tauri::command]
-pub async fn window_minimize(app: AppHandle) -> Result<(), String> {
-    let window = app
-        .get_webview_window("main")
+/// Retries a remote fs operation once with a fresh SFTP session when the
+/// first attempt hits a closed session or a timeout.
+async fn with_sftp_retry<T, F, Fut>(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    label: &str,
+    op: F,
+) -> Result<T, String>
+where
+    F: Fn(Arc<russh_sftp::client::SftpSession>) -> Fut,
+    Fut: std::future::Future<Output = Result<T, String>>,
+{
+    let sftp = get_sftp_or_reconnect(state, connection_id).await?;
+    let first = match tokio::time::timeout(Duration::from_secs(10), op(sftp)).await {
+        Ok(inner) => inner,
+        Err(_) => Err("DISCONNECTED: SFTP session timeout".to_string()),
+    };
+
+    match first {
+        Err(e) if e.to_lowercase().contains("session closed") || e.contains("DISCONNECTED:") => {
+            crate::log_info!("[FS] SFTP session closed or timed out during {}, retrying...", label);
+            {
+                let mut connections = state.connections.lock().await;
+                if let Some(c) = connections.get_mut(connection_id) {
+                    c.sftp_session = None;
+                }
+            }
+            let sftp = get_sftp_or_reconnect(state, connection_id).await?;
+            match tokio::time::timeout(Duration::from_secs(10), op(sftp)).await {
+                Ok(inner) => inner,
+                Err(_) => Err("DISCONNECTED: SFTP session timeout".to_string()),
+            }
+        }
+        other => other,
+    }
+}
+
+/// Splits an owner/group spec into a numeric id when possible.
+fn parse_numeric_id(value: &Option<String>) -> Result<Option<u32>, ()> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(v) => v.parse::<u32>().map(Some).map_err(|_| ()),
+    }
+}
+
+fn chown_spec(owner: &Option<String>, group: &Option<String>) -> String {
+    let owner = owner.as_deref().map(str::trim).unwrap_or("");
+    let group = group.as_deref().map(str::trim).unwrap_or("");
+    if group.is_empty() {
+        owner.to_string()
+    } else {
+        format!("{}:{}", owner, group)
+    }
+}
+
+#[tauri::command]
+pub async fn fs_chmod(
+    connection_id: String,
+    path: String,
+    mode: String,
+    recursive: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mode = FileSystem::parse_mode(&mode).map_err(crate::redact::error)?;
+    let recursive = recursive.unwrap_or(false);
+
+    if connection_id == "local" {
+        let fs = state.file_system.clone();
+        return tokio::task::spawn_blocking(move || fs.chmod_local(&path, mode, recursive))
+            .await
+            .map_err(crate::redact::error)?
+            .map_err(crate::redact::error);
+    }
+
+    if recursive {
+        // A single server-side chmod is far cheaper than a setstat per entry.
+        let command = format!("chmod -R {:o} -- {}", mode, shell_quote(&path));
+        if let Ok((0, _, _)) = exec_remote_capture(&connection_id, &command, &state).await {
+            return Ok(());
+        }
+    }
+
+    with_sftp_retry(&state, &connection_id, "chmod", |sftp| {
+        let fs = state.file_system.clone();
+        let path = path.clone();
+        async move {
+            fs.chmod_remote(&sftp, &path, mode, recursive)
+                .await
+                .map_err(crate::redact::error)
+        }
+    })
+    .await
+}
+
+/// Sets `file_mode` on files and `dir_mode` on directories under `root`
+/// (optionally only entries whose name matches `glob`). With `dry_run` nothing
+/// changes and the report previews what would.
+#[tauri::command]
+pub async fn fs_chmod_bulk(
+    connection_id: String,
+    root: String,
+    file_mode: Option<String>,
+    dir_mode: Option<String>,
+    glob: Option<String>,
+    dry_run: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<crate::bulk_chmod::BulkChmodReport, String> {
+    let plan = crate::bulk_chmod::BulkChmod::new(file_mode.as_deref(), dir_mode.as_deref(), glob)?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    if connection_id == "local" {
+        return tokio::task::spawn_blocking(move || {
+            crate::bulk_chmod::walk_local(&root, &plan, dry_run)
+        })
+        .await
+        .map_err(crate::redact::error)?
+        .map_err(crate::redact::error);
+    }
+
+    let style = remote_path_style(&state, &connection_id).await;
+    let root = style.to_sftp(&root);
+    if style == RemotePathStyle::Posix {
+        let script = plan.find_script(&root, dry_run);
+        match exec_remote_capture(&connection_id, &script, &state).await {
+            Ok((status, stdout, stderr)) if status != crate::bulk_chmod::FIND_MISSING => {
+                let stdout = String::from_utf8_lossy(&stdout);
+                let stderr = String::from_utf8_lossy(&stderr);
+                return Ok(plan.parse_find_output(&stdout, &stderr, dry_run));
+            }
+            Ok(_) => {}
+            Err(e) => crate::log_error!("[FS] find-based chmod failed, walking over SFTP: {}", e),
+        }
+    }
+
+    with_sftp_retry(&state, &connection_id, "bulk chmod", |sftp| {
+        let plan = plan.clone();
+        let root = root.clone();
+        async move {
+            crate::bulk_chmod::walk_sftp(&sftp, &root, &plan, dry_run)
+                .await
+                .map_err(crate::redact::error)
+        }
+    })
+    .await
+}
+
+/// Changes the owner and/or group of a path. Numeric ids go through
+/// `lchown`/SFTP setstat directly; user and group names need the `chown`
+/// binary to resolve them.
+#[tauri::command]
+pub async fn fs_chown(
+    connection_id: String,
+    path: String,
+    owner: Option<String>,
+    group: Option<String>,
+    recursive: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let recursive = recursive.unwrap_or(false);
+    let spec = chown_spec(&owner, &group);
+    if spec.is_empty() {
+        return Err("Either owner or group must be provided".to_string());
+    }
+    let numeric = match (parse_numeric_id(&owner), parse_numeric_id(&group)) {
+        (Ok(uid), Ok(gid)) => Some((uid, gid)),
+        _ => None,
+    };
+
+    if connection_id == "local" {
+        if let Some((uid, gid)) = numeric {
+            let fs = state.file_system.clone();
+            return tokio::task::spawn_blocking(move || fs.chown_local(&path, uid, gid, recursive))
+                .await
+                .map_err(crate::redact::error)?
+                .map_err(crate::redact::error);
+        }
+        if cfg!(target_os = "windows") {
+            return Err("Changing ownership is not supported on Windows".to_string());
+        }
+        let mut cmd = tokio::process::Command::new("chown");
+        cmd.arg("-h");
+        if recursive {
+            cmd.arg("-R");
+        }
+        let output = cmd
+            .arg(&spec)
+            .arg("--")
+            .arg(&path)
+            .output()
+            .await
+            .map_err(|e| crate::redact::error(format!("Failed to run chown: {}", e)))?;
+        return if output.status.success() {
+            Ok(())
+        } else {
+            Err(crate::redact::error(format!(
+                "chown failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        };
+    }
+
+    if let Some((uid, gid)) = numeric {
+        if !recursive {
+            return with_sftp_retry(&state, &connection_id, "chown", |sftp| {
+                let fs = state.file_system.clone();
+                let path = path.clone();
+                async move {
+                    fs.chown_remote(&sftp, &path, uid, gid, false)
+                        .await
+                        .map_err(crate::redact::error)
+                }
+            })
+            .await;
+        }
+    }
+
+    let command = format!(
+        "chown -h{} {} -- {}",
+        if recursive { " -R" } else { "" },
+        shell_quote(&spec),
+        shell_quote(&path)
+    );
+    match exec_remote_capture(&connection_id, &command, &state).await {
+        Ok((0, _, _)) => Ok(()),
+        Ok((code, _, stderr)) => {
+            if let Some((uid, gid)) = numeric {
+                // No usable chown binary (or not permitted via shell): walk via SFTP.
+                return with_sftp_retry(&state, &connection_id, "chown", |sftp| {
+                    let fs = state.file_system.clone();
+                    let path = path.clone();
+                    async move {
+                        fs.chown_remote(&sftp, &path, uid, gid, true)
+                            .await
+                            .map_err(crate::redact::error)
+                    }
+                })
+                .await;
+            }
+            Err(crate::redact::error(format!(
+                "chown failed (Exit {}): {}",
+                code,
+                String::from_utf8_lossy(&stderr).trim()
+            )))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn connection_is_windows(state: &AppState, connection_id: &str) -> bool {
+    remote_path_style(state, connection_id).await == RemotePathStyle::Windows
+}
+
+/// Path conventions of the connected server (see `crate::remote_path`).
+async fn remote_path_style(state: &AppState, connection_id: &str) -> RemotePathStyle {
+    let connections = state.connections.lock().await;
+    RemotePathStyle::from_os(
+        connections
+            .get(connection_id)
+            .and_then(|c| c.detected_os.as_deref()),
+    )
+}
+
+/// How the connected server's file system compares names (see `crate::path_names`).
+async fn remote_name_rules(state: &AppState, connection_id: &str) -> crate::path_names::NameRules {
+    let connections = state.connections.lock().await;
+    crate::path_names::NameRules::for_os(
+        connections
+            .get(connection_id)
+            .and_then(|c| c.detected_os.as_deref()),
+    )
+}
+
+/// Exit status a POSIX shell reports when the command itself was not found.
+const EXIT_COMMAND_NOT_FOUND: u32 = 127;
+
+#[tauri::command]
+pub async fn fs_compress(
+    connection_id: String,
+    paths: Vec<String>,
+    archive_path: String,
+    format: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use crate::archive::{self as archive_ops, ArchiveFormat};
+
+    if paths.is_empty() {
+        return Err("No paths to compress".to_string());
+    }
+    let format = match format {
+        Some(format) => ArchiveFormat::parse(&format).map_err(crate::redact::error)?,
+        None => ArchiveFormat::from_path(&archive_path).unwrap_or(ArchiveFormat::Zip),
+    };
+
+    if connection_id == "local" {
+        return match format {
+            ArchiveFormat::Zip => {
+                let sources: Vec<std::path::PathBuf> =
+                    paths.iter().map(std::path::PathBuf::from).collect();
+                let archive_file = std::path::PathBuf::from(&archive_path);
+                tokio::task::spawn_blocking(move || {
+                    archive_ops::zip_local_paths(&sources, &archive_file)
+                })
+                .await
+                .map_err(crate::redact::error)?
+                .map_err(crate::redact::error)
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                let flags = if format == ArchiveFormat::TarGz { "-czf" } else { "-cf" };
+                let mut args = vec![flags.to_string(), archive_path.clone()];
+                for path in &paths {
+                    let path = std::path::Path::new(path);
+                    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+                    let name = path.file_name().ok_or("Cannot archive a root path")?;
+                    args.push("-C".to_string());
+                    args.push(parent.to_string_lossy().to_string());
+                    args.push(name.to_string_lossy().to_string());
+                }
+                archive_ops::tar_local(&args)
+                    .await
+                    .map_err(crate::redact::error)
+            }
+        };
+    }
+
+    if !connection_is_windows(&state, &connection_id).await {
+        let command = archive_ops::compress_command(format, &paths, &archive_path);
+        crate::log_info!("[FS] Attempting server-side archive: {}", command);
+        match exec_remote_capture(&connection_id, &command, &state).await {
+            Ok((0, _, _)) => return Ok(()),
+            Ok((EXIT_COMMAND_NOT_FOUND, _, _)) => {
+                crate::log_info!("[FS] Archive tool missing on server, falling back to SFTP");
+            }
+            Ok((code, _, stderr)) => {
+                return Err(crate::redact::error(format!(
+                    "Archive command failed (Exit {}): {}",
+                    code,
+                    String::from_utf8_lossy(&stderr).trim()
+                )))
+            }
+            Err(e) => crate::log_info!("[FS] Server-side archive unavailable ({}), falling back to SFTP", e),
+        }
+    }
+
+    if format != ArchiveFormat::Zip {
+        return Err("This server cannot create tar archives; choose zip instead".to_string());
+    }
+    let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+    archive_ops::compress_via_sftp(&sftp, &paths, &archive_path)
+        .await
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn fs_extract(
+    connection_id: String,
+    archive: String,
+    dest: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use crate::archive::{self as archive_ops, ArchiveFormat};
+
+    let format = ArchiveFormat::from_path(&archive)
+        .ok_or_else(|| crate::redact::error(format!("Unrecognized archive type: {}", archive)))?;
+
+    if connection_id == "local" {
+        return match format {
+            ArchiveFormat::Zip => {
+                let (source, target) = (
+                    std::path::PathBuf::from(&archive),
+                    std::path::PathBuf::from(&dest),
+                );
+                tokio::task::spawn_blocking(move || archive_ops::unzip_local(&source, &target))
+                    .await
+                    .map_err(crate::redact::error)?
+                    .map_err(crate::redact::error)
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                std::fs::create_dir_all(&dest).map_err(crate::redact::error)?;
+                let flags = if format == ArchiveFormat::TarGz { "-xzf" } else { "-xf" };
+                archive_ops::tar_local(&[
+                    flags.to_string(),
+                    archive.clone(),
+                    "-C".to_string(),
+                    dest.clone(),
+                ])
+                .await
+                .map_err(crate::redact::error)
+            }
+        };
+    }
+
+    if !connection_is_windows(&state, &connection_id).await {
+        let command = archive_ops::extract_command(format, &archive, &dest);
+        crate::log_info!("[FS] Attempting server-side extract: {}", command);
+        match exec_remote_capture(&connection_id, &command, &state).await {
+            Ok((0, _, _)) => return Ok(()),
+            Ok((EXIT_COMMAND_NOT_FOUND, _, _)) => {
+                crate::log_info!("[FS] Extract tool missing on server, falling back to SFTP");
+            }
+            Ok((code, _, stderr)) => {
+                return Err(crate::redact::error(format!(
+                    "Extract command failed (Exit {}): {}",
+                    code,
+                    String::from_utf8_lossy(&stderr).trim()
+                )))
+            }
+            Err(e) => crate::log_info!("[FS] Server-side extract unavailable ({}), falling back to SFTP", e),
+        }
+    }
+
+    if format != ArchiveFormat::Zip {
+        return Err("This server cannot extract tar archives".to_string());
+    }
+    let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+    archive_ops::extract_via_sftp(&sftp, &archive, &dest)
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Returns the hex digest of a file. Remote files are hashed on the server when a
+/// hashing tool is available, otherwise streamed through SFTP.
+/// Digest of a single file. Remote files are hashed server-side when a tool is
+/// available; otherwise the file is streamed through SFTP (or SCP) and hashed here.
+pub(crate) async fn checksum_path(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    path: &str,
+    algo: crate::hashing::HashAlgorithm,
+) -> Result<crate::hashing::Checksum, String> {
+    use crate::hashing::{self, Checksum, ChecksumMethod};
+
+    if connection_id == "local" {
+        let local = path.to_string();
+        if std::path::Path::new(&local).is_dir() {
+            return Err(crate::redact::error(format!("'{}' is a directory", local)));
+        }
+        let digest = tokio::task::spawn_blocking(move || hashing::hash_local_file(&local, algo))
+            .await
+            .map_err(crate::redact::error)?
+            .map_err(crate::redact::error)?;
+        return Ok(Checksum {
+            path: path.to_string(),
+            algorithm: algo,
+            digest,
+            method: ChecksumMethod::Stream,
+        });
+    }
+
+    let style = remote_path_style(state, connection_id).await;
+    let path = style.to_sftp(path);
+    let command = match style {
+        RemotePathStyle::Windows => algo.powershell_command(&style.to_native(&path)),
+        RemotePathStyle::Posix => algo.posix_command(&shell_quote(&path)),
+    };
+    match exec_remote_capture(connection_id, &command, state).await {
+        Ok((0, stdout, _)) => {
+            if let Some(digest) = algo.parse_output(&String::from_utf8_lossy(&stdout)) {
+                return Ok(Checksum {
+                    path,
+                    algorithm: algo,
+                    digest,
+                    method: ChecksumMethod::Server,
+                });
+            }
+            crate::log_info!("[FS] Unexpected {} output from server, hashing over SFTP", algo.as_str());
+        }
+        Ok((code, _, stderr)) => crate::log_info!(
+            "[FS] Server-side {} failed (Exit {}): {}, hashing over SFTP",
+            algo.as_str(),
+            code,
+            String::from_utf8_lossy(&stderr).trim()
+        ),
+        Err(e) => crate::log_info!("[FS] Server-side hashing unavailable ({}), hashing over SFTP", e),
+    }
+
+    // Large files can take a while to stream, so this path has no fixed timeout.
+    let digest = if uses_scp_fallback(state, connection_id).await {
+        let channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
+        let content = state
+            .file_system
+            .read_scp(channel, &path)
+            .await
+            .map_err(crate::redact::error)?;
+        hashing::hash_bytes(&content, algo)
+    } else {
+        let sftp = get_sftp_or_reconnect(state, connection_id).await?;
+        if sftp
+            .metadata(&path)
+            .await
+            .map(|meta| meta.is_dir())
+            .unwrap_or(false)
+        {
+            return Err(crate::redact::error(format!("'{}' is a directory", path)));
+        }
+        hashing::hash_remote_file(&sftp, &path, algo)
+            .await
+            .map_err(crate::redact::error)?
+    };
+    Ok(Checksum {
+        path,
+        algorithm: algo,
+        digest,
+        method: ChecksumMethod::Stream,
+    })
+}
+
+#[tauri::command]
+pub async fn fs_hash(
+    connection_id: String,
+    path: String,
+    algo: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let algo = crate::hashing::HashAlgorithm::parse(&algo).map_err(crate::redact::error)?;
+    checksum_path(&state, &connection_id, &path, algo)
+        .await
+        .map(|checksum| checksum.digest)
+}
+
+/// Like `fs_hash`, but reports the algorithm and whether the server computed it.
+/// Defaults to SHA-256.
+#[tauri::command]
+pub async fn fs_checksum(
+    connection_id: String,
+    path: String,
+    algo: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<crate::hashing::Checksum, String> {
+    let algo = match algo {
+        Some(algo) => crate::hashing::HashAlgorithm::parse(&algo).map_err(crate::redact::error)?,
+        None => crate::hashing::HashAlgorithm::Sha256,
+    };
+    checksum_path(&state, &connection_id, &path, algo).await
+}
+
+#[tauri::command]
+pub async fn fs_append(
+    connection_id: String,
+    path: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if connection_id == "local" {
+        return state
+            .file_system
+            .append_local(&path, content.as_bytes())
+            .map_err(crate::redact::error);
+    }
+
+    // Not retried: a timed-out append may already have landed on the server.
+    let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
+    match tokio::time::timeout(
+        Duration::from_secs(10),
+        state
+            .file_system
+            .append_remote(&sftp, &path, content.as_bytes()),
+    )
+    .await
+    {
+        Ok(result) => result.map_err(crate::redact::error),
+        Err(_) => {
+            {
+                let mut connections = state.connections.lock().await;
+                if let Some(c) = connections.get_mut(&connection_id) {
+                    c.sftp_session = None;
+                }
+            }
+            Err("DISCONNECTED: SFTP append timed out after 10s".to_string())
+        }
+    }
+}
+
+/// Maximum number of lines `fs_read_tail` will return.
+const MAX_TAIL_LINES: usize = 10_000;
+
+#[tauri::command]
+pub async fn fs_read_tail(
+    connection_id: String,
+    path: String,
+    lines: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let lines = lines.unwrap_or(200).clamp(1, MAX_TAIL_LINES);
+
+    if connection_id == "local" {
+        let fs = state.file_system.clone();
+        return tokio::task::spawn_blocking(move || fs.read_tail_local(&path, lines))
+            .await
+            .map_err(crate::redact::error)?
+            .map_err(crate::redact::error);
+    }
+
+    with_sftp_retry(&state, &connection_id, "tail", |sftp| {
+        let fs = state.file_system.clone();
+        let path = path.clone();
+        async move {
+            fs.read_tail_remote(&sftp, &path, lines)
+                .await
+                .map_err(crate::redact::error)
+        }
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn window_is_maximized(app: AppHandle) -> bool {
+    let Some(window) = app.get_webview_window("main") else {
+        return false;
+    };
+
+    let maximized = window.is_maximized().unwrap_or(false);
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    maximized || fullscreen
+}
+
+#[tauri::command]
+pub async fn window_maximize(app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
         .ok_or("Main window not found")?;
-    window.minimize().map_err(|e| e.to_string())
+
+    #[cfg(target_os = "macos")]
+    {
+        let fullscreen = window.is_fullscreen().map_err(crate::redact::error)?;
+        window
+            .set_fullscreen(!fullscreen)
+            .map_err(crate::redact::error)?;
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        if window.is_maximized().map_err(crate::redact::error)? {
+            window.unmaximize().map_err(crate::redact::error)?;
+        } else {
+            window.maximize().map_err(crate::redact::error)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn window_minimize(app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    window.minimize().map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn window_close(app: AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("Main window not found")?;
+    window.close().map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn ssh_exec(
+    app: AppHandle,
+    connection_id: String,
+    command: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    audit_command(&app, &connection_id, &command, "ssh_exec").await;
+    if connection_id == "local" {
+        // Execute local command
+        let (shell, arg) = if cfg!(target_os = "windows") {
+            ("powershell", "-Command")
+        } else {
+            ("sh", "-c")
+        };
+
+        let output = std::process::Command::new(shell)
+            .arg(arg)
+            .arg(&command)
+            .output()
+            .map_err(|e| crate::redact::error(format!("Failed to execute local command: {}", e)))?;
+
+        if output.status.success() {
+            String::from_utf8(output.stdout)
+                .map_err(|e| crate::redact::error(format!("Invalid UTF-8 output: {}", e)))
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(crate::redact::error(format!("Command failed: {}", stderr)))
+        }
+    } else {
+        // Execute SSH command. The connections lock is not held while the
+        // channel waits for a free slot or the command runs.
+        let session = state
+            .connections
+            .lock()
+            .await
+            .get(&connection_id)
+            .and_then(|conn| conn.session.clone());
+        if let Some(session) = session {
+            let mut channel =
+                crate::channel_budget::open_session(&connection_id, &*session.lock().await)
+                    .await
+                    .map_err(crate::redact::error)?;
+            channel
+                .exec(true, command)
+                .await
+                .map_err(crate::redact::error)?;
+
+            let mut stdout = Vec::new();
+            let mut stderr = Vec::new();
+            let mut exit_status = 0;
+
+            while let Some(msg) = channel.wait().await {
+                match msg {
+                    russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+                    russh::ChannelMsg::ExtendedData { ref data, .. } => {
+                        stderr.extend_from_slice(data)
+                    }
+                    russh::ChannelMsg::ExitStatus { exit_status: code } => {
+                        exit_status = code;
+                    }
+                    _ => {}
+                }
+            }
+
+            if exit_status == 0 {
+                return String::from_utf8(stdout).map_err(crate::redact::error);
+            } else {
+                let err_str = String::from_utf8_lossy(&stderr);
+                return Err(crate::redact::error(format!(
+                    "Remote command failed (Exit {}): {}",
+                    exit_status, err_str
+                )));
+            }
+        }
+        Err("Connection not found".to_string())
+    }
+}
+
+/// Runs one search on several connections at once, at most
+/// `scope.concurrency` hosts at a time. Each host's matches are emitted as
+/// `search:result` when it answers; `search:done` follows the last one.
+#[tauri::command]
+pub async fn global_search(
+    app: AppHandle,
+    search_id: String,
+    query: String,
+    scope: crate::global_search::SearchScope,
+    state: State<'_, AppState>,
+) -> Result<crate::global_search::SearchSummary, String> {
+    use crate::global_search::{HostSearchResult, SearchSummary};
+
+    let command = crate::global_search::build_command(&query, &scope)?;
+    let mut targets = Vec::new();
+    {
+        let connections = state.connections.lock().await;
+        for connection_id in &scope.connection_ids {
+            if targets.iter().any(|(id, ..)| id == connection_id) {
+                continue;
+            }
+            let (name, host, windows) = match connections.get(connection_id) {
+                Some(conn) => (
+                    conn.config.name.clone(),
+                    conn.config.host.clone(),
+                    conn.detected_os
+                        .as_deref()
+                        .is_some_and(|os| os.eq_ignore_ascii_case("windows")),
+                ),
+                None => (connection_id.clone(), String::new(), false),
+            };
+            targets.push((connection_id.clone(), name, host, windows));
+        }
+    }
+    for (connection_id, ..) in &targets {
+        audit_command(&app, connection_id, &command, "global_search").await;
+    }
+
+    let cancelled = crate::global_search::register(&search_id);
+    let permits = Arc::new(tokio::sync::Semaphore::new(scope.concurrency_limit()));
+    let limit = scope.result_limit();
+    let mut tasks = tokio::task::JoinSet::new();
+    for (connection_id, connection_name, host, windows) in targets {
+        let app = app.clone();
+        let permits = permits.clone();
+        let cancelled = cancelled.clone();
+        let command = command.clone();
+        let search_id = search_id.clone();
+        let kind = scope.kind;
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            if cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+                return None;
+            }
+            let state = app.state::<AppState>();
+            let outcome = if windows {
+                Err("Search is not supported on Windows hosts".to_string())
+            } else {
+                match tokio::time::timeout(
+                    Duration::from_secs(60),
+                    exec_remote_capture(&connection_id, &command, &state),
+                )
+                .await
+                {
+                    Ok(result) => result,
+                    Err(_) => Err("Search timed out after 60s".to_string()),
+                }
+            };
+            let (matches, truncated, error) = match outcome {
+                Ok((_, stdout, stderr)) => {
+                    let (matches, truncated) =
+                        crate::global_search::parse_output(kind, &stdout, limit);
+                    // The pipeline's status is `head`'s; a failing grep/find
+                    // shows up as stderr with no output.
+                    let stderr = String::from_utf8_lossy(&stderr).trim().to_string();
+                    let error = (matches.is_empty() && !stderr.is_empty()).then_some(stderr);
+                    (matches, truncated, error)
+                }
+                Err(e) => (Vec::new(), false, Some(e)),
+            };
+            let result = HostSearchResult {
+                search_id,
+                connection_id,
+                connection_name,
+                host,
+                matches,
+                truncated,
+                error,
+            };
+            let _ = app.emit("search:result", &result);
+            Some(result)
+        });
+    }
+
+    let mut summary = SearchSummary {
+        search_id: search_id.clone(),
+        ..Default::default()
+    };
+    while let Some(joined) = tasks.join_next().await {
+        let Ok(Some(result)) = joined else {
+            continue;
+        };
+        summary.hosts += 1;
+        summary.matches += result.matches.len();
+        if result.error.is_some() {
+            summary.failed += 1;
+        } else if !result.matches.is_empty() {
+            summary.hosts_with_matches += 1;
+        }
+    }
+    summary.cancelled = cancelled.load(std::sync::atomic::Ordering::Relaxed);
+    crate::global_search::unregister(&search_id);
+    let _ = app.emit("search:done", &summary);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn global_search_cancel(search_id: String) -> Result<bool, String> {
+    Ok(crate::global_search::cancel(&search_id))
+}
+
+/// Runs `command` on several connections at once, at most `concurrency` hosts
+/// at a time (see [`crate::exec_batch`]). Output streams as
+/// `exec-batch:output`, each host's result is emitted as `exec-batch:host`,
+/// and all results are returned keyed by connection id.
+#[tauri::command]
+pub async fn ssh_exec_batch(
+    app: AppHandle,
+    connection_ids: Vec<String>,
+    command: String,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    batch_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<std::collections::BTreeMap<String, crate::exec_batch::BatchHostResult>, String> {
+    if command.trim().is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    let batch_id = batch_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let targets = exec_batch_targets(&connection_ids, &state).await;
+    for target in &targets {
+        audit_command(&app, &target.connection_id, &command, "ssh_exec_batch").await;
+    }
+    Ok(run_exec_batch(&app, targets, command, concurrency, timeout_secs, batch_id).await)
+}
+
+/// A host of an exec batch, as resolved from its connection id.
+struct ExecBatchTarget {
+    connection_id: String,
+    connection_name: String,
+    host: String,
+    detected_os: Option<String>,
+}
+
+/// Resolves `connection_ids` once each, in order. Ids that are not connected
+/// are kept so their result reports the failure to open a channel.
+async fn exec_batch_targets(
+    connection_ids: &[String],
+    state: &State<'_, AppState>,
+) -> Vec<ExecBatchTarget> {
+    let connections = state.connections.lock().await;
+    let mut targets: Vec<ExecBatchTarget> = Vec::new();
+    for connection_id in connection_ids {
+        if targets.iter().any(|t| &t.connection_id == connection_id) {
+            continue;
+        }
+        let target = match connections.get(connection_id) {
+            Some(conn) => ExecBatchTarget {
+                connection_id: connection_id.clone(),
+                connection_name: conn.config.name.clone(),
+                host: conn.config.host.clone(),
+                detected_os: conn.detected_os.clone(),
+            },
+            None => ExecBatchTarget {
+                connection_id: connection_id.clone(),
+                connection_name: connection_id.clone(),
+                host: String::new(),
+                detected_os: None,
+            },
+        };
+        targets.push(target);
+    }
+    targets
+}
+
+/// The batch runner behind `ssh_exec_batch` and `snippets_run`.
+async fn run_exec_batch(
+    app: &AppHandle,
+    targets: Vec<ExecBatchTarget>,
+    command: String,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    batch_id: String,
+) -> std::collections::BTreeMap<String, crate::exec_batch::BatchHostResult> {
+    use crate::exec_batch::{BatchHostResult, BatchOutput, OutputStream, StreamBuffer};
+
+    let permits = Arc::new(tokio::sync::Semaphore::new(
+        crate::exec_batch::concurrency_limit(concurrency),
+    ));
+    let limit = crate::exec_batch::timeout(timeout_secs);
+    let mut tasks = tokio::task::JoinSet::new();
+    for ExecBatchTarget {
+        connection_id,
+        connection_name,
+        host,
+        ..
+    } in targets
+    {
+        let app = app.clone();
+        let permits = permits.clone();
+        let command = command.clone();
+        let batch_id = batch_id.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            let state = app.state::<AppState>();
+            let started = std::time::Instant::now();
+            let emit = |stream: OutputStream, data: String| {
+                if !data.is_empty() {
+                    let output = BatchOutput {
+                        batch_id: batch_id.clone(),
+                        connection_id: connection_id.clone(),
+                        stream,
+                        data,
+                    };
+                    let _ = app.emit("exec-batch:output", &output);
+                }
+            };
+            let mut stdout = StreamBuffer::default();
+            let mut stderr = StreamBuffer::default();
+            let run = async {
+                let mut channel =
+                    open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+                channel
+                    .exec(true, command.as_str())
+                    .await
+                    .map_err(crate::redact::error)?;
+                let mut exit_status = None;
+                while let Some(msg) = channel.wait().await {
+                    match msg {
+                        russh::ChannelMsg::Data { ref data } => {
+                            emit(OutputStream::Stdout, stdout.push(data))
+                        }
+                        russh::ChannelMsg::ExtendedData { ref data, .. } => {
+                            emit(OutputStream::Stderr, stderr.push(data))
+                        }
+                        russh::ChannelMsg::ExitStatus { exit_status: code } => {
+                            exit_status = Some(code)
+                        }
+                        _ => {}
+                    }
+                }
+                Ok::<_, String>(exit_status)
+            };
+            let (exit_status, error) = match tokio::time::timeout(limit, run).await {
+                Ok(Ok(exit_status)) => (exit_status, None),
+                Ok(Err(e)) => (None, Some(e)),
+                Err(_) => (None, Some(format!("Timed out after {}s", limit.as_secs()))),
+            };
+            emit(OutputStream::Stdout, stdout.flush());
+            emit(OutputStream::Stderr, stderr.flush());
+            let result = BatchHostResult {
+                batch_id: batch_id.clone(),
+                connection_id: connection_id.clone(),
+                connection_name,
+                host,
+                exit_status,
+                stdout: stdout.captured(),
+                stderr: stderr.captured(),
+                truncated: stdout.truncated || stderr.truncated,
+                duration_ms: started.elapsed().as_millis() as u64,
+                error,
+            };
+            let _ = app.emit("exec-batch:host", &result);
+            Some(result)
+        });
+    }
+
+    let mut results = std::collections::BTreeMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(Some(result)) = joined {
+            results.insert(result.connection_id.clone(), result);
+        }
+    }
+    results
+}
+
+/// Runs `command` on one connection with the batch runner, for work started by
+/// the app itself rather than from a terminal (see [`crate::snippet_schedule`]).
+pub(crate) async fn exec_on_connection(
+    app: &AppHandle,
+    connection_id: &str,
+    command: String,
+    timeout_secs: Option<u64>,
+    batch_id: String,
+    source: &str,
+) -> Option<crate::exec_batch::BatchHostResult> {
+    let state = app.state::<AppState>();
+    let targets = exec_batch_targets(&[connection_id.to_string()], &state).await;
+    audit_command(app, connection_id, &command, source).await;
+    run_exec_batch(app, targets, command, Some(1), timeout_secs, batch_id)
+        .await
+        .remove(connection_id)
+}
+
+#[tauri::command]
+pub async fn ssh_import_config(
+    app: AppHandle,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let home = app.path().home_dir().map_err(crate::redact::error)?;
+    let config_path = home.join(".ssh/config");
+
+    // println!("[SSH] Importing config from: {:?}", config_path);
+
+    crate::ssh_config::parse_config(&config_path).map_err(crate::redact::error)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshImportSourceRequest {
+    pub source_type: String,
+    pub path: Option<String>,
+    pub content: Option<String>,
+}
+
+#[tauri::command]
+pub async fn ssh_import_config_from_file(
+    path: String,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let normalized = path.trim();
+    if normalized.is_empty() {
+        return Err("Select an SSH config file path first.".to_string());
+    }
+
+    let config_path = std::path::Path::new(normalized);
+    if !config_path.exists() {
+        return Err("SSH config file not found.".to_string());
+    }
+    if !config_path.is_file() {
+        return Err("Selected SSH config path is not a file.".to_string());
+    }
+    let metadata = std::fs::metadata(config_path)
+        .map_err(|e| crate::redact::error(format!("Cannot stat SSH config file: {}", e)))?;
+    if metadata.len() > MAX_IMPORT_TEXT_BYTES as u64 {
+        return Err("SSH config file too large (max 1 MiB).".to_string());
+    }
+    crate::ssh_config::parse_config(config_path).map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn ssh_import_config_from_text(
+    content: String,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    if content.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    if content.len() > MAX_IMPORT_TEXT_BYTES {
+        return Err("Pasted SSH config is too large (max 1 MiB).".to_string());
+    }
+
+    crate::ssh_config::parse_config_text(&content).map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn ssh_import_config_by_source(
+    app: AppHandle,
+    request: SshImportSourceRequest,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    match request.source_type.as_str() {
+        "default_ssh" => ssh_import_config(app).await,
+        "file" => {
+            let path = request.path.as_deref().unwrap_or("").trim().to_string();
+            if path.is_empty() {
+                return Err("Select an SSH config file path first.".to_string());
+            }
+            ssh_import_config_from_file(path).await
+        }
+        "text" => {
+            let content = request.content.as_deref().unwrap_or("").to_string();
+            if content.trim().is_empty() {
+                return Err("Paste SSH config text first.".to_string());
+            }
+
+            if content.len() > MAX_IMPORT_TEXT_BYTES {
+                return Err("Pasted SSH config is too large (max 1 MiB).".to_string());
+            }
+            ssh_import_config_from_text(content).await
+        }
+        "putty" => import_putty_sessions(app).await,
+        "ansible" => {
+            // A file path wins over pasted text.
+            let path = request.path.as_deref().unwrap_or("").trim();
+            let pasted = request.content.as_deref().filter(|c| !c.trim().is_empty());
+            let content = match pasted {
+                Some(content) if path.is_empty() => content.to_string(),
+                None if path.is_empty() => {
+                    return Err("Select an Ansible inventory file first.".to_string());
+                }
+                _ => {
+                    let metadata = std::fs::metadata(path).map_err(|e| {
+                        crate::redact::error(format!("Cannot read Ansible inventory: {}", e))
+                    })?;
+                    if !metadata.is_file() {
+                        return Err("Selected inventory path is not a file.".to_string());
+                    }
+                    if metadata.len() > MAX_IMPORT_TEXT_BYTES as u64 {
+                        return Err("Ansible inventory too large (max 1 MiB).".to_string());
+                    }
+                    std::fs::read_to_string(path).map_err(|e| {
+                        crate::redact::error(format!("Cannot read Ansible inventory: {}", e))
+                    })?
+                }
+            };
+            if content.len() > MAX_IMPORT_TEXT_BYTES {
+                return Err("Ansible inventory too large (max 1 MiB).".to_string());
+            }
+            crate::ansible_inventory::parse_inventory(&content).map_err(crate::redact::error)
+        }
+        _ => Err("Unsupported SSH import source.".to_string()),
+    }
+}
+
+/// Lists EC2 instances through the AWS CLI as importable connections. With
+/// `refresh_minutes` the query is saved and re-run in the background, emitting
+/// `cloud:refreshed` with the current instances.
+#[tauri::command]
+pub async fn cloud_import_aws(
+    app: AppHandle,
+    profile: Option<String>,
+    region: Option<String>,
+    options: Option<crate::cloud_import::aws::AwsQuery>,
+    refresh_minutes: Option<u32>,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let query = crate::cloud_import::aws::AwsQuery {
+        profile,
+        region,
+        ..options.unwrap_or_default()
+    };
+    cloud_import(
+        app,
+        crate::cloud_import::CloudQuery::Aws(query),
+        refresh_minutes,
+    )
+    .await
+}
+
+/// Imports from any provider; `query` is tagged with `provider`
+/// (`aws`, `gcp` or `azure`).
+#[tauri::command]
+pub async fn cloud_import(
+    app: AppHandle,
+    query: crate::cloud_import::CloudQuery,
+    refresh_minutes: Option<u32>,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let connections = query.run().await.map_err(crate::redact::error)?;
+    if let Some(minutes) = refresh_minutes.filter(|minutes| *minutes > 0) {
+        let path = crate::cloud_import::sources_path(&get_data_dir(&app));
+        crate::cloud_import::upsert_source(&path, query, minutes).map_err(crate::redact::error)?;
+    }
+    Ok(connections)
+}
+
+#[tauri::command]
+pub async fn cloud_sources_list(
+    app: AppHandle,
+) -> Result<Vec<crate::cloud_import::CloudSource>, String> {
+    let path = crate::cloud_import::sources_path(&get_data_dir(&app));
+    crate::cloud_import::load_sources(&path).map_err(crate::redact::error)
+}
+
+/// Stops refreshing a saved cloud source; imported connections stay.
+#[tauri::command]
+pub async fn cloud_sources_remove(app: AppHandle, id: String) -> Result<(), String> {
+    let path = crate::cloud_import::sources_path(&get_data_dir(&app));
+    let mut sources = crate::cloud_import::load_sources(&path).map_err(crate::redact::error)?;
+    sources.retain(|source| source.id != id);
+    crate::cloud_import::save_sources(&path, &sources).map_err(crate::redact::error)
+}
+
+/// Tailnet peers from `tailscale status --json` as importable connections,
+/// addressed by MagicDNS name unless `useIp` is set.
+#[tauri::command]
+pub async fn discover_tailscale(
+    query: Option<crate::mesh_discovery::tailscale::TailscaleQuery>,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    crate::mesh_discovery::tailscale::discover(&query.unwrap_or_default())
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Members of the ZeroTier networks this node has joined (or `networkIds`)
+/// as importable connections, read from the ZeroTier Central API.
+#[tauri::command]
+pub async fn discover_zerotier(
+    query: Option<crate::mesh_discovery::zerotier::ZerotierQuery>,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    crate::mesh_discovery::zerotier::discover(&query.unwrap_or_default())
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Starts looking for SSH servers on the local network (see
+/// [`crate::lan_discovery`]). Hosts are emitted as `discovery:host` while the
+/// search runs and `discovery:done` follows; returns the discovery id.
+#[tauri::command]
+pub async fn discovery_start(
+    app: AppHandle,
+    options: Option<crate::lan_discovery::DiscoveryOptions>,
+) -> Result<String, String> {
+    use crate::lan_discovery::{DiscoverySummary, HostTable, Sighting};
+
+    let options = options.unwrap_or_default();
+    if let Some(cidr) = options.cidr.as_deref().filter(|c| !c.trim().is_empty()) {
+        // Reject a bad network up front rather than in the done event.
+        crate::lan_discovery::scan::parse_cidr(cidr).map_err(crate::redact::error)?;
+    } else if !options.mdns {
+        return Err("Enable mDNS or give a network to scan".to_string());
+    }
+
+    let discovery_id = uuid::Uuid::new_v4().to_string();
+    let table = Arc::new(std::sync::Mutex::new(HostTable::new(&discovery_id)));
+    let report = {
+        let app = app.clone();
+        let table = table.clone();
+        move |sighting: Sighting| {
+            let changed = table.lock().ok().and_then(|mut t| t.merge(sighting));
+            if let Some(host) = changed {
+                let _ = app.emit("discovery:host", &host);
+            }
+        }
+    };
+
+    let id = discovery_id.clone();
+    let task = tokio::spawn(async move {
+        let browse = async {
+            if !options.mdns {
+                return None;
+            }
+            crate::lan_discovery::browse_mdns(options.mdns_duration(), |service| {
+                report(Sighting {
+                    address: service.address,
+                    port: service.port,
+                    source: "mdns",
+                    name: Some(service.name),
+                    hostname: Some(service.hostname),
+                    banner: None,
+                })
+            })
+            .await
+            .err()
+            .map(|e| format!("mDNS browse failed: {}", e))
+        };
+        let sweep = async {
+            let cidr = options.cidr.as_deref().filter(|c| !c.trim().is_empty())?;
+            crate::lan_discovery::sweep(cidr, &options, |address, banner| {
+                report(Sighting {
+                    address: address.ip(),
+                    port: address.port(),
+                    source: "scan",
+                    name: None,
+                    hostname: None,
+                    banner,
+                })
+            })
+            .await
+            .err()
+        };
+        let (browse_error, sweep_error) = tokio::join!(browse, sweep);
+        let errors: Vec<String> = browse_error.into_iter().chain(sweep_error).collect();
+        for error in &errors {
+            crate::log_error!("[DISCOVERY] {}", error);
+        }
+        crate::lan_discovery::unregister(&id);
+        let summary = DiscoverySummary {
+            hosts: table.lock().map(|t| t.count()).unwrap_or(0),
+            discovery_id: id,
+            errors,
+        };
+        let _ = app.emit("discovery:done", &summary);
+    });
+    crate::lan_discovery::register(&discovery_id, task.abort_handle());
+    Ok(discovery_id)
+}
+
+/// Stops the running LAN discovery; no `discovery:done` is emitted for it.
+#[tauri::command]
+pub async fn discovery_stop() -> Result<bool, String> {
+    Ok(crate::lan_discovery::stop())
+}
+
+/// Saved PuTTY sessions as importable connections; unencrypted `.ppk` keys are
+/// converted into the app's key directory (see [`crate::putty`]).
+#[tauri::command]
+pub async fn import_putty_sessions(
+    app: AppHandle,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let home = app.path().home_dir().map_err(crate::redact::error)?;
+    let keys_dir = get_data_dir(&app).join("keys");
+    tokio::task::spawn_blocking(move || {
+        crate::putty::import_sessions(crate::putty::read_sessions(&home), &keys_dir)
+    })
+    .await
+    .map_err(crate::redact::error)
+}
+
+/// Helper to internalize a single key file
+fn internalize_key(path: &str, data_dir: &std::path::Path) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+
+    let src_path = std::path::Path::new(path);
+
+    // Canonicalize paths to ensure robust comparison
+    let data_dir_canonical = data_dir
+        .canonicalize()
+        .unwrap_or_else(|_| data_dir.to_path_buf());
+    let src_path_canonical = src_path
+        .canonicalize()
+        .unwrap_or_else(|_| src_path.to_path_buf());
+
+    // If already in data dir, return as is (but maybe canonicalized)
+    if src_path_canonical.starts_with(&data_dir_canonical) {
+        return None;
+    }
+
+    if !src_path.exists() || !src_path.is_file() {
+        // If we can't find it, we can't copy it.
+        return None;
+    }
+
+    let keys_dir = data_dir.join("keys");
+    if !keys_dir.exists() {
+        let _ = std::fs::create_dir_all(&keys_dir);
+    }
+
+    let filename = src_path.file_name().unwrap_or_default().to_string_lossy();
+
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    let hash = hasher.finish();
+    let dest_filename = format!("{:x}_{}", hash, filename);
+    let dest_path = keys_dir.join(dest_filename);
+
+    if dest_path.exists() {
+        // Already exists? Use it.
+        return Some(dest_path.to_string_lossy().to_string());
+    }
+
+    match crate::vault::at_rest::copy_in(src_path, &dest_path) {
+        Ok(_) => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                if let Ok(metadata) = std::fs::metadata(&dest_path) {
+                    let mut perms = metadata.permissions();
+                    perms.set_mode(0o600);
+                    let _ = std::fs::set_permissions(&dest_path, perms);
+                }
+            }
+            Some(dest_path.to_string_lossy().to_string())
+        }
+        Err(e) => {
+            crate::log_error!(
+                "[SSH Internalize] Failed to copy key from {:?} to {:?}: {}",
+                src_path,
+                dest_path,
+                e
+            );
+            None
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn ssh_internalize_connections(
+    app: AppHandle,
+    connections: Vec<crate::ssh_config::ParsedSshConnection>,
+) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
+    let data_dir = get_data_dir(&app);
+    let mut updated_connections = connections.clone();
+    let mut internalized_count = 0;
+
+    for conn in &mut updated_connections {
+        if let Some(path) = &conn.private_key_path {
+            if let Some(new_path) = internalize_key(path, &data_dir) {
+                conn.private_key_path = Some(new_path);
+                internalized_count += 1;
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    crate::log_info!(
+        "[SSH Internalize] Internalized keys for {} connections",
+        internalized_count
+    );
+    Ok(updated_connections)
+}
+
+// Snippets Commands
+use crate::snippets::Snippet;
+
+/// Saved snippets, optionally narrowed to a folder (and its subfolders), a tag,
+/// and those that apply to `connection_id` given its scope and detected OS.
+#[tauri::command]
+pub async fn snippets_list(
+    connection_id: Option<String>,
+    folder: Option<String>,
+    tag: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Snippet>, String> {
+    let mut snippets = state.snippets_manager.list().await?;
+    if let Some(folder) = folder.as_deref() {
+        snippets.retain(|snippet| snippet.in_folder(folder));
+    }
+    if let Some(tag) = tag.as_deref().filter(|tag| !tag.trim().is_empty()) {
+        snippets.retain(|snippet| snippet.has_tag(tag));
+    }
+    if let Some(connection_id) = connection_id.as_deref() {
+        let detected_os = if connection_id == "local" {
+            Some(crate::snippets::local_os())
+        } else {
+            state
+                .connections
+                .lock()
+                .await
+                .get(connection_id)
+                .and_then(|handle| handle.detected_os.clone())
+        };
+        snippets.retain(|snippet| snippet.applies_to(connection_id, detected_os.as_deref()));
+    }
+    Ok(snippets)
+}
+
+#[tauri::command]
+pub async fn snippets_save(snippet: Snippet, state: State<'_, AppState>) -> Result<(), String> {
+    state.snippets_manager.save(snippet).await
+}
+
+#[tauri::command]
+pub async fn snippets_delete(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.snippets_manager.delete(id).await?;
+    let snippets = state.snippets_manager.list().await?;
+    state.snippet_scheduler.forget_removed(&snippets)
+}
+
+/// Every scheduled snippet with its next and last run.
+#[tauri::command]
+pub async fn snippets_schedule_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::snippet_schedule::ScheduleStatus>, String> {
+    let snippets = state.snippets_manager.list().await?;
+    Ok(state.snippet_scheduler.status(&snippets))
+}
+
+/// Recorded scheduled runs, newest first, of one snippet or of all of them.
+#[tauri::command]
+pub async fn snippets_schedule_history(
+    snippet_id: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::snippet_schedule::ScheduledRun>, String> {
+    Ok(state
+        .snippet_scheduler
+        .history(snippet_id.as_deref(), limit))
+}
+
+/// Commands from `~/.zsh_history` and `~/.bash_history`, locally or on
+/// `connection_id` over SFTP, ranked as snippet candidates (see
+/// [`crate::snippets::rank_history`]). Missing history files are skipped.
+#[tauri::command]
+pub async fn snippets_import_history(
+    connection_id: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::snippets::HistoryCandidate>, String> {
+    const READ_TIMEOUT_SECS: u64 = 10;
+
+    let connection_id = connection_id.unwrap_or_else(|| "local".to_string());
+    let home = ghost_fs_cwd(&state, &connection_id).await?;
+    let mut histories = Vec::new();
+    for path in crate::ghost::history_seed::history_file_paths(&home) {
+        let content = if connection_id == "local" {
+            match std::fs::read(&path) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(_) => continue,
+            }
+        } else {
+            match read_remote_connection_file(&state, &connection_id, &path, READ_TIMEOUT_SECS)
+                .await
+            {
+                Ok(content) => content,
+                Err(e) => {
+                    crate::log_error!("[SNIPPETS] Skipping {}: {}", path, e);
+                    continue;
+                }
+            }
+        };
+        let file_name = path.rsplit('/').next().unwrap_or(&path).to_string();
+        histories.push((
+            file_name,
+            crate::ghost::history_import::parse_shell_history(&content),
+        ));
+    }
+    let saved = state.snippets_manager.list().await?;
+    Ok(crate::snippets::rank_history(&histories, &saved, limit))
 }
 
+/// Runs a snippet on several connections through the `ssh_exec_batch` runner,
+/// so output and results stream as `exec-batch:output` / `exec-batch:host`.
+/// `params` fill the snippet's `{{name}}` placeholders. Hosts outside the
+/// snippet's connection or OS scope are reported as failed without running it.
 #[tauri::command]
-pub async fn window_close(app: AppHandle) -> Result<(), String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
-    window.close().map_err(|e| e.to_string())
+pub async fn snippets_run(
+    app: AppHandle,
+    snippet_id: String,
+    connection_ids: Vec<String>,
+    params: Option<HashMap<String, String>>,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+    batch_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<std::collections::BTreeMap<String, crate::exec_batch::BatchHostResult>, String> {
+    let snippet = state
+        .snippets_manager
+        .list()
+        .await?
+        .into_iter()
+        .find(|snippet| snippet.id == snippet_id)
+        .ok_or_else(|| crate::redact::error(format!("Snippet {} not found", snippet_id)))?;
+    let command = snippet.render(&params.unwrap_or_default())?;
+    if command.trim().is_empty() {
+        return Err("Command is empty".to_string());
+    }
+    let batch_id = batch_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let (targets, skipped): (Vec<_>, Vec<_>) = exec_batch_targets(&connection_ids, &state)
+        .await
+        .into_iter()
+        .partition(|target| {
+            snippet.applies_to(&target.connection_id, target.detected_os.as_deref())
+        });
+    for target in &targets {
+        audit_command(&app, &target.connection_id, &command, "snippets_run").await;
+    }
+    let mut results = run_exec_batch(
+        &app,
+        targets,
+        command,
+        concurrency,
+        timeout_secs,
+        batch_id.clone(),
+    )
+    .await;
+    for target in skipped {
+        let result = crate::exec_batch::BatchHostResult {
+            batch_id: batch_id.clone(),
+            connection_id: target.connection_id.clone(),
+            connection_name: target.connection_name,
+            host: target.host,
+            exit_status: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            truncated: false,
+            duration_ms: 0,
+            error: Some(format!(
+                "Snippet '{}' is not scoped to this host",
+                snippet.name
+            )),
+        };
+        let _ = app.emit("exec-batch:host", &result);
+        results.insert(target.connection_id, result);
+    }
+    Ok(results)
+}
+
+/// Snippets, command history and connected hosts ranked by meaning rather
+/// than keywords (see [`crate::semantic_index`]). `kinds` limits the sources.
+#[tauri::command]
+pub async fn semantic_search(
+    app: AppHandle,
+    query: String,
+    kinds: Option<Vec<crate::semantic_index::DocKind>>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<crate::semantic_index::SemanticSearchResult, String> {
+    use crate::ai::embeddings::Embedder;
+    use crate::semantic_index::{DocKind, SemanticIndex, SemanticSearchResult};
+
+    if query.trim().is_empty() {
+        return Ok(SemanticSearchResult::default());
+    }
+    let wanted = |kind: DocKind| kinds.as_ref().is_none_or(|k| k.contains(&kind));
+    let mut docs = Vec::new();
+    if wanted(DocKind::Snippet) {
+        let snippets = state.snippets_manager.list().await?;
+        docs.extend(snippets.iter().map(crate::semantic_index::snippet_doc));
+    }
+    if wanted(DocKind::History) {
+        let history = state
+            .pty_manager
+            .history()
+            .top(None, crate::semantic_index::MAX_HISTORY_DOCS);
+        docs.extend(history.iter().map(crate::semantic_index::history_doc));
+    }
+    if wanted(DocKind::Host) {
+        let connections = state.connections.lock().await;
+        docs.extend(connections.values().map(|conn| {
+            crate::semantic_index::host_doc(
+                &conn.config,
+                conn.detected_os.as_deref(),
+                conn.detected_shell.as_deref(),
+            )
+        }));
+    }
+
+    let config = crate::ai::read_ai_config(&app);
+    let path = crate::semantic_index::index_path(&get_data_dir(&app));
+    let _guard = crate::semantic_index::INDEX_LOCK.lock().await;
+    let mut index = SemanticIndex::load(&path);
+    let mut embedder = Embedder::for_config(&config);
+    let mut warning = None;
+    let mut query_vector = loop {
+        let missing = index.prepare(&embedder.id(), &docs);
+        let mut texts: Vec<String> = missing.iter().map(|i| docs[*i].text.clone()).collect();
+        texts.push(query.clone());
+        match embedder.embed(&config, &texts).await {
+            Ok(mut vectors) => {
+                let query_vector = vectors.pop().unwrap_or_default();
+                for (i, vector) in missing.into_iter().zip(vectors) {
+                    index.insert(&docs[i], vector);
+                }
+                break query_vector;
+            }
+            Err(e) if embedder != Embedder::Local => {
+                crate::log_error!("[AI] Embeddings via {} failed: {}", embedder.id(), e);
+                warning = Some(e);
+                embedder = Embedder::Local;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    if let Err(e) = index.save(&path) {
+        crate::log_error!("[AI] Failed to save semantic index: {}", e);
+    }
+
+    crate::ai::embeddings::normalize(&mut query_vector);
+    Ok(SemanticSearchResult {
+        model: embedder.id(),
+        hits: index.search(
+            &query_vector,
+            &docs,
+            limit.unwrap_or(crate::semantic_index::DEFAULT_LIMIT),
+        ),
+        warning,
+    })
+}
+
+/// Deletes the cached embeddings; the next search rebuilds them.
+#[tauri::command]
+pub async fn semantic_index_clear(app: AppHandle) -> Result<(), String> {
+    let path = crate::semantic_index::index_path(&get_data_dir(&app));
+    let _guard = crate::semantic_index::INDEX_LOCK.lock().await;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn probes_list(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::probes::HealthProbe>, String> {
+    state.probes_manager.list()
+}
+
+#[tauri::command]
+pub async fn probes_save(
+    probes: Vec<crate::probes::HealthProbe>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.probes_manager.save(probes)
+}
+
+#[tauri::command]
+pub async fn probes_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::probes::ProbeStatus>, String> {
+    state.probes_manager.status()
+}
+
+#[tauri::command]
+pub async fn inbox_list(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::inbox::InboxWatch>, String> {
+    state.inbox_manager.list()
+}
+
+#[tauri::command]
+pub async fn inbox_save(
+    watches: Vec<crate::inbox::InboxWatch>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.inbox_manager.save(watches)
+}
+
+#[tauri::command]
+pub async fn inbox_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::inbox::InboxStatus>, String> {
+    state.inbox_manager.status()
+}
+
+#[tauri::command]
+pub async fn connections_health(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::connection_health::ConnectionHealth>, String> {
+    Ok(state.health_monitor.status())
+}
+
+#[tauri::command]
+pub async fn connections_health_settings_get(
+    state: State<'_, AppState>,
+) -> Result<crate::connection_health::HealthSettings, String> {
+    Ok(state.health_monitor.settings())
+}
+
+#[tauri::command]
+pub async fn connections_health_settings_save(
+    settings: crate::connection_health::HealthSettings,
+    state: State<'_, AppState>,
+) -> Result<crate::connection_health::HealthSettings, String> {
+    state.health_monitor.save_settings(settings)
+}
+
+/// Per-host connection limits; see [`crate::host_limits`].
+#[tauri::command]
+pub async fn ssh_host_limits_get(
+    state: State<'_, AppState>,
+) -> Result<crate::host_limits::HostLimitSettings, String> {
+    Ok(state.ssh_manager.host_limits.settings())
+}
+
+#[tauri::command]
+pub async fn ssh_host_limits_save(
+    settings: crate::host_limits::HostLimitSettings,
+    state: State<'_, AppState>,
+) -> Result<crate::host_limits::HostLimitSettings, String> {
+    state.ssh_manager.host_limits.save_settings(settings)
+}
+
+// Keystroke Macro Commands
+use crate::macros::KeystrokeMacro;
+
+fn macros_path(app: &AppHandle) -> std::path::PathBuf {
+    get_data_dir(app).join("macros.json")
+}
+
+#[tauri::command]
+pub async fn macros_list(app: AppHandle) -> Result<Vec<KeystrokeMacro>, String> {
+    crate::macros::read_macros(&macros_path(&app))
+}
+
+#[tauri::command]
+pub async fn macro_record_start(term_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .pty_manager
+        .start_macro_recording(&term_id)
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// Stops recording and saves the captured input as a new macro (nothing is saved
+/// when no keys were typed).
+#[tauri::command]
+pub async fn macro_record_stop(
+    app: AppHandle,
+    term_id: String,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<KeystrokeMacro>, String> {
+    let steps = state
+        .pty_manager
+        .stop_macro_recording(&term_id)
+        .map_err(crate::redact::error)?;
+    if steps.is_empty() {
+        return Ok(None);
+    }
+    let created_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let recorded = KeystrokeMacro {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Untitled macro".to_string()),
+        steps,
+        created_at: Some(created_at),
+    };
+    let _guard = crate::macros::MACROS_MUTATION_LOCK
+        .lock()
+        .map_err(crate::redact::error)?;
+    let path = macros_path(&app);
+    let mut macros = crate::macros::read_macros(&path)?;
+    macros.push(recorded.clone());
+    crate::macros::write_macros(&path, macros)?;
+    Ok(Some(recorded))
+}
+
+/// Replays a saved macro into `term_id`; `speed` scales the recorded pauses
+/// (default 1.0, 2.0 = twice as fast).
+#[tauri::command]
+pub async fn macro_play(
+    app: AppHandle,
+    term_id: String,
+    macro_id: String,
+    speed: Option<f64>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || !(0.1..=100.0).contains(&speed) {
+        return Err("Playback speed must be between 0.1 and 100".to_string());
+    }
+    let recorded = crate::macros::read_macros(&macros_path(&app))?
+        .into_iter()
+        .find(|m| m.id == macro_id)
+        .ok_or_else(|| crate::redact::error(format!("Macro {} not found", macro_id)))?;
+    state
+        .pty_manager
+        .play_macro(&term_id, &recorded.steps, speed)
+        .await
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn macros_rename(app: AppHandle, id: String, name: String) -> Result<(), String> {
+    let _guard = crate::macros::MACROS_MUTATION_LOCK
+        .lock()
+        .map_err(crate::redact::error)?;
+    let path = macros_path(&app);
+    let mut macros = crate::macros::read_macros(&path)?;
+    let recorded = macros
+        .iter_mut()
+        .find(|m| m.id == id)
+        .ok_or_else(|| crate::redact::error(format!("Macro {} not found", id)))?;
+    recorded.name = name;
+    crate::macros::write_macros(&path, macros)
+}
+
+#[tauri::command]
+pub async fn macros_delete(app: AppHandle, id: String) -> Result<(), String> {
+    let _guard = crate::macros::MACROS_MUTATION_LOCK
+        .lock()
+        .map_err(crate::redact::error)?;
+    let path = macros_path(&app);
+    let mut macros = crate::macros::read_macros(&path)?;
+    macros.retain(|m| m.id != id);
+    crate::macros::write_macros(&path, macros)
+}
+
+// Output Trigger Commands
+use crate::triggers::{TriggerAction, TriggerFire, TriggerRule};
+
+fn triggers_path(app: &AppHandle) -> std::path::PathBuf {
+    get_data_dir(app).join("triggers.json")
+}
+
+/// Loads the saved rules into the engine and performs fired actions for the app's lifetime.
+fn spawn_trigger_dispatcher(app: AppHandle, pty_manager: &PtyManager, data_dir: &Path) {
+    match crate::triggers::read_triggers(&data_dir.join("triggers.json")) {
+        Ok(rules) => pty_manager.triggers().set_rules(rules),
+        Err(e) => crate::log_error!("[TERM] Failed to load triggers: {}", e),
+    }
+    let Some(mut receiver) = pty_manager.triggers().take_receiver() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        while let Some(fire) = receiver.recv().await {
+            run_trigger_actions(&app, fire).await;
+        }
+    });
+}
+
+async fn run_trigger_actions(app: &AppHandle, fire: TriggerFire) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    for action in &fire.actions {
+        let result = match action {
+            TriggerAction::SendText { text } => {
+                match approve_terminal_write(app, &state, &fire.term_id, text, None).await {
+                    Ok(()) => state
+                        .pty_manager
+                        .write(&fire.term_id, text)
+                        .await
+                        .map_err(crate::redact::error),
+                    Err(e) => Err(e),
+                }
+            }
+            TriggerAction::Notify { title, message } => app
+                .emit(
+                    "trigger:notify",
+                    serde_json::json!({
+                        "termId": fire.term_id,
+                        "connectionId": fire.connection_id,
+                        "ruleId": fire.rule_id,
+                        "title": title.clone().unwrap_or_else(|| fire.rule_name.clone()),
+                        "message": message,
+                        "matched": fire.matched,
+                    }),
+                )
+                .map_err(crate::redact::error),
+            TriggerAction::StartTunnel { tunnel_id } => crate::tunnels::commands::tunnel_start(
+                app.clone(),
+                tunnel_id.clone(),
+                state.clone(),
+            )
+            .await
+            .map(|_| ()),
+            TriggerAction::RunSnippet { snippet_id } => {
+                match state.snippets_manager.list().await.and_then(|snippets| {
+                    snippets
+                        .into_iter()
+                        .find(|snippet| &snippet.id == snippet_id)
+                        .ok_or_else(|| {
+                            crate::redact::error(format!("Snippet {} not found", snippet_id))
+                        })
+                }) {
+                    Ok(snippet) => {
+                        let data = format!("{}\r", snippet.command);
+                        match approve_terminal_write(app, &state, &fire.term_id, &data, None).await
+                        {
+                            Ok(()) => state
+                                .pty_manager
+                                .write(&fire.term_id, &data)
+                                .await
+                                .map_err(crate::redact::error),
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(e) = result {
+            crate::log_error!(
+                "[TERM] Trigger '{}' action failed in {}: {}",
+                fire.rule_name,
+                fire.term_id,
+                e
+            );
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn triggers_list(app: AppHandle) -> Result<Vec<TriggerRule>, String> {
+    crate::triggers::read_triggers(&triggers_path(&app))
+}
+
+/// Creates or replaces a rule (by id) and reloads the engine.
+#[tauri::command]
+pub async fn triggers_save(
+    app: AppHandle,
+    mut rule: TriggerRule,
+    state: State<'_, AppState>,
+) -> Result<TriggerRule, String> {
+    crate::triggers::compile(&rule)?;
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+    let _guard = crate::triggers::TRIGGERS_MUTATION_LOCK
+        .lock()
+        .map_err(crate::redact::error)?;
+    let path = triggers_path(&app);
+    let mut rules = crate::triggers::read_triggers(&path)?;
+    match rules.iter_mut().find(|existing| existing.id == rule.id) {
+        Some(existing) => *existing = rule.clone(),
+        None => rules.push(rule.clone()),
+    }
+    crate::triggers::write_triggers(&path, rules.clone())?;
+    state.pty_manager.triggers().set_rules(rules);
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn triggers_delete(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let _guard = crate::triggers::TRIGGERS_MUTATION_LOCK
+        .lock()
+        .map_err(crate::redact::error)?;
+    let path = triggers_path(&app);
+    let mut rules = crate::triggers::read_triggers(&path)?;
+    rules.retain(|rule| rule.id != id);
+    crate::triggers::write_triggers(&path, rules.clone())?;
+    state.pty_manager.triggers().set_rules(rules);
+    Ok(())
+}
+
+/// Pauses or resumes trigger matching for one terminal (on by default).
+#[tauri::command]
+pub async fn triggers_set_terminal_enabled(
+    term_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .pty_manager
+        .triggers()
+        .set_terminal_enabled(&term_id, enabled);
+    Ok(())
+}
+
+// Command History Commands
+use crate::command_history::HistoryEntry;
+
+const HISTORY_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+fn spawn_history_flusher(pty_manager: &PtyManager, data_dir: &Path) {
+    let history = pty_manager.history().clone();
+    if let Err(e) = history.load(&data_dir.join("command_history.json")) {
+        crate::log_error!("[TERM] Failed to load command history: {}", e);
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(HISTORY_FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = history.flush() {
+                crate::log_error!("[TERM] {}", e);
+            }
+        }
+    });
+}
+
+/// Commands run in terminals (captured through shell integration), newest first.
+/// Every word of `query` must appear; `connection_id` limits it to one host.
+#[tauri::command]
+pub async fn history_search(
+    query: String,
+    connection_id: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(state.pty_manager.history().search(
+        &query,
+        connection_id.as_deref(),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    ))
+}
+
+/// Most frequently run commands, for a "recently run on this host" palette.
+#[tauri::command]
+pub async fn history_top(
+    connection_id: Option<String>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<HistoryEntry>, String> {
+    Ok(state.pty_manager.history().top(
+        connection_id.as_deref(),
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT),
+    ))
+}
+
+#[tauri::command]
+pub async fn history_clear(
+    connection_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let history = state.pty_manager.history();
+    history.clear(connection_id.as_deref());
+    history.flush()
+}
+
+// Custom Actions Commands
+use crate::actions::CustomAction;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionRunResult {
+    pub command: String,
+    pub exit_code: u32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[tauri::command]
+pub async fn actions_list(state: State<'_, AppState>) -> Result<Vec<CustomAction>, String> {
+    state.actions_manager.list().await
+}
+
+#[tauri::command]
+pub async fn actions_save(action: CustomAction, state: State<'_, AppState>) -> Result<(), String> {
+    state.actions_manager.save(action).await
+}
+
+#[tauri::command]
+pub async fn actions_delete(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.actions_manager.delete(id).await
+}
+
+/// Expands an action for `connection_id` (and optionally a selected `path`)
+/// into the exact command line that would run.
+async fn render_action(
+    action: &CustomAction,
+    connection_id: &str,
+    path: Option<String>,
+    state: &AppState,
+) -> Result<String, String> {
+    use crate::actions::{ActionVars, Quoting};
+
+    if let Some(scope) = action.connection_id.as_deref() {
+        if scope != connection_id {
+            return Err(crate::redact::error(format!(
+                "Action '{}' is not available on this connection",
+                action.name
+            )));
+        }
+    }
+
+    let (vars, quoting) = if connection_id == "local" {
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default();
+        let vars = ActionVars {
+            path,
+            host: "localhost".to_string(),
+            user,
+            port: 0,
+        };
+        let quoting = if cfg!(windows) {
+            Quoting::PowerShell
+        } else {
+            Quoting::Posix
+        };
+        (vars, quoting)
+    } else {
+        let config = {
+            let connections = state.connections.lock().await;
+            connections
+                .get(connection_id)
+                .map(|c| c.config.clone())
+                .ok_or("Connection not found")?
+        };
+        let vars = ActionVars {
+            path,
+            host: config.host,
+            user: config.username,
+            port: config.port,
+        };
+        let quoting = if connection_is_windows(state, connection_id).await {
+            Quoting::PowerShell
+        } else {
+            Quoting::Posix
+        };
+        (vars, quoting)
+    };
+
+    crate::actions::render(&action.command, &vars, quoting)
 }
 
 #[tauri::command]
-pub async fn ssh_exec(
+pub async fn actions_preview(
+    action_id: String,
     connection_id: String,
-    command: String,
+    path: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    if connection_id == "local" {
-        // Execute local command
-        let (shell, arg) = if cfg!(target_os = "windows") {
-            ("powershell", "-Command")
+    let action = state.actions_manager.get(&action_id).await?;
+    render_action(&action, &connection_id, path, &state).await
+}
+
+#[tauri::command]
+pub async fn actions_run(
+    app: AppHandle,
+    action_id: String,
+    connection_id: String,
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ActionRunResult, String> {
+    let action = state.actions_manager.get(&action_id).await?;
+    let command = render_action(&action, &connection_id, path, &state).await?;
+    crate::log_info!("[ACTIONS] Running '{}' on {}", action.name, connection_id);
+    audit_command(&app, &connection_id, &command, "action").await;
+
+    let (exit_code, stdout, stderr) = if connection_id == "local" {
+        let mut cmd = if cfg!(windows) {
+            let mut cmd = tokio::process::Command::new("powershell");
+            cmd.args(["-NoProfile", "-Command", &command]);
+            cmd
         } else {
-            ("sh", "-c")
+            let mut cmd = tokio::process::Command::new("sh");
+            cmd.args(["-c", &command]);
+            cmd
         };
-
-        let output = std::process::Command::new(shell)
-            .arg(arg)
-            .arg(&command)
+        let output = cmd
             .output()
-            .map_err(|e| format!("Failed to execute local command: {}", e))?;
-
-        if output.status.success() {
-            String::from_utf8(output.stdout).map_err(|e| format!("Invalid UTF-8 output: {}", e))
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Command failed: {}", stderr))
-        }
+            .await
+            .map_err(|e| crate::redact::error(format!("Failed to run action: {}", e)))?;
+        let code = output.status.code().map(|c| c as u32).unwrap_or(255);
+        (code, output.stdout, output.stderr)
     } else {
-        // Execute SSH command
-        let connections = state.connections.lock().await;
-        if let Some(conn) = connections.get(&connection_id) {
-            if let Some(session) = &conn.session {
-                let mut channel = session
-                    .lock()
-                    .await
-                    .channel_open_session()
-                    .await
-                    .map_err(|e| e.to_string())?;
-                channel
-                    .exec(true, command)
-                    .await
-                    .map_err(|e| e.to_string())?;
+        exec_remote_capture(&connection_id, &command, &state).await?
+    };
 
-                let mut stdout = Vec::new();
-                let mut stderr = Vec::new();
-                let mut exit_status = 0;
+    Ok(ActionRunResult {
+        command,
+        exit_code,
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+    })
+}
 
-                while let Some(msg) = channel.wait().await {
-                    match msg {
-                        russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
-                        russh::ChannelMsg::ExtendedData { ref data, .. } => {
-                            stderr.extend_from_slice(data)
-                        }
-                        russh::ChannelMsg::ExitStatus { exit_status: code } => {
-                            exit_status = code;
-                        }
-                        _ => {}
-                    }
-                }
+#[tauri::command]
+pub async fn safe_mode_status() -> Result<crate::safe_mode::SafeModeStatus, String> {
+    Ok(crate::safe_mode::status())
+}
 
-                if exit_status == 0 {
-                    return String::from_utf8(stdout).map_err(|e| e.to_string());
-                } else {
-                    let err_str = String::from_utf8_lossy(&stderr);
-                    return Err(format!(
-                        "Remote command failed (Exit {}): {}",
-                        exit_status, err_str
-                    ));
-                }
-            }
-        }
-        Err("Connection not found".to_string())
+/// Moves settings.json aside (`settings.json.bak-<ms>`) so the next start uses
+/// defaults. Returns the backup path, if there was a file to move.
+#[tauri::command]
+pub async fn safe_mode_reset_settings(app: AppHandle) -> Result<Option<String>, String> {
+    let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
+    let settings_path = get_native_settings_path(&app)?;
+    if !settings_path.exists() {
+        return Ok(None);
     }
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup = settings_path.with_file_name(format!("settings.json.bak-{}", stamp));
+    std::fs::rename(&settings_path, &backup).map_err(crate::redact::error)?;
+    clear_data_dir_cache();
+    crate::log_info!("[SAFE] Settings reset; previous file kept at {:?}", backup);
+    Ok(Some(backup.to_string_lossy().to_string()))
 }
 
 #[tauri::command]
-pub async fn ssh_import_config(
+pub async fn safe_mode_disable_plugin(
     app: AppHandle,
-) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
-    let home = app.path().home_dir().map_err(|e| e.to_string())?;
-    let config_path = home.join(".ssh/config");
-
-    // println!("[SSH] Importing config from: {:?}", config_path);
-
-    crate::ssh_config::parse_config(&config_path).map_err(|e| e.to_string())
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::log_info!("[SAFE] Disabling plugin {}", id);
+    state.pty_manager.taps().forget_plugin(&id);
+    crate::plugins::PluginScanner::save_state(&app, id, false).map_err(crate::redact::error)
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct SshImportSourceRequest {
-    pub source_type: String,
-    pub path: Option<String>,
-    pub content: Option<String>,
+/// Starts a normal instance (without `--safe-mode`) and exits this one.
+#[tauri::command]
+pub async fn safe_mode_exit(app: AppHandle) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(crate::redact::error)?;
+    crate::safe_mode::mark_started();
+    std::process::Command::new(exe)
+        .args(crate::safe_mode::args_without_flag())
+        .spawn()
+        .map_err(|e| crate::redact::error(format!("Failed to relaunch: {}", e)))?;
+    app.exit(0);
+    Ok(())
 }
 
+/// Integrity checks behind the "opens to a blank screen" class of reports: data
+/// directory, JSON stores, private key permissions and the CLI link.
 #[tauri::command]
-pub async fn ssh_import_config_from_file(
-    path: String,
-) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
-    let normalized = path.trim();
-    if normalized.is_empty() {
-        return Err("Select an SSH config file path first.".to_string());
-    }
+pub async fn doctor_run(app: AppHandle) -> Result<crate::doctor::DoctorReport, String> {
+    use crate::doctor::{self, CheckStatus, DoctorFix};
 
-    let config_path = std::path::Path::new(normalized);
-    if !config_path.exists() {
-        return Err("SSH config file not found.".to_string());
-    }
-    if !config_path.is_file() {
-        return Err("Selected SSH config path is not a file.".to_string());
+    let mut checks = Vec::new();
+    let settings = read_effective_settings(&app).unwrap_or_else(|_| serde_json::json!({}));
+    let configured = settings
+        .get("dataPath")
+        .and_then(|v| v.as_str())
+        .filter(|p| !p.is_empty())
+        .map(std::path::PathBuf::from);
+    let data_dir = match &configured {
+        Some(dir) => dir.clone(),
+        None => get_data_dir(&app),
+    };
+    checks.extend(doctor::check_data_dir(&data_dir, configured.is_some()));
+
+    if let Ok(settings_path) = get_native_settings_path(&app) {
+        let mut check = doctor::check_json_store("store:settings", "settings.json", &settings_path);
+        if check.status == CheckStatus::Error {
+            if let Ok(good) = get_last_known_good_settings_path(&app) {
+                let parses = std::fs::read_to_string(&good)
+                    .ok()
+                    .is_some_and(|raw| serde_json::from_str::<Value>(&raw).is_ok());
+                if parses {
+                    check.fix = Some(DoctorFix::RestoreBackup {
+                        path: settings_path.to_string_lossy().to_string(),
+                        backup: good.to_string_lossy().to_string(),
+                    });
+                }
+            }
+        }
+        checks.push(check);
     }
-    let metadata = std::fs::metadata(config_path)
-        .map_err(|e| format!("Cannot stat SSH config file: {}", e))?;
-    if metadata.len() > MAX_IMPORT_TEXT_BYTES as u64 {
-        return Err("SSH config file too large (max 1 MiB).".to_string());
+    let store_dir = get_data_dir(&app);
+    for store in doctor::DATA_STORES {
+        checks.push(doctor::check_json_store(
+            &format!("store:{}", store.trim_end_matches(".json")),
+            store,
+            &store_dir.join(store),
+        ));
     }
-    crate::ssh_config::parse_config(config_path).map_err(|e| e.to_string())
-}
 
-#[tauri::command]
-pub async fn ssh_import_config_from_text(
-    content: String,
-) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
-    if content.trim().is_empty() {
-        return Ok(vec![]);
+    let saved = read_store_file(&store_dir.join("connections.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<SavedData>(&raw).ok());
+    let mut seen = HashSet::new();
+    for conn in saved.map(|data| data.connections).unwrap_or_default() {
+        let Some(key) = conn.private_key_path.as_deref().filter(|p| !p.is_empty()) else {
+            continue;
+        };
+        let key = crate::key_rotation::expand_home(key);
+        if seen.insert(key.clone()) {
+            checks.push(doctor::check_key_file(&conn.name, &key));
+        }
     }
 
-    if content.len() > MAX_IMPORT_TEXT_BYTES {
-        return Err("Pasted SSH config is too large (max 1 MiB).".to_string());
+    if cfg!(target_os = "linux") {
+        if let Some(home) = dirs::home_dir() {
+            let installed = std::env::var_os("APPIMAGE")
+                .map(std::path::PathBuf::from)
+                .or_else(|| std::env::current_exe().ok());
+            checks.push(doctor::check_cli_link(
+                &home.join(".local/bin/zync"),
+                installed.as_deref(),
+            ));
+        }
     }
 
-    crate::ssh_config::parse_config_text(&content).map_err(|e| e.to_string())
+    let report = doctor::DoctorReport::new(checks);
+    if !report.healthy {
+        crate::log_error!(
+            "[DOCTOR] Found problems: {:?}",
+            report
+                .checks
+                .iter()
+                .filter(|c| c.status == CheckStatus::Error)
+                .map(|c| &c.id)
+                .collect::<Vec<_>>()
+        );
+    }
+    Ok(report)
 }
 
+/// Applies a fix offered by `doctor_run`. Returns a human-readable summary.
 #[tauri::command]
-pub async fn ssh_import_config_by_source(
+pub async fn doctor_apply_fix(
     app: AppHandle,
-    request: SshImportSourceRequest,
-) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
-    match request.source_type.as_str() {
-        "default_ssh" => ssh_import_config(app).await,
-        "file" => {
-            let path = request.path.as_deref().unwrap_or("").trim().to_string();
-            if path.is_empty() {
-                return Err("Select an SSH config file path first.".to_string());
+    fix: crate::doctor::DoctorFix,
+) -> Result<String, String> {
+    use crate::doctor::DoctorFix;
+
+    crate::log_info!("[DOCTOR] Applying {:?}", fix);
+    match &fix {
+        DoctorFix::ResetDataPath => {
+            let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
+            let mut settings = read_effective_settings(&app)?;
+            if let Some(obj) = settings.as_object_mut() {
+                obj.remove("dataPath");
             }
-            ssh_import_config_from_file(path).await
+            persist_settings_json(&app, &settings)?;
+            clear_data_dir_cache();
+            Ok(format!(
+                "Using the default data directory {}",
+                get_data_dir(&app).display()
+            ))
         }
-        "text" => {
-            let content = request.content.as_deref().unwrap_or("").to_string();
-            if content.trim().is_empty() {
-                return Err("Paste SSH config text first.".to_string());
-            }
-
-            if content.len() > MAX_IMPORT_TEXT_BYTES {
-                return Err("Pasted SSH config is too large (max 1 MiB).".to_string());
-            }
-            ssh_import_config_from_text(content).await
+        DoctorFix::RestoreBackup { path, .. }
+            if get_native_settings_path(&app).is_ok_and(|p| p == Path::new(path)) =>
+        {
+            let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
+            let summary = crate::doctor::apply_fix(&fix).map_err(crate::redact::error)?;
+            clear_data_dir_cache();
+            Ok(summary)
         }
-        _ => Err("Unsupported SSH import source.".to_string()),
+        _ => crate::doctor::apply_fix(&fix).map_err(crate::redact::error),
     }
 }
 
-/// Helper to internalize a single key file
-fn internalize_key(path: &str, data_dir: &std::path::Path) -> Option<String> {
-    if path.is_empty() {
-        return None;
-    }
-
-    let src_path = std::path::Path::new(path);
+#[tauri::command]
+pub async fn app_launch_settings_get() -> Result<crate::launch_settings::LaunchFlags, String> {
+    Ok(crate::launch_settings::load())
+}
 
-    // Canonicalize paths to ensure robust comparison
-    let data_dir_canonical = data_dir
-        .canonicalize()
-        .unwrap_or_else(|_| data_dir.to_path_buf());
-    let src_path_canonical = src_path
-        .canonicalize()
-        .unwrap_or_else(|_| src_path.to_path_buf());
+/// Saves rendering workarounds (see `crate::launch_settings`) and restarts the app
+/// so they apply to the new webview.
+#[tauri::command]
+pub async fn app_relaunch_with(
+    app: AppHandle,
+    flags: crate::launch_settings::LaunchFlags,
+) -> Result<(), String> {
+    crate::launch_settings::save(&flags).map_err(crate::redact::error)?;
+    crate::launch_settings::clear_applied();
+    crate::log_info!("[LAUNCH] Relaunching with {:?}", flags);
+    app.restart()
+}
 
-    // If already in data dir, return as is (but maybe canonicalized)
-    if src_path_canonical.starts_with(&data_dir_canonical) {
-        return None;
-    }
+/// Proxy the webview should pass to requests it makes itself (update checks);
+/// `None` when the system configuration applies.
+#[tauri::command]
+pub async fn network_proxy_url() -> Result<Option<String>, String> {
+    Ok(crate::http_client::proxy_url())
+}
 
-    if !src_path.exists() || !src_path.is_file() {
-        // If we can't find it, we can't copy it.
-        return None;
-    }
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KillSwitchReport {
+    pub enabled: bool,
+    pub connections: usize,
+    pub terminals: usize,
+    pub tunnels: usize,
+    pub transfers: usize,
+}
 
-    let keys_dir = data_dir.join("keys");
-    if !keys_dir.exists() {
-        let _ = std::fs::create_dir_all(&keys_dir);
-    }
+#[tauri::command]
+pub async fn network_kill_switch_get() -> Result<bool, String> {
+    Ok(crate::kill_switch::is_engaged())
+}
 
-    let filename = src_path.file_name().unwrap_or_default().to_string_lossy();
+/// Engages or releases the network kill switch (see `crate::kill_switch`).
+/// Engaging blocks new connections first, then cancels transfers and AI agent
+/// runs, stops every tunnel and closes every SSH session with its terminals.
+#[tauri::command]
+pub async fn network_kill_switch_set(
+    app: AppHandle,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<KillSwitchReport, String> {
+    crate::kill_switch::set_engaged(enabled);
+    crate::log_info!(
+        "[NET] Kill switch {}",
+        if enabled { "engaged" } else { "released" }
+    );
+    let mut report = KillSwitchReport {
+        enabled,
+        ..Default::default()
+    };
 
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::new();
-    path.hash(&mut hasher);
-    let hash = hasher.finish();
-    let dest_filename = format!("{:x}_{}", hash, filename);
-    let dest_path = keys_dir.join(dest_filename);
+    if enabled {
+        for token in state.transfers.lock().await.values() {
+            token.store(true, std::sync::atomic::Ordering::Relaxed);
+            report.transfers += 1;
+        }
+        for token in state.agent_runs.lock().await.values() {
+            token.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
 
-    if dest_path.exists() {
-        // Already exists? Use it.
-        return Some(dest_path.to_string_lossy().to_string());
-    }
+        let connection_ids: Vec<String> = state.connections.lock().await.keys().cloned().collect();
+        for id in &connection_ids {
+            crate::plugin_hooks::emit(
+                &app,
+                crate::plugin_hooks::Hook::BeforeDisconnect,
+                crate::plugin_hooks::BeforeDisconnect { connection_id: id },
+            );
+        }
+        if let Err(error) =
+            crate::tunnels::stop_tunnels_for_connections(&app, &state, &connection_ids).await
+        {
+            crate::log_error!("[NET] Stopping saved tunnels: {error}");
+        }
+        report.tunnels = state.tunnel_manager.stop_all().await;
 
-    match std::fs::copy(src_path, &dest_path) {
-        Ok(_) => {
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Ok(metadata) = std::fs::metadata(&dest_path) {
-                    let mut perms = metadata.permissions();
-                    perms.set_mode(0o600);
-                    let _ = std::fs::set_permissions(&dest_path, perms);
+        let sessions: Vec<(String, Option<Arc<Mutex<Handle<Client>>>>)> = state
+            .connections
+            .lock()
+            .await
+            .drain()
+            .map(|(id, handle)| (id, handle.session))
+            .collect();
+        for (id, session) in sessions {
+            match state.pty_manager.close_by_connection(&id).await {
+                Ok(closed_terminals) => {
+                    for term_id in &closed_terminals {
+                        state_sync::notify(&app, StateKind::Terminal, term_id, ChangeKind::Removed);
+                    }
+                    report.terminals += closed_terminals.len();
                 }
+                Err(error) => crate::log_error!("[NET] Closing terminals of {id}: {error}"),
             }
-            Some(dest_path.to_string_lossy().to_string())
-        }
-        Err(e) => {
-            eprintln!(
-                "[SSH Internalize] Failed to copy key from {:?} to {:?}: {}",
-                src_path, dest_path, e
-            );
-            None
+            if let Some(session) = session {
+                let disconnect = async {
+                    session
+                        .lock()
+                        .await
+                        .disconnect(russh::Disconnect::ByApplication, "work offline", "en")
+                        .await
+                };
+                if tokio::time::timeout(Duration::from_secs(3), disconnect)
+                    .await
+                    .is_err()
+                {
+                    crate::log_error!("[NET] Session {id} did not acknowledge disconnect; dropped");
+                }
+            }
+            crate::listing_cache::forget_connection(&id);
+            state_sync::notify(&app, StateKind::Connection, &id, ChangeKind::Removed);
+            report.connections += 1;
         }
     }
-}
-
-#[tauri::command]
-pub async fn ssh_internalize_connections(
-    app: AppHandle,
-    connections: Vec<crate::ssh_config::ParsedSshConnection>,
-) -> Result<Vec<crate::ssh_config::ParsedSshConnection>, String> {
-    let data_dir = get_data_dir(&app);
-    let mut updated_connections = connections.clone();
-    let mut internalized_count = 0;
 
-    for conn in &mut updated_connections {
-        if let Some(path) = &conn.private_key_path {
-            if let Some(new_path) = internalize_key(path, &data_dir) {
-                conn.private_key_path = Some(new_path);
-                internalized_count += 1;
+    if crate::safe_mode::is_active() {
+        crate::log_error!("[NET] Safe mode: kill switch not persisted");
+    } else {
+        let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
+        let persisted = read_effective_settings(&app).and_then(|mut settings| {
+            if let Some(obj) = settings.as_object_mut() {
+                obj.insert(
+                    crate::kill_switch::SETTINGS_KEY.to_string(),
+                    Value::Bool(enabled),
+                );
             }
+            persist_settings_json(&app, &settings)
+        });
+        if let Err(error) = persisted {
+            crate::log_error!("[NET] Could not persist kill switch: {error}");
         }
     }
 
-    #[cfg(debug_assertions)]
-    println!(
-        "[SSH Internalize] Internalized keys for {} connections",
-        internalized_count
-    );
-    Ok(updated_connections)
-}
-
-// Snippets Commands
-use crate::snippets::Snippet;
-
-#[tauri::command]
-pub async fn snippets_list(state: State<'_, AppState>) -> Result<Vec<Snippet>, String> {
-    state.snippets_manager.list().await
-}
-
-#[tauri::command]
-pub async fn snippets_save(snippet: Snippet, state: State<'_, AppState>) -> Result<(), String> {
-    state.snippets_manager.save(snippet).await
+    let _ = app.emit("network:kill-switch", &report);
+    Ok(report)
 }
 
+/// Connections, terminals and tunnels as currently registered, for a window
+/// that is hydrating; see `crate::state_sync`.
 #[tauri::command]
-pub async fn snippets_delete(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    state.snippets_manager.delete(id).await
+pub async fn state_snapshot(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<state_sync::StateSnapshot, String> {
+    Ok(state_sync::snapshot(&app, &state).await)
 }
 
 #[tauri::command]
 pub async fn settings_get(app: AppHandle) -> Result<serde_json::Value, String> {
+    if crate::safe_mode::is_active() {
+        return Ok(Value::Object(serde_json::Map::new()));
+    }
     read_effective_settings(&app)
 }
 
 #[tauri::command]
 pub async fn settings_set(app: AppHandle, settings: serde_json::Value) -> Result<(), String> {
+    if crate::safe_mode::is_active() {
+        return Err(
+            "Settings are read-only in safe mode; reset them or restart normally".to_string(),
+        );
+    }
     let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
     let current = read_effective_settings(&app)?;
     let current_data_path = data_path_from_settings(&current);
@@ -4034,13 +8719,13 @@ pub async fn settings_get_path(app: AppHandle) -> Result<String, String> {
 pub async fn settings_read_raw(app: AppHandle) -> Result<SettingsFilePayload, String> {
     let path = get_native_settings_path(&app)?;
     let content = if path.exists() {
-        std::fs::read_to_string(&path).map_err(|e| e.to_string())?
+        std::fs::read_to_string(&path).map_err(crate::redact::error)?
     } else {
         let migrated = read_effective_settings(&app)?;
         if migrated.is_object() && !migrated.as_object().map(|o| o.is_empty()).unwrap_or(true) {
             format!(
                 "{}\n",
-                serde_json::to_string_pretty(&migrated).map_err(|e| e.to_string())?
+                serde_json::to_string_pretty(&migrated).map_err(crate::redact::error)?
             )
         } else {
             "{}\n".to_string()
@@ -4080,7 +8765,7 @@ pub async fn settings_write_raw(
     }
 
     let parsed: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Invalid JSON in settings.json: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Invalid JSON in settings.json: {}", e)))?;
     let validated = ensure_object_settings(parsed)?;
     validate_settings_schema(&validated)?;
 
@@ -4090,7 +8775,7 @@ pub async fn settings_write_raw(
         clear_data_dir_cache();
     }
 
-    let saved_content = std::fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
+    let saved_content = std::fs::read_to_string(&settings_path).map_err(crate::redact::error)?;
     let modified_ms = settings_mtime_ms(&settings_path);
     Ok(SettingsFilePayload {
         path: settings_path.to_string_lossy().to_string(),
@@ -4117,9 +8802,10 @@ pub async fn settings_restore_last_known_good(
         return Err("No last-known-good settings backup found.".to_string());
     }
 
-    let backup_content = std::fs::read_to_string(&backup_path).map_err(|e| e.to_string())?;
-    let parsed_backup = serde_json::from_str::<Value>(&backup_content)
-        .map_err(|e| format!("Invalid JSON in last-known-good backup: {}", e))?;
+    let backup_content = std::fs::read_to_string(&backup_path).map_err(crate::redact::error)?;
+    let parsed_backup = serde_json::from_str::<Value>(&backup_content).map_err(|e| {
+        crate::redact::error(format!("Invalid JSON in last-known-good backup: {}", e))
+    })?;
     let validated_backup = ensure_object_settings(parsed_backup)?;
     validate_settings_schema(&validated_backup)?;
     write_atomic_file(&settings_path, &backup_content)?;
@@ -4128,7 +8814,7 @@ pub async fn settings_restore_last_known_good(
         clear_data_dir_cache();
     }
 
-    let saved_content = std::fs::read_to_string(&settings_path).map_err(|e| e.to_string())?;
+    let saved_content = std::fs::read_to_string(&settings_path).map_err(crate::redact::error)?;
     let modified_ms = settings_mtime_ms(&settings_path);
     Ok(SettingsFilePayload {
         path: settings_path.to_string_lossy().to_string(),
@@ -4137,6 +8823,72 @@ pub async fn settings_restore_last_known_good(
     })
 }
 
+/// Moves the data directory to `new_path`. Every file is copied and verified
+/// first; then `dataPath` is pointed at the new location in the bootstrap
+/// settings and in a `settings.json` inside it. The old directory is kept, and
+/// a failure at any step leaves the previous location in effect.
+#[tauri::command]
+pub async fn storage_migrate(
+    app: AppHandle,
+    new_path: String,
+) -> Result<crate::storage_migration::MigrationReport, String> {
+    let _mutation_guard = SETTINGS_MUTATION_LOCK.lock().await;
+    if let Some(state) = app.try_state::<AppState>() {
+        if let Err(e) = state.pty_manager.history().flush() {
+            crate::log_error!("[DataDir] {}", e);
+        }
+    }
+
+    let previous = read_effective_settings(&app)?;
+    let source = get_data_dir(&app);
+    let target = std::path::PathBuf::from(new_path.trim());
+    let copied = {
+        let (source, target) = (source.clone(), target.clone());
+        tokio::task::spawn_blocking(move || {
+            crate::storage_migration::copy_verified(&source, &target)
+        })
+        .await
+        .map_err(crate::redact::error)??
+    };
+
+    let mut next = previous.clone();
+    if let Some(obj) = next.as_object_mut() {
+        obj.insert(
+            "dataPath".to_string(),
+            Value::String(target.to_string_lossy().to_string()),
+        );
+    }
+    let updated = serde_json::to_string_pretty(&next)
+        .map_err(crate::redact::error)
+        .and_then(|json| {
+            persist_settings_json(&app, &next)?;
+            write_atomic_file(&target.join("settings.json"), &json).inspect_err(|_| {
+                if let Err(e) = persist_settings_json(&app, &previous) {
+                    crate::log_error!("[DataDir] Failed to restore settings: {}", e);
+                }
+            })
+        });
+    if let Err(e) = updated {
+        copied.rollback();
+        return Err(crate::redact::error(format!(
+            "Failed to update settings: {}",
+            e
+        )));
+    }
+
+    clear_data_dir_cache();
+    let report = copied.report(&source);
+    crate::log_info!(
+        "[DataDir] Migrated {} files ({} bytes) from {} to {}",
+        report.files,
+        report.bytes,
+        report.from,
+        report.to
+    );
+    Ok(report)
+}
+
+use crate::transfer_pipeline::ProcessorChain;
 use tauri::Emitter;
 
 #[derive(Clone, serde::Serialize)]
@@ -4150,6 +8902,74 @@ struct TransferProgress {
 struct TransferSuccess {
     id: String,
     destination_connection_id: String,
+    /// Entries a recursive transfer left out (special files, hidden entries when asked).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<crate::fs::SkippedEntry>,
+    /// Files an upload with `skipUnchanged` did not send because the server had them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unchanged: Option<crate::transfer_dedupe::DedupeSummary>,
+}
+
+/// `transfer-success` for the UI, and the `transfer-completed` plugin hook.
+fn emit_transfer_success(app_handle: &AppHandle, success: TransferSuccess) {
+    crate::plugin_hooks::emit(
+        app_handle,
+        crate::plugin_hooks::Hook::TransferCompleted,
+        &success,
+    );
+    let _ = app_handle.emit("transfer-success", success);
+}
+
+/// Audit-trail entry for a command run outside a terminal; `source` says who ran it.
+pub(crate) async fn audit_command(
+    app: &AppHandle,
+    connection_id: &str,
+    command: &str,
+    source: &str,
+) {
+    let event = crate::audit::AuditEvent::new(
+        connection_id,
+        crate::audit::AuditKind::Command,
+        command.to_string(),
+        serde_json::json!({ "source": source }),
+    );
+    audit_record(app, event).await;
+}
+
+/// Audit-trail entry for deleted (or, with `soft`, trashed) paths.
+async fn audit_delete(app: &AppHandle, connection_id: &str, paths: &[String], soft: Option<bool>) {
+    let event = crate::audit::AuditEvent::new(
+        connection_id,
+        crate::audit::AuditKind::Delete,
+        paths.join(", "),
+        serde_json::json!({ "paths": paths, "soft": soft.unwrap_or(false) }),
+    );
+    audit_record(app, event).await;
+}
+
+/// Audit-trail entry for a finished transfer (the transfer "manifest" auditors export).
+async fn audit_transfer(
+    app: &AppHandle,
+    transfer_id: &str,
+    connection_id: &str,
+    direction: &str,
+    source: &str,
+    destination: &str,
+    skipped: &[crate::fs::SkippedEntry],
+) {
+    let event = crate::audit::AuditEvent::new(
+        connection_id,
+        crate::audit::AuditKind::Transfer,
+        format!("{} -> {}", source, destination),
+        serde_json::json!({
+            "transferId": transfer_id,
+            "direction": direction,
+            "source": source,
+            "destination": destination,
+            "skipped": skipped,
+        }),
+    );
+    audit_record(app, event).await;
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -4170,21 +8990,55 @@ fn upload_recursive<'a>(
     total_size: &'a mut u64,
     transferred: &'a mut u64,
     cancel_token: &'a std::sync::atomic::AtomicBool,
+    preserve_symlinks: bool,
+    processors: &'a ProcessorChain,
+    walk: &'a mut Walk,
+    unchanged: &'a HashSet<std::path::PathBuf>,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + 'a>> {
     Box::pin(async move {
+        if preserve_symlinks && local_path.is_symlink() {
+            let target = std::fs::read_link(local_path).map_err(|e| {
+                crate::redact::error(format!(
+                    "Failed to read link '{}': {}",
+                    local_path.display(),
+                    e
+                ))
+            })?;
+            return file_system
+                .create_symlink_remote(sftp, &target.to_string_lossy(), remote_path)
+                .await
+                .map_err(crate::redact::error);
+        }
+
+        // Opening a FIFO or device would block or stream forever.
+        let special = std::fs::metadata(local_path)
+            .ok()
+            .and_then(|meta| SkipReason::from_local(&meta));
+        if walk.skip_special(&local_path.to_string_lossy(), special)? {
+            return Ok(());
+        }
+
         if local_path.is_dir() {
             // Create remote directory
             let _ = file_system.create_dir_remote(sftp, remote_path).await;
 
-            for entry in std::fs::read_dir(local_path).map_err(|e| e.to_string())? {
-                let entry = entry.map_err(|e| e.to_string())?;
-                let path = entry.path();
-                let name = entry.file_name().to_string_lossy().to_string();
-                let new_remote = if remote_path.ends_with('/') {
-                    format!("{}{}", remote_path, name)
-                } else {
-                    format!("{}/{}", remote_path, name)
-                };
+            let mut names = Vec::new();
+            for entry in std::fs::read_dir(local_path).map_err(crate::redact::error)? {
+                let entry = entry.map_err(crate::redact::error)?;
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+            let existing = remote_dir_names(sftp, remote_path)
+                .await
+                .unwrap_or_default();
+            let children = walk.plan_children(&names, &existing, |name| {
+                local_path.join(name).to_string_lossy().to_string()
+            });
+            for (name, remote_name) in children {
+                let path = local_path.join(&name);
+                if walk.skip_child(&path.to_string_lossy(), &name) {
+                    continue;
+                }
+                let new_remote = crate::remote_path::join(remote_path, &remote_name);
 
                 upload_recursive(
                     sftp,
@@ -4196,13 +9050,18 @@ fn upload_recursive<'a>(
                     total_size,
                     transferred,
                     cancel_token,
+                    preserve_symlinks,
+                    processors,
+                    walk,
+                    unchanged,
                 )
                 .await?;
             }
+        } else if unchanged.contains(local_path) {
+            // Identical on the server already; nothing to send.
         } else {
             // Upload file with chunked progress
             use russh_sftp::protocol::OpenFlags;
-            use tokio::io::AsyncWriteExt;
 
             // Open remote file
             let mut remote_file = sftp
@@ -4211,7 +9070,12 @@ fn upload_recursive<'a>(
                     OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
                 )
                 .await
-                .map_err(|e| format!("Failed to open remote file '{}': {}", remote_path, e))?;
+                .map_err(|e| {
+                    crate::redact::error(format!(
+                        "Failed to open remote file '{}': {}",
+                        remote_path, e
+                    ))
+                })?;
 
             // Full-Duplex Channel (Pipes local reads to remote writes)
             let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, String>>(4);
@@ -4223,7 +9087,12 @@ fn upload_recursive<'a>(
                 let mut file = match tokio::fs::File::open(local_path_buf).await {
                     Ok(f) => f,
                     Err(e) => {
-                        let _ = tx.send(Err(format!("Local open failed: {}", e))).await;
+                        let _ = tx
+                            .send(Err(crate::redact::error(format!(
+                                "Local open failed: {}",
+                                e
+                            ))))
+                            .await;
                         return;
                     }
                 };
@@ -4238,7 +9107,12 @@ fn upload_recursive<'a>(
                             }
                         }
                         Err(e) => {
-                            let _ = tx.send(Err(format!("Local read failed: {}", e))).await;
+                            let _ = tx
+                                .send(Err(crate::redact::error(format!(
+                                    "Local read failed: {}",
+                                    e
+                                ))))
+                                .await;
                             break;
                         }
                     }
@@ -4246,6 +9120,8 @@ fn upload_recursive<'a>(
             });
 
             let mut last_emit = std::time::Instant::now();
+            let mut pipeline = processors.start();
+            let mut written = 0u64;
 
             // Main loop: Receive from reader and Write to Server concurrently
             while let Some(chunk_res) = rx.recv().await {
@@ -4253,14 +9129,26 @@ fn upload_recursive<'a>(
                 if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
                     return Err("Cancelled".to_string());
                 }
+                let chunk = if processors.is_empty() {
+                    chunk
+                } else {
+                    pipeline.process(&chunk).map_err(crate::redact::error)?
+                };
 
-                remote_file
-                    .write_all(&chunk)
-                    .await
-                    .map_err(|e| format!("SFTP write failed: {}", e))?;
+                crate::transfer_retry::write_chunk(
+                    sftp,
+                    &mut remote_file,
+                    remote_path,
+                    written,
+                    &chunk,
+                )
+                .await?;
 
                 let n = chunk.len();
+                written += n as u64;
                 *transferred += n as u64;
+                // Processed output can outgrow the pre-transform estimate.
+                *total_size = (*total_size).max(*transferred);
 
                 if last_emit.elapsed().as_millis() >= 100 {
                     let _ = app.emit(
@@ -4274,11 +9162,125 @@ fn upload_recursive<'a>(
                     last_emit = std::time::Instant::now();
                 }
             }
+
+            if !processors.is_empty() {
+                let tail = pipeline.finish().map_err(crate::redact::error)?;
+                crate::transfer_retry::write_chunk(
+                    sftp,
+                    &mut remote_file,
+                    remote_path,
+                    written,
+                    &tail,
+                )
+                .await?;
+                *transferred += tail.len() as u64;
+                *total_size = (*total_size).max(*transferred);
+            }
         }
         Ok(())
     })
 }
 
+/// Local-to-local copy through a processor chain.
+fn copy_local_processed(
+    source: &std::path::Path,
+    destination: &std::path::Path,
+    processors: &ProcessorChain,
+) -> Result<(), String> {
+    use std::io::{Read, Write};
+
+    let mut input = std::fs::File::open(source).map_err(crate::redact::error)?;
+    let mut output = std::fs::File::create(destination).map_err(crate::redact::error)?;
+    let mut pipeline = processors.start();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        let n = input.read(&mut buffer).map_err(crate::redact::error)?;
+        if n == 0 {
+            break;
+        }
+        let data = pipeline
+            .process(&buffer[..n])
+            .map_err(crate::redact::error)?;
+        output.write_all(&data).map_err(crate::redact::error)?;
+    }
+    let tail = pipeline.finish().map_err(crate::redact::error)?;
+    output.write_all(&tail).map_err(crate::redact::error)
+}
+
+/// Runs an in-memory buffer through a processor chain (SCP transfers are whole-file).
+fn apply_processors(processors: &ProcessorChain, content: &[u8]) -> Result<Vec<u8>, String> {
+    if processors.is_empty() {
+        return Ok(content.to_vec());
+    }
+    let mut pipeline = processors.start();
+    let mut out = pipeline.process(content).map_err(crate::redact::error)?;
+    out.extend(pipeline.finish().map_err(crate::redact::error)?);
+    Ok(out)
+}
+
+fn local_file_mode(path: &std::path::Path) -> u32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(meta) = std::fs::metadata(path) {
+            return meta.permissions().mode() & 0o7777;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    0o644
+}
+
+#[tauri::command]
+pub async fn transfer_processors_list(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::transfer_pipeline::ProcessorInfo>, String> {
+    crate::plugin_wasm::sync_processors(&app, &state.transfer_processors);
+    Ok(state.transfer_processors.list())
+}
+
+/// Files of an upload the server already holds with the same SHA-256, hashed
+/// remotely in one exec per path batch. On any error nothing is skipped.
+async fn unchanged_upload_files(
+    state: &State<'_, AppState>,
+    connection_id: &str,
+    local: &std::path::Path,
+    remote: &str,
+) -> (
+    HashSet<std::path::PathBuf>,
+    crate::transfer_dedupe::DedupeSummary,
+) {
+    let local_root = local.to_path_buf();
+    let remote_root = remote.to_string();
+    let Ok(files) = tokio::task::spawn_blocking(move || {
+        crate::transfer_dedupe::list_files(&local_root, &remote_root)
+    })
+    .await
+    else {
+        return Default::default();
+    };
+    let paths: Vec<&str> = files.iter().map(|f| f.remote.as_str()).collect();
+    let mut remote_digests = HashMap::new();
+    for command in crate::transfer_dedupe::hash_commands(&paths) {
+        match exec_remote_capture(connection_id, &command, state).await {
+            Ok((_, stdout, _)) => {
+                remote_digests.extend(crate::transfer_dedupe::parse_digests(&stdout))
+            }
+            Err(e) => {
+                crate::log_error!("[FS] Remote checksums failed, uploading everything: {}", e);
+                return Default::default();
+            }
+        }
+    }
+    if remote_digests.is_empty() {
+        return Default::default();
+    }
+    tokio::task::spawn_blocking(move || crate::transfer_dedupe::unchanged(&files, &remote_digests))
+        .await
+        .unwrap_or_default()
+}
+
 // Helper to calculate local size or directory size recursively
 fn get_local_size(path: &std::path::Path) -> u64 {
     if path.is_dir() {
@@ -4301,8 +9303,23 @@ pub async fn sftp_put(
     local_path: String,
     remote_path: String,
     transfer_id: String,
+    preserve_symlinks: Option<bool>,
+    processors: Option<Vec<String>>,
+    walk_flags: Option<WalkFlags>,
+    skip_unchanged: Option<bool>,
     _state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let preserve_symlinks = preserve_symlinks.unwrap_or(false);
+    let skip_unchanged = skip_unchanged.unwrap_or(false);
+    if processors.as_ref().is_some_and(|names| !names.is_empty()) {
+        crate::plugin_wasm::sync_processors(&app, &_state.transfer_processors);
+    }
+    let processors = _state
+        .transfer_processors
+        .chain(&processors.unwrap_or_default())
+        .map_err(crate::redact::error)?;
+    let mut walk = Walk::new(walk_flags.unwrap_or_default());
+    walk.name_rules = remote_name_rules(&_state, &id).await;
     // Spawn background task
     let app_handle = app.clone();
     let connection_id = id.clone();
@@ -4322,6 +9339,7 @@ pub async fn sftp_put(
     tauri::async_runtime::spawn(async move {
         // Retrieve state inside task
         let state = app_handle.state::<AppState>();
+        let mut unchanged_summary = None;
 
         let result = async {
             if connection_id == "local" {
@@ -4331,13 +9349,55 @@ pub async fn sftp_put(
                     // Todo recursive local
                     return Err("Local directory copy not yet implemented".to_string());
                 }
-                std::fs::copy(&local, &remote).map_err(|e| e.to_string())?;
+                if processors.is_empty() {
+                    std::fs::copy(&local, &remote).map_err(crate::redact::error)?;
+                } else {
+                    copy_local_processed(path, std::path::Path::new(&remote), &processors)?;
+                }
+            } else if uses_scp_fallback(&state, &connection_id).await {
+                let path = std::path::Path::new(&local);
+                if path.is_dir() {
+                    return Err(
+                        "Folder uploads need SFTP, which this server does not offer".to_string()
+                    );
+                }
+                let content = std::fs::read(path).map_err(crate::redact::error)?;
+                let content = apply_processors(&processors, &content)?;
+                let channel =
+                    open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+                state
+                    .file_system
+                    .write_scp(channel, &remote, &content, local_file_mode(path))
+                    .await
+                    .map_err(crate::redact::error)?;
+                let _ = app_handle.emit(
+                    "transfer-progress",
+                    TransferProgress {
+                        id: tid.clone(),
+                        transferred: content.len() as u64,
+                        total: content.len() as u64,
+                    },
+                );
             } else {
                 let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
                 let path = std::path::Path::new(&local);
 
+                // Processed uploads never match the local digest, so only plain ones are compared.
+                let unchanged = if skip_unchanged
+                    && processors.is_empty()
+                    && !connection_is_windows(&state, &connection_id).await
+                {
+                    let (files, summary) =
+                        unchanged_upload_files(&state, &connection_id, path, &remote).await;
+                    unchanged_summary = Some(summary);
+                    files
+                } else {
+                    HashSet::new()
+                };
+
                 // Calculate total size for progress bar
-                let mut total_size = get_local_size(path);
+                let mut total_size = get_local_size(path)
+                    .saturating_sub(unchanged_summary.as_ref().map_or(0, |s| s.bytes_saved));
                 if total_size == 0 {
                     total_size = 1;
                 } // Avoid division by zero
@@ -4363,6 +9423,10 @@ pub async fn sftp_put(
                     &mut total_size,
                     &mut transferred,
                     &cancel_token,
+                    preserve_symlinks,
+                    &processors,
+                    &mut walk,
+                    &unchanged,
                 )
                 .await?;
             }
@@ -4377,11 +9441,31 @@ pub async fn sftp_put(
 
         match result {
             Ok(_) => {
-                let _ = app_handle.emit(
-                    "transfer-success",
+                audit_transfer(
+                    &app_handle,
+                    &tid,
+                    &connection_id,
+                    "upload",
+                    &local,
+                    &remote,
+                    &walk.skipped,
+                )
+                .await;
+                if let Some(summary) = unchanged_summary.as_ref().filter(|s| s.files > 0) {
+                    crate::log_info!(
+                        "[FS] Skipped {} unchanged files ({} bytes) uploading to {}",
+                        summary.files,
+                        summary.bytes_saved,
+                        remote
+                    );
+                }
+                emit_transfer_success(
+                    &app_handle,
                     TransferSuccess {
                         id: tid,
                         destination_connection_id: connection_id,
+                        skipped: walk.skipped,
+                        unchanged: unchanged_summary,
                     },
                 );
             }
@@ -4404,6 +9488,281 @@ pub async fn sftp_put(
     Ok(())
 }
 
+/// Serves the rsync bridge on `listener`: the first client presenting `token`
+/// has its command run on `connection_id`, with stdin and output relayed as
+/// bridge frames.
+async fn serve_rsync_bridge(
+    app: AppHandle,
+    listener: tokio::net::TcpListener,
+    token: String,
+    connection_id: String,
+) {
+    use crate::rsync::{frame, FRAME_EXIT, FRAME_STDERR, FRAME_STDOUT};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+    let (mut reader, mut writer, command) = loop {
+        let Ok((socket, _)) = listener.accept().await else {
+            return;
+        };
+        let (reader, writer) = socket.into_split();
+        let mut reader = tokio::io::BufReader::new(reader);
+        let header = tokio::time::timeout(Duration::from_secs(5), async {
+            let mut presented = String::new();
+            let mut command = String::new();
+            reader.read_line(&mut presented).await.ok()?;
+            reader.read_line(&mut command).await.ok()?;
+            (presented.trim_end() == token).then(|| command.trim_end().to_string())
+        })
+        .await;
+        if let Ok(Some(command)) = header {
+            break (reader, writer, command);
+        }
+    };
+    drop(listener);
+
+    let failed = |message: String| {
+        let mut out = frame(FRAME_STDERR, format!("{}\n", message).as_bytes());
+        out.extend(frame(FRAME_EXIT, &255u32.to_be_bytes()));
+        out
+    };
+    let state = app.state::<AppState>();
+    let mut channel = match open_ssh_channel_with_single_reconnect(&connection_id, &state).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            let _ = writer.write_all(&failed(e)).await;
+            return;
+        }
+    };
+    if let Err(e) = channel.exec(true, command.as_str()).await {
+        let _ = writer.write_all(&failed(e.to_string())).await;
+        return;
+    }
+
+    let mut input = vec![0u8; 32 * 1024];
+    let mut input_open = true;
+    loop {
+        tokio::select! {
+            msg = channel.wait() => {
+                let (out, done) = match msg {
+                    Some(russh::ChannelMsg::Data { ref data }) => (frame(FRAME_STDOUT, data), false),
+                    Some(russh::ChannelMsg::ExtendedData { ref data, .. }) => {
+                        (frame(FRAME_STDERR, data), false)
+                    }
+                    Some(russh::ChannelMsg::ExitStatus { exit_status }) => {
+                        (frame(FRAME_EXIT, &exit_status.to_be_bytes()), true)
+                    }
+                    Some(_) => continue,
+                    // Closed without an exit status.
+                    None => (frame(FRAME_EXIT, &255u32.to_be_bytes()), true),
+                };
+                if writer.write_all(&out).await.is_err() || done {
+                    break;
+                }
+            }
+            read = reader.read(&mut input), if input_open => match read {
+                Ok(0) | Err(_) => {
+                    input_open = false;
+                    let _ = channel.eof().await;
+                }
+                Ok(n) => {
+                    if channel.data(&input[..n]).await.is_err() {
+                        break;
+                    }
+                }
+            },
+        }
+    }
+    let _ = channel.close().await;
+}
+
+/// Delta-syncs a tree with the local `rsync` binary; exactly one side must be remote.
+/// Reports through the same `transfer-*` events as SFTP transfers.
+#[tauri::command]
+pub async fn transfer_rsync(
+    app: AppHandle,
+    source: crate::rsync::RsyncEndpoint,
+    destination: crate::rsync::RsyncEndpoint,
+    options: Option<crate::rsync::RsyncOptions>,
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use crate::rsync::{self as rsync_ops, RsyncVersion};
+    use tokio::io::AsyncReadExt;
+
+    let options = options.unwrap_or_default();
+    let remote = match (source.is_local(), destination.is_local()) {
+        (true, false) => &destination,
+        (false, true) => &source,
+        _ => return Err("rsync needs exactly one local and one remote side".to_string()),
+    };
+    if !state
+        .connections
+        .lock()
+        .await
+        .contains_key(&remote.connection_id)
+    {
+        return Err("Connection not found".to_string());
+    }
+    // rsync reaches the server through the bridge, which runs on this connection.
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .map_err(|e| crate::redact::error(format!("Failed to start the rsync bridge: {}", e)))?;
+    let bridge_port = listener.local_addr().map_err(crate::redact::error)?.port();
+    let bridge_token = uuid::Uuid::new_v4().to_string();
+    let exe = std::env::current_exe().map_err(crate::redact::error)?;
+    let remote_shell = rsync_ops::remote_shell(&exe, bridge_port);
+
+    let version_output = tokio::process::Command::new("rsync")
+        .arg("--version")
+        .output()
+        .await
+        .map_err(|e| {
+            crate::redact::error(format!("rsync is not installed or not on PATH: {}", e))
+        })?;
+    let version = RsyncVersion::parse(&String::from_utf8_lossy(&version_output.stdout));
+
+    let spec = |endpoint: &crate::rsync::RsyncEndpoint| {
+        if endpoint.is_local() {
+            endpoint.path.clone()
+        } else {
+            rsync_ops::remote_spec(&endpoint.path)
+        }
+    };
+    let args = rsync_ops::build_args(
+        &options,
+        version,
+        &remote_shell,
+        spec(&source),
+        spec(&destination),
+    );
+    crate::log_info!("[RSYNC] rsync {}", args.join(" "));
+
+    let mut child = tokio::process::Command::new("rsync")
+        .args(&args)
+        .env(rsync_ops::BRIDGE_TOKEN_ENV, &bridge_token)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| crate::redact::error(format!("Failed to start rsync: {}", e)))?;
+
+    let cancel_token = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    {
+        let mut transfers = state.transfers.lock().await;
+        transfers.insert(transfer_id.clone(), cancel_token.clone());
+    }
+
+    let app_handle = app.clone();
+    let tid = transfer_id.clone();
+    let destination_connection_id = destination.connection_id.clone();
+    let bridge = tokio::spawn(serve_rsync_bridge(
+        app.clone(),
+        listener,
+        bridge_token,
+        remote.connection_id.clone(),
+    ));
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        let mut stdout = child.stdout.take();
+        let mut stderr = child.stderr.take();
+        let stderr_task = tokio::spawn(async move {
+            let mut buf = Vec::new();
+            if let Some(stderr) = stderr.as_mut() {
+                let _ = stderr.read_to_end(&mut buf).await;
+            }
+            buf
+        });
+
+        // Progress lines are separated by `\r`, so split on both line endings.
+        let mut pending = Vec::new();
+        let mut chunk = vec![0u8; 8192];
+        let mut last_emit = std::time::Instant::now();
+        let mut cancelled = false;
+        while let Some(out) = stdout.as_mut() {
+            if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
+                cancelled = true;
+                let _ = child.kill().await;
+                break;
+            }
+            let read = tokio::select! {
+                read = out.read(&mut chunk) => read,
+                _ = tokio::time::sleep(Duration::from_millis(250)) => {
+                    if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
+                        cancelled = true;
+                        let _ = child.kill().await;
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let n = match read {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            pending.extend_from_slice(&chunk[..n]);
+            while let Some(pos) = pending.iter().position(|b| *b == b'\r' || *b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=pos).collect();
+                let Some(progress) = rsync_ops::parse_progress(&String::from_utf8_lossy(&line))
+                else {
+                    continue;
+                };
+                if last_emit.elapsed().as_millis() >= 100 {
+                    let _ = app_handle.emit(
+                        "transfer-progress",
+                        TransferProgress {
+                            id: tid.clone(),
+                            transferred: progress.transferred,
+                            total: progress.estimated_total(),
+                        },
+                    );
+                    last_emit = std::time::Instant::now();
+                }
+            }
+        }
+
+        let status = child.wait().await;
+        bridge.abort();
+        let stderr = stderr_task.await.unwrap_or_default();
+        {
+            let mut transfers = state.transfers.lock().await;
+            transfers.remove(&tid);
+        }
+
+        let result = match status {
+            _ if cancelled => Err("Cancelled".to_string()),
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(crate::redact::error(format!(
+                "rsync exited with {}: {}",
+                status
+                    .code()
+                    .map(|c| c.to_string())
+                    .unwrap_or_else(|| "signal".to_string()),
+                String::from_utf8_lossy(&stderr).trim()
+            ))),
+            Err(e) => Err(crate::redact::error(format!("rsync failed: {}", e))),
+        };
+        match result {
+            Ok(()) => {
+                emit_transfer_success(
+                    &app_handle,
+                    TransferSuccess {
+                        id: tid,
+                        destination_connection_id,
+                        skipped: Vec::new(),
+                        unchanged: None,
+                    },
+                );
+            }
+            Err(error) => {
+                let _ = app_handle.emit("transfer-error", TransferError { id: tid, error });
+            }
+        }
+    });
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn sftp_cancel_transfer(
     state: State<'_, AppState>,
@@ -4425,8 +9784,11 @@ pub async fn sftp_copy_to_server(
     destination_path: String,
     transfer_id: String,
     mode: Option<String>, // "standard" or "turbo" (Ignored, always standard now)
+    preserve_symlinks: Option<bool>,
+    walk_flags: Option<WalkFlags>,
     _state: State<'_, AppState>, // kept for signature compatibility if needed, but we use app_handle.state()
 ) -> Result<(), String> {
+    let preserve_symlinks = preserve_symlinks.unwrap_or(false);
     let app_handle = app.clone();
     let src_id = source_connection_id.clone();
     let src_path = source_path.clone();
@@ -4434,6 +9796,8 @@ pub async fn sftp_copy_to_server(
     let dst_path = destination_path.clone();
     let tid = transfer_id.clone();
     let _mode = mode.unwrap_or_else(|| "standard".to_string());
+    let mut walk = Walk::new(walk_flags.unwrap_or_default());
+    walk.name_rules = remote_name_rules(&_state, &destination_connection_id).await;
 
     tauri::async_runtime::spawn(async move {
         let state = app_handle.state::<AppState>();
@@ -4482,6 +9846,8 @@ pub async fn sftp_copy_to_server(
                 total_size,
                 &mut transferred,
                 &cancel_token,
+                preserve_symlinks,
+                &mut walk,
             )
             .await?;
 
@@ -4506,11 +9872,23 @@ pub async fn sftp_copy_to_server(
                     },
                 );
 
-                let _ = app_handle.emit(
-                    "transfer-success",
+                audit_transfer(
+                    &app_handle,
+                    &tid,
+                    &dst_id,
+                    "server-to-server",
+                    &format!("{}:{}", src_id, src_path),
+                    &dst_path,
+                    &walk.skipped,
+                )
+                .await;
+                emit_transfer_success(
+                    &app_handle,
                     TransferSuccess {
                         id: tid,
                         destination_connection_id: dst_id,
+                        skipped: walk.skipped,
+                        unchanged: None,
                     },
                 );
             }
@@ -4527,6 +9905,8 @@ pub async fn sftp_copy_to_server(
                             // reusing struct or just ID? Frontend expects error or distinct event?
                             id: tid.clone(),
                             destination_connection_id: dst_id, // Payload matches success for ID extraction
+                            skipped: Vec::new(),
+                            unchanged: None,
                         },
                     );
                     // Or separate event? Frontend listens for 'transfer-error' usually.
@@ -4559,43 +9939,52 @@ async fn copy_recursive_optimized(
     total_size: u64,
     transferred: &mut u64,
     cancel_token: &Arc<std::sync::atomic::AtomicBool>,
+    preserve_symlinks: bool,
+    walk: &mut Walk,
 ) -> Result<(), String> {
     use russh_sftp::protocol::OpenFlags;
-    use tokio::io::AsyncWriteExt;
 
     if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
         return Err("Cancelled".to_string());
     }
 
+    if preserve_symlinks {
+        if let Some(target) = remote_symlink_target(src_sftp, src_path).await {
+            // OpenSSH order: target first (see FileSystem::create_symlink_remote).
+            return dst_sftp.symlink(target, dst_path).await.map_err(|e| {
+                crate::redact::error(format!("Failed to create symlink '{}': {}", dst_path, e))
+            });
+        }
+    }
+
     let metadata = src_sftp
         .metadata(src_path)
         .await
-        .map_err(|e| format!("Failed to stat source: {}", e))?;
+        .map_err(|e| crate::redact::error(format!("Failed to stat source: {}", e)))?;
+    let special = SkipReason::from_mode(metadata.permissions.unwrap_or(0));
+    if walk.skip_special(src_path, special)? {
+        return Ok(());
+    }
 
     if metadata.is_dir() {
         // Create remote dir (ignore error if exists)
         let _ = dst_sftp.create_dir(dst_path).await;
 
-        let entries = src_sftp
-            .read_dir(src_path)
+        let names = remote_dir_names(src_sftp, src_path)
+            .await
+            .map_err(|e| crate::redact::error(format!("Read dir failed: {}", e)))?;
+        let existing = remote_dir_names(dst_sftp, dst_path)
             .await
-            .map_err(|e| format!("Read dir failed: {}", e))?;
-        for entry in entries {
-            let filename = entry.file_name();
-            if filename == "." || filename == ".." {
+            .unwrap_or_default();
+        let children = walk.plan_children(&names, &existing, |name| {
+            crate::remote_path::join(src_path, name)
+        });
+        for (filename, dst_name) in children {
+            let new_src = crate::remote_path::join(src_path, &filename);
+            if walk.skip_child(&new_src, &filename) {
                 continue;
             }
-
-            let new_src = if src_path.ends_with('/') {
-                format!("{}{}", src_path, filename)
-            } else {
-                format!("{}/{}", src_path, filename)
-            };
-            let new_dst = if dst_path.ends_with('/') {
-                format!("{}{}", dst_path, filename)
-            } else {
-                format!("{}/{}", dst_path, filename)
-            };
+            let new_dst = crate::remote_path::join(dst_path, &dst_name);
 
             Box::pin(copy_recursive_optimized(
                 src_sftp,
@@ -4607,65 +9996,61 @@ async fn copy_recursive_optimized(
                 total_size,
                 transferred,
                 cancel_token,
+                preserve_symlinks,
+                walk,
             ))
             .await?;
         }
     } else {
         // File copy
-        let mut src_file = src_sftp
+        let src_file = src_sftp
             .open_with_flags(src_path, OpenFlags::READ)
             .await
-            .map_err(|e| format!("Open src failed: {}", e))?;
+            .map_err(|e| crate::redact::error(format!("Open src failed: {}", e)))?;
         let mut dst_file = dst_sftp
             .open_with_flags(
                 dst_path,
                 OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
             )
             .await
-            .map_err(|e| format!("Open dst failed: {}", e))?;
+            .map_err(|e| crate::redact::error(format!("Open dst failed: {}", e)))?;
 
-        // 4MB buffer to maximize throughput on high-latency links
         // Full-Duplex Channel (Remote Source reads piped to Remote Destination writes)
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, String>>(4);
-
-        // Spawn Source Reader Task
-        tokio::spawn(async move {
-            use tokio::io::AsyncReadExt;
-            loop {
-                let mut buffer = vec![0u8; 4194304]; // 4MB Chunk
-                match src_file.read(&mut buffer).await {
-                    Ok(0) => break,
-                    Ok(n) => {
-                        buffer.truncate(n);
-                        if tx.send(Ok(buffer)).await.is_err() {
-                            break;
-                        }
-                    }
-                    Err(e) => {
-                        let _ = tx
-                            .send(Err(format!("SFTP source read failed: {}", e)))
-                            .await;
-                        break;
-                    }
-                }
-            }
-        });
+        let mut rx = crate::transfer_retry::spawn_remote_reader(src_file);
+        let mut copied = 0u64;
+        let mut read_attempts = 0;
 
         let mut last_emit = std::time::Instant::now();
 
         // Main loop: Receive from source and Write to destination concurrently
         while let Some(chunk_res) = rx.recv().await {
-            let chunk = chunk_res?;
+            let chunk = match chunk_res {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    // Restart the reader where the failed read began.
+                    let src_file = crate::transfer_retry::recover(
+                        src_sftp,
+                        src_path,
+                        OpenFlags::READ,
+                        copied,
+                        &mut read_attempts,
+                        e,
+                    )
+                    .await?;
+                    rx = crate::transfer_retry::spawn_remote_reader(src_file);
+                    continue;
+                }
+            };
+            read_attempts = 0;
             if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
                 return Err("Cancelled".to_string());
             }
 
-            dst_file
-                .write_all(&chunk)
-                .await
-                .map_err(|e| format!("SFTP destination write failed: {}", e))?;
+            crate::transfer_retry::write_chunk(dst_sftp, &mut dst_file, dst_path, copied, &chunk)
+                .await?;
 
             let n = chunk.len();
+            copied += n as u64;
             *transferred += n as u64;
 
             if last_emit.elapsed().as_millis() >= 200 {
@@ -4695,7 +10080,31 @@ async fn copy_recursive_optimized(
     Ok(())
 }
 
-// Helper for recursive download
+/// Entry names of a remote directory, without `.` and `..`.
+async fn remote_dir_names(
+    sftp: &russh_sftp::client::SftpSession,
+    path: &str,
+) -> Result<Vec<String>, russh_sftp::client::error::Error> {
+    Ok(sftp
+        .read_dir(path)
+        .await?
+        .map(|entry| entry.file_name())
+        .filter(|name| name != "." && name != "..")
+        .collect())
+}
+
+/// Entry names of a local directory; empty if it cannot be read.
+fn local_dir_names(path: &std::path::Path) -> Vec<String> {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn download_recursive<'a>(
     sftp: &'a russh_sftp::client::SftpSession,
     remote_path: &'a str,
@@ -4705,38 +10114,51 @@ fn download_recursive<'a>(
     total_size: &'a mut u64,
     transferred: &'a mut u64,
     cancel_token: &'a std::sync::atomic::AtomicBool,
+    preserve_symlinks: bool,
+    processors: &'a ProcessorChain,
+    walk: &'a mut Walk,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + 'a>> {
     Box::pin(async move {
+        if preserve_symlinks {
+            if let Some(target) = remote_symlink_target(sftp, remote_path).await {
+                return FileSystem::create_symlink_local(&target, &local_path.to_string_lossy())
+                    .map_err(crate::redact::error);
+            }
+        }
+
         // Check if remote is dir or file
-        let metadata = sftp
-            .metadata(remote_path)
-            .await
-            .map_err(|e| format!("Failed to stat remote path '{}': {}", remote_path, e))?;
+        let metadata = sftp.metadata(remote_path).await.map_err(|e| {
+            crate::redact::error(format!(
+                "Failed to stat remote path '{}': {}",
+                remote_path, e
+            ))
+        })?;
+        let special = SkipReason::from_mode(metadata.permissions.unwrap_or(0));
+        if walk.skip_special(remote_path, special)? {
+            return Ok(());
+        }
 
         if metadata.is_dir() {
             // Create local directory
             std::fs::create_dir_all(local_path)
-                .map_err(|e| format!("Failed to create local dir: {}", e))?;
+                .map_err(|e| crate::redact::error(format!("Failed to create local dir: {}", e)))?;
 
             // List remote directory
-            let entries = sftp
-                .read_dir(remote_path)
+            let names = remote_dir_names(sftp, remote_path)
                 .await
-                .map_err(|e| format!("Failed to read remote dir: {}", e))?;
+                .map_err(|e| crate::redact::error(format!("Failed to read remote dir: {}", e)))?;
+            let existing = local_dir_names(local_path);
+            let children = walk.plan_children(&names, &existing, |name| {
+                crate::remote_path::join(remote_path, name)
+            });
 
-            for entry in entries {
-                let name = entry.file_name();
-                if name == "." || name == ".." {
+            for (name, local_name) in children {
+                let new_remote = crate::remote_path::join(remote_path, &name);
+                if walk.skip_child(&new_remote, &name) {
                     continue;
                 }
 
-                let new_remote = if remote_path.ends_with('/') {
-                    format!("{}{}", remote_path, name)
-                } else {
-                    format!("{}/{}", remote_path, name)
-                };
-
-                let new_local = local_path.join(&name);
+                let new_local = local_path.join(&local_name);
 
                 download_recursive(
                     sftp,
@@ -4747,6 +10169,9 @@ fn download_recursive<'a>(
                     total_size,
                     transferred,
                     cancel_token,
+                    preserve_symlinks,
+                    processors,
+                    walk,
                 )
                 .await?;
             }
@@ -4757,55 +10182,66 @@ fn download_recursive<'a>(
             // Create local file using tokio for async writing
             let mut local_file = tokio::fs::File::create(local_path)
                 .await
-                .map_err(|e| format!("Failed to create local file: {}", e))?;
-
-            // Full-Duplex Channel (Remote reads piped to local disk writes)
-            let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, String>>(4);
+                .map_err(|e| crate::redact::error(format!("Failed to create local file: {}", e)))?;
 
             // Open remote file
-            let mut remote_file = sftp
+            let remote_file = sftp
                 .open_with_flags(remote_path, OpenFlags::READ)
                 .await
-                .map_err(|e| format!("Failed to open remote file '{}': {}", remote_path, e))?;
+                .map_err(|e| {
+                    crate::redact::error(format!(
+                        "Failed to open remote file '{}': {}",
+                        remote_path, e
+                    ))
+                })?;
 
-            // Spawn Remote Reader Task
-            tokio::spawn(async move {
-                use tokio::io::AsyncReadExt;
-                loop {
-                    let mut buffer = vec![0u8; 4 * 1024 * 1024];
-                    match remote_file.read(&mut buffer).await {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            buffer.truncate(n);
-                            if tx.send(Ok(buffer)).await.is_err() {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            let _ = tx.send(Err(format!("SFTP read failed: {}", e))).await;
-                            break;
-                        }
-                    }
-                }
-            });
+            // Full-Duplex Channel (Remote reads piped to local disk writes)
+            let mut rx = crate::transfer_retry::spawn_remote_reader(remote_file);
+            let mut received = 0u64;
+            let mut read_attempts = 0;
 
             let mut last_emit = std::time::Instant::now();
+            let mut pipeline = processors.start();
+            use tokio::io::AsyncWriteExt;
 
             // Main loop: Receive from remote reader and Write to Local Disk concurrently
             while let Some(chunk_res) = rx.recv().await {
-                let chunk = chunk_res?;
+                let chunk = match chunk_res {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        // Restart the reader where the failed read began.
+                        let remote_file = crate::transfer_retry::recover(
+                            sftp,
+                            remote_path,
+                            OpenFlags::READ,
+                            received,
+                            &mut read_attempts,
+                            e,
+                        )
+                        .await?;
+                        rx = crate::transfer_retry::spawn_remote_reader(remote_file);
+                        continue;
+                    }
+                };
+                read_attempts = 0;
+                received += chunk.len() as u64;
                 if cancel_token.load(std::sync::atomic::Ordering::Relaxed) {
                     return Err("Cancelled".to_string());
                 }
+                let chunk = if processors.is_empty() {
+                    chunk
+                } else {
+                    pipeline.process(&chunk).map_err(crate::redact::error)?
+                };
 
-                use tokio::io::AsyncWriteExt;
                 local_file
                     .write_all(&chunk)
                     .await
-                    .map_err(|e| format!("Local write failed: {}", e))?;
+                    .map_err(|e| crate::redact::error(format!("Local write failed: {}", e)))?;
 
                 let n = chunk.len();
                 *transferred += n as u64;
+                *total_size = (*total_size).max(*transferred);
 
                 if last_emit.elapsed().as_millis() >= 100 {
                     let _ = app.emit(
@@ -4819,11 +10255,30 @@ fn download_recursive<'a>(
                     last_emit = std::time::Instant::now();
                 }
             }
+
+            if !processors.is_empty() {
+                let tail = pipeline.finish().map_err(crate::redact::error)?;
+                local_file
+                    .write_all(&tail)
+                    .await
+                    .map_err(|e| crate::redact::error(format!("Local write failed: {}", e)))?;
+                *transferred += tail.len() as u64;
+                *total_size = (*total_size).max(*transferred);
+            }
         }
         Ok(())
     })
 }
 
+/// Returns the link target when `path` is a symlink on the remote side.
+async fn remote_symlink_target(sftp: &russh_sftp::client::SftpSession, path: &str) -> Option<String> {
+    let attrs = sftp.symlink_metadata(path).await.ok()?;
+    if (attrs.permissions.unwrap_or(0) & 0o170000) != 0o120000 {
+        return None;
+    }
+    sftp.read_link(path).await.ok()
+}
+
 // Helper to calculate remote size recursively
 async fn get_remote_size(sftp: &russh_sftp::client::SftpSession, path: &str) -> u64 {
     let mut total_size = 0;
@@ -4848,11 +10303,7 @@ async fn get_remote_size(sftp: &russh_sftp::client::SftpSession, path: &str) ->
                     continue;
                 }
 
-                let next_path = if current_path.ends_with('/') {
-                    format!("{}{}", current_path, filename)
-                } else {
-                    format!("{}/{}", current_path, filename)
-                };
+                let next_path = crate::remote_path::join(&current_path, &filename);
 
                 // Stat the entry to get attributes
                 if let Ok(attrs) = sftp.metadata(&next_path).await {
@@ -4876,8 +10327,21 @@ pub async fn sftp_get(
     remote_path: String,
     local_path: String,
     transfer_id: String,
+    preserve_symlinks: Option<bool>,
+    processors: Option<Vec<String>>,
+    walk_flags: Option<WalkFlags>,
     _state: State<'_, AppState>,
 ) -> Result<(), String> {
+    let preserve_symlinks = preserve_symlinks.unwrap_or(false);
+    if processors.as_ref().is_some_and(|names| !names.is_empty()) {
+        crate::plugin_wasm::sync_processors(&app, &_state.transfer_processors);
+    }
+    let processors = _state
+        .transfer_processors
+        .chain(&processors.unwrap_or_default())
+        .map_err(crate::redact::error)?;
+    let mut walk = Walk::new(walk_flags.unwrap_or_default());
+    walk.name_rules = crate::path_names::NameRules::local();
     let app_handle = app.clone();
     let connection_id = id.clone();
     let remote = remote_path.clone();
@@ -4888,6 +10352,27 @@ pub async fn sftp_get(
         let state = app_handle.state::<AppState>();
 
         let result = async {
+            if uses_scp_fallback(&state, &connection_id).await {
+                let channel =
+                    open_ssh_channel_with_single_reconnect(&connection_id, &state).await?;
+                let content = state
+                    .file_system
+                    .read_scp(channel, &remote)
+                    .await
+                    .map_err(crate::redact::error)?;
+                let content = apply_processors(&processors, &content)?;
+                std::fs::write(&local, &content).map_err(crate::redact::error)?;
+                let _ = app_handle.emit(
+                    "transfer-progress",
+                    TransferProgress {
+                        id: tid.clone(),
+                        transferred: content.len() as u64,
+                        total: content.len() as u64,
+                    },
+                );
+                return Ok(());
+            }
+
             // Retrieve session
             let sftp = get_sftp_or_reconnect(&state, &connection_id).await?;
             let local_p = std::path::Path::new(&local);
@@ -4927,6 +10412,9 @@ pub async fn sftp_get(
                 &mut total_size,
                 &mut transferred,
                 &cancel_token,
+                preserve_symlinks,
+                &processors,
+                &mut walk,
             )
             .await;
 
@@ -4942,11 +10430,23 @@ pub async fn sftp_get(
 
         match result {
             Ok(_) => {
-                let _ = app_handle.emit(
-                    "transfer-success",
+                audit_transfer(
+                    &app_handle,
+                    &tid,
+                    &connection_id,
+                    "download",
+                    &remote,
+                    &local,
+                    &walk.skipped,
+                )
+                .await;
+                emit_transfer_success(
+                    &app_handle,
                     TransferSuccess {
                         id: tid,
                         destination_connection_id: "local".to_string(),
+                        skipped: walk.skipped,
+                        unchanged: None,
                     },
                 );
             }
@@ -4974,7 +10474,7 @@ pub async fn shell_open(app: tauri::AppHandle, path: String) -> Result<(), Strin
     use tauri_plugin_opener::OpenerExt;
     app.opener()
         .open_url(path, None::<String>)
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -5113,10 +10613,9 @@ async fn query_remote_windows_shells(
 
     let mut channel = open_ssh_channel_with_single_reconnect(connection_id, state).await?;
     let list_cmd = "cmd /c \"where powershell.exe >nul 2>nul && echo powershell & where pwsh.exe >nul 2>nul && echo pwsh & where cmd.exe >nul 2>nul && echo cmd\"";
-    channel
-        .exec(true, list_cmd)
-        .await
-        .map_err(|e| format!("Failed to query remote Windows shells: {}", e))?;
+    channel.exec(true, list_cmd).await.map_err(|e| {
+        crate::redact::error(format!("Failed to query remote Windows shells: {}", e))
+    })?;
 
     let mut output = String::new();
     let mut stderr = String::new();
@@ -5140,7 +10639,7 @@ async fn query_remote_windows_shells(
     }
 
     if !stderr.trim().is_empty() {
-        eprintln!(
+        crate::log_error!(
             "[Shells] Remote Windows stderr for '{}': {}",
             connection_id,
             stderr.trim()
@@ -5410,7 +10909,7 @@ pub async fn shell_get_connection_shells(
         channel
             .exec(true, list_cmd)
             .await
-            .map_err(|e| format!("Failed to query remote shells: {}", e))?;
+            .map_err(|e| crate::redact::error(format!("Failed to query remote shells: {}", e)))?;
         // Drain the channel to completion. Breaking early on ExitStatus risks
         // losing trailing Data messages that haven't been pulled yet, which is
         // how this query was returning empty on some hosts.
@@ -5435,9 +10934,10 @@ pub async fn shell_get_connection_shells(
     };
 
     if let Err(err) = query_result {
-        eprintln!(
+        crate::log_error!(
             "[Shells] Remote query FAILED for '{}': {}",
-            connection_id, err
+            connection_id,
+            err
         );
         // Return Err — not Ok([]) — so the frontend keeps any cached shells
         // visible and exposes an explicit reload affordance instead of caching
@@ -5445,7 +10945,7 @@ pub async fn shell_get_connection_shells(
         return Err(err);
     }
     if !stderr.trim().is_empty() {
-        eprintln!(
+        crate::log_error!(
             "[Shells] Remote stderr for '{}': {}",
             connection_id,
             stderr.trim()
@@ -5506,7 +11006,7 @@ pub async fn shell_get_connection_shells(
 
 #[tauri::command]
 pub async fn app_get_exe_dir() -> Result<String, String> {
-    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+    let exe_path = std::env::current_exe().map_err(crate::redact::error)?;
     let exe_dir = exe_path
         .parent()
         .ok_or("Could not get executable directory")?;
@@ -5517,21 +11017,41 @@ pub async fn app_get_exe_dir() -> Result<String, String> {
 pub async fn app_exit(app: tauri::AppHandle) {
     app.exit(0);
 }
+
+use crate::plugin_permissions::Capability;
+
 #[tauri::command]
 pub async fn plugins_load(app: AppHandle) -> Result<Vec<crate::plugins::Plugin>, String> {
-    crate::plugins::PluginScanner::scan(&app).map_err(|e| e.to_string())
+    let mut plugins = crate::plugins::PluginScanner::scan(&app).map_err(crate::redact::error)?;
+    if crate::safe_mode::is_active() {
+        // Listed so they can be managed, but not run; the saved state is untouched.
+        for plugin in plugins.iter_mut() {
+            if !plugin.path.starts_with("builtin://") {
+                plugin.enabled = false;
+            }
+        }
+    }
+    Ok(plugins)
 }
 
 #[tauri::command]
-pub async fn plugins_toggle(app: AppHandle, id: String, enabled: bool) -> Result<(), String> {
-    crate::plugins::PluginScanner::save_state(&app, id, enabled).map_err(|e| e.to_string())
+pub async fn plugins_toggle(
+    app: AppHandle,
+    id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if !enabled {
+        state.pty_manager.taps().forget_plugin(&id);
+    }
+    crate::plugins::PluginScanner::save_state(&app, id, enabled).map_err(crate::redact::error)
 }
 
 #[tauri::command]
 pub async fn plugins_install(app: AppHandle, url: String) -> Result<String, String> {
     crate::plugins::PluginScanner::install_plugin(&app, &url)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -5543,69 +11063,312 @@ pub async fn plugins_install_local(app: AppHandle, path: String) -> Result<Strin
         crate::plugins::PluginScanner::install_plugin_from_local_path(&app_handle, &local_path)
     })
     .await
-    .map_err(|e| format!("Local plugin install task failed: {e}"))?
-    .map_err(|e| e.to_string())
+    .map_err(|e| crate::redact::error(format!("Local plugin install task failed: {e}")))?
+    .map_err(crate::redact::error)
+}
+
+/// The plugin registry index, with installed versions and available updates.
+#[tauri::command]
+pub async fn plugins_registry_list(
+    app: AppHandle,
+    refresh: Option<bool>,
+) -> Result<Vec<crate::plugin_registry::RegistryEntry>, String> {
+    crate::plugin_registry::list(&app, refresh.unwrap_or(false))
+        .await
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn plugins_registry_search(
+    app: AppHandle,
+    query: String,
+) -> Result<Vec<crate::plugin_registry::RegistryEntry>, String> {
+    let entries = crate::plugin_registry::list(&app, false)
+        .await
+        .map_err(crate::redact::error)?;
+    Ok(crate::plugin_registry::search(entries, &query))
+}
+
+/// Installs or upgrades a plugin from the registry; returns the installed version.
+#[tauri::command]
+pub async fn plugins_install_by_id(app: AppHandle, id: String) -> Result<String, String> {
+    crate::plugin_registry::install(&app, &id)
+        .await
+        .map_err(crate::redact::error)
+}
+
+#[tauri::command]
+pub async fn plugins_uninstall(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.pty_manager.taps().forget_plugin(&id);
+    crate::plugins::PluginScanner::uninstall_plugin(&app, &id).map_err(crate::redact::error)?;
+    crate::plugin_hooks::invalidate();
+    crate::plugins::PluginScanner::forget_grants(&app, &id).map_err(crate::redact::error)
+}
+
+/// Lists the permissions a plugin declared and how the user answered them.
+#[tauri::command]
+pub async fn plugins_permissions_get(
+    app: AppHandle,
+    plugin_id: String,
+) -> Result<Vec<crate::plugin_permissions::PermissionStatus>, String> {
+    crate::plugin_permissions::statuses(&app, &plugin_id)
 }
 
+/// Answers a `plugin-permission:request`, or changes an earlier answer.
 #[tauri::command]
-pub async fn plugins_uninstall(app: AppHandle, id: String) -> Result<(), String> {
-    crate::plugins::PluginScanner::uninstall_plugin(&app, &id).map_err(|e| e.to_string())
+pub async fn plugins_permissions_set(
+    app: AppHandle,
+    plugin_id: String,
+    capability: String,
+    allowed: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::plugin_permissions::answer(&app, &plugin_id, &capability, allowed)?;
+    if !allowed && Capability::parse(&capability) == Some(Capability::Terminal) {
+        state.pty_manager.taps().forget_plugin(&plugin_id);
+    }
+    Ok(())
+}
+
+/// Returns a plugin's settings, with defaults from its `settingsSchema`.
+#[tauri::command]
+pub async fn plugin_settings_get(
+    app: AppHandle,
+    plugin_id: String,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    crate::plugin_settings::get(&app, &plugin_id)
+}
+
+/// Updates some of a plugin's settings; `null` resets one to its default.
+#[tauri::command]
+pub async fn plugin_settings_set(
+    app: AppHandle,
+    plugin_id: String,
+    values: serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    crate::plugin_settings::set(&app, &plugin_id, values)
 }
 
 #[tauri::command]
-pub async fn plugin_fs_read(path: String, state: State<'_, AppState>) -> Result<String, String> {
+pub async fn plugin_fs_read(
+    app: AppHandle,
+    plugin_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::FsRead, Some(&path))?;
     state
         .file_system
         .read_file("local", &path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
 pub async fn plugin_fs_write(
+    app: AppHandle,
+    plugin_id: String,
     path: String,
     content: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::FsWrite, Some(&path))?;
     state
         .file_system
-        .write_file("local", &path, &content)
+        .write_file("local", &path, content.as_bytes())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
 pub async fn plugin_fs_list(
+    app: AppHandle,
+    plugin_id: String,
     path: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<FileEntry>, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::FsRead, Some(&path))?;
     state
         .file_system
         .list_local(&path)
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
-pub async fn plugin_fs_exists(path: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn plugin_fs_exists(
+    app: AppHandle,
+    plugin_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::FsRead, Some(&path))?;
     state
         .file_system
         .exists("local", &path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
 }
 
 #[tauri::command]
-pub async fn plugin_fs_create_dir(path: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn plugin_fs_create_dir(
+    app: AppHandle,
+    plugin_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::FsWrite, Some(&path))?;
     state
         .file_system
         .create_dir("local", &path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(crate::redact::error)
+}
+
+/// Scrollback returned by `plugin_terminal_read_buffer` when no size is given.
+const PLUGIN_TERMINAL_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Sends input to a terminal on behalf of a plugin, as if it had been typed.
+#[tauri::command]
+pub async fn plugin_terminal_write(
+    app: AppHandle,
+    plugin_id: String,
+    term_id: String,
+    data: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Terminal, None)?;
+    approve_terminal_write(&app, &state, &term_id, &data, None).await?;
+    state
+        .pty_manager
+        .write(&term_id, &data)
+        .await
+        .map_err(crate::redact::error)
+}
+
+/// The tail of a terminal's in-memory scrollback, at most `max_bytes`.
+#[tauri::command]
+pub async fn plugin_terminal_read_buffer(
+    app: AppHandle,
+    plugin_id: String,
+    term_id: String,
+    max_bytes: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Terminal, None)?;
+    let max_bytes = max_bytes.unwrap_or(PLUGIN_TERMINAL_BUFFER_BYTES);
+    Ok(state
+        .pty_manager
+        .scrollback(&term_id, Some(max_bytes), false)
+        .map(|snapshot| snapshot.data)
+        .unwrap_or_default())
+}
+
+/// Forwards the terminal's output to the plugin as `plugin-terminal:output`.
+#[tauri::command]
+pub async fn plugin_terminal_subscribe(
+    app: AppHandle,
+    plugin_id: String,
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Terminal, None)?;
+    if state.pty_manager.connection_id(&term_id).await.is_none() {
+        return Err(crate::redact::error(format!(
+            "Session not found: {}",
+            term_id
+        )));
+    }
+    state.pty_manager.taps().subscribe(&term_id, &plugin_id);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plugin_terminal_unsubscribe(
+    plugin_id: String,
+    term_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(state.pty_manager.taps().unsubscribe(&term_id, &plugin_id))
+}
+
+/// Runs a command on an open connection for a plugin; audited as `plugin:<id>`.
+#[tauri::command]
+pub async fn plugin_ssh_exec(
+    app: AppHandle,
+    plugin_id: String,
+    connection_id: String,
+    command: String,
+    timeout_secs: Option<u64>,
+) -> Result<crate::exec_batch::BatchHostResult, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Exec, None)?;
+    exec_on_connection(
+        &app,
+        &connection_id,
+        command,
+        timeout_secs,
+        uuid::Uuid::new_v4().to_string(),
+        &format!("plugin:{}", plugin_id),
+    )
+    .await
+    .ok_or_else(|| crate::redact::error(format!("Connection {} is not open", connection_id)))
+}
+
+/// Reads a text file on a remote connection. Local files go through
+/// `plugin_fs_read`, which checks the plugin's folders.
+#[tauri::command]
+pub async fn plugin_sftp_read(
+    app: AppHandle,
+    plugin_id: String,
+    connection_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<crate::text_encoding::TextFile, String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Ssh, None)?;
+    if connection_id == "local" {
+        return Err("Plugins read local files with plugin_fs_read".to_string());
+    }
+    read_connection_text(&state, &connection_id, path).await
+}
+
+/// Writes a text file on a remote connection, keeping the previous content as a
+/// file version so the change can be undone.
+#[tauri::command]
+pub async fn plugin_sftp_write(
+    app: AppHandle,
+    plugin_id: String,
+    connection_id: String,
+    path: String,
+    content: String,
+    format: Option<crate::text_encoding::TextFormat>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::plugin_permissions::require(&app, &plugin_id, Capability::Ssh, None)?;
+    if connection_id == "local" {
+        return Err("Plugins write local files with plugin_fs_write".to_string());
+    }
+    write_connection_text(&state, &connection_id, path, content, format, Some(true)).await
+}
+
+/// Calls `function` in a plugin's WebAssembly module; see [`crate::plugin_wasm`].
+#[tauri::command]
+pub async fn plugin_wasm_call(
+    app: AppHandle,
+    plugin_id: String,
+    function: String,
+    input: Option<Value>,
+) -> Result<Value, String> {
+    let plugin = crate::plugin_permissions::require_enabled(&app, &plugin_id)?;
+    crate::plugin_wasm::call(&app, &plugin, &function, input.unwrap_or(Value::Null)).await
 }
 
 #[tauri::command]
 pub async fn plugin_window_create(
     app: AppHandle,
+    plugin_id: String,
     url: Option<String>,
     html: Option<String>,
     title: Option<String>,
@@ -5613,6 +11376,11 @@ pub async fn plugin_window_create(
     height: Option<f64>,
 ) -> Result<(), String> {
     use tauri::WebviewWindowBuilder;
+    if url.is_some() {
+        crate::plugin_permissions::require(&app, &plugin_id, Capability::Network, None)?;
+    } else {
+        crate::plugin_permissions::require_enabled(&app, &plugin_id)?;
+    }
     let label = format!("plugin-window-{}", uuid::Uuid::new_v4());
     let mut temp_html_path: Option<std::path::PathBuf> = None;
     let mut builder = WebviewWindowBuilder::new(
@@ -5624,19 +11392,27 @@ pub async fn plugin_window_create(
             let cache_dir = app
                 .path()
                 .app_cache_dir()
-                .map_err(|e| format!("Failed to resolve app cache dir: {}", e))?
+                .map_err(|e| {
+                    crate::redact::error(format!("Failed to resolve app cache dir: {}", e))
+                })?
                 .join("plugin-window-html");
             if !cache_dir.exists() {
-                std::fs::create_dir_all(&cache_dir)
-                    .map_err(|e| format!("Failed to create plugin cache dir: {}", e))?;
+                std::fs::create_dir_all(&cache_dir).map_err(|e| {
+                    crate::redact::error(format!("Failed to create plugin cache dir: {}", e))
+                })?;
             }
             let file_path =
                 cache_dir.join(format!("zync-plugin-window-{}.html", uuid::Uuid::new_v4()));
-            std::fs::write(&file_path, h)
-                .map_err(|e| format!("Failed to write temporary plugin HTML file: {}", e))?;
+            std::fs::write(&file_path, h).map_err(|e| {
+                crate::redact::error(format!("Failed to write temporary plugin HTML file: {}", e))
+            })?;
             temp_html_path = Some(file_path.clone());
-            let file_url = url::Url::from_file_path(&file_path)
-                .map_err(|_| format!("Failed to create file URL for {}", file_path.display()))?;
+            let file_url = url::Url::from_file_path(&file_path).map_err(|_| {
+                crate::redact::error(format!(
+                    "Failed to create file URL for {}",
+                    file_path.display()
+                ))
+            })?;
             tauri::WebviewUrl::External(file_url)
         } else {
             return Err("Must provide url or html".into());
@@ -5662,7 +11438,7 @@ pub async fn plugin_window_create(
                 files.insert(label, file_path);
             }
             Err(lock_error) => {
-                eprintln!(
+                crate::log_error!(
                     "Failed to register plugin window temp file for cleanup: {}",
                     lock_error
                 );
@@ -5680,7 +11456,7 @@ pub fn cleanup_plugin_window_temp_file(window_label: &str) {
     };
     if let Some(path) = maybe_path {
         if let Err(error) = std::fs::remove_file(&path) {
-            eprintln!(
+            crate::log_error!(
                 "[plugin-window] Failed to remove temporary HTML file {}: {}",
                 path.display(),
                 error
@@ -5693,7 +11469,7 @@ pub fn cleanup_stale_plugin_window_temp_files(app: &AppHandle) {
     let cache_dir = match app.path().app_cache_dir() {
         Ok(dir) => dir.join("plugin-window-html"),
         Err(error) => {
-            eprintln!("[plugin-window] Failed to resolve cache dir: {}", error);
+            crate::log_error!("[plugin-window] Failed to resolve cache dir: {}", error);
             return;
         }
     };
@@ -5707,7 +11483,7 @@ pub fn cleanup_stale_plugin_window_temp_files(app: &AppHandle) {
     let entries = match std::fs::read_dir(&cache_dir) {
         Ok(entries) => entries,
         Err(error) => {
-            eprintln!(
+            crate::log_error!(
                 "[plugin-window] Failed to scan temp cache dir {}: {}",
                 cache_dir.display(),
                 error
@@ -5735,7 +11511,7 @@ pub fn cleanup_stale_plugin_window_temp_files(app: &AppHandle) {
 
         if should_remove {
             if let Err(error) = std::fs::remove_file(&path) {
-                eprintln!(
+                crate::log_error!(
                     "[plugin-window] Failed to remove stale HTML file {}: {}",
                     path.display(),
                     error
@@ -5753,7 +11529,7 @@ pub async fn config_select_folder(app: AppHandle) -> Result<Option<String>, Stri
         app_for_picker.dialog().file().blocking_pick_folder()
     })
     .await
-    .map_err(|e| format!("Folder picker task failed: {}", e))?;
+    .map_err(|e| crate::redact::error(format!("Folder picker task failed: {}", e)))?;
     Ok(path.map(|p| p.to_string()))
 }
 
@@ -5768,22 +11544,22 @@ pub async fn system_install_cli(app: AppHandle) -> Result<String, String> {
     #[cfg(not(target_os = "windows"))]
     {
         use tauri::Manager;
-        let home = app.path().home_dir().map_err(|e| e.to_string())?;
+        let home = app.path().home_dir().map_err(crate::redact::error)?;
         let local_bin = home.join(".local/bin");
 
         if !local_bin.exists() {
-            std::fs::create_dir_all(&local_bin).map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(&local_bin).map_err(crate::redact::error)?;
         }
 
-        let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
+        let exe_path = std::env::current_exe().map_err(crate::redact::error)?;
         let target_path = local_bin.join("zync");
 
         // Remove existing if any
         if target_path.exists() {
-            std::fs::remove_file(&target_path).map_err(|e| e.to_string())?;
+            std::fs::remove_file(&target_path).map_err(crate::redact::error)?;
         }
 
-        std::os::unix::fs::symlink(exe_path, &target_path).map_err(|e| e.to_string())?;
+        std::os::unix::fs::symlink(exe_path, &target_path).map_err(crate::redact::error)?;
 
         Ok(format!("Installed zync to {:?}", target_path))
     }
@@ -5852,7 +11628,9 @@ pub async fn sftp_download_as_zip(
                 let conns = state_ref.connections.lock().await;
                 conns
                     .get(&connection_id)
-                    .ok_or_else(|| format!("Connection '{}' not found", connection_id))?
+                    .ok_or_else(|| {
+                        crate::redact::error(format!("Connection '{}' not found", connection_id))
+                    })?
                     .session
                     .clone()
                     .ok_or_else(|| "SSH session not initialised".to_string())?
@@ -5892,28 +11670,29 @@ pub async fn sftp_download_as_zip(
             let tar_cmd = format!("tar -czf -{}", tar_args);
 
             // Open SSH exec channel.
-            let mut channel = session
-                .lock()
-                .await
-                .channel_open_session()
-                .await
-                .map_err(|e| format!("Failed to open SSH channel: {}", e))?;
+            let mut channel =
+                crate::channel_budget::open_session(&connection_id, &*session.lock().await)
+                    .await
+                    .map_err(|e| {
+                        crate::redact::error(format!("Failed to open SSH channel: {}", e))
+                    })?;
             channel
                 .exec(true, tar_cmd.as_str())
                 .await
-                .map_err(|e| format!("Failed to exec tar: {}", e))?;
+                .map_err(|e| crate::redact::error(format!("Failed to exec tar: {}", e)))?;
 
             // Ensure parent directory exists.
             if let Some(parent) = std::path::Path::new(&local_path).parent() {
                 if !parent.exists() {
-                    std::fs::create_dir_all(parent)
-                        .map_err(|e| format!("Cannot create output directory: {}", e))?;
+                    std::fs::create_dir_all(parent).map_err(|e| {
+                        crate::redact::error(format!("Cannot create output directory: {}", e))
+                    })?;
                 }
             }
 
             let mut out_file = tokio::fs::File::create(&local_path)
                 .await
-                .map_err(|e| format!("Cannot create output file: {}", e))?;
+                .map_err(|e| crate::redact::error(format!("Cannot create output file: {}", e)))?;
 
             let _ = app_handle.emit(
                 "transfer-progress",
@@ -5940,7 +11719,7 @@ pub async fn sftp_download_as_zip(
                         out_file
                             .write_all(data)
                             .await
-                            .map_err(|e| format!("Write failed: {}", e))?;
+                            .map_err(|e| crate::redact::error(format!("Write failed: {}", e)))?;
                         bytes_written += data.len() as u64;
                         if last_emit.elapsed().as_millis() >= 150 {
                             let _ = app_handle.emit(
@@ -5966,11 +11745,11 @@ pub async fn sftp_download_as_zip(
 
             if exit_status != 0 {
                 let stderr = String::from_utf8_lossy(&stderr_buf);
-                return Err(format!(
+                return Err(crate::redact::error(format!(
                     "tar failed (exit {}): {}",
                     exit_status,
                     stderr.trim()
-                ));
+                )));
             }
 
             // Emit 100% progress.
@@ -5994,11 +11773,13 @@ pub async fn sftp_download_as_zip(
 
         match result {
             Ok(_) => {
-                let _ = app_handle.emit(
-                    "transfer-success",
+                emit_transfer_success(
+                    &app_handle,
                     TransferSuccess {
                         id: tid,
                         destination_connection_id: "local".to_string(),
+                        skipped: Vec::new(),
+                        unchanged: None,
                     },
                 );
             }
@@ -6016,10 +11797,12 @@ pub async fn sftp_download_as_zip(
 pub async fn ai_translate(
     app: AppHandle,
     query: String,
-    context: crate::ai::TerminalContext,
+    mut context: crate::ai::TerminalContext,
     request_id: String,
+    state: State<'_, AppState>,
 ) -> Result<crate::ai::AiTranslateResponse, String> {
     let config = require_enabled_ai(&app)?;
+    resolve_context_cwd(&state, &mut context).await;
     crate::ai::translate(&app, query, context, request_id, config).await
 }
 
@@ -6027,11 +11810,13 @@ pub async fn ai_translate(
 pub async fn ai_translate_stream(
     app: AppHandle,
     query: String,
-    context: crate::ai::TerminalContext,
+    mut context: crate::ai::TerminalContext,
     request_id: String,
     history: Vec<crate::ai::ChatMessage>,
+    state: State<'_, AppState>,
 ) -> Result<(), String> {
     let config = require_enabled_ai(&app)?;
+    resolve_context_cwd(&state, &mut context).await;
     tauri::async_runtime::spawn(crate::ai::translate_stream(
         app, query, context, request_id, config, history,
     ));
@@ -6069,9 +11854,10 @@ pub async fn ai_get_provider_models(app: AppHandle) -> Result<Vec<String>, Strin
 pub async fn ai_agent_run(
     app: AppHandle,
     state: State<'_, AppState>,
-    request: crate::ai::AgentRunRequest,
+    mut request: crate::ai::AgentRunRequest,
 ) -> Result<(), String> {
     let config = require_enabled_ai(&app)?;
+    resolve_context_cwd(&state, &mut request.context).await;
 
     let cancel = Arc::new(AtomicBool::new(false));
     let run_id = request.run_id.clone();
@@ -6096,6 +11882,16 @@ pub async fn ai_agent_run(
     Ok(())
 }
 
+/// Prefers the cwd the terminal's shell last reported over the frontend's guess.
+async fn resolve_context_cwd(state: &AppState, context: &mut crate::ai::TerminalContext) {
+    let Some(term_id) = context.term_id.as_deref() else {
+        return;
+    };
+    if let Ok(Some(cwd)) = state.pty_manager.cwd(term_id).await {
+        context.cwd = Some(cwd);
+    }
+}
+
 fn require_enabled_ai(app: &AppHandle) -> Result<crate::ai::AiConfig, String> {
     let config = crate::ai::read_ai_config(app);
     if !config.enabled {
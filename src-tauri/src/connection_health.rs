@@ -0,0 +1,470 @@
+//! Background reachability checks of saved hosts, for sidebar badges.
+//!
+//! Off by default. Once enabled in `connection_health.json`, every saved
+//! connection is dialed each `intervalSecs`: a plain TCP connect in `tcp` mode,
+//! or in `ssh` mode a connect followed by waiting for the server's SSH
+//! identification line, which also catches ports that accept but have no
+//! working sshd behind them. No credentials are sent in either mode.
+//! Connections reached through a jump host are not dialed and stay `unknown`.
+//!
+//! Results are kept in memory, returned by `connections_health` and emitted as
+//! `connection:health` after each check. Nothing is dialed while the offline
+//! kill switch is engaged.
+
+use crate::commands::AppState;
+use crate::types::{ResolutionOptions, SavedData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+const SCHEDULER_TICK: Duration = Duration::from_secs(5);
+const MIN_INTERVAL_SECS: u64 = 30;
+const MAX_TIMEOUT_MS: u64 = 30_000;
+/// Hosts dialed at once during a round.
+const CONCURRENCY: usize = 16;
+/// Bytes read while waiting for the SSH identification line.
+const MAX_GREETING: usize = 2048;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthMode {
+    #[default]
+    Tcp,
+    Ssh,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthSettings {
+    pub enabled: bool,
+    pub mode: HealthMode,
+    pub interval_secs: u64,
+    /// Per host, covering the connect and, in `ssh` mode, the greeting.
+    pub timeout_ms: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: HealthMode::Tcp,
+            interval_secs: 120,
+            timeout_ms: 3000,
+        }
+    }
+}
+
+impl HealthSettings {
+    fn normalized(mut self) -> Self {
+        self.interval_secs = self.interval_secs.max(MIN_INTERVAL_SECS);
+        self.timeout_ms = self.timeout_ms.clamp(100, MAX_TIMEOUT_MS);
+        self
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthState {
+    /// Not checked yet, checks are off, or the host is behind a jump host.
+    #[default]
+    Unknown,
+    Up,
+    Down,
+}
+
+/// Payload of `connection:health` and an entry of `connections_health`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionHealth {
+    pub connection_id: String,
+    pub state: HealthState,
+    /// Time to establish the TCP connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked_at: Option<u64>,
+    /// Address that answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// SSH identification line, in `ssh` mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A saved connection as the monitor sees it.
+#[derive(Debug, Clone)]
+struct Target {
+    id: String,
+    host: String,
+    port: u16,
+    resolution: Option<ResolutionOptions>,
+    via_jump_host: bool,
+}
+
+#[derive(Default)]
+struct Schedule {
+    status: HashMap<String, ConnectionHealth>,
+    /// When the next round may start, in ms since the epoch.
+    next_round: u64,
+    running: bool,
+}
+
+pub struct HealthMonitor {
+    settings_path: PathBuf,
+    connections_path: PathBuf,
+    schedule: Mutex<Schedule>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl HealthMonitor {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            settings_path: app_data_dir.join("connection_health.json"),
+            connections_path: app_data_dir.join("connections.json"),
+            schedule: Mutex::new(Schedule::default()),
+        }
+    }
+
+    /// Saved settings, or the defaults when the file is missing or unreadable.
+    pub fn settings(&self) -> HealthSettings {
+        std::fs::read(&self.settings_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HealthSettings>(&bytes).ok())
+            .unwrap_or_default()
+            .normalized()
+    }
+
+    /// Stores `settings` and starts a fresh round on the next tick. Turning
+    /// checks off forgets every result.
+    pub fn save_settings(&self, settings: HealthSettings) -> Result<HealthSettings, String> {
+        let settings = settings.normalized();
+        let content = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.settings_path, &content)
+            .map_err(|e| e.to_string())?;
+        if let Ok(mut schedule) = self.schedule.lock() {
+            schedule.next_round = 0;
+            if !settings.enabled {
+                schedule.status.clear();
+            }
+        }
+        Ok(settings)
+    }
+
+    fn targets(&self) -> Vec<Target> {
+        crate::vault::at_rest::peek_to_string(&self.connections_path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<SavedData>(&data).ok())
+            .map(|data| data.connections)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| Target {
+                via_jump_host: c.jump_server_id.as_deref().is_some_and(|id| !id.is_empty()),
+                id: c.id,
+                host: c.host,
+                port: c.port,
+                resolution: c.resolution,
+            })
+            .collect()
+    }
+
+    /// Last result of every saved connection, in saved order.
+    pub fn status(&self) -> Vec<ConnectionHealth> {
+        let targets = self.targets();
+        let Ok(schedule) = self.schedule.lock() else {
+            return Vec::new();
+        };
+        targets
+            .iter()
+            .map(|target| {
+                schedule
+                    .status
+                    .get(&target.id)
+                    .cloned()
+                    .unwrap_or_else(|| ConnectionHealth {
+                        connection_id: target.id.clone(),
+                        ..Default::default()
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether a round is due at `now`; marks it running if so.
+    fn take_round(&self, now: u64) -> bool {
+        let Ok(mut schedule) = self.schedule.lock() else {
+            return false;
+        };
+        if schedule.running || schedule.next_round > now {
+            return false;
+        }
+        schedule.running = true;
+        true
+    }
+
+    fn finish_round(&self, interval: Duration, now: u64) {
+        if let Ok(mut schedule) = self.schedule.lock() {
+            schedule.running = false;
+            schedule.next_round = now + interval.as_millis() as u64;
+        }
+    }
+
+    /// Drops results of connections that were deleted since the last round.
+    fn retain(&self, targets: &[Target]) {
+        if let Ok(mut schedule) = self.schedule.lock() {
+            schedule
+                .status
+                .retain(|id, _| targets.iter().any(|t| &t.id == id));
+        }
+    }
+
+    fn record(&self, health: ConnectionHealth) {
+        if let Ok(mut schedule) = self.schedule.lock() {
+            schedule.status.insert(health.connection_id.clone(), health);
+        }
+    }
+}
+
+/// Reads until a line starting with `SSH-` arrives (servers may send other
+/// lines first), the peer closes, or [`MAX_GREETING`] bytes were read.
+async fn read_banner(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::with_capacity(256);
+    let mut chunk = [0u8; 256];
+    while buf.len() < MAX_GREETING {
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+        // Only complete lines; a partial one is read again with the rest.
+        let complete = match buf.iter().rposition(|b| *b == b'\n') {
+            Some(end) => &buf[..=end],
+            None => continue,
+        };
+        if let Some(banner) = complete
+            .split(|b| *b == b'\n')
+            .find_map(crate::lan_discovery::scan::parse_banner)
+        {
+            return Some(banner);
+        }
+    }
+    None
+}
+
+/// Dials one of `addresses` (in order) within `settings.timeout_ms`.
+async fn dial(addresses: &[SocketAddr], settings: &HealthSettings) -> ConnectionHealth {
+    let deadline = Instant::now() + settings.timeout();
+    let mut last_error = "No addresses to dial".to_string();
+    for address in addresses {
+        let started = Instant::now();
+        let remaining = deadline.saturating_duration_since(started);
+        let mut stream = match tokio::time::timeout(remaining, TcpStream::connect(address)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                last_error = format!("{}: {}", address, e);
+                continue;
+            }
+            Err(_) => {
+                last_error = format!("{}: timed out after {}ms", address, settings.timeout_ms);
+                break;
+            }
+        };
+        let mut health = ConnectionHealth {
+            state: HealthState::Up,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            address: Some(address.to_string()),
+            ..Default::default()
+        };
+        if settings.mode == HealthMode::Ssh {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, read_banner(&mut stream)).await {
+                Ok(Some(banner)) => health.banner = Some(banner),
+                Ok(None) => {
+                    health.state = HealthState::Down;
+                    health.error = Some("Port is open but did not answer as SSH".to_string());
+                }
+                Err(_) => {
+                    health.state = HealthState::Down;
+                    health.error =
+                        Some(format!("No SSH greeting within {}ms", settings.timeout_ms));
+                }
+            }
+        }
+        return health;
+    }
+    ConnectionHealth {
+        state: HealthState::Down,
+        error: Some(last_error),
+        ..Default::default()
+    }
+}
+
+async fn check(target: &Target, settings: &HealthSettings) -> ConnectionHealth {
+    let mut health = if target.via_jump_host {
+        ConnectionHealth {
+            error: Some("Reached through a jump host; not checked".to_string()),
+            ..Default::default()
+        }
+    } else {
+        match crate::dns::resolve_host(&target.host, target.port, target.resolution.as_ref()).await
+        {
+            Ok(resolved) => dial(&resolved.addresses, settings).await,
+            Err(e) => ConnectionHealth {
+                state: HealthState::Down,
+                error: Some(e.to_string()),
+                ..Default::default()
+            },
+        }
+    };
+    health.connection_id = target.id.clone();
+    health.checked_at = Some(now_ms());
+    health
+}
+
+async fn run_round(app: &AppHandle, monitor: Arc<HealthMonitor>, settings: HealthSettings) {
+    let targets = monitor.targets();
+    monitor.retain(&targets);
+    let limiter = Arc::new(tokio::sync::Semaphore::new(CONCURRENCY));
+    let mut checks = tokio::task::JoinSet::new();
+    for target in targets {
+        let limiter = limiter.clone();
+        let settings = settings.clone();
+        checks.spawn(async move {
+            let _permit = limiter.acquire_owned().await.ok()?;
+            Some(check(&target, &settings).await)
+        });
+    }
+    while let Some(result) = checks.join_next().await {
+        if let Ok(Some(health)) = result {
+            monitor.record(health.clone());
+            let _ = app.emit("connection:health", health);
+        }
+    }
+}
+
+pub fn spawn_health_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = app.try_state::<AppState>() else {
+                continue;
+            };
+            let monitor = state.health_monitor.clone();
+            let settings = monitor.settings();
+            if !settings.enabled || crate::kill_switch::ensure_online().is_err() {
+                continue;
+            }
+            if !monitor.take_round(now_ms()) {
+                continue;
+            }
+            run_round(&app, monitor.clone(), settings.clone()).await;
+            monitor.finish_round(Duration::from_secs(settings.interval_secs), now_ms());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    fn settings(mode: HealthMode) -> HealthSettings {
+        HealthSettings {
+            enabled: true,
+            mode,
+            timeout_ms: 500,
+            ..Default::default()
+        }
+    }
+
+    async fn listener(greeting: Option<&'static [u8]>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                if let Some(greeting) = greeting {
+                    let _ = socket.write_all(greeting).await;
+                }
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    drop(socket);
+                });
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn tcp_mode_only_needs_an_open_port() {
+        let addr = listener(None).await;
+        let health = dial(&[addr], &settings(HealthMode::Tcp)).await;
+        assert_eq!(health.state, HealthState::Up);
+        assert_eq!(health.address, Some(addr.to_string()));
+        assert!(health.latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn ssh_mode_waits_for_the_identification_line() {
+        let ssh = listener(Some(b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n")).await;
+        let health = dial(&[ssh], &settings(HealthMode::Ssh)).await;
+        assert_eq!(health.state, HealthState::Up);
+        assert_eq!(health.banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+
+        let silent = listener(None).await;
+        let health = dial(&[silent], &settings(HealthMode::Ssh)).await;
+        assert_eq!(health.state, HealthState::Down);
+        assert!(health.error.unwrap().contains("No SSH greeting"));
+    }
+
+    #[tokio::test]
+    async fn closed_ports_are_down() {
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap()
+        };
+        let health = dial(&[closed], &settings(HealthMode::Tcp)).await;
+        assert_eq!(health.state, HealthState::Down);
+        assert!(health.error.is_some());
+    }
+
+    #[test]
+    fn rounds_wait_for_the_interval() {
+        let monitor = HealthMonitor::new(std::env::temp_dir());
+        assert!(monitor.take_round(1000));
+        assert!(!monitor.take_round(1000), "a round is already running");
+        monitor.finish_round(Duration::from_secs(60), 1000);
+        assert!(!monitor.take_round(30_000));
+        assert!(monitor.take_round(61_000));
+    }
+
+    #[test]
+    fn clamps_settings() {
+        let settings = HealthSettings {
+            interval_secs: 1,
+            timeout_ms: 10_000_000,
+            ..Default::default()
+        }
+        .normalized();
+        assert_eq!(settings.interval_secs, MIN_INTERVAL_SECS);
+        assert_eq!(settings.timeout_ms, MAX_TIMEOUT_MS);
+        assert!(!HealthSettings::default().enabled);
+    }
+}
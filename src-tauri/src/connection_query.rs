@@ -0,0 +1,230 @@
+//! Server-side filtering of saved connections for large inventories.
+//!
+//! `connections_query` applies a [`ConnectionFilter`] to `connections.json` and
+//! returns one page of matches, so the host list does not need every
+//! connection in the frontend to search, group or page through 500+ hosts.
+
+use crate::types::SavedConnection;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionSort {
+    #[default]
+    Name,
+    /// Most recently connected first; never-connected hosts last.
+    Recent,
+    Created,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConnectionFilter {
+    /// Every word must appear in the name, host, username or a tag (case-insensitive).
+    pub text: Option<String>,
+    /// Connections carrying all of these tags.
+    pub tags: Vec<String>,
+    /// Folder path; `""` selects connections outside any folder.
+    pub folder: Option<String>,
+    /// With `folder`, include connections in its subfolders.
+    pub include_subfolders: bool,
+    pub favorites_only: bool,
+    pub color: Option<String>,
+    /// Only connections used within this many days.
+    pub recent_days: Option<u64>,
+    pub sort: ConnectionSort,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionPage {
+    pub connections: Vec<SavedConnection>,
+    /// Matches before `offset` / `limit` were applied.
+    pub total: usize,
+}
+
+fn normalize_folder(folder: &str) -> &str {
+    folder.trim().trim_matches('/')
+}
+
+fn folder_matches(connection: &SavedConnection, folder: &str, include_subfolders: bool) -> bool {
+    let wanted = normalize_folder(folder);
+    let actual = normalize_folder(connection.folder.as_deref().unwrap_or_default());
+    actual == wanted
+        || (include_subfolders
+            && (wanted.is_empty()
+                || actual
+                    .strip_prefix(wanted)
+                    .is_some_and(|rest| rest.starts_with('/'))))
+}
+
+fn has_tag(connection: &SavedConnection, tag: &str) -> bool {
+    connection
+        .tags
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .any(|t| t.trim().eq_ignore_ascii_case(tag.trim()))
+}
+
+fn text_matches(connection: &SavedConnection, words: &[String]) -> bool {
+    if words.is_empty() {
+        return true;
+    }
+    let mut haystack = format!(
+        "{}\n{}\n{}",
+        connection.name, connection.host, connection.username
+    );
+    for tag in connection.tags.as_deref().unwrap_or_default() {
+        haystack.push('\n');
+        haystack.push_str(tag);
+    }
+    let haystack = haystack.to_lowercase();
+    words.iter().all(|word| haystack.contains(word.as_str()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn query(connections: Vec<SavedConnection>, filter: &ConnectionFilter) -> ConnectionPage {
+    let words: Vec<String> = filter
+        .text
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect();
+    let recent_since = filter
+        .recent_days
+        .map(|days| now_ms().saturating_sub(days.saturating_mul(DAY_MS)));
+
+    let mut matches: Vec<SavedConnection> = connections
+        .into_iter()
+        .filter(|connection| {
+            (!filter.favorites_only || connection.is_favorite == Some(true))
+                && filter.tags.iter().all(|tag| has_tag(connection, tag))
+                && filter.folder.as_deref().is_none_or(|folder| {
+                    folder_matches(connection, folder, filter.include_subfolders)
+                })
+                && filter.color.as_deref().is_none_or(|color| {
+                    connection
+                        .color
+                        .as_deref()
+                        .is_some_and(|c| c.eq_ignore_ascii_case(color))
+                })
+                && recent_since
+                    .is_none_or(|since| connection.last_connected.is_some_and(|at| at >= since))
+                && text_matches(connection, &words)
+        })
+        .collect();
+
+    match filter.sort {
+        ConnectionSort::Name => matches.sort_by_cached_key(|c| c.name.to_lowercase()),
+        ConnectionSort::Recent => matches.sort_by(|a, b| b.last_connected.cmp(&a.last_connected)),
+        ConnectionSort::Created => matches.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
+    }
+
+    let total = matches.len();
+    let connections = matches
+        .into_iter()
+        .skip(filter.offset)
+        .take(filter.limit.unwrap_or(usize::MAX))
+        .collect();
+    ConnectionPage { connections, total }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(name: &str, folder: Option<&str>, tags: &[&str]) -> SavedConnection {
+        serde_json::from_value(serde_json::json!({
+            "id": name,
+            "name": name,
+            "host": format!("{name}.example.com"),
+            "port": 22,
+            "username": "deploy",
+            "folder": folder,
+            "tags": tags,
+        }))
+        .unwrap()
+    }
+
+    fn names(page: &ConnectionPage) -> Vec<&str> {
+        page.connections.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    fn inventory() -> Vec<SavedConnection> {
+        let mut web = connection("web-1", Some("prod/eu"), &["web", "Prod"]);
+        web.is_favorite = Some(true);
+        web.color = Some("#ef4444".to_string());
+        web.last_connected = Some(now_ms());
+        let mut db = connection("db-1", Some("prod"), &["db", "prod"]);
+        db.last_connected = Some(now_ms() - 30 * DAY_MS);
+        vec![
+            web,
+            db,
+            connection("lab", None, &[]),
+            connection("staging-web", Some("staging"), &["web"]),
+        ]
+    }
+
+    #[test]
+    fn filters_by_tag_folder_and_text() {
+        let filter = |value: serde_json::Value| -> ConnectionFilter {
+            serde_json::from_value(value).unwrap()
+        };
+        let by_tags = query(
+            inventory(),
+            &filter(serde_json::json!({ "tags": ["prod", "WEB"] })),
+        );
+        assert_eq!(names(&by_tags), ["web-1"]);
+
+        let prod = filter(serde_json::json!({ "folder": "/prod" }));
+        assert_eq!(names(&query(inventory(), &prod)), ["db-1"]);
+        let prod_tree = filter(serde_json::json!({ "folder": "prod", "includeSubfolders": true }));
+        assert_eq!(names(&query(inventory(), &prod_tree)), ["db-1", "web-1"]);
+        let unfiled = filter(serde_json::json!({ "folder": "" }));
+        assert_eq!(names(&query(inventory(), &unfiled)), ["lab"]);
+
+        let text = filter(serde_json::json!({ "text": "WEB example" }));
+        assert_eq!(names(&query(inventory(), &text)), ["staging-web", "web-1"]);
+    }
+
+    #[test]
+    fn filters_favorites_color_and_recent_with_paging() {
+        let favorites = ConnectionFilter {
+            favorites_only: true,
+            color: Some("#EF4444".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(&query(inventory(), &favorites)), ["web-1"]);
+
+        let recent = ConnectionFilter {
+            recent_days: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(names(&query(inventory(), &recent)), ["web-1"]);
+
+        let page = query(
+            inventory(),
+            &ConnectionFilter {
+                sort: ConnectionSort::Recent,
+                offset: 1,
+                limit: Some(2),
+                ..Default::default()
+            },
+        );
+        assert_eq!(page.total, 4);
+        assert_eq!(names(&page), ["db-1", "lab"]);
+    }
+}
@@ -0,0 +1,798 @@
+//! Dev containers of remote projects, driven through `docker` over SSH.
+//!
+//! Attaching reads the project's `.devcontainer/devcontainer.json` (or
+//! `.devcontainer.json`), finds the container created for it by label
+//! (`devcontainer.local_folder`, the one the devcontainer CLI and VS Code
+//! use) and starts it, or creates it: with the `devcontainer` CLI when the
+//! server has it, so features and lifecycle commands run, otherwise from the
+//! config's image, Dockerfile or compose service.
+//!
+//! An attached [`DevcontainerSession`] scopes work to the container:
+//! terminals, commands and listings run through `docker exec` as the
+//! configured user in the workspace folder, and files move with `docker cp`.
+
+use crate::ai::tool_exec_support::shell_quote;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+/// Label the devcontainer CLI puts on containers, holding the project path.
+pub const FOLDER_LABEL: &str = "devcontainer.local_folder";
+pub const CONFIG_LABEL: &str = "devcontainer.config_file";
+/// Config locations tried in order, relative to the project.
+pub const CONFIG_CANDIDATES: [&str; 2] = [".devcontainer/devcontainer.json", ".devcontainer.json"];
+/// Keeps an image's container running when its command is overridden,
+/// as the devcontainer CLI does.
+const KEEP_ALIVE: &str =
+    "echo Container started; trap \"exit 0\" TERM; while sleep 1 & wait $!; do :; done";
+/// Exit status of [`detect_command`] when the project has no config.
+pub const NO_CONFIG_STATUS: u32 = 3;
+
+/// Attached containers by session id.
+static SESSIONS: LazyLock<Mutex<HashMap<String, DevcontainerSession>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            Self::One(value) => vec![value.clone()],
+            Self::Many(values) => values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BuildConfig {
+    pub dockerfile: Option<String>,
+    pub context: Option<String>,
+    pub args: BTreeMap<String, String>,
+}
+
+/// The parts of `devcontainer.json` needed to run the container.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DevcontainerConfig {
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub build: Option<BuildConfig>,
+    /// Pre-`build` spelling of `build.dockerfile`.
+    pub docker_file: Option<String>,
+    /// Pre-`build` spelling of `build.context`.
+    pub context: Option<String>,
+    pub docker_compose_file: Option<OneOrMany>,
+    pub service: Option<String>,
+    pub workspace_folder: Option<String>,
+    pub remote_user: Option<String>,
+    pub container_user: Option<String>,
+    pub run_args: Vec<String>,
+    pub container_env: BTreeMap<String, String>,
+    /// `null` values unset a variable; those are left out.
+    pub remote_env: BTreeMap<String, Option<String>>,
+    pub override_command: Option<bool>,
+}
+
+/// Where the container comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerSource {
+    Image(String),
+    Dockerfile {
+        dockerfile: String,
+        context: String,
+        args: BTreeMap<String, String>,
+    },
+    Compose {
+        files: Vec<String>,
+        service: String,
+    },
+}
+
+/// A container attached with `devcontainer_attach`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DevcontainerSession {
+    pub id: String,
+    pub connection_id: String,
+    pub name: String,
+    /// Project folder on the server.
+    pub project_path: String,
+    /// Config file, relative to `project_path`.
+    pub config_path: String,
+    pub container_id: String,
+    /// Project folder inside the container.
+    pub workspace_folder: String,
+    /// User commands run as; the image's default when unset.
+    pub remote_user: Option<String>,
+    pub remote_env: BTreeMap<String, String>,
+}
+
+/// Result line of `devcontainer up`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpResult {
+    outcome: String,
+    container_id: Option<String>,
+    remote_user: Option<String>,
+    remote_workspace_folder: Option<String>,
+    message: Option<String>,
+}
+
+/// What `devcontainer up` reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliUp {
+    pub container_id: String,
+    pub remote_user: Option<String>,
+    pub workspace_folder: Option<String>,
+}
+
+/// Strips `//` and `/* */` comments and trailing commas, which
+/// `devcontainer.json` allows, leaving plain JSON.
+pub fn strip_jsonc(text: &str) -> String {
+    let mut out = Vec::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                out.extend(chars.next());
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+
+    // Drop commas followed only by whitespace and a closing bracket.
+    let mut json = String::with_capacity(out.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in out.iter().enumerate() {
+        if in_string {
+            in_string = escaped || *c != '"';
+            escaped = !escaped && *c == '\\';
+        } else if *c == '"' {
+            in_string = true;
+        } else if *c == ',' {
+            let next = out[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']') | None) {
+                continue;
+            }
+        }
+        json.push(*c);
+    }
+    json
+}
+
+pub fn parse_config(text: &str) -> Result<DevcontainerConfig> {
+    serde_json::from_str(&strip_jsonc(text)).context("Invalid devcontainer.json")
+}
+
+/// Prints the config's path relative to `project`, then its content; exits
+/// with [`NO_CONFIG_STATUS`] when there is none.
+pub fn detect_command(project: &str) -> String {
+    let candidates: Vec<String> = CONFIG_CANDIDATES.iter().map(|c| shell_quote(c)).collect();
+    format!(
+        "cd {} || exit 2; for f in {}; do if [ -f \"$f\" ]; then echo \"$f\"; cat \"$f\"; exit 0; fi; done; exit {}",
+        shell_quote(project),
+        candidates.join(" "),
+        NO_CONFIG_STATUS
+    )
+}
+
+/// Splits [`detect_command`] output into the config path and its content.
+pub fn split_detected(stdout: &str) -> Option<(String, String)> {
+    let (path, content) = stdout.split_once('\n')?;
+    Some((path.trim().to_string(), content.to_string()))
+}
+
+/// Joins `path` onto `base` unless it is absolute, resolving `..` segments.
+fn join_path(base: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = if path.starts_with('/') {
+        Vec::new()
+    } else {
+        base.split('/').filter(|part| !part.is_empty()).collect()
+    };
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+fn parent_dir(path: &str) -> &str {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) => "/",
+        Some((parent, _)) => parent,
+        None => ".",
+    }
+}
+
+fn base_name(path: &str) -> &str {
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(path)
+}
+
+/// Short stable hash for image tags (FNV-1a).
+fn path_hash(path: &str) -> String {
+    let hash = path.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+impl DevcontainerConfig {
+    /// How the container is created. Relative paths are resolved against the
+    /// directory holding the config, as the spec defines.
+    pub fn source(&self, project: &str, config_path: &str) -> Result<ContainerSource> {
+        let config_dir = parent_dir(&join_path(project, config_path)).to_string();
+        if let Some(files) = &self.docker_compose_file {
+            let service = self
+                .service
+                .clone()
+                .ok_or_else(|| anyhow!("devcontainer.json names compose files but no `service`"))?;
+            let files = files
+                .to_vec()
+                .iter()
+                .map(|file| join_path(&config_dir, file))
+                .collect();
+            return Ok(ContainerSource::Compose { files, service });
+        }
+        let build = self.build.clone().unwrap_or_default();
+        if let Some(dockerfile) = build.dockerfile.or_else(|| self.docker_file.clone()) {
+            let context = build
+                .context
+                .or_else(|| self.context.clone())
+                .unwrap_or_else(|| ".".to_string());
+            return Ok(ContainerSource::Dockerfile {
+                dockerfile: join_path(&config_dir, &dockerfile),
+                context: join_path(&config_dir, &context),
+                args: build.args,
+            });
+        }
+        match &self.image {
+            Some(image) => Ok(ContainerSource::Image(image.clone())),
+            None => {
+                bail!("devcontainer.json has no `image`, `build.dockerfile` or `dockerComposeFile`")
+            }
+        }
+    }
+
+    /// Project folder inside the container: `workspaceFolder`, else where
+    /// the devcontainer CLI mounts it (`/` for compose, which mounts nothing).
+    pub fn workspace_folder(&self, project: &str) -> String {
+        match &self.workspace_folder {
+            Some(folder) if !folder.trim().is_empty() => folder.trim().to_string(),
+            _ if self.docker_compose_file.is_some() => "/".to_string(),
+            _ => format!("/workspaces/{}", base_name(project)),
+        }
+    }
+
+    /// `name`, else the project folder's name.
+    pub fn display_name(&self, project: &str) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| base_name(project).to_string())
+    }
+
+    pub fn user(&self) -> Option<String> {
+        self.remote_user
+            .clone()
+            .or_else(|| self.container_user.clone())
+            .filter(|user| !user.trim().is_empty())
+    }
+
+    pub fn remote_env(&self) -> BTreeMap<String, String> {
+        self.remote_env
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), value.clone()?)))
+            .collect()
+    }
+}
+
+/// Lists containers created for `project` as `<id>\t<state>` lines.
+pub fn find_command(project: &str) -> String {
+    format!(
+        "docker ps -a --filter {} --format '{{{{.ID}}}}\t{{{{.State}}}}'",
+        shell_quote(&format!("label={}={}", FOLDER_LABEL, project))
+    )
+}
+
+/// First container in [`find_command`] output and whether it is running.
+pub fn parse_found(stdout: &str) -> Option<(String, bool)> {
+    stdout.lines().find_map(|line| {
+        let (id, state) = line.trim().split_once('\t')?;
+        Some((id.to_string(), state.trim() == "running"))
+    })
+}
+
+/// Runs `devcontainer up` when the CLI is installed; exits 127 otherwise.
+pub fn cli_up_command(project: &str) -> String {
+    format!(
+        "command -v devcontainer >/dev/null 2>&1 || exit 127; devcontainer up --workspace-folder {}",
+        shell_quote(project)
+    )
+}
+
+/// Reads the JSON result line `devcontainer up` ends its output with.
+pub fn parse_cli_up(stdout: &str) -> Result<CliUp> {
+    let result: UpResult = stdout
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str(line.trim()).ok())
+        .ok_or_else(|| anyhow!("`devcontainer up` printed no result"))?;
+    if result.outcome != "success" {
+        bail!(
+            "`devcontainer up` failed: {}",
+            result.message.unwrap_or(result.outcome)
+        );
+    }
+    Ok(CliUp {
+        container_id: result
+            .container_id
+            .ok_or_else(|| anyhow!("`devcontainer up` reported no container"))?,
+        remote_user: result.remote_user,
+        workspace_folder: result.remote_workspace_folder,
+    })
+}
+
+/// Shell script creating and starting the container without the
+/// devcontainer CLI. Build output goes to stderr; the last stdout line is
+/// the container id.
+pub fn create_script(
+    config: &DevcontainerConfig,
+    project: &str,
+    config_path: &str,
+) -> Result<String> {
+    let workspace = config.workspace_folder(project);
+    let (build, image) = match config.source(project, config_path)? {
+        ContainerSource::Compose { files, service } => {
+            let files: Vec<String> = files
+                .iter()
+                .map(|file| format!("-f {}", shell_quote(file)))
+                .collect();
+            return Ok(format!(
+                "if docker compose version >/dev/null 2>&1; then dc='docker compose'; else dc=docker-compose; fi; \
+                 cd {dir} && $dc {files} up -d {service} >&2 && $dc {files} ps -q {service}",
+                dir = shell_quote(parent_dir(&join_path(project, config_path))),
+                files = files.join(" "),
+                service = shell_quote(&service),
+            ));
+        }
+        ContainerSource::Image(image) => (String::new(), image),
+        ContainerSource::Dockerfile {
+            dockerfile,
+            context,
+            args,
+        } => {
+            let tag = format!("zync-devcontainer-{}", path_hash(project));
+            let build_args: String = args
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        " --build-arg {}",
+                        shell_quote(&format!("{}={}", key, value))
+                    )
+                })
+                .collect();
+            (
+                format!(
+                    "docker build -t {tag} -f {}{} {} >&2 && ",
+                    shell_quote(&dockerfile),
+                    build_args,
+                    shell_quote(&context),
+                    tag = tag
+                ),
+                tag,
+            )
+        }
+    };
+
+    let mut run = vec![
+        "docker run -d".to_string(),
+        format!(
+            "--label {}",
+            shell_quote(&format!("{}={}", FOLDER_LABEL, project))
+        ),
+        format!(
+            "--label {}",
+            shell_quote(&format!(
+                "{}={}",
+                CONFIG_LABEL,
+                join_path(project, config_path)
+            ))
+        ),
+        format!(
+            "--mount {}",
+            shell_quote(&format!(
+                "type=bind,source={},target={}",
+                project, workspace
+            ))
+        ),
+        format!("-w {}", shell_quote(&workspace)),
+    ];
+    if let Some(user) = config
+        .container_user
+        .as_deref()
+        .filter(|u| !u.trim().is_empty())
+    {
+        run.push(format!("-u {}", shell_quote(user)));
+    }
+    for (key, value) in &config.container_env {
+        run.push(format!("-e {}", shell_quote(&format!("{}={}", key, value))));
+    }
+    run.extend(config.run_args.iter().map(|arg| shell_quote(arg)));
+    let override_command = config.override_command.unwrap_or(true);
+    if override_command {
+        run.push("--entrypoint /bin/sh".to_string());
+    }
+    run.push(shell_quote(&image));
+    if override_command {
+        run.push(format!("-c {}", shell_quote(KEEP_ALIVE)));
+    }
+    Ok(format!("{}{}", build, run.join(" ")))
+}
+
+impl DevcontainerSession {
+    /// `docker exec` of `command` (run by `sh -c`) inside the container.
+    /// `tty` allocates a terminal, `stdin` keeps input open.
+    pub fn exec_command(&self, command: &str, tty: bool, stdin: bool) -> String {
+        let mut parts = vec!["docker exec".to_string()];
+        match (tty, stdin) {
+            (true, _) => parts.push("-it".to_string()),
+            (false, true) => parts.push("-i".to_string()),
+            (false, false) => {}
+        }
+        if let Some(user) = &self.remote_user {
+            parts.push(format!("-u {}", shell_quote(user)));
+        }
+        parts.push(format!("-w {}", shell_quote(&self.workspace_folder)));
+        for (key, value) in &self.remote_env {
+            parts.push(format!("-e {}", shell_quote(&format!("{}={}", key, value))));
+        }
+        parts.push(shell_quote(&self.container_id));
+        parts.push(format!("sh -c {}", shell_quote(command)));
+        parts.join(" ")
+    }
+
+    /// Interactive login shell, bash when the image has it.
+    pub fn shell_command(&self) -> String {
+        self.exec_command(
+            "if command -v bash >/dev/null 2>&1; then exec bash -l; else exec sh -l; fi",
+            true,
+            false,
+        )
+    }
+
+    /// Resolves `path` against the workspace folder.
+    pub fn container_path(&self, path: &str) -> String {
+        join_path(&self.workspace_folder, path)
+    }
+
+    /// Lists a directory in `ls -la` format.
+    pub fn list_command(&self, path: &str) -> String {
+        let path = shell_quote(&self.container_path(path));
+        self.exec_command(
+            &format!(
+                "LC_ALL=C ls -la --time-style=+%s -- {p} 2>/dev/null || LC_ALL=C ls -la -- {p}",
+                p = path
+            ),
+            false,
+            false,
+        )
+    }
+
+    /// Writes stdin to `path` as the session user, so existing files keep
+    /// their owner (`docker cp` would leave them owned by root).
+    pub fn write_command(&self, path: &str) -> String {
+        self.exec_command(
+            &format!("cat > {}", shell_quote(&self.container_path(path))),
+            false,
+            true,
+        )
+    }
+
+    /// Streams `path` out of the container as a tar archive.
+    pub fn read_command(&self, path: &str) -> String {
+        format!(
+            "docker cp -L {} -",
+            shell_quote(&format!(
+                "{}:{}",
+                self.container_id,
+                self.container_path(path)
+            ))
+        )
+    }
+
+    /// `docker cp` between a path on the server and one in the container.
+    pub fn copy_command(
+        &self,
+        from_host: &str,
+        to_container: &str,
+        into_container: bool,
+    ) -> String {
+        let container = shell_quote(&format!(
+            "{}:{}",
+            self.container_id,
+            self.container_path(to_container)
+        ));
+        if into_container {
+            format!("docker cp {} {}", shell_quote(from_host), container)
+        } else {
+            format!("docker cp {} {}", container, shell_quote(from_host))
+        }
+    }
+}
+
+fn parse_octal(field: &[u8]) -> Result<usize> {
+    let text = std::str::from_utf8(field)?.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    usize::from_str_radix(text, 8).map_err(|_| anyhow!("Invalid tar header"))
+}
+
+/// Content of the first regular file in a tar archive (what `docker cp
+/// <path> -` streams). PAX and GNU extension headers are skipped.
+pub fn first_file_in_tar(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut offset = 0;
+    while offset + 512 <= archive.len() {
+        let header = &archive[offset..offset + 512];
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        let size = parse_octal(&header[124..136])?;
+        let start = offset + 512;
+        match header[156] {
+            b'0' | 0 => {
+                return archive
+                    .get(start..start + size)
+                    .map(<[u8]>::to_vec)
+                    .ok_or_else(|| anyhow!("Truncated tar archive"));
+            }
+            b'5' => bail!("Path is a directory"),
+            _ => offset = start + size.div_ceil(512) * 512,
+        }
+    }
+    bail!("Path is not a regular file")
+}
+
+pub fn session_id(connection_id: &str, container_id: &str) -> String {
+    let short: String = container_id.chars().take(12).collect();
+    format!("{}:{}", connection_id, short)
+}
+
+pub fn register(session: DevcontainerSession) {
+    SESSIONS.lock().unwrap().insert(session.id.clone(), session);
+}
+
+pub fn get(session_id: &str) -> Result<DevcontainerSession> {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| anyhow!("Dev container session {} is not attached", session_id))
+}
+
+pub fn list() -> Vec<DevcontainerSession> {
+    let mut sessions: Vec<DevcontainerSession> =
+        SESSIONS.lock().unwrap().values().cloned().collect();
+    sessions.sort_by(|a, b| a.id.cmp(&b.id));
+    sessions
+}
+
+pub fn detach(session_id: &str) -> bool {
+    SESSIONS.lock().unwrap().remove(session_id).is_some()
+}
+
+/// Forgets the sessions of a closed connection; the containers keep running.
+pub fn forget_connection(connection_id: &str) {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_, session| session.connection_id != connection_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        // Node app
+        "name": "web", /* inline */
+        "build": {"dockerfile": "Dockerfile", "context": "..", "args": {"NODE": "20"}},
+        "remoteUser": "node",
+        "remoteEnv": {"PATH_EXTRA": "/opt/bin", "UNSET": null},
+        "containerEnv": {"TZ": "UTC"},
+        "runArgs": ["--cap-add=SYS_PTRACE",],
+        "postCreateCommand": "echo // not a comment",
+    }"#;
+
+    fn session() -> DevcontainerSession {
+        DevcontainerSession {
+            id: "c1:abc".to_string(),
+            connection_id: "c1".to_string(),
+            name: "web".to_string(),
+            project_path: "/home/me/app".to_string(),
+            config_path: ".devcontainer/devcontainer.json".to_string(),
+            container_id: "abc123".to_string(),
+            workspace_folder: "/workspaces/app".to_string(),
+            remote_user: Some("node".to_string()),
+            remote_env: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn parses_jsonc_configs() {
+        let config = parse_config(CONFIG).unwrap();
+        assert_eq!(config.name.as_deref(), Some("web"));
+        assert_eq!(config.user().as_deref(), Some("node"));
+        assert_eq!(config.run_args, ["--cap-add=SYS_PTRACE"]);
+        assert_eq!(config.remote_env().len(), 1);
+        assert_eq!(strip_jsonc(r#"{"a": "x,}", }"#), r#"{"a": "x,}" }"#);
+        assert_eq!(strip_jsonc(r#"{"a": "\"//"}"#), r#"{"a": "\"//"}"#);
+    }
+
+    #[test]
+    fn resolves_the_container_source() {
+        let config = parse_config(CONFIG).unwrap();
+        let source = config
+            .source("/home/me/app", ".devcontainer/devcontainer.json")
+            .unwrap();
+        assert_eq!(
+            source,
+            ContainerSource::Dockerfile {
+                dockerfile: "/home/me/app/.devcontainer/Dockerfile".to_string(),
+                context: "/home/me/app".to_string(),
+                args: BTreeMap::from([("NODE".to_string(), "20".to_string())]),
+            }
+        );
+        assert_eq!(config.workspace_folder("/home/me/app"), "/workspaces/app");
+
+        let compose = parse_config(
+            r#"{"dockerComposeFile": ["../docker-compose.yml", "dev.yml"], "service": "api"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            compose
+                .source("/srv/api", ".devcontainer/devcontainer.json")
+                .unwrap(),
+            ContainerSource::Compose {
+                files: vec![
+                    "/srv/api/docker-compose.yml".to_string(),
+                    "/srv/api/.devcontainer/dev.yml".to_string()
+                ],
+                service: "api".to_string(),
+            }
+        );
+        assert_eq!(compose.workspace_folder("/srv/api"), "/");
+        assert!(parse_config("{}")
+            .unwrap()
+            .source("/p", ".devcontainer.json")
+            .is_err());
+    }
+
+    #[test]
+    fn builds_run_scripts() {
+        let config =
+            parse_config(r#"{"image": "mcr.microsoft.com/devcontainers/base:ubuntu"}"#).unwrap();
+        let script = create_script(&config, "/home/me/app", ".devcontainer.json").unwrap();
+        assert!(
+            script.starts_with("docker run -d --label 'devcontainer.local_folder=/home/me/app'")
+        );
+        assert!(script.contains("'type=bind,source=/home/me/app,target=/workspaces/app'"));
+        assert!(script
+            .contains("--entrypoint /bin/sh 'mcr.microsoft.com/devcontainers/base:ubuntu' -c "));
+
+        let config = parse_config(CONFIG).unwrap();
+        let script =
+            create_script(&config, "/home/me/app", ".devcontainer/devcontainer.json").unwrap();
+        assert!(script.starts_with("docker build -t zync-devcontainer-"));
+        assert!(script.contains(" --build-arg 'NODE=20' '/home/me/app' >&2 && docker run -d"));
+        assert!(script.contains("-e 'TZ=UTC' '--cap-add=SYS_PTRACE'"));
+    }
+
+    #[test]
+    fn reads_docker_and_cli_output() {
+        assert_eq!(
+            parse_found("3f2a\texited\n9b1c\trunning\n"),
+            Some(("3f2a".to_string(), false))
+        );
+        assert_eq!(parse_found(""), None);
+        let up = parse_cli_up(
+            "[1 ms] Start\n{\"outcome\":\"success\",\"containerId\":\"f00\",\"remoteUser\":\"vscode\",\"remoteWorkspaceFolder\":\"/workspaces/app\"}\n",
+        )
+        .unwrap();
+        assert_eq!(up.container_id, "f00");
+        assert_eq!(up.remote_user.as_deref(), Some("vscode"));
+        assert!(
+            parse_cli_up("{\"outcome\":\"error\",\"message\":\"no docker\"}")
+                .unwrap_err()
+                .to_string()
+                .contains("no docker")
+        );
+        assert_eq!(
+            split_detected(".devcontainer.json\n{}\n"),
+            Some((".devcontainer.json".to_string(), "{}\n".to_string()))
+        );
+    }
+
+    #[test]
+    fn scopes_commands_to_the_container() {
+        let session = session();
+        assert_eq!(
+            session.exec_command("make test", false, false),
+            "docker exec -u 'node' -w '/workspaces/app' 'abc123' sh -c 'make test'"
+        );
+        assert!(session
+            .shell_command()
+            .starts_with("docker exec -it -u 'node'"));
+        assert_eq!(
+            session.container_path("src/main.rs"),
+            "/workspaces/app/src/main.rs"
+        );
+        assert_eq!(session.container_path("/etc/hosts"), "/etc/hosts");
+        assert_eq!(
+            session.read_command("a.txt"),
+            "docker cp -L 'abc123:/workspaces/app/a.txt' -"
+        );
+        assert_eq!(
+            session.copy_command("/tmp/x", "out", false),
+            "docker cp 'abc123:/workspaces/app/out' '/tmp/x'"
+        );
+    }
+
+    #[test]
+    fn extracts_the_first_file_from_tar() {
+        let mut header = [0u8; 512];
+        header[..5].copy_from_slice(b"a.txt");
+        header[124..136].copy_from_slice(b"00000000005\0");
+        header[156] = b'0';
+        let mut archive = header.to_vec();
+        archive.extend_from_slice(b"hello");
+        archive.resize(2048, 0);
+        assert_eq!(first_file_in_tar(&archive).unwrap(), b"hello");
+        assert!(first_file_in_tar(&archive[..515]).is_err());
+
+        archive[156] = b'5';
+        assert!(first_file_in_tar(&archive).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! Host resolution for outbound SSH connections.
+//!
+//! Honors per-connection [`ResolutionOptions`]: a static address override, a custom
+//! DNS server (queried directly over UDP), an IPv4/IPv6 preference, and a bounded
+//! resolution timeout. Everything else falls back to the system resolver.
+
+use crate::types::{AddressFamilyPreference, ResolutionOptions};
+use anyhow::{anyhow, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+const DEFAULT_RESOLUTION_TIMEOUT_MS: u64 = 10_000;
+const DNS_PORT: u16 = 53;
+const DNS_TYPE_A: u16 = 1;
+const DNS_TYPE_AAAA: u16 = 28;
+const DNS_CLASS_IN: u16 = 1;
+const MAX_DNS_PACKET: usize = 4096;
+
+/// How the final address list was produced — surfaced in connect progress events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionSource {
+    Literal,
+    StaticOverride,
+    CustomDns,
+    System,
+}
+
+impl ResolutionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Literal => "literal",
+            Self::StaticOverride => "static-override",
+            Self::CustomDns => "custom-dns",
+            Self::System => "system",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedHost {
+    pub addresses: Vec<SocketAddr>,
+    pub source: ResolutionSource,
+}
+
+pub fn resolution_timeout(options: Option<&ResolutionOptions>) -> Duration {
+    Duration::from_millis(
+        options
+            .and_then(|opts| opts.timeout_ms)
+            .filter(|ms| *ms > 0)
+            .unwrap_or(DEFAULT_RESOLUTION_TIMEOUT_MS),
+    )
+}
+
+/// Resolve `host:port` honoring the connection's resolution options.
+/// The whole lookup (including custom DNS round trips) is bounded by the configured timeout.
+pub async fn resolve_host(
+    host: &str,
+    port: u16,
+    options: Option<&ResolutionOptions>,
+) -> Result<ResolvedHost> {
+    let timeout = resolution_timeout(options);
+    tokio::time::timeout(timeout, resolve_host_inner(host, port, options))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "Resolving '{}' timed out after {}ms",
+                host,
+                timeout.as_millis()
+            )
+        })?
+}
+
+async fn resolve_host_inner(
+    host: &str,
+    port: u16,
+    options: Option<&ResolutionOptions>,
+) -> Result<ResolvedHost> {
+    let preference = options
+        .and_then(|opts| opts.address_family)
+        .unwrap_or_default();
+
+    if let Some(addr) = static_override(options)? {
+        return Ok(ResolvedHost {
+            addresses: vec![SocketAddr::new(addr, port)],
+            source: ResolutionSource::StaticOverride,
+        });
+    }
+
+    if let Ok(addr) = host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+        return Ok(ResolvedHost {
+            addresses: vec![SocketAddr::new(addr, port)],
+            source: ResolutionSource::Literal,
+        });
+    }
+
+    let custom_server = options
+        .and_then(|opts| opts.dns_server.as_deref())
+        .map(str::trim)
+        .filter(|server| !server.is_empty());
+
+    let (mut addresses, source) = if let Some(server) = custom_server {
+        let server_addr = parse_dns_server(server)?;
+        let ips = query_custom_dns(server_addr, host, preference).await?;
+        (
+            ips.into_iter()
+                .map(|ip| SocketAddr::new(ip, port))
+                .collect::<Vec<_>>(),
+            ResolutionSource::CustomDns,
+        )
+    } else {
+        let addrs = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| anyhow!("Failed to resolve '{}': {}", host, e))?
+            .collect::<Vec<_>>();
+        (addrs, ResolutionSource::System)
+    };
+
+    order_by_preference(&mut addresses, preference);
+    addresses.dedup();
+    if addresses.is_empty() {
+        return Err(anyhow!("No addresses found for '{}'", host));
+    }
+
+    Ok(ResolvedHost { addresses, source })
+}
+
+fn static_override(options: Option<&ResolutionOptions>) -> Result<Option<IpAddr>> {
+    let Some(raw) = options
+        .and_then(|opts| opts.static_address.as_deref())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    else {
+        return Ok(None);
+    };
+    raw.trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+        .map(Some)
+        .map_err(|_| anyhow!("Static address override '{}' is not a valid IP address", raw))
+}
+
+/// Stable sort so the preferred family is tried first while keeping resolver order within a family.
+pub(crate) fn order_by_preference(addresses: &mut [SocketAddr], preference: AddressFamilyPreference) {
+    match preference {
+        AddressFamilyPreference::Any => {}
+        AddressFamilyPreference::Ipv4 => addresses.sort_by_key(|addr| !addr.is_ipv4()),
+        AddressFamilyPreference::Ipv6 => addresses.sort_by_key(|addr| !addr.is_ipv6()),
+    }
+}
+
+/// Accepts `1.1.1.1`, `1.1.1.1:5353`, `2606:4700::1111`, or `[2606:4700::1111]:53`.
+pub(crate) fn parse_dns_server(value: &str) -> Result<SocketAddr> {
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    value
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        .map_err(|_| anyhow!("Invalid DNS server '{}': expected an IP address", value))
+}
+
+async fn query_custom_dns(
+    server: SocketAddr,
+    host: &str,
+    preference: AddressFamilyPreference,
+) -> Result<Vec<IpAddr>> {
+    let bind_addr: SocketAddr = if server.is_ipv4() {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)
+    } else {
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .await
+        .map_err(|e| anyhow!("Failed to open DNS socket: {}", e))?;
+    socket
+        .connect(server)
+        .await
+        .map_err(|e| anyhow!("Failed to reach DNS server {}: {}", server, e))?;
+
+    let query_types = match preference {
+        AddressFamilyPreference::Ipv6 => [DNS_TYPE_AAAA, DNS_TYPE_A],
+        _ => [DNS_TYPE_A, DNS_TYPE_AAAA],
+    };
+
+    let mut results = Vec::new();
+    let mut last_error = None;
+    for (index, qtype) in query_types.iter().enumerate() {
+        let id = (std::process::id() as u16).wrapping_add(index as u16);
+        let packet = encode_query(id, host, *qtype)?;
+        socket
+            .send(&packet)
+            .await
+            .map_err(|e| anyhow!("Failed to send DNS query to {}: {}", server, e))?;
+
+        let mut buf = vec![0u8; MAX_DNS_PACKET];
+        let len = socket
+            .recv(&mut buf)
+            .await
+            .map_err(|e| anyhow!("Failed to read DNS response from {}: {}", server, e))?;
+        match parse_response(id, &buf[..len]) {
+            Ok(ips) => results.extend(ips),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    if results.is_empty() {
+        return Err(last_error.unwrap_or_else(|| {
+            anyhow!("DNS server {} returned no addresses for '{}'", server, host)
+        }));
+    }
+    Ok(results)
+}
+
+pub(crate) fn encode_query(id: u16, host: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0x0100u16.to_be_bytes()); // standard query, recursion desired
+    packet.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // ANCOUNT, NSCOUNT, ARCOUNT
+
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(anyhow!("Invalid hostname '{}' for DNS query", host));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Result<u16> {
+    packet
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Truncated DNS response"))
+}
+
+/// Advance past a (possibly compressed) domain name and return the offset after it.
+fn skip_name(packet: &[u8], mut offset: usize) -> Result<usize> {
+    loop {
+        let len = *packet
+            .get(offset)
+            .ok_or_else(|| anyhow!("Truncated DNS name"))?;
+        if len & 0xC0 == 0xC0 {
+            return Ok(offset + 2);
+        }
+        if len == 0 {
+            return Ok(offset + 1);
+        }
+        offset += 1 + len as usize;
+    }
+}
+
+pub(crate) fn parse_response(expected_id: u16, packet: &[u8]) -> Result<Vec<IpAddr>> {
+    if read_u16(packet, 0)? != expected_id {
+        return Err(anyhow!("DNS response id mismatch"));
+    }
+    let flags = read_u16(packet, 2)?;
+    let rcode = flags & 0x000F;
+    if rcode == 3 {
+        return Err(anyhow!("DNS server reported NXDOMAIN"));
+    }
+    if rcode != 0 {
+        return Err(anyhow!("DNS server returned error code {}", rcode));
+    }
+
+    let qdcount = read_u16(packet, 4)?;
+    let ancount = read_u16(packet, 6)?;
+    let mut offset = 12;
+    for _ in 0..qdcount {
+        offset = skip_name(packet, offset)? + 4;
+    }
+
+    let mut addresses = Vec::new();
+    for _ in 0..ancount {
+        offset = skip_name(packet, offset)?;
+        let rtype = read_u16(packet, offset)?;
+        let rdlength = read_u16(packet, offset + 8)? as usize;
+        let rdata_start = offset + 10;
+        let rdata = packet
+            .get(rdata_start..rdata_start + rdlength)
+            .ok_or_else(|| anyhow!("Truncated DNS record"))?;
+        match (rtype, rdlength) {
+            (DNS_TYPE_A, 4) => {
+                addresses.push(IpAddr::V4(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3])))
+            }
+            (DNS_TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(rdata);
+                addresses.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => {}
+        }
+        offset = rdata_start + rdlength;
+    }
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer_packet(id: u16, host: &str, records: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut packet = encode_query(id, host, DNS_TYPE_A).expect("query");
+        packet[2] = 0x81;
+        packet[3] = 0x80;
+        packet[6..8].copy_from_slice(&(records.len() as u16).to_be_bytes());
+        for (rtype, rdata) in records {
+            packet.extend_from_slice(&[0xC0, 0x0C]); // pointer to question name
+            packet.extend_from_slice(&rtype.to_be_bytes());
+            packet.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+            packet.extend_from_slice(&300u32.to_be_bytes());
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(rdata);
+        }
+        packet
+    }
+
+    #[test]
+    fn encode_query_writes_labels_and_type() {
+        let packet = encode_query(0x1234, "db.internal", DNS_TYPE_AAAA).expect("query");
+        assert_eq!(&packet[0..2], &[0x12, 0x34]);
+        assert_eq!(&packet[12..], b"\x02db\x08internal\x00\x00\x1c\x00\x01");
+    }
+
+    #[test]
+    fn encode_query_rejects_empty_labels() {
+        assert!(encode_query(1, "bad..host", DNS_TYPE_A).is_err());
+    }
+
+    #[test]
+    fn parse_response_reads_a_and_aaaa_records() {
+        let mut v6 = vec![0u8; 16];
+        v6[15] = 1;
+        let packet = answer_packet(
+            7,
+            "host.example",
+            &[(DNS_TYPE_A, vec![10, 0, 0, 5]), (5, vec![0xC0, 0x0C]), (DNS_TYPE_AAAA, v6)],
+        );
+        let ips = parse_response(7, &packet).expect("parse");
+        assert_eq!(
+            ips,
+            vec![
+                "10.0.0.5".parse::<IpAddr>().unwrap(),
+                "::1".parse::<IpAddr>().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_response_reports_nxdomain_and_id_mismatch() {
+        let mut packet = answer_packet(9, "missing.example", &[]);
+        assert!(parse_response(10, &packet).is_err());
+        packet[3] = 0x83;
+        let err = parse_response(9, &packet).unwrap_err().to_string();
+        assert!(err.contains("NXDOMAIN"));
+    }
+
+    #[test]
+    fn parse_dns_server_defaults_port_53() {
+        assert_eq!(
+            parse_dns_server("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            parse_dns_server("[2606:4700::1111]:5353").unwrap().port(),
+            5353
+        );
+        assert_eq!(parse_dns_server("2606:4700::1111").unwrap().port(), 53);
+        assert!(parse_dns_server("dns.example").is_err());
+    }
+
+    #[test]
+    fn order_by_preference_keeps_family_order_stable() {
+        let mut addrs: Vec<SocketAddr> = vec![
+            "[::1]:22".parse().unwrap(),
+            "10.0.0.1:22".parse().unwrap(),
+            "[::2]:22".parse().unwrap(),
+            "10.0.0.2:22".parse().unwrap(),
+        ];
+        order_by_preference(&mut addrs, AddressFamilyPreference::Ipv4);
+        assert_eq!(addrs[0], "10.0.0.1:22".parse().unwrap());
+        assert_eq!(addrs[1], "10.0.0.2:22".parse().unwrap());
+        order_by_preference(&mut addrs, AddressFamilyPreference::Ipv6);
+        assert_eq!(addrs[0], "[::1]:22".parse().unwrap());
+    }
+
+    #[tokio::test]
+    async fn resolve_host_prefers_static_override() {
+        let options = ResolutionOptions {
+            static_address: Some("192.0.2.10".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolve_host("ignored.invalid", 2222, Some(&options))
+            .await
+            .expect("resolve");
+        assert_eq!(resolved.source, ResolutionSource::StaticOverride);
+        assert_eq!(resolved.addresses, vec!["192.0.2.10:2222".parse().unwrap()]);
+    }
+}
@@ -0,0 +1,514 @@
+//! `doctor_run`: startup integrity checks with one-click fixes.
+//!
+//! Most "opens to a blank screen" reports come down to a handful of causes: a
+//! `dataPath` that vanished or is read-only, a JSON store that no longer parses
+//! (often a half-synced cloud copy), private keys with loose permissions, or a
+//! CLI link left pointing at a deleted AppImage. Each check returns a
+//! [`DoctorCheck`]; the ones that can be repaired carry a [`DoctorFix`] that the
+//! frontend hands back to `doctor_apply_fix`.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// JSON stores kept in the data directory.
+pub const DATA_STORES: &[&str] = &[
+    "connections.json",
+    "tunnels.json",
+    "snippets.json",
+    "actions.json",
+    "session.json",
+    "ghost_history.json",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DoctorFix {
+    /// Drop `dataPath` from settings so the default data directory is used.
+    ResetDataPath,
+    /// Replace a broken store with a leftover copy that still parses.
+    RestoreBackup { path: String, backup: String },
+    /// Move a broken store aside (`<name>.corrupt-<ms>`) so the app starts empty.
+    Quarantine { path: String },
+    /// `chmod 600` a private key.
+    RestrictKey { path: String },
+    /// Point the CLI link at the installed app.
+    RelinkCli { link: String, target: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorCheck {
+    pub id: String,
+    pub title: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<DoctorFix>,
+}
+
+impl DoctorCheck {
+    fn new(
+        id: impl Into<String>,
+        title: impl Into<String>,
+        status: CheckStatus,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            title: title.into(),
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: DoctorFix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorReport {
+    /// No check ended in `error`.
+    pub healthy: bool,
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn new(checks: Vec<DoctorCheck>) -> Self {
+        Self {
+            healthy: checks.iter().all(|c| c.status != CheckStatus::Error),
+            checks,
+        }
+    }
+}
+
+/// Names that Dropbox, Google Drive, Syncthing, Nextcloud or iCloud give to
+/// conflicting copies, e.g. `connections (conflicted copy 2024-01-02).json`,
+/// `connections.sync-conflict-20240102-101010-ABC.json` or `connections 2.json`.
+pub fn is_conflict_copy(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    if lower.contains("conflict") {
+        return true;
+    }
+    let stem = lower.strip_suffix(".json").unwrap_or(&lower);
+    stem.rsplit_once(' ')
+        .is_some_and(|(_, n)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn cloud_provider(path: &Path) -> Option<&'static str> {
+    let text = path.to_string_lossy();
+    [
+        ("Dropbox", "Dropbox"),
+        ("OneDrive", "OneDrive"),
+        ("Mobile Documents", "iCloud Drive"),
+        ("iCloud", "iCloud Drive"),
+        ("Google Drive", "Google Drive"),
+        ("GoogleDrive", "Google Drive"),
+        ("Nextcloud", "Nextcloud"),
+    ]
+    .iter()
+    .find(|(needle, _)| text.contains(needle))
+    .map(|(_, name)| *name)
+}
+
+/// Existence, writability and sync-conflict checks for the data directory.
+pub fn check_data_dir(dir: &Path, custom: bool) -> Vec<DoctorCheck> {
+    let title = "Data directory";
+    let reset = |check: DoctorCheck| {
+        if custom {
+            check.with_fix(DoctorFix::ResetDataPath)
+        } else {
+            check
+        }
+    };
+    if !dir.exists() {
+        return vec![reset(DoctorCheck::new(
+            "data-dir",
+            title,
+            CheckStatus::Error,
+            format!("{} does not exist", dir.display()),
+        ))];
+    }
+    if !dir.is_dir() {
+        return vec![reset(DoctorCheck::new(
+            "data-dir",
+            title,
+            CheckStatus::Error,
+            format!("{} is not a directory", dir.display()),
+        ))];
+    }
+    let probe = dir.join(format!(".zync-doctor-{}", uuid::Uuid::new_v4()));
+    let writable = std::fs::write(&probe, b"ok").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    if !writable {
+        return vec![reset(DoctorCheck::new(
+            "data-dir",
+            title,
+            CheckStatus::Error,
+            format!("{} is not writable", dir.display()),
+        ))];
+    }
+
+    let mut checks = Vec::new();
+    let dir_name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if is_conflict_copy(&dir_name) {
+        checks.push(reset(DoctorCheck::new(
+            "data-dir",
+            title,
+            CheckStatus::Warning,
+            format!(
+                "{} looks like a sync-conflict copy of the real folder",
+                dir.display()
+            ),
+        )));
+    } else {
+        let detail = match cloud_provider(dir) {
+            Some(provider) => format!("{} (synced by {})", dir.display(), provider),
+            None => dir.display().to_string(),
+        };
+        checks.push(DoctorCheck::new("data-dir", title, CheckStatus::Ok, detail));
+    }
+
+    let mut conflicts: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| {
+                    name.ends_with(".json")
+                        && is_conflict_copy(name)
+                        && DATA_STORES
+                            .iter()
+                            .any(|store| name.starts_with(store.trim_end_matches(".json")))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    conflicts.sort();
+    if !conflicts.is_empty() {
+        checks.push(DoctorCheck::new(
+            "sync-conflicts",
+            "Sync conflict copies",
+            CheckStatus::Warning,
+            format!(
+                "Merge or delete these by hand; only the original is read: {}",
+                conflicts.join(", ")
+            ),
+        ));
+    }
+    checks
+}
+
+/// Leftover `<name>.json.bak.<uuid>` / `.json.tmp.<uuid>` siblings from interrupted
+/// atomic writes, newest first, that parse as JSON.
+fn parseable_leftovers(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    let mut found: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    let other = e.file_name().to_string_lossy().to_string();
+                    other.starts_with(&format!("{}.bak", name))
+                        || other.starts_with(&format!("{}.tmp", name))
+                })
+                .filter(|e| {
+                    std::fs::read_to_string(e.path())
+                        .ok()
+                        .is_some_and(|raw| serde_json::from_str::<serde_json::Value>(&raw).is_ok())
+                })
+                .map(|e| {
+                    let modified = e
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .unwrap_or(std::time::UNIX_EPOCH);
+                    (modified, e.path())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    found.sort_by(|a, b| b.0.cmp(&a.0));
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+pub fn check_json_store(id: &str, title: &str, path: &Path) -> DoctorCheck {
+    let raw = match crate::vault::at_rest::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return DoctorCheck::new(id, title, CheckStatus::Skipped, "Not created yet");
+        }
+        Err(_) if std::fs::read(path).is_ok_and(|b| crate::vault::at_rest::is_sealed(&b)) => {
+            return DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Skipped,
+                "Encrypted at rest; unlock the vault to check it",
+            );
+        }
+        Err(e) => {
+            return DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Error,
+                format!("Cannot read {}: {}", path.display(), e),
+            );
+        }
+    };
+    match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(_) => DoctorCheck::new(id, title, CheckStatus::Ok, path.display().to_string()),
+        Err(e) => {
+            let check = DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Error,
+                format!("{} is not valid JSON: {}", path.display(), e),
+            );
+            let path_text = path.to_string_lossy().to_string();
+            match parseable_leftovers(path).into_iter().next() {
+                Some(backup) => check.with_fix(DoctorFix::RestoreBackup {
+                    path: path_text,
+                    backup: backup.to_string_lossy().to_string(),
+                }),
+                None => check.with_fix(DoctorFix::Quarantine { path: path_text }),
+            }
+        }
+    }
+}
+
+/// Private keys referenced by saved connections: present, and not readable by others.
+pub fn check_key_file(connection: &str, path: &Path) -> DoctorCheck {
+    let id = format!("key:{}", path.display());
+    let title = format!("Private key for {}", connection);
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            return DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Error,
+                format!("{}: {}", path.display(), e),
+            );
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = meta.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            // OpenSSH refuses keys like this ("UNPROTECTED PRIVATE KEY FILE").
+            return DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Warning,
+                format!("{} has mode {:o}; it should be 600", path.display(), mode),
+            )
+            .with_fix(DoctorFix::RestrictKey {
+                path: path.to_string_lossy().to_string(),
+            });
+        }
+    }
+    let _ = meta;
+    DoctorCheck::new(id, title, CheckStatus::Ok, path.display().to_string())
+}
+
+/// The `~/.local/bin/zync` link created by the Linux install script, if any.
+pub fn check_cli_link(link: &Path, app: Option<&Path>) -> DoctorCheck {
+    let id = "cli-link";
+    let title = "Command-line launcher";
+    let Ok(meta) = std::fs::symlink_metadata(link) else {
+        return DoctorCheck::new(id, title, CheckStatus::Skipped, "Not installed");
+    };
+    if !meta.file_type().is_symlink() {
+        return DoctorCheck::new(id, title, CheckStatus::Ok, link.display().to_string());
+    }
+    let target = std::fs::read_link(link).unwrap_or_default();
+    let relink = |check: DoctorCheck| match app {
+        Some(app) => check.with_fix(DoctorFix::RelinkCli {
+            link: link.to_string_lossy().to_string(),
+            target: app.to_string_lossy().to_string(),
+        }),
+        None => check,
+    };
+    if !link.exists() {
+        return relink(DoctorCheck::new(
+            id,
+            title,
+            CheckStatus::Error,
+            format!("{} points to missing {}", link.display(), target.display()),
+        ));
+    }
+    match app {
+        Some(app) if std::fs::canonicalize(link).ok() != std::fs::canonicalize(app).ok() => {
+            relink(DoctorCheck::new(
+                id,
+                title,
+                CheckStatus::Warning,
+                format!(
+                    "{} points to {}, not the running app {}",
+                    link.display(),
+                    target.display(),
+                    app.display()
+                ),
+            ))
+        }
+        _ => DoctorCheck::new(
+            id,
+            title,
+            CheckStatus::Ok,
+            format!("{} -> {}", link.display(), target.display()),
+        ),
+    }
+}
+
+/// Applies the file-level fixes; `ResetDataPath` needs the settings store and is
+/// handled by the command.
+pub fn apply_fix(fix: &DoctorFix) -> Result<String> {
+    match fix {
+        DoctorFix::ResetDataPath => Err(anyhow!("ResetDataPath is applied through settings")),
+        DoctorFix::RestoreBackup { path, backup } => {
+            let content = std::fs::read(backup)?;
+            serde_json::from_slice::<serde_json::Value>(&content)
+                .map_err(|e| anyhow!("{} no longer parses: {}", backup, e))?;
+            let corrupt = quarantine_path(Path::new(path));
+            std::fs::rename(path, &corrupt)?;
+            crate::atomic_io::durable_replace(Path::new(path), &content)?;
+            Ok(format!(
+                "Restored {} from {}; the broken file is at {}",
+                path,
+                backup,
+                corrupt.display()
+            ))
+        }
+        DoctorFix::Quarantine { path } => {
+            let corrupt = quarantine_path(Path::new(path));
+            std::fs::rename(path, &corrupt)?;
+            Ok(format!("Moved {} to {}", path, corrupt.display()))
+        }
+        DoctorFix::RestrictKey { path } => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+            }
+            Ok(format!("Restricted {} to its owner", path))
+        }
+        DoctorFix::RelinkCli { link, target } => {
+            if !Path::new(target).exists() {
+                return Err(anyhow!("{} does not exist", target));
+            }
+            relink(Path::new(link), Path::new(target))?;
+            Ok(format!("{} now points to {}", link, target))
+        }
+    }
+}
+
+#[cfg(unix)]
+fn relink(link: &Path, target: &Path) -> Result<()> {
+    let _ = std::fs::remove_file(link);
+    std::os::unix::fs::symlink(target, link)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn relink(_link: &Path, _target: &Path) -> Result<()> {
+    Err(anyhow!("The CLI link is only managed on Linux"))
+}
+
+fn quarantine_path(path: &Path) -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}.corrupt-{}", name, stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("zync-doctor-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn recognises_conflict_copies() {
+        assert!(is_conflict_copy(
+            "connections (conflicted copy 2024-01-02).json"
+        ));
+        assert!(is_conflict_copy(
+            "connections.sync-conflict-20240102-101010-ABC.json"
+        ));
+        assert!(is_conflict_copy("connections 2.json"));
+        assert!(!is_conflict_copy("connections.json"));
+        assert!(!is_conflict_copy("My Data"));
+    }
+
+    #[test]
+    fn broken_store_prefers_parseable_leftover() {
+        let dir = scratch("store");
+        let store = dir.join("connections.json");
+        std::fs::write(&store, b"{\"connections\": [").unwrap();
+        let check = check_json_store("store", "Connections", &store);
+        assert_eq!(check.status, CheckStatus::Error);
+        assert!(matches!(check.fix, Some(DoctorFix::Quarantine { .. })));
+
+        let leftover = dir.join("connections.json.bak.1234");
+        std::fs::write(&leftover, b"{\"connections\": []}").unwrap();
+        let check = check_json_store("store", "Connections", &store);
+        let fix = check.fix.unwrap();
+        assert_eq!(
+            fix,
+            DoctorFix::RestoreBackup {
+                path: store.to_string_lossy().to_string(),
+                backup: leftover.to_string_lossy().to_string(),
+            }
+        );
+        apply_fix(&fix).unwrap();
+        assert_eq!(
+            check_json_store("store", "Connections", &store).status,
+            CheckStatus::Ok
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn loose_key_permissions_are_fixable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = scratch("key");
+        let key = dir.join("id_ed25519");
+        std::fs::write(&key, b"key").unwrap();
+        std::fs::set_permissions(&key, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let check = check_key_file("prod", &key);
+        assert_eq!(check.status, CheckStatus::Warning);
+        apply_fix(&check.fix.unwrap()).unwrap();
+        assert_eq!(check_key_file("prod", &key).status, CheckStatus::Ok);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
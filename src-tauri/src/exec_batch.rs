@@ -0,0 +1,156 @@
+//! One command on many connections: a lightweight fleet runner.
+//!
+//! `ssh_exec_batch` opens an exec channel on each selected connection (at most
+//! `concurrency` at a time, reconnecting stored sessions that dropped) and
+//! streams output as `exec-batch:output` while the commands run. Each host's
+//! result is emitted as `exec-batch:host` when it finishes and the command
+//! returns all of them keyed by connection id. Captured output is capped per
+//! stream; the live events are not.
+
+use serde::Serialize;
+use std::time::Duration;
+
+pub const DEFAULT_CONCURRENCY: usize = 8;
+const MAX_CONCURRENCY: usize = 32;
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_TIMEOUT: Duration = Duration::from_secs(3600);
+/// Bytes of stdout and of stderr kept per host in the result.
+pub const MAX_CAPTURED: usize = 1024 * 1024;
+
+pub fn concurrency_limit(concurrency: Option<usize>) -> usize {
+    concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY)
+}
+
+pub fn timeout(timeout_secs: Option<u64>) -> Duration {
+    timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TIMEOUT)
+        .clamp(Duration::from_secs(1), MAX_TIMEOUT)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Payload of `exec-batch:output`: a piece of one host's output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOutput {
+    pub batch_id: String,
+    pub connection_id: String,
+    pub stream: OutputStream,
+    pub data: String,
+}
+
+/// One host's outcome; payload of `exec-batch:host`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchHostResult {
+    pub batch_id: String,
+    pub connection_id: String,
+    /// Saved name of the connection, for attribution in the results list.
+    pub connection_name: String,
+    pub host: String,
+    /// `None` when the command never ran or was killed without a status.
+    pub exit_status: Option<u32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Output beyond [`MAX_CAPTURED`] was left out of `stdout`/`stderr`.
+    pub truncated: bool,
+    pub duration_ms: u64,
+    /// Connection, channel or timeout failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BatchHostResult {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none() && self.exit_status == Some(0)
+    }
+}
+
+/// Output of one stream: decoded for events as it arrives, captured up to
+/// [`MAX_CAPTURED`] for the result.
+#[derive(Debug, Default)]
+pub struct StreamBuffer {
+    /// Trailing bytes of an incomplete UTF-8 sequence.
+    pending: Vec<u8>,
+    captured: Vec<u8>,
+    pub truncated: bool,
+}
+
+impl StreamBuffer {
+    /// Adds a chunk; returns the text that can be emitted now. A multibyte
+    /// character split across chunks is held back until it is complete.
+    pub fn push(&mut self, data: &[u8]) -> String {
+        let room = MAX_CAPTURED - self.captured.len();
+        if data.len() > room {
+            self.truncated = true;
+        }
+        self.captured
+            .extend_from_slice(&data[..data.len().min(room)]);
+
+        self.pending.extend_from_slice(data);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // Invalid bytes are replaced, not held.
+            Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+
+    /// Text still held back at the end of the stream.
+    pub fn flush(&mut self) -> String {
+        String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned()
+    }
+
+    pub fn captured(&self) -> String {
+        String::from_utf8_lossy(&self.captured).into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_back_split_characters() {
+        let mut buffer = StreamBuffer::default();
+        let bytes = "héllo".as_bytes();
+        assert_eq!(buffer.push(&bytes[..2]), "h");
+        assert_eq!(buffer.push(&bytes[2..]), "éllo");
+        assert_eq!(buffer.push(b"\xff!"), "\u{FFFD}!");
+        assert_eq!(buffer.push(&[0xC3]), "");
+        assert_eq!(buffer.flush(), "\u{FFFD}");
+        assert_eq!(buffer.captured(), "héllo\u{FFFD}!\u{FFFD}");
+    }
+
+    #[test]
+    fn caps_captured_output() {
+        let mut buffer = StreamBuffer::default();
+        buffer.push(&vec![b'a'; MAX_CAPTURED - 1]);
+        assert!(!buffer.truncated);
+        assert_eq!(buffer.push(b"bc"), "bc");
+        assert!(buffer.truncated);
+        assert_eq!(buffer.captured().len(), MAX_CAPTURED);
+        assert!(buffer.captured().ends_with('b'));
+    }
+
+    #[test]
+    fn clamps_limits() {
+        assert_eq!(concurrency_limit(None), DEFAULT_CONCURRENCY);
+        assert_eq!(concurrency_limit(Some(0)), 1);
+        assert_eq!(concurrency_limit(Some(1000)), MAX_CONCURRENCY);
+        assert_eq!(timeout(Some(0)), Duration::from_secs(1));
+        assert_eq!(timeout(None), DEFAULT_TIMEOUT);
+    }
+}
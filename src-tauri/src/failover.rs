@@ -0,0 +1,183 @@
+//! Fallback endpoints for saved connections (`ConnectionConfig::fallbacks`).
+//!
+//! A host that is reached differently from the office, over a VPN or from the
+//! public internet keeps one connection with several routes: its own host, port
+//! and jump chain first, then each fallback in order. `SshManager::connect_with_fallbacks`
+//! dials them one at a time, reports each try as `ssh:connect-attempt`, and stops
+//! at the first route that connects. Errors no other route can fix, such as a
+//! rejected login or an unreadable key, end the sequence instead of being retried.
+
+use crate::types::ConnectionConfig;
+use serde::Serialize;
+
+pub const ATTEMPT_EVENT: &str = "ssh:connect-attempt";
+
+/// One route to try: the connection config rewritten for it, and how to name it.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub label: String,
+    /// `None` for the connection's own endpoint.
+    pub fallback_index: Option<usize>,
+    pub config: ConnectionConfig,
+}
+
+/// Payload for `ssh:connect-attempt`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectAttempt {
+    pub connection_id: String,
+    /// 1-based position of this route.
+    pub attempt: usize,
+    pub total: usize,
+    pub label: String,
+    /// `host:port` of the target, as dialled (through the jump host when `via` is set).
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub via: Option<String>,
+    /// `trying`, `failed` or `connected`.
+    pub stage: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ConnectAttempt {
+    pub fn new(endpoint: &Endpoint, attempt: usize, total: usize, stage: &str) -> Self {
+        Self {
+            connection_id: endpoint.config.id.clone(),
+            attempt,
+            total,
+            label: endpoint.label.clone(),
+            address: format!("{}:{}", endpoint.config.host, endpoint.config.port),
+            via: endpoint
+                .config
+                .jump_host
+                .as_ref()
+                .map(|jump| format!("{}:{}", jump.host, jump.port)),
+            stage: stage.to_string(),
+            error: None,
+        }
+    }
+}
+
+/// The primary endpoint followed by every fallback, in the order they are tried.
+/// Fallbacks inherit everything but the route from the connection itself.
+pub fn endpoints(config: &ConnectionConfig) -> Vec<Endpoint> {
+    let mut primary = config.clone();
+    primary.fallbacks = None;
+    let mut endpoints = vec![Endpoint {
+        label: "primary".to_string(),
+        fallback_index: None,
+        config: primary.clone(),
+    }];
+    for (index, fallback) in config.fallbacks.iter().flatten().enumerate() {
+        let mut route = primary.clone();
+        if let Some(host) = fallback
+            .host
+            .as_deref()
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+        {
+            route.host = host.to_string();
+            // A static address or DNS override belongs to the primary host name.
+            route.resolution = None;
+        }
+        route.port = fallback.port.unwrap_or(route.port);
+        route.jump_host = fallback.jump_host.clone();
+        let label = fallback
+            .label
+            .as_deref()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}:{}", route.host, route.port));
+        endpoints.push(Endpoint {
+            label,
+            fallback_index: Some(index),
+            config: route,
+        });
+    }
+    endpoints
+}
+
+/// Whether a failed attempt may succeed on another route. Credential and
+/// configuration errors fail the same way everywhere, and repeating a rejected
+/// login only brings the host closer to a lockout.
+pub fn should_try_next(error: &str) -> bool {
+    const FINAL: [&str; 6] = [
+        "Authentication failed",
+        "No SSH authentication configured",
+        "Failed to read private key",
+        "Failed to decode private key",
+        "was not resolved before authentication",
+        crate::kill_switch::OFFLINE_ERROR,
+    ];
+    !FINAL.iter().any(|marker| error.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AuthMethod, FallbackEndpoint};
+
+    fn config() -> ConnectionConfig {
+        serde_json::from_value(serde_json::json!({
+            "id": "c1",
+            "name": "db",
+            "host": "10.0.0.5",
+            "port": 22,
+            "username": "ops",
+            "auth_method": { "type": "Password", "password": "x" },
+            "jump_host": null,
+            "resolution": { "staticAddress": "10.0.0.5" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn fallbacks_follow_the_primary_in_order() {
+        let mut config = config();
+        let mut bastion = config.clone();
+        bastion.host = "bastion.example.com".to_string();
+        bastion.auth_method = AuthMethod::Password {
+            password: "y".to_string(),
+        };
+        config.fallbacks = Some(vec![
+            FallbackEndpoint {
+                label: Some("VPN".to_string()),
+                host: Some("100.64.0.5".to_string()),
+                ..Default::default()
+            },
+            FallbackEndpoint {
+                port: Some(2222),
+                jump_host: Some(Box::new(bastion)),
+                ..Default::default()
+            },
+        ]);
+
+        let endpoints = endpoints(&config);
+        let labels: Vec<_> = endpoints.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["primary", "VPN", "10.0.0.5:2222"]);
+        assert!(endpoints.iter().all(|e| e.config.fallbacks.is_none()));
+
+        let vpn = &endpoints[1].config;
+        assert_eq!((vpn.host.as_str(), vpn.port), ("100.64.0.5", 22));
+        assert!(vpn.resolution.is_none());
+
+        let public = ConnectAttempt::new(&endpoints[2], 3, 3, "trying");
+        assert_eq!(public.address, "10.0.0.5:2222");
+        assert_eq!(public.via.as_deref(), Some("bastion.example.com:22"));
+        assert!(endpoints[2].config.resolution.is_some());
+    }
+
+    #[test]
+    fn credential_errors_end_the_sequence() {
+        assert!(should_try_next(
+            "Failed to connect to 10.0.0.5:22: Connection timed out"
+        ));
+        assert!(should_try_next("Failed to connect to jump host: refused"));
+        assert!(!should_try_next("Authentication failed"));
+        assert!(!should_try_next(
+            "Failed to read private key file: No such file or directory"
+        ));
+    }
+}
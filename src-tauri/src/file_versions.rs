@@ -0,0 +1,206 @@
+//! Snapshots of files taken before the editor overwrites them.
+//!
+//! Saving with `snapshot: true` first copies the file's current content into
+//! `<data>/file_versions/<key>/`, where `<key>` hashes the connection id and
+//! path. Snapshots stay on this machine, out of reach of whatever the edit
+//! broke on the server, and `fs_version_restore` writes one back. The
+//! directory holds `versions.json` (newest first) and one `<id>.bin` per
+//! version. Unchanged content is not stored twice in a row, and only the
+//! newest [`MAX_VERSIONS`] are kept per file.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MAX_VERSIONS: usize = 20;
+/// Larger files are not snapshotted; config files are far below this.
+pub const MAX_SNAPSHOT_BYTES: usize = 16 * 1024 * 1024;
+const STORE_DIR: &str = "file_versions";
+const INDEX_FILE: &str = "versions.json";
+
+/// Serializes index updates; snapshots are rare, so one lock for all files.
+static STORE_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    pub id: String,
+    /// Unix milliseconds.
+    pub created_at: u64,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionIndex {
+    connection_id: String,
+    path: String,
+    versions: Vec<FileVersion>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub struct VersionStore {
+    root: PathBuf,
+}
+
+impl VersionStore {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            root: data_dir.join(STORE_DIR),
+        }
+    }
+
+    fn dir(&self, connection_id: &str, path: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(connection_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(path.as_bytes());
+        self.root.join(hex(&hasher.finalize()[..12]))
+    }
+
+    fn load(dir: &Path) -> VersionIndex {
+        std::fs::read(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Versions of `path` on `connection_id`, newest first.
+    pub fn list(&self, connection_id: &str, path: &str) -> Vec<FileVersion> {
+        Self::load(&self.dir(connection_id, path)).versions
+    }
+
+    /// Stores `content` as the newest version of `path`. Returns `None` when
+    /// it equals the newest stored version.
+    pub fn snapshot(
+        &self,
+        connection_id: &str,
+        path: &str,
+        content: &[u8],
+    ) -> Result<Option<FileVersion>> {
+        if content.len() > MAX_SNAPSHOT_BYTES {
+            bail!(
+                "{} is too large to snapshot ({} bytes, max {})",
+                path,
+                content.len(),
+                MAX_SNAPSHOT_BYTES
+            );
+        }
+        let _guard = STORE_LOCK.lock().map_err(|e| anyhow!(e.to_string()))?;
+        let dir = self.dir(connection_id, path);
+        let mut index = Self::load(&dir);
+        let sha256 = hex(&Sha256::digest(content));
+        if index.versions.first().is_some_and(|v| v.sha256 == sha256) {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(&dir)?;
+        let created_at = now_ms();
+        let version = FileVersion {
+            id: format!("{}-{}", created_at, &sha256[..8]),
+            created_at,
+            size: content.len() as u64,
+            sha256,
+        };
+        crate::atomic_io::durable_replace(&dir.join(format!("{}.bin", version.id)), content)?;
+
+        index.connection_id = connection_id.to_string();
+        index.path = path.to_string();
+        index.versions.insert(0, version.clone());
+        for dropped in index
+            .versions
+            .split_off(index.versions.len().min(MAX_VERSIONS))
+        {
+            let _ = std::fs::remove_file(dir.join(format!("{}.bin", dropped.id)));
+        }
+        crate::atomic_io::durable_replace(
+            &dir.join(INDEX_FILE),
+            &serde_json::to_vec_pretty(&index)?,
+        )?;
+        Ok(Some(version))
+    }
+
+    /// Content of one stored version.
+    pub fn read(&self, connection_id: &str, path: &str, id: &str) -> Result<Vec<u8>> {
+        let dir = self.dir(connection_id, path);
+        let version = Self::load(&dir)
+            .versions
+            .into_iter()
+            .find(|v| v.id == id)
+            .ok_or_else(|| anyhow!("No version {} of {}", id, path))?;
+        let content = std::fs::read(dir.join(format!("{}.bin", version.id)))?;
+        if hex(&Sha256::digest(&content)) != version.sha256 {
+            bail!("Version {} of {} is corrupted", id, path);
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> (VersionStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("zync-versions-{}", uuid::Uuid::new_v4()));
+        (VersionStore::new(&dir), dir)
+    }
+
+    #[test]
+    fn keeps_distinct_versions_newest_first() {
+        let (store, dir) = store();
+        let first = store
+            .snapshot("c1", "/etc/nginx/nginx.conf", b"v1")
+            .unwrap()
+            .unwrap();
+        assert!(store
+            .snapshot("c1", "/etc/nginx/nginx.conf", b"v1")
+            .unwrap()
+            .is_none());
+        let second = store
+            .snapshot("c1", "/etc/nginx/nginx.conf", b"v2")
+            .unwrap()
+            .unwrap();
+
+        let versions = store.list("c1", "/etc/nginx/nginx.conf");
+        assert_eq!(versions, [second.clone(), first.clone()]);
+        assert_eq!(
+            store
+                .read("c1", "/etc/nginx/nginx.conf", &first.id)
+                .unwrap(),
+            b"v1"
+        );
+        // Same path on another connection is a separate history.
+        assert!(store.list("c2", "/etc/nginx/nginx.conf").is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn prunes_beyond_the_limit() {
+        let (store, dir) = store();
+        let first = store.snapshot("c1", "/etc/hosts", b"0").unwrap().unwrap();
+        for i in 1..=MAX_VERSIONS {
+            store
+                .snapshot("c1", "/etc/hosts", i.to_string().as_bytes())
+                .unwrap();
+        }
+        let versions = store.list("c1", "/etc/hosts");
+        assert_eq!(versions.len(), MAX_VERSIONS);
+        assert!(versions.iter().all(|v| v.id != first.id));
+        assert!(store.read("c1", "/etc/hosts", &first.id).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -14,10 +14,347 @@ pub struct FileEntry {
     pub size: u64,
     pub last_modified: u64,
     pub permissions: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Where a symlink points (only set when `type` is `"l"`).
+    #[serde(default)]
+    pub link_target: Option<String>,
+    /// Type of the symlink's resolved target (`"d"` or `"-"`), `None` when dangling.
+    #[serde(default)]
+    pub link_target_type: Option<String>,
 }
 
 pub struct FileSystem;
 
+/// What recursive operations do with FIFOs, sockets and device nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecialFilePolicy {
+    /// Leave them out and list them in the operation result.
+    #[default]
+    Skip,
+    /// Fail the operation on the first one.
+    Error,
+}
+
+/// Flags for recursive transfers and copies.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WalkFlags {
+    /// Leave out dot-entries found while descending (explicitly selected paths are kept).
+    pub skip_hidden: bool,
+    pub special_files: SpecialFilePolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    Hidden,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+    /// Another entry in the same directory has a name the destination
+    /// considers equal (case or Unicode normalization).
+    NameCollision,
+}
+
+impl SkipReason {
+    /// Special-file kind from the `S_IFMT` bits of an SFTP/stat mode.
+    pub fn from_mode(mode: u32) -> Option<Self> {
+        match mode & 0o170000 {
+            0o010000 => Some(Self::Fifo),
+            0o020000 => Some(Self::CharDevice),
+            0o060000 => Some(Self::BlockDevice),
+            0o140000 => Some(Self::Socket),
+            _ => None,
+        }
+    }
+
+    pub fn from_local(metadata: &fs::Metadata) -> Option<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            let file_type = metadata.file_type();
+            if file_type.is_fifo() {
+                return Some(Self::Fifo);
+            }
+            if file_type.is_socket() {
+                return Some(Self::Socket);
+            }
+            if file_type.is_char_device() {
+                return Some(Self::CharDevice);
+            }
+            if file_type.is_block_device() {
+                return Some(Self::BlockDevice);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = metadata;
+        None
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Hidden => "hidden entry",
+            Self::Fifo => "named pipe",
+            Self::Socket => "socket",
+            Self::CharDevice => "character device",
+            Self::BlockDevice => "block device",
+            Self::NameCollision => "entry whose name collides with a sibling",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedEntry {
+    pub path: String,
+    pub reason: SkipReason,
+    /// The sibling that was copied instead, for `nameCollision`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicts_with: Option<String>,
+}
+
+/// Flags plus the entries left out so far; threaded through one recursive operation.
+#[derive(Debug, Default)]
+pub struct Walk {
+    pub flags: WalkFlags,
+    pub skipped: Vec<SkippedEntry>,
+    /// How the destination compares names; see [`crate::path_names`].
+    pub name_rules: crate::path_names::NameRules,
+}
+
+impl Walk {
+    pub fn new(flags: WalkFlags) -> Self {
+        Self {
+            flags,
+            skipped: Vec::new(),
+            name_rules: Default::default(),
+        }
+    }
+
+    /// `(source name, destination name)` for the children of one directory,
+    /// given the names already in the destination directory. Children whose
+    /// names collide at the destination are recorded and left out.
+    pub fn plan_children(
+        &mut self,
+        names: &[String],
+        existing: &[String],
+        path_of: impl Fn(&str) -> String,
+    ) -> Vec<(String, String)> {
+        let plan = crate::path_names::plan_children(names, existing, self.name_rules);
+        for (name, kept) in plan.conflicts {
+            self.skipped.push(SkippedEntry {
+                path: path_of(&name),
+                reason: SkipReason::NameCollision,
+                conflicts_with: Some(path_of(&kept)),
+            });
+        }
+        plan.transfer
+    }
+
+    /// Hidden-entry filter for children found while descending.
+    pub fn skip_child(&mut self, path: &str, name: &str) -> bool {
+        if self.flags.skip_hidden && name.starts_with('.') {
+            self.skipped.push(SkippedEntry {
+                path: path.to_string(),
+                reason: SkipReason::Hidden,
+                conflicts_with: None,
+            });
+            return true;
+        }
+        false
+    }
+
+    /// `Ok(true)` when `path` is a special file that was skipped and recorded.
+    pub fn skip_special(&mut self, path: &str, kind: Option<SkipReason>) -> Result<bool, String> {
+        let Some(kind) = kind else {
+            return Ok(false);
+        };
+        match self.flags.special_files {
+            SpecialFilePolicy::Skip => {
+                self.skipped.push(SkippedEntry {
+                    path: path.to_string(),
+                    reason: kind,
+                    conflicts_with: None,
+                });
+                Ok(true)
+            }
+            SpecialFilePolicy::Error => {
+                Err(format!("Refusing to copy {} '{}'", kind.describe(), path))
+            }
+        }
+    }
+}
+
+const TAIL_BLOCK_SIZE: u64 = 64 * 1024;
+/// Hard stop for tail reads so a file without newlines can't be pulled in whole.
+const TAIL_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// True once `buf` holds at least `lines` line breaks (ignoring a trailing
+/// newline): the first line may be partial, but the last `lines` are whole.
+fn tail_is_complete(buf: &[u8], lines: usize) -> bool {
+    if buf.len() >= TAIL_MAX_BYTES {
+        return true;
+    }
+    let body = buf.strip_suffix(b"\n").unwrap_or(buf);
+    body.iter().filter(|&&b| b == b'\n').count() >= lines
+}
+
+fn last_lines(buf: &[u8], lines: usize) -> String {
+    let text = String::from_utf8_lossy(buf);
+    let body = text.strip_suffix('\n').unwrap_or(&text);
+    let all: Vec<&str> = body
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let start = all.len().saturating_sub(lines);
+    all[start..].join("\n")
+}
+
+fn scp_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Parses an SCP `C<mode> <size> <name>` control line.
+fn parse_scp_header(line: &str) -> Result<(u32, u64, String)> {
+    let rest = line
+        .strip_prefix('C')
+        .ok_or_else(|| anyhow!("Unexpected SCP control line: {}", line))?;
+    let mut parts = rest.splitn(3, ' ');
+    let mode = parts
+        .next()
+        .and_then(|m| u32::from_str_radix(m, 8).ok())
+        .ok_or_else(|| anyhow!("Bad mode in SCP header: {}", line))?;
+    let size = parts
+        .next()
+        .and_then(|s| s.parse::<u64>().ok())
+        .ok_or_else(|| anyhow!("Bad size in SCP header: {}", line))?;
+    let name = parts.next().unwrap_or_default().to_string();
+    Ok((mode, size, name))
+}
+
+/// Splits off the first `n` whitespace-separated fields and returns the rest of
+/// the line untouched, so file names keep their inner spacing.
+fn split_fields(line: &str, n: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(n);
+    let mut rest = line.trim_start();
+    while fields.len() < n {
+        let end = rest.find(char::is_whitespace)?;
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    Some((fields, rest))
+}
+
+fn ls_mode_bits(perms: &str) -> String {
+    let bits: u32 = perms
+        .chars()
+        .skip(1)
+        .take(9)
+        .map(|c| !matches!(c, '-' | 'S' | 'T') as u32)
+        .fold(0, |acc, bit| (acc << 1) | bit);
+    format!("{:o}", bits)
+}
+
+/// Parses one line of `ls -la` (optionally with `--time-style=+%s`) into an entry.
+fn parse_ls_line(line: &str, dir: &str) -> Option<FileEntry> {
+    let kind = line.chars().next()?;
+    if !"-dlcbps".contains(kind) {
+        return None; // "total 12" and friends
+    }
+    // Device files print "major, minor" where the size normally goes.
+    let (head, _) = split_fields(line, 5)?;
+    let device = head[4].ends_with(',');
+    let size_fields = if device { 6 } else { 5 };
+    let (fields, rest) = split_fields(line, size_fields)?;
+    let size = if device {
+        0
+    } else {
+        fields[4].parse().unwrap_or(0)
+    };
+
+    // `--time-style=+%s` leaves a single epoch column; plain `ls` prints three.
+    let (last_modified, name_part) = match split_fields(rest, 1) {
+        Some((epoch, name)) if epoch[0].chars().all(|c| c.is_ascii_digit()) && !name.is_empty() => {
+            (epoch[0].parse::<u64>().unwrap_or(0) * 1000, name)
+        }
+        _ => (0, split_fields(rest, 3)?.1),
+    };
+
+    let (name, link_target) = match (kind, name_part.split_once(" -> ")) {
+        ('l', Some((name, target))) => (name.to_string(), Some(target.to_string())),
+        _ => (name_part.to_string(), None),
+    };
+    if name.is_empty() || name == "." || name == ".." {
+        return None;
+    }
+
+    let path = if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    };
+    Some(FileEntry {
+        name,
+        path,
+        r#type: match kind {
+            'd' => "d",
+            'l' => "l",
+            _ => "-",
+        }
+        .to_string(),
+        size,
+        last_modified,
+        permissions: ls_mode_bits(fields[0]),
+        owner: Some(fields[2].to_string()),
+        group: Some(fields[3].to_string()),
+        link_target,
+        link_target_type: None,
+    })
+}
+
+/// Entries of an `ls -la` listing of `dir`, directories first.
+pub(crate) fn parse_ls_listing(output: &str, dir: &str) -> Vec<FileEntry> {
+    let mut result: Vec<FileEntry> = output
+        .lines()
+        .filter_map(|line| parse_ls_line(line, dir))
+        .collect();
+    result.sort_by(|a, b| {
+        let a_dir = a.r#type == "d" || a.r#type == "l";
+        let b_dir = b.r#type == "d" || b.r#type == "l";
+        b_dir.cmp(&a_dir).then_with(|| a.name.cmp(&b.name))
+    });
+    result
+}
+
+async fn scp_read_ack<S: tokio::io::AsyncRead + Unpin>(stream: &mut S) -> Result<()> {
+    use tokio::io::AsyncReadExt;
+    match stream.read_u8().await? {
+        0 => Ok(()),
+        _ => {
+            let message = scp_read_line(stream).await.unwrap_or_default();
+            Err(anyhow!("SCP error: {}", message.trim()))
+        }
+    }
+}
+
+async fn scp_read_line<S: tokio::io::AsyncRead + Unpin>(stream: &mut S) -> Result<String> {
+    use tokio::io::AsyncReadExt;
+    let mut line = Vec::new();
+    loop {
+        let byte = stream.read_u8().await?;
+        if byte == b'\n' {
+            break;
+        }
+        line.push(byte);
+    }
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
 impl FileSystem {
     pub fn new() -> Self {
         Self
@@ -73,6 +410,27 @@ impl FileSystem {
                 "666".to_string()
             };
 
+            #[cfg(unix)]
+            let (owner, group) = (
+                Some(metadata.uid().to_string()),
+                Some(metadata.gid().to_string()),
+            );
+
+            #[cfg(windows)]
+            let (owner, group) = (None, None);
+
+            let (link_target, link_target_type) = if file_type == "l" {
+                let target = fs::read_link(entry.path())
+                    .ok()
+                    .map(|t| t.to_string_lossy().to_string());
+                let target_type = fs::metadata(entry.path())
+                    .ok()
+                    .map(|m| if m.is_dir() { "d" } else { "-" }.to_string());
+                (target, target_type)
+            } else {
+                (None, None)
+            };
+
             entries.push(FileEntry {
                 name: file_name,
                 path: entry.path().to_string_lossy().to_string(),
@@ -80,6 +438,10 @@ impl FileSystem {
                 size,
                 last_modified,
                 permissions,
+                owner,
+                group,
+                link_target,
+                link_target_type,
             });
         }
 
@@ -136,13 +498,18 @@ impl FileSystem {
                 "-"
             };
 
-            // Construct path manually
-            let full_path = if path == "/" {
-                format!("/{}", name)
-            } else if path.ends_with('/') {
-                format!("{}{}", path, name)
+            let full_path = crate::remote_path::join(path, &name);
+
+            let (link_target, link_target_type) = if type_str == "l" {
+                let target = sftp.read_link(&full_path).await.ok();
+                let target_type = sftp
+                    .metadata(&full_path)
+                    .await
+                    .ok()
+                    .map(|m| if m.is_dir() { "d" } else { "-" }.to_string());
+                (target, target_type)
             } else {
-                format!("{}/{}", path, name)
+                (None, None)
             };
 
             result.push(FileEntry {
@@ -152,6 +519,10 @@ impl FileSystem {
                 size,
                 last_modified: mtime,
                 permissions: format!("{:o}", perms & 0o777),
+                owner: attrs.user.clone().or_else(|| attrs.uid.map(|uid| uid.to_string())),
+                group: attrs.group.clone().or_else(|| attrs.gid.map(|gid| gid.to_string())),
+                link_target,
+                link_target_type,
             });
         }
 
@@ -179,12 +550,16 @@ impl FileSystem {
         }
     }
 
-    pub async fn read_file(&self, _connection_id: &str, path: &str) -> Result<String> {
-        let content = fs::read(path).map_err(|e| anyhow!("Failed to read file: {}", e))?;
+    pub async fn read_file(&self, connection_id: &str, path: &str) -> Result<String> {
+        let content = self.read_file_bytes(connection_id, path).await?;
         Ok(String::from_utf8_lossy(&content).to_string())
     }
 
-    pub async fn write_file(&self, connection_id: &str, path: &str, content: &str) -> Result<()> {
+    pub async fn read_file_bytes(&self, _connection_id: &str, path: &str) -> Result<Vec<u8>> {
+        fs::read(path).map_err(|e| anyhow!("Failed to read file: {}", e))
+    }
+
+    pub async fn write_file(&self, connection_id: &str, path: &str, content: &[u8]) -> Result<()> {
         if connection_id == "local" {
             fs::write(path, content).map_err(|e| anyhow!("Failed to write file: {}", e))
         } else {
@@ -234,11 +609,20 @@ impl FileSystem {
         }
     }
 
-    pub async fn copy(&self, connection_id: &str, from: &str, to: &str) -> Result<()> {
+    pub async fn copy(
+        &self,
+        connection_id: &str,
+        from: &str,
+        to: &str,
+        preserve_symlinks: bool,
+    ) -> Result<()> {
         if connection_id == "local" {
+            if preserve_symlinks && Self::is_local_symlink(from) {
+                return Self::recreate_symlink_local(from, to);
+            }
             let metadata = fs::metadata(from).map_err(|e| anyhow!("Source not found: {}", e))?;
             if metadata.is_dir() {
-                Self::copy_dir_recursive(from, to)
+                Self::copy_dir_recursive(from, to, preserve_symlinks)
             } else {
                 fs::copy(from, to).map_err(|e| anyhow!("Failed to copy file: {}", e))?;
                 Ok(())
@@ -262,12 +646,10 @@ impl FileSystem {
         &self,
         sftp: &russh_sftp::client::SftpSession,
         path: &str,
-    ) -> Result<String> {
-        let content = sftp
-            .read(path)
+    ) -> Result<Vec<u8>> {
+        sftp.read(path)
             .await
-            .map_err(|e| anyhow!("Failed to read remote file: {}", e))?;
-        Ok(String::from_utf8_lossy(&content).to_string())
+            .map_err(|e| anyhow!("Failed to read remote file: {}", e))
     }
 
     pub async fn write_remote(
@@ -329,6 +711,48 @@ impl FileSystem {
             .map_err(|e| anyhow!("Failed to rename remote file: {}", e))
     }
 
+    /// Rename for servers whose plain `SSH_FXP_RENAME` refuses to overwrite: an
+    /// existing target is moved aside first and put back if the rename fails.
+    pub async fn rename_remote_replacing(
+        &self,
+        sftp: &russh_sftp::client::SftpSession,
+        old_path: &str,
+        new_path: &str,
+    ) -> Result<()> {
+        if !self.exists_remote(sftp, new_path).await? {
+            return self.rename_remote(sftp, old_path, new_path).await;
+        }
+        let backup = format!(
+            "{}.zync-replace-{}",
+            new_path,
+            uuid::Uuid::new_v4().simple()
+        );
+        sftp.rename(new_path, &backup)
+            .await
+            .map_err(|e| anyhow!("Failed to move '{}' aside: {}", new_path, e))?;
+        if let Err(e) = sftp.rename(old_path, new_path).await {
+            if let Err(restore) = sftp.rename(&backup, new_path).await {
+                return Err(anyhow!(
+                    "Failed to rename remote file: {}; restoring '{}' from '{}' also failed: {}",
+                    e,
+                    new_path,
+                    backup,
+                    restore
+                ));
+            }
+            return Err(anyhow!("Failed to rename remote file: {}", e));
+        }
+        if let Err(e) = self.delete_remote(sftp, &backup).await {
+            crate::log_error!(
+                "[FS] Replaced '{}' but could not remove '{}': {}",
+                new_path,
+                backup,
+                e
+            );
+        }
+        Ok(())
+    }
+
     pub async fn delete_remote(
         &self,
         sftp: &russh_sftp::client::SftpSession,
@@ -364,11 +788,7 @@ impl FileSystem {
                     continue;
                 }
 
-                let full_path = if path.ends_with('/') {
-                    format!("{}{}", path, name)
-                } else {
-                    format!("{}/{}", path, name)
-                };
+                let full_path = crate::remote_path::join(path, &name);
 
                 // Check if directory
                 let is_dir = entry.file_type().is_dir();
@@ -394,14 +814,24 @@ impl FileSystem {
         sftp: &russh_sftp::client::SftpSession,
         from: &str,
         to: &str,
+        preserve_symlinks: bool,
     ) -> Result<()> {
+        if preserve_symlinks && self.is_remote_symlink(sftp, from).await {
+            let target = sftp
+                .read_link(from)
+                .await
+                .map_err(|e| anyhow!("Failed to read link '{}': {}", from, e))?;
+            return self.create_symlink_remote(sftp, &target, to).await;
+        }
+
         let metadata = sftp
             .metadata(from)
             .await
             .map_err(|e| anyhow!("Failed to stat source '{}': {}", from, e))?;
 
         if metadata.is_dir() {
-            self.copy_dir_recursive_remote(sftp, from, to).await
+            self.copy_dir_recursive_remote(sftp, from, to, preserve_symlinks)
+                .await
         } else {
             self.copy_file_remote(sftp, from, to).await
         }
@@ -417,7 +847,7 @@ impl FileSystem {
         use russh_sftp::protocol::OpenFlags;
         use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-        println!("[FS] Copying file from '{}' to '{}'", from, to);
+        crate::log_info!("[FS] Copying file from '{}' to '{}'", from, to);
 
         // Read
         let mut source = sftp
@@ -459,7 +889,7 @@ impl FileSystem {
             .await
             .map_err(|e| anyhow!("Flush error: {}", e))?;
 
-        println!("[FS] Copied {} bytes", total_bytes);
+        crate::log_info!("[FS] Copied {} bytes", total_bytes);
         Ok(())
     }
 
@@ -469,9 +899,10 @@ impl FileSystem {
         sftp: &'a russh_sftp::client::SftpSession,
         from: &'a str,
         to: &'a str,
+        preserve_symlinks: bool,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
         Box::pin(async move {
-            println!("[FS] Copying directory from '{}' to '{}'", from, to);
+            crate::log_info!("[FS] Copying directory from '{}' to '{}'", from, to);
 
             // Create dest dir
             // Ignore error if it already exists (could be merging)
@@ -505,8 +936,15 @@ impl FileSystem {
                 // Recursive call
                 let is_dir = entry.file_type().is_dir();
 
-                if is_dir && !entry.file_type().is_symlink() {
-                    self.copy_dir_recursive_remote(sftp, &source_path, &dest_path)
+                if entry.file_type().is_symlink() && preserve_symlinks {
+                    let target = sftp
+                        .read_link(&source_path)
+                        .await
+                        .map_err(|e| anyhow!("Failed to read link '{}': {}", source_path, e))?;
+                    self.create_symlink_remote(sftp, &target, &dest_path)
+                        .await?;
+                } else if is_dir && !entry.file_type().is_symlink() {
+                    self.copy_dir_recursive_remote(sftp, &source_path, &dest_path, preserve_symlinks)
                         .await?;
                 } else {
                     // If it is a symlink, treated as file (might fail read if dangling, or copy content if valid)
@@ -568,7 +1006,448 @@ impl FileSystem {
         Err(anyhow!("Too many duplicate files (limit 100)"))
     }
 
-    fn copy_dir_recursive(from: &str, to: &str) -> Result<()> {
+    // --- Append & Tail ---
+
+    pub fn append_local(&self, path: &str, content: &[u8]) -> Result<()> {
+        use std::io::Write;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("Failed to open '{}' for append: {}", path, e))?;
+        file.write_all(content)
+            .map_err(|e| anyhow!("Failed to append to '{}': {}", path, e))
+    }
+
+    pub async fn append_remote(
+        &self,
+        sftp: &russh_sftp::client::SftpSession,
+        path: &str,
+        content: &[u8],
+    ) -> Result<()> {
+        use russh_sftp::protocol::OpenFlags;
+        use tokio::io::AsyncWriteExt;
+
+        let mut file = sftp
+            .open_with_flags(
+                path,
+                OpenFlags::WRITE | OpenFlags::APPEND | OpenFlags::CREATE,
+            )
+            .await
+            .map_err(|e| anyhow!("Failed to open '{}' for append: {}", path, e))?;
+        file.write_all(content)
+            .await
+            .map_err(|e| anyhow!("Failed to append to '{}': {}", path, e))?;
+        file.shutdown()
+            .await
+            .map_err(|e| anyhow!("Failed to close '{}': {}", path, e))?;
+        Ok(())
+    }
+
+    /// Returns the last `lines` lines of a local file, reading backwards in blocks.
+    pub fn read_tail_local(&self, path: &str, lines: usize) -> Result<String> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let mut file =
+            fs::File::open(path).map_err(|e| anyhow!("Failed to open '{}': {}", path, e))?;
+        let size = file.metadata()?.len();
+        let mut buf: Vec<u8> = Vec::new();
+        let mut end = size;
+        while end > 0 && !tail_is_complete(&buf, lines) {
+            let start = end.saturating_sub(TAIL_BLOCK_SIZE);
+            let mut block = vec![0u8; (end - start) as usize];
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut block)?;
+            block.extend_from_slice(&buf);
+            buf = block;
+            end = start;
+        }
+        Ok(last_lines(&buf, lines))
+    }
+
+    /// Remote counterpart of [`Self::read_tail_local`]; only the blocks needed
+    /// to cover `lines` are transferred.
+    pub async fn read_tail_remote(
+        &self,
+        sftp: &russh_sftp::client::SftpSession,
+        path: &str,
+        lines: usize,
+    ) -> Result<String> {
+        use russh_sftp::protocol::OpenFlags;
+        use std::io::SeekFrom;
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        let size = sftp
+            .metadata(path)
+            .await
+            .map_err(|e| anyhow!("Failed to stat '{}': {}", path, e))?
+            .len();
+        let mut file = sftp
+            .open_with_flags(path, OpenFlags::READ)
+            .await
+            .map_err(|e| anyhow!("Failed to open '{}': {}", path, e))?;
+
+        let mut buf: Vec<u8> = Vec::new();
+        let mut end = size;
+        while end > 0 && !tail_is_complete(&buf, lines) {
+            let start = end.saturating_sub(TAIL_BLOCK_SIZE);
+            let mut block = vec![0u8; (end - start) as usize];
+            file.seek(SeekFrom::Start(start)).await?;
+            file.read_exact(&mut block)
+                .await
+                .map_err(|e| anyhow!("Failed to read '{}': {}", path, e))?;
+            block.extend_from_slice(&buf);
+            buf = block;
+            end = start;
+        }
+        Ok(last_lines(&buf, lines))
+    }
+
+    // --- Permissions & Ownership ---
+
+    /// Parses an octal mode string such as `755` or `0644` into permission bits.
+    pub fn parse_mode(mode: &str) -> Result<u32> {
+        let trimmed = mode.trim();
+        let digits = trimmed.strip_prefix("0o").unwrap_or(trimmed);
+        let bits = u32::from_str_radix(digits, 8)
+            .map_err(|_| anyhow!("Invalid mode '{}': expected octal digits like 755", mode))?;
+        if bits > 0o7777 {
+            return Err(anyhow!("Invalid mode '{}': out of range", mode));
+        }
+        Ok(bits)
+    }
+
+    pub fn chmod_local(&self, path: &str, mode: u32, recursive: bool) -> Result<()> {
+        let metadata =
+            fs::symlink_metadata(path).map_err(|e| anyhow!("Failed to read metadata: {}", e))?;
+        // Never follow symlinks: chmod on a link would change its target instead.
+        if metadata.file_type().is_symlink() {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(mode))
+                .map_err(|e| anyhow!("Failed to chmod '{}': {}", path, e))?;
+        }
+
+        #[cfg(windows)]
+        {
+            // Windows only understands the read-only flag; map it from the owner write bit.
+            let mut perms = metadata.permissions();
+            perms.set_readonly(mode & 0o200 == 0);
+            fs::set_permissions(path, perms)
+                .map_err(|e| anyhow!("Failed to chmod '{}': {}", path, e))?;
+        }
+
+        if recursive && metadata.is_dir() {
+            for entry in fs::read_dir(path).map_err(|e| anyhow!("Failed to read directory: {}", e))? {
+                let entry = entry.map_err(|e| anyhow!("Failed to read entry: {}", e))?;
+                self.chmod_local(&entry.path().to_string_lossy(), mode, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub fn chown_local(
+        &self,
+        path: &str,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        recursive: bool,
+    ) -> Result<()> {
+        let metadata =
+            fs::symlink_metadata(path).map_err(|e| anyhow!("Failed to read metadata: {}", e))?;
+        std::os::unix::fs::lchown(path, uid, gid)
+            .map_err(|e| anyhow!("Failed to chown '{}': {}", path, e))?;
+
+        if recursive && metadata.is_dir() {
+            for entry in fs::read_dir(path).map_err(|e| anyhow!("Failed to read directory: {}", e))? {
+                let entry = entry.map_err(|e| anyhow!("Failed to read entry: {}", e))?;
+                self.chown_local(&entry.path().to_string_lossy(), uid, gid, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    pub fn chown_local(
+        &self,
+        _path: &str,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        _recursive: bool,
+    ) -> Result<()> {
+        Err(anyhow!("Changing ownership is not supported on Windows"))
+    }
+
+    pub fn chmod_remote<'a>(
+        &'a self,
+        sftp: &'a russh_sftp::client::SftpSession,
+        path: &'a str,
+        mode: u32,
+        recursive: bool,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut attrs = sftp
+                .symlink_metadata(path)
+                .await
+                .map_err(|e| anyhow!("Failed to stat '{}': {}", path, e))?;
+            let file_type = attrs.permissions.unwrap_or(0) & 0o170000;
+            if file_type == 0o120000 {
+                return Ok(());
+            }
+
+            // Only send the permission bits; everything else stays untouched on the server.
+            attrs.size = None;
+            attrs.uid = None;
+            attrs.user = None;
+            attrs.gid = None;
+            attrs.group = None;
+            attrs.atime = None;
+            attrs.mtime = None;
+            attrs.permissions = Some(mode);
+            sftp.set_metadata(path, attrs)
+                .await
+                .map_err(|e| anyhow!("Failed to chmod '{}': {}", path, e))?;
+
+            if recursive && file_type == 0o040000 {
+                for child in self.remote_children(sftp, path).await? {
+                    self.chmod_remote(sftp, &child, mode, true).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    pub fn chown_remote<'a>(
+        &'a self,
+        sftp: &'a russh_sftp::client::SftpSession,
+        path: &'a str,
+        uid: Option<u32>,
+        gid: Option<u32>,
+        recursive: bool,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut attrs = sftp
+                .symlink_metadata(path)
+                .await
+                .map_err(|e| anyhow!("Failed to stat '{}': {}", path, e))?;
+            let file_type = attrs.permissions.unwrap_or(0) & 0o170000;
+            if file_type == 0o120000 {
+                return Ok(());
+            }
+
+            // SFTP v3 sets uid and gid together, so keep the current value for
+            // whichever side was not requested.
+            attrs.size = None;
+            attrs.user = None;
+            attrs.group = None;
+            attrs.permissions = None;
+            attrs.atime = None;
+            attrs.mtime = None;
+            if uid.is_some() {
+                attrs.uid = uid;
+            }
+            if gid.is_some() {
+                attrs.gid = gid;
+            }
+            sftp.set_metadata(path, attrs)
+                .await
+                .map_err(|e| anyhow!("Failed to chown '{}': {}", path, e))?;
+
+            if recursive && file_type == 0o040000 {
+                for child in self.remote_children(sftp, path).await? {
+                    self.chown_remote(sftp, &child, uid, gid, true).await?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    async fn remote_children(
+        &self,
+        sftp: &russh_sftp::client::SftpSession,
+        path: &str,
+    ) -> Result<Vec<String>> {
+        let entries = sftp
+            .read_dir(path)
+            .await
+            .map_err(|e| anyhow!("Failed to list dir '{}': {}", path, e))?;
+        Ok(entries
+            .map(|entry| entry.file_name())
+            .filter(|name| name != "." && name != "..")
+            .map(|name| crate::remote_path::join(path, &name))
+            .collect())
+    }
+
+    // --- Symlinks ---
+
+    fn is_local_symlink(path: &str) -> bool {
+        fs::symlink_metadata(path)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false)
+    }
+
+    async fn is_remote_symlink(&self, sftp: &russh_sftp::client::SftpSession, path: &str) -> bool {
+        sftp.symlink_metadata(path)
+            .await
+            .map(|m| (m.permissions.unwrap_or(0) & 0o170000) == 0o120000)
+            .unwrap_or(false)
+    }
+
+    /// Creates `link_path` pointing at `target`. The target is stored as given,
+    /// so relative targets stay relative to the link's directory.
+    pub fn create_symlink_local(target: &str, link_path: &str) -> Result<()> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(target, link_path)
+                .map_err(|e| anyhow!("Failed to create symlink '{}': {}", link_path, e))
+        }
+
+        #[cfg(windows)]
+        {
+            // Windows needs to know up front whether the link is for a directory.
+            let resolved = std::path::Path::new(link_path)
+                .parent()
+                .map(|parent| parent.join(target))
+                .unwrap_or_else(|| std::path::PathBuf::from(target));
+            let result = if resolved.is_dir() {
+                std::os::windows::fs::symlink_dir(target, link_path)
+            } else {
+                std::os::windows::fs::symlink_file(target, link_path)
+            };
+            result.map_err(|e| anyhow!("Failed to create symlink '{}': {}", link_path, e))
+        }
+    }
+
+    fn recreate_symlink_local(from: &str, to: &str) -> Result<()> {
+        let target =
+            fs::read_link(from).map_err(|e| anyhow!("Failed to read link '{}': {}", from, e))?;
+        Self::create_symlink_local(&target.to_string_lossy(), to)
+    }
+
+    pub async fn create_symlink_remote(
+        &self,
+        sftp: &russh_sftp::client::SftpSession,
+        target: &str,
+        link_path: &str,
+    ) -> Result<()> {
+        // OpenSSH's sftp-server (and most servers that copied it) reads the
+        // SSH_FXP_SYMLINK arguments in reverse order from the draft spec, so the
+        // target has to go in the first slot.
+        sftp.symlink(target, link_path)
+            .await
+            .map_err(|e| anyhow!("Failed to create symlink '{}': {}", link_path, e))
+    }
+
+    // --- SCP fallback (servers without the sftp subsystem) ---
+
+    /// Lists a directory by parsing `ls -la`, since SCP itself cannot enumerate.
+    pub async fn list_scp(
+        &self,
+        mut channel: russh::Channel<russh::client::Msg>,
+        path: &str,
+    ) -> Result<Vec<FileEntry>> {
+        let path = if path.is_empty() { "." } else { path };
+        let quoted = scp_quote(path);
+        let command = format!(
+            "LC_ALL=C ls -la --time-style=+%s -- {p} 2>/dev/null || LC_ALL=C ls -la -- {p}",
+            p = quoted
+        );
+        channel.exec(true, command).await?;
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let mut exit_code = 0;
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+                russh::ChannelMsg::ExtendedData { ref data, .. } => stderr.extend_from_slice(data),
+                russh::ChannelMsg::ExitStatus { exit_status } => exit_code = exit_status,
+                _ => {}
+            }
+        }
+        if exit_code != 0 {
+            return Err(anyhow!(
+                "Listing '{}' failed: {}",
+                path,
+                String::from_utf8_lossy(&stderr).trim()
+            ));
+        }
+
+        Ok(parse_ls_listing(&String::from_utf8_lossy(&stdout), path))
+    }
+
+    /// Downloads one file with `scp -f`.
+    pub async fn read_scp(
+        &self,
+        channel: russh::Channel<russh::client::Msg>,
+        path: &str,
+    ) -> Result<Vec<u8>> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        channel
+            .exec(true, format!("scp -f -- {}", scp_quote(path)))
+            .await?;
+        let mut stream = channel.into_stream();
+
+        stream.write_all(&[0]).await?;
+        let header = loop {
+            let kind = stream.read_u8().await?;
+            let line = scp_read_line(&mut stream).await?;
+            match kind {
+                b'C' => break format!("C{}", line),
+                // Time records (`scp -p`) carry nothing we need.
+                b'T' => stream.write_all(&[0]).await?,
+                b'D' => return Err(anyhow!("'{}' is a directory", path)),
+                _ => return Err(anyhow!("SCP error: {}", line.trim())),
+            }
+        };
+        let (_, size, _) = parse_scp_header(&header)?;
+        stream.write_all(&[0]).await?;
+
+        let mut content = vec![0u8; size as usize];
+        stream.read_exact(&mut content).await?;
+        scp_read_ack(&mut stream).await?;
+        stream.write_all(&[0]).await?;
+        let _ = stream.shutdown().await;
+        Ok(content)
+    }
+
+    /// Uploads one file with `scp -t`, replacing whatever is at `path`.
+    pub async fn write_scp(
+        &self,
+        channel: russh::Channel<russh::client::Msg>,
+        path: &str,
+        content: &[u8],
+        mode: u32,
+    ) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if name.is_empty() || name.contains('\n') {
+            return Err(anyhow!("Invalid file name for SCP: '{}'", path));
+        }
+        channel
+            .exec(true, format!("scp -t -- {}", scp_quote(path)))
+            .await?;
+        let mut stream = channel.into_stream();
+
+        scp_read_ack(&mut stream).await?;
+        stream
+            .write_all(format!("C{:04o} {} {}\n", mode & 0o7777, content.len(), name).as_bytes())
+            .await?;
+        scp_read_ack(&mut stream).await?;
+        stream.write_all(content).await?;
+        stream.write_all(&[0]).await?;
+        scp_read_ack(&mut stream).await?;
+        let _ = stream.shutdown().await;
+        Ok(())
+    }
+
+    fn copy_dir_recursive(from: &str, to: &str, preserve_symlinks: bool) -> Result<()> {
         fs::create_dir_all(to).map_err(|e| anyhow!("Failed to create destination dir: {}", e))?;
         for entry in fs::read_dir(from).map_err(|e| anyhow!("Failed to read source dir: {}", e))? {
             let entry = entry.map_err(|e| anyhow!("Failed to read entry: {}", e))?;
@@ -576,10 +1455,16 @@ impl FileSystem {
                 .file_type()
                 .map_err(|e| anyhow!("Failed to read file type: {}", e))?;
             let dest_path = std::path::Path::new(to).join(entry.file_name());
-            if ft.is_dir() {
+            if ft.is_symlink() && preserve_symlinks {
+                Self::recreate_symlink_local(
+                    &entry.path().to_string_lossy(),
+                    &dest_path.to_string_lossy(),
+                )?;
+            } else if ft.is_dir() {
                 Self::copy_dir_recursive(
                     &entry.path().to_string_lossy(),
                     &dest_path.to_string_lossy(),
+                    preserve_symlinks,
                 )?;
             } else {
                 fs::copy(entry.path(), dest_path)
@@ -589,3 +1474,203 @@ impl FileSystem {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::FileSystem;
+
+    #[test]
+    fn parse_scp_header_reads_mode_size_and_name() {
+        let (mode, size, name) = super::parse_scp_header("C0644 1234 my file.txt").unwrap();
+        assert_eq!(mode, 0o644);
+        assert_eq!(size, 1234);
+        assert_eq!(name, "my file.txt");
+        assert!(super::parse_scp_header("D0755 0 dir").is_err());
+    }
+
+    #[test]
+    fn parse_ls_line_handles_epoch_and_plain_formats() {
+        let entry = super::parse_ls_line(
+            "-rw-r--r--    1 root     root          42 1700000000 notes  v2.txt",
+            "/etc",
+        )
+        .unwrap();
+        assert_eq!(entry.name, "notes  v2.txt");
+        assert_eq!(entry.path, "/etc/notes  v2.txt");
+        assert_eq!(entry.size, 42);
+        assert_eq!(entry.last_modified, 1_700_000_000_000);
+        assert_eq!(entry.permissions, "644");
+
+        let link =
+            super::parse_ls_line("lrwxrwxrwx 1 root root 7 Jan  1 12:00 bin -> usr/bin", "/")
+                .unwrap();
+        assert_eq!(link.path, "/bin");
+        assert_eq!(link.r#type, "l");
+        assert_eq!(link.link_target.as_deref(), Some("usr/bin"));
+
+        let device =
+            super::parse_ls_line("crw-rw-rw- 1 root root 1, 3 1700000000 null", "/dev").unwrap();
+        assert_eq!(device.name, "null");
+        assert_eq!(device.size, 0);
+
+        assert!(super::parse_ls_line("total 8", "/").is_none());
+        assert!(super::parse_ls_line("drwxr-xr-x 2 root root 4096 1700000000 .", "/").is_none());
+    }
+
+    #[test]
+    fn last_lines_handles_trailing_newline_and_short_files() {
+        assert_eq!(super::last_lines(b"a\nb\nc\n", 2), "b\nc");
+        assert_eq!(super::last_lines(b"a\nb", 5), "a\nb");
+        assert_eq!(super::last_lines(b"", 3), "");
+        assert!(super::tail_is_complete(b"x\ny\nz\n", 2));
+        assert!(!super::tail_is_complete(b"y\nz\n", 2));
+    }
+
+    #[test]
+    fn read_tail_local_spans_multiple_blocks() {
+        let path = std::env::temp_dir().join(format!("zync-tail-{}.log", uuid::Uuid::new_v4()));
+        let content: String = (0..20_000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let fs = FileSystem::new();
+        let tail = fs.read_tail_local(&path.to_string_lossy(), 3).unwrap();
+        assert_eq!(tail, "line 19997\nline 19998\nline 19999");
+        fs.append_local(&path.to_string_lossy(), b"appended\n").unwrap();
+        assert_eq!(fs.read_tail_local(&path.to_string_lossy(), 1).unwrap(), "appended");
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn parse_mode_accepts_octal_forms() {
+        assert_eq!(FileSystem::parse_mode("755").unwrap(), 0o755);
+        assert_eq!(FileSystem::parse_mode("0644").unwrap(), 0o644);
+        assert_eq!(FileSystem::parse_mode("0o4750").unwrap(), 0o4750);
+        assert!(FileSystem::parse_mode("789").is_err());
+        assert!(FileSystem::parse_mode("17777").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn chmod_local_recurses_into_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("zync-chmod-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::fs::write(root.join("nested/file.txt"), b"x").unwrap();
+
+        let fs = FileSystem::new();
+        fs.chmod_local(&root.to_string_lossy(), 0o750, true).unwrap();
+        let mode = std::fs::metadata(root.join("nested/file.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn copy_local_preserves_symlinks_when_requested() {
+        let root = std::env::temp_dir().join(format!("zync-symlink-{}", uuid::Uuid::new_v4()));
+        let src = root.join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("real.txt"), b"data").unwrap();
+        std::os::unix::fs::symlink("real.txt", src.join("link.txt")).unwrap();
+
+        let fs = FileSystem::new();
+        let preserved = root.join("preserved");
+        fs.copy("local", &src.to_string_lossy(), &preserved.to_string_lossy(), true)
+            .await
+            .unwrap();
+        let link = preserved.join("link.txt");
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link).unwrap().to_string_lossy(), "real.txt");
+
+        let dereferenced = root.join("dereferenced");
+        fs.copy("local", &src.to_string_lossy(), &dereferenced.to_string_lossy(), false)
+            .await
+            .unwrap();
+        let copied = dereferenced.join("link.txt");
+        assert!(!std::fs::symlink_metadata(&copied).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read(&copied).unwrap(), b"data");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn list_local_reports_link_target() {
+        let root = std::env::temp_dir().join(format!("zync-linklist-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("dir")).unwrap();
+        std::os::unix::fs::symlink("dir", root.join("to-dir")).unwrap();
+        std::os::unix::fs::symlink("missing", root.join("dangling")).unwrap();
+
+        let entries = FileSystem::new().list_local(&root.to_string_lossy()).unwrap();
+        let to_dir = entries.iter().find(|e| e.name == "to-dir").unwrap();
+        assert_eq!(to_dir.r#type, "l");
+        assert_eq!(to_dir.link_target.as_deref(), Some("dir"));
+        assert_eq!(to_dir.link_target_type.as_deref(), Some("d"));
+        let dangling = entries.iter().find(|e| e.name == "dangling").unwrap();
+        assert_eq!(dangling.link_target.as_deref(), Some("missing"));
+        assert_eq!(dangling.link_target_type, None);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn walk_skips_special_files_and_hidden_children() {
+        use super::{SkipReason, SpecialFilePolicy, Walk, WalkFlags};
+
+        assert_eq!(SkipReason::from_mode(0o010644), Some(SkipReason::Fifo));
+        assert_eq!(SkipReason::from_mode(0o140755), Some(SkipReason::Socket));
+        assert_eq!(SkipReason::from_mode(0o100644), None);
+        assert_eq!(SkipReason::from_mode(0o040755), None);
+
+        let mut walk = Walk::new(WalkFlags {
+            skip_hidden: true,
+            special_files: SpecialFilePolicy::Skip,
+        });
+        assert!(walk.skip_child("/srv/.git", ".git"));
+        assert!(!walk.skip_child("/srv/app", "app"));
+        assert!(walk
+            .skip_special("/dev/null", Some(SkipReason::CharDevice))
+            .unwrap());
+        assert!(!walk.skip_special("/srv/app", None).unwrap());
+        assert_eq!(walk.skipped.len(), 2);
+        assert_eq!(walk.skipped[1].reason, SkipReason::CharDevice);
+
+        let mut strict = Walk::new(WalkFlags {
+            skip_hidden: false,
+            special_files: SpecialFilePolicy::Error,
+        });
+        assert!(!strict.skip_child("/srv/.env", ".env"));
+        assert!(strict
+            .skip_special("/run/app.sock", Some(SkipReason::Socket))
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn local_fifo_is_detected() {
+        let dir = std::env::temp_dir().join(format!("zync-fifo-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("pipe");
+        let created = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false);
+        if created {
+            let meta = std::fs::metadata(&fifo).unwrap();
+            assert_eq!(
+                super::SkipReason::from_local(&meta),
+                Some(super::SkipReason::Fifo)
+            );
+        }
+        let meta = std::fs::metadata(&dir).unwrap();
+        assert_eq!(super::SkipReason::from_local(&meta), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
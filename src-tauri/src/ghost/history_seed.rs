@@ -57,7 +57,7 @@ pub async fn seed_remote_shell_history(
         let content = match read_remote_file(state, &request.connection_id, &path).await {
             Ok(content) => content,
             Err(err) => {
-                eprintln!(
+                crate::log_error!(
                     "[Ghost] history seed read failed: {}",
                     classify_history_read_error(&err)
                 );
@@ -90,7 +90,7 @@ pub async fn seed_remote_shell_history(
 
 // Note: command text is never logged; only counts and skip reasons are returned.
 
-fn history_file_paths(home_path: &str) -> Vec<String> {
+pub(crate) fn history_file_paths(home_path: &str) -> Vec<String> {
     let trimmed = home_path.trim();
     if trimmed.is_empty() || trimmed == "~" || trimmed == "/" {
         return Vec::new();
@@ -59,7 +59,7 @@ impl GhostManager {
         let json = match serde_json::to_string(data) {
             Ok(j) => j,
             Err(e) => {
-                eprintln!("[Ghost] Failed to serialize history: {}", e);
+                crate::log_error!("[Ghost] Failed to serialize history: {}", e);
                 return;
             }
         };
@@ -78,12 +78,12 @@ impl GhostManager {
         let tmp_path = self.persist_path.with_extension(unique);
 
         if let Err(e) = tokio::fs::write(&tmp_path, &json).await {
-            eprintln!("[Ghost] Failed to write tmp history: {}", e);
+            crate::log_error!("[Ghost] Failed to write tmp history: {}", e);
             return;
         }
 
         if let Err(e) = tokio::fs::rename(&tmp_path, &self.persist_path).await {
-            eprintln!("[Ghost] Failed to rename tmp history: {}", e);
+            crate::log_error!("[Ghost] Failed to rename tmp history: {}", e);
             let _ = tokio::fs::remove_file(&tmp_path).await;
         }
     }
@@ -0,0 +1,326 @@
+//! Workspace-wide find: one query run on several connections at once.
+//!
+//! `global_search` turns a query into a remote `grep -r` (file contents) or
+//! `find` (file names) command, runs it on the selected connections with
+//! bounded concurrency and emits each host's matches as `search:result` as soon
+//! as that host answers, then `search:done` with the totals. Output is capped
+//! per host on the server side (`head`), so a query that matches half a disk
+//! does not flood the channel.
+
+use crate::ai::tool_exec_support::shell_quote;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+
+pub const DEFAULT_MAX_RESULTS_PER_HOST: usize = 200;
+const MAX_RESULTS_PER_HOST: usize = 5000;
+pub const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 16;
+/// Matched lines are cut to this many characters (minified files, binaries).
+const MAX_LINE_CHARS: usize = 400;
+
+static CANCELLED: LazyLock<Mutex<HashMap<String, Arc<AtomicBool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    /// Lines inside files (`grep -r`).
+    #[default]
+    Content,
+    /// File and directory names (`find`).
+    Name,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchScope {
+    pub connection_ids: Vec<String>,
+    /// Remote directories to search; `~` and `~/…` are the login user's home.
+    pub paths: Vec<String>,
+    pub kind: SearchKind,
+    pub case_sensitive: bool,
+    /// Treat a content query as an extended regex instead of a fixed string.
+    pub regex: bool,
+    /// File name glob for content searches, e.g. `*.conf`.
+    pub include: Option<String>,
+    pub max_results_per_host: usize,
+    /// Hosts searched at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for SearchScope {
+    fn default() -> Self {
+        Self {
+            connection_ids: Vec::new(),
+            paths: vec!["~".to_string()],
+            kind: SearchKind::default(),
+            case_sensitive: false,
+            regex: false,
+            include: None,
+            max_results_per_host: DEFAULT_MAX_RESULTS_PER_HOST,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl SearchScope {
+    pub fn result_limit(&self) -> usize {
+        self.max_results_per_host.clamp(1, MAX_RESULTS_PER_HOST)
+    }
+
+    pub fn concurrency_limit(&self) -> usize {
+        self.concurrency.clamp(1, MAX_CONCURRENCY)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub path: String,
+    /// 1-based line number; content searches only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Payload of `search:result`: everything one host returned.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostSearchResult {
+    pub search_id: String,
+    pub connection_id: String,
+    /// Saved name of the connection, for attribution in the results list.
+    pub connection_name: String,
+    pub host: String,
+    pub matches: Vec<SearchMatch>,
+    /// More matches existed than `maxResultsPerHost`.
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload of `search:done` and the command's return value.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub search_id: String,
+    pub hosts: usize,
+    pub hosts_with_matches: usize,
+    pub failed: usize,
+    pub matches: usize,
+    pub cancelled: bool,
+}
+
+/// Registers a running search; the flag is set by [`cancel`].
+pub fn register(search_id: &str) -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut searches) = CANCELLED.lock() {
+        searches.insert(search_id.to_string(), flag.clone());
+    }
+    flag
+}
+
+pub fn unregister(search_id: &str) {
+    if let Ok(mut searches) = CANCELLED.lock() {
+        searches.remove(search_id);
+    }
+}
+
+/// Stops a running search; hosts already queried still report.
+pub fn cancel(search_id: &str) -> bool {
+    CANCELLED
+        .lock()
+        .ok()
+        .and_then(|searches| searches.get(search_id).cloned())
+        .map(|flag| flag.store(true, Ordering::Relaxed))
+        .is_some()
+}
+
+/// Shell word for a remote path, keeping a leading `~` expandable.
+fn quote_path(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some("") => "\"$HOME\"".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("\"$HOME\"{}", shell_quote(rest)),
+        _ => shell_quote(path),
+    }
+}
+
+/// Escapes `find -name` pattern characters so the query matches literally.
+fn escape_glob(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for c in query.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The POSIX shell command that runs the search on one host. One line more
+/// than the limit is requested so truncation can be detected.
+pub fn build_command(query: &str, scope: &SearchScope) -> Result<String, String> {
+    if query.trim().is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    if query.contains('\n') || query.contains('\0') {
+        return Err("Search query must be a single line".to_string());
+    }
+    let paths = if scope.paths.iter().all(|p| p.trim().is_empty()) {
+        quote_path("~")
+    } else {
+        scope
+            .paths
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(quote_path)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let head = format!("head -n {}", scope.result_limit() + 1);
+    Ok(match scope.kind {
+        SearchKind::Content => {
+            let mut flags = String::from("-rIsn --null");
+            flags.push_str(if scope.regex { " -E" } else { " -F" });
+            if !scope.case_sensitive {
+                flags.push_str(" -i");
+            }
+            if let Some(include) = scope.include.as_deref().filter(|g| !g.trim().is_empty()) {
+                flags.push_str(&format!(" --include={}", shell_quote(include.trim())));
+            }
+            format!(
+                "grep {} -e {} -- {} | {}",
+                flags,
+                shell_quote(query),
+                paths,
+                head
+            )
+        }
+        SearchKind::Name => format!(
+            "find {} {} {} -print 2>/dev/null | {}",
+            paths,
+            if scope.case_sensitive {
+                "-name"
+            } else {
+                "-iname"
+            },
+            shell_quote(&format!("*{}*", escape_glob(query))),
+            head
+        ),
+    })
+}
+
+fn clip(text: &str) -> String {
+    match text.char_indices().nth(MAX_LINE_CHARS) {
+        Some((at, _)) => format!("{}…", &text[..at]),
+        None => text.to_string(),
+    }
+}
+
+/// Parses the command's output into at most `limit` matches; the flag is set
+/// when the output held more.
+pub fn parse_output(kind: SearchKind, stdout: &[u8], limit: usize) -> (Vec<SearchMatch>, bool) {
+    let text = String::from_utf8_lossy(stdout);
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    for line in text.lines().filter(|line| !line.is_empty()) {
+        if matches.len() == limit {
+            truncated = true;
+            break;
+        }
+        let parsed = match kind {
+            // `path\0line:text` thanks to `--null`.
+            SearchKind::Content => line.split_once('\0').and_then(|(path, rest)| {
+                let (number, text) = rest.split_once(':')?;
+                Some(SearchMatch {
+                    path: path.to_string(),
+                    line: number.parse().ok(),
+                    text: Some(clip(text)),
+                })
+            }),
+            SearchKind::Name => Some(SearchMatch {
+                path: line.to_string(),
+                line: None,
+                text: None,
+            }),
+        };
+        matches.extend(parsed);
+    }
+    (matches, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_grep_command() {
+        let scope = SearchScope {
+            paths: vec!["~/app".to_string(), "/etc/it's".to_string()],
+            include: Some("*.conf".to_string()),
+            max_results_per_host: 50,
+            ..Default::default()
+        };
+        assert_eq!(
+            build_command("max_connections = 100", &scope).unwrap(),
+            "grep -rIsn --null -F -i --include='*.conf' -e 'max_connections = 100' -- \
+             \"$HOME\"'/app' '/etc/it'\\''s' | head -n 51"
+        );
+        assert!(build_command("  ", &scope).is_err());
+        assert!(build_command("a\nb", &scope).is_err());
+    }
+
+    #[test]
+    fn builds_find_command() {
+        let scope = SearchScope {
+            kind: SearchKind::Name,
+            case_sensitive: true,
+            paths: vec![],
+            ..Default::default()
+        };
+        assert_eq!(
+            build_command("nginx*.conf", &scope).unwrap(),
+            "find \"$HOME\" -name '*nginx\\*.conf*' -print 2>/dev/null | head -n 201"
+        );
+    }
+
+    #[test]
+    fn parses_grep_output_with_limit() {
+        let stdout = b"/etc/app.conf\x0012:port: 8080\n/srv/a:b.txt\x003:x\n/tmp/c\x001:y\n";
+        let (matches, truncated) = parse_output(SearchKind::Content, stdout, 2);
+        assert!(truncated);
+        assert_eq!(
+            matches,
+            [
+                SearchMatch {
+                    path: "/etc/app.conf".to_string(),
+                    line: Some(12),
+                    text: Some("port: 8080".to_string()),
+                },
+                SearchMatch {
+                    path: "/srv/a:b.txt".to_string(),
+                    line: Some(3),
+                    text: Some("x".to_string()),
+                },
+            ]
+        );
+
+        let (names, truncated) = parse_output(SearchKind::Name, b"/a\n/b\n", 5);
+        assert!(!truncated);
+        assert_eq!(names.len(), 2);
+    }
+
+    #[test]
+    fn cancels_registered_searches() {
+        let flag = register("s1");
+        assert!(!cancel("missing"));
+        assert!(cancel("s1"));
+        assert!(flag.load(Ordering::Relaxed));
+        unregister("s1");
+        assert!(!cancel("s1"));
+    }
+}
@@ -0,0 +1,235 @@
+//! File hashing for cross-host comparisons.
+//!
+//! Remote files are hashed server-side (`sha256sum`, `shasum`, `md5`, or
+//! `Get-FileHash` on Windows) so nothing has to be downloaded. Local files and
+//! servers without those tools go through the streaming [`Hasher`].
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+const READ_CHUNK: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "").as_str() {
+            "md5" => Ok(Self::Md5),
+            "sha1" => Ok(Self::Sha1),
+            "sha256" => Ok(Self::Sha256),
+            other => Err(anyhow!("Unsupported hash algorithm '{}'", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// Length of the hex digest, used to validate server output.
+    pub fn hex_len(&self) -> usize {
+        match self {
+            Self::Md5 => 32,
+            Self::Sha1 => 40,
+            Self::Sha256 => 64,
+        }
+    }
+
+    /// POSIX command that prints the digest of `quoted_path` as its first word.
+    /// GNU coreutils names come first; BSD/macOS equivalents are the fallback.
+    pub fn posix_command(&self, quoted_path: &str) -> String {
+        match self {
+            Self::Md5 => format!(
+                "md5sum -- {p} 2>/dev/null || md5 -q -- {p}",
+                p = quoted_path
+            ),
+            Self::Sha1 => format!(
+                "sha1sum -- {p} 2>/dev/null || shasum -a 1 -- {p}",
+                p = quoted_path
+            ),
+            Self::Sha256 => format!(
+                "sha256sum -- {p} 2>/dev/null || shasum -a 256 -- {p}",
+                p = quoted_path
+            ),
+        }
+    }
+
+    pub fn powershell_command(&self, path: &str) -> String {
+        let algo = match self {
+            Self::Md5 => "MD5",
+            Self::Sha1 => "SHA1",
+            Self::Sha256 => "SHA256",
+        };
+        crate::remote_path::powershell_command(&format!(
+            "(Get-FileHash -Algorithm {} -LiteralPath '{}').Hash",
+            algo,
+            path.replace('\'', "''")
+        ))
+    }
+
+    /// Extracts and validates the digest from command output.
+    pub fn parse_output(&self, stdout: &str) -> Option<String> {
+        let digest = stdout.split_whitespace().next()?.to_ascii_lowercase();
+        (digest.len() == self.hex_len() && digest.chars().all(|c| c.is_ascii_hexdigit()))
+            .then_some(digest)
+    }
+}
+
+/// How a checksum was produced, so callers can tell a cheap server-side digest
+/// from one that streamed the whole file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumMethod {
+    Server,
+    Stream,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checksum {
+    pub path: String,
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+    pub method: ChecksumMethod,
+}
+
+/// Incremental hasher over any of the supported algorithms.
+pub enum Hasher {
+    Md5(md5::Context),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    pub fn new(algo: HashAlgorithm) -> Self {
+        match algo {
+            HashAlgorithm::Md5 => Self::Md5(md5::Context::new()),
+            HashAlgorithm::Sha1 => Self::Sha1(<sha1::Sha1 as sha1::Digest>::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(<sha2::Sha256 as sha2::Digest>::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(ctx) => ctx.consume(data),
+            Self::Sha1(h) => sha1::Digest::update(h, data),
+            Self::Sha256(h) => sha2::Digest::update(h, data),
+        }
+    }
+
+    pub fn finalize_hex(self) -> String {
+        let bytes: Vec<u8> = match self {
+            Self::Md5(ctx) => ctx.compute().0.to_vec(),
+            Self::Sha1(h) => sha1::Digest::finalize(h).to_vec(),
+            Self::Sha256(h) => sha2::Digest::finalize(h).to_vec(),
+        };
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+pub fn hash_reader<R: Read>(mut reader: R, algo: HashAlgorithm) -> Result<String> {
+    let mut hasher = Hasher::new(algo);
+    let mut buffer = vec![0u8; READ_CHUNK];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+pub fn hash_bytes(data: &[u8], algo: HashAlgorithm) -> String {
+    let mut hasher = Hasher::new(algo);
+    hasher.update(data);
+    hasher.finalize_hex()
+}
+
+pub fn hash_local_file(path: &str, algo: HashAlgorithm) -> Result<String> {
+    let file =
+        std::fs::File::open(path).map_err(|e| anyhow!("Failed to open '{}': {}", path, e))?;
+    hash_reader(file, algo)
+}
+
+/// Streams a remote file through the hasher over SFTP.
+pub async fn hash_remote_file(
+    sftp: &russh_sftp::client::SftpSession,
+    path: &str,
+    algo: HashAlgorithm,
+) -> Result<String> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = sftp
+        .open(path)
+        .await
+        .map_err(|e| anyhow!("Failed to open '{}': {}", path, e))?;
+    let mut hasher = Hasher::new(algo);
+    let mut buffer = vec![0u8; READ_CHUNK];
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_digests_of_abc() {
+        let input = b"abc";
+        assert_eq!(
+            hash_reader(&input[..], HashAlgorithm::Md5).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hash_reader(&input[..], HashAlgorithm::Sha1).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hash_reader(&input[..], HashAlgorithm::Sha256).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn hash_bytes_matches_streaming_hasher() {
+        let data: Vec<u8> = (0..3_000_000u32).map(|i| (i % 253) as u8).collect();
+        assert_eq!(
+            hash_bytes(&data, HashAlgorithm::Sha256),
+            hash_reader(&data[..], HashAlgorithm::Sha256).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_accepts_common_spellings() {
+        assert_eq!(HashAlgorithm::parse("SHA-256").unwrap(), HashAlgorithm::Sha256);
+        assert_eq!(HashAlgorithm::parse("sha1").unwrap(), HashAlgorithm::Sha1);
+        assert!(HashAlgorithm::parse("crc32").is_err());
+    }
+
+    #[test]
+    fn parse_output_validates_digest() {
+        let algo = HashAlgorithm::Md5;
+        assert_eq!(
+            algo.parse_output("900150983CD24FB0D6963F7D28E17F72  /tmp/abc\n").as_deref(),
+            Some("900150983cd24fb0d6963f7d28e17f72")
+        );
+        assert_eq!(algo.parse_output("md5sum: /tmp/abc: No such file"), None);
+    }
+}
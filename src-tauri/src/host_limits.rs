@@ -0,0 +1,331 @@
+//! Per-host limits on SSH connection attempts.
+//!
+//! Servers behind fail2ban or sshguard ban clients that open many connections
+//! or fail authentication repeatedly, which is easy to do by reconnecting a
+//! dozen tabs at once or retrying after a typoed password. Every hop of
+//! [`crate::ssh::SshManager::connect`] takes a slot here first. A slot is
+//! granted when fewer than `maxConcurrent` attempts to the same `host:port` are
+//! in flight, fewer than `maxPerMinute` were started in the last minute, and
+//! the host is not cooling down after failed authentication. Each consecutive
+//! failure doubles the cooldown, up to [`MAX_COOLDOWN`], and a success clears
+//! it. Attempts that have to wait are queued rather than refused. The wait is
+//! reported through `ssh:connect-queued` so the UI can explain the pause.
+//!
+//! Settings live in `host_limits.json`; a limit of 0 turns that check off.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const MAX_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HostLimitSettings {
+    /// Attempts to one host that may handshake and authenticate at once.
+    pub max_concurrent: u32,
+    /// Attempts to one host started within any 60 seconds.
+    pub max_per_minute: u32,
+    /// Pause after the first failed authentication; doubles per failure.
+    pub auth_failure_cooldown_secs: u64,
+}
+
+impl Default for HostLimitSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_per_minute: 10,
+            auth_failure_cooldown_secs: 5,
+        }
+    }
+}
+
+impl HostLimitSettings {
+    fn cooldown(&self, failures: u32) -> Duration {
+        if failures == 0 || self.auth_failure_cooldown_secs == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 1u64 << (failures - 1).min(16);
+        Duration::from_secs(self.auth_failure_cooldown_secs.saturating_mul(factor))
+            .min(MAX_COOLDOWN)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WaitReason {
+    /// `maxConcurrent` attempts to the host are already in flight.
+    Concurrency,
+    /// `maxPerMinute` attempts were started in the last minute.
+    RateLimit,
+    /// Authentication failed recently.
+    AuthCooldown,
+}
+
+/// Payload of `ssh:connect-queued`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectQueued {
+    pub connection_id: String,
+    pub host: String,
+    pub reason: WaitReason,
+    /// Known wait; absent while waiting for another attempt to finish.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct HostState {
+    started: VecDeque<Instant>,
+    failures: u32,
+    last_failure: Option<Instant>,
+    /// Recreated when `max_concurrent` changes; permits of the old one drain.
+    permits: Option<(u32, Arc<Semaphore>)>,
+}
+
+impl HostState {
+    /// How long a new attempt has to wait before the rate and cooldown
+    /// checks allow it, and why.
+    fn delay(
+        &mut self,
+        settings: &HostLimitSettings,
+        now: Instant,
+    ) -> Option<(WaitReason, Duration)> {
+        while self
+            .started
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW)
+        {
+            self.started.pop_front();
+        }
+        if let Some(failed_at) = self.last_failure {
+            let until = failed_at + settings.cooldown(self.failures);
+            if until > now {
+                return Some((WaitReason::AuthCooldown, until - now));
+            }
+        }
+        let max = settings.max_per_minute as usize;
+        if max > 0 && self.started.len() >= max {
+            let oldest = self.started[self.started.len() - max];
+            return Some((WaitReason::RateLimit, oldest + RATE_WINDOW - now));
+        }
+        None
+    }
+
+    fn semaphore(&mut self, max_concurrent: u32) -> Option<Arc<Semaphore>> {
+        if max_concurrent == 0 {
+            return None;
+        }
+        match &self.permits {
+            Some((size, semaphore)) if *size == max_concurrent => Some(semaphore.clone()),
+            _ => {
+                let semaphore = Arc::new(Semaphore::new(max_concurrent as usize));
+                self.permits = Some((max_concurrent, semaphore.clone()));
+                Some(semaphore)
+            }
+        }
+    }
+}
+
+/// Held for the duration of one connection attempt.
+pub struct HostSlot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+pub struct HostLimiter {
+    settings_path: PathBuf,
+    settings: Mutex<HostLimitSettings>,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+pub fn host_key(host: &str, port: u16) -> String {
+    format!("{}:{}", host.trim().to_ascii_lowercase(), port)
+}
+
+impl HostLimiter {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        let settings_path = app_data_dir.join("host_limits.json");
+        let settings = std::fs::read(&settings_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self {
+            settings_path,
+            settings: Mutex::new(settings),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn settings(&self) -> HostLimitSettings {
+        self.settings.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn save_settings(&self, settings: HostLimitSettings) -> Result<HostLimitSettings, String> {
+        let content = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.settings_path, &content)
+            .map_err(|e| e.to_string())?;
+        if let Ok(mut current) = self.settings.lock() {
+            *current = settings.clone();
+        }
+        Ok(settings)
+    }
+
+    /// Waits until an attempt to `key` is allowed, calling `on_wait` each time
+    /// the attempt has to queue.
+    pub async fn acquire(
+        &self,
+        key: &str,
+        mut on_wait: impl FnMut(WaitReason, Option<Duration>),
+    ) -> HostSlot {
+        loop {
+            let settings = self.settings();
+            let (delay, semaphore) = {
+                let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+                let host = hosts.entry(key.to_string()).or_default();
+                (
+                    host.delay(&settings, Instant::now()),
+                    host.semaphore(settings.max_concurrent),
+                )
+            };
+            if let Some((reason, wait)) = delay {
+                on_wait(reason, Some(wait));
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            let permit = match semaphore {
+                None => None,
+                Some(semaphore) => match semaphore.clone().try_acquire_owned() {
+                    Ok(permit) => Some(permit),
+                    Err(_) => {
+                        on_wait(WaitReason::Concurrency, None);
+                        match semaphore.acquire_owned().await {
+                            Ok(permit) => Some(permit),
+                            Err(_) => continue,
+                        }
+                    }
+                },
+            };
+
+            // Another attempt may have used up the rate budget or failed
+            // authentication while this one waited for a permit.
+            let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+            let host = hosts.entry(key.to_string()).or_default();
+            let now = Instant::now();
+            if host.delay(&settings, now).is_some() {
+                continue;
+            }
+            host.started.push_back(now);
+            return HostSlot { _permit: permit };
+        }
+    }
+
+    pub fn auth_failed(&self, key: &str) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let host = hosts.entry(key.to_string()).or_default();
+        host.failures = host.failures.saturating_add(1);
+        host.last_failure = Some(Instant::now());
+    }
+
+    pub fn auth_succeeded(&self, key: &str) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(host) = hosts.get_mut(key) {
+            host.failures = 0;
+            host.last_failure = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(max_concurrent: u32, max_per_minute: u32, cooldown: u64) -> HostLimitSettings {
+        HostLimitSettings {
+            max_concurrent,
+            max_per_minute,
+            auth_failure_cooldown_secs: cooldown,
+        }
+    }
+
+    #[test]
+    fn rate_limit_waits_for_the_oldest_attempt_to_leave_the_window() {
+        let settings = settings(0, 2, 0);
+        let start = Instant::now();
+        let mut host = HostState::default();
+        host.started.push_back(start);
+        host.started.push_back(start + Duration::from_secs(10));
+        assert_eq!(
+            host.delay(&settings, start + Duration::from_secs(20)),
+            Some((WaitReason::RateLimit, Duration::from_secs(40)))
+        );
+        assert_eq!(host.delay(&settings, start + Duration::from_secs(60)), None);
+        assert_eq!(host.started.len(), 1);
+    }
+
+    #[test]
+    fn auth_cooldown_doubles_and_is_capped() {
+        let settings = settings(0, 0, 5);
+        assert_eq!(settings.cooldown(0), Duration::ZERO);
+        assert_eq!(settings.cooldown(1), Duration::from_secs(5));
+        assert_eq!(settings.cooldown(3), Duration::from_secs(20));
+        assert_eq!(settings.cooldown(40), MAX_COOLDOWN);
+
+        let failed_at = Instant::now();
+        let mut host = HostState {
+            failures: 2,
+            last_failure: Some(failed_at),
+            ..Default::default()
+        };
+        assert_eq!(
+            host.delay(&settings, failed_at + Duration::from_secs(4)),
+            Some((WaitReason::AuthCooldown, Duration::from_secs(6)))
+        );
+        assert_eq!(
+            host.delay(&settings, failed_at + Duration::from_secs(10)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_attempts_queue_until_a_slot_frees() {
+        let dir = std::env::temp_dir().join(format!("zync-host-limits-{}", uuid::Uuid::new_v4()));
+        let limiter = Arc::new(HostLimiter::new(dir));
+        *limiter.settings.lock().unwrap() = settings(1, 0, 0);
+
+        let first = limiter.acquire("db:22", |_, _| {}).await;
+        let queued = Arc::new(Mutex::new(Vec::new()));
+        let waiter = {
+            let limiter = limiter.clone();
+            let queued = queued.clone();
+            tokio::spawn(async move {
+                limiter
+                    .acquire("db:22", |reason, _| queued.lock().unwrap().push(reason))
+                    .await
+            })
+        };
+        // Other hosts are not affected.
+        let _other = limiter.acquire("web:22", |_, _| panic!("queued")).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        let _second = waiter.await.unwrap();
+        assert_eq!(*queued.lock().unwrap(), [WaitReason::Concurrency]);
+    }
+
+    #[test]
+    fn success_clears_the_failure_streak() {
+        let dir = std::env::temp_dir().join(format!("zync-host-limits-{}", uuid::Uuid::new_v4()));
+        let limiter = HostLimiter::new(dir);
+        limiter.auth_failed("db:22");
+        limiter.auth_failed("db:22");
+        limiter.auth_succeeded("db:22");
+        let hosts = limiter.hosts.lock().unwrap();
+        assert_eq!(hosts["db:22"].failures, 0);
+        assert!(hosts["db:22"].last_failure.is_none());
+    }
+}
@@ -0,0 +1,257 @@
+//! Shared HTTP client factory that honors the proxy configuration.
+//!
+//! Every backend request (AI providers, plugin installs, cloud sync) builds its
+//! client through [`builder`]. The `proxy` object in settings.json selects how:
+//!
+//! - `system` (default): environment variables (`HTTPS_PROXY`, `NO_PROXY`, ...)
+//!   and the macOS / Windows system proxy, as detected by reqwest.
+//! - `none`: always connect directly.
+//! - `manual`: the proxy at `url`.
+//! - `pac`: the PAC file at `pacUrl`. Its script is not executed; the first
+//!   proxy it can return is used for every request, with `noProxy` hosts and
+//!   loopback going direct. Until the file has been fetched, `system` applies.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+const PAC_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Hosts that never go through a manual or PAC proxy, in addition to `noProxy`.
+const ALWAYS_DIRECT: &str = "localhost,127.0.0.1,::1";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    #[default]
+    System,
+    None,
+    Manual,
+    Pac,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxySettings {
+    #[serde(default)]
+    pub mode: ProxyMode,
+    pub url: Option<String>,
+    pub pac_url: Option<String>,
+    /// Comma-separated hosts, domains (`.corp.example`) or CIDRs that bypass the proxy.
+    pub no_proxy: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ProxyState {
+    settings: ProxySettings,
+    /// Proxy URL picked from the PAC file, once fetched; `None` inside means DIRECT.
+    pac_proxy: Option<Option<String>>,
+}
+
+static STATE: LazyLock<RwLock<ProxyState>> = LazyLock::new(Default::default);
+
+/// Checks the `proxy` settings object before it is saved.
+pub fn validate_settings(value: &Value) -> Result<(), String> {
+    let settings =
+        ProxySettings::deserialize(value).map_err(|e| format!("Invalid \"proxy\": {}", e))?;
+    match settings.mode {
+        ProxyMode::Manual => {
+            let url = settings.url.as_deref().unwrap_or_default();
+            reqwest::Proxy::all(url)
+                .map_err(|e| format!("Invalid \"proxy.url\" {:?}: {}", url, e))?;
+        }
+        ProxyMode::Pac => {
+            let pac_url = settings.pac_url.as_deref().unwrap_or_default();
+            reqwest::Url::parse(pac_url)
+                .map_err(|e| format!("Invalid \"proxy.pacUrl\" {:?}: {}", pac_url, e))?;
+        }
+        ProxyMode::System | ProxyMode::None => {}
+    }
+    Ok(())
+}
+
+/// Applies the `proxy` object of `settings` to clients built from now on; a PAC
+/// file is fetched in the background.
+pub fn configure(settings: &Value) {
+    let proxy = settings
+        .get("proxy")
+        .and_then(|value| ProxySettings::deserialize(value).ok())
+        .unwrap_or_default();
+    let Ok(mut state) = STATE.write() else {
+        return;
+    };
+    if state.settings == proxy {
+        return;
+    }
+    crate::log_info!("[HTTP] Proxy mode: {:?}", proxy.mode);
+    state.settings = proxy.clone();
+    state.pac_proxy = None;
+    drop(state);
+
+    if let (ProxyMode::Pac, Some(pac_url)) = (proxy.mode, proxy.pac_url) {
+        tauri::async_runtime::spawn(async move {
+            let picked = match fetch_pac(&pac_url).await {
+                Ok(script) => pac_proxy(&script),
+                Err(e) => {
+                    crate::log_error!("[HTTP] Could not load PAC file {}: {}", pac_url, e);
+                    return;
+                }
+            };
+            crate::log_info!(
+                "[HTTP] PAC file {} selects {}",
+                pac_url,
+                picked.as_deref().unwrap_or("DIRECT")
+            );
+            if let Ok(mut state) = STATE.write() {
+                if state.settings.pac_url.as_deref() == Some(pac_url.as_str()) {
+                    state.pac_proxy = Some(picked);
+                }
+            }
+        });
+    }
+}
+
+async fn fetch_pac(pac_url: &str) -> Result<String, reqwest::Error> {
+    reqwest::Client::builder()
+        .no_proxy()
+        .timeout(PAC_FETCH_TIMEOUT)
+        .build()?
+        .get(pac_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await
+}
+
+/// The first proxy a PAC script returns, as a URL. Directives are read from
+/// string literals in `return` statements: `PROXY`, `HTTP` and `HTTPS` map to
+/// HTTP(S) proxies, `SOCKS`/`SOCKS5` to SOCKS5. `None` when only DIRECT is used.
+fn pac_proxy(script: &str) -> Option<String> {
+    let mut rest = script;
+    while let Some(at) = rest.find("return") {
+        rest = &rest[at + "return".len()..];
+        let literal = rest.trim_start();
+        let Some(quote) = literal.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let Some(end) = literal[1..].find(quote) else {
+            continue;
+        };
+        for directive in literal[1..1 + end].split(';') {
+            let mut parts = directive.split_whitespace();
+            let (Some(kind), Some(host)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let scheme = match kind.to_ascii_uppercase().as_str() {
+                "PROXY" | "HTTP" => "http",
+                "HTTPS" => "https",
+                "SOCKS" | "SOCKS5" => "socks5h",
+                _ => continue,
+            };
+            return Some(format!("{}://{}", scheme, host));
+        }
+    }
+    None
+}
+
+/// Explicit proxy URL for the current settings, or `None` when the system
+/// configuration (or a direct connection) applies.
+pub fn proxy_url() -> Option<String> {
+    let state = STATE.read().ok()?;
+    match state.settings.mode {
+        ProxyMode::Manual => state.settings.url.clone(),
+        ProxyMode::Pac => state.pac_proxy.clone().flatten(),
+        ProxyMode::System | ProxyMode::None => None,
+    }
+}
+
+fn no_proxy_list(settings: &ProxySettings) -> String {
+    match settings.no_proxy.as_deref().map(str::trim) {
+        Some(extra) if !extra.is_empty() => format!("{},{}", ALWAYS_DIRECT, extra),
+        _ => ALWAYS_DIRECT.to_string(),
+    }
+}
+
+/// A client builder with the configured proxy applied. Callers add their own
+/// timeouts and build.
+pub fn builder() -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    let Ok(state) = STATE.read() else {
+        return builder;
+    };
+    let explicit = match state.settings.mode {
+        ProxyMode::System => return builder,
+        ProxyMode::None => return builder.no_proxy(),
+        ProxyMode::Manual => state.settings.url.clone(),
+        ProxyMode::Pac => match &state.pac_proxy {
+            None => return builder,
+            Some(None) => return builder.no_proxy(),
+            Some(Some(url)) => Some(url.clone()),
+        },
+    };
+    let Some(url) = explicit else {
+        return builder;
+    };
+    match reqwest::Proxy::all(&url) {
+        Ok(proxy) => builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy_list(
+            &state.settings,
+        )))),
+        Err(e) => {
+            crate::log_error!("[HTTP] Ignoring invalid proxy {}: {}", url, e);
+            builder
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pac_proxy_takes_first_proxy_directive() {
+        let script = r#"
+            function FindProxyForURL(url, host) {
+                if (isPlainHostName(host)) return "DIRECT";
+                if (dnsDomainIs(host, ".corp.example")) return 'DIRECT';
+                return "PROXY proxy.corp.example:8080; DIRECT";
+            }
+        "#;
+        assert_eq!(
+            pac_proxy(script).as_deref(),
+            Some("http://proxy.corp.example:8080")
+        );
+        assert_eq!(
+            pac_proxy(r#"return "SOCKS5 10.0.0.1:1080";"#).as_deref(),
+            Some("socks5h://10.0.0.1:1080")
+        );
+        assert_eq!(pac_proxy(r#"return "DIRECT";"#), None);
+        assert_eq!(pac_proxy("return proxyFor(host);"), None);
+    }
+
+    #[test]
+    fn validates_proxy_settings() {
+        assert!(validate_settings(&json!({})).is_ok());
+        assert!(validate_settings(&json!({ "mode": "none" })).is_ok());
+        assert!(
+            validate_settings(&json!({ "mode": "manual", "url": "http://proxy:3128" })).is_ok()
+        );
+        assert!(validate_settings(&json!({ "mode": "manual" })).is_err());
+        assert!(validate_settings(&json!({ "mode": "pac", "pacUrl": "not a url" })).is_err());
+        assert!(validate_settings(&json!({ "mode": "auto" })).is_err());
+    }
+
+    #[test]
+    fn no_proxy_always_includes_loopback() {
+        let settings = ProxySettings {
+            no_proxy: Some(" .corp.example ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            no_proxy_list(&settings),
+            "localhost,127.0.0.1,::1,.corp.example"
+        );
+        assert_eq!(no_proxy_list(&ProxySettings::default()), ALWAYS_DIRECT);
+    }
+}
@@ -0,0 +1,667 @@
+//! Inbox folders: remote directories whose new files are downloaded as they
+//! appear, e.g. a server's `exports/` or `backups/`.
+//!
+//! Each watch belongs to a saved connection and polls its `remoteDir` over the
+//! connection's SFTP session every `intervalSecs` while it is connected. A
+//! file is downloaded once two polls in a row see the same size and mtime, so
+//! a file still being written is not fetched half-done. Hidden files (often
+//! upload temporaries) and names not matching the optional `pattern` glob
+//! are ignored. Files already in the directory when a watch starts are
+//! skipped unless `skipExisting` is off.
+//!
+//! Downloads go to a hidden temp file in `localDir` and are renamed into
+//! place. If a file with the same name and identical content is already there,
+//! the download is dropped as a duplicate. A same-named file with different
+//! content keeps the new one as `name (1).ext`. Every file fetched emits
+//! `inbox:downloaded`, and every poll emits `inbox:status`. Watch definitions
+//! live in `inbox.json`. The remote files already handled are remembered in
+//! `inbox_seen.json`, so restarting does not fetch them again.
+
+use crate::commands::AppState;
+use russh_sftp::client::SftpSession;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub(crate) static INBOX_MUTATION_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+const SCHEDULER_TICK: Duration = Duration::from_secs(5);
+const MIN_INTERVAL_SECS: u64 = 10;
+const PART_SUFFIX: &str = ".zync-part";
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxWatch {
+    pub id: String,
+    pub connection_id: String,
+    pub name: String,
+    pub remote_dir: String,
+    pub local_dir: String,
+    /// `find -name` style glob on the file name, e.g. `*.csv`.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub skip_existing: bool,
+}
+
+impl InboxWatch {
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(MIN_INTERVAL_SECS))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct InboxData {
+    watches: Vec<InboxWatch>,
+}
+
+/// What identifies one version of a remote file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    mtime: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchSeen {
+    /// Set once the directory's initial contents have been recorded.
+    primed: bool,
+    files: HashMap<String, FileStamp>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SeenData {
+    watches: HashMap<String, WatchSeen>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InboxState {
+    /// Not polled yet, or its connection is not connected.
+    #[default]
+    Idle,
+    Watching,
+    Error,
+}
+
+/// Payload of `inbox:status` and an entry of `inbox_status`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxStatus {
+    pub watch_id: String,
+    pub connection_id: String,
+    pub name: String,
+    pub state: InboxState,
+    /// Files downloaded since the app started.
+    pub downloaded: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_polled_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Payload of `inbox:downloaded`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxDownload {
+    pub watch_id: String,
+    pub connection_id: String,
+    pub remote_path: String,
+    pub local_path: String,
+    pub size: u64,
+    /// An identical file was already in `localDir`; nothing new was written.
+    pub duplicate: bool,
+}
+
+#[derive(Default)]
+struct Schedule {
+    status: HashMap<String, InboxStatus>,
+    next_due: HashMap<String, u64>,
+    running: HashSet<String>,
+    /// Files seen once with this stamp, downloaded if the next poll agrees.
+    pending: HashMap<String, HashMap<String, FileStamp>>,
+}
+
+pub struct InboxManager {
+    file_path: PathBuf,
+    seen_path: PathBuf,
+    schedule: Mutex<Schedule>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Files of one listing that are ready to download. Updates `seen` and
+/// `pending` for the next poll.
+fn plan(
+    watch: &InboxWatch,
+    listing: &[(String, FileStamp)],
+    seen: &mut WatchSeen,
+    pending: &mut HashMap<String, FileStamp>,
+) -> Vec<(String, FileStamp)> {
+    let listed: HashSet<&str> = listing.iter().map(|(name, _)| name.as_str()).collect();
+    seen.files.retain(|name, _| listed.contains(name.as_str()));
+    pending.retain(|name, _| listed.contains(name.as_str()));
+    if !seen.primed {
+        seen.primed = true;
+        if watch.skip_existing {
+            seen.files.extend(listing.iter().cloned());
+            return Vec::new();
+        }
+    }
+
+    let mut ready = Vec::new();
+    for (name, stamp) in listing {
+        if seen.files.get(name) == Some(stamp) {
+            continue;
+        }
+        if pending.get(name) == Some(stamp) {
+            pending.remove(name);
+            ready.push((name.clone(), *stamp));
+        } else {
+            pending.insert(name.clone(), *stamp);
+        }
+    }
+    ready
+}
+
+/// Whether a remote name can be written into the local folder as is.
+fn is_candidate(watch: &InboxWatch, name: &str) -> bool {
+    !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && watch
+            .pattern
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+            .is_none_or(|pattern| crate::bulk_chmod::glob_match(pattern, name))
+}
+
+/// `name`, or `name (n).ext` for the first `n` not taken in `dir`.
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot..]),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+fn file_sha256(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            return Ok(hasher.finalize().to_vec());
+        }
+        hasher.update(&buf[..read]);
+    }
+}
+
+impl InboxManager {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            file_path: app_data_dir.join("inbox.json"),
+            seen_path: app_data_dir.join("inbox_seen.json"),
+            schedule: Mutex::new(Schedule::default()),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<InboxWatch>, String> {
+        let _guard = INBOX_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        if !self.file_path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.file_path).map_err(|e| e.to_string())?;
+        let data: InboxData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        Ok(data.watches)
+    }
+
+    /// Replaces all watches. Edited watches are polled again on the next tick;
+    /// state of removed watches is dropped.
+    pub fn save(&self, watches: Vec<InboxWatch>) -> Result<(), String> {
+        for watch in &watches {
+            if watch.id.trim().is_empty() || watch.connection_id.trim().is_empty() {
+                return Err("Every inbox needs an id and a connection".to_string());
+            }
+            if watch.remote_dir.trim().is_empty() || watch.local_dir.trim().is_empty() {
+                return Err(format!(
+                    "Inbox '{}' needs a remote and a local folder",
+                    watch.name
+                ));
+            }
+        }
+        let _guard = INBOX_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        let previous: HashMap<String, InboxWatch> = std::fs::read_to_string(&self.file_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<InboxData>(&content).ok())
+            .map(|data| {
+                data.watches
+                    .into_iter()
+                    .map(|w| (w.id.clone(), w))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let content = serde_json::to_vec_pretty(&InboxData {
+            watches: watches.clone(),
+        })
+        .map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.file_path, &content).map_err(|e| e.to_string())?;
+
+        // A watch pointed somewhere else starts over, as a new one would.
+        let unchanged: HashSet<&str> = watches
+            .iter()
+            .filter(|w| {
+                previous.get(&w.id).is_some_and(|p| {
+                    p.connection_id == w.connection_id && p.remote_dir == w.remote_dir
+                })
+            })
+            .map(|w| w.id.as_str())
+            .collect();
+        let mut seen = self.load_seen();
+        seen.watches.retain(|id, _| unchanged.contains(id.as_str()));
+        self.store_seen(&seen)?;
+        if let Ok(mut schedule) = self.schedule.lock() {
+            let ids: HashSet<&str> = watches.iter().map(|w| w.id.as_str()).collect();
+            schedule.status.retain(|id, _| ids.contains(id.as_str()));
+            schedule
+                .pending
+                .retain(|id, _| unchanged.contains(id.as_str()));
+            schedule.next_due.clear();
+        }
+        Ok(())
+    }
+
+    /// Current status of every watch, in definition order.
+    pub fn status(&self) -> Result<Vec<InboxStatus>, String> {
+        let watches = self.list()?;
+        let schedule = self.schedule.lock().map_err(|e| e.to_string())?;
+        Ok(watches
+            .iter()
+            .map(|watch| {
+                let mut status = schedule.status.get(&watch.id).cloned().unwrap_or_default();
+                status.watch_id = watch.id.clone();
+                status.connection_id = watch.connection_id.clone();
+                status.name = watch.name.clone();
+                status
+            })
+            .collect())
+    }
+
+    fn load_seen(&self) -> SeenData {
+        std::fs::read(&self.seen_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn store_seen(&self, seen: &SeenData) -> Result<(), String> {
+        let content = serde_json::to_vec_pretty(seen).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.seen_path, &content).map_err(|e| e.to_string())
+    }
+
+    /// Enabled watches due at `now` that are not already polling; marks them running.
+    fn take_due(&self, watches: &[InboxWatch], now: u64) -> Vec<InboxWatch> {
+        let Ok(mut schedule) = self.schedule.lock() else {
+            return Vec::new();
+        };
+        let due: Vec<InboxWatch> = watches
+            .iter()
+            .filter(|watch| watch.enabled && !schedule.running.contains(&watch.id))
+            .filter(|watch| {
+                schedule
+                    .next_due
+                    .get(&watch.id)
+                    .is_none_or(|due| *due <= now)
+            })
+            .cloned()
+            .collect();
+        for watch in &due {
+            schedule.running.insert(watch.id.clone());
+        }
+        due
+    }
+
+    /// Files to download from `listing`, persisting what was seen.
+    fn ready_files(
+        &self,
+        watch: &InboxWatch,
+        listing: &[(String, FileStamp)],
+    ) -> Vec<(String, FileStamp)> {
+        let Ok(_guard) = INBOX_MUTATION_LOCK.lock() else {
+            return Vec::new();
+        };
+        let Ok(mut schedule) = self.schedule.lock() else {
+            return Vec::new();
+        };
+        let mut seen = self.load_seen();
+        let entry = seen.watches.entry(watch.id.clone()).or_default();
+        let was_primed = entry.primed;
+        let pending = schedule.pending.entry(watch.id.clone()).or_default();
+        let ready = plan(watch, listing, entry, pending);
+        if !was_primed {
+            let _ = self.store_seen(&seen);
+        }
+        ready
+    }
+
+    fn mark_seen(&self, watch_id: &str, name: &str, stamp: FileStamp) {
+        let Ok(_guard) = INBOX_MUTATION_LOCK.lock() else {
+            return;
+        };
+        let mut seen = self.load_seen();
+        seen.watches
+            .entry(watch_id.to_string())
+            .or_default()
+            .files
+            .insert(name.to_string(), stamp);
+        if let Err(e) = self.store_seen(&seen) {
+            crate::log_error!("[INBOX] Failed to record {}: {}", name, e);
+        }
+    }
+
+    fn finish(
+        &self,
+        watch: &InboxWatch,
+        outcome: Option<Result<(), String>>,
+        downloaded: &[String],
+        now: u64,
+    ) -> Option<InboxStatus> {
+        let mut schedule = self.schedule.lock().ok()?;
+        schedule.running.remove(&watch.id);
+        schedule
+            .next_due
+            .insert(watch.id.clone(), now + watch.interval().as_millis() as u64);
+        let status = schedule.status.entry(watch.id.clone()).or_default();
+        status.watch_id = watch.id.clone();
+        status.connection_id = watch.connection_id.clone();
+        status.name = watch.name.clone();
+        status.downloaded += downloaded.len() as u64;
+        if let Some(last) = downloaded.last() {
+            status.last_file = Some(last.clone());
+        }
+        match outcome {
+            Some(Ok(())) => {
+                status.state = InboxState::Watching;
+                status.last_polled_at = Some(now);
+                status.error = None;
+            }
+            Some(Err(error)) => {
+                status.state = InboxState::Error;
+                status.last_polled_at = Some(now);
+                status.error = Some(error);
+            }
+            None => status.state = InboxState::Idle,
+        }
+        Some(status.clone())
+    }
+}
+
+fn join_remote(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+async fn list_remote(
+    sftp: &SftpSession,
+    watch: &InboxWatch,
+) -> Result<Vec<(String, FileStamp)>, String> {
+    let entries = sftp
+        .read_dir(&watch.remote_dir)
+        .await
+        .map_err(|e| format!("{}: {}", watch.remote_dir, e))?;
+    Ok(entries
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let metadata = entry.metadata();
+            (metadata.file_type().is_file() && is_candidate(watch, &name)).then(|| {
+                let stamp = FileStamp {
+                    size: metadata.size.unwrap_or(0),
+                    mtime: metadata.mtime.unwrap_or(0),
+                };
+                (name, stamp)
+            })
+        })
+        .collect())
+}
+
+/// Downloads one file into `localDir`. Returns the local path and whether it
+/// duplicated a file already there.
+async fn download(
+    sftp: &SftpSession,
+    watch: &InboxWatch,
+    name: &str,
+) -> Result<(PathBuf, bool), String> {
+    use tokio::io::AsyncWriteExt;
+
+    let local_dir = PathBuf::from(&watch.local_dir);
+    tokio::fs::create_dir_all(&local_dir)
+        .await
+        .map_err(|e| format!("{}: {}", local_dir.display(), e))?;
+    let remote_path = join_remote(&watch.remote_dir, name);
+    let part = local_dir.join(format!(".{}{}", name, PART_SUFFIX));
+    let result = async {
+        let mut source = sftp
+            .open(&remote_path)
+            .await
+            .map_err(|e| format!("{}: {}", remote_path, e))?;
+        let mut dest = tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| format!("{}: {}", part.display(), e))?;
+        tokio::io::copy(&mut source, &mut dest)
+            .await
+            .map_err(|e| format!("{}: {}", remote_path, e))?;
+        dest.flush().await.map_err(|e| e.to_string())?;
+        dest.sync_all().await.map_err(|e| e.to_string())
+    }
+    .await;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(e);
+    }
+
+    let existing = local_dir.join(name);
+    let part_for_hash = part.clone();
+    let duplicate = existing.is_file()
+        && tokio::task::spawn_blocking(move || {
+            Ok::<_, std::io::Error>(file_sha256(&existing)? == file_sha256(&part_for_hash)?)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .unwrap_or(false);
+    if duplicate {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Ok((local_dir.join(name), true));
+    }
+    let target = free_path(&local_dir, name);
+    tokio::fs::rename(&part, &target)
+        .await
+        .map_err(|e| format!("{}: {}", target.display(), e))?;
+    Ok((target, false))
+}
+
+async fn poll(app: &AppHandle, state: &AppState, watch: InboxWatch) {
+    let sftp = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&watch.connection_id)
+            .and_then(|c| c.sftp_session.clone())
+    };
+    let mut downloaded = Vec::new();
+    let outcome = match sftp {
+        None => None,
+        Some(sftp) => Some(
+            async {
+                let listing = list_remote(&sftp, &watch).await?;
+                for (name, stamp) in state.inbox_manager.ready_files(&watch, &listing) {
+                    let (local, duplicate) = download(&sftp, &watch, &name).await?;
+                    state.inbox_manager.mark_seen(&watch.id, &name, stamp);
+                    let _ = app.emit(
+                        "inbox:downloaded",
+                        InboxDownload {
+                            watch_id: watch.id.clone(),
+                            connection_id: watch.connection_id.clone(),
+                            remote_path: join_remote(&watch.remote_dir, &name),
+                            local_path: local.to_string_lossy().to_string(),
+                            size: stamp.size,
+                            duplicate,
+                        },
+                    );
+                    if !duplicate {
+                        downloaded.push(name);
+                    }
+                }
+                Ok(())
+            }
+            .await,
+        ),
+    };
+    if let Some(Err(error)) = &outcome {
+        crate::log_error!("[INBOX] '{}': {}", watch.name, error);
+    }
+    if let Some(status) = state
+        .inbox_manager
+        .finish(&watch, outcome, &downloaded, now_ms())
+    {
+        let _ = app.emit("inbox:status", &status);
+    }
+}
+
+pub fn spawn_inbox_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = app.try_state::<AppState>() else {
+                continue;
+            };
+            let Ok(watches) = state.inbox_manager.list() else {
+                continue;
+            };
+            for watch in state.inbox_manager.take_due(&watches, now_ms()) {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(state) = app.try_state::<AppState>() {
+                        poll(&app, &state, watch).await;
+                    }
+                });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(skip_existing: bool) -> InboxWatch {
+        InboxWatch {
+            id: "w1".to_string(),
+            connection_id: "c1".to_string(),
+            name: "Reports".to_string(),
+            remote_dir: "/srv/exports".to_string(),
+            local_dir: "/tmp/reports".to_string(),
+            pattern: Some("*.csv".to_string()),
+            interval_secs: 30,
+            enabled: true,
+            skip_existing,
+        }
+    }
+
+    fn stamp(size: u64, mtime: u32) -> FileStamp {
+        FileStamp { size, mtime }
+    }
+
+    #[test]
+    fn downloads_a_file_once_it_stops_changing() {
+        let watch = watch(true);
+        let mut seen = WatchSeen::default();
+        let mut pending = HashMap::new();
+        let old = ("old.csv".to_string(), stamp(10, 1));
+        assert!(plan(&watch, &[old.clone()], &mut seen, &mut pending).is_empty());
+
+        let growing = ("new.csv".to_string(), stamp(5, 2));
+        let listing = [old.clone(), growing.clone()];
+        assert!(plan(&watch, &listing, &mut seen, &mut pending).is_empty());
+        let grown = ("new.csv".to_string(), stamp(50, 3));
+        let listing = [old.clone(), grown.clone()];
+        assert!(plan(&watch, &listing, &mut seen, &mut pending).is_empty());
+        assert_eq!(
+            plan(&watch, &listing, &mut seen, &mut pending),
+            [grown.clone()]
+        );
+        seen.files.insert(grown.0.clone(), grown.1);
+        assert!(plan(&watch, &listing, &mut seen, &mut pending).is_empty());
+    }
+
+    #[test]
+    fn existing_files_are_fetched_when_not_skipped() {
+        let watch = watch(false);
+        let mut seen = WatchSeen::default();
+        let mut pending = HashMap::new();
+        let old = [("old.csv".to_string(), stamp(10, 1))];
+        assert!(plan(&watch, &old, &mut seen, &mut pending).is_empty());
+        assert_eq!(plan(&watch, &old, &mut seen, &mut pending), old);
+    }
+
+    #[test]
+    fn skips_hidden_and_unmatched_names() {
+        let watch = watch(true);
+        assert!(is_candidate(&watch, "report.csv"));
+        assert!(!is_candidate(&watch, ".report.csv"));
+        assert!(!is_candidate(&watch, "report.txt"));
+        let any = InboxWatch {
+            pattern: None,
+            ..watch
+        };
+        assert!(is_candidate(&any, "report.txt"));
+    }
+
+    #[test]
+    fn free_path_numbers_taken_names() {
+        let dir = std::env::temp_dir().join(format!("zync-inbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert_eq!(free_path(&dir, "a.csv"), dir.join("a.csv"));
+        std::fs::write(dir.join("a.csv"), b"1").unwrap();
+        std::fs::write(dir.join("a (1).csv"), b"2").unwrap();
+        assert_eq!(free_path(&dir, "a.csv"), dir.join("a (2).csv"));
+        std::fs::write(dir.join("README"), b"3").unwrap();
+        assert_eq!(free_path(&dir, "README"), dir.join("README (1)"));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,323 @@
+//! Lines typed into each terminal, for up-arrow recall in the AI bar and re-run.
+//!
+//! `PtyManager::write` feeds keystrokes here and the output readers feed what
+//! the terminal prints. A line is rebuilt from printable input, backspace and
+//! Ctrl-U and kept when Enter is pressed. Nothing is kept while the terminal is
+//! in secure entry: the current output line is a password, passphrase or
+//! one-time-code prompt and stays one while the user types, i.e. the remote
+//! side has echo off. Lines edited with keys the backend cannot follow (arrows,
+//! Tab completion, history search) are dropped rather than recorded wrong.
+//! History is in memory only and goes away with the terminal.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Lines kept per terminal; the oldest go first.
+const MAX_LINES: usize = 500;
+const MAX_LINE_BYTES: usize = 4096;
+/// Tail of the current output line kept for prompt detection.
+const PROMPT_TAIL_CHARS: usize = 160;
+
+/// Words that mark a prompt for hidden input when the line ends with `:`.
+const SECURE_PROMPT_WORDS: &[&str] = &[
+    "password",
+    "passphrase",
+    "passcode",
+    "pin",
+    "verification code",
+    "one-time",
+    "otp",
+    "token",
+    "secret",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputLine {
+    pub text: String,
+    /// Unix milliseconds when Enter was pressed.
+    pub entered_at: u64,
+}
+
+#[derive(Default)]
+struct TerminalInput {
+    lines: VecDeque<InputLine>,
+    /// Line being typed.
+    current: String,
+    /// The current line was edited in a way that cannot be reconstructed.
+    tainted: bool,
+    /// Last visible characters of the current output line.
+    output_tail: String,
+    /// Inside an escape sequence of the output stream.
+    in_escape: bool,
+    /// Inside an OSC string (window title, shell integration marks).
+    in_osc: bool,
+    /// The output line is a secure prompt that nothing typed has been echoed to.
+    secure: bool,
+}
+
+/// Per-terminal input history keyed by `term_id`.
+#[derive(Clone, Default)]
+pub struct InputHistories(Arc<Mutex<HashMap<String, TerminalInput>>>);
+
+impl InputHistories {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn input(&self, term_id: &str, data: &str) {
+        if data.is_empty() {
+            return;
+        }
+        let Ok(mut terminals) = self.0.lock() else {
+            return;
+        };
+        let terminal = terminals.entry(term_id.to_string()).or_default();
+        let data = strip_paste_markers(data);
+        let mut chars = data.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' | '\n' => {
+                    terminal.submit();
+                    // A pasted CRLF is one line end.
+                    if c == '\r' {
+                        chars.next_if_eq(&'\n');
+                    }
+                }
+                '\x7f' | '\x08' => {
+                    terminal.current.pop();
+                }
+                // Ctrl-U clears the line; Ctrl-C abandons it (and any prompt).
+                '\x15' => terminal.reset_line(),
+                '\x03' => {
+                    terminal.reset_line();
+                    terminal.secure = false;
+                }
+                '\x1b' => {
+                    terminal.tainted = true;
+                    // Skip the rest of a CSI / SS3 sequence.
+                    if chars.next_if(|c| *c == '[' || *c == 'O').is_some() {
+                        while chars.next_if(|c| !('@'..='~').contains(c)).is_some() {}
+                        chars.next();
+                    }
+                }
+                c if c.is_control() => terminal.tainted = true,
+                c => {
+                    if terminal.current.len() < MAX_LINE_BYTES {
+                        terminal.current.push(c);
+                    } else {
+                        terminal.tainted = true;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn output(&self, term_id: &str, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let Ok(mut terminals) = self.0.lock() else {
+            return;
+        };
+        let terminal = terminals.entry(term_id.to_string()).or_default();
+        for c in String::from_utf8_lossy(data).chars() {
+            if terminal.in_osc {
+                // Ends at BEL or at the ESC of an ST.
+                if c == '\x07' || c == '\x1b' {
+                    terminal.in_osc = false;
+                    terminal.in_escape = c == '\x1b';
+                }
+                continue;
+            }
+            if terminal.in_escape {
+                if c == ']' {
+                    terminal.in_osc = true;
+                    terminal.in_escape = false;
+                } else if ('@'..='~').contains(&c) && c != '[' {
+                    // Ends CSI sequences and simple two-byte escapes alike.
+                    terminal.in_escape = false;
+                }
+                continue;
+            }
+            match c {
+                '\x1b' => terminal.in_escape = true,
+                '\r' | '\n' => terminal.output_tail.clear(),
+                c if c.is_control() => {}
+                c => {
+                    if terminal.output_tail.chars().count() >= PROMPT_TAIL_CHARS {
+                        terminal.output_tail.remove(0);
+                    }
+                    terminal.output_tail.push(c);
+                }
+            }
+        }
+        // Echoed input moves the line off the prompt, so this also ends secure
+        // entry at prompts that do echo.
+        terminal.secure = is_secure_prompt(&terminal.output_tail);
+    }
+
+    /// Most recent lines first.
+    pub fn lines(&self, term_id: &str, limit: usize) -> Vec<InputLine> {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|terminals| {
+                terminals
+                    .get(term_id)
+                    .map(|terminal| terminal.lines.iter().rev().take(limit).cloned().collect())
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, term_id: &str) {
+        if let Ok(mut terminals) = self.0.lock() {
+            terminals.remove(term_id);
+        }
+    }
+}
+
+impl TerminalInput {
+    fn reset_line(&mut self) {
+        self.current.clear();
+        self.tainted = false;
+    }
+
+    fn submit(&mut self) {
+        let line = std::mem::take(&mut self.current);
+        let keep = !self.secure && !self.tainted;
+        self.tainted = false;
+        self.secure = false;
+        // Entering a line moves the cursor off a prompt even before output arrives.
+        self.output_tail.clear();
+
+        let text = line.trim();
+        if !keep
+            || text.is_empty()
+            || line.starts_with(' ')
+            || !crate::ghost::token::history_entry_safe_to_store(text)
+        {
+            return;
+        }
+        if self.lines.back().is_some_and(|last| last.text == text) {
+            self.lines.pop_back();
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(InputLine {
+            text: text.to_string(),
+            entered_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        });
+    }
+}
+
+fn strip_paste_markers(data: &str) -> std::borrow::Cow<'_, str> {
+    if data.contains("\x1b[200~") || data.contains("\x1b[201~") {
+        data.replace("\x1b[200~", "")
+            .replace("\x1b[201~", "")
+            .into()
+    } else {
+        data.into()
+    }
+}
+
+/// Whether an output line asks for input the terminal will not echo, such as
+/// `Password:`, `[sudo] password for ann:` or `Enter passphrase for key '~/.ssh/id':`.
+fn is_secure_prompt(line: &str) -> bool {
+    let line = line.trim_end().to_lowercase();
+    let Some(prompt) = line.strip_suffix(':') else {
+        return false;
+    };
+    SECURE_PROMPT_WORDS.iter().any(|word| {
+        prompt.match_indices(word).any(|(at, _)| {
+            let before = prompt[..at].chars().next_back();
+            let after = prompt[at + word.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(history: &InputHistories, term_id: &str) -> Vec<String> {
+        history
+            .lines(term_id, usize::MAX)
+            .into_iter()
+            .map(|line| line.text)
+            .collect()
+    }
+
+    #[test]
+    fn rebuilds_lines_from_keystrokes() {
+        let history = InputHistories::new();
+        history.input("t1", "ls -la\r");
+        history.input("t1", "gti");
+        history.input("t1", "\x7f\x7f\x7fgit status\r");
+        history.input("t1", "rm -rf /tmp/x\x15echo hi\r");
+        history.input("t1", "echo hi\r");
+        history.input("t1", " hidden\r");
+        assert_eq!(texts(&history, "t1"), ["echo hi", "git status", "ls -la"]);
+        assert!(texts(&history, "t2").is_empty());
+    }
+
+    #[test]
+    fn drops_lines_edited_with_untracked_keys() {
+        let history = InputHistories::new();
+        history.input("t1", "git sta\t\r");
+        history.input("t1", "\x1b[A\r");
+        history.input("t1", "\x1b[200~make build\x1b[201~\r");
+        assert_eq!(texts(&history, "t1"), ["make build"]);
+    }
+
+    #[test]
+    fn skips_input_typed_at_secure_prompts() {
+        let history = InputHistories::new();
+        history.input("t1", "sudo apt update\r");
+        history.output("t1", b"sudo apt update\r\n[sudo] password for ann: ");
+        history.input("t1", "hunter2\r");
+        history.output("t1", b"\r\nHit:1 http://deb.debian.org\r\n$ ");
+        history.input("t1", "ssh-add\r");
+        history.output(
+            "t1",
+            b"\x1b[1mEnter passphrase for /home/ann/.ssh/id_ed25519:\x1b[0m ",
+        );
+        history.input("t1", "correct horse\x03");
+        history.input("t1", "whoami\r");
+        assert_eq!(
+            texts(&history, "t1"),
+            ["whoami", "ssh-add", "sudo apt update"]
+        );
+    }
+
+    #[test]
+    fn recognizes_secure_prompts() {
+        assert!(is_secure_prompt("Password: "));
+        assert!(is_secure_prompt("Verification code:"));
+        assert!(is_secure_prompt("Enter PIN for 'YubiKey':"));
+        assert!(!is_secure_prompt("Spinning up:"));
+        assert!(!is_secure_prompt("$ cat password.txt"));
+        assert!(!is_secure_prompt("user@host:~$ "));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let history = InputHistories::new();
+        for i in 0..MAX_LINES + 10 {
+            history.input("t1", &format!("echo {i}\r"));
+        }
+        let lines = history.lines("t1", usize::MAX);
+        assert_eq!(lines.len(), MAX_LINES);
+        assert_eq!(lines[0].text, format!("echo {}", MAX_LINES + 9));
+        assert_eq!(history.lines("t1", 2).len(), 2);
+        history.remove("t1");
+        assert!(history.lines("t1", 10).is_empty());
+    }
+}
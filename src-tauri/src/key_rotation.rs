@@ -0,0 +1,276 @@
+//! Building blocks for `ssh_rotate_key`.
+//!
+//! Keys are generated with the local `ssh-keygen` so the files on disk are
+//! exactly what OpenSSH itself would write. Deployment mirrors `ssh-copy-id`:
+//! the public key is appended to `~/.ssh/authorized_keys` over the existing
+//! session, only if it is not already there.
+//!
+//! Passphrases never go on `ssh-keygen`'s command line, where any local user
+//! could read them. `ssh-keygen` asks for them through `SSH_ASKPASS`, which is
+//! this executable in askpass mode ([`run_askpass`]) printing the passphrase
+//! handed to it in the child's environment.
+
+use crate::ai::tool_exec_support::shell_quote;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Ed25519,
+    Ecdsa,
+    Rsa,
+}
+
+impl KeyType {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(Self::Ed25519),
+            "ecdsa" => Ok(Self::Ecdsa),
+            "rsa" => Ok(Self::Rsa),
+            other => Err(anyhow!("Unsupported key type '{}'", other)),
+        }
+    }
+
+    fn keygen_args(&self) -> &'static [&'static str] {
+        match self {
+            Self::Ed25519 => &["-t", "ed25519"],
+            Self::Ecdsa => &["-t", "ecdsa", "-b", "521"],
+            Self::Rsa => &["-t", "rsa", "-b", "4096"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRotationResult {
+    pub new_key_path: String,
+    pub public_key: String,
+    pub old_key_removed: bool,
+    /// Non-fatal problems, e.g. the old key could not be removed from the server.
+    pub warnings: Vec<String>,
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix('~') {
+        if let Some(home) = dirs::home_dir() {
+            return PathBuf::from(format!("{}{}", home.to_string_lossy(), rest));
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// `<dir of old key>/zync_<label>_<stamp>`, so rotated keys sit next to the one they replace.
+pub fn rotated_key_path(old_key: &Path, label: &str, stamp: &str) -> PathBuf {
+    let label: String = label
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let label = label.trim_matches('_');
+    let name = if label.is_empty() {
+        format!("zync_{}", stamp)
+    } else {
+        format!("zync_{}_{}", label, stamp)
+    };
+    old_key
+        .parent()
+        .map(|dir| dir.join(&name))
+        .unwrap_or_else(|| PathBuf::from(&name))
+}
+
+/// The base64 blob of an OpenSSH public key line (`type blob [comment]`).
+pub fn key_blob(public_key: &str) -> Option<&str> {
+    let blob = public_key.split_whitespace().nth(1)?;
+    blob.chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c))
+        .then_some(blob)
+}
+
+/// Appends `public_key` to `authorized_keys` unless it is already present.
+pub fn install_command(public_key: &str) -> String {
+    let key = shell_quote(public_key.trim());
+    format!(
+        "umask 077; mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys && \
+         (grep -qxF -- {key} ~/.ssh/authorized_keys || printf '%s\\n' {key} >> ~/.ssh/authorized_keys)",
+        key = key
+    )
+}
+
+/// Drops every `authorized_keys` line carrying `blob`. The filtered copy replaces
+/// the file only when grep succeeded (exit 1 just means no line was kept), so a
+/// read error can never leave the file truncated.
+pub fn remove_command(blob: &str) -> String {
+    format!(
+        "f=~/.ssh/authorized_keys; t=$(mktemp \"$f.XXXXXX\") || exit 1; \
+         grep -vF -- {blob} \"$f\" > \"$t\"; rc=$?; \
+         if [ $rc -le 1 ]; then mv -f \"$t\" \"$f\"; else rm -f \"$t\"; exit $rc; fi",
+        blob = shell_quote(blob)
+    )
+}
+
+const ASKPASS_ENV: &str = "ZYNC_ASKPASS_SECRET";
+
+/// When started as `ssh-keygen`'s askpass program, prints the passphrase and
+/// returns the exit code.
+pub fn run_askpass() -> Option<i32> {
+    use std::io::Write;
+    let secret = std::env::var(ASKPASS_ENV).ok()?;
+    let mut stdout = std::io::stdout();
+    Some(match writeln!(stdout, "{}", secret) {
+        Ok(()) => 0,
+        Err(_) => 1,
+    })
+}
+
+/// Hands `passphrase` to `ssh-keygen`: an empty one through `flag` (`-N`/`-P`),
+/// anything else through the askpass program so it stays off the command line.
+fn pass_passphrase(
+    command: &mut tokio::process::Command,
+    flag: &str,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    match passphrase.filter(|p| !p.is_empty()) {
+        None => {
+            command.arg(flag).arg("");
+        }
+        Some(passphrase) => {
+            let exe = std::env::current_exe()
+                .map_err(|e| anyhow!("Failed to locate the askpass helper: {}", e))?;
+            command
+                .env("SSH_ASKPASS", exe)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env(ASKPASS_ENV, passphrase);
+        }
+    }
+    Ok(())
+}
+
+/// Runs `ssh-keygen` to create `path` (and `path.pub`); returns the public key line.
+pub async fn generate(
+    path: &Path,
+    key_type: KeyType,
+    passphrase: Option<&str>,
+    comment: &str,
+) -> Result<String> {
+    if path.exists() {
+        return Err(anyhow!(
+            "Refusing to overwrite existing key {}",
+            path.display()
+        ));
+    }
+    let mut command = tokio::process::Command::new("ssh-keygen");
+    command.args(key_type.keygen_args()).arg("-q");
+    pass_passphrase(&mut command, "-N", passphrase)?;
+    let output = command
+        .arg("-C")
+        .arg(comment)
+        .arg("-f")
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| anyhow!("Failed to run ssh-keygen: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ssh-keygen failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let public = tokio::fs::read_to_string(public_path(path)).await?;
+    Ok(public.trim().to_string())
+}
+
+fn public_path(key: &Path) -> PathBuf {
+    let mut name = key.as_os_str().to_os_string();
+    name.push(".pub");
+    PathBuf::from(name)
+}
+
+/// Public half of an existing key: the `.pub` file when present, else derived with `ssh-keygen -y`.
+pub async fn public_key_of(path: &Path, passphrase: Option<&str>) -> Result<String> {
+    if let Ok(public) = tokio::fs::read_to_string(public_path(path)).await {
+        if key_blob(&public).is_some() {
+            return Ok(public.trim().to_string());
+        }
+    }
+    // ssh-keygen cannot read a key sealed at rest.
+    let key = crate::vault::at_rest::PlaintextKey::open(path)
+        .map_err(|e| anyhow!("Could not read {}: {}", path.display(), e))?;
+    let mut command = tokio::process::Command::new("ssh-keygen");
+    command.arg("-y");
+    pass_passphrase(&mut command, "-P", passphrase)?;
+    let output = command
+        .arg("-f")
+        .arg(key.path())
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| anyhow!("Failed to run ssh-keygen: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Could not read public key of {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Deletes a generated key pair after a failed rotation.
+pub fn discard(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(public_path(path));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGt9kzF4n0uYd3aP+8sQ1lFz2b7p3yQJfX8m0k1cY9Ad me@laptop";
+
+    #[test]
+    fn extracts_key_blob() {
+        assert_eq!(
+            key_blob(PUBLIC),
+            Some("AAAAC3NzaC1lZDI1NTE5AAAAIGt9kzF4n0uYd3aP+8sQ1lFz2b7p3yQJfX8m0k1cY9Ad")
+        );
+        assert_eq!(key_blob("ssh-ed25519"), None);
+        assert_eq!(key_blob("ssh-ed25519 not;base64"), None);
+    }
+
+    #[test]
+    fn rotated_path_sits_next_to_old_key() {
+        assert_eq!(
+            rotated_key_path(Path::new("/home/me/.ssh/id_ed25519"), "Prod DB", "20261016"),
+            PathBuf::from("/home/me/.ssh/zync_prod_db_20261016")
+        );
+        assert_eq!(
+            rotated_key_path(Path::new("/keys/a"), "---", "1"),
+            PathBuf::from("/keys/zync_1")
+        );
+    }
+
+    #[test]
+    fn commands_quote_keys() {
+        let install = install_command(PUBLIC);
+        assert!(install.contains("grep -qxF -- 'ssh-ed25519 AAAA"));
+        assert!(install.contains(">> ~/.ssh/authorized_keys"));
+        let remove = remove_command(key_blob(PUBLIC).unwrap());
+        assert!(remove.contains("grep -vF -- 'AAAAC3Nza"));
+        assert!(remove.contains("if [ $rc -le 1 ]; then mv -f"));
+        assert!(!remove.contains("cat "));
+    }
+
+    #[test]
+    fn parses_key_types() {
+        assert_eq!(KeyType::parse("ED25519").unwrap(), KeyType::Ed25519);
+        assert!(KeyType::parse("dsa").is_err());
+    }
+}
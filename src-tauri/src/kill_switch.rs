@@ -0,0 +1,54 @@
+//! App-wide network kill switch ("work offline").
+//!
+//! While engaged, new SSH connections (including reconnects and connection
+//! tests), AI provider requests, plugin downloads and signatures, cloud sync,
+//! cloud and mesh inventory imports and LAN discovery are refused.
+//! Engaging it also tears down what is already open; see
+//! `commands::network_kill_switch_set`. The state is stored in settings.json as
+//! `networkKillSwitch` so it survives a restart.
+
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const SETTINGS_KEY: &str = "networkKillSwitch";
+pub const OFFLINE_ERROR: &str = "Network access is disabled while Work Offline is on";
+
+static ENGAGED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_engaged() -> bool {
+    ENGAGED.load(Ordering::SeqCst)
+}
+
+/// Returns whether the value changed.
+pub fn set_engaged(engaged: bool) -> bool {
+    ENGAGED.swap(engaged, Ordering::SeqCst) != engaged
+}
+
+/// Fails with [`OFFLINE_ERROR`] while the kill switch is engaged.
+pub fn ensure_online() -> Result<(), String> {
+    if is_engaged() {
+        Err(OFFLINE_ERROR.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+pub fn engaged_in_settings(settings: &Value) -> bool {
+    settings
+        .get(SETTINGS_KEY)
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_flag_from_settings() {
+        assert!(engaged_in_settings(&json!({ "networkKillSwitch": true })));
+        assert!(!engaged_in_settings(&json!({ "networkKillSwitch": "yes" })));
+        assert!(!engaged_in_settings(&json!({})));
+    }
+}
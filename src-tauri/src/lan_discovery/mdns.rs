@@ -0,0 +1,281 @@
+//! One-shot multicast DNS browsing of `_ssh._tcp.local` (RFC 6762 §5.1).
+//!
+//! Queries go out from an ephemeral port, so responders answer by unicast
+//! and nothing has to bind 5353, which the system responder usually holds.
+//! Answers may be split over several packets; [`ServiceCache`] collects PTR,
+//! SRV and address records until an instance is complete.
+
+use anyhow::{anyhow, bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub const SERVICE: &str = "_ssh._tcp.local";
+pub const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+pub const MDNS_PORT: u16 = 5353;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_SRV: u16 = 33;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+/// Compression pointers followed before a name is rejected as a loop.
+const MAX_POINTERS: usize = 16;
+
+/// A fully resolved `_ssh._tcp` instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshService {
+    /// Instance label, usually the machine's name.
+    pub name: String,
+    /// `.local` host name from the SRV record.
+    pub hostname: String,
+    pub port: u16,
+    pub address: IpAddr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Record {
+    Ptr(String),
+    Srv { port: u16, target: String },
+    Address(IpAddr),
+    Other,
+}
+
+/// PTR query for [`SERVICE`].
+pub fn browse_query() -> Vec<u8> {
+    let mut packet = vec![0u8; 12];
+    packet[5] = 1; // QDCOUNT
+    for label in SERVICE.split('.') {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+fn read_u16(packet: &[u8], offset: usize) -> Result<u16> {
+    packet
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("Truncated mDNS packet"))
+}
+
+/// Reads a possibly compressed name; returns it (lowercase, no trailing dot)
+/// and the offset after it.
+fn read_name(packet: &[u8], mut offset: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let len = *packet
+            .get(offset)
+            .ok_or_else(|| anyhow!("Truncated mDNS name"))? as usize;
+        if len & 0xC0 == 0xC0 {
+            pointers += 1;
+            if pointers > MAX_POINTERS {
+                bail!("mDNS name compression loop");
+            }
+            end.get_or_insert(offset + 2);
+            offset = (read_u16(packet, offset)? & 0x3FFF) as usize;
+            continue;
+        }
+        if len == 0 {
+            let end = end.unwrap_or(offset + 1);
+            return Ok((labels.join(".").to_lowercase(), end));
+        }
+        let label = packet
+            .get(offset + 1..offset + 1 + len)
+            .ok_or_else(|| anyhow!("Truncated mDNS label"))?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        offset += 1 + len;
+    }
+}
+
+/// Every answer, authority and additional record of a response.
+fn parse_records(packet: &[u8]) -> Result<Vec<(String, Record)>> {
+    let flags = read_u16(packet, 2)?;
+    if flags & 0x8000 == 0 {
+        return Ok(Vec::new()); // another host's query
+    }
+    let questions = read_u16(packet, 4)?;
+    let records = read_u16(packet, 6)? as usize
+        + read_u16(packet, 8)? as usize
+        + read_u16(packet, 10)? as usize;
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(packet, offset)?.1 + 4;
+    }
+    let mut parsed = Vec::with_capacity(records);
+    for _ in 0..records {
+        let (name, after) = read_name(packet, offset)?;
+        let rtype = read_u16(packet, after)?;
+        let length = read_u16(packet, after + 8)? as usize;
+        let start = after + 10;
+        let data = packet
+            .get(start..start + length)
+            .ok_or_else(|| anyhow!("Truncated mDNS record"))?;
+        let record = match (rtype, length) {
+            (TYPE_PTR, _) => Record::Ptr(read_name(packet, start)?.0),
+            (TYPE_SRV, 7..) => Record::Srv {
+                port: read_u16(data, 4)?,
+                target: read_name(packet, start + 6)?.0,
+            },
+            (TYPE_A, 4) => Record::Address(IpAddr::V4(Ipv4Addr::new(
+                data[0], data[1], data[2], data[3],
+            ))),
+            (TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                Record::Address(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => Record::Other,
+        };
+        parsed.push((name, record));
+        offset = start + length;
+    }
+    Ok(parsed)
+}
+
+/// Records seen so far, across responses.
+#[derive(Debug, Default)]
+pub struct ServiceCache {
+    instances: HashSet<String>,
+    services: HashMap<String, (u16, String)>,
+    addresses: HashMap<String, Vec<IpAddr>>,
+    reported: HashSet<(String, IpAddr)>,
+}
+
+impl ServiceCache {
+    /// Takes in one response; returns instances that became resolvable, one
+    /// per address. Unparseable packets are ignored.
+    pub fn ingest(&mut self, packet: &[u8]) -> Vec<SshService> {
+        let Ok(records) = parse_records(packet) else {
+            return Vec::new();
+        };
+        for (name, record) in records {
+            match record {
+                Record::Ptr(instance) if name == SERVICE => {
+                    self.instances.insert(instance);
+                }
+                Record::Srv { port, target } => {
+                    self.services.insert(name, (port, target));
+                }
+                Record::Address(address) => {
+                    let addresses = self.addresses.entry(name).or_default();
+                    if !addresses.contains(&address) {
+                        addresses.push(address);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut found = Vec::new();
+        for instance in &self.instances {
+            let Some((port, target)) = self.services.get(instance) else {
+                continue;
+            };
+            for address in self.addresses.get(target).into_iter().flatten() {
+                if self.reported.insert((instance.clone(), *address)) {
+                    found.push(SshService {
+                        name: instance
+                            .strip_suffix(&format!(".{}", SERVICE))
+                            .unwrap_or(instance)
+                            .to_string(),
+                        hostname: target.clone(),
+                        port: *port,
+                        address: *address,
+                    });
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push_name(packet: &mut Vec<u8>, name: &str) {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+    }
+
+    fn push_record(packet: &mut Vec<u8>, name: &[u8], rtype: u16, data: &[u8]) {
+        packet.extend_from_slice(name);
+        packet.extend_from_slice(&rtype.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&120u32.to_be_bytes());
+        packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        packet.extend_from_slice(data);
+    }
+
+    fn response(records: usize) -> Vec<u8> {
+        vec![0, 0, 0x84, 0, 0, 0, 0, records as u8, 0, 0, 0, 0]
+    }
+
+    #[test]
+    fn builds_a_ptr_query() {
+        let query = browse_query();
+        assert_eq!(&query[..6], &[0, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            &query[12..],
+            b"\x04_ssh\x04_tcp\x05local\x00\x00\x0c\x00\x01"
+        );
+    }
+
+    #[test]
+    fn resolves_instances_across_packets() {
+        // PTR _ssh._tcp.local -> nas._ssh._tcp.local (compressed against the owner).
+        let mut first = response(2);
+        let owner = first.len();
+        push_name(&mut first, SERVICE);
+        let mut ptr = vec![3];
+        ptr.extend_from_slice(b"NAS");
+        ptr.extend_from_slice(&[0xC0, owner as u8]);
+        let ptr_data_at = first.len() + 10;
+        first.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        first.extend_from_slice(&CLASS_IN.to_be_bytes());
+        first.extend_from_slice(&120u32.to_be_bytes());
+        first.extend_from_slice(&(ptr.len() as u16).to_be_bytes());
+        first.extend_from_slice(&ptr);
+        // SRV nas._ssh._tcp.local -> port 2222 on nas.local
+        let mut srv = vec![0, 0, 0, 0, 0x08, 0xAE];
+        push_name(&mut srv, "nas.local");
+        push_record(&mut first, &[0xC0, ptr_data_at as u8], TYPE_SRV, &srv);
+
+        let mut cache = ServiceCache::default();
+        assert!(cache.ingest(&first).is_empty());
+
+        let mut second = response(1);
+        let mut name = Vec::new();
+        push_name(&mut name, "nas.local");
+        push_record(&mut second, &name, TYPE_A, &[192, 168, 1, 20]);
+        let found = cache.ingest(&second);
+        assert_eq!(
+            found,
+            [SshService {
+                name: "nas".to_string(),
+                hostname: "nas.local".to_string(),
+                port: 2222,
+                address: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20)),
+            }]
+        );
+        // Repeated answers are not reported again.
+        assert!(cache.ingest(&second).is_empty());
+    }
+
+    #[test]
+    fn ignores_queries_and_pointer_loops() {
+        let mut cache = ServiceCache::default();
+        assert!(cache.ingest(&browse_query()).is_empty());
+        let mut looped = response(1);
+        looped.extend_from_slice(&[0xC0, 12]);
+        assert!(parse_records(&looped).is_err());
+    }
+}
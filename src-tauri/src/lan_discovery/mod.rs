@@ -0,0 +1,302 @@
+//! SSH servers on the local network, for adding them as connections.
+//!
+//! Two sources feed one result list: an mDNS browse for `_ssh._tcp` services
+//! (hosts that advertise themselves, with their names) and an optional TCP
+//! sweep of a CIDR (hosts that don't). A host found both ways is reported once
+//! per change, keyed by address and port, so the UI can merge updates. Only
+//! one discovery runs at a time; starting another stops the previous one.
+
+pub mod mdns;
+pub mod scan;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+pub const DEFAULT_MDNS_SECONDS: u64 = 8;
+const MAX_MDNS_SECONDS: u64 = 60;
+pub const DEFAULT_CONCURRENCY: usize = 64;
+const MAX_CONCURRENCY: usize = 256;
+/// Seconds after the start at which the mDNS query is (re)sent.
+const MDNS_QUERY_AT: &[u64] = &[0, 1, 3];
+
+/// The running discovery: its id and the task driving it.
+static ACTIVE: LazyLock<Mutex<Option<(String, tokio::task::AbortHandle)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiscoveryOptions {
+    /// Browse for `_ssh._tcp` mDNS services.
+    pub mdns: bool,
+    /// How long to wait for mDNS answers.
+    pub mdns_seconds: u64,
+    /// IPv4 network to sweep, e.g. `192.168.1.0/24`; no sweep when unset.
+    pub cidr: Option<String>,
+    /// Port probed by the sweep.
+    pub port: u16,
+    /// Connect timeout per probed address.
+    pub timeout_ms: u64,
+    /// Addresses probed at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            mdns: true,
+            mdns_seconds: DEFAULT_MDNS_SECONDS,
+            cidr: None,
+            port: 22,
+            timeout_ms: scan::DEFAULT_CONNECT_TIMEOUT.as_millis() as u64,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    pub fn mdns_duration(&self) -> Duration {
+        Duration::from_secs(self.mdns_seconds.clamp(1, MAX_MDNS_SECONDS))
+    }
+}
+
+/// Payload of `discovery:host`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredHost {
+    pub discovery_id: String,
+    /// `address:port`; stable across updates for the same host.
+    pub id: String,
+    pub address: String,
+    pub port: u16,
+    /// mDNS instance name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `.local` host name advertised over mDNS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// SSH identification string, e.g. `SSH-2.0-OpenSSH_9.6`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    /// `mdns` and/or `scan`.
+    pub sources: Vec<String>,
+}
+
+/// Payload of `discovery:done`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverySummary {
+    pub discovery_id: String,
+    pub hosts: usize,
+    /// Sources that failed, e.g. no multicast route.
+    pub errors: Vec<String>,
+}
+
+/// What one source learned about a host.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sighting {
+    pub address: IpAddr,
+    pub port: u16,
+    pub source: &'static str,
+    pub name: Option<String>,
+    pub hostname: Option<String>,
+    pub banner: Option<String>,
+}
+
+/// Hosts found so far by one discovery.
+#[derive(Debug, Default)]
+pub struct HostTable {
+    discovery_id: String,
+    hosts: HashMap<String, DiscoveredHost>,
+}
+
+impl HostTable {
+    pub fn new(discovery_id: &str) -> Self {
+        Self {
+            discovery_id: discovery_id.to_string(),
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Merges a sighting; returns the host when it is new or changed.
+    pub fn merge(&mut self, sighting: Sighting) -> Option<DiscoveredHost> {
+        let id = SocketAddr::new(sighting.address, sighting.port).to_string();
+        let host = self
+            .hosts
+            .entry(id.clone())
+            .or_insert_with(|| DiscoveredHost {
+                discovery_id: self.discovery_id.clone(),
+                id,
+                address: sighting.address.to_string(),
+                port: sighting.port,
+                ..Default::default()
+            });
+        let before = host.clone();
+        host.name = host.name.take().or(sighting.name);
+        host.hostname = host.hostname.take().or(sighting.hostname);
+        host.banner = host.banner.take().or(sighting.banner);
+        if !host.sources.iter().any(|source| source == sighting.source) {
+            host.sources.push(sighting.source.to_string());
+        }
+        (before.sources.is_empty() || *host != before).then(|| host.clone())
+    }
+
+    /// Distinct hosts found.
+    pub fn count(&self) -> usize {
+        self.hosts.len()
+    }
+}
+
+/// Makes `discovery_id` the running discovery, stopping any previous one.
+pub fn register(discovery_id: &str, task: tokio::task::AbortHandle) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some((_, previous)) = active.replace((discovery_id.to_string(), task)) {
+            previous.abort();
+        }
+    }
+}
+
+/// Forgets `discovery_id` once it has finished by itself.
+pub fn unregister(discovery_id: &str) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        if active.as_ref().is_some_and(|(id, _)| id == discovery_id) {
+            *active = None;
+        }
+    }
+}
+
+/// Stops the running discovery; returns whether one was running.
+pub fn stop() -> bool {
+    let running = ACTIVE.lock().ok().and_then(|mut active| active.take());
+    match running {
+        Some((_, task)) => {
+            task.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Browses `_ssh._tcp` for `duration`, calling `found` for each service as it
+/// resolves. Only the default multicast interface is queried.
+pub async fn browse_mdns(
+    duration: Duration,
+    found: impl Fn(mdns::SshService),
+) -> std::io::Result<()> {
+    crate::kill_switch::ensure_online().map_err(std::io::Error::other)?;
+    let socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_multicast_ttl_v4(255)?;
+    let target = SocketAddrV4::new(mdns::MDNS_ADDR, mdns::MDNS_PORT);
+    let query = mdns::browse_query();
+    let start = Instant::now();
+    let deadline = start + duration;
+    let mut sends = MDNS_QUERY_AT
+        .iter()
+        .map(|secs| start + Duration::from_secs(*secs))
+        .filter(|at| *at < deadline)
+        .peekable();
+    let mut cache = mdns::ServiceCache::default();
+    let mut buf = vec![0u8; 9000];
+    loop {
+        while sends.next_if(|at| *at <= Instant::now()).is_some() {
+            socket.send_to(&query, target).await?;
+        }
+        let wake = sends.peek().copied().unwrap_or(deadline).min(deadline);
+        if Instant::now() >= deadline {
+            return Ok(());
+        }
+        if let Ok(received) = tokio::time::timeout_at(wake, socket.recv_from(&mut buf)).await {
+            let (len, _) = received?;
+            for service in cache.ingest(&buf[..len]) {
+                found(service);
+            }
+        }
+    }
+}
+
+/// Probes every address of `cidr` on `options.port`, calling `found` for
+/// each one that accepts a connection.
+pub async fn sweep(
+    cidr: &str,
+    options: &DiscoveryOptions,
+    found: impl Fn(SocketAddr, Option<String>),
+) -> Result<(), String> {
+    crate::kill_switch::ensure_online()?;
+    let addresses = scan::parse_cidr(cidr).map_err(|e| e.to_string())?;
+    let timeout = Duration::from_millis(options.timeout_ms.max(1));
+    let permits = Arc::new(tokio::sync::Semaphore::new(
+        options.concurrency.clamp(1, MAX_CONCURRENCY),
+    ));
+    let mut tasks = tokio::task::JoinSet::new();
+    for address in addresses {
+        let target = SocketAddr::from((address, options.port));
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            scan::probe(target, timeout)
+                .await
+                .map(|banner| (target, banner))
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(Some((target, banner))) = joined {
+            found(target, banner);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sighting(source: &'static str) -> Sighting {
+        Sighting {
+            address: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20)),
+            port: 22,
+            source,
+            name: None,
+            hostname: None,
+            banner: None,
+        }
+    }
+
+    #[test]
+    fn reports_hosts_once_per_change() {
+        let mut table = HostTable::new("d1");
+        let first = table
+            .merge(Sighting {
+                banner: Some("SSH-2.0-OpenSSH_9.6".to_string()),
+                ..sighting("scan")
+            })
+            .unwrap();
+        assert_eq!(first.id, "192.168.1.20:22");
+        assert_eq!(first.discovery_id, "d1");
+        assert_eq!(first.sources, ["scan"]);
+        assert!(table.merge(sighting("scan")).is_none());
+
+        let merged = table
+            .merge(Sighting {
+                name: Some("nas".to_string()),
+                hostname: Some("nas.local".to_string()),
+                ..sighting("mdns")
+            })
+            .unwrap();
+        assert_eq!(merged.name.as_deref(), Some("nas"));
+        assert_eq!(merged.banner.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(merged.sources, ["scan", "mdns"]);
+        assert_eq!(table.count(), 1);
+    }
+
+    #[test]
+    fn defaults_to_mdns_only() {
+        let options: DiscoveryOptions = serde_json::from_str("{}").unwrap();
+        assert!(options.mdns);
+        assert!(options.cidr.is_none());
+        assert_eq!(options.port, 22);
+    }
+}
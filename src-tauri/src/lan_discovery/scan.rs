@@ -0,0 +1,127 @@
+//! TCP sweep of an IPv4 CIDR for an open SSH port.
+//!
+//! Hosts are probed with a plain connect; when it succeeds the server's
+//! identification line (`SSH-2.0-OpenSSH_9.6`) is read so that other services
+//! listening on the port can be told apart.
+
+use anyhow::{anyhow, bail, Result};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+/// Largest sweep accepted (a /16).
+pub const MAX_HOSTS: u32 = 1 << 16;
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(600);
+const BANNER_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_BANNER: usize = 255;
+
+/// Parses `192.168.1.0/24` (a bare address is a /32) into the addresses to
+/// probe. Network and broadcast addresses are skipped below /31.
+pub fn parse_cidr(cidr: &str) -> Result<Vec<Ipv4Addr>> {
+    let cidr = cidr.trim();
+    let (address, prefix) = cidr.split_once('/').unwrap_or((cidr, "32"));
+    let address: Ipv4Addr = address
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid IPv4 network: {}", cidr))?;
+    let prefix: u32 = prefix
+        .trim()
+        .parse()
+        .ok()
+        .filter(|prefix| *prefix <= 32)
+        .ok_or_else(|| anyhow!("Invalid prefix length in {}", cidr))?;
+    let size = 1u64 << (32 - prefix);
+    if size > MAX_HOSTS as u64 {
+        bail!("{} is too large to scan (at most a /16)", cidr);
+    }
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    let network = u32::from(address) & mask;
+    let (first, last) = if prefix < 31 {
+        (network + 1, network + size as u32 - 2)
+    } else {
+        (network, network + (size as u32 - 1))
+    };
+    Ok((first..=last).map(Ipv4Addr::from).collect())
+}
+
+/// First line of an SSH identification string, if `data` starts with one.
+pub fn parse_banner(data: &[u8]) -> Option<String> {
+    let line = data.split(|b| *b == b'\n').next()?;
+    let line = String::from_utf8_lossy(line)
+        .trim_end_matches('\r')
+        .to_string();
+    line.starts_with("SSH-").then_some(line)
+}
+
+/// Connects to `address`; `None` when the port is closed or filtered,
+/// `Some(banner)` when it accepted, with the banner when the peer sent an
+/// SSH identification in time.
+pub async fn probe(address: SocketAddr, connect_timeout: Duration) -> Option<Option<String>> {
+    // Engaging the kill switch mid-sweep stops the remaining probes.
+    if crate::kill_switch::is_engaged() {
+        return None;
+    }
+    let mut stream = tokio::time::timeout(connect_timeout, TcpStream::connect(address))
+        .await
+        .ok()?
+        .ok()?;
+    let mut buf = [0u8; MAX_BANNER];
+    let mut len = 0;
+    // A silent or slow peer still counts as open, just without a banner.
+    let _ = tokio::time::timeout(BANNER_TIMEOUT, async {
+        while len < buf.len() && !buf[..len].contains(&b'\n') {
+            match stream.read(&mut buf[len..]).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => len += n,
+            }
+        }
+    })
+    .await;
+    Some(parse_banner(&buf[..len]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_networks_without_network_and_broadcast() {
+        let hosts = parse_cidr("192.168.1.77/30").unwrap();
+        assert_eq!(
+            hosts,
+            [
+                Ipv4Addr::new(192, 168, 1, 77),
+                Ipv4Addr::new(192, 168, 1, 78)
+            ]
+        );
+        assert_eq!(parse_cidr("10.0.0.0/24").unwrap().len(), 254);
+        assert_eq!(
+            parse_cidr("10.0.0.8").unwrap(),
+            [Ipv4Addr::new(10, 0, 0, 8)]
+        );
+        assert_eq!(parse_cidr("10.0.0.8/31").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rejects_bad_or_oversized_networks() {
+        assert!(parse_cidr("10.0.0.0/8").is_err());
+        assert!(parse_cidr("10.0.0.0/33").is_err());
+        assert!(parse_cidr("fe80::/64").is_err());
+        assert_eq!(parse_cidr("10.0.0.0/16").unwrap().len(), 65534);
+    }
+
+    #[test]
+    fn reads_ssh_identification() {
+        assert_eq!(
+            parse_banner(b"SSH-2.0-OpenSSH_9.6\r\n").as_deref(),
+            Some("SSH-2.0-OpenSSH_9.6")
+        );
+        assert_eq!(parse_banner(b"HTTP/1.1 400 Bad Request\r\n"), None);
+        assert_eq!(parse_banner(b""), None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Rendering workarounds applied before the webview starts.
+//!
+//! Users hitting EGL/driver crashes often cannot reach the settings UI at all, so
+//! these flags live in their own file (`<config>/Zync/launch.json`) rather than in
+//! webview-managed settings, and are turned into environment variables at the top
+//! of `run()`. Variables the user already exported are left alone.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Lists the variables this module set, so a relaunch can drop the ones that were
+/// switched off instead of the child inheriting them.
+const MANAGED_VAR: &str = "ZYNC_LAUNCH_MANAGED";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayBackend {
+    X11,
+    Wayland,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchFlags {
+    /// Render with Mesa's software rasterizer / WebView2 without GPU.
+    pub software_rendering: bool,
+    /// Keep GPU rendering but skip accelerated compositing (the usual EGL crash site).
+    pub disable_gpu_compositing: bool,
+    /// Linux only: force the GTK backend.
+    pub display_backend: Option<DisplayBackend>,
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("Zync").join("launch.json"))
+}
+
+/// Saved flags, or the defaults when the file is missing or unreadable.
+pub fn load() -> LaunchFlags {
+    let Some(path) = path() else {
+        return LaunchFlags::default();
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|e| {
+            crate::log_error!("[LAUNCH] Ignoring invalid {}: {}", path.display(), e);
+            LaunchFlags::default()
+        }),
+        Err(_) => LaunchFlags::default(),
+    }
+}
+
+pub fn save(flags: &LaunchFlags) -> Result<()> {
+    let path = path().ok_or_else(|| anyhow::anyhow!("No config directory on this system"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    crate::atomic_io::durable_replace(&path, serde_json::to_string_pretty(flags)?.as_bytes())?;
+    Ok(())
+}
+
+/// Environment that implements `flags` on `os` (`std::env::consts::OS` values).
+pub fn env_for(flags: &LaunchFlags, os: &str) -> Vec<(&'static str, String)> {
+    let mut vars = Vec::new();
+    match os {
+        "linux" => {
+            if flags.software_rendering {
+                vars.push(("LIBGL_ALWAYS_SOFTWARE", "1".to_string()));
+            }
+            if flags.software_rendering || flags.disable_gpu_compositing {
+                vars.push(("WEBKIT_DISABLE_COMPOSITING_MODE", "1".to_string()));
+                vars.push(("WEBKIT_DISABLE_DMABUF_RENDERER", "1".to_string()));
+            }
+            match flags.display_backend {
+                Some(DisplayBackend::X11) => vars.push(("GDK_BACKEND", "x11".to_string())),
+                Some(DisplayBackend::Wayland) => vars.push(("GDK_BACKEND", "wayland".to_string())),
+                None => {}
+            }
+        }
+        "windows" => {
+            let mut args = Vec::new();
+            if flags.software_rendering {
+                args.push("--disable-gpu");
+            }
+            if flags.disable_gpu_compositing {
+                args.push("--disable-gpu-compositing");
+            }
+            if !args.is_empty() {
+                vars.push(("WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS", args.join(" ")));
+            }
+        }
+        // WKWebView has no equivalent switches.
+        _ => {}
+    }
+    vars
+}
+
+/// Exports the environment for the saved flags. Call before any thread is spawned.
+pub fn apply() {
+    let flags = load();
+    let mut managed = Vec::new();
+    for (key, value) in env_for(&flags, std::env::consts::OS) {
+        if std::env::var_os(key).is_some() {
+            continue;
+        }
+        std::env::set_var(key, value);
+        managed.push(key);
+    }
+    if !managed.is_empty() {
+        crate::log_info!("[LAUNCH] Applied launch flags: {}", managed.join(", "));
+        std::env::set_var(MANAGED_VAR, managed.join(","));
+    }
+}
+
+/// Forgets the variables `apply` exported so the relaunched process starts clean.
+pub fn clear_applied() {
+    if let Some(managed) = std::env::var_os(MANAGED_VAR) {
+        for key in managed
+            .to_string_lossy()
+            .split(',')
+            .filter(|k| !k.is_empty())
+        {
+            std::env::remove_var(key);
+        }
+        std::env::remove_var(MANAGED_VAR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_flags_per_platform() {
+        let flags = LaunchFlags {
+            software_rendering: false,
+            disable_gpu_compositing: true,
+            display_backend: Some(DisplayBackend::X11),
+        };
+        let linux = env_for(&flags, "linux");
+        assert!(linux.contains(&("WEBKIT_DISABLE_COMPOSITING_MODE", "1".to_string())));
+        assert!(linux.contains(&("GDK_BACKEND", "x11".to_string())));
+        assert!(!linux.iter().any(|(k, _)| *k == "LIBGL_ALWAYS_SOFTWARE"));
+
+        let windows = env_for(&flags, "windows");
+        assert_eq!(
+            windows,
+            vec![(
+                "WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS",
+                "--disable-gpu-compositing".to_string()
+            )]
+        );
+        assert!(env_for(&flags, "macos").is_empty());
+        assert!(env_for(&LaunchFlags::default(), "linux").is_empty());
+    }
+
+    #[test]
+    fn parses_partial_file() {
+        let flags: LaunchFlags = serde_json::from_str(r#"{"displayBackend":"wayland"}"#).unwrap();
+        assert_eq!(flags.display_backend, Some(DisplayBackend::Wayland));
+        assert!(!flags.software_rendering);
+    }
+}
@@ -1,28 +1,113 @@
+mod actions;
 mod ai;
+mod ansible_inventory;
+mod archive;
 mod atomic_io;
+mod audit;
+mod bulk_chmod;
+mod bundle;
+mod channel_budget;
+mod cloud_import;
+mod command_history;
 mod commands;
+mod connection_health;
+mod connection_query;
+mod devcontainer;
+mod dns;
+mod doctor;
+mod exec_batch;
+mod failover;
+mod file_versions;
 mod fs;
 mod ghost;
+mod global_search;
+mod hashing;
+mod host_limits;
+mod http_client;
+mod inbox;
+mod input_history;
+mod key_rotation;
+mod kill_switch;
+mod lan_discovery;
+mod launch_settings;
+mod listing_cache;
+mod login_steps;
+mod macros;
+mod mesh_discovery;
+mod mosh;
+mod paste;
+mod path_names;
+mod persistent_session;
+mod probes;
+mod plugin_hooks;
+mod plugin_permissions;
+mod plugin_registry;
+mod plugin_settings;
+mod plugin_signing;
+mod plugin_wasm;
 pub mod plugins;
 mod pty;
+mod putty;
+mod quiet_login;
+mod recording;
+mod redact;
+mod remote_clipboard;
+mod remote_path;
+mod rsync;
+mod safe_mode;
+mod scrollback;
+mod semantic_index;
 mod session;
+mod session_log;
+mod sftp_extensions;
 mod shell_icons;
+mod shell_integration;
+mod snippet_schedule;
 mod snippets;
 mod ssh;
 mod ssh_config;
 mod ssh_parser;
+mod ssh_security;
+mod state_sync;
+mod storage_migration;
 mod sync;
+mod tcp_options;
+mod terminal_registry;
+mod terminal_taps;
+#[cfg(test)]
+mod test_server;
+mod text_encoding;
+mod trash;
+mod transfer_dedupe;
+mod transfer_pipeline;
+mod transfer_retry;
+mod triggers;
 mod tunnels;
 pub use tunnels::{remote_forward_map_key, tunnel_runtime_id, TunnelManager};
 mod types;
 mod utils;
 mod vault;
+mod zmodem;
 
 use commands::AppState;
 use tauri::{Emitter, Manager};
 
+/// When started as a helper for an external tool (rsync's remote shell with
+/// `--rsync-bridge`, or `ssh-keygen`'s askpass program), does that job and
+/// returns the exit code instead of starting the app.
+pub fn run_helper() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(rsync::BRIDGE_FLAG) {
+        return Some(rsync::run_bridge(&args[1..]));
+    }
+    key_rotation::run_askpass()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    safe_mode::init();
+    launch_settings::apply();
+
     // Release builds only: dev (`tauri dev`) shares the same app identifier as the
     // installed app, so single-instance would focus the production window instead of
     // launching the dev instance.
@@ -59,11 +144,14 @@ pub fn run() {
 
             let app_handle = app.handle().clone();
             let data_dir = commands::get_data_dir(&app_handle);
+            redact::init(&data_dir.join("keys"));
+            vault::at_rest::init(&data_dir);
             let app_state = AppState::new(data_dir.clone(), app_handle.clone());
             app.manage(app_state);
             app.manage(tokio::sync::Mutex::new(vault::store::VaultService::new(
                 data_dir,
             )));
+            vault::at_rest::spawn_idle_lock(app_handle.clone());
             commands::cleanup_stale_plugin_window_temp_files(&app_handle);
             Ok(())
         })
@@ -71,6 +159,7 @@ pub fn run() {
             if webview.label() == "main"
                 && matches!(payload.event(), tauri::webview::PageLoadEvent::Finished)
             {
+                safe_mode::mark_started();
                 let _ = webview.window().show();
             }
         })
@@ -123,37 +212,92 @@ pub fn run() {
             commands::ssh_test_connection,
             commands::ssh_extract_pem,
             commands::ssh_migrate_all_keys,
+            commands::ssh_rotate_key,
             commands::ssh_disconnect,
             commands::ssh_transport_lost,
             commands::ssh_disconnect_vault_backed,
             commands::terminal_write,
             commands::terminal_navigate,
             commands::terminal_resize,
+            commands::terminal_record_start,
+            commands::terminal_record_stop,
+            commands::terminal_record_status,
+            commands::terminal_record_export,
+            commands::terminal_get_log_path,
+            commands::audit_export_bundle,
+            commands::audit_query,
             commands::terminal_create,
             commands::terminal_close,
             commands::terminal_has_active_processes,
+            commands::terminal_get_cwd,
+            commands::terminal_input_history,
+            commands::connection_hushlogin,
+            commands::connection_security_info,
+            commands::remote_clipboard_set,
+            commands::remote_clipboard_get,
+            commands::remote_sessions_list,
+            commands::remote_sessions_attach,
+            commands::devcontainer_attach,
+            commands::devcontainer_list,
+            commands::devcontainer_detach,
+            commands::devcontainer_terminal,
+            commands::devcontainer_exec,
+            commands::devcontainer_list_dir,
+            commands::devcontainer_read_file,
+            commands::devcontainer_write_file,
+            commands::devcontainer_copy,
+            commands::terminal_get_scrollback,
+            commands::terminal_scrollback_stats,
             commands::connections_get,
+            commands::connections_query,
             commands::connections_save,
             commands::connections_export_to_file,
             commands::connections_import_from_file,
+            commands::connections_export,
+            commands::connections_import,
             commands::fs_list,
             commands::fs_read_file,
             commands::fs_write_file,
+            commands::fs_versions,
+            commands::fs_version_read,
+            commands::fs_version_restore,
             commands::fs_cwd,
             commands::fs_touch,
             commands::fs_mkdir,
             commands::fs_rename,
             commands::fs_delete,
             commands::fs_delete_batch,
+            commands::fs_trash_list,
+            commands::fs_restore,
+            commands::fs_empty_trash,
             commands::fs_copy,
             commands::fs_copy_batch,
             commands::fs_rename_batch,
             commands::fs_exists,
+            commands::fs_chmod,
+            commands::fs_chmod_bulk,
+            commands::fs_chown,
+            commands::fs_create_symlink,
+            commands::fs_compress,
+            commands::fs_extract,
+            commands::fs_hash,
+            commands::fs_checksum,
+            commands::fs_append,
+            commands::fs_read_tail,
             tunnels::commands::tunnel_get_all,
             tunnels::commands::tunnel_start_local,
+            tunnels::commands::tunnel_check_port,
+            tunnels::commands::tunnel_suggest_port,
             tunnels::commands::tunnel_start_remote,
+            tunnels::commands::tunnel_start_remote_dynamic,
+            tunnels::commands::exit_node_start,
+            tunnels::commands::exit_node_stop,
+            tunnels::commands::exit_node_status,
             tunnels::commands::tunnel_stop,
             tunnels::commands::tunnel_list,
+            tunnels::commands::tunnel_stats,
+            tunnels::commands::tunnel_logs,
+            tunnels::commands::tunnel_watch_connections,
             tunnels::commands::tunnel_save,
             tunnels::commands::tunnel_delete,
             tunnels::commands::tunnel_start,
@@ -163,26 +307,89 @@ pub fn run() {
             commands::window_minimize,
             commands::window_close,
             commands::ssh_exec,
+            commands::ssh_exec_batch,
+            commands::global_search,
+            commands::global_search_cancel,
             commands::ssh_import_config,
             commands::ssh_import_config_from_file,
             commands::ssh_import_config_from_text,
             commands::ssh_import_config_by_source,
+            commands::import_putty_sessions,
+            commands::ssh_export_config,
+            commands::cloud_import_aws,
+            commands::cloud_import,
+            commands::discover_tailscale,
+            commands::discover_zerotier,
+            commands::discovery_start,
+            commands::discovery_stop,
+            commands::cloud_sources_list,
+            commands::cloud_sources_remove,
             commands::ssh_internalize_connections,
             commands::snippets_list,
             commands::snippets_save,
             commands::snippets_delete,
+            commands::snippets_run,
+            commands::snippets_import_history,
+            commands::snippets_schedule_status,
+            commands::snippets_schedule_history,
+            commands::semantic_search,
+            commands::semantic_index_clear,
+            commands::probes_list,
+            commands::probes_save,
+            commands::probes_status,
+            commands::inbox_list,
+            commands::inbox_save,
+            commands::inbox_status,
+            commands::connections_health,
+            commands::connections_health_settings_get,
+            commands::connections_health_settings_save,
+            commands::ssh_host_limits_get,
+            commands::ssh_host_limits_save,
+            commands::macros_list,
+            commands::macro_record_start,
+            commands::macro_record_stop,
+            commands::macro_play,
+            commands::macros_rename,
+            commands::macros_delete,
+            commands::triggers_list,
+            commands::triggers_save,
+            commands::triggers_delete,
+            commands::triggers_set_terminal_enabled,
+            commands::history_search,
+            commands::history_top,
+            commands::history_clear,
+            commands::actions_list,
+            commands::actions_save,
+            commands::actions_delete,
+            commands::actions_preview,
+            commands::actions_run,
             commands::save_secret,
             commands::get_secret,
             commands::delete_secret,
             commands::get_system_info,
             commands::settings_get,
+            commands::network_proxy_url,
+            commands::network_kill_switch_get,
+            commands::network_kill_switch_set,
+            commands::state_snapshot,
+            commands::app_launch_settings_get,
+            commands::app_relaunch_with,
+            commands::safe_mode_status,
+            commands::safe_mode_reset_settings,
+            commands::safe_mode_disable_plugin,
+            commands::safe_mode_exit,
+            commands::doctor_run,
+            commands::doctor_apply_fix,
             commands::settings_set,
             commands::settings_get_path,
             commands::settings_read_raw,
             commands::settings_write_raw,
             commands::settings_restore_last_known_good,
+            commands::storage_migrate,
             commands::sftp_put,
             commands::sftp_get,
+            commands::transfer_rsync,
+            commands::transfer_processors_list,
             commands::sftp_copy_to_server,
             commands::sftp_cancel_transfer,
             commands::sftp_download_as_zip,
@@ -200,17 +407,35 @@ pub fn run() {
             commands::plugins_toggle,
             commands::plugins_install,
             commands::plugins_install_local,
+            commands::plugins_registry_list,
+            commands::plugins_registry_search,
+            commands::plugins_install_by_id,
             commands::plugins_uninstall,
+            commands::plugins_permissions_get,
+            commands::plugins_permissions_set,
+            commands::plugin_settings_get,
+            commands::plugin_settings_set,
             commands::plugin_fs_read,
             commands::plugin_fs_write,
             commands::plugin_fs_list,
             commands::plugin_fs_exists,
             commands::plugin_fs_create_dir,
+            commands::plugin_terminal_write,
+            commands::plugin_terminal_read_buffer,
+            commands::plugin_terminal_subscribe,
+            commands::plugin_terminal_unsubscribe,
+            commands::plugin_ssh_exec,
+            commands::plugin_sftp_read,
+            commands::plugin_sftp_write,
+            commands::plugin_wasm_call,
             commands::plugin_window_create,
             commands::config_select_folder,
             commands::system_install_cli,
             commands::ssh_parse_command,
             commands::ai_translate,
+            commands::ai_dangerous_command_challenge,
+            commands::ai_dangerous_command_confirm,
+            commands::ai_terminal_run,
             commands::ai_translate_stream,
             commands::ai_check_ollama,
             commands::ai_get_ollama_models,
@@ -248,6 +473,8 @@ pub fn run() {
             vault::commands::vault_unlock_with_recovery_key,
             vault::commands::vault_export,
             vault::commands::vault_import,
+            vault::commands::vault_at_rest_status,
+            vault::commands::vault_at_rest_configure,
             sync::commands::sync_status,
             sync::commands::sync_collection_status,
             sync::commands::sync_collection_discover_remote,
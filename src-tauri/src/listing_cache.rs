@@ -0,0 +1,204 @@
+//! Short-lived cache of remote directory listings, filled by background prefetch.
+//!
+//! After the file panel lists a remote directory, its immediate child directories
+//! are listed in the background, [`PREFETCH_CONCURRENCY`] at a time per connection,
+//! so opening one of them is answered from memory instead of a full round of SFTP
+//! requests. A cached listing is served once, within [`MAX_AGE`], and only while
+//! the directory's mtime is unchanged, which costs one `stat` instead of a `read_dir`.
+
+use crate::fs::{FileEntry, FileSystem};
+use russh_sftp::client::SftpSession;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+pub const MAX_AGE: Duration = Duration::from_secs(30);
+/// Child directories prefetched per listing.
+const MAX_PREFETCH_DIRS: usize = 24;
+const PREFETCH_CONCURRENCY: usize = 4;
+const PREFETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Listings kept across all connections; the oldest go first.
+const MAX_LISTINGS: usize = 256;
+
+struct CachedListing {
+    entries: Vec<FileEntry>,
+    mtime: Option<u32>,
+    fetched: Instant,
+}
+
+type ListingKey = (String, String);
+
+static LISTINGS: LazyLock<Mutex<HashMap<ListingKey, CachedListing>>> =
+    LazyLock::new(Default::default);
+
+static PREFETCH_PERMITS: LazyLock<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    LazyLock::new(Default::default);
+
+fn key(connection_id: &str, path: &str) -> ListingKey {
+    let path = match path.trim_end_matches('/') {
+        "" if path.starts_with('/') => "/",
+        trimmed => trimmed,
+    };
+    (connection_id.to_string(), path.to_string())
+}
+
+fn store(connection_id: &str, path: &str, mtime: Option<u32>, entries: Vec<FileEntry>) {
+    let Ok(mut listings) = LISTINGS.lock() else {
+        return;
+    };
+    listings.retain(|_, cached| cached.fetched.elapsed() < MAX_AGE);
+    if listings.len() >= MAX_LISTINGS {
+        if let Some(oldest) = listings
+            .iter()
+            .min_by_key(|(_, cached)| cached.fetched)
+            .map(|(key, _)| key.clone())
+        {
+            listings.remove(&oldest);
+        }
+    }
+    listings.insert(
+        key(connection_id, path),
+        CachedListing {
+            entries,
+            mtime,
+            fetched: Instant::now(),
+        },
+    );
+}
+
+/// Removes and returns the listing for `path` if it is still within [`MAX_AGE`].
+fn take_recent(connection_id: &str, path: &str) -> Option<CachedListing> {
+    let cached = LISTINGS.lock().ok()?.remove(&key(connection_id, path))?;
+    (cached.fetched.elapsed() < MAX_AGE).then_some(cached)
+}
+
+fn is_cached(connection_id: &str, path: &str) -> bool {
+    LISTINGS.lock().is_ok_and(|listings| {
+        listings
+            .get(&key(connection_id, path))
+            .is_some_and(|cached| cached.fetched.elapsed() < MAX_AGE)
+    })
+}
+
+/// Prefetched listing of `path`, if there is a recent one and the directory's
+/// mtime has not moved since it was taken.
+pub async fn take(sftp: &SftpSession, connection_id: &str, path: &str) -> Option<Vec<FileEntry>> {
+    let cached = take_recent(connection_id, path)?;
+    let mtime = sftp.metadata(path).await.ok()?.mtime;
+    (mtime.is_some() && mtime == cached.mtime).then_some(cached.entries)
+}
+
+/// Drops everything cached for a connection, e.g. on disconnect.
+pub fn forget_connection(connection_id: &str) {
+    if let Ok(mut listings) = LISTINGS.lock() {
+        listings.retain(|(id, _), _| id != connection_id);
+    }
+    if let Ok(mut permits) = PREFETCH_PERMITS.lock() {
+        permits.remove(connection_id);
+    }
+}
+
+/// Child directories of a listing worth prefetching: real directories (not
+/// symlinks), hidden ones skipped, in listing order.
+fn prefetch_targets(entries: &[FileEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .filter(|entry| entry.r#type == "d" && !entry.name.starts_with('.'))
+        .take(MAX_PREFETCH_DIRS)
+        .map(|entry| entry.path.clone())
+        .collect()
+}
+
+fn permits(connection_id: &str) -> Option<Arc<Semaphore>> {
+    let mut permits = PREFETCH_PERMITS.lock().ok()?;
+    Some(
+        permits
+            .entry(connection_id.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(PREFETCH_CONCURRENCY)))
+            .clone(),
+    )
+}
+
+/// Lists the child directories of `entries` in the background and caches them.
+/// Failures are ignored; the panel simply lists the directory itself later.
+pub fn spawn_prefetch(sftp: Arc<SftpSession>, connection_id: &str, entries: &[FileEntry]) {
+    let Some(permits) = permits(connection_id) else {
+        return;
+    };
+    for path in prefetch_targets(entries) {
+        if is_cached(connection_id, &path) {
+            continue;
+        }
+        let sftp = sftp.clone();
+        let permits = permits.clone();
+        let connection_id = connection_id.to_string();
+        tokio::spawn(async move {
+            let Ok(_permit) = permits.acquire().await else {
+                return;
+            };
+            if is_cached(&connection_id, &path) {
+                return;
+            }
+            let listing = tokio::time::timeout(PREFETCH_TIMEOUT, async {
+                let mtime = sftp.metadata(&path).await.ok()?.mtime;
+                let entries = FileSystem::new().list_remote(&sftp, &path).await.ok()?;
+                Some((mtime, entries))
+            })
+            .await;
+            if let Ok(Some((mtime, entries))) = listing {
+                store(&connection_id, &path, mtime, entries);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, kind: &str) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            path: format!("/srv/{name}"),
+            r#type: kind.to_string(),
+            size: 0,
+            last_modified: 0,
+            permissions: "755".to_string(),
+            owner: None,
+            group: None,
+            link_target: None,
+            link_target_type: None,
+        }
+    }
+
+    #[test]
+    fn prefetches_visible_directories_only() {
+        let entries = vec![
+            entry("app", "d"),
+            entry(".git", "d"),
+            entry("current", "l"),
+            entry("notes.txt", "-"),
+            entry("logs", "d"),
+        ];
+        assert_eq!(prefetch_targets(&entries), vec!["/srv/app", "/srv/logs"]);
+    }
+
+    #[test]
+    fn listings_are_served_once_and_keyed_without_trailing_slash() {
+        store("cache-test", "/srv/app/", Some(7), vec![entry("a", "-")]);
+        assert!(is_cached("cache-test", "/srv/app"));
+        assert!(!is_cached("other", "/srv/app"));
+
+        let cached = take_recent("cache-test", "/srv/app").expect("cached listing");
+        assert_eq!(cached.mtime, Some(7));
+        assert_eq!(cached.entries.len(), 1);
+        assert!(take_recent("cache-test", "/srv/app").is_none());
+        assert_eq!(key("c", "/").1, "/");
+        assert_eq!(key("c", "//").1, "/");
+
+        store("cache-test", "/srv/logs", None, Vec::new());
+        forget_connection("cache-test");
+        assert!(!is_cached("cache-test", "/srv/logs"));
+    }
+}
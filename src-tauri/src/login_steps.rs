@@ -0,0 +1,193 @@
+//! Post-connect login steps.
+//!
+//! Each connection can carry an ordered list of [`LoginStep`]s (e.g. "the VPN-only
+//! marker file exists", "run `kinit`"). They run over exec channels right after
+//! authentication, before SFTP or any terminal is attached. The first failing step
+//! aborts the connection with a `LOGIN_STEP_FAILED:` error carrying a JSON payload
+//! so the UI can show which step failed and why.
+
+use crate::ssh::Client;
+use crate::types::LoginStep;
+use russh::client::Handle;
+use serde::Serialize;
+use std::time::Duration;
+
+pub const LOGIN_STEP_FAILED: &str = "LOGIN_STEP_FAILED";
+const DEFAULT_STEP_TIMEOUT_SECS: u64 = 30;
+/// Only the tail of a failing step's output is kept for the error payload.
+const MAX_OUTPUT_BYTES: usize = 2048;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginStepFailure {
+    pub index: usize,
+    pub name: String,
+    pub command: String,
+    /// `exit`, `timeout` or `channel`.
+    pub reason: String,
+    pub exit_code: Option<u32>,
+    pub output: String,
+}
+
+impl LoginStepFailure {
+    pub fn to_error_string(&self) -> String {
+        format!(
+            "{}:{}",
+            LOGIN_STEP_FAILED,
+            serde_json::to_string(self).unwrap_or_default()
+        )
+    }
+}
+
+fn step_label(step: &LoginStep, index: usize) -> String {
+    step.name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Step {}", index + 1))
+}
+
+fn output_tail(stdout: &[u8], stderr: &[u8]) -> String {
+    let mut combined = String::from_utf8_lossy(stdout).to_string();
+    if !stderr.is_empty() {
+        if !combined.is_empty() && !combined.ends_with('\n') {
+            combined.push('\n');
+        }
+        combined.push_str(&String::from_utf8_lossy(stderr));
+    }
+    let trimmed = combined.trim_end();
+    if trimmed.len() <= MAX_OUTPUT_BYTES {
+        return trimmed.to_string();
+    }
+    let mut start = trimmed.len() - MAX_OUTPUT_BYTES;
+    while !trimmed.is_char_boundary(start) {
+        start += 1;
+    }
+    trimmed[start..].to_string()
+}
+
+async fn run_step(session: &Handle<Client>, command: &str) -> Result<(u32, Vec<u8>, Vec<u8>), String> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| e.to_string())?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_status = None;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            russh::ChannelMsg::Data { ref data } => stdout.extend_from_slice(data),
+            russh::ChannelMsg::ExtendedData { ref data, .. } => stderr.extend_from_slice(data),
+            russh::ChannelMsg::ExitStatus { exit_status: code } => exit_status = Some(code),
+            _ => {}
+        }
+    }
+    // A channel that closes without an exit status (killed by a signal) counts as a failure.
+    Ok((exit_status.unwrap_or(255), stdout, stderr))
+}
+
+/// Runs `steps` in order. Returns the first failure that is not marked
+/// `continue_on_failure`.
+pub async fn run_login_steps(
+    session: &Handle<Client>,
+    steps: &[LoginStep],
+) -> Result<(), LoginStepFailure> {
+    for (index, step) in steps.iter().enumerate() {
+        let command = step.command.trim();
+        if command.is_empty() {
+            continue;
+        }
+        let name = step_label(step, index);
+        let timeout =
+            Duration::from_secs(step.timeout_secs.unwrap_or(DEFAULT_STEP_TIMEOUT_SECS).max(1));
+        crate::log_info!("[SSH] Running login step '{}'", name);
+
+        let failure = match tokio::time::timeout(timeout, run_step(session, command)).await {
+            Ok(Ok((0, _, _))) => None,
+            Ok(Ok((code, stdout, stderr))) => Some(LoginStepFailure {
+                index,
+                name: name.clone(),
+                command: command.to_string(),
+                reason: "exit".to_string(),
+                exit_code: Some(code),
+                output: output_tail(&stdout, &stderr),
+            }),
+            Ok(Err(e)) => Some(LoginStepFailure {
+                index,
+                name: name.clone(),
+                command: command.to_string(),
+                reason: "channel".to_string(),
+                exit_code: None,
+                output: e,
+            }),
+            Err(_) => Some(LoginStepFailure {
+                index,
+                name: name.clone(),
+                command: command.to_string(),
+                reason: "timeout".to_string(),
+                exit_code: None,
+                output: format!("Timed out after {}s", timeout.as_secs()),
+            }),
+        };
+
+        if let Some(failure) = failure {
+            if step.continue_on_failure {
+                crate::log_error!(
+                    "[SSH] Login step '{}' failed ({}), continuing",
+                    name,
+                    failure.reason
+                );
+                continue;
+            }
+            return Err(failure);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_label_falls_back_to_position() {
+        let step = LoginStep {
+            name: Some("  ".to_string()),
+            command: "true".to_string(),
+            timeout_secs: None,
+            continue_on_failure: false,
+        };
+        assert_eq!(step_label(&step, 2), "Step 3");
+    }
+
+    #[test]
+    fn output_tail_keeps_the_end_of_long_output() {
+        let stdout = "a".repeat(MAX_OUTPUT_BYTES + 100);
+        let tail = output_tail(stdout.as_bytes(), b"kinit: no credentials");
+        assert!(tail.len() <= MAX_OUTPUT_BYTES);
+        assert!(tail.ends_with("kinit: no credentials"));
+    }
+
+    #[test]
+    fn failure_error_string_is_prefixed_json() {
+        let failure = LoginStepFailure {
+            index: 0,
+            name: "VPN marker".to_string(),
+            command: "test -f /etc/vpn-only".to_string(),
+            reason: "exit".to_string(),
+            exit_code: Some(1),
+            output: String::new(),
+        };
+        let error = failure.to_error_string();
+        let json = error.strip_prefix("LOGIN_STEP_FAILED:").unwrap();
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(value["name"], "VPN marker");
+        assert_eq!(value["exitCode"], 1);
+    }
+}
@@ -0,0 +1,191 @@
+//! Keystroke macros: terminal input captured with its timing and replayed later.
+//!
+//! While a terminal is recording, every `PtyManager::write` is appended as a
+//! step with the delay since the previous one. Saved macros live in
+//! `<data>/macros.json` next to `snippets.json`; playback writes the steps back
+//! to a terminal, scaling the delays by the requested speed.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+pub(crate) static MACROS_MUTATION_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+/// Pauses longer than this (the user stepped away) are shortened when recorded.
+const MAX_STEP_DELAY: Duration = Duration::from_secs(5);
+/// Steps per macro; a runaway recording stops growing past this.
+const MAX_STEPS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroStep {
+    /// Wait before sending `data`, at 1x speed.
+    pub delay_ms: u64,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystrokeMacro {
+    pub id: String,
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MacrosData {
+    pub macros: Vec<KeystrokeMacro>,
+}
+
+struct MacroRecording {
+    last: Instant,
+    steps: Vec<MacroStep>,
+}
+
+/// In-progress macro recordings keyed by `term_id`.
+#[derive(Clone, Default)]
+pub struct MacroRecorders {
+    active: Arc<Mutex<HashMap<String, MacroRecording>>>,
+    count: Arc<AtomicUsize>,
+}
+
+impl MacroRecorders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self, term_id: &str) -> Result<()> {
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| anyhow!("Macro recorder lock poisoned"))?;
+        if active.contains_key(term_id) {
+            return Err(anyhow!(
+                "A macro is already being recorded in this terminal"
+            ));
+        }
+        active.insert(
+            term_id.to_string(),
+            MacroRecording {
+                last: Instant::now(),
+                steps: Vec::new(),
+            },
+        );
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn input(&self, term_id: &str, data: &str) {
+        if self.count.load(Ordering::Relaxed) == 0 || data.is_empty() {
+            return;
+        }
+        let Ok(mut active) = self.active.lock() else {
+            return;
+        };
+        if let Some(recording) = active.get_mut(term_id) {
+            if recording.steps.len() >= MAX_STEPS {
+                return;
+            }
+            let now = Instant::now();
+            let delay = now.duration_since(recording.last).min(MAX_STEP_DELAY);
+            recording.last = now;
+            recording.steps.push(MacroStep {
+                delay_ms: delay.as_millis() as u64,
+                data: data.to_string(),
+            });
+        }
+    }
+
+    pub fn stop(&self, term_id: &str) -> Result<Vec<MacroStep>> {
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| anyhow!("Macro recorder lock poisoned"))?;
+        let recording = active
+            .remove(term_id)
+            .ok_or_else(|| anyhow!("No macro is being recorded in this terminal"))?;
+        self.count.fetch_sub(1, Ordering::SeqCst);
+        let mut steps = recording.steps;
+        // Time spent before the first keystroke is not part of the macro.
+        if let Some(first) = steps.first_mut() {
+            first.delay_ms = 0;
+        }
+        Ok(steps)
+    }
+
+    pub fn is_recording(&self, term_id: &str) -> bool {
+        self.active
+            .lock()
+            .map(|active| active.contains_key(term_id))
+            .unwrap_or(false)
+    }
+
+    pub fn discard(&self, term_id: &str) {
+        if self.count.load(Ordering::Relaxed) > 0 {
+            let _ = self.stop(term_id);
+        }
+    }
+}
+
+/// Delay before a step at `speed` (2.0 = twice as fast).
+pub fn scaled_delay(step: &MacroStep, speed: f64) -> Duration {
+    Duration::from_secs_f64(step.delay_ms as f64 / 1000.0 / speed)
+}
+
+pub fn read_macros(path: &Path) -> Result<Vec<KeystrokeMacro>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let data: MacrosData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    Ok(data.macros)
+}
+
+pub fn write_macros(path: &Path, macros: Vec<KeystrokeMacro>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&MacrosData { macros }).map_err(|e| e.to_string())?;
+    crate::atomic_io::durable_replace(path, json.as_bytes())
+        .map_err(|e| format!("Failed to write macros file: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_input_with_timing_per_terminal() {
+        let recorders = MacroRecorders::new();
+        recorders.input("t1", "ignored");
+        recorders.start("t1").unwrap();
+        assert!(recorders.start("t1").is_err());
+        std::thread::sleep(Duration::from_millis(20));
+        recorders.input("t1", "ls");
+        std::thread::sleep(Duration::from_millis(20));
+        recorders.input("t1", "\r");
+        recorders.input("t2", "other terminal");
+
+        let steps = recorders.stop("t1").unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].delay_ms, 0);
+        assert_eq!(steps[0].data, "ls");
+        assert!(steps[1].delay_ms >= 15);
+        assert!(recorders.stop("t1").is_err());
+        assert!(!recorders.is_recording("t1"));
+    }
+
+    #[test]
+    fn scales_delays_by_speed() {
+        let step = MacroStep {
+            delay_ms: 400,
+            data: "x".to_string(),
+        };
+        assert_eq!(scaled_delay(&step, 1.0), Duration::from_millis(400));
+        assert_eq!(scaled_delay(&step, 2.0), Duration::from_millis(200));
+        assert_eq!(scaled_delay(&step, 0.5), Duration::from_millis(800));
+    }
+}
@@ -2,6 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    if let Some(code) = tauri_app_lib::run_helper() {
+        std::process::exit(code);
+    }
+
     // On Windows release builds, allocate and immediately hide a console.
     // This is necessary for portable-pty/ConPTY to work without spawning
     // visible console windows for each PTY session.
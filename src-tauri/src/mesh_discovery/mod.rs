@@ -0,0 +1,19 @@
+//! Peers of mesh VPNs (Tailscale, ZeroTier) as importable connections.
+//!
+//! Like [`crate::cloud_import`], discovery asks the tools already set up on
+//! this machine: the `tailscale` CLI knows the whole tailnet, while ZeroTier
+//! members and their managed addresses are only known to the network
+//! controller, so they come from the ZeroTier Central API. Connection ids are
+//! stable per node, so a repeated discovery updates the same entries.
+
+pub mod tailscale;
+pub mod zerotier;
+
+/// Default SSH user for discovered peers: `username` when set, else the local
+/// login name (as `ssh host` would use), else `root`.
+fn username_for(username: &Option<String>) -> String {
+    crate::cloud_import::non_empty(username)
+        .map(str::to_string)
+        .or_else(crate::cloud_import::local_username)
+        .unwrap_or_else(|| "root".to_string())
+}
@@ -0,0 +1,246 @@
+//! Tailnet peers via `tailscale status --json`.
+
+use super::username_for;
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// CLI inside the macOS app, for installs without the `tailscale` command.
+const MACOS_APP_CLI: &str = "/Applications/Tailscale.app/Contents/MacOS/Tailscale";
+/// Operating systems that do not run an SSH server.
+const NO_SSH_OS: &[&str] = &["ios", "android", "tvos"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TailscaleQuery {
+    /// Only peers the coordination server reports as online.
+    pub online_only: bool,
+    /// Connect by Tailscale IP instead of the MagicDNS name.
+    pub use_ip: bool,
+    pub username: Option<String>,
+    /// ACL tags (`tag:server` or `server`); a peer must carry all of them.
+    pub tags: Vec<String>,
+}
+
+impl Default for TailscaleQuery {
+    fn default() -> Self {
+        Self {
+            online_only: true,
+            use_ip: false,
+            username: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Status {
+    #[serde(default)]
+    backend_state: String,
+    /// `null` on a tailnet without other nodes.
+    #[serde(default)]
+    peer: Option<BTreeMap<String, Peer>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Peer {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(default)]
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "OS", default)]
+    os: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Option<Vec<String>>,
+    #[serde(default)]
+    online: bool,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+fn program() -> &'static str {
+    if cfg!(target_os = "macos") && std::path::Path::new(MACOS_APP_CLI).is_file() {
+        MACOS_APP_CLI
+    } else {
+        "tailscale"
+    }
+}
+
+fn tag_name(tag: &str) -> &str {
+    let tag = tag.trim();
+    tag.strip_prefix("tag:").unwrap_or(tag)
+}
+
+/// Maps `tailscale status --json` to connections. Peers that are offline
+/// (with `online_only`), run a mobile OS or have no address are left out.
+pub fn parse_status(json: &[u8], query: &TailscaleQuery) -> Result<Vec<ParsedSshConnection>> {
+    let status: Status =
+        serde_json::from_slice(json).context("Unexpected `tailscale status` output")?;
+    if status.backend_state != "Running" {
+        bail!(
+            "Tailscale is not connected (state: {})",
+            if status.backend_state.is_empty() {
+                "unknown"
+            } else {
+                status.backend_state.as_str()
+            }
+        );
+    }
+    let wanted: Vec<&str> = query
+        .tags
+        .iter()
+        .map(|tag| tag_name(tag))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let username = username_for(&query.username);
+    let mut connections = Vec::new();
+    for peer in status.peer.unwrap_or_default().into_values() {
+        if query.online_only && !peer.online {
+            continue;
+        }
+        if NO_SSH_OS.contains(&peer.os.to_lowercase().as_str()) {
+            continue;
+        }
+        let tags: Vec<&str> = peer
+            .tags
+            .iter()
+            .flatten()
+            .map(|tag| tag_name(tag))
+            .collect();
+        if !wanted.iter().all(|tag| tags.contains(tag)) {
+            continue;
+        }
+        let ips = peer.tailscale_ips.unwrap_or_default();
+        let ip = ips.iter().find(|ip| ip.contains('.')).or(ips.first());
+        let dns_name = peer.dns_name.trim_end_matches('.');
+        let host = if query.use_ip || dns_name.is_empty() {
+            ip.map(String::as_str)
+        } else {
+            Some(dns_name)
+        };
+        let Some(host) = host else {
+            continue;
+        };
+        let short_name = dns_name.split('.').next().unwrap_or_default();
+        let name = if peer.host_name.is_empty() {
+            short_name.to_string()
+        } else {
+            peer.host_name.clone()
+        };
+        let mut aliases = vec![name.clone()];
+        for alias in [short_name, dns_name] {
+            if !alias.is_empty() && !aliases.iter().any(|a| a == alias) {
+                aliases.push(alias.to_string());
+            }
+        }
+        let mut connection_tags = vec!["tailscale".to_string()];
+        if !peer.os.is_empty() {
+            connection_tags.push(peer.os.to_lowercase());
+        }
+        connection_tags.extend(tags.iter().map(|tag| tag.to_string()));
+        connections.push(ParsedSshConnection {
+            id: format!("tailscale_{}", peer.id),
+            name,
+            host: host.to_string(),
+            username: username.clone(),
+            port: 22,
+            private_key_path: None,
+            jump_server_alias: None,
+            jump_server_id: None,
+            aliases,
+            identities_only: None,
+            tags: connection_tags,
+            folder: None,
+        });
+    }
+    connections.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(connections)
+}
+
+pub async fn discover(query: &TailscaleQuery) -> Result<Vec<ParsedSshConnection>> {
+    let args = ["status".to_string(), "--json".to_string()];
+    let stdout = crate::cloud_import::run_cli(program(), &args).await?;
+    parse_status(&stdout, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = r#"{
+      "BackendState": "Running",
+      "MagicDNSSuffix": "tail1234.ts.net",
+      "Self": {"ID": "n0", "HostName": "laptop", "DNSName": "laptop.tail1234.ts.net.", "OS": "macOS"},
+      "Peer": {
+        "nodekey:a": {
+          "ID": "n1", "HostName": "web", "DNSName": "web.tail1234.ts.net.", "OS": "linux",
+          "TailscaleIPs": ["100.64.0.2", "fd7a:115c:a1e0::2"], "Online": true, "Tags": ["tag:server"]
+        },
+        "nodekey:b": {
+          "ID": "n2", "HostName": "nas", "DNSName": "nas.tail1234.ts.net.", "OS": "linux",
+          "TailscaleIPs": ["100.64.0.3"], "Online": false
+        },
+        "nodekey:c": {
+          "ID": "n3", "HostName": "phone", "DNSName": "phone.tail1234.ts.net.", "OS": "iOS",
+          "TailscaleIPs": ["100.64.0.4"], "Online": true
+        }
+      }
+    }"#;
+
+    #[test]
+    fn maps_online_peers_to_connections() {
+        let query = TailscaleQuery {
+            username: Some("ops".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_status(STATUS.as_bytes(), &query).unwrap();
+        assert_eq!(connections.len(), 1);
+        let web = &connections[0];
+        assert_eq!(web.id, "tailscale_n1");
+        assert_eq!(web.host, "web.tail1234.ts.net");
+        assert_eq!(web.username, "ops");
+        assert_eq!(web.aliases, ["web", "web.tail1234.ts.net"]);
+        assert_eq!(web.tags, ["tailscale", "linux", "server"]);
+    }
+
+    #[test]
+    fn filters_by_tag_and_uses_ips_on_request() {
+        let all = TailscaleQuery {
+            online_only: false,
+            use_ip: true,
+            ..Default::default()
+        };
+        let connections = parse_status(STATUS.as_bytes(), &all).unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[0].name, "nas");
+        assert_eq!(connections[1].host, "100.64.0.2");
+
+        let tagged = TailscaleQuery {
+            online_only: false,
+            tags: vec!["server".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(parse_status(STATUS.as_bytes(), &tagged).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_a_disconnected_client() {
+        let error = parse_status(
+            br#"{"BackendState": "NeedsLogin", "Peer": null}"#,
+            &Default::default(),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("NeedsLogin"));
+        assert!(parse_status(
+            br#"{"BackendState": "Running", "Peer": null}"#,
+            &Default::default()
+        )
+        .unwrap()
+        .is_empty());
+    }
+}
@@ -0,0 +1,317 @@
+//! ZeroTier network members via the ZeroTier Central API.
+//!
+//! The local `zerotier-cli` only knows this node's own networks and
+//! addresses; member names and managed IPs live in the network controller.
+//! Networks default to those this node has joined (`zerotier-cli -j
+//! listnetworks`), and the API token comes from the query or the
+//! `ZEROTIER_CENTRAL_TOKEN` environment variable.
+
+use super::username_for;
+use crate::ssh_config::ParsedSshConnection;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CENTRAL_API: &str = "https://api.zerotier.com/api/v1";
+const TOKEN_ENV: &str = "ZEROTIER_CENTRAL_TOKEN";
+const API_TIMEOUT: Duration = Duration::from_secs(20);
+/// Members seen by the controller within this window count as online.
+const ONLINE_WINDOW_MS: u64 = 10 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ZerotierQuery {
+    /// Network ids to list; the networks this node has joined when empty.
+    pub network_ids: Vec<String>,
+    /// Central API token; `ZEROTIER_CENTRAL_TOKEN` when unset.
+    pub api_token: Option<String>,
+    /// Base URL of a Central-compatible controller API.
+    pub api_url: Option<String>,
+    /// Only members the controller has seen recently.
+    pub online_only: bool,
+    pub username: Option<String>,
+}
+
+impl Default for ZerotierQuery {
+    fn default() -> Self {
+        Self {
+            network_ids: Vec::new(),
+            api_token: None,
+            api_url: None,
+            online_only: true,
+            username: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalNetwork {
+    nwid: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    assigned_addresses: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Member {
+    node_id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    last_seen: Option<u64>,
+    #[serde(default)]
+    last_online: Option<u64>,
+    #[serde(default)]
+    config: Option<MemberConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemberConfig {
+    #[serde(default)]
+    authorized: bool,
+    #[serde(default)]
+    ip_assignments: Vec<String>,
+}
+
+/// A network to list, with this node's own addresses on it.
+#[derive(Debug, Clone, Default)]
+struct Network {
+    id: String,
+    name: String,
+    own_addresses: Vec<String>,
+}
+
+fn program() -> &'static str {
+    if cfg!(windows) {
+        "zerotier-cli.bat"
+    } else {
+        "zerotier-cli"
+    }
+}
+
+fn parse_local_networks(json: &[u8]) -> Result<Vec<Network>> {
+    let networks: Vec<LocalNetwork> =
+        serde_json::from_slice(json).context("Unexpected `zerotier-cli listnetworks` output")?;
+    Ok(networks
+        .into_iter()
+        .map(|network| Network {
+            id: network.nwid,
+            name: network.name,
+            // `10.147.17.5/24` -> `10.147.17.5`
+            own_addresses: network
+                .assigned_addresses
+                .iter()
+                .map(|address| address.split('/').next().unwrap_or(address).to_string())
+                .collect(),
+        })
+        .collect())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Maps a network's member list to connections. Unauthorized members, this
+/// node, members without an address and (with `online_only`) members not
+/// seen recently are left out.
+fn parse_members(
+    json: &[u8],
+    network: &Network,
+    query: &ZerotierQuery,
+    now: u64,
+) -> Result<Vec<ParsedSshConnection>> {
+    let members: Vec<Member> =
+        serde_json::from_slice(json).context("Unexpected ZeroTier member list")?;
+    let username = username_for(&query.username);
+    let mut connections = Vec::new();
+    for member in members {
+        let Some(config) = member.config.filter(|config| config.authorized) else {
+            continue;
+        };
+        let Some(address) = config
+            .ip_assignments
+            .iter()
+            .find(|ip| ip.contains('.'))
+            .or(config.ip_assignments.first())
+        else {
+            continue;
+        };
+        if network.own_addresses.contains(address) {
+            continue;
+        }
+        let seen = member.last_seen.or(member.last_online).unwrap_or(0);
+        if query.online_only && now.saturating_sub(seen) > ONLINE_WINDOW_MS {
+            continue;
+        }
+        let name = if member.name.trim().is_empty() {
+            member.node_id.clone()
+        } else {
+            member.name.trim().to_string()
+        };
+        let mut tags = vec!["zerotier".to_string()];
+        if !network.name.is_empty() {
+            tags.push(network.name.clone());
+        }
+        connections.push(ParsedSshConnection {
+            id: format!("zerotier_{}_{}", network.id, member.node_id),
+            name: name.clone(),
+            host: address.clone(),
+            username: username.clone(),
+            port: 22,
+            private_key_path: None,
+            jump_server_alias: None,
+            jump_server_id: None,
+            aliases: vec![name],
+            identities_only: None,
+            tags,
+            folder: None,
+        });
+    }
+    Ok(connections)
+}
+
+pub async fn discover(query: &ZerotierQuery) -> Result<Vec<ParsedSshConnection>> {
+    let token = crate::cloud_import::non_empty(&query.api_token)
+        .map(str::to_string)
+        .or_else(|| {
+            std::env::var(TOKEN_ENV)
+                .ok()
+                .filter(|t| !t.trim().is_empty())
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "A ZeroTier Central API token is required (set {} or pass apiToken)",
+                TOKEN_ENV
+            )
+        })?;
+    let api_url = crate::cloud_import::non_empty(&query.api_url)
+        .unwrap_or(CENTRAL_API)
+        .trim_end_matches('/')
+        .to_string();
+
+    // Joined networks also tell which members are this node.
+    let joined = match crate::cloud_import::run_cli(
+        program(),
+        &["-j".to_string(), "listnetworks".to_string()],
+    )
+    .await
+    {
+        Ok(stdout) => parse_local_networks(&stdout)?,
+        Err(e) if query.network_ids.is_empty() => return Err(e),
+        Err(_) => Vec::new(),
+    };
+    let networks: Vec<Network> = if query.network_ids.is_empty() {
+        joined
+    } else {
+        query
+            .network_ids
+            .iter()
+            .map(|id| id.trim())
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                joined
+                    .iter()
+                    .find(|network| network.id == id)
+                    .cloned()
+                    .unwrap_or_else(|| Network {
+                        id: id.to_string(),
+                        ..Default::default()
+                    })
+            })
+            .collect()
+    };
+    if networks.is_empty() {
+        bail!("This node has not joined any ZeroTier network");
+    }
+
+    crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
+    let client = crate::http_client::builder().timeout(API_TIMEOUT).build()?;
+    let now = now_ms();
+    let mut connections = Vec::new();
+    for network in &networks {
+        let response = client
+            .get(format!("{}/network/{}/member", api_url, network.id))
+            .header("Authorization", format!("token {}", token))
+            .send()
+            .await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            bail!("The ZeroTier API token cannot read network {}", network.id);
+        }
+        if !status.is_success() {
+            bail!("ZeroTier API error for network {}: {}", network.id, status);
+        }
+        let body = response.bytes().await?;
+        connections.extend(parse_members(&body, network, query, now)?);
+    }
+    Ok(connections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn members() -> String {
+        format!(
+            r#"[
+              {{"nodeId": "aa01", "name": "web", "lastSeen": {recent},
+                "config": {{"authorized": true, "ipAssignments": ["fd00::1", "10.147.17.20"]}}}},
+              {{"nodeId": "aa02", "name": "", "lastSeen": {old},
+                "config": {{"authorized": true, "ipAssignments": ["10.147.17.21"]}}}},
+              {{"nodeId": "aa03", "name": "self", "lastSeen": {recent},
+                "config": {{"authorized": true, "ipAssignments": ["10.147.17.5"]}}}},
+              {{"nodeId": "aa04", "name": "pending", "lastSeen": {recent},
+                "config": {{"authorized": false, "ipAssignments": ["10.147.17.22"]}}}}
+            ]"#,
+            recent = NOW - 60_000,
+            old = NOW - 2 * ONLINE_WINDOW_MS,
+        )
+    }
+
+    #[test]
+    fn reads_joined_networks() {
+        let networks = parse_local_networks(
+            br#"[{"nwid": "8056c2e21c000001", "name": "home", "status": "OK",
+                  "assignedAddresses": ["10.147.17.5/24", "fd00::5/88"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(networks[0].id, "8056c2e21c000001");
+        assert_eq!(networks[0].own_addresses, ["10.147.17.5", "fd00::5"]);
+    }
+
+    #[test]
+    fn maps_authorized_members_to_connections() {
+        let network = Network {
+            id: "8056c2e21c000001".to_string(),
+            name: "home".to_string(),
+            own_addresses: vec!["10.147.17.5".to_string()],
+        };
+        let query = ZerotierQuery {
+            username: Some("pi".to_string()),
+            ..Default::default()
+        };
+        let connections = parse_members(members().as_bytes(), &network, &query, NOW).unwrap();
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].id, "zerotier_8056c2e21c000001_aa01");
+        assert_eq!(connections[0].host, "10.147.17.20");
+        assert_eq!(connections[0].username, "pi");
+        assert_eq!(connections[0].tags, ["zerotier", "home"]);
+
+        let all = ZerotierQuery {
+            online_only: false,
+            ..Default::default()
+        };
+        let connections = parse_members(members().as_bytes(), &network, &all, NOW).unwrap();
+        assert_eq!(connections.len(), 2);
+        assert_eq!(connections[1].name, "aa02");
+    }
+}
@@ -0,0 +1,174 @@
+//! Mosh terminals.
+//!
+//! The server side is bootstrapped exactly like the `mosh` wrapper script does:
+//! `mosh-server new` runs over the already-authenticated SSH session and prints
+//! `MOSH CONNECT <port> <key>`. The client side is the stock `mosh-client`
+//! (bundled next to the app binary, else from `PATH`) running in a local PTY
+//! with `MOSH_KEY` set, so its output reaches the frontend through the normal
+//! terminal output path and SSP roaming/prediction behave exactly as upstream.
+
+use crate::ai::tool_exec_support::shell_quote;
+use crate::types::MoshOptions;
+use anyhow::{anyhow, Result};
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+const DEFAULT_LOCALE: &str = "LANG=en_US.UTF-8";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoshConnect {
+    pub port: u16,
+    /// 22-character base64 AES key; handed to the client via `MOSH_KEY`, never argv.
+    pub key: String,
+}
+
+/// The command run over SSH to start a detached `mosh-server` for this login.
+pub fn server_command(options: &MoshOptions) -> String {
+    let server = options
+        .server_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(shell_quote)
+        .unwrap_or_else(|| "mosh-server".to_string());
+    let locale = options
+        .locale
+        .as_deref()
+        .map(str::trim)
+        .filter(|locale| !locale.is_empty())
+        .unwrap_or(DEFAULT_LOCALE);
+    // -s binds to the address the SSH client connected to; -c 256 advertises 256 colours.
+    let mut command = format!("{} new -s -c 256 -l {}", server, shell_quote(locale));
+    if let Some(range) = options
+        .port_range
+        .as_deref()
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+    {
+        command.push_str(&format!(" -p {}", shell_quote(range)));
+    }
+    command.push_str(" 2>&1");
+    command
+}
+
+/// Finds the `MOSH CONNECT <port> <key>` line in `mosh-server new` output.
+pub fn parse_connect(output: &str) -> Result<MoshConnect> {
+    for line in output.lines() {
+        let mut parts = line.trim().split_whitespace();
+        if parts.next() != Some("MOSH") || parts.next() != Some("CONNECT") {
+            continue;
+        }
+        let port = parts
+            .next()
+            .and_then(|p| p.parse::<u16>().ok())
+            .ok_or_else(|| anyhow!("mosh-server reported an invalid port"))?;
+        let key = parts
+            .next()
+            .filter(|k| {
+                k.len() == 22
+                    && k.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+            })
+            .ok_or_else(|| anyhow!("mosh-server reported an invalid session key"))?;
+        return Ok(MoshConnect {
+            port,
+            key: key.to_string(),
+        });
+    }
+    let hint = output.trim();
+    if hint.contains("not found") {
+        return Err(anyhow!("mosh-server is not installed on the server"));
+    }
+    Err(anyhow!(
+        "mosh-server did not start: {}",
+        if hint.is_empty() { "no output" } else { hint }
+    ))
+}
+
+fn client_name() -> &'static str {
+    if cfg!(windows) {
+        "mosh-client.exe"
+    } else {
+        "mosh-client"
+    }
+}
+
+/// `mosh-client` shipped next to the app executable, else the first one on `PATH`.
+pub fn client_program() -> Result<PathBuf> {
+    let bundled = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(client_name())))
+        .filter(|path| path.is_file());
+    if let Some(path) = bundled {
+        return Ok(path);
+    }
+    std::env::var_os("PATH")
+        .and_then(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(client_name()))
+                .find(|path| path.is_file())
+        })
+        .ok_or_else(|| anyhow!("mosh-client was not found; install mosh to use mosh terminals"))
+}
+
+/// Everything needed to spawn the local client process.
+#[derive(Debug, Clone)]
+pub struct MoshClientCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub key: String,
+}
+
+impl MoshClientCommand {
+    pub fn new(program: PathBuf, server: IpAddr, connect: MoshConnect) -> Self {
+        Self {
+            program,
+            args: vec![server.to_string(), connect.port.to_string()],
+            key: connect.key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_server_command() {
+        assert_eq!(
+            server_command(&MoshOptions::default()),
+            "mosh-server new -s -c 256 -l 'LANG=en_US.UTF-8' 2>&1"
+        );
+        let options = MoshOptions {
+            server_path: Some("/opt/mosh/bin/mosh-server".to_string()),
+            port_range: Some("60000:60010".to_string()),
+            locale: Some("LC_ALL=C.UTF-8".to_string()),
+        };
+        assert_eq!(
+            server_command(&options),
+            "'/opt/mosh/bin/mosh-server' new -s -c 256 -l 'LC_ALL=C.UTF-8' -p '60000:60010' 2>&1"
+        );
+    }
+
+    #[test]
+    fn parses_connect_line() {
+        let output = "\r\nMOSH CONNECT 60001 4NeCCgvZFe2RnPgrcU1PQw\r\n\r\n\
+                      mosh-server (mosh 1.4.0) [build mosh 1.4.0]\r\n\
+                      [mosh-server detached, pid = 4242]\r\n";
+        assert_eq!(
+            parse_connect(output).unwrap(),
+            MoshConnect {
+                port: 60001,
+                key: "4NeCCgvZFe2RnPgrcU1PQw".to_string(),
+            }
+        );
+        assert!(parse_connect("MOSH CONNECT 60001 short")
+            .unwrap_err()
+            .to_string()
+            .contains("session key"));
+        assert!(parse_connect("sh: mosh-server: not found")
+            .unwrap_err()
+            .to_string()
+            .contains("not installed"));
+    }
+}
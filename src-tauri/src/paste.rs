@@ -0,0 +1,194 @@
+//! Large terminal writes: paced chunks and bracketed paste.
+//!
+//! A multi-megabyte paste sent as one channel write can stall the SSH window
+//! or overrun the remote line discipline, so `PtyManager::write` sends input of
+//! `CHUNKED_WRITE_BYTES` or more in `CHUNK_BYTES` pieces with a short pause
+//! after each. The terminal's input lock is held meanwhile, so keys typed
+//! during the paste arrive after it. When the program in the terminal has
+//! turned on bracketed paste (`CSI ? 2004 h`), such a write is wrapped in
+//! `CSI 200 ~` / `CSI 201 ~` unless it already is, so a shell inserts it
+//! instead of running it line by line. Writes of `PROGRESS_MIN_BYTES` or more
+//! report progress after each chunk.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Writes this large are split into chunks.
+pub const CHUNKED_WRITE_BYTES: usize = 64 * 1024;
+pub const CHUNK_BYTES: usize = 16 * 1024;
+/// Pause after each chunk so the remote side can drain its input.
+pub const CHUNK_PAUSE: Duration = Duration::from_millis(4);
+/// Writes this large emit `terminal:paste-progress`.
+pub const PROGRESS_MIN_BYTES: usize = 1024 * 1024;
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+const MODE_ON: &[u8] = b"\x1b[?2004h";
+const MODE_OFF: &[u8] = b"\x1b[?2004l";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteProgress {
+    pub term_id: String,
+    pub sent: usize,
+    pub total: usize,
+}
+
+#[derive(Default)]
+struct PasteState {
+    bracketed: bool,
+    input_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+/// Bracketed paste mode and input lock per `term_id`.
+#[derive(Clone, Default)]
+pub struct PasteModes(Arc<Mutex<HashMap<String, PasteState>>>);
+
+impl PasteModes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follows the mode switches in terminal output; the last one in `data` wins.
+    pub fn output(&self, term_id: &str, data: &[u8]) {
+        let on = rfind(data, MODE_ON);
+        let off = rfind(data, MODE_OFF);
+        if on.is_none() && off.is_none() {
+            return;
+        }
+        if let Ok(mut terminals) = self.0.lock() {
+            terminals.entry(term_id.to_string()).or_default().bracketed = on > off;
+        }
+    }
+
+    pub fn bracketed(&self, term_id: &str) -> bool {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|terminals| terminals.get(term_id).map(|state| state.bracketed))
+            .unwrap_or(false)
+    }
+
+    /// Held for the duration of each write to the terminal.
+    pub fn input_lock(&self, term_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.0
+            .lock()
+            .map(|mut terminals| {
+                terminals
+                    .entry(term_id.to_string())
+                    .or_default()
+                    .input_lock
+                    .clone()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn remove(&self, term_id: &str) {
+        if let Ok(mut terminals) = self.0.lock() {
+            terminals.remove(term_id);
+        }
+    }
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+}
+
+/// Wraps a large multi-line write in bracketed paste markers when the terminal
+/// asked for them. End markers inside the text are dropped so pasted content
+/// cannot end the paste early and have the rest executed.
+pub fn prepare(input: Vec<u8>, bracketed: bool) -> Vec<u8> {
+    if !bracketed
+        || input.len() < CHUNKED_WRITE_BYTES
+        || input.starts_with(PASTE_START)
+        || (!input.contains(&b'\n') && !input.contains(&b'\r'))
+    {
+        return input;
+    }
+    let mut wrapped = Vec::with_capacity(input.len() + PASTE_START.len() + PASTE_END.len());
+    wrapped.extend_from_slice(PASTE_START);
+    let mut rest = &input[..];
+    while let Some(at) = rest
+        .windows(PASTE_END.len())
+        .position(|window| window == PASTE_END)
+    {
+        wrapped.extend_from_slice(&rest[..at]);
+        rest = &rest[at + PASTE_END.len()..];
+    }
+    wrapped.extend_from_slice(rest);
+    wrapped.extend_from_slice(PASTE_END);
+    wrapped
+}
+
+/// The pieces `input` is written in: itself when small, else `CHUNK_BYTES` chunks.
+pub fn chunks(input: &[u8]) -> std::slice::Chunks<'_, u8> {
+    let size = if input.len() >= CHUNKED_WRITE_BYTES {
+        CHUNK_BYTES
+    } else {
+        input.len().max(1)
+    };
+    input.chunks(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_bracketed_paste_mode() {
+        let modes = PasteModes::new();
+        assert!(!modes.bracketed("t1"));
+        modes.output("t1", b"\x1b[?2004h$ ");
+        assert!(modes.bracketed("t1"));
+        modes.output("t1", b"plain output");
+        assert!(modes.bracketed("t1"));
+        modes.output("t1", b"\x1b[?2004h\r\n\x1b[?2004lvim");
+        assert!(!modes.bracketed("t1"));
+        modes.remove("t1");
+        assert!(!modes.bracketed("t1"));
+    }
+
+    #[test]
+    fn wraps_only_large_multiline_writes() {
+        let script = "echo hi\n".repeat(CHUNKED_WRITE_BYTES / 8).into_bytes();
+        assert_eq!(prepare(script.clone(), false), script);
+        assert_eq!(prepare(b"ls\r".to_vec(), true), b"ls\r");
+        assert_eq!(
+            prepare(vec![b'x'; CHUNKED_WRITE_BYTES], true).len(),
+            CHUNKED_WRITE_BYTES
+        );
+
+        let wrapped = prepare(script.clone(), true);
+        assert!(wrapped.starts_with(PASTE_START) && wrapped.ends_with(PASTE_END));
+        assert_eq!(
+            wrapped.len(),
+            script.len() + PASTE_START.len() + PASTE_END.len()
+        );
+        assert_eq!(prepare(wrapped.clone(), true), wrapped);
+    }
+
+    #[test]
+    fn strips_end_markers_from_wrapped_text() {
+        let mut input = b"echo a\n\x1b[201~rm -rf ~\n".to_vec();
+        input.resize(CHUNKED_WRITE_BYTES, b' ');
+        let wrapped = prepare(input, true);
+        assert_eq!(
+            rfind(&wrapped, PASTE_END),
+            Some(wrapped.len() - PASTE_END.len())
+        );
+    }
+
+    #[test]
+    fn splits_large_writes() {
+        assert_eq!(chunks(b"ls\r").count(), 1);
+        assert_eq!(chunks(b"").count(), 0);
+        let large = vec![b'a'; CHUNKED_WRITE_BYTES + 1];
+        let pieces: Vec<&[u8]> = chunks(&large).collect();
+        assert_eq!(pieces.len(), CHUNKED_WRITE_BYTES / CHUNK_BYTES + 1);
+        assert!(pieces.iter().all(|piece| piece.len() <= CHUNK_BYTES));
+    }
+}
@@ -0,0 +1,167 @@
+//! File names that refer to the same entry on some file systems.
+//!
+//! macOS stores names decomposed (NFD) and ignores case, Windows ignores case,
+//! Linux keeps names byte-for-byte. Copied naively, `café` written on a Mac
+//! arrives on Linux as a second, differently encoded `café`, and `README` and
+//! `readme` from Linux overwrite each other on a Mac. Recursive transfers use
+//! this to map a name onto the equivalent one already at the destination and
+//! to find sibling names the destination cannot keep apart.
+
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+
+/// How a destination file system compares names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NameRules {
+    pub fold_case: bool,
+    /// NFC and NFD spellings are the same entry (APFS/HFS+).
+    pub fold_normalization: bool,
+}
+
+impl NameRules {
+    /// Rules for a server from its detected OS.
+    pub fn for_os(os: Option<&str>) -> Self {
+        match os.map(|os| os.to_ascii_lowercase()).as_deref() {
+            Some("macos") | Some("darwin") => Self {
+                fold_case: true,
+                fold_normalization: true,
+            },
+            Some("windows") => Self {
+                fold_case: true,
+                fold_normalization: false,
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Rules for this machine's default file system.
+    pub fn local() -> Self {
+        Self::for_os(Some(std::env::consts::OS))
+    }
+
+    /// The name as this destination compares it.
+    pub fn key(&self, name: &str) -> String {
+        key(name, self.fold_case, self.fold_normalization)
+    }
+}
+
+fn key(name: &str, fold_case: bool, fold_normalization: bool) -> String {
+    let name: String = if fold_normalization {
+        name.nfc().collect()
+    } else {
+        name.to_string()
+    };
+    if fold_case {
+        name.to_lowercase()
+    } else {
+        name
+    }
+}
+
+/// Plan for one directory's children.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChildPlan {
+    /// `(source name, destination name)` for each child to transfer.
+    pub transfer: Vec<(String, String)>,
+    /// `(source name, name it collides with)` for children left out.
+    pub conflicts: Vec<(String, String)>,
+}
+
+/// Decides what each source child is called at the destination.
+///
+/// A child whose name matches an existing destination entry up to Unicode
+/// normalization (and case, where the destination folds it) is written to
+/// that entry instead of creating a look-alike next to it, unless the source
+/// has that exact name too. Source siblings that the destination would store
+/// as one entry are conflicts: the first, in source order, is transferred and
+/// the others are left out.
+pub fn plan_children(names: &[String], existing: &[String], rules: NameRules) -> ChildPlan {
+    let source: HashSet<&str> = names.iter().map(String::as_str).collect();
+    let existing: HashMap<String, &String> = existing
+        .iter()
+        .map(|name| (key(name, rules.fold_case, true), name))
+        .collect();
+    let mut taken: HashMap<String, &String> = HashMap::new();
+    let mut plan = ChildPlan::default();
+    for name in names {
+        if let Some(first) = taken.get(&rules.key(name)) {
+            plan.conflicts.push((name.clone(), (*first).clone()));
+            continue;
+        }
+        taken.insert(rules.key(name), name);
+        let destination = existing
+            .get(&key(name, rules.fold_case, true))
+            .filter(|existing| !source.contains(existing.as_str()))
+            .map_or(name, |existing| *existing);
+        plan.transfer.push((name.clone(), destination.clone()));
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAFE_NFC: &str = "caf\u{e9}";
+    const CAFE_NFD: &str = "cafe\u{301}";
+    const LINUX: NameRules = NameRules {
+        fold_case: false,
+        fold_normalization: false,
+    };
+    const MACOS: NameRules = NameRules {
+        fold_case: true,
+        fold_normalization: true,
+    };
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn rules_follow_the_os() {
+        assert_eq!(NameRules::for_os(Some("macos")), MACOS);
+        assert!(NameRules::for_os(Some("Windows")).fold_case);
+        assert_eq!(NameRules::for_os(Some("ubuntu")), LINUX);
+        assert_eq!(NameRules::for_os(None), LINUX);
+        assert_eq!(MACOS.key(CAFE_NFD), MACOS.key(&CAFE_NFC.to_uppercase()));
+    }
+
+    #[test]
+    fn reuses_equivalent_destination_names() {
+        // Mac upload into a Linux directory that already has the NFC spelling.
+        let plan = plan_children(&names(&[CAFE_NFD, "new"]), &names(&[CAFE_NFC]), LINUX);
+        assert_eq!(
+            plan.transfer,
+            [
+                (CAFE_NFD.to_string(), CAFE_NFC.to_string()),
+                ("new".to_string(), "new".to_string())
+            ]
+        );
+        assert!(plan.conflicts.is_empty());
+
+        // Case only matters where the destination folds it.
+        let plan = plan_children(&names(&["Makefile"]), &names(&["makefile"]), LINUX);
+        assert_eq!(plan.transfer[0].1, "Makefile");
+        let plan = plan_children(&names(&["Makefile"]), &names(&["makefile"]), MACOS);
+        assert_eq!(plan.transfer[0].1, "makefile");
+    }
+
+    #[test]
+    fn reports_siblings_the_destination_cannot_keep_apart() {
+        let source = names(&["README", "readme", CAFE_NFC, CAFE_NFD]);
+        let plan = plan_children(&source, &names(&[CAFE_NFC]), LINUX);
+        assert!(plan.conflicts.is_empty());
+        // Both spellings exist in the source, so neither is redirected.
+        assert!(plan.transfer.iter().all(|(from, to)| from == to));
+
+        let plan = plan_children(&source, &[], MACOS);
+        assert_eq!(plan.transfer.len(), 2);
+        assert_eq!(
+            plan.conflicts,
+            [
+                ("readme".to_string(), "README".to_string()),
+                (CAFE_NFD.to_string(), CAFE_NFC.to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Remote shells wrapped in tmux or screen so they outlive the SSH connection.
+//!
+//! Each connection gets one named session (`zync-<id>`). Opening a terminal
+//! attaches to it if it exists and creates it otherwise, so closing the app or
+//! dropping the network leaves running jobs alone and the next terminal picks
+//! up where the last one stopped.
+//!
+//! [`LIST_COMMAND`] / [`parse_sessions`] also surface every other tmux or
+//! screen session on the server so forgotten long-running work can be found
+//! and reattached.
+
+use crate::ai::tool_exec_support::shell_quote;
+use crate::types::PersistentSessionMode;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+/// Prints one line per installed multiplexer.
+pub const DETECT_COMMAND: &str =
+    "command -v tmux >/dev/null 2>&1 && echo tmux; command -v screen >/dev/null 2>&1 && echo screen; true";
+
+/// Picks a multiplexer from the output of [`DETECT_COMMAND`], honouring the configured mode.
+pub fn choose(mode: PersistentSessionMode, detected: &str) -> Option<Multiplexer> {
+    let has = |name: &str| detected.lines().any(|line| line.trim() == name);
+    match mode {
+        PersistentSessionMode::Tmux => has("tmux").then_some(Multiplexer::Tmux),
+        PersistentSessionMode::Screen => has("screen").then_some(Multiplexer::Screen),
+        PersistentSessionMode::Auto => {
+            if has("tmux") {
+                Some(Multiplexer::Tmux)
+            } else if has("screen") {
+                Some(Multiplexer::Screen)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// tmux rejects `.` and `:` in session names; keep the rest readable.
+pub fn session_name(connection_id: &str) -> String {
+    let id: String = connection_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("zync-{}", id)
+}
+
+/// Command that attaches to the named session, creating it (with `shell`, if given) first.
+pub fn launch_command(multiplexer: Multiplexer, name: &str, shell: Option<&str>) -> String {
+    let shell = shell
+        .map(|s| format!(" {}", shell_quote(s)))
+        .unwrap_or_default();
+    match multiplexer {
+        Multiplexer::Tmux => format!("exec tmux new-session -A -s {}{}", shell_quote(name), shell),
+        // -xRR: attach (sharing with other displays) or create.
+        Multiplexer::Screen => format!("exec screen -xRR -S {}{}", shell_quote(name), shell),
+    }
+}
+
+/// Lists tmux sessions (tab-separated via `-F`) and `screen -ls`, each after a marker line.
+pub const LIST_COMMAND: &str = "if command -v tmux >/dev/null 2>&1; then echo '@@tmux'; \
+     tmux list-sessions -F '#{session_name}\t#{session_windows}\t#{session_attached}\t#{session_created}' 2>/dev/null; fi; \
+     if command -v screen >/dev/null 2>&1; then echo '@@screen'; screen -ls 2>/dev/null; fi; true";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSession {
+    pub multiplexer: Multiplexer,
+    /// What `attach_command` needs: the tmux session name, or screen's `pid.name`.
+    pub id: String,
+    pub name: String,
+    /// tmux only; screen does not report window counts in `-ls`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub windows: Option<u32>,
+    pub attached: bool,
+    /// Unix seconds (tmux).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// screen's own rendering of the start time, in the server's locale.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_label: Option<String>,
+    /// Started by zync's `persistent_session` (named by [`session_name`]).
+    pub managed: bool,
+}
+
+/// Parses the output of [`LIST_COMMAND`].
+pub fn parse_sessions(output: &str) -> Vec<RemoteSession> {
+    let mut sessions = Vec::new();
+    let mut section = None;
+    for line in output.lines() {
+        match line.trim() {
+            "@@tmux" => section = Some(Multiplexer::Tmux),
+            "@@screen" => section = Some(Multiplexer::Screen),
+            _ => {
+                let parsed = match section {
+                    Some(Multiplexer::Tmux) => parse_tmux_line(line),
+                    Some(Multiplexer::Screen) => parse_screen_line(line),
+                    None => None,
+                };
+                sessions.extend(parsed);
+            }
+        }
+    }
+    sessions
+}
+
+fn parse_tmux_line(line: &str) -> Option<RemoteSession> {
+    let mut fields = line.split('\t');
+    let name = fields.next().filter(|name| !name.is_empty())?.to_string();
+    let windows = fields.next()?.trim().parse().ok()?;
+    let attached = fields.next()?.trim().parse::<u32>().ok()? > 0;
+    let created_at = fields
+        .next()
+        .and_then(|created| created.trim().parse().ok());
+    Some(RemoteSession {
+        multiplexer: Multiplexer::Tmux,
+        id: name.clone(),
+        managed: name.starts_with("zync-"),
+        name,
+        windows: Some(windows),
+        attached,
+        created_at,
+        created_label: None,
+    })
+}
+
+/// `\t12345.work\t(10/01/2024 09:12:44 AM)\t(Detached)`; the date is optional.
+fn parse_screen_line(line: &str) -> Option<RemoteSession> {
+    if !line.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut fields = line.split('\t').map(str::trim).filter(|f| !f.is_empty());
+    let id = fields.next()?;
+    let (pid, name) = id.split_once('.')?;
+    if pid.is_empty() || !pid.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let parens: Vec<&str> = fields
+        .filter_map(|f| f.strip_prefix('(')?.strip_suffix(')'))
+        .collect();
+    let state = parens.last()?.to_ascii_lowercase();
+    let created_label = (parens.len() > 1).then(|| parens[0].to_string());
+    Some(RemoteSession {
+        multiplexer: Multiplexer::Screen,
+        id: id.to_string(),
+        name: name.to_string(),
+        windows: None,
+        attached: state.contains("attached") && !state.contains("detached"),
+        created_at: None,
+        created_label,
+        managed: name.starts_with("zync-"),
+    })
+}
+
+/// Command that joins an existing session without detaching anyone else.
+pub fn attach_command(multiplexer: Multiplexer, id: &str) -> String {
+    match multiplexer {
+        // `=` makes tmux match the name exactly instead of as a prefix/pattern.
+        Multiplexer::Tmux => format!(
+            "exec tmux attach-session -t {}",
+            shell_quote(&format!("={}", id))
+        ),
+        Multiplexer::Screen => format!("exec screen -x {}", shell_quote(id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_prefers_tmux_then_screen() {
+        assert_eq!(
+            choose(PersistentSessionMode::Auto, "tmux\nscreen\n"),
+            Some(Multiplexer::Tmux)
+        );
+        assert_eq!(
+            choose(PersistentSessionMode::Auto, "screen\n"),
+            Some(Multiplexer::Screen)
+        );
+        assert_eq!(choose(PersistentSessionMode::Auto, ""), None);
+        assert_eq!(choose(PersistentSessionMode::Tmux, "screen\n"), None);
+    }
+
+    #[test]
+    fn builds_attach_or_create_commands() {
+        let name = session_name("a1b2.c3:d4");
+        assert_eq!(name, "zync-a1b2-c3-d4");
+        assert_eq!(
+            launch_command(Multiplexer::Tmux, &name, None),
+            "exec tmux new-session -A -s 'zync-a1b2-c3-d4'"
+        );
+        assert_eq!(
+            launch_command(Multiplexer::Screen, &name, Some("/bin/zsh")),
+            "exec screen -xRR -S 'zync-a1b2-c3-d4' '/bin/zsh'"
+        );
+    }
+
+    #[test]
+    fn parses_tmux_and_screen_listings() {
+        let output = "@@tmux\nzync-web-1\t3\t1\t1727773200\nbuild\t1\t0\t1727776800\n@@screen\n\
+                      There are screens on:\n\t4242.backup\t(10/01/2024 09:12:44 AM)\t(Detached)\n\
+                      \t977.pts-0.web-1\t(Multi, attached)\n2 Sockets in /run/screen/S-me.\n";
+        let sessions = parse_sessions(output);
+        assert_eq!(sessions.len(), 4);
+        assert_eq!(sessions[0].name, "zync-web-1");
+        assert!(sessions[0].managed && sessions[0].attached);
+        assert_eq!(sessions[0].windows, Some(3));
+        assert_eq!(sessions[1].created_at, Some(1727776800));
+        assert!(!sessions[1].attached);
+
+        assert_eq!(sessions[2].multiplexer, Multiplexer::Screen);
+        assert_eq!(sessions[2].id, "4242.backup");
+        assert_eq!(sessions[2].name, "backup");
+        assert_eq!(
+            sessions[2].created_label.as_deref(),
+            Some("10/01/2024 09:12:44 AM")
+        );
+        assert!(!sessions[2].attached);
+        assert_eq!(sessions[3].name, "pts-0.web-1");
+        assert!(sessions[3].attached);
+
+        // No multiplexer output at all (e.g. "no server running").
+        assert!(
+            parse_sessions("@@tmux\n@@screen\nNo Sockets found in /run/screen/S-me.\n").is_empty()
+        );
+    }
+
+    #[test]
+    fn attaches_without_creating() {
+        assert_eq!(
+            attach_command(Multiplexer::Tmux, "build.1"),
+            "exec tmux attach-session -t '=build.1'"
+        );
+        assert_eq!(
+            attach_command(Multiplexer::Screen, "4242.backup"),
+            "exec screen -x '4242.backup'"
+        );
+    }
+}
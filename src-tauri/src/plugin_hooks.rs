@@ -0,0 +1,182 @@
+//! Lifecycle hooks for plugins.
+//!
+//! A plugin lists the hooks it wants in its manifest
+//! (`"hooks": ["connection-opened", "transfer-completed"]`) and the backend
+//! emits `plugin-hook:<name>` when that happens, with the enabled plugins that
+//! registered for it in `pluginIds`. Nothing is emitted for a hook no plugin
+//! registered for. Hooks only notify: `before-disconnect` goes out just before
+//! a connection's terminals and session are closed, but cannot delay or stop it.
+
+use crate::plugins::PluginScanner;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Emitter};
+
+/// Plugin ids registered for each hook.
+type Registrations = HashMap<Hook, Vec<String>>;
+
+/// `None` until read from the manifests.
+static REGISTRATIONS: LazyLock<Mutex<Option<Registrations>>> = LazyLock::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hook {
+    ConnectionOpened,
+    TerminalCreated,
+    TransferCompleted,
+    BeforeDisconnect,
+}
+
+impl Hook {
+    const ALL: [Hook; 4] = [
+        Hook::ConnectionOpened,
+        Hook::TerminalCreated,
+        Hook::TransferCompleted,
+        Hook::BeforeDisconnect,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::ConnectionOpened => "connection-opened",
+            Hook::TerminalCreated => "terminal-created",
+            Hook::TransferCompleted => "transfer-completed",
+            Hook::BeforeDisconnect => "before-disconnect",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|hook| hook.name() == name)
+    }
+}
+
+/// Payload of `plugin-hook:connection-opened`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionOpened<'a> {
+    pub connection_id: &'a str,
+    pub name: &'a str,
+    pub host: &'a str,
+    pub port: u16,
+    pub username: &'a str,
+}
+
+/// Payload of `plugin-hook:terminal-created`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalCreated<'a> {
+    pub term_id: &'a str,
+    pub connection_id: &'a str,
+}
+
+/// Payload of `plugin-hook:before-disconnect`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeforeDisconnect<'a> {
+    pub connection_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HookEvent<T> {
+    hook: &'static str,
+    plugin_ids: Vec<String>,
+    #[serde(flatten)]
+    payload: T,
+}
+
+/// Reads the registrations from the manifests again on the next hook; called
+/// whenever plugins are installed, removed, enabled or disabled.
+pub fn invalidate() {
+    if let Ok(mut registrations) = REGISTRATIONS.lock() {
+        *registrations = None;
+    }
+}
+
+fn registrations(app: &AppHandle) -> Registrations {
+    let mut registrations = HashMap::new();
+    let plugins = match PluginScanner::scan(app) {
+        Ok(plugins) => plugins,
+        Err(e) => {
+            crate::log_error!("[Plugins] Failed to read hook registrations: {}", e);
+            return registrations;
+        }
+    };
+    let safe_mode = crate::safe_mode::is_active();
+    for plugin in plugins {
+        if !plugin.enabled || (safe_mode && !plugin.path.starts_with("builtin://")) {
+            continue;
+        }
+        for name in plugin.manifest.hooks.iter().flatten() {
+            match Hook::parse(name) {
+                Some(hook) => registrations
+                    .entry(hook)
+                    .or_default()
+                    .push(plugin.manifest.id.clone()),
+                None => crate::log_error!(
+                    "[Plugins] {} registers unknown hook {}",
+                    plugin.manifest.id,
+                    name
+                ),
+            }
+        }
+    }
+    registrations
+}
+
+fn registered(app: &AppHandle, hook: Hook) -> Vec<String> {
+    let Ok(mut cache) = REGISTRATIONS.lock() else {
+        return Vec::new();
+    };
+    cache
+        .get_or_insert_with(|| registrations(app))
+        .get(&hook)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Sends `plugin-hook:<name>` to the plugins registered for `hook`.
+pub fn emit<T: Serialize>(app: &AppHandle, hook: Hook, payload: T) {
+    let plugin_ids = registered(app, hook);
+    if plugin_ids.is_empty() {
+        return;
+    }
+    let event = HookEvent {
+        hook: hook.name(),
+        plugin_ids,
+        payload,
+    };
+    if let Err(e) = app.emit(&format!("plugin-hook:{}", hook.name()), event) {
+        crate::log_error!("[Plugins] Failed to emit hook {}: {}", hook.name(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_events_carry_plugins_and_payload_side_by_side() {
+        for hook in Hook::ALL {
+            assert_eq!(Hook::parse(hook.name()), Some(hook));
+        }
+        assert_eq!(Hook::parse("connection-closed"), None);
+
+        let event = HookEvent {
+            hook: Hook::TerminalCreated.name(),
+            plugin_ids: vec!["com.example.notes".to_string()],
+            payload: TerminalCreated {
+                term_id: "t1",
+                connection_id: "c1",
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({
+                "hook": "terminal-created",
+                "pluginIds": ["com.example.notes"],
+                "termId": "t1",
+                "connectionId": "c1",
+            })
+        );
+    }
+}
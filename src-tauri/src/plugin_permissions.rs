@@ -0,0 +1,349 @@
+//! Capabilities a plugin declares in its manifest (`permissions`) and the
+//! user's answers to them.
+//!
+//! A plugin gets nothing it did not declare. What it declared is still asked
+//! for once: the first call that needs a capability emits
+//! `plugin-permission:request` and fails, and the answer given through
+//! `plugins_permissions_set` is kept in `plugins.json`. An answer holds for the
+//! scope it was given for, so an update that widens a plugin's folders asks again.
+
+use crate::plugins::{Plugin, PluginScanner};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const REQUEST_EVENT: &str = "plugin-permission:request";
+
+/// Requests already shown and not answered yet, so a busy plugin prompts once.
+static PENDING: LazyLock<Mutex<HashSet<(String, Capability)>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Manifest `permissions`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Permissions {
+    /// Local folders the plugin may use through the `plugin_fs_*` commands.
+    #[serde(default)]
+    pub fs: Vec<FsScope>,
+    /// Open external URLs in plugin windows.
+    #[serde(default)]
+    pub network: bool,
+    /// Run commands on open connections.
+    #[serde(default)]
+    pub exec: bool,
+    /// Read and write remote files on open connections.
+    #[serde(default)]
+    pub ssh: bool,
+    /// Write to terminals, read their scrollback and follow their output.
+    #[serde(default)]
+    pub terminal: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FsScope {
+    /// An absolute folder, or one under the home directory written as `~/...`.
+    pub path: String,
+    #[serde(default)]
+    pub write: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    FsRead,
+    FsWrite,
+    Network,
+    Exec,
+    Ssh,
+    Terminal,
+}
+
+impl Capability {
+    const ALL: [Capability; 6] = [
+        Capability::FsRead,
+        Capability::FsWrite,
+        Capability::Network,
+        Capability::Exec,
+        Capability::Ssh,
+        Capability::Terminal,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Capability::FsRead => "fs.read",
+            Capability::FsWrite => "fs.write",
+            Capability::Network => "network",
+            Capability::Exec => "exec",
+            Capability::Ssh => "ssh",
+            Capability::Terminal => "terminal",
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.key() == key)
+    }
+}
+
+/// The user's answer for one capability, as stored in `plugins.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Grant {
+    pub allowed: bool,
+    /// The folders the answer was given for; empty for other capabilities.
+    #[serde(default)]
+    pub scope: Vec<String>,
+}
+
+/// One declared capability, as listed by `plugins_permissions_get` and sent
+/// with `plugin-permission:request`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionStatus {
+    pub plugin_id: String,
+    pub plugin_name: String,
+    pub capability: &'static str,
+    pub scope: Vec<String>,
+    /// `None` until the user has answered.
+    pub granted: Option<bool>,
+}
+
+impl Permissions {
+    /// The scope declared for `capability`, or `None` when it was not declared.
+    fn declared(&self, capability: Capability) -> Option<Vec<String>> {
+        let folders = |write_only: bool| {
+            let folders: Vec<String> = self
+                .fs
+                .iter()
+                .filter(|scope| !write_only || scope.write)
+                .map(|scope| scope.path.clone())
+                .collect();
+            (!folders.is_empty()).then_some(folders)
+        };
+        match capability {
+            Capability::FsRead => folders(false),
+            Capability::FsWrite => folders(true),
+            Capability::Network => self.network.then(Vec::new),
+            Capability::Exec => self.exec.then(Vec::new),
+            Capability::Ssh => self.ssh.then(Vec::new),
+            Capability::Terminal => self.terminal.then(Vec::new),
+        }
+    }
+
+    fn allows_path(&self, capability: Capability, path: &Path, home: Option<&Path>) -> bool {
+        self.fs
+            .iter()
+            .filter(|scope| capability != Capability::FsWrite || scope.write)
+            .filter_map(|scope| resolve(&scope.path, home).ok())
+            .any(|root| path.starts_with(root))
+    }
+}
+
+/// Makes `raw` absolute and follows links in the part of it that exists, so a
+/// link inside a scope cannot lead out of it.
+fn resolve(raw: &str, home: Option<&Path>) -> Result<PathBuf, String> {
+    let path = if raw == "~" || raw.starts_with("~/") {
+        home.ok_or("Home directory is unknown")?
+            .join(raw.trim_start_matches('~').trim_start_matches('/'))
+    } else {
+        PathBuf::from(raw)
+    };
+    if !path.is_absolute() {
+        return Err(format!("Plugin paths must be absolute: {}", raw));
+    }
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(format!("Plugin paths may not contain '..': {}", raw));
+    }
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(real) = std::fs::canonicalize(existing) {
+            return Ok(missing.iter().rev().fold(real, |acc, name| acc.join(name)));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return Ok(path),
+        }
+    }
+}
+
+/// The plugin, if it may run at all: it exists, is enabled and safe mode is off.
+pub fn require_enabled(app: &AppHandle, plugin_id: &str) -> Result<Plugin, String> {
+    if crate::safe_mode::is_active() {
+        return Err("Plugins do not run in safe mode".to_string());
+    }
+    let plugin = PluginScanner::find(app, plugin_id).map_err(|e| e.to_string())?;
+    if !plugin.enabled {
+        return Err(format!("Plugin {} is disabled", plugin_id));
+    }
+    Ok(plugin)
+}
+
+/// Checks that plugin `plugin_id` may use `capability`, and for filesystem
+/// capabilities that `path` is inside one of its folders. Asks the user when
+/// the capability has not been answered yet.
+pub fn require(
+    app: &AppHandle,
+    plugin_id: &str,
+    capability: Capability,
+    path: Option<&str>,
+) -> Result<Plugin, String> {
+    let plugin = require_enabled(app, plugin_id)?;
+    let name = plugin.manifest.name.clone();
+    let permissions = plugin.manifest.permissions.clone().unwrap_or_default();
+    let Some(scope) = permissions.declared(capability) else {
+        return Err(format!(
+            "Plugin {} did not declare the {} permission",
+            name,
+            capability.key()
+        ));
+    };
+    if let Some(path) = path {
+        let home = app.path().home_dir().ok();
+        let target = resolve(path, home.as_deref())?;
+        if !permissions.allows_path(capability, &target, home.as_deref()) {
+            return Err(format!(
+                "{} is outside the folders plugin {} may use",
+                path, name
+            ));
+        }
+    }
+
+    let grants = PluginScanner::grants(app, plugin_id).map_err(|e| e.to_string())?;
+    match grants.get(capability.key()) {
+        Some(grant) if grant.scope == scope && grant.allowed => Ok(plugin),
+        Some(grant) if grant.scope == scope => Err(format!(
+            "Plugin {} is not allowed to use {}",
+            name,
+            capability.key()
+        )),
+        _ => {
+            let first = PENDING
+                .lock()
+                .map(|mut pending| pending.insert((plugin_id.to_string(), capability)))
+                .unwrap_or(true);
+            if first {
+                let request = PermissionStatus {
+                    plugin_id: plugin_id.to_string(),
+                    plugin_name: name.clone(),
+                    capability: capability.key(),
+                    scope,
+                    granted: None,
+                };
+                let _ = app.emit(REQUEST_EVENT, request);
+            }
+            Err(format!(
+                "Plugin {} is waiting for permission to use {}",
+                name,
+                capability.key()
+            ))
+        }
+    }
+}
+
+/// Records the user's answer for a declared capability.
+pub fn answer(
+    app: &AppHandle,
+    plugin_id: &str,
+    capability: &str,
+    allowed: bool,
+) -> Result<(), String> {
+    let capability = Capability::parse(capability)
+        .ok_or_else(|| format!("Unknown permission {}", capability))?;
+    let plugin = PluginScanner::find(app, plugin_id).map_err(|e| e.to_string())?;
+    let scope = plugin
+        .manifest
+        .permissions
+        .unwrap_or_default()
+        .declared(capability)
+        .ok_or_else(|| {
+            format!(
+                "Plugin {} did not declare the {} permission",
+                plugin_id,
+                capability.key()
+            )
+        })?;
+    PluginScanner::save_grant(app, plugin_id, capability.key(), Grant { allowed, scope })
+        .map_err(|e| e.to_string())?;
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.remove(&(plugin_id.to_string(), capability));
+    }
+    Ok(())
+}
+
+/// Every capability the plugin declared, with the user's answer if there is one.
+pub fn statuses(app: &AppHandle, plugin_id: &str) -> Result<Vec<PermissionStatus>, String> {
+    let plugin = PluginScanner::find(app, plugin_id).map_err(|e| e.to_string())?;
+    let grants = PluginScanner::grants(app, plugin_id).map_err(|e| e.to_string())?;
+    let permissions = plugin.manifest.permissions.unwrap_or_default();
+    Ok(Capability::ALL
+        .into_iter()
+        .filter_map(|capability| {
+            let scope = permissions.declared(capability)?;
+            let granted = grants
+                .get(capability.key())
+                .filter(|grant| grant.scope == scope)
+                .map(|grant| grant.allowed);
+            Some(PermissionStatus {
+                plugin_id: plugin_id.to_string(),
+                plugin_name: plugin.manifest.name.clone(),
+                capability: capability.key(),
+                scope,
+                granted,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_bound_filesystem_access() {
+        let root = std::env::temp_dir().join(format!("zync-perm-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::create_dir_all(root.join("secrets")).unwrap();
+        let permissions = Permissions {
+            fs: vec![
+                FsScope {
+                    path: "~/notes".to_string(),
+                    write: true,
+                },
+                FsScope {
+                    path: root.join("secrets").to_string_lossy().into_owned(),
+                    write: false,
+                },
+            ],
+            ..Default::default()
+        };
+        let home = Some(root.as_path());
+        let check = |capability, raw: &str| {
+            resolve(raw, home)
+                .map(|path| permissions.allows_path(capability, &path, home))
+                .unwrap_or(false)
+        };
+
+        assert!(check(Capability::FsWrite, "~/notes/new/todo.md"));
+        assert!(check(Capability::FsRead, "~/secrets/key"));
+        assert!(!check(Capability::FsWrite, "~/secrets/key"));
+        assert!(!check(Capability::FsRead, "~/notes/../secrets/key"));
+        assert!(!check(Capability::FsRead, "~/notes-old/todo.md"));
+        assert!(!check(Capability::FsRead, "notes/todo.md"));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secrets"), root.join("notes/link")).unwrap();
+            assert!(!check(Capability::FsWrite, "~/notes/link/key"));
+        }
+
+        assert_eq!(permissions.declared(Capability::FsWrite).unwrap().len(), 1);
+        assert!(permissions.declared(Capability::Exec).is_none());
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
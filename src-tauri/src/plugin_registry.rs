@@ -0,0 +1,335 @@
+//! Plugin registry: the index the marketplace lists, and installs from it.
+//!
+//! The index is a JSON document (`{"plugins": [...]}`) at the URL in
+//! `settings.plugins.registryUrl`, the zync-extensions marketplace by default.
+//! Every entry names a version, a download URL and the SHA-256 of that
+//! download; `plugins_install_by_id` refuses packages whose digest or manifest
+//! does not match the entry, and only replaces an installed plugin with a newer
+//! version. Entries published before checksums were required still install,
+//! with a `plugin-registry:unverified` warning. Publisher signatures are
+//! checked by [`crate::plugin_signing`].
+
+use crate::plugins::{Plugin, PluginScanner};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+pub const DEFAULT_INDEX_URL: &str =
+    "https://raw.githubusercontent.com/zync-sh/zync-extensions/main/marketplace.json";
+const INDEX_URL_SETTING: &str = "/plugins/registryUrl";
+pub const UNVERIFIED_EVENT: &str = "plugin-registry:unverified";
+/// How long a fetched index answers searches before it is fetched again.
+const INDEX_TTL: Duration = Duration::from_secs(300);
+
+static INDEX: LazyLock<Mutex<Option<CachedIndex>>> = LazyLock::new(|| Mutex::new(None));
+
+struct CachedIndex {
+    url: String,
+    fetched_at: Instant,
+    entries: Vec<RegistryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryEntry {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: String,
+    pub download_url: String,
+    /// Hex SHA-256 of the package at `download_url`.
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Publisher signature of the package; `<downloadUrl>.sig` is tried without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<crate::plugin_signing::PackageSignature>,
+    /// Version of the installed copy, if any.
+    #[serde(default, skip_deserializing)]
+    pub installed_version: Option<String>,
+    #[serde(default, skip_deserializing)]
+    pub update_available: bool,
+    /// Presentation fields (`icon`, `thumbnailUrl`, `type`, `editor`, ...) passed to the UI as written.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UnverifiedWarning<'a> {
+    plugin_id: &'a str,
+    version: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    #[serde(default)]
+    plugins: Vec<RegistryEntry>,
+}
+
+fn index_url(app: &AppHandle) -> String {
+    crate::commands::read_effective_settings(app)
+        .ok()
+        .and_then(|settings| {
+            settings
+                .pointer(INDEX_URL_SETTING)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| DEFAULT_INDEX_URL.to_string())
+}
+
+async fn fetch_index(url: &str) -> Result<Vec<RegistryEntry>> {
+    crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
+    let client = crate::http_client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Failed to fetch plugin registry: status {}",
+            response.status()
+        ));
+    }
+    let index: Index = serde_json::from_slice(&response.bytes().await?)
+        .context("Plugin registry is not a valid index")?;
+    Ok(index.plugins)
+}
+
+/// The registry index, annotated with what is installed. `fresh` skips the cache.
+pub async fn list(app: &AppHandle, fresh: bool) -> Result<Vec<RegistryEntry>> {
+    let url = index_url(app);
+    let cached = INDEX.lock().ok().and_then(|cache| {
+        cache
+            .as_ref()
+            .filter(|c| !fresh && c.url == url && c.fetched_at.elapsed() < INDEX_TTL)
+            .map(|c| c.entries.clone())
+    });
+    let mut entries = match cached {
+        Some(entries) => entries,
+        None => {
+            let entries = fetch_index(&url).await?;
+            if let Ok(mut cache) = INDEX.lock() {
+                *cache = Some(CachedIndex {
+                    url,
+                    fetched_at: Instant::now(),
+                    entries: entries.clone(),
+                });
+            }
+            entries
+        }
+    };
+    let installed = PluginScanner::scan(app)?;
+    annotate(&mut entries, &installed);
+    Ok(entries)
+}
+
+fn annotate(entries: &mut [RegistryEntry], installed: &[Plugin]) {
+    for entry in entries {
+        entry.installed_version = installed
+            .iter()
+            .find(|plugin| plugin.manifest.id == entry.id)
+            .map(|plugin| plugin.manifest.version.clone());
+        entry.update_available = entry
+            .installed_version
+            .as_deref()
+            .is_some_and(|local| compare_versions(&entry.version, local) == Ordering::Greater);
+    }
+}
+
+/// Entries matching every word of `query` in their id, name, description or
+/// author; name matches come first.
+pub fn search(entries: Vec<RegistryEntry>, query: &str) -> Vec<RegistryEntry> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut matches: Vec<(bool, RegistryEntry)> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let name = entry.name.to_lowercase();
+            let text = format!(
+                "{} {} {} {}",
+                entry.id, name, entry.description, entry.author
+            )
+            .to_lowercase();
+            words
+                .iter()
+                .all(|word| text.contains(word))
+                .then(|| (words.iter().all(|word| name.contains(word)), entry))
+        })
+        .collect();
+    matches.sort_by(|(a_name, a), (b_name, b)| {
+        b_name
+            .cmp(a_name)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Orders dotted versions numerically (`1.10.0` > `1.9.2`); a pre-release
+/// (`2.0.0-beta`) sorts before its release.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split('+').next().unwrap_or(version);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let parts = core
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        (parts, pre)
+    }
+    let (a_parts, a_pre) = split(a);
+    let (b_parts, b_pre) = split(b);
+    let len = a_parts.len().max(b_parts.len());
+    let part = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    (0..len)
+        .map(|i| part(&a_parts, i).cmp(&part(&b_parts, i)))
+        .find(|order| order.is_ne())
+        .unwrap_or_else(|| match (a_pre, b_pre) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => a.cmp(b),
+        })
+}
+
+/// Checks the package against the entry's digest. `false` when the entry has
+/// none to check against.
+fn verify_checksum(entry: &RegistryEntry, bytes: &[u8]) -> Result<bool> {
+    let Some(expected) = entry
+        .sha256
+        .as_deref()
+        .map(|digest| digest.trim().to_ascii_lowercase())
+        .filter(|digest| !digest.is_empty())
+    else {
+        return Ok(false);
+    };
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if actual != expected {
+        return Err(anyhow!(
+            "Checksum mismatch for {} {}: expected {}, got {}",
+            entry.id,
+            entry.version,
+            expected,
+            actual
+        ));
+    }
+    Ok(true)
+}
+
+/// Installs the registry's current version of `id`, or upgrades an older
+/// installed copy. Returns the installed version.
+pub async fn install(app: &AppHandle, id: &str) -> Result<String> {
+    let entry = list(app, true)
+        .await?
+        .into_iter()
+        .find(|entry| entry.id == id)
+        .ok_or_else(|| anyhow!("Plugin {} is not in the registry", id))?;
+    if let Some(local) = entry.installed_version.as_deref() {
+        if !entry.update_available {
+            return Err(anyhow!("{} {} is already installed", entry.name, local));
+        }
+    }
+    crate::log_info!(
+        "[Plugins] Installing {} {} from registry",
+        entry.id,
+        entry.version
+    );
+    let bytes = PluginScanner::download(&entry.download_url).await?;
+    if !verify_checksum(&entry, &bytes)? {
+        crate::log_error!(
+            "[Plugins] Warning: registry entry for {} {} has no sha256; installing unverified",
+            entry.id,
+            entry.version
+        );
+        let warning = UnverifiedWarning {
+            plugin_id: &entry.id,
+            version: &entry.version,
+        };
+        let _ = app.emit(UNVERIFIED_EVENT, warning);
+    }
+    let signature = match entry.signature.clone() {
+        Some(signature) => Some(signature),
+        None => crate::plugin_signing::fetch_detached(&entry.download_url).await?,
+    };
+    let app = app.clone();
+    let version = entry.version.clone();
+    tokio::task::spawn_blocking(move || {
+        PluginScanner::install_zip_as(&app, bytes, signature.as_ref(), &entry.id, &entry.version)
+    })
+    .await
+    .map_err(|e| anyhow!("Plugin install task failed: {}", e))??;
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, name: &str, description: &str) -> RegistryEntry {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "version": "1.0.0",
+            "description": description,
+            "downloadUrl": "https://example.com/p.zip",
+            "icon": "Plug",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("v2.0", "2.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0-beta", "2.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("2.0.0-rc.1", "2.0.0-beta"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn search_prefers_name_matches() {
+        let entries = vec![
+            entry("a", "Log Viewer", "Tail remote logs"),
+            entry("b", "Docker", "Containers and logs"),
+            entry("c", "Dracula", "Dark theme"),
+        ];
+        let names: Vec<_> = search(entries, "log").into_iter().map(|e| e.name).collect();
+        assert_eq!(names, ["Log Viewer", "Docker"]);
+    }
+
+    #[test]
+    fn checksums_must_match_when_present() {
+        let mut package = entry("a", "Log Viewer", "");
+        assert_eq!(package.extra["icon"], "Plug");
+        // Older entries carry no digest; they install with a warning.
+        assert!(!verify_checksum(&package, b"zip").unwrap());
+        package.sha256 =
+            Some("54E3D15C8A7D2D4F8EB6B8C7A88F8F3B1E4F1B7D5FE2B4C51A8A6A3F2E4C3B2A".into());
+        assert!(verify_checksum(&package, b"zip").is_err());
+        package.sha256 = Some(
+            Sha256::digest(b"zip")
+                .iter()
+                .map(|b| format!("{:02X}", b))
+                .collect(),
+        );
+        assert!(verify_checksum(&package, b"zip").unwrap());
+    }
+}
@@ -0,0 +1,251 @@
+//! Per-plugin settings declared by the manifest's `settingsSchema`.
+//!
+//! The schema maps each setting to its type, default and optional bounds or
+//! allowed values. Values live in `plugin-settings/<id>.json` under the config
+//! directory (the id base64url-encoded, as for `plugin-data`), hold only keys
+//! the schema declares, and are checked against it on every write. Reads merge
+//! the stored values over the schema's defaults.
+
+use crate::plugins::PluginScanner;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Manifest `settingsSchema`, keyed by setting name.
+pub type SettingsSchema = BTreeMap<String, SettingSpec>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingSpec {
+    #[serde(rename = "type")]
+    pub kind: SettingKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The only values accepted, when set.
+    #[serde(default, rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<Vec<Value>>,
+    /// Bounds for numbers, or lengths for strings and arrays.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingKind {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl SettingKind {
+    fn name(self) -> &'static str {
+        match self {
+            SettingKind::String => "string",
+            SettingKind::Number => "number",
+            SettingKind::Integer => "integer",
+            SettingKind::Boolean => "boolean",
+            SettingKind::Array => "array",
+            SettingKind::Object => "object",
+        }
+    }
+}
+
+impl SettingSpec {
+    fn check(&self, key: &str, value: &Value) -> Result<(), String> {
+        let type_ok = match self.kind {
+            SettingKind::String => value.is_string(),
+            SettingKind::Number => value.is_number(),
+            SettingKind::Integer => value.is_i64() || value.is_u64(),
+            SettingKind::Boolean => value.is_boolean(),
+            SettingKind::Array => value.is_array(),
+            SettingKind::Object => value.is_object(),
+        };
+        if !type_ok {
+            return Err(format!(
+                "Invalid \"{}\": expected {}.",
+                key,
+                self.kind.name()
+            ));
+        }
+        if let Some(allowed) = &self.allowed {
+            if !allowed.contains(value) {
+                return Err(format!(
+                    "Invalid \"{}\": not one of the allowed values.",
+                    key
+                ));
+            }
+        }
+        let measure = match value {
+            Value::Number(number) => number.as_f64(),
+            Value::String(text) => Some(text.chars().count() as f64),
+            Value::Array(items) => Some(items.len() as f64),
+            _ => None,
+        };
+        if let Some(measure) = measure {
+            if self.minimum.is_some_and(|min| measure < min)
+                || self.maximum.is_some_and(|max| measure > max)
+            {
+                return Err(format!(
+                    "Invalid \"{}\": {} must be between {} and {}.",
+                    key,
+                    if value.is_number() { "value" } else { "length" },
+                    self.minimum.map_or("-".to_string(), |min| min.to_string()),
+                    self.maximum.map_or("-".to_string(), |max| max.to_string()),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stored values over the schema's defaults; stored keys the schema no longer
+/// declares, or whose value no longer fits it, fall back to the default.
+fn effective(schema: &SettingsSchema, stored: &Map<String, Value>) -> Map<String, Value> {
+    schema
+        .iter()
+        .filter_map(|(key, spec)| {
+            let value = stored
+                .get(key)
+                .filter(|value| spec.check(key, value).is_ok())
+                .or(spec.default.as_ref())?;
+            Some((key.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Applies `changes` to `stored`; a `null` value resets the setting to its default.
+fn apply(
+    schema: &SettingsSchema,
+    stored: &mut Map<String, Value>,
+    changes: Map<String, Value>,
+) -> Result<(), String> {
+    for (key, value) in &changes {
+        let spec = schema
+            .get(key)
+            .ok_or_else(|| format!("Unknown setting \"{}\".", key))?;
+        if !value.is_null() {
+            spec.check(key, value)?;
+        }
+    }
+    for (key, value) in changes {
+        if value.is_null() {
+            stored.remove(&key);
+        } else {
+            stored.insert(key, value);
+        }
+    }
+    stored.retain(|key, _| schema.contains_key(key));
+    Ok(())
+}
+
+fn settings_path(app: &AppHandle, plugin_id: &str) -> Result<PathBuf, String> {
+    use base64::{engine::general_purpose, Engine as _};
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config directory: {}", e))?;
+    Ok(config_dir.join("plugin-settings").join(format!(
+        "{}.json",
+        general_purpose::URL_SAFE_NO_PAD.encode(plugin_id)
+    )))
+}
+
+fn schema(app: &AppHandle, plugin_id: &str) -> Result<SettingsSchema, String> {
+    PluginScanner::find(app, plugin_id)
+        .map_err(|e| e.to_string())?
+        .manifest
+        .settings_schema
+        .ok_or_else(|| format!("Plugin {} declares no settings", plugin_id))
+}
+
+fn read_stored(app: &AppHandle, plugin_id: &str) -> Result<Map<String, Value>, String> {
+    let path = settings_path(app, plugin_id)?;
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Map::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// The plugin's settings: every declared key with its stored value or default.
+pub fn get(app: &AppHandle, plugin_id: &str) -> Result<Map<String, Value>, String> {
+    let schema = schema(app, plugin_id)?;
+    Ok(effective(&schema, &read_stored(app, plugin_id)?))
+}
+
+/// Validates and stores `changes`, then returns the settings as [`get`] would.
+pub fn set(
+    app: &AppHandle,
+    plugin_id: &str,
+    changes: Map<String, Value>,
+) -> Result<Map<String, Value>, String> {
+    let schema = schema(app, plugin_id)?;
+    let mut stored = read_stored(app, plugin_id)?;
+    apply(&schema, &mut stored, changes)?;
+    let content = serde_json::to_vec_pretty(&stored).map_err(|e| e.to_string())?;
+    crate::atomic_io::durable_replace(&settings_path(app, plugin_id)?, &content)
+        .map_err(|e| format!("Failed to save plugin settings: {}", e))?;
+    Ok(effective(&schema, &stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> SettingsSchema {
+        serde_json::from_value(json!({
+            "interval": { "type": "integer", "default": 30, "minimum": 5, "maximum": 3600 },
+            "mode": { "type": "string", "enum": ["tail", "follow"], "default": "tail" },
+            "token": { "type": "string" },
+        }))
+        .unwrap()
+    }
+
+    fn changes(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn writes_are_checked_against_the_schema() {
+        let schema = schema();
+        let mut stored = Map::new();
+        assert_eq!(
+            Value::Object(effective(&schema, &stored)),
+            json!({ "interval": 30, "mode": "tail" })
+        );
+
+        apply(
+            &schema,
+            &mut stored,
+            changes(json!({ "interval": 60, "token": "abc" })),
+        )
+        .unwrap();
+        assert!(apply(&schema, &mut stored, changes(json!({ "interval": 1 }))).is_err());
+        assert!(apply(&schema, &mut stored, changes(json!({ "interval": 7.5 }))).is_err());
+        assert!(apply(&schema, &mut stored, changes(json!({ "mode": "grep" }))).is_err());
+        assert!(apply(&schema, &mut stored, changes(json!({ "colour": "red" }))).is_err());
+        assert_eq!(
+            Value::Object(effective(&schema, &stored)),
+            json!({ "interval": 60, "mode": "tail", "token": "abc" })
+        );
+
+        apply(&schema, &mut stored, changes(json!({ "interval": null }))).unwrap();
+        assert_eq!(Value::Object(stored.clone()), json!({ "token": "abc" }));
+
+        stored.insert("interval".to_string(), json!("soon"));
+        assert_eq!(effective(&schema, &stored)["interval"], 30);
+    }
+}
@@ -0,0 +1,268 @@
+//! Publisher signatures on plugin packages.
+//!
+//! A package is signed with a detached document next to the zip (`<zip>.sig`,
+//! or `signature` in its registry entry) holding the publisher's ed25519 public
+//! key and the signature over the zip's bytes, both base64. The key must be one
+//! of [`SHIPPED_PUBLISHERS`] or listed in `settings.plugins.trustedPublishers`
+//! (`[{"name": "...", "publicKey": "..."}]`) for the package to count as signed.
+//! A signature that does not match the package always fails the install;
+//! packages without one, or signed by an unknown key, are allowed, allowed with a
+//! `plugin-signature:unsigned` warning, or refused per
+//! `settings.plugins.unsignedPackages` (`"allow"`, `"warn"` or `"refuse"`,
+//! `"warn"` by default).
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+pub const UNSIGNED_EVENT: &str = "plugin-signature:unsigned";
+const TRUSTED_SETTING: &str = "/plugins/trustedPublishers";
+const POLICY_SETTING: &str = "/plugins/unsignedPackages";
+
+/// Publishers trusted without any setting, as `(name, base64 public key)`.
+/// Empty until the zync-extensions release-signing key is published; add it
+/// here with a link to where it is published.
+const SHIPPED_PUBLISHERS: &[(&str, &str)] = &[];
+
+/// A detached package signature (`<zip>.sig`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageSignature {
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Publisher {
+    name: String,
+    public_key: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnsignedPolicy {
+    Allow,
+    Warn,
+    Refuse,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    /// Signed by this trusted publisher.
+    Signed(String),
+    /// Why the package does not count as signed.
+    Unsigned(String),
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UnsignedWarning<'a> {
+    plugin_id: &'a str,
+    reason: &'a str,
+}
+
+fn decode<const N: usize>(value: &str, what: &str) -> Result<[u8; N]> {
+    general_purpose::STANDARD
+        .decode(value.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Package signature has a malformed {}", what))
+}
+
+fn check(
+    bytes: &[u8],
+    signature: Option<&PackageSignature>,
+    trusted: &[Publisher],
+) -> Result<Verdict> {
+    let Some(signature) = signature else {
+        return Ok(Verdict::Unsigned("is not signed".to_string()));
+    };
+    let key_bytes = decode::<32>(&signature.public_key, "public key")?;
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|_| anyhow!("Package signature has a malformed public key"))?;
+    let signature_bytes = decode::<64>(&signature.signature, "signature")?;
+    key.verify_strict(bytes, &Signature::from_bytes(&signature_bytes))
+        .map_err(|_| anyhow!("Package signature does not match the package"))?;
+
+    let publisher = trusted.iter().find(|publisher| {
+        decode::<32>(&publisher.public_key, "public key").ok() == Some(key_bytes)
+    });
+    Ok(match publisher {
+        Some(publisher) => Verdict::Signed(publisher.name.clone()),
+        None => Verdict::Unsigned(format!(
+            "is signed by an untrusted key ({})",
+            signature.public_key.trim()
+        )),
+    })
+}
+
+fn trusted_publishers(settings: &Value) -> Vec<Publisher> {
+    let mut publishers: Vec<Publisher> = SHIPPED_PUBLISHERS
+        .iter()
+        .map(|(name, public_key)| Publisher {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+        })
+        .collect();
+    if let Some(listed) = settings.pointer(TRUSTED_SETTING) {
+        match serde_json::from_value::<Vec<Publisher>>(listed.clone()) {
+            Ok(listed) => publishers.extend(listed),
+            Err(e) => crate::log_error!(
+                "[Plugins] Ignoring invalid plugins.trustedPublishers: {}",
+                e
+            ),
+        }
+    }
+    publishers
+}
+
+fn unsigned_policy(settings: &Value) -> UnsignedPolicy {
+    match settings.pointer(POLICY_SETTING).and_then(Value::as_str) {
+        Some("allow") => UnsignedPolicy::Allow,
+        Some("refuse") => UnsignedPolicy::Refuse,
+        _ => UnsignedPolicy::Warn,
+    }
+}
+
+/// Checks a package before it is installed and returns its publisher, or
+/// `None` when the policy lets an unsigned package through. Plugin directories
+/// have no signature and are checked with `signature` set to `None`.
+pub fn verify(
+    app: &AppHandle,
+    plugin_id: &str,
+    bytes: &[u8],
+    signature: Option<&PackageSignature>,
+) -> Result<Option<String>> {
+    let settings = crate::commands::read_effective_settings(app).unwrap_or(Value::Null);
+    let reason = match check(bytes, signature, &trusted_publishers(&settings))? {
+        Verdict::Signed(publisher) => {
+            crate::log_info!("[Plugins] {} is signed by {}", plugin_id, publisher);
+            return Ok(Some(publisher));
+        }
+        Verdict::Unsigned(reason) => reason,
+    };
+    match unsigned_policy(&settings) {
+        UnsignedPolicy::Allow => {}
+        UnsignedPolicy::Warn => {
+            crate::log_error!("[Plugins] Warning: {} {}", plugin_id, reason);
+            let warning = UnsignedWarning {
+                plugin_id,
+                reason: &reason,
+            };
+            let _ = app.emit(UNSIGNED_EVENT, warning);
+        }
+        UnsignedPolicy::Refuse => {
+            return Err(anyhow!(
+                "Plugin {} {}; unsigned plugins are refused by settings.plugins.unsignedPackages",
+                plugin_id,
+                reason
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// Fetches `<url>.sig`; `None` when the server has none (404). Any other
+/// failure is an error rather than an unsigned package.
+pub async fn fetch_detached(url: &str) -> Result<Option<PackageSignature>> {
+    crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
+    let mut sig_url = url::Url::parse(url)?;
+    sig_url.set_path(&format!("{}.sig", sig_url.path()));
+    let client = crate::http_client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+    let response = client.get(sig_url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Failed to fetch package signature: status {}",
+            response.status()
+        ));
+    }
+    let signature = serde_json::from_slice(&response.bytes().await?)
+        .map_err(|e| anyhow!("Invalid package signature: {}", e))?;
+    Ok(Some(signature))
+}
+
+/// Reads `<path>.sig` next to a local package; `None` when there is none.
+pub fn read_detached(path: &std::path::Path) -> Result<Option<PackageSignature>> {
+    let mut sig_path = path.as_os_str().to_owned();
+    sig_path.push(".sig");
+    match std::fs::read(&sig_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid package signature: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn sign(key: &SigningKey, bytes: &[u8]) -> PackageSignature {
+        PackageSignature {
+            public_key: general_purpose::STANDARD.encode(key.verifying_key().to_bytes()),
+            signature: general_purpose::STANDARD.encode(key.sign(bytes).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn only_trusted_keys_make_a_package_signed() {
+        let zync = SigningKey::from_bytes(&[7; 32]);
+        let stranger = SigningKey::from_bytes(&[9; 32]);
+        let settings = serde_json::json!({
+            "plugins": {
+                "trustedPublishers": [{
+                    "name": "Zync",
+                    "publicKey": general_purpose::STANDARD.encode(zync.verifying_key().to_bytes()),
+                }],
+                "unsignedPackages": "refuse",
+            }
+        });
+        let trusted = trusted_publishers(&settings);
+        assert_eq!(unsigned_policy(&settings), UnsignedPolicy::Refuse);
+        assert_eq!(unsigned_policy(&Value::Null), UnsignedPolicy::Warn);
+
+        let package = b"PK\x03\x04 plugin";
+        assert_eq!(
+            check(package, Some(&sign(&zync, package)), &trusted).unwrap(),
+            Verdict::Signed("Zync".to_string())
+        );
+        assert!(matches!(
+            check(package, Some(&sign(&stranger, package)), &trusted).unwrap(),
+            Verdict::Unsigned(_)
+        ));
+        assert!(matches!(
+            check(package, None, &trusted).unwrap(),
+            Verdict::Unsigned(_)
+        ));
+        assert!(check(
+            b"PK\x03\x04 tampered",
+            Some(&sign(&zync, package)),
+            &trusted
+        )
+        .is_err());
+
+        let mut malformed = sign(&zync, package);
+        malformed.signature = "c2hvcnQ=".to_string();
+        assert!(check(package, Some(&malformed), &trusted).is_err());
+    }
+
+    #[test]
+    fn shipped_publishers_are_valid_keys() {
+        for publisher in trusted_publishers(&Value::Null) {
+            let key = decode::<32>(&publisher.public_key, "public key").unwrap();
+            assert!(VerifyingKey::from_bytes(&key).is_ok(), "{}", publisher.name);
+        }
+    }
+}
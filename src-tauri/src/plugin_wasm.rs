@@ -0,0 +1,714 @@
+//! Backend plugins compiled to WebAssembly.
+//!
+//! A plugin whose manifest has a `wasm` entry ships a module that runs in the
+//! app process under wasmtime instead of in the webview, for work the webview
+//! cannot do well (parsers, protocol handlers, heavy data crunching). The
+//! module sees nothing of the host beyond the functions it imports from the
+//! `zync` namespace, and every call runs in a fresh instance with a fuel budget
+//! and a memory cap, so a runaway module is stopped instead of hanging the app.
+//!
+//! Values cross the boundary as UTF-8 JSON. The module exports `memory` and
+//! `alloc(len) -> ptr`, and each function the app calls has the shape
+//! `(ptr, len) -> i64`: its input is the JSON at `ptr`, and it returns the
+//! address and length of its JSON output packed as `ptr << 32 | len`. Host
+//! functions work the same way in the other direction and answer with
+//! `{"ok": value}` or `{"error": message}` in a buffer taken from `alloc`:
+//!
+//! - `log(ptr, len)`: writes the text to the app log, tagged with the plugin id.
+//! - `emit`: `{event, payload}` is sent to the UI as `plugin-wasm:event`.
+//! - `fs_read`, `fs_write`, `fs_list`: `{path}` / `{path, content}` inside the
+//!   plugin's own data folder, `plugin-data/<id>` under the config directory.
+//! - `exec`: `{connectionId, command, timeoutSecs?}` runs a command on an open
+//!   connection and returns its exit status and output. Needs the `exec`
+//!   permission; see [`crate::plugin_permissions`].
+//!
+//! `wasm.transferProcessors` (`[{name, description, function}]`) offers
+//! functions as transfer processors, listed as `<plugin id>/<name>` next to
+//! the built-in ones in [`crate::transfer_pipeline`]. A processor gets the
+//! whole file as `{"data": base64}` once it has been read and answers the same
+//! way; it can only `log`.
+
+use crate::transfer_pipeline::{ProcessorRegistry, TransferProcessor};
+use anyhow::anyhow;
+use base64::{engine::general_purpose, Engine as _};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter, Manager};
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+pub const EVENT: &str = "plugin-wasm:event";
+
+/// Fuel a single call may burn, roughly one unit per instruction: a few
+/// hundred milliseconds of work.
+const FUEL_PER_CALL: u64 = 500_000_000;
+const MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+/// Largest JSON message accepted in either direction.
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
+
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).expect("fuel metering is supported on every target")
+});
+
+/// Compiled modules by path, compiled again when the file changes.
+static MODULES: LazyLock<Mutex<HashMap<PathBuf, (SystemTime, Module)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The `wasm` entry of a plugin manifest.
+#[derive(Debug, Clone, serde::Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmManifest {
+    /// Path of the `.wasm` module, relative to the plugin folder.
+    pub module: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transfer_processors: Vec<WasmProcessor>,
+}
+
+/// A module function offered as a transfer processor.
+#[derive(Debug, Clone, serde::Serialize, Deserialize)]
+pub struct WasmProcessor {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub function: String,
+}
+
+/// What host functions can reach during one call.
+struct Host {
+    app: AppHandle,
+    plugin_id: String,
+    data_dir: PathBuf,
+    runtime: tokio::runtime::Handle,
+    limits: StoreLimits,
+}
+
+/// Data of a call's store; it carries the limits wasmtime enforces.
+trait Sandboxed: 'static {
+    fn limits(&mut self) -> &mut StoreLimits;
+}
+
+impl Sandboxed for Host {
+    fn limits(&mut self) -> &mut StoreLimits {
+        &mut self.limits
+    }
+}
+
+/// Store data of a transfer processor call, which only gets `log`.
+struct ProcessorHost {
+    plugin_id: String,
+    limits: StoreLimits,
+}
+
+impl Sandboxed for ProcessorHost {
+    fn limits(&mut self) -> &mut StoreLimits {
+        &mut self.limits
+    }
+}
+
+fn store_limits() -> StoreLimits {
+    StoreLimitsBuilder::new()
+        .memory_size(MAX_MEMORY_BYTES)
+        .instances(1)
+        .build()
+}
+
+/// Folder a plugin's module may read and write through the `fs_*` host functions.
+pub fn data_dir(app: &AppHandle, plugin_id: &str) -> Result<PathBuf, String> {
+    let config_dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config directory: {}", e))?;
+    Ok(config_dir
+        .join("plugin-data")
+        .join(general_purpose::URL_SAFE_NO_PAD.encode(plugin_id)))
+}
+
+fn load_module(path: &Path) -> Result<Module, String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if let Some((stamp, module)) = MODULES.lock().map_err(|e| e.to_string())?.get(path) {
+        if *stamp == modified {
+            return Ok(module.clone());
+        }
+    }
+    let module = Module::from_file(&ENGINE, path)
+        .map_err(|e| format!("Invalid WebAssembly module {}: {}", path.display(), e))?;
+    MODULES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(path.to_path_buf(), (modified, module.clone()));
+    Ok(module)
+}
+
+fn pack(ptr: i32, len: usize) -> i64 {
+    (((ptr as u32) as u64) << 32 | len as u64) as i64
+}
+
+fn unpack(packed: i64) -> (usize, usize) {
+    let packed = packed as u64;
+    ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize)
+}
+
+fn read_guest<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> Result<Vec<u8>, String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or("Plugin module exports no memory")?;
+    let len = usize::try_from(len).map_err(|_| "Negative message length".to_string())?;
+    if len > MAX_MESSAGE_BYTES {
+        return Err(format!(
+            "Message is larger than {} bytes",
+            MAX_MESSAGE_BYTES
+        ));
+    }
+    let mut bytes = vec![0; len];
+    memory
+        .read(&*caller, ptr as u32 as usize, &mut bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+fn write_guest<T>(caller: &mut Caller<'_, T>, bytes: &[u8]) -> wasmtime::Result<i64> {
+    let alloc = caller
+        .get_export("alloc")
+        .and_then(|export| export.into_func())
+        .ok_or_else(|| wasmtime::Error::msg("Plugin module exports no alloc"))?
+        .typed::<i32, i32>(&*caller)?;
+    let ptr = alloc.call(&mut *caller, bytes.len() as i32)?;
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| wasmtime::Error::msg("Plugin module exports no memory"))?;
+    memory.write(&mut *caller, ptr as u32 as usize, bytes)?;
+    Ok(pack(ptr, bytes.len()))
+}
+
+type Handler<T> = fn(&T, Value) -> Result<Value, String>;
+
+/// Registers a `(ptr, len) -> i64` host function that answers `{ok}` or `{error}`.
+fn define<T: 'static>(
+    linker: &mut Linker<T>,
+    name: &str,
+    handler: Handler<T>,
+) -> wasmtime::Result<()> {
+    linker.func_wrap(
+        "zync",
+        name,
+        move |mut caller: Caller<'_, T>, ptr: i32, len: i32| -> wasmtime::Result<i64> {
+            let request = read_guest(&mut caller, ptr, len).and_then(|bytes| {
+                serde_json::from_slice::<Value>(&bytes).map_err(|e| e.to_string())
+            });
+            let response = match request.and_then(|request| handler(caller.data(), request)) {
+                Ok(value) => json!({ "ok": value }),
+                Err(error) => json!({ "error": error }),
+            };
+            write_guest(&mut caller, &serde_json::to_vec(&response)?)
+        },
+    )?;
+    Ok(())
+}
+
+fn host_functions() -> wasmtime::Result<Linker<Host>> {
+    let mut linker = Linker::new(&ENGINE);
+    linker.func_wrap(
+        "zync",
+        "log",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| {
+            if let Ok(bytes) = read_guest(&mut caller, ptr, len) {
+                crate::log_info!(
+                    "[PLUGIN] {}: {}",
+                    caller.data().plugin_id,
+                    String::from_utf8_lossy(&bytes)
+                );
+            }
+        },
+    )?;
+    define(&mut linker, "emit", host_emit)?;
+    define(&mut linker, "fs_read", host_fs_read)?;
+    define(&mut linker, "fs_write", host_fs_write)?;
+    define(&mut linker, "fs_list", host_fs_list)?;
+    define(&mut linker, "exec", host_exec)?;
+    Ok(linker)
+}
+
+fn processor_functions() -> wasmtime::Result<Linker<ProcessorHost>> {
+    let mut linker = Linker::new(&ENGINE);
+    linker.func_wrap(
+        "zync",
+        "log",
+        |mut caller: Caller<'_, ProcessorHost>, ptr: i32, len: i32| {
+            if let Ok(bytes) = read_guest(&mut caller, ptr, len) {
+                crate::log_info!(
+                    "[PLUGIN] {}: {}",
+                    caller.data().plugin_id,
+                    String::from_utf8_lossy(&bytes)
+                );
+            }
+        },
+    )?;
+    Ok(linker)
+}
+
+fn field<'a>(request: &'a Value, name: &str) -> Result<&'a str, String> {
+    request
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing '{}'", name))
+}
+
+/// `relative` inside the plugin's data folder; `..` and absolute paths are refused.
+fn data_path(data_dir: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative.trim_start_matches(['/', '\\']));
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!(
+            "'{}' is outside the plugin data folder",
+            relative.display()
+        ));
+    }
+    Ok(data_dir.join(relative))
+}
+
+fn host_emit(host: &Host, request: Value) -> Result<Value, String> {
+    let event = field(&request, "event")?;
+    host.app
+        .emit(
+            EVENT,
+            json!({
+                "pluginId": host.plugin_id,
+                "event": event,
+                "payload": request.get("payload").cloned().unwrap_or(Value::Null),
+            }),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(Value::Null)
+}
+
+fn host_fs_read(host: &Host, request: Value) -> Result<Value, String> {
+    let path = data_path(&host.data_dir, field(&request, "path")?)?;
+    std::fs::read_to_string(&path)
+        .map(Value::String)
+        .map_err(|e| e.to_string())
+}
+
+fn host_fs_write(host: &Host, request: Value) -> Result<Value, String> {
+    let path = data_path(&host.data_dir, field(&request, "path")?)?;
+    let content = field(&request, "content")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    crate::atomic_io::durable_replace(&path, content.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(Value::Null)
+}
+
+fn host_fs_list(host: &Host, request: Value) -> Result<Value, String> {
+    let path = data_path(
+        &host.data_dir,
+        request.get("path").and_then(Value::as_str).unwrap_or(""),
+    )?;
+    if !path.exists() {
+        return Ok(json!([]));
+    }
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(&path).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        entries.push(json!({
+            "name": entry.file_name().to_string_lossy(),
+            "isDir": metadata.is_dir(),
+            "size": metadata.len(),
+        }));
+    }
+    Ok(Value::Array(entries))
+}
+
+fn host_exec(host: &Host, request: Value) -> Result<Value, String> {
+    // Checked first, so a module without the grant learns nothing from the call.
+    crate::plugin_permissions::require(
+        &host.app,
+        &host.plugin_id,
+        crate::plugin_permissions::Capability::Exec,
+        None,
+    )?;
+    let connection_id = field(&request, "connectionId")?;
+    let command = field(&request, "command")?;
+    let timeout_secs = request.get("timeoutSecs").and_then(Value::as_u64);
+    let source = format!("plugin:{}", host.plugin_id);
+    let result = host
+        .runtime
+        .block_on(crate::commands::exec_on_connection(
+            &host.app,
+            connection_id,
+            command.to_string(),
+            timeout_secs,
+            uuid::Uuid::new_v4().to_string(),
+            &source,
+        ))
+        .ok_or_else(|| format!("Connection {} is not open", connection_id))?;
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
+
+fn call_error(error: wasmtime::Error) -> String {
+    if error.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::OutOfFuel) {
+        "Plugin call exceeded its execution budget".to_string()
+    } else {
+        format!("Plugin call failed: {}", error)
+    }
+}
+
+fn run<T: Sandboxed>(
+    module: &Module,
+    linker: &Linker<T>,
+    data: T,
+    function: &str,
+    input: &[u8],
+) -> Result<Value, String> {
+    let mut store = Store::new(&ENGINE, data);
+    store.limiter(|data| data.limits());
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+    let instance = linker
+        .instantiate(&mut store, module)
+        .map_err(|e| format!("Failed to start plugin module: {}", e))?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or("Plugin module exports no memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut store, "alloc")
+        .map_err(|e| format!("Plugin module has no usable alloc: {}", e))?;
+    let entry = instance
+        .get_typed_func::<(i32, i32), i64>(&mut store, function)
+        .map_err(|e| format!("Plugin module has no function '{}': {}", function, e))?;
+
+    let ptr = alloc
+        .call(&mut store, input.len() as i32)
+        .map_err(call_error)?;
+    memory
+        .write(&mut store, ptr as u32 as usize, input)
+        .map_err(|e| e.to_string())?;
+    let (out_ptr, out_len) = unpack(
+        entry
+            .call(&mut store, (ptr, input.len() as i32))
+            .map_err(call_error)?,
+    );
+    if out_len == 0 {
+        return Ok(Value::Null);
+    }
+    if out_len > MAX_MESSAGE_BYTES {
+        return Err(format!(
+            "Plugin returned more than {} bytes",
+            MAX_MESSAGE_BYTES
+        ));
+    }
+    let mut output = vec![0; out_len];
+    memory
+        .read(&store, out_ptr, &mut output)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&output).map_err(|e| format!("Plugin returned invalid JSON: {}", e))
+}
+
+/// The plugin's module file, which has to be inside the plugin folder.
+fn module_path(plugin: &crate::plugins::Plugin) -> Result<PathBuf, String> {
+    let wasm = plugin
+        .manifest
+        .wasm
+        .as_ref()
+        .ok_or_else(|| format!("Plugin {} has no WebAssembly module", plugin.manifest.id))?;
+    let root = std::fs::canonicalize(&plugin.path).map_err(|e| e.to_string())?;
+    let module_path = std::fs::canonicalize(root.join(&wasm.module))
+        .map_err(|e| format!("{}: {}", wasm.module, e))?;
+    if !module_path.starts_with(&root) {
+        return Err("Illegal manifest.wasm.module path: outside plugin root".to_string());
+    }
+    Ok(module_path)
+}
+
+/// Calls `function` exported by the plugin's module with `input` and returns
+/// its JSON output. Runs on a blocking thread; host functions that need the
+/// async runtime, like `exec`, block on it from there.
+pub async fn call(
+    app: &AppHandle,
+    plugin: &crate::plugins::Plugin,
+    function: &str,
+    input: Value,
+) -> Result<Value, String> {
+    let manifest = &plugin.manifest;
+    let module_path = module_path(plugin)?;
+    let host = Host {
+        app: app.clone(),
+        plugin_id: manifest.id.clone(),
+        data_dir: data_dir(app, &manifest.id)?,
+        runtime: tokio::runtime::Handle::current(),
+        limits: store_limits(),
+    };
+    let input = serde_json::to_vec(&input).map_err(|e| e.to_string())?;
+    let function = function.to_string();
+    tokio::task::spawn_blocking(move || {
+        let module = load_module(&module_path)?;
+        let linker = host_functions().map_err(|e| e.to_string())?;
+        run(&module, &linker, host, &function, &input)
+    })
+    .await
+    .map_err(|e| format!("Plugin call task failed: {}", e))?
+}
+
+/// Largest file a plugin processor takes; its base64 has to fit in one message.
+const MAX_PROCESSOR_INPUT: usize = MAX_MESSAGE_BYTES / 4 * 3 - 64;
+
+struct PluginProcessorFactory {
+    name: String,
+    description: String,
+    plugin_id: String,
+    function: String,
+    module: Module,
+}
+
+impl crate::transfer_pipeline::ProcessorFactory for PluginProcessorFactory {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn description(&self) -> &str {
+        &self.description
+    }
+    fn create(&self) -> Box<dyn TransferProcessor> {
+        Box::new(PluginProcessor {
+            name: self.name.clone(),
+            plugin_id: self.plugin_id.clone(),
+            function: self.function.clone(),
+            module: self.module.clone(),
+            buffer: Vec::new(),
+        })
+    }
+}
+
+/// Collects the file, then hands it to the module in one call.
+struct PluginProcessor {
+    name: String,
+    plugin_id: String,
+    function: String,
+    module: Module,
+    buffer: Vec<u8>,
+}
+
+impl TransferProcessor for PluginProcessor {
+    fn process(&mut self, chunk: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if self.buffer.len() + chunk.len() > MAX_PROCESSOR_INPUT {
+            return Err(anyhow!(
+                "File is too large for processor {} (limit {} bytes)",
+                self.name,
+                MAX_PROCESSOR_INPUT
+            ));
+        }
+        self.buffer.extend_from_slice(chunk);
+        Ok(Vec::new())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<Vec<u8>> {
+        let data = general_purpose::STANDARD.encode(std::mem::take(&mut self.buffer));
+        let input = serde_json::to_vec(&json!({ "data": data }))?;
+        let linker = processor_functions().map_err(|e| anyhow!(e.to_string()))?;
+        let host = ProcessorHost {
+            plugin_id: self.plugin_id.clone(),
+            limits: store_limits(),
+        };
+        let output = run(&self.module, &linker, host, &self.function, &input)
+            .map_err(|e| anyhow!("Processor {}: {}", self.name, e))?;
+        let data = output
+            .get("data")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Processor {} returned no data", self.name))?;
+        general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| anyhow!("Processor {} returned invalid base64: {}", self.name, e))
+    }
+}
+
+/// Replaces the plugin processors in `registry` with those of the enabled
+/// plugins. Called before processors are listed or resolved for a transfer.
+pub fn sync_processors(app: &AppHandle, registry: &ProcessorRegistry) {
+    registry.retain(|name| !name.contains('/'));
+    let plugins = match crate::plugins::PluginScanner::scan(app) {
+        Ok(plugins) => plugins,
+        Err(e) => {
+            crate::log_error!("[Plugins] Failed to read transfer processors: {}", e);
+            return;
+        }
+    };
+    let safe_mode = crate::safe_mode::is_active();
+    for plugin in plugins {
+        if !plugin.enabled || (safe_mode && !plugin.path.starts_with("builtin://")) {
+            continue;
+        }
+        let Some(wasm) = plugin.manifest.wasm.as_ref() else {
+            continue;
+        };
+        if wasm.transfer_processors.is_empty() {
+            continue;
+        }
+        let module = match module_path(&plugin).and_then(|path| load_module(&path)) {
+            Ok(module) => module,
+            Err(e) => {
+                crate::log_error!(
+                    "[Plugins] Skipping transfer processors of {}: {}",
+                    plugin.manifest.id,
+                    e
+                );
+                continue;
+            }
+        };
+        for processor in &wasm.transfer_processors {
+            registry.register(Arc::new(PluginProcessorFactory {
+                name: format!("{}/{}", plugin.manifest.id, processor.name),
+                description: processor.description.clone(),
+                plugin_id: plugin.manifest.id.clone(),
+                function: processor.function.clone(),
+                module: module.clone(),
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_results_round_trip() {
+        assert_eq!(unpack(pack(0x10_0000, 42)), (0x10_0000, 42));
+        assert_eq!(unpack(pack(-8, 7)), (0xffff_fff8, 7));
+    }
+
+    /// Bump allocator, a host round trip, an endless loop and a memory grab
+    /// past the cap. `grow` answers `["refused"]` when growing fails.
+    const SANDBOX_WAT: &str = r#"
+        (module
+          (import "zync" "echo" (func $echo (param i32 i32) (result i64)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "[\"grown\"]")
+          (data (i32.const 16) "[\"refused\"]")
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "call_host") (param i32 i32) (result i64)
+            (call $echo (local.get 0) (local.get 1)))
+          (func (export "spin") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0))
+          (func (export "grow") (param i32 i32) (result i64)
+            (if (result i64)
+              (i32.eq (memory.grow (i32.const 8192)) (i32.const -1))
+              (then (i64.const 0x10_0000_000b))
+              (else (i64.const 9)))))
+    "#;
+
+    struct TestHost {
+        limits: StoreLimits,
+    }
+
+    impl Sandboxed for TestHost {
+        fn limits(&mut self) -> &mut StoreLimits {
+            &mut self.limits
+        }
+    }
+
+    fn echo(_: &TestHost, request: Value) -> Result<Value, String> {
+        Ok(json!({ "echo": request }))
+    }
+
+    fn call_sandbox(function: &str, input: Value) -> Result<Value, String> {
+        let module = Module::new(&ENGINE, SANDBOX_WAT).unwrap();
+        let mut linker = Linker::new(&ENGINE);
+        define(&mut linker, "echo", echo).unwrap();
+        let host = TestHost {
+            limits: store_limits(),
+        };
+        run(
+            &module,
+            &linker,
+            host,
+            function,
+            &serde_json::to_vec(&input).unwrap(),
+        )
+    }
+
+    #[test]
+    fn host_functions_answer_through_guest_memory() {
+        assert_eq!(
+            call_sandbox("call_host", json!({ "path": "a.json" })).unwrap(),
+            json!({ "ok": { "echo": { "path": "a.json" } } })
+        );
+    }
+
+    #[test]
+    fn endless_calls_run_out_of_fuel() {
+        assert_eq!(
+            call_sandbox("spin", Value::Null).unwrap_err(),
+            "Plugin call exceeded its execution budget"
+        );
+    }
+
+    #[test]
+    fn memory_cannot_grow_past_the_cap() {
+        assert_eq!(
+            call_sandbox("grow", Value::Null).unwrap(),
+            json!(["refused"])
+        );
+    }
+
+    /// Answers with its own input, so a processor built on it hands the file back.
+    const IDENTITY_WAT: &str = r#"
+        (module
+          (import "zync" "log" (func (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "identity") (param i32 i32) (result i64)
+            (i64.or
+              (i64.shl (i64.extend_i32_u (local.get 0)) (i64.const 32))
+              (i64.extend_i32_u (local.get 1)))))
+    "#;
+
+    #[test]
+    fn plugin_processors_run_in_transfer_chains() {
+        let registry = ProcessorRegistry::with_builtins();
+        registry.register(Arc::new(PluginProcessorFactory {
+            name: "com.example/identity".to_string(),
+            description: String::new(),
+            plugin_id: "com.example".to_string(),
+            function: "identity".to_string(),
+            module: Module::new(&ENGINE, IDENTITY_WAT).unwrap(),
+        }));
+        let chain = registry
+            .chain(&["com.example/identity".to_string()])
+            .unwrap();
+        let mut pipeline = chain.start();
+        assert!(pipeline.process(b"access.log ").unwrap().is_empty());
+        assert!(pipeline.process(b"contents").unwrap().is_empty());
+        assert_eq!(pipeline.finish().unwrap(), b"access.log contents");
+    }
+
+    #[test]
+    fn data_paths_stay_in_the_plugin_folder() {
+        let root = Path::new("/cfg/plugin-data/abc");
+        assert_eq!(
+            data_path(root, "/cache/index.json").unwrap(),
+            root.join("cache/index.json")
+        );
+        assert!(data_path(root, "../other/secrets.json").is_err());
+        assert!(data_path(root, "cache/../../x").is_err());
+    }
+}
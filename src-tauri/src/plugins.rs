@@ -44,6 +44,18 @@ pub struct Manifest {
     pub icons_path: Option<String>,
     #[serde(default)]
     pub editor: Option<EditorManifest>,
+    /// Backend module run by [`crate::plugin_wasm`].
+    #[serde(default)]
+    pub wasm: Option<crate::plugin_wasm::WasmManifest>,
+    /// Capabilities checked by [`crate::plugin_permissions`].
+    #[serde(default)]
+    pub permissions: Option<crate::plugin_permissions::Permissions>,
+    /// Lifecycle hooks the plugin receives; see [`crate::plugin_hooks`].
+    #[serde(default)]
+    pub hooks: Option<Vec<String>>,
+    /// Settings the plugin stores through [`crate::plugin_settings`].
+    #[serde(default, rename = "settingsSchema")]
+    pub settings_schema: Option<crate::plugin_settings::SettingsSchema>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -55,11 +67,20 @@ pub struct Plugin {
     #[serde(rename = "editorHtml")]
     pub editor_html: Option<String>,
     pub enabled: bool,
+    /// Trusted publisher whose signature the installed package carried; see
+    /// [`crate::plugin_signing`].
+    pub publisher: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct PluginState {
     enabled_plugins: HashMap<String, bool>,
+    /// Permission answers per plugin, keyed by capability.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    grants: HashMap<String, HashMap<String, crate::plugin_permissions::Grant>>,
+    /// Publisher of each plugin installed from a signed package.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    publishers: HashMap<String, String>,
 }
 
 pub struct PluginScanner;
@@ -91,6 +112,7 @@ impl PluginScanner {
                             .enabled_plugins
                             .get(&plugin.manifest.id)
                             .unwrap_or(&true);
+                        plugin.publisher = state.publishers.get(&plugin.manifest.id).cloned();
                         plugins.push(plugin);
                     }
                 }
@@ -122,12 +144,16 @@ impl PluginScanner {
         Ok(plugins)
     }
 
-    fn load_state(app: &AppHandle) -> Result<PluginState> {
+    fn state_path(app: &AppHandle) -> Result<PathBuf> {
         let config_dir = app
             .path()
             .app_config_dir()
             .context("Failed to resolve app config directory")?;
-        let state_path = config_dir.join("plugins.json");
+        Ok(config_dir.join("plugins.json"))
+    }
+
+    fn load_state(app: &AppHandle) -> Result<PluginState> {
+        let state_path = Self::state_path(app)?;
 
         if state_path.exists() {
             let content = fs::read_to_string(state_path)?;
@@ -138,23 +164,77 @@ impl PluginScanner {
         }
     }
 
-    pub fn save_state(app: &AppHandle, id: String, enabled: bool) -> Result<()> {
-        let config_dir = app
-            .path()
-            .app_config_dir()
-            .context("Failed to resolve app config directory")?;
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir)?;
+    /// Writes `plugins.json`; every change to it goes through here.
+    fn write_state(app: &AppHandle, state: &PluginState) -> Result<()> {
+        let state_path = Self::state_path(app)?;
+        if let Some(config_dir) = state_path.parent() {
+            fs::create_dir_all(config_dir)?;
         }
+        let content = serde_json::to_string_pretty(state)?;
+        fs::write(state_path, content)?;
+        Ok(())
+    }
 
-        let state_path = config_dir.join("plugins.json");
+    pub fn save_state(app: &AppHandle, id: String, enabled: bool) -> Result<()> {
         let mut state = Self::load_state(app)?;
-
         state.enabled_plugins.insert(id, enabled);
+        Self::write_state(app, &state)?;
+        crate::plugin_hooks::invalidate();
+        Ok(())
+    }
 
-        let content = serde_json::to_string_pretty(&state)?;
-        fs::write(state_path, content)?;
+    /// The installed or built-in plugin with this id.
+    pub fn find(app: &AppHandle, id: &str) -> Result<Plugin> {
+        Self::scan(app)?
+            .into_iter()
+            .find(|plugin| plugin.manifest.id == id)
+            .ok_or_else(|| anyhow!("Plugin {} not found", id))
+    }
+
+    /// Permission answers recorded for a plugin, keyed by capability.
+    pub fn grants(
+        app: &AppHandle,
+        id: &str,
+    ) -> Result<HashMap<String, crate::plugin_permissions::Grant>> {
+        Ok(Self::load_state(app)?.grants.remove(id).unwrap_or_default())
+    }
 
+    pub fn save_grant(
+        app: &AppHandle,
+        id: &str,
+        capability: &str,
+        grant: crate::plugin_permissions::Grant,
+    ) -> Result<()> {
+        let mut state = Self::load_state(app)?;
+        state
+            .grants
+            .entry(id.to_string())
+            .or_default()
+            .insert(capability.to_string(), grant);
+        Self::write_state(app, &state)
+    }
+
+    /// Drops a plugin's permission answers and publisher, so a reinstall asks again.
+    pub fn forget_grants(app: &AppHandle, id: &str) -> Result<()> {
+        let mut state = Self::load_state(app)?;
+        let had_grants = state.grants.remove(id).is_some();
+        if state.publishers.remove(id).is_some() || had_grants {
+            Self::write_state(app, &state)?;
+        }
+        Ok(())
+    }
+
+    fn save_publisher(app: &AppHandle, id: &str, publisher: Option<String>) -> Result<()> {
+        let mut state = Self::load_state(app)?;
+        let changed = match publisher {
+            Some(publisher) => {
+                state.publishers.insert(id.to_string(), publisher.clone()) != Some(publisher)
+            }
+            None => state.publishers.remove(id).is_some(),
+        };
+        if changed {
+            Self::write_state(app, &state)?;
+        }
         Ok(())
     }
 
@@ -173,6 +253,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: Some(r#"
@@ -264,6 +348,7 @@ impl PluginScanner {
             style: None,
             editor_html: None,
             enabled: true, // Default, will be overwritten by scan
+            publisher: None,
         }
     }
 
@@ -282,6 +367,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: Some("editor-provider".to_string()),
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: Some(EditorManifest {
                     entry: Some("editor.html".to_string()),
                     display_name: Some("Plugin Editor (Bridge Demo)".to_string()),
@@ -437,6 +526,7 @@ impl PluginScanner {
                 "#.to_string()
             ),
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -455,6 +545,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: Some("editor-provider".to_string()),
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: Some(EditorManifest {
                     entry: None,
                     display_name: Some("CodeMirror".to_string()),
@@ -476,6 +570,7 @@ impl PluginScanner {
             style: None,
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -494,6 +589,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -513,6 +612,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -531,6 +631,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -550,6 +654,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -568,6 +673,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -587,6 +696,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -605,6 +715,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -624,6 +738,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -642,6 +757,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -661,6 +780,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -679,6 +799,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -698,6 +822,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -716,6 +841,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -735,6 +864,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -753,6 +883,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -772,6 +906,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -790,6 +925,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -809,6 +948,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -827,6 +967,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -846,6 +990,7 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
@@ -864,6 +1009,10 @@ impl PluginScanner {
                 icon: None,
                 manifest_type: None,
                 icons_path: None,
+                wasm: None,
+                permissions: None,
+                hooks: None,
+                settings_schema: None,
                 editor: None,
             },
             script: None,
@@ -883,14 +1032,21 @@ impl PluginScanner {
             ),
             editor_html: None,
             enabled: true,
+            publisher: None,
         }
     }
 
     pub async fn install_plugin(app: &AppHandle, url: &str) -> Result<String> {
-        println!("[Plugins] Installing from: {}", url);
+        crate::log_info!("[Plugins] Installing from: {}", url);
+        let bytes = Self::download(url).await?;
+        let signature = crate::plugin_signing::fetch_detached(url).await?;
+        Self::install_zip(app, &bytes, signature.as_ref(), None)
+    }
+
+    pub(crate) async fn download(url: &str) -> Result<Vec<u8>> {
+        crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
 
-        // 1. Download
-        let client = reqwest::Client::builder()
+        let client = crate::http_client::builder()
             .timeout(Duration::from_secs(30))
             .build()
             .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
@@ -903,10 +1059,46 @@ impl PluginScanner {
             ));
         }
 
-        let bytes = response.bytes().await?;
-        let cursor = std::io::Cursor::new(bytes);
-        let mut archive = zip::ZipArchive::new(cursor)?;
-        Self::install_from_zip_archive(app, &mut archive)
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Installs a downloaded plugin zip whose manifest must carry `id` and `version`,
+    /// replacing an installed copy of the same plugin.
+    pub(crate) fn install_zip_as(
+        app: &AppHandle,
+        bytes: Vec<u8>,
+        signature: Option<&crate::plugin_signing::PackageSignature>,
+        id: &str,
+        version: &str,
+    ) -> Result<String> {
+        Self::install_zip(app, &bytes, signature, Some((id, version)))
+    }
+
+    /// Checks the package's signature (and its id and version, when `expected`)
+    /// before installing it, and records its publisher.
+    fn install_zip(
+        app: &AppHandle,
+        bytes: &[u8],
+        signature: Option<&crate::plugin_signing::PackageSignature>,
+        expected: Option<(&str, &str)>,
+    ) -> Result<String> {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+        let manifest = Self::read_manifest_from_archive(&mut archive)?;
+        if let Some((id, version)) = expected {
+            if manifest.id != id || manifest.version != version {
+                return Err(anyhow!(
+                    "Package contains {} {} instead of {} {}",
+                    manifest.id,
+                    manifest.version,
+                    id,
+                    version
+                ));
+            }
+        }
+        let publisher = crate::plugin_signing::verify(app, &manifest.id, bytes, signature)?;
+        let id = Self::install_from_zip_archive(app, &mut archive)?;
+        Self::save_publisher(app, &id, publisher)?;
+        Ok(id)
     }
 
     pub fn install_plugin_from_local_path(app: &AppHandle, path: &str) -> Result<String> {
@@ -915,11 +1107,12 @@ impl PluginScanner {
             .with_context(|| format!("Failed to resolve path: {}", candidate_path.display()))?;
 
         if source_path.is_file() {
-            let file = fs::File::open(&source_path)
+            let bytes = fs::read(&source_path)
                 .with_context(|| format!("Failed to open plugin archive: {}", source_path.display()))?;
-            let mut archive = zip::ZipArchive::new(file)
+            zip::ZipArchive::new(std::io::Cursor::new(&bytes))
                 .with_context(|| format!("Invalid plugin archive: {}", source_path.display()))?;
-            return Self::install_from_zip_archive(app, &mut archive);
+            let signature = crate::plugin_signing::read_detached(&source_path)?;
+            return Self::install_zip(app, &bytes, signature.as_ref(), None);
         }
 
         if source_path.is_dir() {
@@ -962,7 +1155,7 @@ impl PluginScanner {
         let manifest = Self::read_manifest_from_archive(archive)?;
         let (_plugins_dir, target_dir, temp_dir) = Self::prepare_install_paths(app, &manifest.id)?;
 
-        println!("[Plugins] Extracting to temp: {:?}", temp_dir);
+        crate::log_info!("[Plugins] Extracting to temp: {:?}", temp_dir);
         if let Err(e) = archive.extract(&temp_dir) {
             let _ = fs::remove_dir_all(&temp_dir);
             return Err(e.into());
@@ -984,6 +1177,8 @@ impl PluginScanner {
         let manifest: Manifest =
             serde_json::from_str(&manifest_content).context("Invalid manifest.json in plugin directory")?;
 
+        // A directory has no package to sign, so it installs as unsigned.
+        let publisher = crate::plugin_signing::verify(app, &manifest.id, &[], None)?;
         let (_plugins_dir, target_dir, temp_dir) = Self::prepare_install_paths(app, &manifest.id)?;
         Self::copy_dir_recursive(source_dir, &temp_dir)?;
 
@@ -993,6 +1188,7 @@ impl PluginScanner {
         }
 
         Self::finalize_install(&target_dir, &temp_dir)?;
+        Self::save_publisher(app, &manifest.id, publisher)?;
         Ok(manifest.id)
     }
 
@@ -1023,6 +1219,7 @@ impl PluginScanner {
             fs::remove_dir_all(target_dir)?;
         }
         fs::rename(temp_dir, target_dir)?;
+        crate::plugin_hooks::invalidate();
         Ok(())
     }
 
@@ -1133,6 +1330,7 @@ impl PluginScanner {
             style,
             editor_html,
             enabled: true, // Default, overwritten by scan
+            publisher: None,
         })
     }
 
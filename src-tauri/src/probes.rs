@@ -0,0 +1,680 @@
+//! Health probes: a small uptime view of services on connected servers.
+//!
+//! Each probe belongs to a saved connection and is checked over that
+//! connection's SSH session while it is connected: an HTTP URL fetched through
+//! a direct-tcpip channel (HTTPS via `curl` on the server), a TCP port opened
+//! the same way, or a command whose exit code is compared. The scheduler runs
+//! due probes every few seconds and emits `probe:status` after each check.
+//! After `failureThreshold` consecutive failures a probe is `down` and
+//! `probe:alert` is emitted once; its first success afterwards emits a
+//! `recovered` alert. Probe definitions live in `probes.json`; status is kept
+//! in memory only.
+
+use crate::commands::AppState;
+use crate::ssh::Client;
+use russh::client::Handle;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub(crate) static PROBES_MUTATION_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+const SCHEDULER_TICK: Duration = Duration::from_secs(5);
+const MIN_INTERVAL_SECS: u64 = 10;
+/// Bytes of an HTTP response read to find the status line.
+const MAX_STATUS_LINE_BYTES: usize = 1024;
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ProbeCheck {
+    /// `url` as seen from the server, e.g. `http://127.0.0.1:8080/health`.
+    /// Without `expectedStatus` any 2xx or 3xx answer passes.
+    #[serde(rename_all = "camelCase")]
+    Http {
+        url: String,
+        #[serde(default)]
+        expected_status: Option<u16>,
+    },
+    /// A TCP connect to `host:port` from the server.
+    Tcp { host: String, port: u16 },
+    #[serde(rename_all = "camelCase")]
+    Command {
+        command: String,
+        #[serde(default)]
+        expected_exit_code: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthProbe {
+    pub id: String,
+    pub connection_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub check: ProbeCheck,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Consecutive failures before the probe counts as down.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl HealthProbe {
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(MIN_INTERVAL_SECS))
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.clamp(1, 120))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProbesData {
+    probes: Vec<HealthProbe>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeState {
+    /// Not checked yet, or its connection is not connected.
+    #[default]
+    Unknown,
+    Up,
+    /// Failing, but fewer than `failureThreshold` times in a row.
+    Degraded,
+    Down,
+}
+
+/// Payload of `probe:status` and an entry of `probes_status`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeStatus {
+    pub probe_id: String,
+    pub connection_id: String,
+    pub name: String,
+    pub state: ProbeState,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_ok_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// What the last check saw, e.g. `HTTP 200` or `exit 0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertKind {
+    Down,
+    Recovered,
+}
+
+/// Payload of `probe:alert`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeAlert {
+    pub kind: AlertKind,
+    pub status: ProbeStatus,
+}
+
+impl ProbeStatus {
+    /// Applies one check result; returns the alert to raise, if any.
+    fn record(
+        &mut self,
+        outcome: Result<String, String>,
+        latency: Duration,
+        threshold: u32,
+        now: u64,
+    ) -> Option<AlertKind> {
+        let was_down = self.state == ProbeState::Down;
+        self.last_checked_at = Some(now);
+        self.latency_ms = Some(latency.as_millis() as u64);
+        match outcome {
+            Ok(detail) => {
+                self.state = ProbeState::Up;
+                self.consecutive_failures = 0;
+                self.last_ok_at = Some(now);
+                self.detail = Some(detail);
+                self.error = None;
+                was_down.then_some(AlertKind::Recovered)
+            }
+            Err(error) => {
+                self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+                self.error = Some(error);
+                self.detail = None;
+                if self.consecutive_failures >= threshold.max(1) {
+                    self.state = ProbeState::Down;
+                    (!was_down).then_some(AlertKind::Down)
+                } else {
+                    self.state = ProbeState::Degraded;
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Schedule {
+    status: HashMap<String, ProbeStatus>,
+    next_due: HashMap<String, u64>,
+    running: HashSet<String>,
+}
+
+pub struct ProbesManager {
+    file_path: PathBuf,
+    schedule: Mutex<Schedule>,
+}
+
+impl ProbesManager {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            file_path: app_data_dir.join("probes.json"),
+            schedule: Mutex::new(Schedule::default()),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<HealthProbe>, String> {
+        let _guard = PROBES_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        if !self.file_path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&self.file_path).map_err(|e| e.to_string())?;
+        let data: ProbesData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        Ok(data.probes)
+    }
+
+    /// Replaces all probe definitions. Edited probes are checked again on the
+    /// next tick; status of removed probes is dropped.
+    pub fn save(&self, probes: Vec<HealthProbe>) -> Result<(), String> {
+        for probe in &probes {
+            if probe.id.trim().is_empty() || probe.connection_id.trim().is_empty() {
+                return Err("Every probe needs an id and a connection".to_string());
+            }
+            if let ProbeCheck::Http { url, .. } = &probe.check {
+                http_target(url)?;
+            }
+        }
+        let _guard = PROBES_MUTATION_LOCK
+            .lock()
+            .map_err(|error| error.to_string())?;
+        let content = serde_json::to_vec_pretty(&ProbesData {
+            probes: probes.clone(),
+        })
+        .map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.file_path, &content).map_err(|e| e.to_string())?;
+        if let Ok(mut schedule) = self.schedule.lock() {
+            let ids: HashSet<&str> = probes.iter().map(|p| p.id.as_str()).collect();
+            schedule.status.retain(|id, _| ids.contains(id.as_str()));
+            schedule.next_due.clear();
+        }
+        Ok(())
+    }
+
+    /// Current status of every probe, in definition order.
+    pub fn status(&self) -> Result<Vec<ProbeStatus>, String> {
+        let probes = self.list()?;
+        let schedule = self.schedule.lock().map_err(|e| e.to_string())?;
+        Ok(probes
+            .iter()
+            .map(|probe| {
+                let mut status = schedule.status.get(&probe.id).cloned().unwrap_or_default();
+                status.probe_id = probe.id.clone();
+                status.connection_id = probe.connection_id.clone();
+                status.name = probe.name.clone();
+                status
+            })
+            .collect())
+    }
+
+    /// Enabled probes due at `now` that are not already running; marks them running.
+    fn take_due(&self, probes: &[HealthProbe], now: u64) -> Vec<HealthProbe> {
+        let Ok(mut schedule) = self.schedule.lock() else {
+            return Vec::new();
+        };
+        let due: Vec<HealthProbe> = probes
+            .iter()
+            .filter(|probe| probe.enabled && !schedule.running.contains(&probe.id))
+            .filter(|probe| {
+                schedule
+                    .next_due
+                    .get(&probe.id)
+                    .is_none_or(|due| *due <= now)
+            })
+            .cloned()
+            .collect();
+        for probe in &due {
+            schedule.running.insert(probe.id.clone());
+        }
+        due
+    }
+
+    fn finish(
+        &self,
+        probe: &HealthProbe,
+        outcome: Option<(Result<String, String>, Duration)>,
+        now: u64,
+    ) -> Option<(ProbeStatus, Option<AlertKind>)> {
+        let mut schedule = self.schedule.lock().ok()?;
+        schedule.running.remove(&probe.id);
+        schedule
+            .next_due
+            .insert(probe.id.clone(), now + probe.interval().as_millis() as u64);
+        let status = schedule.status.entry(probe.id.clone()).or_default();
+        status.probe_id = probe.id.clone();
+        status.connection_id = probe.connection_id.clone();
+        status.name = probe.name.clone();
+        let alert = match outcome {
+            Some((result, latency)) => status.record(result, latency, probe.failure_threshold, now),
+            // Not connected: nothing was checked, so no alert either way.
+            None => {
+                status.state = ProbeState::Unknown;
+                status.consecutive_failures = 0;
+                None
+            }
+        };
+        Some((status.clone(), alert))
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `(host, port, is_https, request)` for a probe URL.
+fn http_target(url: &str) -> Result<(String, u16, bool, String), String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid probe URL '{}': {}", url, e))?;
+    let https = match parsed.scheme() {
+        "http" => false,
+        "https" => true,
+        other => return Err(format!("Unsupported probe URL scheme '{}'", other)),
+    };
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| format!("Probe URL '{}' has no host", url))?
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let mut path = parsed.path().to_string();
+    if let Some(query) = parsed.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    let host_header = match parsed.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.clone(),
+    };
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: zync-probe\r\nConnection: close\r\n\r\n",
+        path, host_header
+    );
+    Ok((host, port, https, request))
+}
+
+/// Status code from an HTTP/1.x status line.
+fn parse_status_line(response: &[u8]) -> Option<u16> {
+    let line = response.split(|b| *b == b'\n').next()?;
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.split_whitespace();
+    parts
+        .next()
+        .filter(|version| version.starts_with("HTTP/"))?;
+    parts.next()?.parse().ok()
+}
+
+fn status_ok(status: u16, expected: Option<u16>) -> bool {
+    match expected {
+        Some(expected) => status == expected,
+        None => (200..400).contains(&status),
+    }
+}
+
+async fn exec(
+    connection_id: &str,
+    session: &Arc<tokio::sync::Mutex<Handle<Client>>>,
+    command: &str,
+) -> Result<u32, String> {
+    let mut channel = {
+        let guard = session.lock().await;
+        crate::channel_budget::open_session(connection_id, &guard)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut exit_status = None;
+    while let Some(msg) = channel.wait().await {
+        if let russh::ChannelMsg::ExitStatus { exit_status: code } = msg {
+            exit_status = Some(code);
+        }
+    }
+    exit_status.ok_or_else(|| "Command ended without an exit status".to_string())
+}
+
+async fn run_check(
+    connection_id: &str,
+    session: &Arc<tokio::sync::Mutex<Handle<Client>>>,
+    check: &ProbeCheck,
+    timeout: Duration,
+) -> Result<String, String> {
+    match check {
+        ProbeCheck::Tcp { host, port } => {
+            let channel = {
+                let guard = session.lock().await;
+                guard
+                    .channel_open_direct_tcpip(host.clone(), *port as u32, "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| format!("{}:{} unreachable: {}", host, port, e))?
+            };
+            let _ = channel.close().await;
+            Ok("connected".to_string())
+        }
+        ProbeCheck::Command {
+            command,
+            expected_exit_code,
+        } => {
+            let code = exec(connection_id, session, command).await?;
+            if code == *expected_exit_code {
+                Ok(format!("exit {}", code))
+            } else {
+                Err(format!("exit {} (expected {})", code, expected_exit_code))
+            }
+        }
+        ProbeCheck::Http {
+            url,
+            expected_status,
+        } => {
+            let (host, port, https, request) = http_target(url)?;
+            let status = if https {
+                // No TLS over the channel; let the server's curl fetch it.
+                let command = format!(
+                    "curl -sk -o /dev/null -m {} -w '%{{http_code}}' {}",
+                    timeout.as_secs().max(1),
+                    crate::ai::tool_exec_support::shell_quote(url)
+                );
+                let mut channel = {
+                    let guard = session.lock().await;
+                    crate::channel_budget::open_session(connection_id, &guard)
+                        .await
+                        .map_err(|e| e.to_string())?
+                };
+                channel
+                    .exec(true, command)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let mut output = Vec::new();
+                while let Some(msg) = channel.wait().await {
+                    if let russh::ChannelMsg::Data { ref data } = msg {
+                        output.extend_from_slice(data);
+                    }
+                }
+                String::from_utf8_lossy(&output)
+                    .trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|code| *code != 0)
+                    .ok_or_else(|| format!("{} did not answer (curl on the server)", url))?
+            } else {
+                let channel = {
+                    let guard = session.lock().await;
+                    guard
+                        .channel_open_direct_tcpip(host.clone(), port as u32, "127.0.0.1", 0)
+                        .await
+                        .map_err(|e| format!("{}:{} unreachable: {}", host, port, e))?
+                };
+                let mut stream = channel.into_stream();
+                stream
+                    .write_all(request.as_bytes())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let mut response = Vec::new();
+                let mut buf = [0u8; 512];
+                while !response.contains(&b'\n') && response.len() < MAX_STATUS_LINE_BYTES {
+                    let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+                    if n == 0 {
+                        break;
+                    }
+                    response.extend_from_slice(&buf[..n]);
+                }
+                let _ = stream.shutdown().await;
+                parse_status_line(&response)
+                    .ok_or_else(|| format!("{} sent no HTTP status line", url))?
+            };
+            if status_ok(status, *expected_status) {
+                Ok(format!("HTTP {}", status))
+            } else {
+                Err(format!("HTTP {}", status))
+            }
+        }
+    }
+}
+
+async fn check_probe(app: &AppHandle, state: &AppState, probe: HealthProbe) {
+    let session = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&probe.connection_id)
+            .and_then(|c| c.session.clone())
+    };
+    let outcome = match session {
+        Some(session) => {
+            let started = std::time::Instant::now();
+            let result = tokio::time::timeout(
+                probe.timeout(),
+                run_check(
+                    &probe.connection_id,
+                    &session,
+                    &probe.check,
+                    probe.timeout(),
+                ),
+            )
+            .await
+            .unwrap_or_else(|_| Err(format!("No answer within {}s", probe.timeout().as_secs())));
+            Some((result, started.elapsed()))
+        }
+        None => None,
+    };
+    let Some((status, alert)) = state.probes_manager.finish(&probe, outcome, now_ms()) else {
+        return;
+    };
+    let _ = app.emit("probe:status", &status);
+    if let Some(kind) = alert {
+        crate::log_info!(
+            "[PROBE] '{}' is {}{}",
+            probe.name,
+            if kind == AlertKind::Down {
+                "down"
+            } else {
+                "back up"
+            },
+            status
+                .error
+                .as_deref()
+                .filter(|_| kind == AlertKind::Down)
+                .map(|e| format!(": {}", e))
+                .unwrap_or_default()
+        );
+        let _ = app.emit("probe:alert", ProbeAlert { kind, status });
+    }
+}
+
+pub fn spawn_probe_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = app.try_state::<AppState>() else {
+                continue;
+            };
+            let Ok(probes) = state.probes_manager.list() else {
+                continue;
+            };
+            for probe in state.probes_manager.take_due(&probes, now_ms()) {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(state) = app.try_state::<AppState>() {
+                        check_probe(&app, &state, probe).await;
+                    }
+                });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(check: ProbeCheck) -> HealthProbe {
+        HealthProbe {
+            id: "p1".to_string(),
+            connection_id: "c1".to_string(),
+            name: "api".to_string(),
+            check,
+            interval_secs: 30,
+            timeout_secs: 5,
+            failure_threshold: 2,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn reads_probe_definitions_with_defaults() {
+        let parsed: HealthProbe = serde_json::from_value(serde_json::json!({
+            "id": "p1",
+            "connectionId": "c1",
+            "name": "nginx",
+            "type": "http",
+            "url": "http://127.0.0.1/health",
+        }))
+        .unwrap();
+        assert_eq!(
+            parsed.check,
+            ProbeCheck::Http {
+                url: "http://127.0.0.1/health".to_string(),
+                expected_status: None,
+            }
+        );
+        assert_eq!(parsed.interval_secs, 60);
+        assert_eq!(parsed.failure_threshold, 3);
+        assert!(parsed.enabled);
+
+        let command: HealthProbe = serde_json::from_value(serde_json::json!({
+            "id": "p2",
+            "connectionId": "c1",
+            "name": "disk",
+            "type": "command",
+            "command": "test $(df --output=pcent / | tail -1 | tr -dc 0-9) -lt 90",
+            "expectedExitCode": 0,
+            "intervalSecs": 1,
+        }))
+        .unwrap();
+        assert_eq!(command.interval(), Duration::from_secs(MIN_INTERVAL_SECS));
+    }
+
+    #[test]
+    fn builds_http_requests() {
+        let (host, port, https, request) =
+            http_target("http://10.0.0.5:8080/health?full=1").unwrap();
+        assert_eq!((host.as_str(), port, https), ("10.0.0.5", 8080, false));
+        assert!(request.starts_with("GET /health?full=1 HTTP/1.1\r\nHost: 10.0.0.5:8080\r\n"));
+        assert!(http_target("https://example.com").unwrap().2);
+        assert!(http_target("ftp://example.com").is_err());
+        assert!(http_target("not a url").is_err());
+    }
+
+    #[test]
+    fn parses_status_lines() {
+        assert_eq!(parse_status_line(b"HTTP/1.1 204 No Content\r\n"), Some(204));
+        assert_eq!(parse_status_line(b"SSH-2.0-OpenSSH\r\n"), None);
+        assert!(status_ok(302, None));
+        assert!(!status_ok(500, None));
+        assert!(status_ok(503, Some(503)));
+        assert!(!status_ok(200, Some(204)));
+    }
+
+    #[test]
+    fn alerts_once_past_the_threshold_and_on_recovery() {
+        let mut status = ProbeStatus::default();
+        let latency = Duration::from_millis(12);
+        assert_eq!(status.record(Ok("HTTP 200".into()), latency, 2, 1), None);
+        assert_eq!(status.state, ProbeState::Up);
+
+        assert_eq!(status.record(Err("HTTP 502".into()), latency, 2, 2), None);
+        assert_eq!(status.state, ProbeState::Degraded);
+        assert_eq!(
+            status.record(Err("HTTP 502".into()), latency, 2, 3),
+            Some(AlertKind::Down)
+        );
+        assert_eq!(status.record(Err("HTTP 502".into()), latency, 2, 4), None);
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.last_ok_at, Some(1));
+
+        assert_eq!(
+            status.record(Ok("HTTP 200".into()), latency, 2, 5),
+            Some(AlertKind::Recovered)
+        );
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.error, None);
+    }
+
+    #[test]
+    fn schedules_each_probe_once_per_interval() {
+        let dir = std::env::temp_dir().join(format!("zync-probes-{}", uuid::Uuid::new_v4()));
+        let manager = ProbesManager::new(dir.clone());
+        let probes = vec![probe(ProbeCheck::Tcp {
+            host: "127.0.0.1".to_string(),
+            port: 5432,
+        })];
+        assert_eq!(manager.take_due(&probes, 1_000).len(), 1);
+        // Still running: not handed out again.
+        assert!(manager.take_due(&probes, 1_000).is_empty());
+
+        manager.finish(&probes[0], None, 1_000);
+        assert!(manager.take_due(&probes, 20_000).is_empty());
+        assert_eq!(manager.take_due(&probes, 31_000).len(), 1);
+
+        manager.save(probes.clone()).unwrap();
+        assert_eq!(manager.list().unwrap().len(), 1);
+        assert_eq!(manager.status().unwrap()[0].state, ProbeState::Unknown);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,8 +1,23 @@
+use crate::command_history::CommandHistory;
+use crate::input_history::{InputHistories, InputLine};
+use crate::macros::{MacroRecorders, MacroStep};
+use crate::paste::{self, PasteModes, PasteProgress};
+use crate::quiet_login::LoginNoiseFilter;
+use crate::recording::{RecordingInfo, SessionRecorders};
+use crate::scrollback::{ScrollbackSnapshot, ScrollbackStats, Scrollbacks};
+use crate::session_log::{SessionLogOptions, SessionLoggers};
+use crate::shell_integration::{CommandTracker, IntegrationShell, ShellEvent};
+use crate::state_sync::{self, ChangeKind, StateKind};
+use crate::terminal_taps::TerminalTaps;
+use crate::triggers::TriggerEngine;
+use crate::types::{BackspaceKey, DeleteKey, TerminalOptions};
+use crate::zmodem::{ZmodemDetector, ZmodemDirection};
 use anyhow::{anyhow, Result};
 use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
 use russh::client::Msg;
 use russh::{Channel, ChannelMsg};
 use serde::Serialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::mem;
@@ -18,10 +33,51 @@ use tokio::time::{Duration, Instant};
 const OUTPUT_BATCH_MS: u64 = 8;
 /// Flush buffered PTY output immediately once it reaches this many bytes.
 const OUTPUT_FLUSH_THRESHOLD: usize = 4096;
+/// Startup commands are sent after this long even if no prompt was recognised.
+const STARTUP_COMMANDS_FALLBACK_MS: u64 = 3000;
+
+/// A connection's `startup_commands`, held until the shell shows its first prompt.
+struct PendingStartup {
+    input: Vec<u8>,
+    deadline: Instant,
+}
+
+impl PendingStartup {
+    fn new(commands: &[String]) -> Option<Self> {
+        let input: String = commands
+            .iter()
+            .map(|command| command.trim())
+            .filter(|command| !command.is_empty())
+            .map(|command| format!("{}\r", command))
+            .collect();
+        (!input.is_empty()).then(|| Self {
+            input: input.into_bytes(),
+            deadline: Instant::now() + Duration::from_millis(STARTUP_COMMANDS_FALLBACK_MS),
+        })
+    }
+}
+
+async fn send_startup_commands(channel: &Channel<Msg>, startup: &mut Option<PendingStartup>) {
+    if let Some(pending) = startup.take() {
+        if let Err(e) = channel.data(&pending.input[..]).await {
+            crate::log_error!("[PTY] Failed to send startup commands: {}", e);
+        }
+    }
+}
+
+/// Output whose last (unterminated) line looks like a shell prompt.
+fn ends_with_prompt(data: &[u8]) -> bool {
+    data.rsplit(|&byte| byte == b'\n')
+        .next()
+        .is_some_and(crate::quiet_login::looks_like_prompt)
+}
+
+/// How long a local reader that hit EOF waits for the child's exit status.
+const LOCAL_EXIT_GRACE_MS: u64 = 500;
 
 enum LocalReaderEvent {
     Data(Vec<u8>),
-    Finished { exit_code: Option<u32> },
+    Finished(TerminalExit),
 }
 
 fn remote_shell_login_flag(shell_override: &str) -> Option<&'static str> {
@@ -116,6 +172,136 @@ fn shell_single_quote(value: &str) -> String {
     value.replace('\'', "'\\''")
 }
 
+const DEFAULT_TERM: &str = "xterm-256color";
+
+/// `TERM` for a terminal; empty or malformed values fall back to `xterm-256color`.
+fn term_name(options: &TerminalOptions) -> &str {
+    options
+        .term
+        .as_deref()
+        .map(str::trim)
+        .filter(|term| {
+            !term.is_empty()
+                && term
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+        })
+        .unwrap_or(DEFAULT_TERM)
+}
+
+/// `LANG` / `LC_ALL` followed by `env`, so a variable set explicitly on the
+/// connection wins over the locale options.
+fn with_locale(options: &TerminalOptions, env: Vec<(String, String)>) -> Vec<(String, String)> {
+    let locale = [("LANG", &options.lang), ("LC_ALL", &options.lc_all)];
+    let mut pairs: Vec<(String, String)> = locale
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = value.as_deref()?.trim();
+            (!value.is_empty()).then(|| (name.to_string(), value.to_string()))
+        })
+        .filter(|(name, _)| !env.iter().any(|(other, _)| other == name))
+        .collect();
+    pairs.extend(env);
+    pairs
+}
+
+/// Rewrites the Backspace (`^?`) and Delete (`ESC [ 3 ~`) bytes xterm.js sends
+/// into the ones configured for the terminal.
+fn translate_keys<'a>(options: &TerminalOptions, data: &'a str) -> Cow<'a, str> {
+    let backspace = options.backspace == BackspaceKey::CtrlH && data.contains('\x7f');
+    let delete = options.delete == DeleteKey::Del && data.contains("\x1b[3~");
+    if !backspace && !delete {
+        return Cow::Borrowed(data);
+    }
+    let mut data = data.to_string();
+    if backspace {
+        data = data.replace('\x7f', "\x08");
+    }
+    if delete {
+        data = data.replace("\x1b[3~", "\x7f");
+    }
+    Cow::Owned(data)
+}
+
+/// Terminal modes for the remote `pty-req`: the tty erase character matches
+/// what Backspace sends.
+fn pty_modes(options: &TerminalOptions) -> [(russh::Pty, u32); 1] {
+    let erase = match options.backspace {
+        BackspaceKey::Del => 0x7f,
+        BackspaceKey::CtrlH => 0x08,
+    };
+    [(russh::Pty::VERASE, erase)]
+}
+
+/// Environment variables as sorted `(name, value)` pairs, dropping names no shell
+/// could export.
+pub(crate) fn environment_pairs(env: Option<&HashMap<String, String>>) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = env
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| {
+            let mut chars = name.chars();
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    pairs.sort();
+    pairs
+}
+
+/// ` export A='1' B='2'` for variables the server would not take as `env` requests.
+/// The leading space keeps it out of history under `HISTCONTROL=ignorespace`.
+fn export_line(vars: &[(String, String)]) -> String {
+    let assignments: Vec<String> = vars
+        .iter()
+        .map(|(name, value)| format!("{}='{}'", name, shell_single_quote(value)))
+        .collect();
+    format!(" export {}\r", assignments.join(" "))
+}
+
+/// Sends one `env` request per variable and returns those the server refused
+/// (sshd only accepts names listed in its `AcceptEnv`).
+async fn request_remote_env(
+    channel: &mut Channel<Msg>,
+    env: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut requested = Vec::new();
+    for (name, value) in env {
+        match channel.set_env(true, name.as_str(), value.as_str()).await {
+            Ok(()) => requested.push((name.clone(), value.clone())),
+            Err(e) => {
+                crate::log_error!("[PTY] Failed to request env {}: {}", name, e);
+                return env.to_vec();
+            }
+        }
+    }
+    // Replies arrive in request order.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut refused = Vec::new();
+    let mut pending = requested.into_iter();
+    while let Some(var) = pending.next() {
+        loop {
+            match tokio::time::timeout_at(deadline, channel.wait()).await {
+                Ok(Some(ChannelMsg::Success)) => break,
+                Ok(Some(ChannelMsg::Failure)) => {
+                    refused.push(var);
+                    break;
+                }
+                Ok(Some(_)) => continue,
+                Ok(None) | Err(_) => {
+                    refused.push(var);
+                    refused.extend(pending);
+                    return refused;
+                }
+            }
+        }
+    }
+    refused
+}
+
 /// Format a path for POSIX `cd`. Tilde prefixes must stay unquoted so the shell
 /// expands them; only the suffix after `~/` is single-quoted when present.
 pub(crate) fn posix_shell_cd_path(path: &str) -> String {
@@ -236,8 +422,82 @@ fn is_posix_interactive_shell(shell: &str) -> bool {
 #[derive(Clone, Serialize)]
 struct TerminalLifecycleEvent {
     generation: u32,
+    #[serde(flatten)]
+    exit: TerminalExit,
+}
+
+/// How a terminal ended: the shell exited or was killed ("Restart shell"), or
+/// the connection under it went away ("Reconnect host").
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum TerminalExitReason {
+    Exited,
+    Signaled,
+    /// The channel or PTY closed without reporting a status.
+    Closed,
+    ConnectionLost,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+struct TerminalExit {
     #[serde(skip_serializing_if = "Option::is_none")]
     exit_code: Option<u32>,
+    /// Signal name without the `SIG` prefix, e.g. `KILL`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signal: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    reason: TerminalExitReason,
+    connection_lost: bool,
+}
+
+impl TerminalExit {
+    fn exited(exit_code: u32) -> Self {
+        Self::with_reason(TerminalExitReason::Exited, Some(exit_code))
+    }
+
+    fn signaled(signal: String, message: Option<String>) -> Self {
+        Self {
+            signal: Some(signal),
+            message,
+            ..Self::with_reason(TerminalExitReason::Signaled, None)
+        }
+    }
+
+    fn closed() -> Self {
+        Self::with_reason(TerminalExitReason::Closed, None)
+    }
+
+    fn connection_lost() -> Self {
+        Self::with_reason(TerminalExitReason::ConnectionLost, None)
+    }
+
+    fn with_reason(reason: TerminalExitReason, exit_code: Option<u32>) -> Self {
+        Self {
+            exit_code,
+            signal: None,
+            message: None,
+            reason,
+            connection_lost: reason == TerminalExitReason::ConnectionLost,
+        }
+    }
+
+    fn from_local(status: &portable_pty::ExitStatus) -> Self {
+        match status.signal() {
+            Some(signal) => Self::signaled(
+                signal.trim_start_matches("SIG").to_uppercase(),
+                None,
+            ),
+            None => Self::exited(status.exit_code()),
+        }
+    }
+}
+
+fn remote_signal_name(signal: &russh::Sig) -> String {
+    match signal {
+        russh::Sig::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
 }
 
 /// Flushes buffered PTY output through the streaming IPC channel.
@@ -259,7 +519,7 @@ fn flush_pending_output(
     frame.extend_from_slice(&output);
 
     if let Err(e) = output_channel.send(InvokeResponseBody::Raw(frame)) {
-        eprintln!("[PTY] Failed to send output on channel: {}", e);
+        crate::log_error!("[PTY] Failed to send output on channel: {}", e);
     }
 }
 
@@ -271,26 +531,195 @@ fn process_tree_has_children(root_pid: u32) -> bool {
     system.processes().values().any(|process| process.parent() == Some(parent))
 }
 
-fn emit_terminal_exit(app_handle: &AppHandle, term_id: &str, generation: u32, exit_code: Option<u32>) {
+/// Working directory of a local process (`/proc/<pid>/cwd` on Linux).
+fn process_cwd(pid: u32) -> Option<String> {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_cwd(UpdateKind::Always),
+    );
+    system
+        .process(pid)?
+        .cwd()
+        .map(|cwd| cwd.to_string_lossy().to_string())
+}
+
+fn emit_terminal_exit(app_handle: &AppHandle, term_id: &str, generation: u32, exit: TerminalExit) {
     if let Err(e) = app_handle.emit(
         &format!("terminal-exit-{}", term_id),
-        TerminalLifecycleEvent {
+        TerminalLifecycleEvent { generation, exit },
+    ) {
+        crate::log_error!("[PTY] Failed to emit exit for {}: {}", term_id, e);
+    }
+}
+
+/// Payload of `terminal:command-start` / `terminal:command-end` (OSC 133 tracking).
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellCommandEvent {
+    term_id: String,
+    generation: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+/// Last working directory each terminal reported through OSC 7 / `633;P;Cwd`.
+#[derive(Clone, Default)]
+struct TerminalCwds(Arc<std::sync::Mutex<HashMap<String, String>>>);
+
+impl TerminalCwds {
+    /// Stores `path`; returns false when it is unchanged.
+    fn set(&self, term_id: &str, path: String) -> bool {
+        let Ok(mut cwds) = self.0.lock() else {
+            return false;
+        };
+        if cwds.get(term_id) == Some(&path) {
+            return false;
+        }
+        cwds.insert(term_id.to_string(), path);
+        true
+    }
+
+    fn get(&self, term_id: &str) -> Option<String> {
+        self.0.lock().ok()?.get(term_id).cloned()
+    }
+
+    fn remove(&self, term_id: &str) {
+        if let Ok(mut cwds) = self.0.lock() {
+            cwds.remove(term_id);
+        }
+    }
+}
+
+fn emit_shell_events(
+    app_handle: &AppHandle,
+    cwds: &TerminalCwds,
+    history: &CommandHistory,
+    term_id: &str,
+    connection_id: &str,
+    generation: u32,
+    events: Vec<ShellEvent>,
+) {
+    for event in events {
+        let (name, payload) = match event {
+            ShellEvent::Cwd { path } => {
+                if cwds.set(term_id, path.clone()) {
+                    if let Err(e) = app_handle.emit(
+                        "terminal:cwd",
+                        serde_json::json!({ "termId": term_id, "generation": generation, "cwd": path }),
+                    ) {
+                        crate::log_error!("[PTY] Failed to emit cwd for {}: {}", term_id, e);
+                    }
+                }
+                continue;
+            }
+            ShellEvent::CommandStart { command } => (
+                "terminal:command-start",
+                ShellCommandEvent {
+                    term_id: term_id.to_string(),
+                    generation,
+                    command,
+                    exit_code: None,
+                    duration_ms: None,
+                },
+            ),
+            ShellEvent::CommandEnd {
+                command,
+                exit_code,
+                duration_ms,
+            } => {
+                if let Some(command) = command.as_deref() {
+                    history.record(connection_id, command, cwds.get(term_id), exit_code);
+                }
+                (
+                    "terminal:command-end",
+                    ShellCommandEvent {
+                        term_id: term_id.to_string(),
+                        generation,
+                        command,
+                        exit_code,
+                        duration_ms: Some(duration_ms),
+                    },
+                )
+            }
+        };
+        if let Err(e) = app_handle.emit(name, payload) {
+            crate::log_error!("[PTY] Failed to emit {} for {}: {}", name, term_id, e);
+        }
+    }
+}
+
+/// Payload of `terminal:zmodem`: a `sz`/`rz` was intercepted and cancelled; the
+/// frontend completes it over SFTP.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ZmodemEvent {
+    term_id: String,
+    connection_id: String,
+    generation: u32,
+    direction: ZmodemDirection,
+    /// Absolute remote paths for downloads, when the command line is known.
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+}
+
+fn emit_zmodem(
+    app_handle: &AppHandle,
+    term_id: &str,
+    connection_id: &str,
+    generation: u32,
+    direction: ZmodemDirection,
+    command: Option<&str>,
+    cwd: Option<String>,
+) {
+    let files = command
+        .and_then(crate::zmodem::transfer_files)
+        .map(|(_, files)| {
+            files
+                .iter()
+                .map(|file| crate::zmodem::resolve(cwd.as_deref(), file))
+                .collect()
+        })
+        .unwrap_or_default();
+    crate::log_info!("[PTY] Intercepted ZMODEM {:?} in {}", direction, term_id);
+    if let Err(e) = app_handle.emit(
+        "terminal:zmodem",
+        ZmodemEvent {
+            term_id: term_id.to_string(),
+            connection_id: connection_id.to_string(),
             generation,
-            exit_code,
+            direction,
+            files,
+            cwd,
         },
     ) {
-        eprintln!("[PTY] Failed to emit exit for {}: {}", term_id, e);
+        crate::log_error!("[PTY] Failed to emit zmodem for {}: {}", term_id, e);
     }
 }
 
 fn emit_connection_transport_lost(app_handle: &AppHandle, connection_id: &str) {
+    state_sync::notify(
+        app_handle,
+        StateKind::Connection,
+        connection_id,
+        ChangeKind::Updated,
+    );
     if let Err(e) = app_handle.emit(
         "connection:transport-lost",
         serde_json::json!({ "connectionId": connection_id }),
     ) {
-        eprintln!(
+        crate::log_error!(
             "[PTY] Failed to emit transport-lost for {}: {}",
-            connection_id, e
+            connection_id,
+            e
         );
     }
 }
@@ -320,16 +749,65 @@ pub struct PtySession {
     pub output_channel: IpcChannel,
     pub handle: TerminalHandle,
     navigate_shell: NavigateShellStyle,
+    /// Current (cols, rows), used as the header size when a recording starts.
+    size: (u16, u16),
+    /// Backspace/Delete translation applied to input.
+    options: TerminalOptions,
 }
 
 pub struct PtyManager {
     sessions: Arc<Mutex<HashMap<String, PtySession>>>,
+    recorders: SessionRecorders,
+    loggers: SessionLoggers,
+    scrollbacks: Scrollbacks,
+    cwds: TerminalCwds,
+    triggers: TriggerEngine,
+    macros: MacroRecorders,
+    history: CommandHistory,
+    inputs: InputHistories,
+    pastes: PasteModes,
+    taps: TerminalTaps,
+}
+
+/// Environment shared by every locally spawned terminal process.
+fn sanitize_child_env(cmd: &mut CommandBuilder, term: &str) {
+    cmd.env("TERM", term);
+
+    // Clear IDE/Editor specific variables that might interfere with git/ssh prompts
+    cmd.env_remove("GIT_ASKPASS");
+    cmd.env_remove("SSH_ASKPASS");
+    cmd.env_remove("VSCODE_GIT_ASKPASS");
+    cmd.env_remove("ELECTRON_RUN_AS_NODE");
+
+    // Fix for AppImage: Unset LD_LIBRARY_PATH and other vars to prevent
+    // bundled libraries from interfering with system binaries (like git).
+    if cfg!(target_os = "linux") && std::env::var("APPIMAGE").is_ok() {
+        cmd.env_remove("LD_LIBRARY_PATH");
+        cmd.env_remove("APPIMAGE");
+        cmd.env_remove("APPDIR");
+        cmd.env_remove("OWD");
+    }
+
+    // Route the new shell through the active exit node, if any.
+    for (key, value) in crate::tunnels::exit_node::active_env() {
+        cmd.env(key, value);
+    }
 }
 
 impl PtyManager {
     pub fn new() -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            recorders: SessionRecorders::new(),
+            loggers: SessionLoggers::new(),
+            scrollbacks: Scrollbacks::new(),
+            cwds: TerminalCwds::default(),
+            triggers: TriggerEngine::new(),
+            macros: MacroRecorders::new(),
+            history: CommandHistory::new(),
+            inputs: InputHistories::new(),
+            pastes: PasteModes::new(),
+            taps: TerminalTaps::new(),
         }
     }
 
@@ -388,21 +866,14 @@ impl PtyManager {
         output_channel: IpcChannel,
         shell_override: Option<String>,
         cwd: Option<String>,
+        session_log: Option<SessionLogOptions>,
+        shell_integration: bool,
+        env: Vec<(String, String)>,
+        options: TerminalOptions,
     ) -> Result<()> {
         // Clean up any existing dead/stale session with this ID before creating a new one
         let _ = self.close(&term_id).await;
 
-        let pty_system = native_pty_system();
-
-        let pair = pty_system
-            .openpty(PtySize {
-                rows,
-                cols,
-                pixel_width: 0,
-                pixel_height: 0,
-            })
-            .map_err(|e| anyhow!("Failed to open PTY: {}", e))?;
-
         // Determine shell to use based on platform and user preference
         let (shell, mut args, is_wsl_shell): (String, Vec<String>, bool) = if cfg!(target_os = "windows") {
             match shell_override.as_deref() {
@@ -471,12 +942,12 @@ impl PtyManager {
                 .filter(|path| !path.is_empty() && path.starts_with('/'));
             if linux_cwd.is_none() {
                 if let Some(original) = provided_cwd {
-                    eprintln!(
+                    crate::log_error!(
                         "[PTY] WSL: provided cwd '{}' is not a Linux path, falling back to '~'",
                         original
                     );
                 } else {
-                    eprintln!("[PTY] WSL: no Linux cwd provided, falling back to '~'");
+                    crate::log_error!("[PTY] WSL: no Linux cwd provided, falling back to '~'");
                 }
             }
             let wsl_cwd = linux_cwd.unwrap_or("~").to_string();
@@ -484,10 +955,27 @@ impl PtyManager {
             args.push(wsl_cwd);
         }
 
+        let mut integration_env = Vec::new();
+        if let Some(kind) =
+            IntegrationShell::detect(&shell).filter(|_| shell_integration && !is_wsl_shell)
+        {
+            let dir = std::env::temp_dir().join("zync-shell-integration");
+            match crate::shell_integration::prepare_local(kind, &dir) {
+                Ok(integration) => {
+                    args.extend(integration.args);
+                    integration_env = integration.env;
+                }
+                Err(e) => crate::log_error!("[PTY] Shell integration unavailable: {}", e),
+            }
+        }
+
         let mut cmd = CommandBuilder::new(&shell);
         for arg in &args {
             cmd.arg(arg);
         }
+        for (key, value) in &integration_env {
+            cmd.env(key, value);
+        }
 
         if !is_wsl_shell {
             if let Some(path) = cwd {
@@ -499,22 +987,97 @@ impl PtyManager {
         if !args.iter().any(|arg| arg == "-i") && is_posix_interactive_shell(&shell) {
             cmd.arg("-i");
         }
-        cmd.env("TERM", "xterm-256color");
-
-        // Clear IDE/Editor specific variables that might interfere with git/ssh prompts
-        cmd.env_remove("GIT_ASKPASS");
-        cmd.env_remove("SSH_ASKPASS");
-        cmd.env_remove("VSCODE_GIT_ASKPASS");
-        cmd.env_remove("ELECTRON_RUN_AS_NODE");
-
-        // Fix for AppImage: Unset LD_LIBRARY_PATH and other vars to prevent
-        // bundled libraries from interfering with system binaries (like git).
-        if cfg!(target_os = "linux") && std::env::var("APPIMAGE").is_ok() {
-            cmd.env_remove("LD_LIBRARY_PATH");
-            cmd.env_remove("APPIMAGE");
-            cmd.env_remove("APPDIR");
-            cmd.env_remove("OWD");
+        // The local tty keeps its own erase character; Backspace/Delete are
+        // only translated on input.
+        sanitize_child_env(&mut cmd, term_name(&options));
+        for (key, value) in with_locale(&options, env) {
+            cmd.env(key, value);
+        }
+
+        let navigate_shell = local_navigate_shell_style(
+            shell_override.as_deref(),
+            is_wsl_shell,
+            &shell,
+        );
+        self.spawn_local(
+            term_id,
+            connection_id,
+            generation,
+            cols,
+            rows,
+            app_handle,
+            output_channel,
+            cmd,
+            navigate_shell,
+            session_log,
+            options,
+        )
+        .await
+    }
+
+    /// Runs `mosh-client` in a local PTY against a server bootstrapped over SSH
+    /// (see `crate::mosh`). The session belongs to `connection_id` like a remote one.
+    pub async fn create_mosh_session(
+        &self,
+        term_id: String,
+        connection_id: String,
+        generation: u32,
+        cols: u16,
+        rows: u16,
+        app_handle: AppHandle,
+        output_channel: IpcChannel,
+        client: crate::mosh::MoshClientCommand,
+        session_log: Option<SessionLogOptions>,
+    ) -> Result<()> {
+        let _ = self.close(&term_id).await;
+
+        let mut cmd = CommandBuilder::new(&client.program);
+        for arg in &client.args {
+            cmd.arg(arg);
         }
+        cmd.env("MOSH_KEY", &client.key);
+        sanitize_child_env(&mut cmd, DEFAULT_TERM);
+
+        self.spawn_local(
+            term_id,
+            connection_id,
+            generation,
+            cols,
+            rows,
+            app_handle,
+            output_channel,
+            cmd,
+            NavigateShellStyle::Posix,
+            session_log,
+            TerminalOptions::default(),
+        )
+        .await
+    }
+
+    async fn spawn_local(
+        &self,
+        term_id: String,
+        connection_id: String,
+        generation: u32,
+        cols: u16,
+        rows: u16,
+        app_handle: AppHandle,
+        output_channel: IpcChannel,
+        cmd: CommandBuilder,
+        navigate_shell: NavigateShellStyle,
+        session_log: Option<SessionLogOptions>,
+        options: TerminalOptions,
+    ) -> Result<()> {
+        let pty_system = native_pty_system();
+
+        let pair = pty_system
+            .openpty(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| anyhow!("Failed to open PTY: {}", e))?;
 
         let mut child = pair
             .slave
@@ -538,11 +1101,7 @@ impl PtyManager {
         let child_killer = child.clone_killer();
         let child_pid = child.process_id();
 
-        let navigate_shell = local_navigate_shell_style(
-            shell_override.as_deref(),
-            is_wsl_shell,
-            &shell,
-        );
+        let connection_id_for_triggers = connection_id.clone();
         let session = PtySession {
             connection_id,
             output_channel: output_channel.clone(),
@@ -555,11 +1114,28 @@ impl PtyManager {
                 child_pid,
             },
             navigate_shell,
+            size: (cols, rows),
+            options,
         };
 
         let mut sessions = self.sessions.lock().await;
         sessions.insert(term_id.clone(), session);
         drop(sessions);
+        self.start_session_log(&term_id, session_log);
+        state_sync::notify(
+            &app_handle,
+            StateKind::Terminal,
+            &term_id,
+            ChangeKind::Added,
+        );
+        crate::plugin_hooks::emit(
+            &app_handle,
+            crate::plugin_hooks::Hook::TerminalCreated,
+            crate::plugin_hooks::TerminalCreated {
+                term_id: &term_id,
+                connection_id: &connection_id_for_triggers,
+            },
+        );
 
         // Spawn a task to read from PTY, but gate its first read until after
         // ready has been published. This keeps the session insertion atomic and
@@ -572,8 +1148,11 @@ impl PtyManager {
         let output_tx_for_wait = output_tx.clone();
 
         tokio::task::spawn_blocking(move || {
-            let exit_code = child.wait().ok().map(|status| status.exit_code());
-            let _ = output_tx_for_wait.blocking_send(LocalReaderEvent::Finished { exit_code });
+            let exit = child
+                .wait()
+                .map(|status| TerminalExit::from_local(&status))
+                .unwrap_or_else(|_| TerminalExit::closed());
+            let _ = output_tx_for_wait.blocking_send(LocalReaderEvent::Finished(exit));
         });
 
         tokio::task::spawn_blocking(move || {
@@ -582,7 +1161,10 @@ impl PtyManager {
             loop {
                 match reader.read(&mut buf) {
                     Ok(0) => {
-                        let _ = output_tx.blocking_send(LocalReaderEvent::Finished { exit_code: None });
+                        // The PTY usually reaches EOF just before `wait` returns;
+                        // give it a moment to report the real status first.
+                        std::thread::sleep(std::time::Duration::from_millis(LOCAL_EXIT_GRACE_MS));
+                        let _ = output_tx.blocking_send(LocalReaderEvent::Finished(TerminalExit::closed()));
                         break;
                     }
                     Ok(n) => {
@@ -594,8 +1176,9 @@ impl PtyManager {
                         }
                     }
                     Err(e) => {
-                        eprintln!("Error reading from PTY: {}", e);
-                        let _ = output_tx.blocking_send(LocalReaderEvent::Finished { exit_code: None });
+                        crate::log_error!("Error reading from PTY: {}", e);
+                        std::thread::sleep(std::time::Duration::from_millis(LOCAL_EXIT_GRACE_MS));
+                        let _ = output_tx.blocking_send(LocalReaderEvent::Finished(TerminalExit::closed()));
                         break;
                     }
                 }
@@ -606,16 +1189,35 @@ impl PtyManager {
         let exit_emitted_clone = exit_emitted.clone();
         let sessions_for_exit = self.sessions.clone();
         let term_id_for_exit = term_id.clone();
+        let recorders = self.recorders.clone();
+        let loggers = self.loggers.clone();
+        let scrollbacks = self.scrollbacks.clone();
+        let cwds = self.cwds.clone();
+        let history = self.history.clone();
+        let triggers = self.triggers.clone();
+        let macros = self.macros.clone();
+        let inputs = self.inputs.clone();
+        let pastes = self.pastes.clone();
+        let taps = self.taps.clone();
 
         let reader_handle = tokio::spawn(async move {
             let mut pending_output = Vec::new();
             let mut flush_deadline: Option<Instant> = None;
+            let mut tracker = CommandTracker::new();
 
             loop {
                 tokio::select! {
                     event = output_rx.recv() => {
                         match event {
                             Some(LocalReaderEvent::Data(chunk)) => {
+                                recorders.output(&term_id_clone, &chunk);
+                                scrollbacks.output(&term_id_clone, &chunk);
+                                loggers.output(&term_id_clone, &chunk);
+                                triggers.output(&term_id_clone, &connection_id_for_triggers, &chunk);
+                                inputs.output(&term_id_clone, &chunk);
+                                pastes.output(&term_id_clone, &chunk);
+                                taps.output(&app_handle_clone, &term_id_clone, &chunk);
+                                emit_shell_events(&app_handle_clone, &cwds, &history, &term_id_clone, &connection_id_for_triggers, generation, tracker.feed(&chunk));
                                 pending_output.extend_from_slice(&chunk);
 
                                 if pending_output.len() >= OUTPUT_FLUSH_THRESHOLD {
@@ -625,14 +1227,23 @@ impl PtyManager {
                                     flush_deadline = Some(Instant::now() + Duration::from_millis(OUTPUT_BATCH_MS));
                                 }
                             }
-                            Some(LocalReaderEvent::Finished { exit_code }) => {
+                            Some(LocalReaderEvent::Finished(exit)) => {
                                 flush_pending_output(&output_channel_clone, generation, &mut pending_output);
+                                recorders.discard(&term_id_clone);
+                                loggers.stop(&term_id_clone);
+                                scrollbacks.discard(&term_id_clone);
+                                cwds.remove(&term_id_clone);
+                                triggers.remove(&term_id_clone);
+                                macros.discard(&term_id_clone);
+                                inputs.remove(&term_id_clone);
+                                pastes.remove(&term_id_clone);
+                                taps.remove(&term_id_clone);
                                 if !exit_emitted_clone.swap(true, Ordering::SeqCst) {
                                     emit_terminal_exit(
                                         &app_handle_clone,
                                         &term_id_clone,
                                         generation,
-                                        exit_code,
+                                        exit,
                                     );
                                     let sessions_ref = sessions_for_exit.clone();
                                     let term_id_cleanup = term_id_for_exit.clone();
@@ -699,6 +1310,13 @@ impl PtyManager {
         shell_override: Option<String>,
         remote_os: Option<String>,
         cwd: Option<String>,
+        session_log: Option<SessionLogOptions>,
+        persistent_launch: Option<String>,
+        integration_bootstrap: Option<String>,
+        quiet_login: bool,
+        startup_commands: Vec<String>,
+        env: Vec<(String, String)>,
+        options: TerminalOptions,
     ) -> Result<()> {
         // Clean up any existing dead/stale session with this ID before creating a new one
         let _ = self.close(&term_id).await;
@@ -707,23 +1325,31 @@ impl PtyManager {
         channel
             .request_pty(
                 false,
-                "xterm-256color",
+                term_name(&options),
                 cols as u32,
                 rows as u32,
                 0,
                 0,
-                &[], // No modes for now
+                &pty_modes(&options),
             )
             .await
             .map_err(|e| anyhow!("Failed to request PTY: {}", e))?;
 
         let remote_is_windows = is_remote_windows(remote_os.as_deref());
+        let env = with_locale(&options, env);
+        let refused_env = request_remote_env(&mut channel, &env).await;
         let selected_shell = shell_override
             .as_deref()
             .map(str::trim)
             .filter(|s| !s.is_empty() && !s.eq_ignore_ascii_case("default"));
 
-        if let Some(shell) = selected_shell {
+        if let Some(launch) = persistent_launch.as_deref() {
+            // tmux/screen attach-or-create; the multiplexer starts the shell itself.
+            channel
+                .exec(false, launch)
+                .await
+                .map_err(|e| anyhow!("Failed to attach persistent session: {}", e))?;
+        } else if let Some(shell) = selected_shell {
             // Start explicit remote shell (path or command name) when user selected one.
             // Unix hosts use `exec` to replace the current command process with the chosen shell.
             // Windows OpenSSH hosts need native shell executables instead of POSIX `exec`.
@@ -752,8 +1378,18 @@ impl PtyManager {
                 .map_err(|e| anyhow!("Failed to request shell: {}", e))?;
         }
 
-        // If cwd is provided, send a cd command immediately.
-        if let Some(path) = cwd {
+        // Variables sshd refused are exported in the new shell instead (POSIX shells
+        // only, and not into a reattached session).
+        if !refused_env.is_empty() && !remote_is_windows && persistent_launch.is_none() {
+            channel
+                .data(export_line(&refused_env).as_bytes())
+                .await
+                .map_err(|e| anyhow!("Failed to export environment: {}", e))?;
+        }
+
+        // If cwd is provided, send a cd command immediately. Skipped when reattaching,
+        // where it would be typed into whatever the persistent session is running.
+        if let Some(path) = cwd.filter(|_| persistent_launch.is_none()) {
             let cd_cmd = if remote_is_windows {
                 match selected_shell.map(classify_windows_shell).unwrap_or(ShellKind::Other) {
                     ShellKind::Cmd => {
@@ -786,6 +1422,17 @@ impl PtyManager {
                 .map_err(|e| anyhow!("Failed to send initial cd command: {}", e))?;
         }
 
+        if let Some(bootstrap) = integration_bootstrap.filter(|_| persistent_launch.is_none()) {
+            channel
+                .data(bootstrap.as_bytes())
+                .await
+                .map_err(|e| anyhow!("Failed to load shell integration: {}", e))?;
+        }
+
+        // Reattaching resumes whatever was running; startup commands are for fresh shells.
+        let mut startup =
+            PendingStartup::new(&startup_commands).filter(|_| persistent_launch.is_none());
+
         // Create channels for communication
         let (tx, mut rx) = mpsc::channel::<Vec<u8>>(32);
         let (resize_tx, mut resize_rx) = mpsc::channel::<(u16, u16)>(4);
@@ -804,11 +1451,28 @@ impl PtyManager {
                 task_handle: None,
             },
             navigate_shell,
+            size: (cols, rows),
+            options,
         };
 
         let mut sessions = self.sessions.lock().await;
         sessions.insert(term_id.clone(), session);
         drop(sessions);
+        self.start_session_log(&term_id, session_log);
+        state_sync::notify(
+            &app_handle,
+            StateKind::Terminal,
+            &term_id,
+            ChangeKind::Added,
+        );
+        crate::plugin_hooks::emit(
+            &app_handle,
+            crate::plugin_hooks::Hook::TerminalCreated,
+            crate::plugin_hooks::TerminalCreated {
+                term_id: &term_id,
+                connection_id: &connection_id_for_transport,
+            },
+        );
 
         // Notify frontend that terminal is ready for input
         let _ = app_handle.emit(
@@ -824,6 +1488,16 @@ impl PtyManager {
         let output_channel_clone = output_channel.clone();
         let sessions_for_exit = self.sessions.clone();
         let term_id_for_exit = term_id.clone();
+        let recorders = self.recorders.clone();
+        let loggers = self.loggers.clone();
+        let scrollbacks = self.scrollbacks.clone();
+        let cwds = self.cwds.clone();
+        let history = self.history.clone();
+        let triggers = self.triggers.clone();
+        let macros = self.macros.clone();
+        let inputs = self.inputs.clone();
+        let pastes = self.pastes.clone();
+        let taps = self.taps.clone();
 
         // Spawn the manager task only after ready has been published so same-generation
         // output/exit events can never arrive before the frontend has seen ready.
@@ -831,13 +1505,55 @@ impl PtyManager {
             let app_handle = app_handle_clone;
             let mut pending_output = Vec::new();
             let mut flush_deadline: Option<Instant> = None;
+            let mut tracker = CommandTracker::new();
+            let mut zmodem = ZmodemDetector::new();
+            let mut quiet = quiet_login.then(LoginNoiseFilter::new);
 
             loop {
                 tokio::select! {
                     msg = channel.wait() => {
                         match msg {
                             Some(ChannelMsg::Data { ref data }) => {
-                                pending_output.extend_from_slice(data.as_ref());
+                                let scan = zmodem.feed(data.as_ref());
+                                if let Some(direction) = scan.started {
+                                    if let Err(e) = channel.data(crate::zmodem::CANCEL).await {
+                                        crate::log_error!("[PTY] Failed to cancel ZMODEM: {}", e);
+                                    }
+                                    emit_zmodem(
+                                        &app_handle,
+                                        &term_id_clone,
+                                        &connection_id_for_transport,
+                                        generation,
+                                        direction,
+                                        tracker.current_command(),
+                                        cwds.get(&term_id_clone),
+                                    );
+                                }
+                                let data = &data[..scan.passthrough];
+                                let filtered;
+                                let data = match quiet.as_mut() {
+                                    Some(filter) => {
+                                        filtered = filter.feed(data);
+                                        if filter.is_done() {
+                                            quiet = None;
+                                        }
+                                        &filtered[..]
+                                    }
+                                    None => data,
+                                };
+                                recorders.output(&term_id_clone, data);
+                                scrollbacks.output(&term_id_clone, data);
+                                loggers.output(&term_id_clone, data);
+                                triggers.output(&term_id_clone, &connection_id_for_transport, data);
+                                inputs.output(&term_id_clone, data);
+                                pastes.output(&term_id_clone, data);
+                                taps.output(&app_handle, &term_id_clone, data);
+                                emit_shell_events(&app_handle, &cwds, &history, &term_id_clone, &connection_id_for_transport, generation, tracker.feed(data));
+                                pending_output.extend_from_slice(data);
+
+                                if startup.is_some() && ends_with_prompt(data) {
+                                    send_startup_commands(&channel, &mut startup).await;
+                                }
 
                                 if pending_output.len() >= OUTPUT_FLUSH_THRESHOLD {
                                     flush_pending_output(&output_channel_clone, generation, &mut pending_output);
@@ -852,20 +1568,35 @@ impl PtyManager {
                                     &app_handle,
                                     &term_id_clone,
                                     generation,
-                                    Some(exit_status),
+                                    TerminalExit::exited(exit_status),
+                                );
+                                break;
+                            }
+                            Some(ChannelMsg::ExitSignal { signal_name, error_message, .. }) => {
+                                flush_pending_output(&output_channel_clone, generation, &mut pending_output);
+                                let message = Some(error_message).filter(|message| !message.is_empty());
+                                emit_terminal_exit(
+                                    &app_handle,
+                                    &term_id_clone,
+                                    generation,
+                                    TerminalExit::signaled(remote_signal_name(&signal_name), message),
                                 );
                                 break;
                             }
                             Some(ChannelMsg::Eof) => {
+                                // The shell's output is done; its exit status (or the
+                                // channel close) follows.
                                 flush_pending_output(&output_channel_clone, generation, &mut pending_output);
-                                emit_connection_transport_lost(&app_handle, &connection_id_for_transport);
-                                emit_terminal_exit(&app_handle, &term_id_clone, generation, None);
+                            }
+                            Some(ChannelMsg::Close) => {
+                                flush_pending_output(&output_channel_clone, generation, &mut pending_output);
+                                emit_terminal_exit(&app_handle, &term_id_clone, generation, TerminalExit::closed());
                                 break;
                             }
                             None => {
                                 flush_pending_output(&output_channel_clone, generation, &mut pending_output);
                                 emit_connection_transport_lost(&app_handle, &connection_id_for_transport);
-                                emit_terminal_exit(&app_handle, &term_id_clone, generation, None);
+                                emit_terminal_exit(&app_handle, &term_id_clone, generation, TerminalExit::connection_lost());
                                 break;
                             }
                             _ => {}
@@ -881,9 +1612,17 @@ impl PtyManager {
                         flush_deadline = None;
                     }
 
+                    _ = async {
+                        if let Some(pending) = startup.as_ref() {
+                            tokio::time::sleep_until(pending.deadline).await;
+                        }
+                    }, if startup.is_some() => {
+                        send_startup_commands(&channel, &mut startup).await;
+                    }
+
                     Some(input) = rx.recv() => {
                         if let Err(e) = channel.data(&input[..]).await {
-                             eprintln!("[PTY] Failed to send data to channel: {}", e);
+                             crate::log_error!("[PTY] Failed to send data to channel: {}", e);
                              emit_connection_transport_lost(&app_handle, &connection_id_for_transport);
                              break;
                         }
@@ -895,13 +1634,22 @@ impl PtyManager {
                             r = latest_r;
                         }
                         if let Err(e) = channel.window_change(c as u32, r as u32, 0, 0).await {
-                            eprintln!("[PTY] Failed to resize channel: {}", e);
+                            crate::log_error!("[PTY] Failed to resize channel: {}", e);
                         }
                     }
                 }
             }
 
             flush_pending_output(&output_channel_clone, generation, &mut pending_output);
+            recorders.discard(&term_id_clone);
+            loggers.stop(&term_id_clone);
+            scrollbacks.discard(&term_id_clone);
+            cwds.remove(&term_id_clone);
+            triggers.remove(&term_id_clone);
+            macros.discard(&term_id_clone);
+            inputs.remove(&term_id_clone);
+            pastes.remove(&term_id_clone);
+            taps.remove(&term_id_clone);
             let _ = channel.close().await;
 
             let mut sessions = sessions_for_exit.lock().await;
@@ -931,33 +1679,66 @@ impl PtyManager {
     }
 
     pub async fn write(&self, term_id: &str, data: &str) -> Result<()> {
-        let (local_writer_opt, remote_tx_opt) = {
+        self.write_with_progress(term_id, data, |_| {}).await
+    }
+
+    /// Writes input to the terminal; large pastes go out in paced chunks (see
+    /// `crate::paste`), calling `on_progress` after each chunk of very large ones.
+    pub async fn write_with_progress(
+        &self,
+        term_id: &str,
+        data: &str,
+        on_progress: impl Fn(PasteProgress) + Send + Sync,
+    ) -> Result<()> {
+        let (local_writer_opt, remote_tx_opt, input) = {
             let sessions = self.sessions.lock().await;
             let session = sessions
                 .get(term_id)
                 .ok_or_else(|| anyhow!("Session not found: {}", term_id))?;
+            let input = translate_keys(&session.options, data).as_bytes().to_vec();
             
             match &session.handle {
-                TerminalHandle::Local { writer, .. } => (Some(writer.clone()), None),
-                TerminalHandle::Remote { tx, .. } => (None, Some(tx.clone())),
+                TerminalHandle::Local { writer, .. } => (Some(writer.clone()), None, input),
+                TerminalHandle::Remote { tx, .. } => (None, Some(tx.clone()), input),
             }
         }; // sessions lock is dropped here
 
-        if let Some(writer) = local_writer_opt {
-            let mut writer = writer.lock().await;
-            writer
-                .write_all(data.as_bytes())
-                .map_err(|e| anyhow!("Failed to write to PTY: {}", e))?;
-            writer
-                .flush()
-                .map_err(|e| anyhow!("Failed to flush PTY: {}", e))?;
-        } else if let Some(tx) = remote_tx_opt {
-            // Send data to the manager task
-            tx.send(data.as_bytes().to_vec())
-                .await
-                .map_err(|e| anyhow!("Failed to send input to SSH task: {}", e))?;
+        let input = paste::prepare(input, self.pastes.bracketed(term_id));
+        let input_lock = self.pastes.input_lock(term_id);
+        let _input_guard = input_lock.lock().await;
+        let total = input.len();
+        let mut sent = 0;
+        for chunk in paste::chunks(&input) {
+            if let Some(writer) = &local_writer_opt {
+                let mut writer = writer.lock().await;
+                writer
+                    .write_all(chunk)
+                    .map_err(|e| anyhow!("Failed to write to PTY: {}", e))?;
+                writer
+                    .flush()
+                    .map_err(|e| anyhow!("Failed to flush PTY: {}", e))?;
+            } else if let Some(tx) = &remote_tx_opt {
+                // Send data to the manager task
+                tx.send(chunk.to_vec())
+                    .await
+                    .map_err(|e| anyhow!("Failed to send input to SSH task: {}", e))?;
+            }
+            sent += chunk.len();
+            if total >= paste::PROGRESS_MIN_BYTES {
+                on_progress(PasteProgress {
+                    term_id: term_id.to_string(),
+                    sent,
+                    total,
+                });
+            }
+            if sent < total {
+                tokio::time::sleep(paste::CHUNK_PAUSE).await;
+            }
         }
 
+        self.recorders.input(term_id, data);
+        self.macros.input(term_id, data);
+        self.inputs.input(term_id, data);
         Ok(())
     }
 
@@ -967,6 +1748,7 @@ impl PtyManager {
             let session = sessions
                 .get_mut(term_id)
                 .ok_or_else(|| anyhow!("Session not found: {}", term_id))?;
+            session.size = (cols, rows);
 
             match &mut session.handle {
                 TerminalHandle::Local { master, .. } => {
@@ -994,9 +1776,152 @@ impl PtyManager {
                 .map_err(|e| anyhow!("Failed to send resize to SSH task: {}", e))?;
         }
 
+        self.recorders.resize(term_id, cols, rows);
         Ok(())
     }
 
+    pub async fn start_recording(
+        &self,
+        term_id: &str,
+        dir: &std::path::Path,
+        title: Option<&str>,
+        capture_input: bool,
+    ) -> Result<RecordingInfo> {
+        let (cols, rows) = {
+            let sessions = self.sessions.lock().await;
+            sessions
+                .get(term_id)
+                .ok_or_else(|| anyhow!("Session not found: {}", term_id))?
+                .size
+        };
+        self.recorders
+            .start(term_id, dir, cols, rows, title, capture_input)
+    }
+
+    pub fn stop_recording(&self, term_id: &str) -> Result<RecordingInfo> {
+        self.recorders.stop(term_id)
+    }
+
+    pub fn recording_status(&self, term_id: &str) -> Option<RecordingInfo> {
+        self.recorders.status(term_id)
+    }
+
+    /// Logging is best-effort: a log directory we cannot write must not block the terminal.
+    fn start_session_log(&self, term_id: &str, options: Option<SessionLogOptions>) {
+        if let Some(options) = options {
+            if let Err(e) = self.loggers.start(term_id, options) {
+                crate::log_error!("[PTY] Failed to start session log for {}: {}", term_id, e);
+            }
+        }
+    }
+
+    pub fn session_log_path(&self, term_id: &str) -> Option<std::path::PathBuf> {
+        self.loggers.path(term_id)
+    }
+
+    /// Caps the terminal's backend scrollback; evicted output spills next to its
+    /// session log when logging is on.
+    pub fn configure_scrollback(&self, term_id: &str, limit_bytes: usize, total_bytes: usize) {
+        self.scrollbacks.set_total_limit(total_bytes);
+        let spill = self
+            .loggers
+            .path(term_id)
+            .map(|log| log.with_extension("spill"));
+        self.scrollbacks.configure(term_id, limit_bytes, spill);
+    }
+
+    pub fn scrollback(
+        &self,
+        term_id: &str,
+        max_bytes: Option<usize>,
+        include_spilled: bool,
+    ) -> Option<ScrollbackSnapshot> {
+        self.scrollbacks
+            .snapshot(term_id, max_bytes, include_spilled)
+    }
+
+    pub fn scrollback_stats(&self) -> ScrollbackStats {
+        self.scrollbacks.stats()
+    }
+
+    pub async fn start_macro_recording(&self, term_id: &str) -> Result<()> {
+        if !self.sessions.lock().await.contains_key(term_id) {
+            return Err(anyhow!("Session not found: {}", term_id));
+        }
+        self.macros.start(term_id)
+    }
+
+    pub fn stop_macro_recording(&self, term_id: &str) -> Result<Vec<MacroStep>> {
+        self.macros.stop(term_id)
+    }
+
+    /// Replays recorded input; stops at the first write that fails (terminal closed).
+    pub async fn play_macro(&self, term_id: &str, steps: &[MacroStep], speed: f64) -> Result<()> {
+        for step in steps {
+            if step.delay_ms > 0 {
+                tokio::time::sleep(crate::macros::scaled_delay(step, speed)).await;
+            }
+            self.write(term_id, &step.data).await?;
+        }
+        Ok(())
+    }
+
+    pub fn triggers(&self) -> &TriggerEngine {
+        &self.triggers
+    }
+
+    pub fn history(&self) -> &CommandHistory {
+        &self.history
+    }
+
+    pub fn taps(&self) -> &TerminalTaps {
+        &self.taps
+    }
+
+    /// Lines recently entered in the terminal, newest first.
+    pub async fn input_history(&self, term_id: &str, limit: usize) -> Result<Vec<InputLine>> {
+        if !self.sessions.lock().await.contains_key(term_id) {
+            return Err(anyhow!("Session not found: {}", term_id));
+        }
+        Ok(self.inputs.lines(term_id, limit))
+    }
+
+    /// Open terminals as (term id, connection id).
+    pub async fn terminals(&self) -> Vec<(String, String)> {
+        let sessions = self.sessions.lock().await;
+        sessions
+            .iter()
+            .map(|(id, session)| (id.clone(), session.connection_id.clone()))
+            .collect()
+    }
+
+    pub async fn connection_id(&self, term_id: &str) -> Option<String> {
+        let sessions = self.sessions.lock().await;
+        sessions
+            .get(term_id)
+            .map(|session| session.connection_id.clone())
+    }
+
+    /// The terminal's working directory: the last one its shell reported (OSC 7),
+    /// else for local terminals the shell process's own cwd. `None` for remote
+    /// shells without shell integration.
+    pub async fn cwd(&self, term_id: &str) -> Result<Option<String>> {
+        let child_pid = {
+            let sessions = self.sessions.lock().await;
+            let session = sessions
+                .get(term_id)
+                .ok_or_else(|| anyhow!("Terminal session not found"))?;
+            match &session.handle {
+                TerminalHandle::Local { child_pid, .. } => *child_pid,
+                TerminalHandle::Remote { .. } => None,
+            }
+        };
+        if let Some(cwd) = self.cwds.get(term_id) {
+            return Ok(Some(cwd));
+        }
+        Ok(child_pid.and_then(process_cwd))
+    }
+
     /// True when the local shell has child processes (foreground/background jobs).
     /// Remote sessions always return false — callers should use output-based busy detection.
     pub async fn has_active_child_processes(&self, term_id: &str) -> bool {
@@ -1024,10 +1949,20 @@ impl PtyManager {
         if let Some(mut session) = sessions.remove(term_id) {
             Self::cleanup_session_handles(&mut session.handle);
         }
+        self.recorders.discard(term_id);
+        self.loggers.stop(term_id);
+        self.scrollbacks.discard(term_id);
+        self.cwds.remove(term_id);
+        self.triggers.remove(term_id);
+        self.macros.discard(term_id);
+        self.inputs.remove(term_id);
+        self.pastes.remove(term_id);
+        self.taps.remove(term_id);
         Ok(())
     }
 
-    pub async fn close_by_connection(&self, connection_id: &str) -> Result<()> {
+    /// Closes every terminal of a connection and returns their ids.
+    pub async fn close_by_connection(&self, connection_id: &str) -> Result<Vec<String>> {
         let mut sessions = self.sessions.lock().await;
         let mut ids_to_remove = Vec::new();
 
@@ -1037,19 +1972,33 @@ impl PtyManager {
             }
         }
 
-        for id in ids_to_remove {
-            if let Some(mut session) = sessions.remove(&id) {
+        for id in &ids_to_remove {
+            if let Some(mut session) = sessions.remove(id) {
                 Self::cleanup_session_handles(&mut session.handle);
             }
+            self.recorders.discard(id);
+            self.loggers.stop(id);
+            self.scrollbacks.discard(id);
+            self.cwds.remove(id);
+            self.triggers.remove(id);
+            self.macros.discard(id);
+            self.inputs.remove(id);
+            self.pastes.remove(id);
+            self.taps.remove(id);
         }
 
-        Ok(())
+        Ok(ids_to_remove)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{build_navigate_cd_command, posix_shell_cd_path, NavigateShellStyle};
+    use super::{
+        build_navigate_cd_command, ends_with_prompt, environment_pairs, export_line,
+        posix_shell_cd_path, term_name, translate_keys, with_locale, NavigateShellStyle,
+        PendingStartup, TerminalExit, TerminalLifecycleEvent,
+    };
+    use crate::types::{BackspaceKey, DeleteKey, TerminalOptions};
 
     #[test]
     fn build_navigate_cd_command_uses_cmd_syntax_for_windows_cmd() {
@@ -1082,4 +2031,113 @@ mod tests {
         assert_eq!(posix_shell_cd_path("/home/user"), "'/home/user'");
         assert_eq!(posix_shell_cd_path("/home/a b"), "'/home/a b'");
     }
+
+    #[test]
+    fn startup_commands_wait_for_a_prompt() {
+        assert!(PendingStartup::new(&[" ".to_string()]).is_none());
+        let pending =
+            PendingStartup::new(&["cd /srv/app".to_string(), "source env.sh ".to_string()])
+                .unwrap();
+        assert_eq!(pending.input, b"cd /srv/app\rsource env.sh\r");
+
+        assert!(ends_with_prompt(b"Last login: today\r\nme@web:~$ "));
+        assert!(!ends_with_prompt(b"Welcome to Ubuntu\r\n"));
+        assert!(!ends_with_prompt(b"loading profile"));
+    }
+
+    #[test]
+    fn environment_is_validated_and_exported_quoted() {
+        let env = std::collections::HashMap::from([
+            ("AWS_PROFILE".to_string(), "prod".to_string()),
+            ("1BAD".to_string(), "x".to_string()),
+            ("GREETING".to_string(), "it's here".to_string()),
+            ("A-B".to_string(), "x".to_string()),
+        ]);
+        let pairs = environment_pairs(Some(&env));
+        assert_eq!(
+            pairs,
+            vec![
+                ("AWS_PROFILE".to_string(), "prod".to_string()),
+                ("GREETING".to_string(), "it's here".to_string()),
+            ]
+        );
+        assert_eq!(
+            export_line(&pairs),
+            " export AWS_PROFILE='prod' GREETING='it'\\''s here'\r"
+        );
+        assert!(environment_pairs(None).is_empty());
+    }
+
+    #[test]
+    fn terminal_options_set_term_locale_and_keys() {
+        let options = TerminalOptions {
+            term: Some("vt100".to_string()),
+            lang: Some("de_DE.UTF-8".to_string()),
+            lc_all: Some(" ".to_string()),
+            backspace: BackspaceKey::CtrlH,
+            delete: DeleteKey::Del,
+        };
+        assert_eq!(term_name(&options), "vt100");
+        assert_eq!(
+            term_name(&TerminalOptions {
+                term: Some("xterm; rm -rf".to_string()),
+                ..TerminalOptions::default()
+            }),
+            "xterm-256color"
+        );
+
+        let env = with_locale(&options, vec![("EDITOR".to_string(), "vim".to_string())]);
+        assert_eq!(
+            env,
+            vec![
+                ("LANG".to_string(), "de_DE.UTF-8".to_string()),
+                ("EDITOR".to_string(), "vim".to_string()),
+            ]
+        );
+        let env = with_locale(&options, vec![("LANG".to_string(), "C".to_string())]);
+        assert_eq!(env, vec![("LANG".to_string(), "C".to_string())]);
+
+        assert_eq!(translate_keys(&options, "ab\x7f\x1b[3~"), "ab\x08\x7f");
+        assert_eq!(
+            translate_keys(&TerminalOptions::default(), "ab\x7f\x1b[3~"),
+            "ab\x7f\x1b[3~"
+        );
+    }
+
+    #[test]
+    fn exit_payload_distinguishes_shell_exit_from_connection_loss() {
+        let payload = |exit| {
+            serde_json::to_value(TerminalLifecycleEvent {
+                generation: 3,
+                exit,
+            })
+            .unwrap()
+        };
+        assert_eq!(
+            payload(TerminalExit::exited(1)),
+            serde_json::json!({
+                "generation": 3,
+                "exit_code": 1,
+                "reason": "exited",
+                "connection_lost": false,
+            })
+        );
+        assert_eq!(
+            payload(TerminalExit::signaled("KILL".to_string(), None)),
+            serde_json::json!({
+                "generation": 3,
+                "signal": "KILL",
+                "reason": "signaled",
+                "connection_lost": false,
+            })
+        );
+        assert_eq!(
+            payload(TerminalExit::connection_lost()),
+            serde_json::json!({
+                "generation": 3,
+                "reason": "connectionLost",
+                "connection_lost": true,
+            })
+        );
+    }
 }
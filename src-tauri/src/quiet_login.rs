@@ -0,0 +1,207 @@
+//! Quiet login: hides the MOTD and other login noise at the start of a remote
+//! shell (`quiet_login` on the connection).
+//!
+//! Until the first prompt appears (or a few seconds pass) output is handled a
+//! line at a time, and lines matching well-known noise — `Last login`, Ubuntu's
+//! landscape/ESM/update notices, the Debian licence blurb, mail notices — are
+//! dropped together with the blank lines around them. Everything after the first
+//! prompt passes through untouched. A permanent fix is `~/.hushlogin` on the
+//! server, which `connection_hushlogin` creates.
+
+use regex::RegexSet;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// Filtering stops after this long even if no prompt was recognised.
+const LOGIN_PHASE: Duration = Duration::from_secs(5);
+/// ... or after this much output.
+const LOGIN_PHASE_BYTES: usize = 64 * 1024;
+
+static NOISE: LazyLock<RegexSet> = LazyLock::new(|| {
+    RegexSet::new([
+        r"^Last (failed )?login: ",
+        r"^There (was|were) \d+ failed login attempts? since",
+        r"^Welcome to .*(Ubuntu|Debian|Linux|GNU)",
+        r"^ \* (Documentation|Management|Support|Strictly confined|Introducing|Ubuntu Pro)",
+        r"^\s*System information as of ",
+        r"^\s*(System load|Usage of /|Memory usage|Swap usage|Processes|Users logged in|IPv[46] address for \S+):",
+        r"^\d+ (updates?|of these updates) (can be|are|is)",
+        r"^\d+ additional security updates? can be applied",
+        r"^To see these additional updates run: apt list --upgradable",
+        r"^(Expanded Security Maintenance|Enable ESM (Apps|Infra)|See https://ubuntu\.com/esm|Learn more about enabling ESM)",
+        r"^The list of available updates is more than a week old\.",
+        r"^To check for new updates run: sudo apt update",
+        r"^\*\*\* System restart required \*\*\*",
+        r"^This system has been minimized by removing packages",
+        r"^To restore this content, you can run the 'unminimize' command\.",
+        r"^The programs included with the Debian GNU/Linux system are free software;",
+        r"^the exact distribution terms for each program are described in the",
+        r"^individual files in /usr/share/doc/\*/copyright\.",
+        r"^Debian GNU/Linux comes with ABSOLUTELY NO WARRANTY, to the extent",
+        r"^permitted by applicable law\.",
+        r"^You have (new )?mail",
+    ])
+    .expect("valid login noise patterns")
+});
+
+pub struct LoginNoiseFilter {
+    line: Vec<u8>,
+    pending_blank_lines: Vec<Vec<u8>>,
+    /// The last non-blank line was shown.
+    last_kept: bool,
+    deadline: Instant,
+    seen_bytes: usize,
+    done: bool,
+}
+
+impl Default for LoginNoiseFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoginNoiseFilter {
+    pub fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            pending_blank_lines: Vec::new(),
+            last_kept: false,
+            deadline: Instant::now() + LOGIN_PHASE,
+            seen_bytes: 0,
+            done: false,
+        }
+    }
+
+    /// The first prompt has been seen; output now passes through unchanged.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the part of `data` to show.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.done {
+            return data.to_vec();
+        }
+        self.seen_bytes += data.len();
+        if Instant::now() > self.deadline || self.seen_bytes > LOGIN_PHASE_BYTES {
+            self.done = true;
+            let mut out = std::mem::take(&mut self.line);
+            out.extend_from_slice(data);
+            return out;
+        }
+
+        let mut out = Vec::with_capacity(data.len());
+        for (i, &byte) in data.iter().enumerate() {
+            self.line.push(byte);
+            if byte == b'\n' {
+                let line = std::mem::take(&mut self.line);
+                self.filter_line(line, &mut out);
+            } else if i + 1 == data.len() && looks_like_prompt(&self.line) {
+                self.done = true;
+                self.pending_blank_lines.clear();
+                out.append(&mut self.line);
+            }
+        }
+        out
+    }
+
+    fn filter_line(&mut self, line: Vec<u8>, out: &mut Vec<u8>) {
+        let text = plain_text(&line);
+        let text = text.trim_end();
+        if text.trim().is_empty() {
+            // Blank lines only survive between two kept lines.
+            self.pending_blank_lines.push(line);
+        } else if NOISE.is_match(text) {
+            self.pending_blank_lines.clear();
+            self.last_kept = false;
+        } else {
+            for blank in self.pending_blank_lines.drain(..) {
+                if self.last_kept {
+                    out.extend_from_slice(&blank);
+                }
+            }
+            out.extend_from_slice(&line);
+            self.last_kept = true;
+        }
+    }
+}
+
+/// A trailing partial line that ends the way prompts do: in a space, or in one of
+/// `$ # % > ❯`. Anything else is a line split across chunks.
+pub(crate) fn looks_like_prompt(line: &[u8]) -> bool {
+    let text = plain_text(line);
+    let trimmed = text.trim_end();
+    !trimmed.is_empty()
+        && (trimmed.len() < text.len() || trimmed.ends_with(['$', '#', '%', '>', '❯']))
+}
+
+/// The line without ANSI escape sequences and carriage returns.
+fn plain_text(line: &[u8]) -> String {
+    let mut out = Vec::with_capacity(line.len());
+    let mut i = 0;
+    while i < line.len() {
+        match line[i] {
+            0x1b => {
+                i += 1;
+                match line.get(i) {
+                    Some(b'[') => {
+                        i += 1;
+                        while i < line.len() && !(0x40..=0x7e).contains(&line[i]) {
+                            i += 1;
+                        }
+                    }
+                    Some(b']') => {
+                        while i < line.len() && line[i] != 0x07 {
+                            if line[i] == 0x1b && line.get(i + 1) == Some(&b'\\') {
+                                i += 1;
+                                break;
+                            }
+                            i += 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            b'\r' => i += 1,
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_ubuntu_motd_until_prompt() {
+        let mut filter = LoginNoiseFilter::new();
+        let mut shown = filter.feed(
+            b"Welcome to Ubuntu 24.04.1 LTS (GNU/Linux 6.8.0-45-generic x86_64)\r\n\r\n \
+              * Documentation:  https://help.ubuntu.com\r\n * Management:     https://landscape.canonical.com\r\n\r\n  \
+              System information as of Tue Oct  1 10:00:00 UTC 2024\r\n\r\n  System load:  0.08\r\n",
+        );
+        shown.extend(filter.feed(
+            b"\r\n0 updates can be applied immediately.\r\n\r\nDeploy notice: maintenance at 22:00\r\n\r\n\
+              Last login: Mon Sep 30 09:00:00 2024 from 10.0.0.2\r\n\x1b[01;32mme@web-1\x1b[00m:~$ ",
+        ));
+        assert_eq!(
+            String::from_utf8(shown).unwrap(),
+            "Deploy notice: maintenance at 22:00\r\n\x1b[01;32mme@web-1\x1b[00m:~$ "
+        );
+        // After the prompt nothing is filtered.
+        assert_eq!(filter.feed(b"Last login: x\r\n"), b"Last login: x\r\n");
+    }
+
+    #[test]
+    fn holds_partial_lines_that_are_not_prompts() {
+        let mut filter = LoginNoiseFilter::new();
+        assert!(filter.feed(b"Last log").is_empty());
+        assert!(filter.feed(b"in: yesterday\r\n").is_empty());
+        assert_eq!(filter.feed(b"[root@db ~]# "), b"[root@db ~]# ");
+    }
+}
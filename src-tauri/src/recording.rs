@@ -0,0 +1,279 @@
+//! Opt-in terminal recording in asciicast v2 format.
+//!
+//! A recording streams straight to a `.cast` file (header line, then one
+//! `[seconds, "o" | "i" | "r", data]` event per line), so long sessions never sit
+//! in memory. Output is fed from the PTY reader tasks and input from
+//! `PtyManager::write`; both are no-ops unless the terminal is being recorded.
+
+use crate::terminal_registry::TerminalRegistry;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingInfo {
+    pub term_id: String,
+    pub path: String,
+    pub started_at: u64,
+    pub duration_secs: f64,
+    pub capture_input: bool,
+}
+
+struct Recording {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    started_at: u64,
+    capture_input: bool,
+    /// Trailing bytes of a UTF-8 sequence split across output chunks.
+    partial_utf8: Vec<u8>,
+}
+
+impl Recording {
+    fn write_event(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        let elapsed = (self.started.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000_000.0;
+        let line = serde_json::to_string(&(elapsed, code, data)).map_err(std::io::Error::other)?;
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn write_output(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let mut buf = std::mem::take(&mut self.partial_utf8);
+        buf.extend_from_slice(bytes);
+        let text = decode_complete_utf8(&mut buf);
+        self.partial_utf8 = buf;
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.write_event("o", &text)
+    }
+
+    fn info(&self, term_id: &str) -> RecordingInfo {
+        RecordingInfo {
+            term_id: term_id.to_string(),
+            path: self.path.to_string_lossy().to_string(),
+            started_at: self.started_at,
+            duration_secs: self.started.elapsed().as_secs_f64(),
+            capture_input: self.capture_input,
+        }
+    }
+}
+
+/// Decodes as much of `buf` as forms complete UTF-8 and leaves an incomplete
+/// trailing sequence in place. Invalid bytes are replaced, not kept.
+fn decode_complete_utf8(buf: &mut Vec<u8>) -> String {
+    let mut out = String::new();
+    let mut rest: &[u8] = buf;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                out.push_str(text);
+                rest = &[];
+                break;
+            }
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                match e.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    None => {
+                        rest = after;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    *buf = rest.to_vec();
+    out
+}
+
+/// Active recordings keyed by `term_id`; cheap to clone into reader tasks.
+#[derive(Clone, Default)]
+pub struct SessionRecorders {
+    active: TerminalRegistry<Recording>,
+}
+
+impl SessionRecorders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(
+        &self,
+        term_id: &str,
+        dir: &Path,
+        cols: u16,
+        rows: u16,
+        title: Option<&str>,
+        capture_input: bool,
+    ) -> Result<RecordingInfo> {
+        if self.active.contains(term_id) {
+            return Err(anyhow!("Terminal is already being recorded"));
+        }
+        std::fs::create_dir_all(dir)?;
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let safe_id: String = term_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let path = dir.join(format!("{}-{}.cast", safe_id, started_at));
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        let mut header = serde_json::json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": started_at,
+            "env": { "TERM": "xterm-256color" },
+        });
+        if let Some(title) = title.filter(|t| !t.trim().is_empty()) {
+            header["title"] = serde_json::Value::String(title.to_string());
+        }
+        writeln!(writer, "{}", header)?;
+
+        let recording = Recording {
+            writer,
+            path,
+            started: Instant::now(),
+            started_at,
+            capture_input,
+            partial_utf8: Vec::new(),
+        };
+        let info = recording.info(term_id);
+        self.active.insert(term_id, recording);
+        Ok(info)
+    }
+
+    pub fn stop(&self, term_id: &str) -> Result<RecordingInfo> {
+        let mut recording = self
+            .active
+            .remove(term_id)
+            .ok_or_else(|| anyhow!("Terminal is not being recorded"))?;
+        let tail = std::mem::take(&mut recording.partial_utf8);
+        if !tail.is_empty() {
+            recording.write_event("o", &String::from_utf8_lossy(&tail))?;
+        }
+        recording.writer.flush()?;
+        Ok(recording.info(term_id))
+    }
+
+    pub fn status(&self, term_id: &str) -> Option<RecordingInfo> {
+        self.active.with(term_id, |r| r.info(term_id))
+    }
+
+    fn with_recording(&self, term_id: &str, f: impl FnOnce(&mut Recording) -> std::io::Result<()>) {
+        if let Some(Err(e)) = self.active.with(term_id, f) {
+            crate::log_error!("[PTY] Recording write failed for {}: {}", term_id, e);
+        }
+    }
+
+    pub fn output(&self, term_id: &str, bytes: &[u8]) {
+        self.with_recording(term_id, |r| r.write_output(bytes));
+    }
+
+    pub fn input(&self, term_id: &str, data: &str) {
+        self.with_recording(term_id, |r| {
+            if r.capture_input {
+                r.write_event("i", data)
+            } else {
+                Ok(())
+            }
+        });
+    }
+
+    pub fn resize(&self, term_id: &str, cols: u16, rows: u16) {
+        self.with_recording(term_id, |r| {
+            r.write_event("r", &format!("{}x{}", cols, rows))
+        });
+    }
+
+    /// Finishes a recording whose terminal went away.
+    pub fn discard(&self, term_id: &str) {
+        let _ = self.stop(term_id);
+    }
+}
+
+/// Copies a finished `.cast` file to `destination`, refusing anything that is not a recording.
+pub fn export(source: &Path, destination: &Path) -> Result<()> {
+    if source.extension().and_then(|e| e.to_str()) != Some("cast") {
+        return Err(anyhow!("Not a terminal recording: {}", source.display()));
+    }
+    let header = std::fs::read_to_string(source)?
+        .lines()
+        .next()
+        .map(str::to_string)
+        .unwrap_or_default();
+    let parsed: serde_json::Value =
+        serde_json::from_str(&header).map_err(|_| anyhow!("Recording has no asciicast header"))?;
+    if parsed.get("version").and_then(|v| v.as_u64()) != Some(2) {
+        return Err(anyhow!("Unsupported asciicast version"));
+    }
+    std::fs::copy(source, destination)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_keeps_split_utf8_for_next_chunk() {
+        let bytes = "héllo".as_bytes();
+        let mut buf = bytes[..2].to_vec(); // "h" + first byte of "é"
+        assert_eq!(decode_complete_utf8(&mut buf), "h");
+        assert_eq!(buf, vec![bytes[1]]);
+        buf.extend_from_slice(&bytes[2..]);
+        assert_eq!(decode_complete_utf8(&mut buf), "éllo");
+        assert!(buf.is_empty());
+
+        let mut invalid = vec![b'a', 0xff, b'b'];
+        assert_eq!(decode_complete_utf8(&mut invalid), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn writes_asciicast_v2_file() {
+        let dir = std::env::temp_dir().join(format!("zync-rec-{}", uuid::Uuid::new_v4()));
+        let recorders = SessionRecorders::new();
+        let info = recorders
+            .start("term-1", &dir, 120, 40, Some("deploy"), false)
+            .unwrap();
+        recorders.output("term-1", b"$ ls\r\n");
+        recorders.input("term-1", "ls\r"); // not captured
+        recorders.resize("term-1", 100, 30);
+        recorders.stop("term-1").unwrap();
+
+        let content = std::fs::read_to_string(&info.path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        let header: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(header["version"], 2);
+        assert_eq!(header["width"], 120);
+        assert_eq!(header["title"], "deploy");
+        assert_eq!(lines.len(), 3);
+        let event: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(event[1], "o");
+        assert_eq!(event[2], "$ ls\r\n");
+        assert!(lines[2].ends_with(",\"r\",\"100x30\"]"));
+
+        let exported = dir.join("copy.cast");
+        export(Path::new(&info.path), &exported).unwrap();
+        assert!(exported.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
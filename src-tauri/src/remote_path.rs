@@ -0,0 +1,240 @@
+//! Remote path handling keyed by the detected server OS.
+//!
+//! Win32-OpenSSH exposes drives over SFTP as `/C:/Users/me`, while anything run
+//! through its shell expects `C:\Users\me`. The file manager keeps every remote
+//! path in SFTP form: [`RemotePathStyle::to_sftp`] accepts either spelling (so a
+//! pasted `C:\logs` works) and [`RemotePathStyle::to_native`] converts back for
+//! server-side commands, which go through PowerShell instead of `rm`/`cp`.
+//! PowerShell scripts are passed as `-EncodedCommand`, so nothing in a path
+//! reaches the server's default shell (cmd expands `%VAR%` even inside quotes).
+
+use crate::ai::tool_exec_support::shell_quote;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemotePathStyle {
+    Posix,
+    Windows,
+}
+
+/// Joins an SFTP-form directory and a child name. SFTP uses `/` on every
+/// server, Windows included.
+pub fn join(base: &str, name: &str) -> String {
+    if base.ends_with('/') {
+        format!("{}{}", base, name)
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+/// Runs `script` with Windows PowerShell, base64 UTF-16LE encoded as
+/// `-EncodedCommand` expects.
+pub fn powershell_command(script: &str) -> String {
+    use base64::{engine::general_purpose, Engine as _};
+    let utf16: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    format!(
+        "powershell -NoProfile -NonInteractive -EncodedCommand {}",
+        general_purpose::STANDARD.encode(utf16)
+    )
+}
+
+/// `C:` style drive prefix (without a leading slash).
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+impl RemotePathStyle {
+    pub fn from_os(detected_os: Option<&str>) -> Self {
+        match detected_os {
+            Some(os) if os.eq_ignore_ascii_case("windows") => Self::Windows,
+            _ => Self::Posix,
+        }
+    }
+
+    /// Normalizes a user- or server-supplied path into the form SFTP expects.
+    pub fn to_sftp(&self, path: &str) -> String {
+        match self {
+            Self::Posix => path.to_string(),
+            Self::Windows => {
+                let forward = path.replace('\\', "/");
+                if has_drive_prefix(&forward) {
+                    let rest = &forward[2..];
+                    let rest = if rest.is_empty() { "/" } else { rest };
+                    format!("/{}{}", &forward[..2].to_ascii_uppercase(), rest)
+                } else {
+                    forward
+                }
+            }
+        }
+    }
+
+    /// Converts an SFTP path into what the server's own shell understands.
+    pub fn to_native(&self, path: &str) -> String {
+        match self {
+            Self::Posix => path.to_string(),
+            Self::Windows => {
+                let trimmed = path
+                    .strip_prefix('/')
+                    .filter(|p| has_drive_prefix(p))
+                    .unwrap_or(path);
+                let native = trimmed.replace('/', "\\");
+                if native.len() == 2 && has_drive_prefix(&native) {
+                    format!("{}\\", native)
+                } else {
+                    native
+                }
+            }
+        }
+    }
+
+    /// Whether `reported`, the directory SFTP starts in, is not a usable home.
+    /// Some Win32-OpenSSH setups start at `/`, the virtual list of drives.
+    pub fn needs_home_lookup(&self, reported: &str) -> bool {
+        match self {
+            Self::Posix => false,
+            Self::Windows => !has_drive_prefix(reported.trim_start_matches('/')),
+        }
+    }
+
+    /// Prints the login user's home directory in native form.
+    pub fn home_command(&self) -> String {
+        match self {
+            Self::Posix => "printf '%s' \"$HOME\"".to_string(),
+            Self::Windows => powershell_command("[Console]::Out.Write($env:USERPROFILE)"),
+        }
+    }
+
+    /// Quotes a native path for the server's command shell.
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Posix => shell_quote(value),
+            Self::Windows => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    fn quoted_list(&self, paths: &[String]) -> String {
+        let separator = match self {
+            Self::Posix => " ",
+            Self::Windows => ",",
+        };
+        paths
+            .iter()
+            .map(|p| self.quote(&self.to_native(p)))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Recursive, forced delete of `paths` (SFTP form).
+    pub fn remove_command(&self, paths: &[String]) -> String {
+        match self {
+            Self::Posix => format!("rm -rf {}", self.quoted_list(paths)),
+            Self::Windows => powershell_command(&format!(
+                "Remove-Item -LiteralPath {} -Recurse -Force -ErrorAction Stop",
+                self.quoted_list(paths)
+            )),
+        }
+    }
+
+    /// Recursive copy of `from` to `to` (SFTP form).
+    pub fn copy_command(&self, from: &str, to: &str, preserve_symlinks: bool) -> String {
+        match self {
+            // `cp -r` already copies symlinks as links on GNU and BSD; `-P` makes
+            // that explicit when the caller asked for symlinks to be preserved.
+            Self::Posix => format!(
+                "{} {} {}",
+                if preserve_symlinks { "cp -RP" } else { "cp -r" },
+                self.quote(from),
+                self.quote(to)
+            ),
+            Self::Windows => powershell_command(&format!(
+                "Copy-Item -LiteralPath {} -Destination {} -Recurse -Force -ErrorAction Stop",
+                self.quote(&self.to_native(from)),
+                self.quote(&self.to_native(to))
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_paths_round_trip_between_forms() {
+        let style = RemotePathStyle::Windows;
+        assert_eq!(style.to_sftp("C:\\Users\\me"), "/C:/Users/me");
+        assert_eq!(style.to_sftp("d:"), "/D:/");
+        assert_eq!(style.to_sftp("/C:/Users/me"), "/C:/Users/me");
+        assert_eq!(style.to_native("/C:/Users/me"), "C:\\Users\\me");
+        assert_eq!(style.to_native("/C:"), "C:\\");
+        assert_eq!(join(&style.to_sftp("C:\\"), "Windows"), "/C:/Windows");
+        assert!(style.needs_home_lookup("/"));
+        assert!(!style.needs_home_lookup("/C:/Users/me"));
+    }
+
+    #[test]
+    fn posix_paths_are_untouched() {
+        let style = RemotePathStyle::Posix;
+        assert_eq!(style.to_sftp("/srv/app"), "/srv/app");
+        assert_eq!(join("/", "etc"), "/etc");
+        assert_eq!(join("/srv", "app"), "/srv/app");
+        assert!(!style.needs_home_lookup("/"));
+        assert_eq!(
+            style.remove_command(&["/tmp/a b".to_string(), "/tmp/c".to_string()]),
+            "rm -rf '/tmp/a b' '/tmp/c'"
+        );
+    }
+
+    fn decoded_script(command: &str) -> String {
+        use base64::{engine::general_purpose, Engine as _};
+        let encoded = command
+            .strip_prefix("powershell -NoProfile -NonInteractive -EncodedCommand ")
+            .unwrap();
+        let bytes = general_purpose::STANDARD.decode(encoded).unwrap();
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn windows_commands_use_powershell_literal_paths() {
+        let style = RemotePathStyle::Windows;
+        assert_eq!(
+            decoded_script(&style.remove_command(&[
+                "/C:/tmp/it's".to_string(),
+                "/C:/tmp/b".to_string()
+            ])),
+            "Remove-Item -LiteralPath 'C:\\tmp\\it''s','C:\\tmp\\b' -Recurse -Force -ErrorAction Stop"
+        );
+        assert!(decoded_script(&style.copy_command("/C:/a", "/D:/b", false))
+            .contains("Copy-Item -LiteralPath 'C:\\a' -Destination 'D:\\b'"));
+    }
+
+    #[test]
+    fn shell_syntax_in_paths_never_reaches_the_remote_shell() {
+        let style = RemotePathStyle::Windows;
+        let command = style.remove_command(&["/C:/%TEMP%/$(calc)\"& del x".to_string()]);
+        assert!(command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " -+/=".contains(c)));
+        assert_eq!(
+            decoded_script(&command),
+            "Remove-Item -LiteralPath 'C:\\%TEMP%\\$(calc)\"& del x' -Recurse -Force -ErrorAction Stop"
+        );
+    }
+
+    #[test]
+    fn detects_style_from_os() {
+        assert_eq!(
+            RemotePathStyle::from_os(Some("Windows")),
+            RemotePathStyle::Windows
+        );
+        assert_eq!(
+            RemotePathStyle::from_os(Some("ubuntu")),
+            RemotePathStyle::Posix
+        );
+        assert_eq!(RemotePathStyle::from_os(None), RemotePathStyle::Posix);
+    }
+}
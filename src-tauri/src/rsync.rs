@@ -0,0 +1,337 @@
+//! Delta transfers through the local `rsync` binary.
+//!
+//! rsync needs a remote shell. Instead of the system `ssh` client, its `-e` is
+//! this executable with [`BRIDGE_FLAG`]: the bridge connects back to a loopback
+//! port served by the transfer, proves itself with the token in
+//! [`BRIDGE_TOKEN_ENV`], and relays stdin and stdout to the remote `rsync
+//! --server` running on an exec channel of zync's own connection. The transfer
+//! therefore uses the connection's authentication, host key checks and jump
+//! hosts as they already are. Output comes back as [`frame`]s so stderr and the
+//! exit status survive the trip.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RsyncOptions {
+    /// Remove files on the destination that no longer exist on the source.
+    pub delete: bool,
+    pub dry_run: bool,
+    pub compress: bool,
+    /// Compare by checksum instead of size + mtime.
+    pub checksum: bool,
+    pub excludes: Vec<String>,
+    pub bandwidth_limit_kbps: Option<u32>,
+}
+
+/// One side of a transfer. `connection_id` is `"local"` for this machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RsyncEndpoint {
+    pub connection_id: String,
+    pub path: String,
+}
+
+impl RsyncEndpoint {
+    pub fn is_local(&self) -> bool {
+        self.connection_id == "local"
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RsyncVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl RsyncVersion {
+    /// Parses the first line of `rsync --version` ("rsync  version 3.2.7  protocol version 31").
+    /// openrsync and other lookalikes come back as `None`.
+    pub fn parse(output: &str) -> Option<Self> {
+        let line = output.lines().next()?;
+        let mut words = line.split_whitespace();
+        if words.next()? != "rsync" || words.next()? != "version" {
+            return None;
+        }
+        let mut numbers = words.next()?.trim_start_matches('v').split('.');
+        Some(Self {
+            major: numbers.next()?.parse().ok()?,
+            minor: numbers
+                .next()
+                .and_then(|m| {
+                    m.trim_end_matches(|c: char| !c.is_ascii_digit())
+                        .parse()
+                        .ok()
+                })
+                .unwrap_or(0),
+        })
+    }
+
+    /// `--info=progress2` (whole-transfer progress) arrived in 3.1.
+    pub fn supports_progress2(&self) -> bool {
+        (self.major, self.minor) >= (3, 1)
+    }
+}
+
+pub const BRIDGE_FLAG: &str = "--rsync-bridge";
+pub const BRIDGE_TOKEN_ENV: &str = "ZYNC_RSYNC_BRIDGE_TOKEN";
+
+pub const FRAME_STDOUT: u8 = 1;
+pub const FRAME_STDERR: u8 = 2;
+/// Carries the remote exit status as a big-endian `u32`.
+pub const FRAME_EXIT: u8 = 3;
+
+/// The `-e` remote shell: this executable in bridge mode, pointed at `port`.
+pub fn remote_shell(exe: &std::path::Path, port: u16) -> String {
+    format!(
+        "{} {} {}",
+        shell_word(&exe.to_string_lossy()),
+        BRIDGE_FLAG,
+        port
+    )
+}
+
+/// Quotes a word for the `-e` string, which rsync splits itself (honouring quotes).
+fn shell_word(value: &str) -> String {
+    if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-~+:@".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Remote side of the rsync command line. The host is a placeholder: the bridge
+/// always runs on the transfer's connection.
+pub fn remote_spec(path: &str) -> String {
+    format!("zync:{}", path)
+}
+
+/// Reads the bridge's arguments as rsync passes them to a remote shell
+/// (`<port> [-l user] host command...`) and returns the port and the command.
+pub fn parse_bridge_args(args: &[String]) -> Result<(u16, String)> {
+    let (port, rest) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("missing bridge port"))?;
+    let port = port.parse().map_err(|_| anyhow!("invalid bridge port"))?;
+    let mut rest = rest.iter();
+    let host = loop {
+        match rest.next().map(String::as_str) {
+            Some("-l") => {
+                rest.next();
+            }
+            Some(option) if option.starts_with('-') => {}
+            Some(host) => break host,
+            None => return Err(anyhow!("missing remote command")),
+        }
+    };
+    let command = rest.map(String::as_str).collect::<Vec<_>>().join(" ");
+    if command.is_empty() || command.contains('\n') {
+        return Err(anyhow!("invalid remote command for {}", host));
+    }
+    Ok((port, command))
+}
+
+/// One bridge message: kind, big-endian length, payload.
+pub fn frame(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 5);
+    out.push(kind);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// Entry point of the bridge process; returns the remote command's exit code.
+pub fn run_bridge(args: &[String]) -> i32 {
+    match bridge(args) {
+        Ok(code) => code,
+        Err(e) => {
+            crate::log_error!("zync rsync bridge: {}", e);
+            255
+        }
+    }
+}
+
+fn bridge(args: &[String]) -> Result<i32> {
+    use std::io::{Read, Write};
+
+    let (port, command) = parse_bridge_args(args)?;
+    let token =
+        std::env::var(BRIDGE_TOKEN_ENV).map_err(|_| anyhow!("{} is not set", BRIDGE_TOKEN_ENV))?;
+    let mut socket = std::net::TcpStream::connect(("127.0.0.1", port))?;
+    socket.write_all(format!("{}\n{}\n", token, command).as_bytes())?;
+
+    let mut upstream = socket.try_clone()?;
+    std::thread::spawn(move || {
+        let _ = std::io::copy(&mut std::io::stdin().lock(), &mut upstream);
+        let _ = upstream.shutdown(std::net::Shutdown::Write);
+    });
+
+    let mut stdout = std::io::stdout().lock();
+    let mut stderr = std::io::stderr().lock();
+    loop {
+        let mut header = [0u8; 5];
+        if socket.read_exact(&mut header).is_err() {
+            return Err(anyhow!(
+                "connection to zync closed before the command exited"
+            ));
+        }
+        let mut data =
+            vec![0u8; u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize];
+        socket.read_exact(&mut data)?;
+        match header[0] {
+            FRAME_STDOUT => {
+                stdout.write_all(&data)?;
+                stdout.flush()?;
+            }
+            FRAME_STDERR => {
+                let _ = stderr.write_all(&data);
+            }
+            FRAME_EXIT => {
+                let status: [u8; 4] = data
+                    .try_into()
+                    .map_err(|_| anyhow!("malformed exit status"))?;
+                return Ok(u32::from_be_bytes(status) as i32);
+            }
+            kind => return Err(anyhow!("unexpected frame {}", kind)),
+        }
+    }
+}
+
+pub fn build_args(
+    options: &RsyncOptions,
+    version: Option<RsyncVersion>,
+    remote_shell: &str,
+    source: String,
+    destination: String,
+) -> Vec<String> {
+    let mut args = vec!["-a".to_string(), "--partial".to_string()];
+    match version {
+        Some(v) if v.supports_progress2() => {
+            args.push("--info=progress2".to_string());
+            args.push("--no-inc-recursive".to_string());
+        }
+        _ => args.push("--progress".to_string()),
+    }
+    // Pass remote paths verbatim instead of through the remote shell.
+    if version.map(|v| v.major >= 3).unwrap_or(false) {
+        args.push("--protect-args".to_string());
+    }
+    if options.delete {
+        args.push("--delete".to_string());
+    }
+    if options.dry_run {
+        args.push("--dry-run".to_string());
+    }
+    if options.compress {
+        args.push("-z".to_string());
+    }
+    if options.checksum {
+        args.push("--checksum".to_string());
+    }
+    if let Some(limit) = options.bandwidth_limit_kbps {
+        args.push(format!("--bwlimit={}", limit));
+    }
+    for pattern in &options.excludes {
+        args.push(format!("--exclude={}", pattern));
+    }
+    args.push("-e".to_string());
+    args.push(remote_shell.to_string());
+    args.push(source);
+    args.push(destination);
+    args
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RsyncProgress {
+    pub transferred: u64,
+    pub percent: u8,
+}
+
+impl RsyncProgress {
+    /// Total implied by the percentage; rsync never prints it directly.
+    pub fn estimated_total(&self) -> u64 {
+        if self.percent == 0 {
+            self.transferred.max(1)
+        } else {
+            (self.transferred * 100 / self.percent as u64).max(self.transferred)
+        }
+    }
+}
+
+/// Parses a progress line such as `  1,234,567  45%   10.50MB/s    0:00:12 (xfr#3, to-chk=10/20)`.
+pub fn parse_progress(line: &str) -> Option<RsyncProgress> {
+    let mut words = line.split_whitespace();
+    let transferred = words.next()?.replace([',', '.'], "").parse().ok()?;
+    let percent = words.next()?.strip_suffix('%')?.parse().ok()?;
+    Some(RsyncProgress {
+        transferred,
+        percent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions_and_progress_support() {
+        let v = RsyncVersion::parse("rsync  version 3.2.7  protocol version 31\n").unwrap();
+        assert!(v.supports_progress2());
+        let old = RsyncVersion::parse("rsync  version 2.6.9  protocol version 29").unwrap();
+        assert!(!old.supports_progress2());
+        assert!(RsyncVersion::parse("openrsync: protocol version 29").is_none());
+    }
+
+    #[test]
+    fn parses_progress_lines() {
+        let p = parse_progress("      1,234,567  45%   10.50MB/s    0:00:12 (xfr#3, to-chk=10/20)")
+            .unwrap();
+        assert_eq!(p.transferred, 1_234_567);
+        assert_eq!(p.percent, 45);
+        assert_eq!(p.estimated_total(), 2_743_482);
+        assert!(parse_progress("sending incremental file list").is_none());
+    }
+
+    #[test]
+    fn bridge_takes_the_command_rsync_hands_its_remote_shell() {
+        let args = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_bridge_args(&args(&[
+                "40123",
+                "zync",
+                "rsync",
+                "--server",
+                "-logDtpre.iLsfxCIvu",
+                ".",
+                "/srv/"
+            ]))
+            .unwrap(),
+            (
+                40123,
+                "rsync --server -logDtpre.iLsfxCIvu . /srv/".to_string()
+            )
+        );
+        assert_eq!(
+            parse_bridge_args(&args(&["1", "-l", "deploy", "zync", "rsync", "--server"]))
+                .unwrap()
+                .1,
+            "rsync --server"
+        );
+        assert!(parse_bridge_args(&args(&["1", "zync"])).is_err());
+        assert!(parse_bridge_args(&args(&["x", "zync", "rsync"])).is_err());
+
+        assert_eq!(
+            frame(FRAME_EXIT, &7u32.to_be_bytes()),
+            vec![3, 0, 0, 0, 4, 0, 0, 0, 7]
+        );
+        assert_eq!(
+            remote_shell(std::path::Path::new("/opt/My Apps/zync"), 40123),
+            "\"/opt/My Apps/zync\" --rsync-bridge 40123"
+        );
+        assert_eq!(remote_spec("/srv/app/"), "zync:/srv/app/");
+    }
+}
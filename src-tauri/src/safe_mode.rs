@@ -0,0 +1,121 @@
+//! Safe-mode startup.
+//!
+//! Safe mode is entered with `--safe-mode` on the command line, or automatically
+//! after [`CRASH_THRESHOLD`] starts in a row never got the main window loaded. A
+//! marker file counts those starts: it is bumped in `run()` and removed once the
+//! main page finishes loading. In safe mode third-party plugins are reported as
+//! disabled and the frontend gets default settings, while the repair commands in
+//! `commands.rs` can reset settings or turn off a plugin for good.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+pub const FLAG: &str = "--safe-mode";
+/// Consecutive unfinished starts before safe mode kicks in on its own.
+pub const CRASH_THRESHOLD: u32 = 3;
+
+static STATUS: OnceLock<SafeModeStatus> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SafeModeReason {
+    Flag,
+    RepeatedCrashes,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModeStatus {
+    pub active: bool,
+    pub reason: Option<SafeModeReason>,
+    /// Starts before this one that never reached a loaded window.
+    pub failed_starts: u32,
+}
+
+fn marker_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("Zync").join("startup.marker"))
+}
+
+/// Decides whether this start runs in safe mode from the command line and the
+/// number of earlier starts that never finished loading.
+pub fn decide(args: &[String], failed_starts: u32) -> SafeModeStatus {
+    let reason = if args.iter().any(|arg| arg == FLAG) {
+        Some(SafeModeReason::Flag)
+    } else if failed_starts >= CRASH_THRESHOLD {
+        Some(SafeModeReason::RepeatedCrashes)
+    } else {
+        None
+    };
+    SafeModeStatus {
+        active: reason.is_some(),
+        reason,
+        failed_starts,
+    }
+}
+
+/// Call once at the top of `run()`: reads and bumps the startup marker.
+pub fn init() -> &'static SafeModeStatus {
+    let marker = marker_path();
+    let failed_starts = marker
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| raw.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let status = decide(&args, failed_starts);
+    if let Some(path) = marker {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(path, (failed_starts + 1).to_string());
+    }
+    if status.active {
+        crate::log_error!(
+            "[SAFE] Starting in safe mode ({:?}, {} failed starts)",
+            status.reason,
+            failed_starts
+        );
+    }
+    STATUS.get_or_init(|| status)
+}
+
+pub fn status() -> SafeModeStatus {
+    STATUS.get().cloned().unwrap_or_default()
+}
+
+pub fn is_active() -> bool {
+    STATUS.get().is_some_and(|status| status.active)
+}
+
+/// The main window loaded: this start is not a crash.
+pub fn mark_started() {
+    if let Some(path) = marker_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Arguments for relaunching without safe mode.
+pub fn args_without_flag() -> Vec<String> {
+    std::env::args().skip(1).filter(|arg| arg != FLAG).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_or_repeated_crashes_enable_safe_mode() {
+        let none: Vec<String> = Vec::new();
+        assert!(!decide(&none, 0).active);
+        assert!(!decide(&none, CRASH_THRESHOLD - 1).active);
+        assert_eq!(
+            decide(&none, CRASH_THRESHOLD).reason,
+            Some(SafeModeReason::RepeatedCrashes)
+        );
+        assert_eq!(
+            decide(&[FLAG.to_string()], 0).reason,
+            Some(SafeModeReason::Flag)
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Backend scrollback with memory caps.
+//!
+//! Each terminal keeps the tail of its output in memory so it can be replayed
+//! (webview reloads, AI context) without asking the frontend. Two caps bound the
+//! memory: one per terminal (`terminal.scrollbackMemoryPerTerminalMB`, or the
+//! connection's `scrollback_limit_mb`) and one across all terminals
+//! (`terminal.scrollbackMemoryTotalMB`). When a cap is hit the oldest chunks are
+//! evicted; terminals with session logging on append them to a spill file next to
+//! the log so the full history stays readable, the rest simply drop them.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_PER_TERMINAL_BYTES: usize = 8 * 1024 * 1024;
+pub const DEFAULT_TOTAL_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackSnapshot {
+    pub data: String,
+    pub memory_bytes: usize,
+    pub spilled_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spill_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackStats {
+    pub terminals: usize,
+    pub memory_bytes: usize,
+    pub total_limit_bytes: usize,
+}
+
+struct Buffer {
+    chunks: VecDeque<Vec<u8>>,
+    bytes: usize,
+    limit: usize,
+    spill: Option<(PathBuf, Option<File>)>,
+    spilled: u64,
+}
+
+impl Buffer {
+    fn new(limit: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            bytes: 0,
+            limit,
+            spill: None,
+            spilled: 0,
+        }
+    }
+
+    /// Drops the oldest chunk (into the spill file when there is one); returns its size.
+    fn evict_front(&mut self) -> usize {
+        let Some(chunk) = self.chunks.pop_front() else {
+            return 0;
+        };
+        self.bytes -= chunk.len();
+        if let Some((path, file)) = self.spill.as_mut() {
+            if file.is_none() {
+                *file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&*path)
+                    .map_err(|e| {
+                        crate::log_error!("[PTY] Cannot open spill file {:?}: {}", path, e)
+                    })
+                    .ok();
+            }
+            if let Some(file) = file.as_mut() {
+                if file.write_all(&chunk).is_ok() {
+                    self.spilled += chunk.len() as u64;
+                }
+            }
+        }
+        chunk.len()
+    }
+
+    fn shrink_to(&mut self, limit: usize) -> usize {
+        let mut freed = 0;
+        while self.bytes > limit && !self.chunks.is_empty() {
+            freed += self.evict_front();
+        }
+        freed
+    }
+}
+
+/// Scrollback buffers keyed by `term_id`; cheap to clone into reader tasks.
+#[derive(Clone)]
+pub struct Scrollbacks {
+    buffers: Arc<Mutex<HashMap<String, Buffer>>>,
+    total: Arc<AtomicUsize>,
+    total_limit: Arc<AtomicUsize>,
+}
+
+impl Default for Scrollbacks {
+    fn default() -> Self {
+        Self {
+            buffers: Arc::default(),
+            total: Arc::default(),
+            total_limit: Arc::new(AtomicUsize::new(DEFAULT_TOTAL_BYTES)),
+        }
+    }
+}
+
+impl Scrollbacks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_total_limit(&self, bytes: usize) {
+        self.total_limit.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Sets the per-terminal cap and spill file, keeping output already buffered.
+    pub fn configure(&self, term_id: &str, limit: usize, spill: Option<PathBuf>) {
+        let Ok(mut buffers) = self.buffers.lock() else {
+            return;
+        };
+        let buffer = buffers
+            .entry(term_id.to_string())
+            .or_insert_with(|| Buffer::new(limit));
+        buffer.limit = limit;
+        buffer.spill = spill.map(|path| (path, None));
+        let freed = buffer.shrink_to(limit);
+        self.total.fetch_sub(freed, Ordering::SeqCst);
+    }
+
+    pub fn output(&self, term_id: &str, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let Ok(mut buffers) = self.buffers.lock() else {
+            return;
+        };
+        let buffer = buffers
+            .entry(term_id.to_string())
+            .or_insert_with(|| Buffer::new(DEFAULT_PER_TERMINAL_BYTES));
+        buffer.chunks.push_back(bytes.to_vec());
+        buffer.bytes += bytes.len();
+        self.total.fetch_add(bytes.len(), Ordering::SeqCst);
+        let freed = buffer.shrink_to(buffer.limit);
+        let total = self.total.fetch_sub(freed, Ordering::SeqCst) - freed;
+
+        let total_limit = self.total_limit.load(Ordering::Relaxed);
+        if total > total_limit {
+            // Over the global cap: trim whichever terminal holds the most.
+            let mut excess = total - total_limit;
+            while excess > 0 {
+                let Some(largest) = buffers
+                    .values_mut()
+                    .filter(|b| !b.chunks.is_empty())
+                    .max_by_key(|b| b.bytes)
+                else {
+                    break;
+                };
+                let freed = largest.evict_front();
+                self.total.fetch_sub(freed, Ordering::SeqCst);
+                excess = excess.saturating_sub(freed);
+            }
+        }
+    }
+
+    /// In-memory tail (at most `max_bytes`), optionally preceded by spilled output.
+    pub fn snapshot(
+        &self,
+        term_id: &str,
+        max_bytes: Option<usize>,
+        include_spilled: bool,
+    ) -> Option<ScrollbackSnapshot> {
+        let mut buffers = self.buffers.lock().ok()?;
+        let buffer = buffers.get_mut(term_id)?;
+        let mut data = Vec::with_capacity(buffer.bytes);
+        let spill_path = buffer.spill.as_ref().map(|(path, _)| path.clone());
+        if include_spilled {
+            if let Some((_, Some(file))) = buffer.spill.as_mut() {
+                let _ = file.flush();
+            }
+            if let Some(path) = &spill_path {
+                if let Ok(mut file) = File::open(path) {
+                    if let Some(max) = max_bytes {
+                        let keep = max.saturating_sub(buffer.bytes) as u64;
+                        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+                        let _ = file.seek(SeekFrom::Start(len.saturating_sub(keep)));
+                    }
+                    let _ = file.read_to_end(&mut data);
+                }
+            }
+        }
+        for chunk in &buffer.chunks {
+            data.extend_from_slice(chunk);
+        }
+        if let Some(max) = max_bytes {
+            if data.len() > max {
+                data.drain(..data.len() - max);
+            }
+        }
+        Some(ScrollbackSnapshot {
+            data: String::from_utf8_lossy(&data).to_string(),
+            memory_bytes: buffer.bytes,
+            spilled_bytes: buffer.spilled,
+            spill_path: spill_path.map(|p| p.to_string_lossy().to_string()),
+        })
+    }
+
+    pub fn stats(&self) -> ScrollbackStats {
+        let terminals = self.buffers.lock().map(|b| b.len()).unwrap_or(0);
+        ScrollbackStats {
+            terminals,
+            memory_bytes: self.total.load(Ordering::SeqCst),
+            total_limit_bytes: self.total_limit.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Forgets a terminal and deletes its spill file (the session log keeps the history).
+    pub fn discard(&self, term_id: &str) {
+        let Ok(mut buffers) = self.buffers.lock() else {
+            return;
+        };
+        if let Some(buffer) = buffers.remove(term_id) {
+            self.total.fetch_sub(buffer.bytes, Ordering::SeqCst);
+            if let Some((path, file)) = buffer.spill {
+                drop(file);
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_output_per_terminal_and_globally() {
+        let scrollbacks = Scrollbacks::new();
+        scrollbacks.set_total_limit(10);
+        scrollbacks.configure("a", 6, None);
+        scrollbacks.output("a", b"1234");
+        scrollbacks.output("a", b"5678");
+        assert_eq!(scrollbacks.snapshot("a", None, false).unwrap().data, "5678");
+
+        scrollbacks.output("b", b"abcdefgh");
+        // a (4) + b (8) > 10: the largest buffer gives up its oldest chunk.
+        let stats = scrollbacks.stats();
+        assert!(stats.memory_bytes <= 10);
+        assert_eq!(scrollbacks.snapshot("a", None, false).unwrap().data, "5678");
+        assert_eq!(scrollbacks.snapshot("b", None, false).unwrap().data, "");
+
+        scrollbacks.discard("a");
+        scrollbacks.discard("b");
+        assert_eq!(scrollbacks.stats().memory_bytes, 0);
+    }
+
+    #[test]
+    fn spills_evicted_output_for_logged_terminals() {
+        let dir = std::env::temp_dir().join(format!("zync-scrollback-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spill = dir.join("term.spill");
+        let scrollbacks = Scrollbacks::new();
+        scrollbacks.configure("t", 4, Some(spill.clone()));
+        for chunk in [b"ab", b"cd", b"ef", b"gh"] {
+            scrollbacks.output("t", chunk);
+        }
+        let memory = scrollbacks.snapshot("t", None, false).unwrap();
+        assert_eq!(memory.data, "efgh");
+        assert_eq!(memory.spilled_bytes, 4);
+        let full = scrollbacks.snapshot("t", None, true).unwrap();
+        assert_eq!(full.data, "abcdefgh");
+        assert_eq!(
+            scrollbacks.snapshot("t", Some(6), true).unwrap().data,
+            "cdefgh"
+        );
+        scrollbacks.discard("t");
+        assert!(!spill.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,285 @@
+//! Meaning-based search over snippets, command history and host facts.
+//!
+//! Every searchable item becomes a short text document. Their embeddings
+//! (see [`crate::ai::embeddings`]) are cached in `<data>/semantic_index.json`
+//! keyed by document id and a hash of its text, so a search only embeds what
+//! is new or changed since the last one. Switching the embedding model
+//! discards the cache, since vectors from different models cannot be compared.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+const INDEX_FILE: &str = "semantic_index.json";
+/// History entries indexed, most frequently run first.
+pub const MAX_HISTORY_DOCS: usize = 2000;
+pub const DEFAULT_LIMIT: usize = 20;
+/// Hits scoring below this are left out.
+const MIN_SCORE: f32 = 0.1;
+
+/// Held while the index is refreshed, so concurrent searches do not embed
+/// the same documents twice.
+pub(crate) static INDEX_LOCK: LazyLock<tokio::sync::Mutex<()>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocKind {
+    Snippet,
+    History,
+    Host,
+}
+
+/// A searchable item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// `snippet:<id>`, `history:<connection>:<command hash>` or `host:<connection>`.
+    pub id: String,
+    pub kind: DocKind,
+    /// Shown in results: the snippet name, the command, the host name.
+    pub title: String,
+    /// What gets embedded.
+    pub text: String,
+    pub connection_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    hash: String,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SemanticIndex {
+    /// [`crate::ai::embeddings::Embedder::id`] of the stored vectors.
+    embedder: String,
+    entries: HashMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticHit {
+    pub id: String,
+    pub kind: DocKind,
+    pub title: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    /// Cosine similarity to the query.
+    pub score: f32,
+}
+
+/// Return value of `semantic_search`.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticSearchResult {
+    /// Embedder the hits were ranked with, e.g. `openai:text-embedding-3-small`.
+    pub model: String,
+    pub hits: Vec<SemanticHit>,
+    /// Why the configured provider was not used, when it fell back to local vectors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+fn text_hash(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(text.as_bytes());
+    digest[..12].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn index_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(INDEX_FILE)
+}
+
+pub fn snippet_doc(snippet: &crate::snippets::Snippet) -> Document {
+    let mut text = format!("{}\n{}", snippet.name, snippet.command);
+    if let Some(category) = snippet.category.as_deref().filter(|c| !c.is_empty()) {
+        text.push_str(&format!("\n{}", category));
+    }
+    if let Some(tags) = snippet.tags.as_ref().filter(|t| !t.is_empty()) {
+        text.push_str(&format!("\n{}", tags.join(" ")));
+    }
+    Document {
+        id: format!("snippet:{}", snippet.id),
+        kind: DocKind::Snippet,
+        title: snippet.name.clone(),
+        text,
+        connection_id: snippet.connection_id.clone(),
+    }
+}
+
+pub fn history_doc(entry: &crate::command_history::HistoryEntry) -> Document {
+    Document {
+        id: format!(
+            "history:{}:{}",
+            entry.connection_id,
+            text_hash(&entry.command)
+        ),
+        kind: DocKind::History,
+        title: entry.command.clone(),
+        text: entry.command.clone(),
+        connection_id: Some(entry.connection_id.clone()),
+    }
+}
+
+/// A connected host: its name, address, user and what was detected about it.
+pub fn host_doc(
+    config: &crate::types::ConnectionConfig,
+    detected_os: Option<&str>,
+    detected_shell: Option<&str>,
+) -> Document {
+    let mut facts = vec![
+        config.name.clone(),
+        format!("{}@{}:{}", config.username, config.host, config.port),
+    ];
+    facts.extend(detected_os.map(|os| format!("os {}", os)));
+    facts.extend(detected_shell.map(|shell| format!("shell {}", shell)));
+    Document {
+        id: format!("host:{}", config.id),
+        kind: DocKind::Host,
+        title: config.name.clone(),
+        text: facts.join("\n"),
+        connection_id: Some(config.id.clone()),
+    }
+}
+
+impl SemanticIndex {
+    /// Reads the stored index; a missing or unreadable file is an empty index.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(path, &json).map_err(|e| e.to_string())
+    }
+
+    /// Drops vectors of another embedder and of documents that are gone;
+    /// returns the indexes of `docs` that still need a vector.
+    pub fn prepare(&mut self, embedder: &str, docs: &[Document]) -> Vec<usize> {
+        if self.embedder != embedder {
+            self.embedder = embedder.to_string();
+            self.entries.clear();
+        }
+        let live: std::collections::HashSet<&str> = docs.iter().map(|d| d.id.as_str()).collect();
+        self.entries.retain(|id, _| live.contains(id.as_str()));
+        docs.iter()
+            .enumerate()
+            .filter(|(_, doc)| {
+                self.entries
+                    .get(&doc.id)
+                    .is_none_or(|entry| entry.hash != text_hash(&doc.text))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn insert(&mut self, doc: &Document, mut vector: Vec<f32>) {
+        crate::ai::embeddings::normalize(&mut vector);
+        self.entries.insert(
+            doc.id.clone(),
+            IndexEntry {
+                hash: text_hash(&doc.text),
+                vector,
+            },
+        );
+    }
+
+    /// Documents ranked by similarity to `query` (a normalized vector).
+    pub fn search(&self, query: &[f32], docs: &[Document], limit: usize) -> Vec<SemanticHit> {
+        let mut hits: Vec<SemanticHit> = docs
+            .iter()
+            .filter_map(|doc| {
+                let entry = self.entries.get(&doc.id)?;
+                if entry.vector.len() != query.len() {
+                    return None;
+                }
+                let score: f32 = entry.vector.iter().zip(query).map(|(a, b)| a * b).sum();
+                (score >= MIN_SCORE).then(|| SemanticHit {
+                    id: doc.id.clone(),
+                    kind: doc.kind,
+                    title: doc.title.clone(),
+                    text: doc.text.clone(),
+                    connection_id: doc.connection_id.clone(),
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(id: &str, text: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            kind: DocKind::Snippet,
+            title: text.to_string(),
+            text: text.to_string(),
+            connection_id: None,
+        }
+    }
+
+    #[test]
+    fn reembeds_only_new_or_changed_documents() {
+        let mut index = SemanticIndex::default();
+        let docs = vec![doc("a", "sync"), doc("b", "uptime")];
+        assert_eq!(index.prepare("local:hash512", &docs), [0, 1]);
+        index.insert(&docs[0], vec![1.0, 0.0]);
+        index.insert(&docs[1], vec![0.0, 1.0]);
+        assert!(index.prepare("local:hash512", &docs).is_empty());
+
+        let changed = vec![doc("a", "sync; echo 3"), doc("c", "df -h")];
+        assert_eq!(index.prepare("local:hash512", &changed), [0, 1]);
+        assert!(!index.entries.contains_key("b"));
+
+        index.insert(&changed[0], vec![1.0, 0.0]);
+        assert_eq!(
+            index.prepare("openai:text-embedding-3-small", &changed),
+            [0, 1]
+        );
+        assert!(index.entries.is_empty());
+    }
+
+    #[test]
+    fn ranks_by_cosine_similarity() {
+        let mut index = SemanticIndex::default();
+        let docs = vec![
+            doc("a", "free page cache"),
+            doc("b", "list files"),
+            doc("c", "x"),
+        ];
+        index.prepare("m", &docs);
+        index.insert(&docs[0], vec![3.0, 4.0]);
+        index.insert(&docs[1], vec![0.0, 1.0]);
+        index.insert(&docs[2], vec![-1.0, 0.0]);
+        let hits = index.search(&[0.6, 0.8], &docs, 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].id, "a");
+        assert!((hits[0].score - 1.0).abs() < 1e-5);
+        assert_eq!(hits[1].id, "b");
+    }
+
+    #[test]
+    fn history_ids_are_stable_per_command() {
+        let entry = crate::command_history::HistoryEntry {
+            command: "sync; echo 3 > /proc/sys/vm/drop_caches".to_string(),
+            connection_id: "c1".to_string(),
+            count: 1,
+            last_run: 0,
+            last_exit_code: None,
+            cwd: None,
+        };
+        assert_eq!(history_doc(&entry).id, history_doc(&entry).id);
+        assert!(history_doc(&entry).id.starts_with("history:c1:"));
+    }
+}
@@ -57,10 +57,11 @@ fn migrate(mut data: SessionData) -> SessionData {
     // the data as-is. serde(default) ensures unknown fields are ignored and
     // missing new fields use their default values, so this is safe.
     if data.version > SESSION_VERSION {
-        eprintln!(
+        crate::log_error!(
             "[Session] Warning: session file version {} is newer than supported version {}. \
              Some data may be ignored.",
-            data.version, SESSION_VERSION
+            data.version,
+            SESSION_VERSION
         );
     }
     data
@@ -94,7 +95,7 @@ pub async fn session_save(app: AppHandle, mut data: SessionData) -> Result<(), S
     // Enforce per-scope tab cap before writing.
     for (scope, tabs) in data.terminals.iter_mut() {
         if tabs.len() > MAX_TABS_PER_SCOPE {
-            eprintln!(
+            crate::log_error!(
                 "[Session] Truncating {} terminal tabs to {} for scope '{}'",
                 tabs.len(), MAX_TABS_PER_SCOPE, scope
             );
@@ -104,16 +105,23 @@ pub async fn session_save(app: AppHandle, mut data: SessionData) -> Result<(), S
     data.version = SESSION_VERSION;
 
     let dir = crate::commands::get_data_dir(&app);
-    tokio::fs::create_dir_all(&dir)
-        .await
-        .map_err(|e| format!("failed to create session directory {:?}: {}", dir, e))?;
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| {
+        crate::redact::error(format!(
+            "failed to create session directory {:?}: {}",
+            dir, e
+        ))
+    })?;
 
-    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&data).map_err(crate::redact::error)?;
 
     // Atomic write: write to a temp file then rename so a crash mid-write never
     // leaves a corrupt session.json. On Windows, tokio::fs::rename replaces an
     // existing destination atomically (same as POSIX on the same volume).
     let tmp = dir.join("session.tmp");
-    tokio::fs::write(&tmp, &json).await.map_err(|e| e.to_string())?;
-    tokio::fs::rename(&tmp, dir.join("session.json")).await.map_err(|e| e.to_string())
+    tokio::fs::write(&tmp, &json)
+        .await
+        .map_err(crate::redact::error)?;
+    tokio::fs::rename(&tmp, dir.join("session.json"))
+        .await
+        .map_err(crate::redact::error)
 }
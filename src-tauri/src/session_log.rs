@@ -0,0 +1,342 @@
+//! Continuous "log all output" files for terminals.
+//!
+//! Each logged terminal writes plain text to `<dir>/<label>-<started>-<term>.log`:
+//! escape sequences and carriage returns are stripped and every line is prefixed
+//! with a UTC timestamp. Once the file passes `max_bytes` it is rotated
+//! logrotate-style (`.1.log`, `.2.log`, ...) and at most `max_files` rotated files
+//! are kept.
+
+use crate::terminal_registry::TerminalRegistry;
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct SessionLogOptions {
+    pub dir: PathBuf,
+    /// Human-readable prefix for the file name, usually the connection name.
+    pub label: String,
+    pub max_bytes: u64,
+    pub max_files: usize,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn format_utc(secs: u64, date_sep: &str, time_sep: &str, between: &str) -> String {
+    let (year, month, day) = crate::trash::civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{:04}{ds}{:02}{ds}{:02}{bw}{:02}{ts}{:02}{ts}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60,
+        ds = date_sep,
+        ts = time_sep,
+        bw = between,
+    )
+}
+
+fn file_safe(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cleaned.trim_matches(|c| c == '_' || c == '.').to_string()
+}
+
+/// The leading part of every log file name for `label` (before `-<timestamp>`).
+pub fn file_label(label: &str) -> String {
+    match file_safe(label) {
+        label if label.is_empty() => "session".to_string(),
+        label => label,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// Saw ESC, waiting for the introducer.
+    Start,
+    /// CSI: parameters until a final byte in 0x40..=0x7e.
+    Csi,
+    /// OSC/DCS/APC string: until BEL or ESC \.
+    String,
+    StringEsc,
+}
+
+/// Strips terminal control sequences from a byte stream, keeping state across chunks.
+#[derive(Debug)]
+pub(crate) struct ControlStripper {
+    state: Escape,
+}
+
+impl ControlStripper {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Escape::None,
+        }
+    }
+
+    pub(crate) fn strip(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (Escape::None, 0x1b) => Escape::Start,
+                (Escape::None, b'\n' | b'\t') => {
+                    out.push(byte);
+                    Escape::None
+                }
+                (Escape::None, b) if b < 0x20 || b == 0x7f => Escape::None,
+                (Escape::None, b) => {
+                    out.push(b);
+                    Escape::None
+                }
+                (Escape::Start, b'[') => Escape::Csi,
+                (Escape::Start, b']' | b'P' | b'_' | b'^' | b'X') => Escape::String,
+                // Two-byte sequences (ESC =, ESC (B, ...) drop the introducer and
+                // whatever charset byte follows on the next turn.
+                (Escape::Start, b'(' | b')' | b'*' | b'+') => Escape::Csi,
+                (Escape::Start, _) => Escape::None,
+                (Escape::Csi, 0x40..=0x7e) => Escape::None,
+                (Escape::Csi, _) => Escape::Csi,
+                (Escape::String, 0x07) => Escape::None,
+                (Escape::String, 0x1b) => Escape::StringEsc,
+                (Escape::String, _) => Escape::String,
+                (Escape::StringEsc, b'\\') => Escape::None,
+                (Escape::StringEsc, _) => Escape::String,
+            };
+        }
+    }
+}
+
+pub struct SessionLogger {
+    options: SessionLogOptions,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    written: u64,
+    at_line_start: bool,
+    stripper: ControlStripper,
+}
+
+impl SessionLogger {
+    pub fn create(term_id: &str, options: SessionLogOptions) -> Result<Self> {
+        std::fs::create_dir_all(&options.dir)?;
+        let stem = format!(
+            "{}-{}-{}",
+            file_label(&options.label),
+            format_utc(now_secs(), "", "", "-"),
+            file_safe(term_id).chars().take(8).collect::<String>()
+        );
+        let path = options.dir.join(format!("{}.log", stem));
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            options,
+            path,
+            writer: BufWriter::new(file),
+            written,
+            at_line_start: true,
+            stripper: ControlStripper::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("session");
+        self.path.with_file_name(format!("{}.{}.log", stem, index))
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        let keep = self.options.max_files;
+        if keep == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(keep));
+            for index in (1..keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        let file = File::create(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+        Ok(())
+    }
+
+    pub fn write_output(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let mut text = Vec::with_capacity(bytes.len());
+        self.stripper.strip(bytes, &mut text);
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let stamp = format!("[{}] ", format_utc(now_secs(), "-", ":", " "));
+        let mut line = Vec::with_capacity(text.len() + stamp.len());
+        for &byte in &text {
+            if self.at_line_start {
+                line.extend_from_slice(stamp.as_bytes());
+                self.at_line_start = false;
+            }
+            line.push(byte);
+            if byte == b'\n' {
+                self.at_line_start = true;
+            }
+        }
+
+        if self.written > 0 && self.written + line.len() as u64 > self.options.max_bytes {
+            self.rotate()?;
+        }
+        self.writer.write_all(&line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Active loggers keyed by `term_id`; cheap to clone into reader tasks.
+#[derive(Clone, Default)]
+pub struct SessionLoggers {
+    active: TerminalRegistry<SessionLogger>,
+}
+
+impl SessionLoggers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start(&self, term_id: &str, options: SessionLogOptions) -> Result<PathBuf> {
+        let logger = SessionLogger::create(term_id, options)?;
+        let path = logger.path().to_path_buf();
+        self.active.insert(term_id, logger);
+        Ok(path)
+    }
+
+    pub fn output(&self, term_id: &str, bytes: &[u8]) {
+        if let Some(Err(e)) = self.active.with(term_id, |l| l.write_output(bytes)) {
+            crate::log_error!("[PTY] Session log write failed for {}: {}", term_id, e);
+        }
+    }
+
+    pub fn path(&self, term_id: &str) -> Option<PathBuf> {
+        self.active.with(term_id, |l| l.path().to_path_buf())
+    }
+
+    pub fn stop(&self, term_id: &str) {
+        if let Some(mut logger) = self.active.remove(term_id) {
+            let _ = logger.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(chunks: &[&[u8]]) -> String {
+        let mut stripper = ControlStripper::new();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            stripper.strip(chunk, &mut out);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn strips_escape_sequences_across_chunks() {
+        assert_eq!(strip(&[b"\x1b[1;3", b"2mred\x1b[0m ok\r\n"]), "red ok\n");
+        assert_eq!(
+            strip(&[
+                b"\x1b]0;user@host: ~\x07$ ",
+                b"\x1b]7;file://h/tmp\x1b\\ls\n"
+            ]),
+            "$ ls\n"
+        );
+        assert_eq!(strip(&[b"\x1b(Bplain\x1b=\n"]), "plain\n");
+        assert_eq!(strip(&["héllo\n".as_bytes()]), "héllo\n");
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(
+            format_utc(1_700_000_000, "-", ":", " "),
+            "2023-11-14 22:13:20"
+        );
+        assert_eq!(format_utc(0, "", "", "-"), "19700101-000000");
+    }
+
+    #[test]
+    fn timestamps_lines_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("zync-log-{}", uuid::Uuid::new_v4()));
+        let mut logger = SessionLogger::create(
+            "abcdef12-3456",
+            SessionLogOptions {
+                dir: dir.clone(),
+                label: "prod db".to_string(),
+                max_bytes: 80,
+                max_files: 2,
+            },
+        )
+        .unwrap();
+        assert!(logger
+            .path()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("prod_db-"));
+
+        logger.write_output(b"first line\r\nsecond").unwrap();
+        logger.write_output(b" half\n").unwrap();
+        logger.flush().unwrap();
+        let content = std::fs::read_to_string(logger.path()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with('[') && lines[0].ends_with("] first line"));
+        assert!(lines[1].ends_with("] second half"));
+
+        for _ in 0..4 {
+            logger
+                .write_output(b"0123456789012345678901234567890\n")
+                .unwrap();
+        }
+        logger.flush().unwrap();
+        assert!(logger.rotated_path(1).exists());
+        assert!(logger.rotated_path(2).exists());
+        assert!(!logger.rotated_path(3).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,90 @@
+//! OpenSSH SFTP extensions that the high-level `russh_sftp` client does not expose.
+//!
+//! `SftpSession` keeps the server's advertised extensions to itself, so these
+//! requests run on a short-lived raw SFTP channel. Whether a server advertises an
+//! extension is remembered per connection, so servers without it are probed once.
+
+use anyhow::{anyhow, Result};
+use russh::client::Msg;
+use russh::Channel;
+use russh_sftp::client::RawSftpSession;
+use russh_sftp::protocol::{Packet, StatusCode};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Rename that atomically replaces an existing target (`rename(2)` semantics).
+pub const POSIX_RENAME: &str = "posix-rename@openssh.com";
+
+static POSIX_RENAME_SUPPORT: LazyLock<Mutex<HashMap<String, bool>>> =
+    LazyLock::new(Default::default);
+
+/// Whether the server advertised `posix-rename@openssh.com`; `None` until probed.
+pub fn posix_rename_supported(connection_id: &str) -> Option<bool> {
+    POSIX_RENAME_SUPPORT
+        .lock()
+        .ok()?
+        .get(connection_id)
+        .copied()
+}
+
+/// Renames `old_path` to `new_path`, replacing the target if it exists. Returns
+/// `Ok(false)` without touching anything when the server lacks the extension.
+pub async fn posix_rename(
+    connection_id: &str,
+    channel: Channel<Msg>,
+    old_path: &str,
+    new_path: &str,
+) -> Result<bool> {
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| anyhow!("Failed to request SFTP subsystem: {}", e))?;
+    let raw = RawSftpSession::new(channel.into_stream());
+    let version = raw
+        .init()
+        .await
+        .map_err(|e| anyhow!("Failed to initialize SFTP: {}", e))?;
+    let supported = version.extensions.contains_key(POSIX_RENAME);
+    if let Ok(mut support) = POSIX_RENAME_SUPPORT.lock() {
+        support.insert(connection_id.to_string(), supported);
+    }
+    if !supported {
+        return Ok(false);
+    }
+
+    match raw
+        .extended(POSIX_RENAME, rename_payload(old_path, new_path))
+        .await
+    {
+        Ok(Packet::Status(status)) if status.status_code != StatusCode::Ok => Err(anyhow!(
+            "Failed to rename remote file: {}",
+            status.error_message
+        )),
+        Ok(_) => Ok(true),
+        Err(e) => Err(anyhow!("Failed to rename remote file: {}", e)),
+    }
+}
+
+/// Request data for `posix-rename@openssh.com`: two SSH strings.
+fn rename_payload(old_path: &str, new_path: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + old_path.len() + new_path.len());
+    for path in [old_path, new_path] {
+        data.extend_from_slice(&(path.len() as u32).to_be_bytes());
+        data.extend_from_slice(path.as_bytes());
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_payload_is_two_length_prefixed_strings() {
+        assert_eq!(
+            rename_payload("a", "bc"),
+            vec![0, 0, 0, 1, b'a', 0, 0, 0, 2, b'b', b'c']
+        );
+        assert_eq!(posix_rename_supported("never-probed"), None);
+    }
+}
@@ -118,7 +118,7 @@ fn load_disk_cache(path: &Path) -> HashMap<String, Option<String>> {
     match serde_json::from_slice(&bytes) {
         Ok(cache) => cache,
         Err(error) => {
-            eprintln!(
+            crate::log_error!(
                 "[ShellIcons] Failed to deserialize disk cache at {}: {}",
                 path.display(),
                 error
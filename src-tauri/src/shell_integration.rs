@@ -0,0 +1,452 @@
+//! Shell-integration command tracking (OSC 133 / OSC 633).
+//!
+//! [`CommandTracker`] watches terminal output for the FinalTerm/VS Code prompt
+//! markers: `133;C` (command started), `133;D;<exit>` (command finished) and
+//! `633;E;<command line>`, plus the working directory from OSC 7
+//! (`7;file://<host><path>`) and `633;P;Cwd=<path>`. Shells that already emit
+//! them (fish 4, starship, iTerm2/VS Code integrations, Ubuntu's `vte.sh`) work
+//! as-is; for bash, zsh and fish the snippets below can be injected when a
+//! session starts (`terminal.shellIntegration`).
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::path::Path;
+use std::time::Instant;
+
+/// Longest OSC payload we buffer; anything longer is not one of ours.
+const MAX_OSC_LEN: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShellEvent {
+    CommandStart {
+        command: Option<String>,
+    },
+    CommandEnd {
+        command: Option<String>,
+        exit_code: Option<i32>,
+        duration_ms: u64,
+    },
+    Cwd {
+        path: String,
+    },
+}
+
+/// Per-terminal OSC scanner. Sequences may be split across output chunks.
+#[derive(Debug, Default)]
+pub struct CommandTracker {
+    osc: Option<Vec<u8>>,
+    escape: bool,
+    command_line: Option<String>,
+    started: Option<Instant>,
+}
+
+impl CommandTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Command line of the command currently running, when the shell reported it.
+    pub fn current_command(&self) -> Option<&str> {
+        self.started.and(self.command_line.as_deref())
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<ShellEvent> {
+        let mut events = Vec::new();
+        for &byte in bytes {
+            match self.osc.as_mut() {
+                Some(payload) => {
+                    if self.escape {
+                        self.escape = false;
+                        let payload = self.osc.take().unwrap_or_default();
+                        if byte == b'\\' {
+                            self.finish(&payload, &mut events);
+                        } else if byte == b']' {
+                            self.osc = Some(Vec::new());
+                        }
+                    } else if byte == 0x07 {
+                        let payload = self.osc.take().unwrap_or_default();
+                        self.finish(&payload, &mut events);
+                    } else if byte == 0x1b {
+                        self.escape = true;
+                    } else if payload.len() < MAX_OSC_LEN {
+                        payload.push(byte);
+                    } else {
+                        self.osc = None;
+                    }
+                }
+                None => {
+                    if self.escape {
+                        self.escape = false;
+                        if byte == b']' {
+                            self.osc = Some(Vec::new());
+                        }
+                    } else if byte == 0x1b {
+                        self.escape = true;
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    fn finish(&mut self, payload: &[u8], events: &mut Vec<ShellEvent>) {
+        let payload = String::from_utf8_lossy(payload);
+        let mut fields = payload.splitn(3, ';');
+        let family = fields.next().unwrap_or_default();
+        if family == "7" {
+            let uri = payload.get(2..).unwrap_or_default();
+            if let Some(path) = parse_osc7(uri) {
+                events.push(ShellEvent::Cwd { path });
+            }
+            return;
+        }
+        if family != "133" && family != "633" {
+            return;
+        }
+        let kind = fields.next().unwrap_or_default();
+        let rest = fields.next();
+        match kind {
+            // 633 E carries `<escaped command line>[;<nonce>]`.
+            "E" if family == "633" => {
+                let line = rest.and_then(|rest| rest.split(';').next()).unwrap_or("");
+                self.command_line = Some(unescape_633(line));
+            }
+            "C" => {
+                self.started = Some(Instant::now());
+                events.push(ShellEvent::CommandStart {
+                    command: self.command_line.clone(),
+                });
+            }
+            "D" => {
+                // A D with no matching C is the first prompt or an empty line.
+                if let Some(started) = self.started.take() {
+                    events.push(ShellEvent::CommandEnd {
+                        command: self.command_line.take(),
+                        exit_code: rest
+                            .and_then(|rest| rest.split(';').next())
+                            .and_then(|code| code.trim().parse().ok()),
+                        duration_ms: started.elapsed().as_millis() as u64,
+                    });
+                }
+            }
+            "A" => self.command_line = None,
+            "P" if family == "633" => {
+                if let Some(path) = rest.and_then(|rest| rest.strip_prefix("Cwd=")) {
+                    let path = unescape_633(path);
+                    if !path.is_empty() {
+                        events.push(ShellEvent::Cwd { path });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Path from an OSC 7 `file://<host>/<percent-encoded path>` URI. The host is
+/// ignored: the terminal already knows which machine it is attached to.
+fn parse_osc7(uri: &str) -> Option<String> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let mut out = Vec::with_capacity(path.len());
+    let bytes = path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = path
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8_lossy(&out).to_string();
+    // `file:///C:/Users/me` on Windows.
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[2] == b':' && bytes[1].is_ascii_alphabetic() {
+        return Some(path[1..].to_string());
+    }
+    Some(path)
+}
+
+/// Reverses VS Code's escaping: `\\` and `\xHH`.
+fn unescape_633(value: &str) -> String {
+    let mut out = Vec::with_capacity(value.len());
+    let bytes = value.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            if bytes.get(i + 1) == Some(&b'\\') {
+                out.push(b'\\');
+                i += 2;
+                continue;
+            }
+            if bytes.get(i + 1) == Some(&b'x') {
+                if let Some(byte) = value
+                    .get(i + 2..i + 4)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    out.push(byte);
+                    i += 4;
+                    continue;
+                }
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl IntegrationShell {
+    /// From a shell path or name such as `/usr/bin/zsh` or `bash.exe`.
+    pub fn detect(shell: &str) -> Option<Self> {
+        let name = shell
+            .trim()
+            .rsplit(['/', '\\'])
+            .next()?
+            .to_ascii_lowercase();
+        match name.strip_suffix(".exe").unwrap_or(&name) {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+
+    pub fn snippet(&self) -> &'static str {
+        match self {
+            Self::Bash => BASH_SNIPPET,
+            Self::Zsh => ZSH_SNIPPET,
+            Self::Fish => FISH_SNIPPET,
+        }
+    }
+}
+
+// `__zync_running`: 0 at the prompt, 1 while a command runs, 2 inside PROMPT_COMMAND
+// (and while this snippet itself is still being sourced).
+const BASH_SNIPPET: &str = r#"if [ -z "$__zync_si" ]; then
+__zync_si=1
+__zync_running=2
+__zync_preexec() {
+  [ "$__zync_running" != 0 ] && return
+  [ "$BASH_COMMAND" = "__zync_precmd" ] && return
+  __zync_running=1
+  local line
+  line=$(HISTTIMEFORMAT= builtin history 1 | sed 's/^ *[0-9]* *//')
+  line=${line//\\/\\\\}
+  line=${line//;/\\x3b}
+  printf '\033]633;E;%s\007\033]133;C\007' "$line"
+}
+__zync_precmd() {
+  local ec=$?
+  [ "$__zync_running" = 1 ] && printf '\033]133;D;%s\007' "$ec"
+  __zync_running=2
+  printf '\033]133;A\007\033]7;file://%s%s\007' "$HOSTNAME" "$PWD"
+}
+trap '__zync_preexec' DEBUG
+__zync_pc=$PROMPT_COMMAND
+while [[ $__zync_pc == *[\;\ ] ]]; do __zync_pc=${__zync_pc%?}; done
+PROMPT_COMMAND="__zync_precmd${__zync_pc:+;$__zync_pc};__zync_running=0"
+unset __zync_pc
+PS1="$PS1\[\033]133;B\007\]"
+fi
+"#;
+
+const ZSH_SNIPPET: &str = r#"if [[ -z $__zync_si ]]; then
+__zync_si=1
+__zync_running=0
+__zync_preexec() {
+  __zync_running=1
+  local line=${1//\\/\\\\}
+  line=${line//;/\\x3b}
+  line=${line//$'\n'/\\x0a}
+  printf '\033]633;E;%s\007\033]133;C\007' "$line"
+}
+__zync_precmd() {
+  local ec=$?
+  (( __zync_running )) && printf '\033]133;D;%s\007' $ec
+  __zync_running=0
+  printf '\033]133;A\007\033]7;file://%s%s\007' "$HOST" "$PWD"
+}
+preexec_functions+=(__zync_preexec)
+precmd_functions=(__zync_precmd $precmd_functions)
+PS1="$PS1%{"$'\033]133;B\007'"%}"
+fi
+"#;
+
+const FISH_SNIPPET: &str = r#"if not set -q __zync_si
+set -g __zync_si 1
+function __zync_preexec --on-event fish_preexec
+  set -l line (string replace -a '\\' '\\\\' -- $argv[1] | string replace -a ';' '\\x3b')
+  printf '\e]633;E;%s\a\e]133;C\a' "$line"
+end
+function __zync_postexec --on-event fish_postexec
+  printf '\e]133;D;%s\a' $status
+end
+function __zync_prompt --on-event fish_prompt
+  printf '\e]133;A\a\e]7;file://%s%s\a' $hostname $PWD
+end
+end
+"#;
+
+/// Line typed into a freshly started remote shell to load the snippet. The leading
+/// space keeps it out of history where `ignorespace` is set; `clear` hides it.
+pub fn remote_bootstrap(shell: IntegrationShell) -> String {
+    let encoded = STANDARD.encode(shell.snippet());
+    match shell {
+        IntegrationShell::Fish => format!(" printf %s '{}' | base64 -d | source; clear\r", encoded),
+        _ => format!(" eval \"$(printf %s '{}' | base64 -d)\"; clear\r", encoded),
+    }
+}
+
+/// Extra arguments and environment that load the snippet into a local shell
+/// without touching the user's rc files. Helper files are written under `dir`.
+pub struct LocalIntegration {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+pub fn prepare_local(shell: IntegrationShell, dir: &Path) -> Result<LocalIntegration> {
+    std::fs::create_dir_all(dir)?;
+    match shell {
+        IntegrationShell::Bash => {
+            let rc = dir.join("bashrc");
+            std::fs::write(
+                &rc,
+                format!("[ -f ~/.bashrc ] && . ~/.bashrc\n{}", BASH_SNIPPET),
+            )?;
+            Ok(LocalIntegration {
+                args: vec!["--rcfile".to_string(), rc.to_string_lossy().to_string()],
+                env: Vec::new(),
+            })
+        }
+        IntegrationShell::Zsh => {
+            // zsh reads $ZDOTDIR/.zshenv and .zshrc; both chain to the user's own
+            // files and the rc restores ZDOTDIR before anything else sees it.
+            let zdotdir = dir.join("zsh");
+            std::fs::create_dir_all(&zdotdir)?;
+            std::fs::write(
+                zdotdir.join(".zshenv"),
+                "__zync_zdotdir=$ZDOTDIR\nZDOTDIR=${ZYNC_USER_ZDOTDIR:-$HOME}\n\
+                 [ -f \"$ZDOTDIR/.zshenv\" ] && . \"$ZDOTDIR/.zshenv\"\n\
+                 ZDOTDIR=$__zync_zdotdir\n",
+            )?;
+            std::fs::write(
+                zdotdir.join(".zshrc"),
+                format!(
+                    "ZDOTDIR=${{ZYNC_USER_ZDOTDIR:-$HOME}}\nunset ZYNC_USER_ZDOTDIR __zync_zdotdir\n\
+                     [ -f \"$ZDOTDIR/.zshrc\" ] && . \"$ZDOTDIR/.zshrc\"\n{}",
+                    ZSH_SNIPPET
+                ),
+            )?;
+            let mut env = vec![("ZDOTDIR".to_string(), zdotdir.to_string_lossy().to_string())];
+            if let Ok(user) = std::env::var("ZDOTDIR") {
+                env.push(("ZYNC_USER_ZDOTDIR".to_string(), user));
+            }
+            Ok(LocalIntegration {
+                args: Vec::new(),
+                env,
+            })
+        }
+        IntegrationShell::Fish => Ok(LocalIntegration {
+            args: vec!["--init-command".to_string(), FISH_SNIPPET.to_string()],
+            env: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_command_across_chunks() {
+        let mut tracker = CommandTracker::new();
+        assert!(tracker.feed(b"\x1b]133;A\x07$ ").is_empty());
+        assert!(tracker
+            .feed(b"\x1b]633;E;ls -la\\x3b echo \\\\o/\x07")
+            .is_empty());
+        let start = tracker.feed(b"\x1b]13");
+        assert!(start.is_empty());
+        assert_eq!(
+            tracker.feed(b"3;C\x07file\r\n"),
+            vec![ShellEvent::CommandStart {
+                command: Some("ls -la; echo \\o/".to_string())
+            }]
+        );
+        match tracker.feed(b"\x1b]133;D;2\x1b\\").as_slice() {
+            [ShellEvent::CommandEnd {
+                command, exit_code, ..
+            }] => {
+                assert_eq!(command.as_deref(), Some("ls -la; echo \\o/"));
+                assert_eq!(*exit_code, Some(2));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ignores_unmatched_end_and_other_osc() {
+        let mut tracker = CommandTracker::new();
+        assert!(tracker.feed(b"\x1b]133;D\x07").is_empty());
+        assert!(tracker.feed(b"\x1b]2;vim\x07\x1b[0m").is_empty());
+        assert_eq!(
+            tracker.feed(b"\x1b]133;C\x07"),
+            vec![ShellEvent::CommandStart { command: None }]
+        );
+    }
+
+    #[test]
+    fn reports_cwd_from_osc7_and_633() {
+        let mut tracker = CommandTracker::new();
+        assert_eq!(
+            tracker.feed(b"\x1b]7;file://web-1/srv/my%20app\x1b\\"),
+            vec![ShellEvent::Cwd {
+                path: "/srv/my app".to_string()
+            }]
+        );
+        assert_eq!(
+            tracker.feed(b"\x1b]7;file:///C:/Users/me\x07"),
+            vec![ShellEvent::Cwd {
+                path: "C:/Users/me".to_string()
+            }]
+        );
+        assert_eq!(
+            tracker.feed(b"\x1b]633;P;Cwd=/tmp/a\\x3bb\x07"),
+            vec![ShellEvent::Cwd {
+                path: "/tmp/a;b".to_string()
+            }]
+        );
+        assert!(tracker.feed(b"\x1b]7;garbage\x07").is_empty());
+    }
+
+    #[test]
+    fn detects_shells_and_builds_bootstrap() {
+        assert_eq!(
+            IntegrationShell::detect("/usr/bin/zsh"),
+            Some(IntegrationShell::Zsh)
+        );
+        assert_eq!(
+            IntegrationShell::detect("bash.exe"),
+            Some(IntegrationShell::Bash)
+        );
+        assert_eq!(IntegrationShell::detect("/bin/dash"), None);
+        let line = remote_bootstrap(IntegrationShell::Bash);
+        assert!(line.starts_with(" eval \"$(printf %s '"));
+        assert!(line.ends_with("; clear\r"));
+        assert!(remote_bootstrap(IntegrationShell::Fish).contains("| source;"));
+    }
+}
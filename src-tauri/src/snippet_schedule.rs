@@ -0,0 +1,531 @@
+//! Scheduled snippets: recurring maintenance such as a disk report or a log
+//! rotation check, run on a connection without opening a terminal.
+//!
+//! A snippet with a `schedule` runs on `schedule.connectionId` whenever its
+//! cron expression matches the current minute, through the exec runner behind
+//! `snippets_run`, so its output streams as `exec-batch:*` events. The
+//! connection has to be open: a run that finds it closed is recorded as failed
+//! instead of connecting on its own. Minutes that pass while the app is closed
+//! are not caught up. Every run emits `snippet-schedule:run` and is appended to
+//! `snippet_runs.json`, which keeps the latest runs of each snippet.
+//!
+//! Cron expressions have the usual five fields (minute, hour, day of month,
+//! month, day of week) with `*`, lists, ranges and `/step`, or one of `@hourly`,
+//! `@daily`, `@weekly`, `@monthly` and `@yearly`. They are read in UTC shifted
+//! by `utcOffsetMinutes`, so a schedule does not follow daylight saving changes.
+
+use crate::commands::AppState;
+use crate::snippets::Snippet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+static HISTORY_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+const SCHEDULER_TICK: Duration = Duration::from_secs(15);
+const RUNS_PER_SNIPPET: usize = 50;
+/// Output kept per stream in the run history; the newest part is kept.
+const HISTORY_OUTPUT_BYTES: usize = 16 * 1024;
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+fn default_true() -> bool {
+    true
+}
+
+/// When and where a snippet runs on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSchedule {
+    /// Five-field cron expression, or a shorthand like `@daily`.
+    pub cron: String,
+    pub connection_id: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Offset from UTC of the zone `cron` is written in, e.g. `120` for UTC+2.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+impl SnippetSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.connection_id.trim().is_empty() {
+            return Err("A scheduled snippet needs a target connection".to_string());
+        }
+        Cron::parse(&self.cron).map(|_| ())
+    }
+
+    /// Minutes since the epoch in the schedule's zone.
+    fn minute_at(&self, now_ms: u64) -> i64 {
+        (now_ms / 60_000) as i64 + self.utc_offset_minutes as i64
+    }
+
+    /// Start of the next matching minute after `now_ms`, within a year.
+    pub fn next_run_at(&self, now_ms: u64) -> Option<u64> {
+        let cron = Cron::parse(&self.cron).ok()?;
+        let minute = cron.next_after(self.minute_at(now_ms))?;
+        u64::try_from((minute - self.utc_offset_minutes as i64) * 60_000).ok()
+    }
+}
+
+/// A parsed cron expression, one bit per allowed value of each field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Sunday is bit 0.
+    weekdays: u64,
+    /// Day of month and day of week were both restricted; either one matches.
+    either_day: bool,
+}
+
+fn parse_field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let number = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| format!("'{}' is not a number", text))
+    };
+    let mut bits = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<usize>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("'{}' has an invalid step", part)),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // `5/15` means every 15 starting at 5.
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("'{}' is outside {}-{}", part, min, max));
+        }
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression '{}' needs five fields: minute hour day month weekday",
+                expression
+            ));
+        };
+        let invalid = |e: String| format!("Invalid cron expression '{}': {}", expression, e);
+        let mut weekdays = parse_field(weekday, 0, 7).map_err(invalid)?;
+        // 7 is Sunday too.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23).map_err(invalid)?,
+            days: parse_field(day, 1, 31).map_err(invalid)?,
+            months: parse_field(month, 1, 12).map_err(invalid)?,
+            weekdays,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the expression matches `minute`, counted from the epoch in the
+    /// zone the expression is written in.
+    pub fn matches(&self, minute: i64) -> bool {
+        let days = minute.div_euclid(MINUTES_PER_DAY);
+        let of_day = minute.rem_euclid(MINUTES_PER_DAY);
+        let (_, month, day) = crate::trash::civil_from_days(days);
+        // 1970-01-01 was a Thursday.
+        let weekday = (days + 4).rem_euclid(7);
+        let hit = |bits: u64, value: i64| bits & (1 << value) != 0;
+        let day_matches = hit(self.days, day as i64);
+        let weekday_matches = hit(self.weekdays, weekday);
+        hit(self.minutes, of_day % 60)
+            && hit(self.hours, of_day / 60)
+            && hit(self.months, month as i64)
+            && if self.either_day {
+                day_matches || weekday_matches
+            } else {
+                day_matches && weekday_matches
+            }
+    }
+
+    /// The first matching minute after `minute`, looking at most a year ahead.
+    pub fn next_after(&self, minute: i64) -> Option<i64> {
+        (minute + 1..=minute + 366 * MINUTES_PER_DAY).find(|m| self.matches(*m))
+    }
+}
+
+/// One scheduled run, as kept in `snippet_runs.json` and emitted as
+/// `snippet-schedule:run`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRun {
+    pub run_id: String,
+    pub snippet_id: String,
+    pub snippet_name: String,
+    pub connection_id: String,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_status: Option<u32>,
+    #[serde(default)]
+    pub stdout: String,
+    #[serde(default)]
+    pub stderr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Entry of `snippets_schedule_status`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleStatus {
+    pub snippet_id: String,
+    pub name: String,
+    pub connection_id: String,
+    pub enabled: bool,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<ScheduledRun>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunHistory {
+    runs: Vec<ScheduledRun>,
+}
+
+#[derive(Default)]
+struct Runs {
+    /// Minute each snippet last started in, so two ticks never start it twice.
+    last_minute: HashMap<String, i64>,
+    running: HashSet<String>,
+}
+
+pub struct SnippetScheduler {
+    history_path: PathBuf,
+    runs: Mutex<Runs>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The last `max` bytes of `text`, cut on a character boundary.
+fn tail(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text[start..].to_string()
+}
+
+impl SnippetScheduler {
+    pub fn new(app_data_dir: PathBuf) -> Self {
+        Self {
+            history_path: app_data_dir.join("snippet_runs.json"),
+            runs: Mutex::new(Runs::default()),
+        }
+    }
+
+    fn load_history(&self) -> RunHistory {
+        std::fs::read(&self.history_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Runs of `snippet_id` (or of every snippet), newest first.
+    pub fn history(&self, snippet_id: Option<&str>, limit: Option<usize>) -> Vec<ScheduledRun> {
+        let _guard = HISTORY_LOCK.lock();
+        let mut runs: Vec<ScheduledRun> = self
+            .load_history()
+            .runs
+            .into_iter()
+            .rev()
+            .filter(|run| snippet_id.is_none_or(|id| run.snippet_id == id))
+            .collect();
+        if let Some(limit) = limit {
+            runs.truncate(limit);
+        }
+        runs
+    }
+
+    fn record(&self, run: &ScheduledRun) -> Result<(), String> {
+        let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+        let mut history = self.load_history();
+        history.runs.push(run.clone());
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        let mut keep: Vec<bool> = history
+            .runs
+            .iter()
+            .rev()
+            .map(|r| {
+                let count = kept.entry(r.snippet_id.as_str()).or_default();
+                *count += 1;
+                *count <= RUNS_PER_SNIPPET
+            })
+            .collect();
+        keep.reverse();
+        let mut keep = keep.into_iter();
+        history.runs.retain(|_| keep.next().unwrap_or(false));
+        let content = serde_json::to_vec_pretty(&history).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.history_path, &content).map_err(|e| e.to_string())
+    }
+
+    /// Drops the history of snippets that no longer exist.
+    pub fn forget_removed(&self, snippets: &[Snippet]) -> Result<(), String> {
+        let _guard = HISTORY_LOCK.lock().map_err(|e| e.to_string())?;
+        let mut history = self.load_history();
+        let before = history.runs.len();
+        history
+            .runs
+            .retain(|run| snippets.iter().any(|s| s.id == run.snippet_id));
+        if history.runs.len() == before {
+            return Ok(());
+        }
+        let content = serde_json::to_vec_pretty(&history).map_err(|e| e.to_string())?;
+        crate::atomic_io::durable_replace(&self.history_path, &content).map_err(|e| e.to_string())
+    }
+
+    pub fn status(&self, snippets: &[Snippet]) -> Vec<ScheduleStatus> {
+        let running = self
+            .runs
+            .lock()
+            .map(|runs| runs.running.clone())
+            .unwrap_or_default();
+        let history = self.history(None, None);
+        let now = now_ms();
+        snippets
+            .iter()
+            .filter_map(|snippet| {
+                let schedule = snippet.schedule.as_ref()?;
+                Some(ScheduleStatus {
+                    snippet_id: snippet.id.clone(),
+                    name: snippet.name.clone(),
+                    connection_id: schedule.connection_id.clone(),
+                    enabled: schedule.enabled,
+                    running: running.contains(&snippet.id),
+                    next_run_at: schedule
+                        .enabled
+                        .then(|| schedule.next_run_at(now))
+                        .flatten(),
+                    last_run: history
+                        .iter()
+                        .find(|run| run.snippet_id == snippet.id)
+                        .cloned(),
+                    error: schedule.validate().err(),
+                })
+            })
+            .collect()
+    }
+
+    /// Enabled schedules matching the minute of `now` that have not started in
+    /// it and are not still running; marks them running.
+    fn take_due(&self, snippets: &[Snippet], now: u64) -> Vec<(Snippet, SnippetSchedule)> {
+        let Ok(mut runs) = self.runs.lock() else {
+            return Vec::new();
+        };
+        let mut due = Vec::new();
+        for snippet in snippets {
+            let Some(schedule) = snippet.schedule.as_ref().filter(|s| s.enabled) else {
+                continue;
+            };
+            let Ok(cron) = Cron::parse(&schedule.cron) else {
+                continue;
+            };
+            let minute = schedule.minute_at(now);
+            if !cron.matches(minute)
+                || runs.last_minute.get(&snippet.id) == Some(&minute)
+                || runs.running.contains(&snippet.id)
+            {
+                continue;
+            }
+            runs.last_minute.insert(snippet.id.clone(), minute);
+            runs.running.insert(snippet.id.clone());
+            due.push((snippet.clone(), schedule.clone()));
+        }
+        due
+    }
+
+    fn finish(&self, snippet_id: &str) {
+        if let Ok(mut runs) = self.runs.lock() {
+            runs.running.remove(snippet_id);
+        }
+    }
+}
+
+async fn run(app: &AppHandle, state: &AppState, snippet: Snippet, schedule: SnippetSchedule) {
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let started_at = now_ms();
+    let connected = state
+        .connections
+        .lock()
+        .await
+        .contains_key(&schedule.connection_id);
+    let result = if connected {
+        crate::commands::exec_on_connection(
+            app,
+            &schedule.connection_id,
+            snippet.command.clone(),
+            schedule.timeout_secs,
+            run_id.clone(),
+            "snippet_schedule",
+        )
+        .await
+    } else {
+        None
+    };
+    let record = match result {
+        Some(result) => ScheduledRun {
+            run_id,
+            snippet_id: snippet.id.clone(),
+            snippet_name: snippet.name.clone(),
+            connection_id: schedule.connection_id.clone(),
+            started_at,
+            duration_ms: result.duration_ms,
+            exit_status: result.exit_status,
+            stdout: tail(&result.stdout, HISTORY_OUTPUT_BYTES),
+            stderr: tail(&result.stderr, HISTORY_OUTPUT_BYTES),
+            error: result.error,
+        },
+        None => ScheduledRun {
+            run_id,
+            snippet_id: snippet.id.clone(),
+            snippet_name: snippet.name.clone(),
+            connection_id: schedule.connection_id.clone(),
+            started_at,
+            duration_ms: 0,
+            exit_status: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some("Connection is not open".to_string()),
+        },
+    };
+    if let Some(error) = &record.error {
+        crate::log_error!(
+            "[SNIPPETS] Scheduled run of '{}' failed: {}",
+            snippet.name,
+            error
+        );
+    }
+    if let Err(e) = state.snippet_scheduler.record(&record) {
+        crate::log_error!("[SNIPPETS] Failed to record scheduled run: {}", e);
+    }
+    state.snippet_scheduler.finish(&snippet.id);
+    let _ = app.emit("snippet-schedule:run", &record);
+}
+
+pub fn spawn_snippet_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULER_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            let Some(state) = app.try_state::<AppState>() else {
+                continue;
+            };
+            let Ok(snippets) = state.snippets_manager.list().await else {
+                continue;
+            };
+            for (snippet, schedule) in state.snippet_scheduler.take_due(&snippets, now_ms()) {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(state) = app.try_state::<AppState>() {
+                        run(&app, &state, snippet, schedule).await;
+                    }
+                });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minutes since the epoch for a UTC date and time.
+    fn at(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        crate::trash::days_from_civil(year, month, day) * MINUTES_PER_DAY + hour * 60 + minute
+    }
+
+    #[test]
+    fn parses_fields_ranges_and_steps() {
+        let cron = Cron::parse("*/15 9-17 * * 1-5").unwrap();
+        // 2026-10-16 is a Friday.
+        assert!(cron.matches(at(2026, 10, 16, 9, 45)));
+        assert!(!cron.matches(at(2026, 10, 16, 9, 50)));
+        assert!(!cron.matches(at(2026, 10, 16, 18, 0)));
+        assert!(!cron.matches(at(2026, 10, 17, 10, 0)));
+
+        let sunday = Cron::parse("30 2 * * 7").unwrap();
+        assert!(sunday.matches(at(2026, 10, 18, 2, 30)));
+        assert_eq!(Cron::parse("@daily"), Cron::parse("0 0 * * *"));
+
+        assert!(Cron::parse("0 0 * *").is_err());
+        assert!(Cron::parse("61 * * * *").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn restricted_day_and_weekday_match_either() {
+        // The 1st of the month, and every Monday.
+        let cron = Cron::parse("0 6 1 * 1").unwrap();
+        assert!(cron.matches(at(2026, 10, 1, 6, 0)));
+        assert!(cron.matches(at(2026, 10, 19, 6, 0)));
+        assert!(!cron.matches(at(2026, 10, 20, 6, 0)));
+    }
+
+    #[test]
+    fn next_run_follows_the_utc_offset() {
+        let schedule = SnippetSchedule {
+            cron: "0 3 * * *".to_string(),
+            connection_id: "c1".to_string(),
+            enabled: true,
+            utc_offset_minutes: 120,
+            timeout_secs: None,
+        };
+        let now = at(2026, 10, 16, 12, 0) as u64 * 60_000;
+        assert_eq!(
+            schedule.next_run_at(now),
+            Some(at(2026, 10, 17, 1, 0) as u64 * 60_000)
+        );
+    }
+
+    #[test]
+    fn tail_keeps_the_end_on_a_char_boundary() {
+        assert_eq!(tail("héllo", 4), "llo");
+        assert_eq!(tail("abc", 10), "abc");
+    }
+}
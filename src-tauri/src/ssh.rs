@@ -7,15 +7,23 @@ use std::sync::Arc;
 use crate::tunnels::TunnelManager;
 use crate::types::{AuthMethod, ConnectionConfig};
 use russh::client::Msg;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
 use tokio::net::TcpStream;
 
+/// Identities the virtual agent serves on one hop of a connection chain.
+pub type AgentKeys = Arc<std::sync::Mutex<Vec<russh_keys::key::KeyPair>>>;
+
 #[derive(Clone)]
 pub struct Client {
     pub tunnel_manager: Arc<TunnelManager>,
     /// Zync connection id for scoping remote forward map lookups.
     pub connection_id: String,
     pub kept_alive_session: Option<Arc<Box<client::Handle<Client>>>>,
-    pub agent_keys: Arc<std::sync::Mutex<Vec<russh_keys::key::KeyPair>>>,
+    /// Keys this hop authenticated with; never shared with jump hosts or other connections.
+    pub agent_keys: AgentKeys,
+    /// Session channel slots of this hop, freed as the server closes channels.
+    pub channel_budget: Arc<crate::channel_budget::ChannelBudget>,
 }
 
 impl std::fmt::Debug for Client {
@@ -25,6 +33,7 @@ impl std::fmt::Debug for Client {
             .field("connection_id", &self.connection_id)
             .field("kept_alive_session", &self.kept_alive_session.is_some())
             .field("agent_keys", &"Vec<KeyPair>")
+            .field("channel_budget", &self.channel_budget.usage())
             .finish()
     }
 }
@@ -35,19 +44,35 @@ impl client::Handler for Client {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &russh_keys::key::PublicKey,
+        server_public_key: &russh_keys::key::PublicKey,
     ) -> Result<bool, Self::Error> {
+        crate::ssh_security::record_host_key(
+            &self.connection_id,
+            crate::ssh_security::HostKeyInfo {
+                key_type: server_public_key.name().to_string(),
+                fingerprint: format!("SHA256:{}", server_public_key.fingerprint()),
+            },
+        );
         // Validation is done during connect if strict checking is enabled,
         // but for now we trust (or could implement known_hosts check here)
         Ok(true)
     }
 
+    async fn channel_close(
+        &mut self,
+        channel: ChannelId,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        self.channel_budget.closed(channel);
+        Ok(())
+    }
+
     async fn server_channel_open_agent_forward(
         &mut self,
         channel: Channel<Msg>,
         _session: &mut client::Session,
     ) -> Result<(), Self::Error> {
-        println!("[SSH] Virtual Agent Request from server!");
+        crate::log_info!("[SSH] Virtual Agent Request from server!");
         let mut stream = channel.into_stream();
         let agent_keys = self.agent_keys.clone();
 
@@ -66,7 +91,7 @@ impl client::Handler for Client {
 
                 // Sanity check length
                 if len == 0 || len > MAX_FORWARDED_AGENT_PACKET_SIZE {
-                    eprintln!(
+                    crate::log_error!(
                         "[SSH] Invalid virtual agent packet size: {}. Closing channel.",
                         len
                     );
@@ -91,7 +116,7 @@ impl client::Handler for Client {
                     break;
                 }
             }
-            println!("[SSH] Virtual Agent channel closed.");
+            crate::log_info!("[SSH] Virtual Agent channel closed.");
         });
         Ok(())
     }
@@ -101,14 +126,15 @@ impl client::Handler for Client {
         channel: Channel<Msg>,
         connected_address: &str,
         connected_port: u32,
-        _originator_address: &str,
-        _originator_port: u32,
+        originator_address: &str,
+        originator_port: u32,
         _session: &mut client::Session,
     ) -> Result<(), Self::Error> {
         // ... (existing implementation) ...
-        println!(
+        crate::log_info!(
             "[TUNNEL] Incoming forwarded connection on {}:{}",
-            connected_address, connected_port
+            connected_address,
+            connected_port
         );
 
         let map_key = crate::tunnels::remote_forward_map_key(
@@ -119,15 +145,38 @@ impl client::Handler for Client {
             let map = self.tunnel_manager.remote_forwards.lock().await;
             map.get(&map_key).cloned()
         };
-
-        if let Some((target_host, target_port, _bind_addr)) = target {
-            println!("[TUNNEL] Forwarding to {}:{}", target_host, target_port);
+        let counters = self
+            .tunnel_manager
+            .remote_forward_counters(&map_key)
+            .unwrap_or_default();
+
+        let source = format!("{}:{}", originator_address, originator_port);
+        if let Some((crate::tunnels::manager::RemoteTarget::Socks, _bind_addr)) = target {
+            crate::log_info!("[TUNNEL] Serving reverse SOCKS client {}", source);
+            tokio::spawn(async move {
+                let connection = counters.open_connection(Some(source));
+                crate::tunnels::dynamic::handle_reverse_socks5_client(
+                    channel.into_stream(),
+                    connection,
+                )
+                .await;
+            });
+            Ok(())
+        } else if let Some((
+            crate::tunnels::manager::RemoteTarget::Fixed(target_host, target_port),
+            _bind_addr,
+        )) = target
+        {
+            crate::log_info!("[TUNNEL] Forwarding to {}:{}", target_host, target_port);
 
             let target_addr = format!("{}:{}", target_host, target_port);
 
             tokio::spawn(async move {
+                let connection = counters.open_connection(Some(source));
                 match TcpStream::connect(&target_addr).await {
-                    Ok(mut local_stream) => {
+                    Ok(local_stream) => {
+                        let mut local_stream =
+                            crate::tunnels::metrics::CountingStream::new(local_stream, &connection);
                         let mut channel_stream = channel.into_stream();
                         if let Err(e) =
                             tokio::io::copy_bidirectional(&mut channel_stream, &mut local_stream)
@@ -137,28 +186,30 @@ impl client::Handler for Client {
                                 "[TUNNEL] copy_bidirectional error between channel_stream and local_stream: {:?}",
                                 e
                             );
+                            connection.fail(&e);
                         }
                     }
-                    Err(e) => eprintln!(
-                        "[TUNNEL] Failed to connect to local target {}: {}",
-                        target_addr, e
-                    ),
+                    Err(e) => {
+                        crate::log_error!(
+                            "[TUNNEL] Failed to connect to local target {}: {}",
+                            target_addr,
+                            e
+                        );
+                        connection.fail(&e);
+                    }
                 }
             });
 
             Ok(())
         } else {
-            eprintln!("[TUNNEL] No tunnel found for port {}", connected_port);
+            crate::log_error!("[TUNNEL] No tunnel found for port {}", connected_port);
             Ok(())
         }
     }
 }
 
 // Minimal SSH Agent Protocol Handler
-fn handle_agent_request(
-    keys_mutex: &Arc<std::sync::Mutex<Vec<russh_keys::key::KeyPair>>>,
-    payload: &[u8],
-) -> Vec<u8> {
+fn handle_agent_request(keys_mutex: &AgentKeys, payload: &[u8]) -> Vec<u8> {
     if payload.is_empty() {
         return vec![5];
     } // SSH_AGENT_FAILURE
@@ -283,23 +334,141 @@ fn handle_agent_request(
     }
 }
 
-pub struct SshManager {
-    // Shared keys for virtual agent
-    pub agent_keys: Arc<std::sync::Mutex<Vec<russh_keys::key::KeyPair>>>,
+/// Payload for `ssh:connect-progress` — lets the UI show which address a host
+/// actually resolved to (split-horizon DNS, static overrides, jump-host routing).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectProgress {
+    pub connection_id: String,
+    pub stage: String,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Generic over the runtime so tests can drive it with a mock app.
+pub struct SshManager<R: Runtime = tauri::Wry> {
+    app_handle: AppHandle<R>,
+    pub host_limits: crate::host_limits::HostLimiter,
 }
 
 impl SshManager {
-    pub fn new() -> Self {
+    pub fn new(app_handle: AppHandle) -> Self {
+        let data_dir = crate::commands::get_data_dir(&app_handle);
+        Self::with_data_dir(app_handle, data_dir)
+    }
+}
+
+impl<R: Runtime> SshManager<R> {
+    pub fn with_data_dir(app_handle: AppHandle<R>, data_dir: std::path::PathBuf) -> Self {
         Self {
-            agent_keys: Arc::new(std::sync::Mutex::new(Vec::new())),
+            app_handle,
+            host_limits: crate::host_limits::HostLimiter::new(data_dir),
+        }
+    }
+
+    /// Waits for a per-host attempt slot, reporting each wait as
+    /// `ssh:connect-queued`. Hold the slot until authentication is done.
+    async fn host_slot(&self, config: &ConnectionConfig) -> crate::host_limits::HostSlot {
+        let key = crate::host_limits::host_key(&config.host, config.port);
+        self.host_limits
+            .acquire(&key, |reason, wait| {
+                let queued = crate::host_limits::ConnectQueued {
+                    connection_id: config.id.clone(),
+                    host: config.host.clone(),
+                    reason,
+                    wait_ms: wait.map(|w| w.as_millis() as u64),
+                };
+                if let Err(e) = self.app_handle.emit("ssh:connect-queued", queued) {
+                    crate::log_error!("[SSH] Failed to emit connect queue state: {}", e);
+                }
+            })
+            .await
+    }
+
+    fn emit_progress(&self, progress: ConnectProgress) {
+        if let Err(e) = self.app_handle.emit("ssh:connect-progress", progress) {
+            crate::log_error!("[SSH] Failed to emit connect progress: {}", e);
+        }
+    }
+
+    /// [`connect`](Self::connect) over the primary endpoint, then each of
+    /// `config.fallbacks` in turn (see `crate::failover`). Returns the session and
+    /// the label of the fallback that connected, `None` for the primary.
+    pub async fn connect_with_fallbacks(
+        &self,
+        config: &ConnectionConfig,
+        tunnel_manager: Arc<crate::tunnels::TunnelManager>,
+        channel_budget: Arc<crate::channel_budget::ChannelBudget>,
+    ) -> Result<(client::Handle<Client>, Option<String>)> {
+        let endpoints = crate::failover::endpoints(config);
+        let total = endpoints.len();
+        let mut failures = Vec::new();
+        for (index, endpoint) in endpoints.into_iter().enumerate() {
+            let attempt =
+                crate::failover::ConnectAttempt::new(&endpoint, index + 1, total, "trying");
+            self.emit_attempt(attempt.clone());
+            match self
+                .connect(
+                    endpoint.config.clone(),
+                    tunnel_manager.clone(),
+                    channel_budget.clone(),
+                )
+                .await
+            {
+                Ok(session) => {
+                    if total > 1 {
+                        crate::log_info!("[SSH] {} connected via '{}'", config.id, endpoint.label);
+                    }
+                    self.emit_attempt(crate::failover::ConnectAttempt {
+                        stage: "connected".to_string(),
+                        ..attempt
+                    });
+                    let label = endpoint.fallback_index.map(|_| endpoint.label);
+                    return Ok((session, label));
+                }
+                Err(e) => {
+                    let message = crate::redact::error(&e);
+                    self.emit_attempt(crate::failover::ConnectAttempt {
+                        stage: "failed".to_string(),
+                        error: Some(message.clone()),
+                        ..attempt
+                    });
+                    if total == 1 || !crate::failover::should_try_next(&message) {
+                        return Err(e);
+                    }
+                    crate::log_error!("[SSH] Route '{}' failed: {}", endpoint.label, message);
+                    failures.push(format!("{}: {}", endpoint.label, message));
+                }
+            }
+        }
+        Err(anyhow!(
+            "All {} routes failed ({})",
+            total,
+            failures.join("; ")
+        ))
+    }
+
+    fn emit_attempt(&self, attempt: crate::failover::ConnectAttempt) {
+        if let Err(e) = self
+            .app_handle
+            .emit(crate::failover::ATTEMPT_EVENT, attempt)
+        {
+            crate::log_error!("[SSH] Failed to emit connect attempt: {}", e);
         }
     }
 
+    /// `channel_budget` tracks the session channels of this connection (not of
+    /// its jump hosts); register it to have `open_session` queue against it.
     pub async fn connect(
         &self,
         config: ConnectionConfig,
         tunnel_manager: Arc<crate::tunnels::TunnelManager>,
+        channel_budget: Arc<crate::channel_budget::ChannelBudget>,
     ) -> Result<client::Handle<Client>> {
+        crate::kill_switch::ensure_online().map_err(|e| anyhow!(e))?;
         // Keep-alive: send a heartbeat every 60s to prevent NAT/firewall timeouts on idle sessions
         let client_config = client::Config {
             keepalive_interval: Some(std::time::Duration::from_secs(60)),
@@ -308,18 +477,42 @@ impl SshManager {
         };
         let client_config = Arc::new(client_config);
 
+        // Each hop gets its own virtual agent key set, so a bastion only ever
+        // sees the identities configured for it, never the target's keys.
+        let agent_keys = AgentKeys::default();
+
         // Recursive Jump Host Logic
         if let Some(ref jump_host_config) = config.jump_host {
             // 1. Connect to Jump Host (Recursive)
-            let jump_session =
-                Box::pin(self.connect((**jump_host_config).clone(), tunnel_manager.clone()))
-                    .await
-                    .map_err(|e| anyhow!("Failed to connect to jump host: {}", e))?;
-
-            // 2. Open Direct TCP/IP Channel through Jump Host
+            let jump_session = Box::pin(self.connect(
+                (**jump_host_config).clone(),
+                tunnel_manager.clone(),
+                crate::channel_budget::ChannelBudget::new(jump_host_config.channel_budget),
+            ))
+            .await
+            .map_err(|e| anyhow!("Failed to connect to jump host: {}", e))?;
+
+            // 2. Open Direct TCP/IP Channel through Jump Host.
+            // The jump host resolves the target name itself (its DNS view is the one that
+            // matters), unless a static address override pins the destination.
+            let target_host = config
+                .resolution
+                .as_ref()
+                .and_then(|opts| opts.static_address.as_deref())
+                .map(str::trim)
+                .filter(|addr| !addr.is_empty())
+                .unwrap_or(config.host.as_str())
+                .to_string();
+            self.emit_progress(ConnectProgress {
+                connection_id: config.id.clone(),
+                stage: "resolved".to_string(),
+                host: config.host.clone(),
+                address: Some(format!("{}:{}", target_host, config.port)),
+                source: Some("jump-host".to_string()),
+            });
             let channel = jump_session
                 .channel_open_direct_tcpip(
-                    config.host.clone(),
+                    target_host,
                     config.port as u32,
                     "0.0.0.0", // Originator IP (dummy)
                     0,         // Originator port (dummy)
@@ -329,13 +522,15 @@ impl SshManager {
 
             // 3. Establish SSH Session over the Channel
             let stream = channel.into_stream();
+            let _slot = self.host_slot(&config).await;
 
             // 4. Create handler with agent keys
             let client_handler = Client {
                 tunnel_manager: tunnel_manager.clone(),
                 connection_id: config.id.clone(),
                 kept_alive_session: Some(Arc::new(Box::new(jump_session))),
-                agent_keys: self.agent_keys.clone(),
+                agent_keys: agent_keys.clone(),
+                channel_budget: channel_budget.clone(),
             };
 
             // russh::client::connect_stream takes stream and handler
@@ -344,7 +539,7 @@ impl SshManager {
 
             // 5. Authenticate (Target)
             return self
-                .authenticate_session(&mut session, &config)
+                .authenticate_session(&mut session, &config, &agent_keys)
                 .await
                 .map(|_| session);
         }
@@ -354,25 +549,83 @@ impl SshManager {
             tunnel_manager: tunnel_manager.clone(),
             connection_id: config.id.clone(),
             kept_alive_session: None,
-            agent_keys: self.agent_keys.clone(),
+            agent_keys: agent_keys.clone(),
+            channel_budget,
         };
 
-        let mut session = client::connect(
-            client_config,
-            (config.host.as_str(), config.port),
-            client_handler,
-        )
-        .await?;
+        let _slot = self.host_slot(&config).await;
+        self.emit_progress(ConnectProgress {
+            connection_id: config.id.clone(),
+            stage: "resolving".to_string(),
+            host: config.host.clone(),
+            address: None,
+            source: None,
+        });
+        let resolved =
+            crate::dns::resolve_host(&config.host, config.port, config.resolution.as_ref())
+                .await
+                .map_err(|e| {
+                    self.emit_progress(ConnectProgress {
+                        connection_id: config.id.clone(),
+                        stage: "resolve-failed".to_string(),
+                        host: config.host.clone(),
+                        address: None,
+                        source: None,
+                    });
+                    e
+                })?;
+        let address_list = resolved
+            .addresses
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        crate::log_info!(
+            "[SSH] Resolved {} via {} -> {}",
+            config.host,
+            resolved.source.as_str(),
+            address_list
+        );
+        self.emit_progress(ConnectProgress {
+            connection_id: config.id.clone(),
+            stage: "resolved".to_string(),
+            host: config.host.clone(),
+            address: Some(address_list),
+            source: Some(resolved.source.as_str().to_string()),
+        });
+
+        // Try each resolved address in preference order, applying the connection's socket options.
+        let stream = crate::tcp_options::connect(&resolved.addresses, config.tcp.as_ref())
+            .await
+            .map_err(|e| anyhow!("Failed to connect to {}:{}: {}", config.host, config.port, e))?;
+        if let Ok(peer) = stream.peer_addr() {
+            self.emit_progress(ConnectProgress {
+                connection_id: config.id.clone(),
+                stage: "connected".to_string(),
+                host: config.host.clone(),
+                address: Some(peer.to_string()),
+                source: Some(resolved.source.as_str().to_string()),
+            });
+        }
+
+        let mut session =
+            russh::client::connect_stream(client_config, stream, client_handler).await?;
 
-        self.authenticate_session(&mut session, &config)
+        self.authenticate_session(&mut session, &config, &agent_keys)
             .await
             .map(|_| session)
     }
 
+    /// Offers exactly the connection's configured credential; keys the virtual
+    /// agent holds for other connections are never tried. Any agent-key fallback
+    /// added here must skip connections with `identities_only` set, so servers with
+    /// a low `MaxAuthTries` only ever see the configured key. A successful key is
+    /// added to `agent_keys`, the key set of this hop only.
     async fn authenticate_session(
         &self,
         session: &mut client::Handle<Client>,
         config: &ConnectionConfig,
+        agent_keys: &AgentKeys,
     ) -> Result<()> {
         let auth_res = match &config.auth_method {
             AuthMethod::Password { password } => {
@@ -398,15 +651,16 @@ impl SshManager {
                         expanded = expanded.replacen("~", &home.to_string_lossy(), 1);
                     }
                 }
-                let key_data = tokio::fs::read_to_string(&expanded)
-                    .await
+                let key_file = std::path::Path::new(&expanded);
+                // Keys imported into the data directory may be sealed at rest.
+                let key_data = crate::vault::at_rest::read_to_string(key_file)
                     .map_err(|e| anyhow!("Failed to read private key file: {}", e))?;
                 Self::auth_with_key_data(
                     session,
                     &config.username,
                     &key_data,
                     passphrase.as_deref(),
-                    &self.agent_keys,
+                    agent_keys,
                 )
                 .await?
             }
@@ -419,7 +673,7 @@ impl SshManager {
                     &config.username,
                     key_data,
                     passphrase.as_deref(),
-                    &self.agent_keys,
+                    agent_keys,
                 )
                 .await?
             }
@@ -431,9 +685,12 @@ impl SshManager {
             }
         };
 
+        let host = crate::host_limits::host_key(&config.host, config.port);
         if !auth_res {
+            self.host_limits.auth_failed(&host);
             return Err(anyhow!("Authentication failed"));
         }
+        self.host_limits.auth_succeeded(&host);
         Ok(())
     }
 
@@ -442,7 +699,7 @@ impl SshManager {
         username: &str,
         key_data: &str,
         passphrase: Option<&str>,
-        agent_keys: &std::sync::Mutex<Vec<russh_keys::key::KeyPair>>,
+        agent_keys: &AgentKeys,
     ) -> Result<bool> {
         let privkey = russh_keys::decode_secret_key(key_data, passphrase)
             .map_err(|e| anyhow!("Failed to decode private key: {}", e))?;
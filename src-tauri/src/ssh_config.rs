@@ -15,6 +15,13 @@ pub struct ParsedSshConnection {
     pub jump_server_alias: Option<String>,
     pub jump_server_id: Option<String>,
     pub aliases: Vec<String>, // Add full alias list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identities_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Folder path to file the connection under, e.g. `prod/web`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
 }
 
 // Helper function to strip wrapping quotes from values
@@ -93,6 +100,9 @@ pub fn parse_config_text(content: &str) -> Result<Vec<ParsedSshConnection>> {
                 jump_server_alias: None,
                 jump_server_id: None,
                 aliases, // Store full alias list
+                identities_only: None,
+                tags: Vec::new(),
+                folder: None,
             });
         } else if let Some(host) = current_host.as_mut() {
             match key_str.to_lowercase().as_str() {
@@ -117,6 +127,13 @@ pub fn parse_config_text(content: &str) -> Result<Vec<ParsedSshConnection>> {
                     host.private_key_path = Some(path);
                 }
                 "proxyjump" => host.jump_server_alias = Some(value_str.to_string()),
+                "identitiesonly" => {
+                    host.identities_only = match value_str.to_ascii_lowercase().as_str() {
+                        "yes" | "true" => Some(true),
+                        "no" | "false" => Some(false),
+                        _ => None,
+                    }
+                }
                 _ => {}
             }
         }
@@ -183,9 +200,105 @@ fn strip_inline_comments(line: &str) -> &str {
     line
 }
 
+const MANAGED_BEGIN: &str = "# >>> zync managed hosts >>>";
+const MANAGED_END: &str = "# <<< zync managed hosts <<<";
+
+/// First pattern of a `Host` line; `None` for any other line.
+fn host_line_alias(line: &str) -> Option<&str> {
+    let line = strip_inline_comments(line).trim();
+    let (key, value) = line.split_once(|c: char| c.is_whitespace() || c == '=')?;
+    if !key.eq_ignore_ascii_case("host") {
+        return None;
+    }
+    value
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+        .split_whitespace()
+        .next()
+}
+
+/// `Host` blocks in `section` as `(alias, text)`, in order.
+fn split_host_blocks(section: &str) -> Vec<(String, String)> {
+    let mut blocks: Vec<(String, String)> = Vec::new();
+    for line in section.lines() {
+        if let Some(alias) = host_line_alias(line) {
+            blocks.push((alias.to_string(), String::new()));
+        }
+        if let Some((_, block)) = blocks.last_mut() {
+            if !line.trim().is_empty() {
+                block.push_str(line);
+                block.push('\n');
+            }
+        }
+    }
+    blocks
+}
+
+/// Writes `blocks` (`(alias, "Host …" text)`) into the zync-managed section of
+/// an ssh_config file, adding the section at the end if there is none. Hosts
+/// already in the section are replaced by alias and the others kept; text
+/// outside the markers is left untouched.
+pub fn merge_managed_hosts(existing: &str, blocks: &[(String, String)]) -> String {
+    let begin = existing.find(MANAGED_BEGIN);
+    let end = begin.and_then(|b| existing[b..].find(MANAGED_END).map(|e| b + e));
+    let (before, section, after) = match (begin, end) {
+        (Some(b), Some(e)) => {
+            let after = existing[e + MANAGED_END.len()..].trim_start_matches(['\r', '\n']);
+            (&existing[..b], &existing[b + MANAGED_BEGIN.len()..e], after)
+        }
+        _ => (existing, "", ""),
+    };
+
+    let mut hosts = split_host_blocks(section);
+    for (alias, block) in blocks {
+        match hosts.iter_mut().find(|(existing, _)| existing == alias) {
+            Some(host) => host.1 = block.clone(),
+            None => hosts.push((alias.clone(), block.clone())),
+        }
+    }
+
+    let mut output = before.to_string();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    if begin.is_none() && !output.is_empty() && !output.ends_with("\n\n") {
+        output.push('\n');
+    }
+    output.push_str(MANAGED_BEGIN);
+    output.push_str("\n# Exported from zync; changes between these markers are overwritten.\n");
+    for (_, block) in &hosts {
+        output.push('\n');
+        output.push_str(block.trim_end());
+        output.push('\n');
+    }
+    output.push_str(MANAGED_END);
+    output.push('\n');
+    if !after.is_empty() {
+        output.push('\n');
+        output.push_str(after);
+    }
+    output
+}
+
+/// `config` with `Include <include>` added at the top (where it applies to
+/// every host), or `None` if it already includes that file.
+pub fn with_include(config: &str, include: &str) -> Option<String> {
+    let full_path = format!("~/.ssh/{}", include);
+    let present = config.lines().any(|line| {
+        let mut words = strip_inline_comments(line).split_whitespace();
+        words
+            .next()
+            .is_some_and(|key| key.eq_ignore_ascii_case("include"))
+            && words.any(|word| {
+                let word = strip_wrapping_quotes(word);
+                word == include || word == full_path
+            })
+    });
+    (!present).then(|| format!("Include {}\n\n{}", include, config))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::parse_config_text;
+    use super::{merge_managed_hosts, parse_config_text, with_include};
 
     #[test]
     fn parse_config_text_parses_basic_host_block() {
@@ -217,4 +330,59 @@ Host app
         assert_eq!(parsed[0].host, "10.0.0.5 # inside");
         assert_eq!(parsed[0].username, "root");
     }
+
+    #[test]
+    fn parse_config_text_reads_identities_only() {
+        let text = r#"
+Host locked
+  HostName 10.0.0.6
+  IdentityFile ~/.ssh/locked_ed25519
+  IdentitiesOnly yes
+
+Host open
+  HostName 10.0.0.7
+"#;
+
+        let parsed = parse_config_text(text).expect("should parse");
+        assert_eq!(parsed[0].identities_only, Some(true));
+        assert_eq!(parsed[1].identities_only, None);
+    }
+
+    fn block(alias: &str, host: &str) -> (String, String) {
+        let text = format!("Host {}\n  HostName {}\n", alias, host);
+        (alias.to_string(), text)
+    }
+
+    #[test]
+    fn merge_managed_hosts_keeps_user_content() {
+        let user = "Host *\n  ServerAliveInterval 30\n";
+        let first = merge_managed_hosts(user, &[block("web", "10.0.0.1")]);
+        assert!(first.starts_with("Host *\n  ServerAliveInterval 30\n\n# >>> zync"));
+        assert!(first.contains("Host web\n  HostName 10.0.0.1\n"));
+
+        let edited = format!("{}\nHost mine\n  User me\n", first);
+        let second = merge_managed_hosts(
+            &edited,
+            &[block("web", "10.0.0.9"), block("db", "10.0.0.2")],
+        );
+        assert!(second.contains("HostName 10.0.0.9"));
+        assert!(!second.contains("HostName 10.0.0.1"));
+        assert!(second.contains("Host db"));
+        assert!(second.ends_with("# <<< zync managed hosts <<<\n\nHost mine\n  User me\n"));
+        assert_eq!(second.matches("# >>> zync managed hosts >>>").count(), 1);
+
+        let parsed = parse_config_text(&second).expect("should parse");
+        let names: Vec<&str> = parsed.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["web", "db", "mine"]);
+    }
+
+    #[test]
+    fn with_include_adds_the_line_once() {
+        let config = "Host a\n  HostName 10.0.0.1\n";
+        let updated = with_include(config, "zync.config").expect("should add include");
+        assert!(updated.starts_with("Include zync.config\n\nHost a"));
+        assert_eq!(with_include(&updated, "zync.config"), None);
+        let quoted = "include \"~/.ssh/zync.config\"\n";
+        assert_eq!(with_include(quoted, "zync.config"), None);
+    }
 }
@@ -6,7 +6,7 @@ use std::collections::HashSet;
 #[serde(rename_all = "camelCase")]
 pub struct ParsedTunnel {
     #[serde(rename = "type")]
-    pub tunnel_type: String, // "local", "remote", or "dynamic"
+    pub tunnel_type: String, // "local", "remote", "dynamic" or "remote-dynamic"
     pub local_port: u16,
     pub remote_host: String,
     pub remote_port: u16,
@@ -47,6 +47,12 @@ pub fn parse_ssh_command(command: &str) -> ParseResult {
         Regex::new(&format!(r"-R\s+{BIND_PREFIX}(\d+):([^:\s]+):(\d+)"))
             .unwrap();
 
+    // Regex for -R without a destination (remote dynamic / reverse SOCKS)
+    // Matches: -R [bind_address:]remote_port
+    let remote_dynamic_re =
+        Regex::new(&format!(r"-R\s+{BIND_PREFIX}(\d+)(?:\s|$)"))
+            .unwrap();
+
     // Regex for -D (Dynamic / SOCKS forwarding)
     // Matches: -D [bind_address:]local_port
     let dynamic_re =
@@ -120,6 +126,26 @@ pub fn parse_ssh_command(command: &str) -> ParseResult {
         }
     }
 
+    // Extract Remote Dynamic (reverse SOCKS) Tunnels
+    for cap in remote_dynamic_re.captures_iter(&cleaned) {
+        if let Some(remote_port_str) = cap.get(1) {
+            if let Ok(remote_port) = remote_port_str.as_str().parse::<u16>() {
+                tunnels.push(ParsedTunnel {
+                    tunnel_type: "remote-dynamic".to_string(),
+                    local_port: 0,
+                    remote_host: "*".to_string(),
+                    remote_port,
+                    name: Some(format!("Reverse SOCKS {remote_port}")),
+                });
+            } else {
+                errors.push(format!(
+                    "Invalid port number in -R flag: {}",
+                    remote_port_str.as_str()
+                ));
+            }
+        }
+    }
+
     // Extract Dynamic (SOCKS) Tunnels
     for cap in dynamic_re.captures_iter(&cleaned) {
         if let Some(local_port_str) = cap.get(1) {
@@ -147,12 +173,13 @@ pub fn parse_ssh_command(command: &str) -> ParseResult {
     // Check for duplicate ports
     let mut seen_ports = HashSet::new();
     for tunnel in &tunnels {
-        let port = if tunnel.tunnel_type == "remote" {
-            tunnel.remote_port
+        // Both kinds of -R listen on the server, so they share one port space.
+        let (side, port) = if tunnel.tunnel_type.starts_with("remote") {
+            ("remote", tunnel.remote_port)
         } else {
-            tunnel.local_port
+            (tunnel.tunnel_type.as_str(), tunnel.local_port)
         };
-        let key = format!("{}:{}", tunnel.tunnel_type, port);
+        let key = format!("{}:{}", side, port);
         if seen_ports.contains(&key) {
             errors.push(format!(
                 "Duplicate {} port: {}",
@@ -201,6 +228,24 @@ mod tests {
         assert_eq!(result.tunnels[0].local_port, 1080);
     }
 
+    #[test]
+    fn parses_remote_dynamic_forward() {
+        let command = "ssh -R 1080 -R 127.0.0.1:1081 -R 9000:localhost:3000 user@host";
+        let result = parse_ssh_command(command);
+        assert!(result.success);
+        let reverse: Vec<u16> = result
+            .tunnels
+            .iter()
+            .filter(|t| t.tunnel_type == "remote-dynamic")
+            .map(|t| t.remote_port)
+            .collect();
+        assert_eq!(reverse, [1080, 1081]);
+        assert_eq!(result.tunnels.len(), 3);
+
+        let duplicate = parse_ssh_command("ssh -R 1080 -R 1080:localhost:80 user@host");
+        assert!(!duplicate.success);
+    }
+
     #[test]
     fn parses_dynamic_forward_with_ipv4_bind() {
         let result = parse_ssh_command("ssh -D 0.0.0.0:1080 user@host");
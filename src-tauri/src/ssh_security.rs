@@ -0,0 +1,393 @@
+//! What crypto an SSH connection actually runs on.
+//!
+//! russh does not expose the negotiated algorithms, so they are derived the way
+//! the protocol defines them: the first entry of the client's preference list
+//! that the server also offers (RFC 4253 §7.1). The server's lists come from a
+//! short pre-auth probe that reads its KEXINIT; the client's are russh's
+//! defaults, which is what `SshManager::connect` uses. The host key itself is
+//! recorded from `check_server_key` during the real handshake.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const SSH_MSG_KEXINIT: u8 = 20;
+const MAX_BANNER_LINES: usize = 64;
+const MAX_PACKET: usize = 256 * 1024;
+
+static HOST_KEYS: LazyLock<Mutex<HashMap<String, HostKeyInfo>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyInfo {
+    pub key_type: String,
+    /// `SHA256:<base64>`, as `ssh-keygen -l` prints it.
+    pub fingerprint: String,
+}
+
+/// Called from the client handler for every server key it accepts.
+pub fn record_host_key(connection_id: &str, key: HostKeyInfo) {
+    if let Ok(mut keys) = HOST_KEYS.lock() {
+        keys.insert(connection_id.to_string(), key);
+    }
+}
+
+pub fn host_key(connection_id: &str) -> Option<HostKeyInfo> {
+    HOST_KEYS.lock().ok()?.get(connection_id).cloned()
+}
+
+/// The ten name-lists of a KEXINIT, in wire order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KexInit {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher_client_to_server: Vec<String>,
+    pub cipher_server_to_client: Vec<String>,
+    pub mac_client_to_server: Vec<String>,
+    pub mac_server_to_client: Vec<String>,
+    pub compression_client_to_server: Vec<String>,
+    pub compression_server_to_client: Vec<String>,
+}
+
+/// Algorithm preferences on our side, most preferred first.
+#[derive(Debug, Clone, Default)]
+pub struct ClientPreferences {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher: Vec<String>,
+    pub mac: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+impl ClientPreferences {
+    /// russh's defaults, which `SshManager::connect` does not override.
+    pub fn russh_defaults() -> Self {
+        fn names<N: AsRef<str>>(list: &[N]) -> Vec<String> {
+            list.iter().map(|name| name.as_ref().to_string()).collect()
+        }
+        let preferred = russh::Preferred::default();
+        Self {
+            kex: names(&preferred.kex),
+            host_key: names(&preferred.key),
+            cipher: names(&preferred.cipher),
+            mac: names(&preferred.mac),
+            compression: names(&preferred.compression),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSecurityInfo {
+    pub connection_id: String,
+    pub server_version: Option<String>,
+    pub kex: Option<String>,
+    pub host_key_algorithm: Option<String>,
+    pub host_key: Option<HostKeyInfo>,
+    pub cipher_client_to_server: Option<String>,
+    pub cipher_server_to_client: Option<String>,
+    /// `None` with an AEAD cipher, which authenticates without a separate MAC.
+    pub mac_client_to_server: Option<String>,
+    pub mac_server_to_client: Option<String>,
+    pub compression: Option<String>,
+    pub post_quantum: bool,
+    /// The server supports a post-quantum key exchange, even if it was not picked.
+    pub server_offers_post_quantum: bool,
+    /// Negotiated algorithms considered weak (SHA-1, CBC, RC4, 3DES, ...).
+    pub legacy: Vec<String>,
+    /// Why algorithm details are missing, when they are.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl ConnectionSecurityInfo {
+    pub fn unavailable(connection_id: &str, note: String) -> Self {
+        Self {
+            connection_id: connection_id.to_string(),
+            server_version: None,
+            kex: None,
+            host_key_algorithm: None,
+            host_key: host_key(connection_id),
+            cipher_client_to_server: None,
+            cipher_server_to_client: None,
+            mac_client_to_server: None,
+            mac_server_to_client: None,
+            compression: None,
+            post_quantum: false,
+            server_offers_post_quantum: false,
+            legacy: Vec::new(),
+            note: Some(note),
+        }
+    }
+}
+
+/// RFC 4253 §7.1: the first client algorithm the server also supports.
+pub fn negotiate(client: &[String], server: &[String]) -> Option<String> {
+    client.iter().find(|name| server.contains(name)).cloned()
+}
+
+fn is_aead(cipher: &str) -> bool {
+    cipher.contains("gcm") || cipher.contains("poly1305")
+}
+
+fn is_post_quantum_kex(kex: &str) -> bool {
+    kex.contains("sntrup") || kex.contains("mlkem") || kex.contains("kyber")
+}
+
+fn is_legacy(name: &str) -> bool {
+    matches!(
+        name,
+        "diffie-hellman-group1-sha1"
+            | "diffie-hellman-group14-sha1"
+            | "diffie-hellman-group-exchange-sha1"
+            | "ssh-rsa"
+            | "ssh-dss"
+            | "3des-cbc"
+            | "hmac-sha1"
+            | "hmac-sha1-96"
+            | "hmac-md5"
+            | "hmac-md5-96"
+    ) || name.ends_with("-cbc")
+        || name.starts_with("arcfour")
+}
+
+pub fn summarize(
+    connection_id: &str,
+    server_version: Option<String>,
+    client: &ClientPreferences,
+    server: &KexInit,
+) -> ConnectionSecurityInfo {
+    let kex = negotiate(&client.kex, &server.kex);
+    let host_key_algorithm = negotiate(&client.host_key, &server.host_key);
+    let cipher_client_to_server = negotiate(&client.cipher, &server.cipher_client_to_server);
+    let cipher_server_to_client = negotiate(&client.cipher, &server.cipher_server_to_client);
+    let mac_for = |cipher: &Option<String>, server_macs: &[String]| match cipher {
+        Some(cipher) if is_aead(cipher) => None,
+        _ => negotiate(&client.mac, server_macs),
+    };
+    let mac_client_to_server = mac_for(&cipher_client_to_server, &server.mac_client_to_server);
+    let mac_server_to_client = mac_for(&cipher_server_to_client, &server.mac_server_to_client);
+    let compression = negotiate(&client.compression, &server.compression_client_to_server);
+
+    let mut legacy: Vec<String> = [
+        &kex,
+        &host_key_algorithm,
+        &cipher_client_to_server,
+        &cipher_server_to_client,
+        &mac_client_to_server,
+        &mac_server_to_client,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|name| is_legacy(name))
+    .cloned()
+    .collect();
+    legacy.dedup();
+
+    ConnectionSecurityInfo {
+        connection_id: connection_id.to_string(),
+        server_version,
+        post_quantum: kex.as_deref().is_some_and(is_post_quantum_kex),
+        server_offers_post_quantum: server.kex.iter().any(|kex| is_post_quantum_kex(kex)),
+        kex,
+        host_key_algorithm,
+        host_key: host_key(connection_id),
+        cipher_client_to_server,
+        cipher_server_to_client,
+        mac_client_to_server,
+        mac_server_to_client,
+        compression,
+        legacy,
+        note: None,
+    }
+}
+
+/// Parses an SSH_MSG_KEXINIT payload (message byte first).
+pub fn parse_kexinit(payload: &[u8]) -> Result<KexInit> {
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        return Err(anyhow!("Server did not start key exchange"));
+    }
+    // Message byte + 16-byte cookie.
+    let mut cursor = payload
+        .get(17..)
+        .ok_or_else(|| anyhow!("Truncated KEXINIT"))?;
+    let mut lists = Vec::with_capacity(10);
+    for _ in 0..10 {
+        if cursor.len() < 4 {
+            return Err(anyhow!("Truncated KEXINIT"));
+        }
+        let len = u32::from_be_bytes([cursor[0], cursor[1], cursor[2], cursor[3]]) as usize;
+        let list = cursor
+            .get(4..4 + len)
+            .ok_or_else(|| anyhow!("Truncated KEXINIT"))?;
+        lists.push(
+            String::from_utf8_lossy(list)
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+        );
+        cursor = &cursor[4 + len..];
+    }
+    let mut lists = lists.into_iter();
+    let mut next = || lists.next().unwrap_or_default();
+    Ok(KexInit {
+        kex: next(),
+        host_key: next(),
+        cipher_client_to_server: next(),
+        cipher_server_to_client: next(),
+        mac_client_to_server: next(),
+        mac_server_to_client: next(),
+        compression_client_to_server: next(),
+        compression_server_to_client: next(),
+    })
+}
+
+/// Exchanges version banners and reads the server's KEXINIT, then stops. The
+/// server logs this as a pre-auth disconnect.
+pub async fn probe_kexinit<S>(stream: &mut S) -> Result<(String, KexInit)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(b"SSH-2.0-zync_probe\r\n").await?;
+    stream.flush().await?;
+
+    // Servers may send other lines before the version line.
+    let mut version = None;
+    for _ in 0..MAX_BANNER_LINES {
+        let mut line = Vec::new();
+        loop {
+            let byte = stream.read_u8().await?;
+            if byte == b'\n' {
+                break;
+            }
+            if line.len() >= 255 {
+                return Err(anyhow!("Server banner line too long"));
+            }
+            line.push(byte);
+        }
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        if line.starts_with("SSH-") {
+            version = Some(line);
+            break;
+        }
+    }
+    let version = version.ok_or_else(|| anyhow!("Server sent no SSH version line"))?;
+
+    let packet_len = stream.read_u32().await? as usize;
+    if !(5..=MAX_PACKET).contains(&packet_len) {
+        return Err(anyhow!("Unexpected packet length {}", packet_len));
+    }
+    let mut packet = vec![0u8; packet_len];
+    stream.read_exact(&mut packet).await?;
+    let padding = packet[0] as usize;
+    let payload = packet
+        .get(1..packet_len.saturating_sub(padding))
+        .ok_or_else(|| anyhow!("Malformed packet padding"))?;
+    Ok((version, parse_kexinit(payload)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(names: &str) -> Vec<String> {
+        names.split(',').map(str::to_string).collect()
+    }
+
+    fn kexinit_packet(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![SSH_MSG_KEXINIT];
+        payload.extend_from_slice(&[7u8; 16]);
+        for names in lists {
+            payload.extend_from_slice(&(names.len() as u32).to_be_bytes());
+            payload.extend_from_slice(names.as_bytes());
+        }
+        payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+        let padding = 4;
+        let mut packet = ((payload.len() + padding + 1) as u32)
+            .to_be_bytes()
+            .to_vec();
+        packet.push(padding as u8);
+        packet.extend_from_slice(&payload);
+        packet.extend_from_slice(&[0u8; 4]);
+        packet
+    }
+
+    #[test]
+    fn negotiates_first_client_choice_the_server_supports() {
+        let client = ClientPreferences {
+            kex: list("curve25519-sha256,diffie-hellman-group14-sha256,ext-info-c"),
+            host_key: list("ssh-ed25519,rsa-sha2-256,ssh-rsa"),
+            cipher: list("chacha20-poly1305@openssh.com,aes256-ctr,aes128-cbc"),
+            mac: list("hmac-sha2-256,hmac-sha1"),
+            compression: list("none,zlib@openssh.com"),
+        };
+        let modern = KexInit {
+            kex: list("sntrup761x25519-sha512@openssh.com,curve25519-sha256"),
+            host_key: list("rsa-sha2-512,ssh-ed25519"),
+            cipher_client_to_server: list("aes256-ctr,chacha20-poly1305@openssh.com"),
+            cipher_server_to_client: list("aes256-ctr"),
+            mac_client_to_server: list("hmac-sha2-256"),
+            mac_server_to_client: list("hmac-sha2-256"),
+            compression_client_to_server: list("none"),
+            compression_server_to_client: list("none"),
+        };
+        let info = summarize("c1", None, &client, &modern);
+        assert_eq!(info.kex.as_deref(), Some("curve25519-sha256"));
+        assert_eq!(info.host_key_algorithm.as_deref(), Some("ssh-ed25519"));
+        assert_eq!(
+            info.cipher_client_to_server.as_deref(),
+            Some("chacha20-poly1305@openssh.com")
+        );
+        assert_eq!(info.mac_client_to_server, None);
+        assert_eq!(info.mac_server_to_client.as_deref(), Some("hmac-sha2-256"));
+        assert!(!info.post_quantum && info.server_offers_post_quantum);
+        assert!(info.legacy.is_empty());
+
+        let old = KexInit {
+            kex: list("diffie-hellman-group14-sha256"),
+            host_key: list("ssh-rsa"),
+            cipher_client_to_server: list("aes128-cbc"),
+            cipher_server_to_client: list("aes128-cbc"),
+            mac_client_to_server: list("hmac-sha1"),
+            mac_server_to_client: list("hmac-sha1"),
+            ..modern
+        };
+        let info = summarize("c1", None, &client, &old);
+        assert_eq!(info.legacy, list("ssh-rsa,aes128-cbc,hmac-sha1"));
+    }
+
+    #[tokio::test]
+    async fn probe_reads_banner_and_kexinit() {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        let packet = kexinit_packet([
+            "curve25519-sha256",
+            "ssh-ed25519",
+            "aes128-ctr",
+            "aes128-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256",
+            "none",
+            "none",
+            "",
+            "",
+        ]);
+        let server_task = tokio::spawn(async move {
+            server
+                .write_all(b"Authorized use only\r\nSSH-2.0-OpenSSH_9.6\r\n")
+                .await
+                .unwrap();
+            server.write_all(&packet).await.unwrap();
+            let mut banner = [0u8; 20];
+            server.read_exact(&mut banner).await.unwrap();
+            banner
+        });
+        let (version, kexinit) = probe_kexinit(&mut client).await.unwrap();
+        assert_eq!(version, "SSH-2.0-OpenSSH_9.6");
+        assert_eq!(kexinit.kex, list("curve25519-sha256"));
+        assert_eq!(kexinit.compression_server_to_client, list("none"));
+        assert_eq!(&server_task.await.unwrap(), b"SSH-2.0-zync_probe\r\n");
+    }
+}
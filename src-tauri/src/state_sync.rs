@@ -0,0 +1,172 @@
+//! Live connections, terminals and tunnels as one shared view for every window.
+//!
+//! The registries live in `AppState`; each change to them is broadcast to all
+//! webviews as `state:changed` carrying a revision that only grows. A window
+//! that opens later hydrates from `state_snapshot` and then applies only events
+//! with a higher revision than the snapshot's, so every window converges on the
+//! same state without keeping its own authoritative copy.
+
+use crate::commands::AppState;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter};
+
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateKind {
+    Connection,
+    Terminal,
+    Tunnel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Updated,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChange {
+    pub revision: u64,
+    pub kind: StateKind,
+    pub id: String,
+    pub change: ChangeKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionSummary {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub username: String,
+    /// False while the session is gone and the connection waits for a reconnect.
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSummary {
+    pub id: String,
+    pub connection_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelSummary {
+    /// Saved tunnel id, or the runtime id of an ad-hoc forward; the same id
+    /// `tunnel:status-change` uses.
+    pub id: String,
+    /// `active` or `degraded`.
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    /// Events with a revision at or below this one are already reflected here.
+    pub revision: u64,
+    pub connections: Vec<ConnectionSummary>,
+    pub terminals: Vec<TerminalSummary>,
+    pub tunnels: Vec<TunnelSummary>,
+}
+
+fn next_revision() -> u64 {
+    REVISION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Broadcasts a registry change to every window.
+pub fn notify(app: &AppHandle, kind: StateKind, id: &str, change: ChangeKind) {
+    let event = StateChange {
+        revision: next_revision(),
+        kind,
+        id: id.to_string(),
+        change,
+    };
+    if let Err(e) = app.emit("state:changed", event) {
+        crate::log_error!(
+            "[STATE] Failed to emit {:?} {} {:?}: {}",
+            kind,
+            id,
+            change,
+            e
+        );
+    }
+}
+
+/// Current registries. The revision is read first: a change racing the snapshot
+/// is then replayed by the window, which is harmless since events name the item
+/// rather than carrying a diff.
+pub async fn snapshot(app: &AppHandle, state: &AppState) -> StateSnapshot {
+    let revision = REVISION.load(Ordering::SeqCst);
+    let mut connections: Vec<ConnectionSummary> = {
+        let connections = state.connections.lock().await;
+        connections
+            .iter()
+            .map(|(id, handle)| ConnectionSummary {
+                id: id.clone(),
+                name: handle.config.name.clone(),
+                host: handle.config.host.clone(),
+                username: handle.config.username.clone(),
+                connected: handle.session.is_some(),
+            })
+            .collect()
+    };
+    connections.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut terminals: Vec<TerminalSummary> = state
+        .pty_manager
+        .terminals()
+        .await
+        .into_iter()
+        .map(|(id, connection_id)| TerminalSummary { id, connection_id })
+        .collect();
+    terminals.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut tunnels = crate::tunnels::running_tunnels(app, state).await;
+    tunnels.sort_by(|a, b| a.id.cmp(&b.id));
+
+    StateSnapshot {
+        revision,
+        connections,
+        terminals,
+        tunnels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revisions_only_grow() {
+        let first = next_revision();
+        let second = next_revision();
+        assert!(second > first);
+        assert!(REVISION.load(Ordering::SeqCst) >= second);
+    }
+
+    #[test]
+    fn change_serializes_in_camel_case() {
+        let event = StateChange {
+            revision: 3,
+            kind: StateKind::Terminal,
+            id: "term-1".to_string(),
+            change: ChangeKind::Removed,
+        };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({
+                "revision": 3,
+                "kind": "terminal",
+                "id": "term-1",
+                "change": "removed"
+            })
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Moving the data directory to a user-chosen location.
+//!
+//! Every file under the current data directory (stores, `keys/`, session logs,
+//! recordings, audit log, vault) is copied to the target and verified by size
+//! and SHA-256 before any setting points at it. The source is never modified, so
+//! an interrupted migration cannot lose data; on failure the files copied into
+//! the target are removed again.
+
+use crate::hashing::{hash_reader, HashAlgorithm};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Open stores keep writing to the old location until the app restarts.
+    pub restart_required: bool,
+}
+
+/// Files copied into the target, kept so a later failure can undo the copy.
+pub struct CopiedTree {
+    target: PathBuf,
+    created_root: bool,
+    files: Vec<PathBuf>,
+    bytes: u64,
+}
+
+impl CopiedTree {
+    pub fn report(&self, source: &Path) -> MigrationReport {
+        MigrationReport {
+            from: source.to_string_lossy().to_string(),
+            to: self.target.to_string_lossy().to_string(),
+            files: self.files.len(),
+            bytes: self.bytes,
+            restart_required: true,
+        }
+    }
+
+    /// Removes everything the copy created in the target.
+    pub fn rollback(self) {
+        if self.created_root {
+            if let Err(e) = fs::remove_dir_all(&self.target) {
+                crate::log_error!(
+                    "[DataDir] Rollback could not remove {:?}: {}",
+                    self.target,
+                    e
+                );
+            }
+            return;
+        }
+        for file in &self.files {
+            let _ = fs::remove_file(self.target.join(file));
+        }
+        // Deepest directories first; non-empty ones (not ours) are left alone.
+        let mut dirs: Vec<PathBuf> = self
+            .files
+            .iter()
+            .flat_map(|file| file.ancestors().skip(1))
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        for dir in dirs {
+            let _ = fs::remove_dir(self.target.join(dir));
+        }
+    }
+}
+
+/// Resolves `path` through its nearest existing ancestor, so targets that do
+/// not exist yet still compare correctly against the source.
+fn normalize(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |acc, part| acc.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// The target must be an absolute path outside the source (and vice versa) that
+/// is either missing or an empty directory.
+pub fn check_target(source: &Path, target: &Path) -> Result<(), String> {
+    if !target.is_absolute() {
+        return Err(format!("{} is not an absolute path", target.display()));
+    }
+    let (source, target_norm) = (normalize(source), normalize(target));
+    if source == target_norm {
+        return Err("The new location is the current data directory".to_string());
+    }
+    if target_norm.starts_with(&source) || source.starts_with(&target_norm) {
+        return Err(
+            "The new location cannot be inside the data directory or contain it".to_string(),
+        );
+    }
+    if target.exists() {
+        if !target.is_dir() {
+            return Err(format!("{} is a file, not a directory", target.display()));
+        }
+        let empty = fs::read_dir(target)
+            .map_err(|e| format!("Cannot read {}: {}", target.display(), e))?
+            .next()
+            .is_none();
+        if !empty {
+            return Err(format!("{} is not empty", target.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Leftovers of interrupted atomic writes and sync downloads are not migrated.
+fn is_temporary(name: &str) -> bool {
+    name.contains(".tmp.") || name.ends_with("-tmp")
+}
+
+/// Regular files under `root`, relative to it. Symlinks are not followed.
+fn collect_files(root: &Path, relative: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let dir = root.join(relative);
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Cannot read {}: {}", dir.display(), e))?;
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        let path = relative.join(entry.file_name());
+        if file_type.is_dir() {
+            collect_files(root, &path, out)?;
+        } else if file_type.is_file() && !is_temporary(&entry.file_name().to_string_lossy()) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn sha256(path: &Path) -> Result<String, String> {
+    let file =
+        fs::File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    hash_reader(file, HashAlgorithm::Sha256)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+}
+
+fn copy_file_verified(from: &Path, to: &Path) -> Result<u64, String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+    }
+    let copied =
+        fs::copy(from, to).map_err(|e| format!("Cannot copy {}: {}", from.display(), e))?;
+    fs::File::open(to)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Cannot flush {}: {}", to.display(), e))?;
+    let expected = fs::metadata(from).map_err(|e| e.to_string())?.len();
+    if copied != expected || sha256(from)? != sha256(to)? {
+        return Err(format!(
+            "{} changed or was corrupted while copying",
+            from.display()
+        ));
+    }
+    Ok(copied)
+}
+
+/// Copies and verifies every file of `source` into `target`. On error, whatever
+/// was already copied is rolled back.
+pub fn copy_verified(source: &Path, target: &Path) -> Result<CopiedTree, String> {
+    check_target(source, target)?;
+    let mut files = Vec::new();
+    collect_files(source, Path::new(""), &mut files)?;
+
+    let mut tree = CopiedTree {
+        target: target.to_path_buf(),
+        created_root: !target.exists(),
+        files: Vec::with_capacity(files.len()),
+        bytes: 0,
+    };
+    if let Err(e) = fs::create_dir_all(target) {
+        return Err(format!("Cannot create {}: {}", target.display(), e));
+    }
+    for file in files {
+        match copy_file_verified(&source.join(&file), &target.join(&file)) {
+            Ok(bytes) => {
+                tree.bytes += bytes;
+                tree.files.push(file);
+            }
+            Err(e) => {
+                tree.files.push(file);
+                tree.rollback();
+                return Err(e);
+            }
+        }
+    }
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch() -> PathBuf {
+        std::env::temp_dir().join(format!("zync-migrate-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn copies_tree_and_rolls_back() {
+        let root = scratch();
+        let source = root.join("data");
+        fs::create_dir_all(source.join("keys")).unwrap();
+        fs::write(source.join("connections.json"), b"{\"connections\":[]}").unwrap();
+        fs::write(source.join("keys/id_ed25519"), b"secret").unwrap();
+        fs::write(source.join("snippets.json.tmp.1234"), b"partial").unwrap();
+
+        let target = root.join("moved");
+        let tree = copy_verified(&source, &target).unwrap();
+        let report = tree.report(&source);
+        assert_eq!(report.files, 2);
+        assert_eq!(report.bytes, 24);
+        assert_eq!(fs::read(target.join("keys/id_ed25519")).unwrap(), b"secret");
+        assert!(!target.join("snippets.json.tmp.1234").exists());
+
+        tree.rollback();
+        assert!(!target.exists());
+        assert!(source.join("keys/id_ed25519").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rejects_nested_or_non_empty_targets() {
+        let root = scratch();
+        let source = root.join("data");
+        fs::create_dir_all(&source).unwrap();
+        let busy = root.join("busy");
+        fs::create_dir_all(&busy).unwrap();
+        fs::write(busy.join("file"), b"x").unwrap();
+
+        assert!(check_target(&source, &source).is_err());
+        assert!(check_target(&source, &source.join("inner/new")).is_err());
+        assert!(check_target(&source, &root).is_err());
+        assert!(check_target(&source, &busy).is_err());
+        assert!(check_target(&source, Path::new("relative/dir")).is_err());
+        assert!(check_target(&source, &root.join("fresh/dir")).is_ok());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -48,8 +48,9 @@ fn sync_local_error(code: &'static str, message: impl Into<String>) -> String {
 }
 
 fn parse_provider(provider: &str) -> Result<SyncProviderKind, String> {
-    SyncProviderKind::parse(provider)
-        .ok_or_else(|| format!("[unknown_provider] Unknown provider: {provider}"))
+    SyncProviderKind::parse(provider).ok_or_else(|| {
+        crate::redact::error(format!("[unknown_provider] Unknown provider: {provider}"))
+    })
 }
 
 fn provider_for(kind: SyncProviderKind) -> SyncResult<Box<dyn VaultProviderV1>> {
@@ -220,10 +221,10 @@ fn ensure_domain_enabled_for_provider(
     if is_domain_enabled(&profile, domain) {
         Ok(())
     } else {
-        Err(format!(
+        Err(crate::redact::error(format!(
             "[sync_domain_disabled] {} sync is disabled for this provider profile.",
             domain.as_str()
-        ))
+        )))
     }
 }
 
@@ -367,9 +368,9 @@ fn resolve_discovered_sync_collection_id(
     match collection_ids.len() {
         0 => Ok(None),
         1 => Ok(collection_ids.into_iter().next()),
-        count => Err(format!(
+        count => Err(crate::redact::error(format!(
             "[sync_collection_ambiguous_remote] Found {count} existing encrypted sync collections in {provider_label}. Choose which backup to link on this device."
-        )),
+        ))),
     }
 }
 
@@ -729,16 +730,24 @@ async fn upload_domain_record<T: serde::Serialize>(
     payload: &T,
     meta: DomainUploadMeta<'_>,
 ) -> Result<u64, String> {
-    let plaintext_bytes = serde_json::to_vec(payload)
-        .map_err(|e| format!("[sync_serialize_failed] Failed to serialize {} record: {e}", meta.domain))?;
+    let plaintext_bytes = serde_json::to_vec(payload).map_err(|e| {
+        crate::redact::error(format!(
+            "[sync_serialize_failed] Failed to serialize {} record: {e}",
+            meta.domain
+        ))
+    })?;
     let aad = domain_aad(
         &manifest.sync_collection_id,
         meta.domain,
         meta.logical_id,
         meta.revision,
     );
-    let envelope = encrypt_record(secret_key, &plaintext_bytes, aad.as_bytes())
-        .map_err(|e| format!("[sync_encrypt_failed] Failed to encrypt {} record: {e}", meta.domain))?;
+    let envelope = encrypt_record(secret_key, &plaintext_bytes, aad.as_bytes()).map_err(|e| {
+        crate::redact::error(format!(
+            "[sync_encrypt_failed] Failed to encrypt {} record: {e}",
+            meta.domain
+        ))
+    })?;
     let encrypted = SyncHostsEncryptedV1 {
         version: 1,
         domain: meta.domain.to_string(),
@@ -1052,7 +1061,9 @@ async fn collect_remote_host_records(
 fn decode_sync_envelope(record: &SyncCredentialEncryptedV1) -> Result<EncryptedEnvelope, String> {
     let nonce_bytes = base64::engine::general_purpose::STANDARD
         .decode(&record.nonce)
-        .map_err(|e| format!("[sync_decode_failed] Invalid nonce encoding: {e}"))?;
+        .map_err(|e| {
+            crate::redact::error(format!("[sync_decode_failed] Invalid nonce encoding: {e}"))
+        })?;
     let nonce: [u8; 24] = nonce_bytes
         .as_slice()
         .try_into()
@@ -1060,7 +1071,11 @@ fn decode_sync_envelope(record: &SyncCredentialEncryptedV1) -> Result<EncryptedE
 
     let ciphertext = base64::engine::general_purpose::STANDARD
         .decode(&record.ciphertext)
-        .map_err(|e| format!("[sync_decode_failed] Invalid ciphertext encoding: {e}"))?;
+        .map_err(|e| {
+            crate::redact::error(format!(
+                "[sync_decode_failed] Invalid ciphertext encoding: {e}"
+            ))
+        })?;
 
     Ok(EncryptedEnvelope { nonce, ciphertext })
 }
@@ -1068,10 +1083,16 @@ fn decode_sync_envelope(record: &SyncCredentialEncryptedV1) -> Result<EncryptedE
 fn decode_sync_hosts_envelope(record: &SyncHostsEncryptedV1) -> Result<EncryptedEnvelope, String> {
     let nonce_bytes = base64::engine::general_purpose::STANDARD
         .decode(&record.nonce)
-        .map_err(|e| format!("[sync_decode_failed] Invalid nonce encoding: {e}"))?;
+        .map_err(|e| {
+            crate::redact::error(format!("[sync_decode_failed] Invalid nonce encoding: {e}"))
+        })?;
     let ciphertext = base64::engine::general_purpose::STANDARD
         .decode(&record.ciphertext)
-        .map_err(|e| format!("[sync_decode_failed] Invalid ciphertext encoding: {e}"))?;
+        .map_err(|e| {
+            crate::redact::error(format!(
+                "[sync_decode_failed] Invalid ciphertext encoding: {e}"
+            ))
+        })?;
 
     let nonce: [u8; 24] = nonce_bytes
         .try_into()
@@ -1168,14 +1189,17 @@ fn parse_remote_sync_record(
     expected_collection_id: &str,
     secret_key: &SecretKey,
 ) -> Result<(String, SyncCredentialPlaintextV1), String> {
-    let encrypted = serde_json::from_slice::<SyncCredentialEncryptedV1>(payload)
-        .map_err(|e| format!("[sync_parse_failed] Failed to parse encrypted record: {e}"))?;
+    let encrypted = serde_json::from_slice::<SyncCredentialEncryptedV1>(payload).map_err(|e| {
+        crate::redact::error(format!(
+            "[sync_parse_failed] Failed to parse encrypted record: {e}"
+        ))
+    })?;
 
     if encrypted.version != 1 {
-        return Err(format!(
+        return Err(crate::redact::error(format!(
             "[sync_schema_unsupported] Unsupported provider record version: {}",
             encrypted.version
-        ));
+        )));
     }
     if encrypted.sync_collection_id != expected_collection_id {
         return Err("[sync_collection_mismatch] Provider record belongs to a different sync collection"
@@ -1189,10 +1213,18 @@ fn parse_remote_sync_record(
 
     let envelope = decode_sync_envelope(&encrypted)?;
     let expected_aad = credential_aad(expected_collection_id, &logical_id, encrypted.revision);
-    let plaintext_bytes = decrypt_record(secret_key, &envelope, expected_aad.as_bytes())
-        .map_err(|e| format!("[sync_decrypt_failed] Failed to decrypt provider record: {e}"))?;
+    let plaintext_bytes =
+        decrypt_record(secret_key, &envelope, expected_aad.as_bytes()).map_err(|e| {
+            crate::redact::error(format!(
+                "[sync_decrypt_failed] Failed to decrypt provider record: {e}"
+            ))
+        })?;
     let mut plaintext = serde_json::from_slice::<SyncCredentialPlaintextV1>(&plaintext_bytes)
-        .map_err(|e| format!("[sync_parse_failed] Failed to parse decrypted payload: {e}"))?;
+        .map_err(|e| {
+            crate::redact::error(format!(
+                "[sync_parse_failed] Failed to parse decrypted payload: {e}"
+            ))
+        })?;
     plaintext.normalize();
     if plaintext.logical_id.trim().is_empty() {
         plaintext.logical_id = logical_id.clone();
@@ -1223,8 +1255,11 @@ fn build_credential_provider_record(
         deleted: false,
     };
 
-    let plaintext_bytes = serde_json::to_vec(&plaintext)
-        .map_err(|e| format!("[sync_serialize_failed] Failed to serialize credential: {e}"))?;
+    let plaintext_bytes = serde_json::to_vec(&plaintext).map_err(|e| {
+        crate::redact::error(format!(
+            "[sync_serialize_failed] Failed to serialize credential: {e}"
+        ))
+    })?;
     let aad = credential_aad(&manifest.sync_collection_id, &logical_id, record.revision);
     let envelope = encrypt_record(secret_key, &plaintext_bytes, aad.as_bytes()).map_err(|e| {
         format!("[sync_encrypt_failed] Failed to encrypt credential for provider sync: {e}")
@@ -1241,8 +1276,11 @@ fn build_credential_provider_record(
         nonce: base64::engine::general_purpose::STANDARD.encode(envelope.nonce),
         ciphertext: base64::engine::general_purpose::STANDARD.encode(envelope.ciphertext),
     };
-    let payload = serde_json::to_vec(&encrypted)
-        .map_err(|e| format!("[sync_serialize_failed] Failed to serialize encrypted record: {e}"))?;
+    let payload = serde_json::to_vec(&encrypted).map_err(|e| {
+        crate::redact::error(format!(
+            "[sync_serialize_failed] Failed to serialize encrypted record: {e}"
+        ))
+    })?;
     let object_name = credential_object_name(&manifest.sync_collection_id, &logical_id);
 
     Ok((logical_id, ProviderUploadRecord { object_name, payload }))
@@ -1497,12 +1535,12 @@ async fn ensure_unlocked_vault_for_credential_restore(
         .map_err(|e| sync_local_error("vault_status_failed", e.to_string()))?
     {
         VaultStatus::Unlocked { .. } => Ok(()),
-        VaultStatus::Locked { .. } => Err(format!(
+        VaultStatus::Locked { .. } => Err(crate::redact::error(format!(
             "[vault_locked] Unlock the local vault before restoring {context}."
-        )),
-        VaultStatus::Uninitialized => Err(format!(
+        ))),
+        VaultStatus::Uninitialized => Err(crate::redact::error(format!(
             "[vault_uninitialized] Initialize the local vault before restoring {context}."
-        )),
+        ))),
     }
 }
 
@@ -1548,9 +1586,11 @@ async fn restore_credentials_from_provider_records(
             Ok(bytes) => bytes,
             Err(error) => {
                 stats.failed = stats.failed.saturating_add(1);
-                eprintln!(
+                crate::log_error!(
                     "[sync] Failed to read provider object '{}': [{}] {}",
-                    object.object_name, error.code, error.message
+                    object.object_name,
+                    error.code,
+                    error.message
                 );
                 continue;
             }
@@ -1567,7 +1607,7 @@ async fn restore_credentials_from_provider_records(
                     stats.skipped = stats.skipped.saturating_add(1);
                 } else {
                     stats.failed = stats.failed.saturating_add(1);
-                    eprintln!(
+                    crate::log_error!(
                         "[sync] Failed to parse provider object '{}': {}",
                         object.object_name, error
                     );
@@ -1602,11 +1642,15 @@ async fn restore_credentials_from_provider_records(
                                 plaintext.updated_at,
                             )
                             .map(|_| decision)
-                            .map_err(|e| format!("[vault_update_failed] {e}")),
+                            .map_err(|e| {
+                                crate::redact::error(format!("[vault_update_failed] {e}"))
+                            }),
                         RestoreDecision::ApplyDelete => svc
                             .item_delete(&existing.id)
                             .map(|_| decision)
-                            .map_err(|e| format!("[vault_delete_failed] {e}")),
+                            .map_err(|e| {
+                                crate::redact::error(format!("[vault_delete_failed] {e}"))
+                            }),
                         RestoreDecision::Conflict
                             if resolve_conflict_logical_ids.contains(&logical_id) =>
                         {
@@ -1622,7 +1666,7 @@ async fn restore_credentials_from_provider_records(
                                 plaintext.updated_at,
                             )
                             .map(|_| RestoreDecision::UpdateExisting)
-                            .map_err(|e| format!("[vault_update_failed] {e}"))
+                            .map_err(|e| crate::redact::error(format!("[vault_update_failed] {e}")))
                         }
                         _ => Ok::<_, String>(decision),
                     }
@@ -1642,11 +1686,15 @@ async fn restore_credentials_from_provider_records(
                                 plaintext.updated_at,
                             )
                             .map(|_| decision)
-                            .map_err(|e| format!("[vault_create_failed] {e}")),
+                            .map_err(|e| {
+                                crate::redact::error(format!("[vault_create_failed] {e}"))
+                            }),
                         _ => Ok::<_, String>(decision),
                     }
                 }
-                Err(error) => Err(format!("[vault_lookup_failed] {error}")),
+                Err(error) => Err(crate::redact::error(format!(
+                    "[vault_lookup_failed] {error}"
+                ))),
             }
         };
 
@@ -1665,16 +1713,17 @@ async fn restore_credentials_from_provider_records(
             }
             Ok(RestoreDecision::Conflict) => {
                 stats.conflicts = stats.conflicts.saturating_add(1);
-                eprintln!(
+                crate::log_error!(
                     "[sync] Conflict detected for '{}' (same revision/timestamp with divergent payload)",
                     logical_id
                 );
             }
             Err(error) => {
                 stats.failed = stats.failed.saturating_add(1);
-                eprintln!(
+                crate::log_error!(
                     "[sync] Failed applying provider object '{}': {}",
-                    object.object_name, error
+                    object.object_name,
+                    error
                 );
             }
         }
@@ -1845,12 +1894,23 @@ pub async fn sync_hosts_upload(
 
     let mut upload_records = Vec::with_capacity(changes.records.len());
     for record in &changes.records {
-        let revision = if record.updated_at == 0 { 1 } else { record.updated_at };
-        let plaintext_bytes = serde_json::to_vec(record)
-            .map_err(|e| format!("[sync_serialize_failed] Failed to serialize host record: {e}"))?;
+        let revision = if record.updated_at == 0 {
+            1
+        } else {
+            record.updated_at
+        };
+        let plaintext_bytes = serde_json::to_vec(record).map_err(|e| {
+            crate::redact::error(format!(
+                "[sync_serialize_failed] Failed to serialize host record: {e}"
+            ))
+        })?;
         let aad = hosts_aad(&manifest.sync_collection_id, &record.logical_id, revision);
-        let envelope = encrypt_record(&secret_key, &plaintext_bytes, aad.as_bytes())
-            .map_err(|e| format!("[sync_encrypt_failed] Failed to encrypt host record: {e}"))?;
+        let envelope =
+            encrypt_record(&secret_key, &plaintext_bytes, aad.as_bytes()).map_err(|e| {
+                crate::redact::error(format!(
+                    "[sync_encrypt_failed] Failed to encrypt host record: {e}"
+                ))
+            })?;
 
         let encrypted = SyncHostsEncryptedV1 {
             version: 1,
@@ -2868,10 +2928,10 @@ pub async fn sync_collection_setup(
         } else {
             "Sync passphrase"
         };
-        return Err(format!(
+        return Err(crate::redact::error(format!(
             "[invalid_sync_passphrase_length] {label} must be at least {} characters.",
             SYNC_COLLECTION_PASSPHRASE_MIN_LENGTH
-        ));
+        )));
     }
     if matches!(args.key_policy_mode, SyncKeyPolicyMode::LocalPassphrase) {
         let mut svc = vault.lock().await;
@@ -2924,7 +2984,7 @@ pub async fn sync_collection_setup(
             {
                 Ok(wrap) => wrap,
                 Err(error) => {
-                    eprintln!(
+                    crate::log_error!(
                         "[sync] Failed to download collection key wrap from provider: {error}"
                     );
                     return Err(error);
@@ -2952,7 +3012,7 @@ pub async fn sync_collection_setup(
     if let Err(error) =
         upload_remote_collection_key_wrap(provider_impl.as_ref(), &app, &outcome.manifest).await
     {
-        eprintln!(
+        crate::log_error!(
             "[sync] Failed to upload collection key wrap to provider (passphrase recovery may not work after wipe): {}",
             error
         );
@@ -3038,10 +3098,10 @@ pub async fn sync_collection_unlock(
             .map(str::trim)
             .unwrap_or_default();
         if passphrase.len() < SYNC_COLLECTION_PASSPHRASE_MIN_LENGTH {
-            return Err(format!(
+            return Err(crate::redact::error(format!(
                 "[invalid_sync_passphrase_length] Sync passphrase must be at least {} characters.",
                 SYNC_COLLECTION_PASSPHRASE_MIN_LENGTH
-            ));
+            )));
         }
         unlock_collection_key_with_passphrase(&provider_data_dir, &mut manifest, passphrase)
             .map_err(|e| sync_error_to_string(&e))?;
@@ -3184,8 +3244,9 @@ pub async fn sync_domain_policy_set(
     args: SyncDomainPolicySetArgs,
 ) -> Result<SyncDomainPoliciesResult, String> {
     let kind = parse_provider(&provider)?;
-    let domain = SyncDomain::parse(&args.domain)
-        .ok_or_else(|| format!("[invalid_domain] Unknown domain: {}", args.domain))?;
+    let domain = SyncDomain::parse(&args.domain).ok_or_else(|| {
+        crate::redact::error(format!("[invalid_domain] Unknown domain: {}", args.domain))
+    })?;
     let provider_data_dir = crate::commands::get_data_dir(&app);
     let mode = args.mode.unwrap_or(SyncPolicyMode::Manual);
     let updated = upsert_profile(&provider_data_dir, kind, |existing| {
@@ -3358,7 +3419,7 @@ pub async fn sync_upload_credential(
     let record = {
         let svc = vault.lock().await;
         svc.item_get(&args.item_id)
-            .map_err(|e| format!("[vault_item_load_failed] {e}"))?
+            .map_err(|e| crate::redact::error(format!("[vault_item_load_failed] {e}")))?
     };
 
     let (logical_id, upload_record) =
@@ -3421,7 +3482,7 @@ pub async fn sync_upload_credentials(
             }
         }
         svc.item_list()
-            .map_err(|e| format!("[vault_list_failed] {e}"))?
+            .map_err(|e| crate::redact::error(format!("[vault_list_failed] {e}")))?
     };
 
     let mut upload_records = Vec::with_capacity(records.len());
@@ -3525,9 +3586,11 @@ pub async fn sync_restore_preview(
             Ok(bytes) => bytes,
             Err(error) => {
                 failed = failed.saturating_add(1);
-                eprintln!(
+                crate::log_error!(
                     "[sync] Preview failed reading provider object '{}': [{}] {}",
-                    object.object_name, error.code, error.message
+                    object.object_name,
+                    error.code,
+                    error.message
                 );
                 continue;
             }
@@ -3544,7 +3607,7 @@ pub async fn sync_restore_preview(
                     stale = stale.saturating_add(1);
                 } else {
                     failed = failed.saturating_add(1);
-                    eprintln!(
+                    crate::log_error!(
                         "[sync] Preview failed parsing provider object '{}': {}",
                         object.object_name, error
                     );
@@ -3586,9 +3649,10 @@ pub async fn sync_restore_preview(
                 }
                 Err(error) => {
                     failed = failed.saturating_add(1);
-                    eprintln!(
+                    crate::log_error!(
                         "[sync] Preview lookup failed for logical id '{}': {}",
-                        logical_id, error
+                        logical_id,
+                        error
                     );
                     continue;
                 }
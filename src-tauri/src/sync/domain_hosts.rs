@@ -165,6 +165,21 @@ pub fn apply_hosts_restore_records(
             is_favorite: Some(record.is_favorite),
             pinned_features: None,
             auth_ref: record.auth_ref.clone(),
+            resolution: None,
+            login_steps: None,
+            session_logging: None,
+            persistent_session: None,
+            mosh: None,
+            scrollback_limit_mb: None,
+            quiet_login: None,
+            identities_only: None,
+            startup_commands: None,
+            env: None,
+            terminal: None,
+            tcp: None,
+            channel_budget: None,
+            color: None,
+            fallbacks: None,
         });
         restored = restored.saturating_add(1);
     }
@@ -201,7 +216,7 @@ fn parse_saved_candidate(path: &Path) -> Option<SavedData> {
 }
 
 fn parse_saved_file(path: &Path) -> SyncResult<SavedData> {
-    let raw = std::fs::read_to_string(path).map_err(|e| {
+    let raw = crate::vault::at_rest::read_to_string(path).map_err(|e| {
         SyncError::new(
             "sync_hosts_read_failed",
             format!("Failed to read hosts file: {e}"),
@@ -222,7 +237,7 @@ fn save_saved_data_atomic(path: &Path, data: &SavedData) -> SyncResult<()> {
             format!("Failed to serialize hosts data: {e}"),
         )
     })?;
-    crate::atomic_io::durable_replace(path, json.as_bytes()).map_err(|e| {
+    crate::vault::at_rest::write(path, json.as_bytes()).map_err(|e| {
         SyncError::new(
             "sync_hosts_write_failed",
             format!("Failed to write hosts file: {e}"),
@@ -276,6 +291,21 @@ mod tests {
             is_favorite: None,
             pinned_features: None,
             auth_ref: None,
+            resolution: None,
+            login_steps: None,
+            session_logging: None,
+            persistent_session: None,
+            mosh: None,
+            scrollback_limit_mb: None,
+            quiet_login: None,
+            identities_only: None,
+            startup_commands: None,
+            env: None,
+            terminal: None,
+            tcp: None,
+            channel_budget: None,
+            color: None,
+            fallbacks: None,
         }
     }
 
@@ -145,6 +145,10 @@ pub fn apply_snippet_restore_records(data_dir: &Path, records: &[SnippetSyncReco
             category: record.category.clone(),
             tags: if record.tags.is_empty() { None } else { Some(record.tags.clone()) },
             connection_id: record.connection_id.clone(),
+            folder: None,
+            connection_ids: None,
+            os: None,
+            schedule: None,
             created_at: Some(restored_at),
             updated_at: Some(restored_at),
         });
@@ -235,6 +239,10 @@ mod tests {
                 category: None,
                 tags: None,
                 connection_id: None,
+                folder: None,
+                connection_ids: None,
+                os: None,
+                schedule: None,
                 created_at: Some(1),
                 updated_at: Some(2),
             }],
@@ -276,6 +284,10 @@ mod tests {
                 category: None,
                 tags: None,
                 connection_id: None,
+                folder: None,
+                connection_ids: None,
+                os: None,
+                schedule: None,
                 created_at: Some(1),
                 updated_at: Some(2),
             }],
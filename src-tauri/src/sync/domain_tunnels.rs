@@ -250,7 +250,7 @@ fn parse_saved_tunnels_candidate(path: &Path) -> Option<SavedTunnelsData> {
 }
 
 fn parse_saved_tunnels_file(path: &Path) -> SyncResult<SavedTunnelsData> {
-    let raw = std::fs::read_to_string(path).map_err(|e| {
+    let raw = crate::vault::at_rest::read_to_string(path).map_err(|e| {
         SyncError::new("sync_tunnels_read_failed", format!("Failed to read tunnels file: {e}"))
     })?;
     serde_json::from_str::<SavedTunnelsData>(&raw).map_err(|e| {
@@ -262,7 +262,7 @@ pub(crate) fn write_saved_tunnels_atomic(path: &Path, data: &SavedTunnelsData) -
     let json = serde_json::to_string_pretty(data).map_err(|e| {
         SyncError::new("sync_tunnels_write_failed", format!("Failed to serialize tunnels data: {e}"))
     })?;
-    crate::atomic_io::durable_replace(path, json.as_bytes()).map_err(|e| {
+    crate::vault::at_rest::write(path, json.as_bytes()).map_err(|e| {
         SyncError::new("sync_tunnels_write_failed", format!("Failed to write tunnels file: {e}"))
     })
 }
@@ -99,14 +99,16 @@ fn b64url_no_pad(bytes: &[u8]) -> String {
 }
 
 fn http_client() -> SyncResult<reqwest::Client> {
-    reqwest::Client::builder()
+    crate::kill_switch::ensure_online().map_err(|e| sync_err("network_offline", e))?;
+    crate::http_client::builder()
         .timeout(Duration::from_secs(30))
         .build()
         .map_err(|e| sync_err("http_client_init_failed", e.to_string()))
 }
 
 fn http_upload_client() -> SyncResult<reqwest::Client> {
-    reqwest::Client::builder()
+    crate::kill_switch::ensure_online().map_err(|e| sync_err("network_offline", e))?;
+    crate::http_client::builder()
         .timeout(Duration::from_secs(180))
         .build()
         .map_err(|e| sync_err("http_client_init_failed", e.to_string()))
@@ -282,7 +284,9 @@ fn delete_refresh_token(key: &str) {
     if let Ok(entry) = refresh_token_entry(key) {
         match entry.delete_credential() {
             Ok(()) | Err(keyring::Error::NoEntry) => {}
-            Err(error) => eprintln!("[sync] Failed to delete refresh token from keyring: {error}"),
+            Err(error) => {
+                crate::log_error!("[sync] Failed to delete refresh token from keyring: {error}")
+            }
         }
     }
 }
@@ -594,7 +598,7 @@ async fn existing_file_ids_for_upload(
             }
             Err(error) => {
                 #[cfg(debug_assertions)]
-                eprintln!(
+                crate::log_error!(
                     "[sync] Prefix listing failed for upload lookup; falling back to per-file lookup: {}",
                     error.message
                 );
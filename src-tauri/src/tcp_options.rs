@@ -0,0 +1,207 @@
+//! TCP socket options for direct SSH connections (`ConnectionConfig::tcp`).
+//!
+//! russh takes whatever stream it is handed, so Nagle, OS-level keepalive and
+//! the source address are decided here when the socket is opened. Without
+//! options the connection is made exactly as before, with OS defaults.
+
+use crate::types::TcpOptions;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::{TcpSocket, TcpStream};
+
+fn bind_ip(options: &TcpOptions) -> io::Result<Option<IpAddr>> {
+    let Some(address) = options
+        .bind_address
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+    else {
+        return Ok(None);
+    };
+    address.parse().map(Some).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid bind address '{}'", address),
+        )
+    })
+}
+
+fn interface(options: &TcpOptions) -> Option<&str> {
+    options
+        .bind_interface
+        .as_deref()
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+}
+
+/// The addresses a socket bound to `bind` can reach: those of the same family.
+fn reachable(addresses: &[SocketAddr], bind: Option<IpAddr>) -> Vec<SocketAddr> {
+    addresses
+        .iter()
+        .filter(|addr| bind.is_none_or(|ip| ip.is_ipv4() == addr.is_ipv4()))
+        .copied()
+        .collect()
+}
+
+/// OS keepalive settings, when `keepalive_secs` asks for them.
+fn keepalive(options: &TcpOptions) -> Option<socket2::TcpKeepalive> {
+    let idle = options.keepalive_secs.filter(|secs| *secs > 0)?;
+    let keepalive = socket2::TcpKeepalive::new().with_time(Duration::from_secs(idle));
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "windows"
+    ))]
+    let keepalive = match options.keepalive_interval_secs.filter(|secs| *secs > 0) {
+        Some(interval) => keepalive.with_interval(Duration::from_secs(interval)),
+        None => keepalive,
+    };
+    Some(keepalive)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "fuchsia"))]
+fn bind_interface(socket: &TcpSocket, interface: &str) -> io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "fuchsia")))]
+fn bind_interface(_socket: &TcpSocket, interface: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Binding to interface '{}' is only supported on Linux; set a bind address instead",
+            interface
+        ),
+    ))
+}
+
+async fn connect_one(
+    addr: SocketAddr,
+    bind: Option<IpAddr>,
+    options: &TcpOptions,
+) -> io::Result<TcpStream> {
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    if let Some(interface) = interface(options) {
+        bind_interface(&socket, interface)?;
+    }
+    if let Some(ip) = bind {
+        socket.bind(SocketAddr::new(ip, 0))?;
+    }
+    socket.connect(addr).await
+}
+
+fn apply(stream: &TcpStream, options: &TcpOptions) -> io::Result<()> {
+    if let Some(nodelay) = options.nodelay {
+        stream.set_nodelay(nodelay)?;
+    }
+    if let Some(keepalive) = keepalive(options) {
+        socket2::SockRef::from(stream).set_tcp_keepalive(&keepalive)?;
+    }
+    Ok(())
+}
+
+/// Connects to the first of `addresses` that accepts, in order, like
+/// `TcpStream::connect`, with `options` applied to the socket.
+pub async fn connect(
+    addresses: &[SocketAddr],
+    options: Option<&TcpOptions>,
+) -> io::Result<TcpStream> {
+    let Some(options) = options else {
+        return TcpStream::connect(addresses).await;
+    };
+    let bind = bind_ip(options)?;
+    let candidates = reachable(addresses, bind);
+    if candidates.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!(
+                "No resolved address has the same IP version as bind address {}",
+                bind.map(|ip| ip.to_string()).unwrap_or_default()
+            ),
+        ));
+    }
+    let mut last_error = None;
+    for addr in candidates {
+        match connect_one(addr, bind, options).await {
+            Ok(stream) => {
+                apply(&stream, options)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "No addresses to connect to")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bind_address_and_filters_families() {
+        let options = TcpOptions {
+            bind_address: Some(" 10.0.0.2 ".to_string()),
+            ..Default::default()
+        };
+        let bind = bind_ip(&options).unwrap();
+        assert_eq!(bind, Some("10.0.0.2".parse().unwrap()));
+        let addresses: Vec<SocketAddr> = vec![
+            "[2001:db8::1]:22".parse().unwrap(),
+            "192.0.2.10:22".parse().unwrap(),
+        ];
+        assert_eq!(reachable(&addresses, bind), [addresses[1]]);
+        assert_eq!(reachable(&addresses, None), addresses);
+
+        let invalid = TcpOptions {
+            bind_address: Some("eth0".to_string()),
+            ..Default::default()
+        };
+        assert!(bind_ip(&invalid).is_err());
+    }
+
+    #[test]
+    fn keepalive_only_when_requested() {
+        assert!(keepalive(&TcpOptions::default()).is_none());
+        assert!(keepalive(&TcpOptions {
+            keepalive_secs: Some(0),
+            ..Default::default()
+        })
+        .is_none());
+        assert!(keepalive(&TcpOptions {
+            keepalive_secs: Some(30),
+            keepalive_interval_secs: Some(10),
+            ..Default::default()
+        })
+        .is_some());
+    }
+
+    #[tokio::test]
+    async fn applies_options_to_the_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap();
+        let options = TcpOptions {
+            nodelay: Some(true),
+            keepalive_secs: Some(30),
+            bind_address: Some("127.0.0.1".to_string()),
+            ..Default::default()
+        };
+        let stream = connect(&[target], Some(&options)).await.unwrap();
+        assert!(stream.nodelay().unwrap());
+        assert!(socket2::SockRef::from(&stream).keepalive().unwrap());
+        let (_, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, stream.local_addr().unwrap());
+
+        let v6_only: Vec<SocketAddr> = vec!["[::1]:22".parse().unwrap()];
+        assert!(connect(&v6_only, Some(&options)).await.is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Per-terminal state keyed by `term_id`, shared with the PTY reader tasks.
+//!
+//! Recorders and session loggers hang off every chunk of terminal output, yet
+//! most terminals have neither. The registry keeps a count next to its map so
+//! those hot paths return without taking the lock while it is empty.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Entries keyed by `term_id`; cheap to clone into reader tasks.
+pub struct TerminalRegistry<T> {
+    entries: Arc<Mutex<HashMap<String, T>>>,
+    count: Arc<AtomicUsize>,
+}
+
+impl<T> Clone for TerminalRegistry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            count: self.count.clone(),
+        }
+    }
+}
+
+impl<T> Default for TerminalRegistry<T> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl<T> TerminalRegistry<T> {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, T>> {
+        // Entries stay usable after a panicking writer; the worst case is a
+        // half-written line in one file.
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn contains(&self, term_id: &str) -> bool {
+        self.count.load(Ordering::Relaxed) > 0 && self.lock().contains_key(term_id)
+    }
+
+    /// Registers `value` for `term_id`, returning the entry it replaces.
+    pub fn insert(&self, term_id: &str, value: T) -> Option<T> {
+        let previous = self.lock().insert(term_id.to_string(), value);
+        if previous.is_none() {
+            self.count.fetch_add(1, Ordering::SeqCst);
+        }
+        previous
+    }
+
+    pub fn remove(&self, term_id: &str) -> Option<T> {
+        if self.count.load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+        let removed = self.lock().remove(term_id);
+        if removed.is_some() {
+            self.count.fetch_sub(1, Ordering::SeqCst);
+        }
+        removed
+    }
+
+    /// Runs `f` on the terminal's entry, if it has one.
+    pub fn with<R>(&self, term_id: &str, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        if self.count.load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+        self.lock().get_mut(term_id).map(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_entries() {
+        let registry = TerminalRegistry::default();
+        let reader = registry.clone();
+        assert_eq!(reader.with("t1", |n: &mut u32| *n), None);
+
+        assert_eq!(registry.insert("t1", 1), None);
+        assert_eq!(registry.insert("t1", 2), Some(1));
+        assert_eq!(reader.with("t1", |n| std::mem::replace(n, 3)), Some(2));
+        assert!(reader.contains("t1"));
+
+        assert_eq!(registry.remove("t1"), Some(3));
+        assert_eq!(registry.remove("t1"), None);
+        assert!(!reader.contains("t1"));
+        assert_eq!(registry.count.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,136 @@
+//! Terminal output forwarded to plugins (`plugin_terminal_subscribe`).
+//!
+//! A plugin with the `terminal` permission can subscribe to a terminal and then
+//! receives everything it prints as `plugin-terminal:output`, one event per read
+//! with every subscribed plugin listed, for highlighters and auto-responders.
+//! Subscriptions end when the plugin unsubscribes or the terminal closes, and
+//! all of a plugin's end when it loses the permission, is disabled or is
+//! uninstalled.
+
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+pub const OUTPUT_EVENT: &str = "plugin-terminal:output";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TapOutput<'a> {
+    term_id: &'a str,
+    plugin_ids: Vec<String>,
+    /// The raw bytes decoded as UTF-8; invalid sequences become U+FFFD.
+    data: String,
+}
+
+/// Plugins subscribed to each terminal.
+#[derive(Clone, Default)]
+pub struct TerminalTaps(Arc<Mutex<HashMap<String, BTreeSet<String>>>>);
+
+impl TerminalTaps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, term_id: &str, plugin_id: &str) {
+        if let Ok(mut taps) = self.0.lock() {
+            taps.entry(term_id.to_string())
+                .or_default()
+                .insert(plugin_id.to_string());
+        }
+    }
+
+    /// Returns false when the plugin was not subscribed.
+    pub fn unsubscribe(&self, term_id: &str, plugin_id: &str) -> bool {
+        let Ok(mut taps) = self.0.lock() else {
+            return false;
+        };
+        let Some(plugins) = taps.get_mut(term_id) else {
+            return false;
+        };
+        let removed = plugins.remove(plugin_id);
+        if plugins.is_empty() {
+            taps.remove(term_id);
+        }
+        removed
+    }
+
+    /// Ends every subscription of `plugin_id`.
+    pub fn forget_plugin(&self, plugin_id: &str) {
+        if let Ok(mut taps) = self.0.lock() {
+            taps.retain(|_, plugins| {
+                plugins.remove(plugin_id);
+                !plugins.is_empty()
+            });
+        }
+    }
+
+    fn subscribers(&self, term_id: &str) -> Vec<String> {
+        self.0
+            .lock()
+            .ok()
+            .and_then(|taps| taps.get(term_id).map(|p| p.iter().cloned().collect()))
+            .unwrap_or_default()
+    }
+
+    pub fn output(&self, app: &AppHandle, term_id: &str, data: &[u8]) {
+        let plugin_ids = self.subscribers(term_id);
+        if plugin_ids.is_empty() || data.is_empty() {
+            return;
+        }
+        let payload = TapOutput {
+            term_id,
+            plugin_ids,
+            data: String::from_utf8_lossy(data).into_owned(),
+        };
+        if let Err(e) = app.emit(OUTPUT_EVENT, payload) {
+            crate::log_error!(
+                "[PTY] Failed to forward output of {} to plugins: {}",
+                term_id,
+                e
+            );
+        }
+    }
+
+    pub fn remove(&self, term_id: &str) {
+        if let Ok(mut taps) = self.0.lock() {
+            taps.remove(term_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriptions_are_per_terminal_and_plugin() {
+        let taps = TerminalTaps::new();
+        taps.subscribe("t1", "com.example.highlight");
+        taps.subscribe("t1", "com.example.responder");
+        taps.subscribe("t2", "com.example.responder");
+        assert_eq!(
+            taps.subscribers("t1"),
+            ["com.example.highlight", "com.example.responder"]
+        );
+
+        assert!(taps.unsubscribe("t1", "com.example.highlight"));
+        assert!(!taps.unsubscribe("t1", "com.example.highlight"));
+        assert_eq!(taps.subscribers("t1"), ["com.example.responder"]);
+
+        taps.remove("t2");
+        assert!(taps.subscribers("t2").is_empty());
+    }
+
+    #[test]
+    fn forgetting_a_plugin_ends_all_its_subscriptions() {
+        let taps = TerminalTaps::new();
+        taps.subscribe("t1", "com.example.highlight");
+        taps.subscribe("t1", "com.example.responder");
+        taps.subscribe("t2", "com.example.responder");
+        taps.forget_plugin("com.example.responder");
+        assert_eq!(taps.subscribers("t1"), ["com.example.highlight"]);
+        assert!(taps.subscribers("t2").is_empty());
+        assert!(!taps.0.lock().unwrap().contains_key("t2"));
+    }
+}
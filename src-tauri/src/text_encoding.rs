@@ -0,0 +1,247 @@
+//! Encoding, BOM and line endings of files opened in the built-in editor.
+//!
+//! `fs_read_file` decodes a file and reports how it was stored; the editor
+//! hands that back to `fs_write_file`, which writes the edited text the same
+//! way. A one-line change to a CRLF, Latin-1 or UTF-16 file then stays a
+//! one-line change instead of rewriting every byte of it.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextEncoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    /// Anything that is not valid UTF-8; every byte maps to one character, so
+    /// it round-trips unchanged.
+    #[serde(rename = "latin-1")]
+    Latin1,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// Both kinds in one file. The text keeps its `\r\n`s and is written as is.
+    Mixed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFormat {
+    pub encoding: TextEncoding,
+    #[serde(default)]
+    pub bom: bool,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+}
+
+/// Result of `fs_read_file`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextFile {
+    pub content: String,
+    pub format: TextFormat,
+}
+
+/// Decodes `bytes`. CRLF files come back with `\n` line endings, which
+/// [`encode`] turns back into `\r\n`.
+pub fn decode(bytes: &[u8]) -> TextFile {
+    let (encoding, bom, body) = if let Some(body) = bytes.strip_prefix(UTF8_BOM) {
+        (TextEncoding::Utf8, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16LE_BOM) {
+        (TextEncoding::Utf16Le, true, body)
+    } else if let Some(body) = bytes.strip_prefix(UTF16BE_BOM) {
+        (TextEncoding::Utf16Be, true, body)
+    } else {
+        (sniff(bytes), false, bytes)
+    };
+
+    let text = match encoding {
+        TextEncoding::Utf8 => String::from_utf8_lossy(body).into_owned(),
+        TextEncoding::Utf16Le => decode_utf16(body, u16::from_le_bytes),
+        TextEncoding::Utf16Be => decode_utf16(body, u16::from_be_bytes),
+        TextEncoding::Latin1 => body.iter().map(|&b| b as char).collect(),
+    };
+
+    let line_ending = line_ending(&text);
+    let content = if line_ending == LineEnding::Crlf {
+        text.replace("\r\n", "\n")
+    } else {
+        text
+    };
+    TextFile {
+        content,
+        format: TextFormat {
+            encoding,
+            bom,
+            line_ending,
+        },
+    }
+}
+
+/// Encodes editor text in `format`. Fails if the text has characters the
+/// encoding cannot store (non-Latin-1 characters in a Latin-1 file).
+pub fn encode(content: &str, format: &TextFormat) -> Result<Vec<u8>> {
+    let text = match format.line_ending {
+        LineEnding::Lf => content.replace("\r\n", "\n"),
+        LineEnding::Crlf => content.replace("\r\n", "\n").replace('\n', "\r\n"),
+        LineEnding::Mixed => content.to_string(),
+    };
+
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    match format.encoding {
+        TextEncoding::Utf8 => {
+            if format.bom {
+                bytes.extend_from_slice(UTF8_BOM);
+            }
+            bytes.extend_from_slice(text.as_bytes());
+        }
+        TextEncoding::Utf16Le => {
+            if format.bom {
+                bytes.extend_from_slice(UTF16LE_BOM);
+            }
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        }
+        TextEncoding::Utf16Be => {
+            if format.bom {
+                bytes.extend_from_slice(UTF16BE_BOM);
+            }
+            bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        }
+        TextEncoding::Latin1 => {
+            for c in text.chars() {
+                let byte = u8::try_from(u32::from(c))
+                    .map_err(|_| anyhow!("'{}' cannot be saved in a Latin-1 file", c))?;
+                bytes.push(byte);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Encoding of a file without a BOM. UTF-16 without a BOM is only assumed
+/// when most of one byte column is zero, as it is for mostly-ASCII text.
+fn sniff(bytes: &[u8]) -> TextEncoding {
+    if bytes.len() >= 4 && bytes.len() % 2 == 0 {
+        let pairs = bytes.len() / 2;
+        let zeros = |offset: usize| {
+            bytes
+                .iter()
+                .skip(offset)
+                .step_by(2)
+                .filter(|&&b| b == 0)
+                .count()
+        };
+        if zeros(1) * 10 >= pairs * 9 && zeros(0) == 0 {
+            return TextEncoding::Utf16Le;
+        }
+        if zeros(0) * 10 >= pairs * 9 && zeros(1) == 0 {
+            return TextEncoding::Utf16Be;
+        }
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        TextEncoding::Utf8
+    } else {
+        TextEncoding::Latin1
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, _) => LineEnding::Lf,
+        (_, 0) => LineEnding::Crlf,
+        _ => LineEnding::Mixed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> TextFile {
+        let file = decode(bytes);
+        assert_eq!(encode(&file.content, &file.format).unwrap(), bytes);
+        file
+    }
+
+    #[test]
+    fn keeps_utf8_files_byte_for_byte() {
+        let file = round_trip(b"a\r\nb\r\n");
+        assert_eq!(file.content, "a\nb\n");
+        assert_eq!(file.format.line_ending, LineEnding::Crlf);
+
+        let file = round_trip(b"\xEF\xBB\xBFcaf\xC3\xA9\n");
+        assert_eq!(file.content, "caf\u{e9}\n");
+        assert!(file.format.bom);
+
+        let file = round_trip(b"one\r\ntwo\nthree");
+        assert_eq!(file.format.line_ending, LineEnding::Mixed);
+        assert_eq!(file.content, "one\r\ntwo\nthree");
+
+        assert_eq!(round_trip(b"").format, TextFormat::default());
+    }
+
+    #[test]
+    fn detects_utf16_and_latin1() {
+        let file = round_trip(b"\xFF\xFEh\0i\0\r\0\n\0");
+        assert_eq!(file.format.encoding, TextEncoding::Utf16Le);
+        assert_eq!(file.content, "hi\n");
+
+        let file = round_trip(b"\0h\0i\0!");
+        assert_eq!(file.format.encoding, TextEncoding::Utf16Be);
+        assert!(!file.format.bom);
+
+        let file = round_trip(b"caf\xE9\n");
+        assert_eq!(file.format.encoding, TextEncoding::Latin1);
+        assert_eq!(file.content, "caf\u{e9}\n");
+    }
+
+    #[test]
+    fn edits_keep_the_original_format() {
+        let file = decode(b"\xFF\xFEa\0\r\0\n\0");
+        let edited = format!("{}b\n", file.content);
+        assert_eq!(
+            encode(&edited, &file.format).unwrap(),
+            b"\xFF\xFEa\0\r\0\n\0b\0\r\0\n\0"
+        );
+
+        let latin1 = decode(b"caf\xE9").format;
+        assert!(encode("\u{20ac}", &latin1).is_err());
+    }
+
+    #[test]
+    fn format_uses_editor_friendly_names() {
+        let format = TextFormat {
+            encoding: TextEncoding::Utf16Le,
+            bom: true,
+            line_ending: LineEnding::Crlf,
+        };
+        let json = serde_json::to_value(format).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"encoding": "utf-16le", "bom": true, "lineEnding": "crlf"})
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Skipping unchanged files on upload.
+//!
+//! Before an upload with `skipUnchanged`, the local tree is listed and the
+//! destination paths are hashed server-side, all of them in one `sha256sum`
+//! call (split only when the path list would not fit on one command line).
+//! Files whose remote digest matches the local one are not sent again, and the
+//! transfer summary reports how many were skipped and how many bytes that saved.
+
+use crate::ai::tool_exec_support::shell_quote;
+use crate::hashing::HashAlgorithm;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Quoted paths per command stay under this, well below any `ARG_MAX`.
+const MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadFile {
+    pub local: PathBuf,
+    pub remote: String,
+    pub size: u64,
+}
+
+/// Part of `transfer-success`: files left alone because they were identical.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupeSummary {
+    pub files: u64,
+    pub bytes_saved: u64,
+}
+
+fn join_remote(parent: &str, name: &str) -> String {
+    if parent.ends_with('/') {
+        format!("{}{}", parent, name)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// Regular files under `local` with the remote path `upload_recursive` gives them.
+pub fn list_files(local: &Path, remote: &str) -> Vec<UploadFile> {
+    let mut files = Vec::new();
+    collect(local, remote, &mut files);
+    files
+}
+
+fn collect(local: &Path, remote: &str, files: &mut Vec<UploadFile>) {
+    let Ok(meta) = std::fs::metadata(local) else {
+        return;
+    };
+    if meta.is_file() {
+        files.push(UploadFile {
+            local: local.to_path_buf(),
+            remote: remote.to_string(),
+            size: meta.len(),
+        });
+    } else if meta.is_dir() && !local.is_symlink() {
+        let Ok(entries) = std::fs::read_dir(local) else {
+            return;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            collect(&entry.path(), &join_remote(remote, &name), files);
+        }
+    }
+}
+
+/// Shell commands printing `digest  path` for each of `paths` that exists,
+/// using `shasum` where coreutils is missing (macOS, BSD).
+pub fn hash_commands(paths: &[&str]) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut batch = String::new();
+    for path in paths {
+        let quoted = shell_quote(path);
+        if !batch.is_empty() && batch.len() + quoted.len() + 1 > MAX_COMMAND_BYTES {
+            commands.push(hash_command(&batch));
+            batch.clear();
+        }
+        batch.push(' ');
+        batch.push_str(&quoted);
+    }
+    if !batch.is_empty() {
+        commands.push(hash_command(&batch));
+    }
+    commands
+}
+
+fn hash_command(quoted_paths: &str) -> String {
+    format!(
+        "if command -v sha256sum >/dev/null 2>&1; then sha256sum --{p} 2>/dev/null; \
+         else shasum -a 256 --{p} 2>/dev/null; fi; true",
+        p = quoted_paths
+    )
+}
+
+/// Remote path to digest from `sha256sum` output. Names the tool had to
+/// escape (a leading `\`, for backslashes or newlines) are left out.
+pub fn parse_digests(stdout: &[u8]) -> HashMap<String, String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|line| !line.starts_with('\\'))
+        .filter_map(|line| {
+            let (digest, rest) = line.split_once(' ')?;
+            let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+            let digest = HashAlgorithm::Sha256.parse_output(digest)?;
+            Some((path.to_string(), digest))
+        })
+        .collect()
+}
+
+/// Local files whose remote copy has the same digest. Local files are only
+/// read when the remote side reported a digest for them.
+pub fn unchanged(
+    files: &[UploadFile],
+    remote_digests: &HashMap<String, String>,
+) -> (HashSet<PathBuf>, DedupeSummary) {
+    let mut same = HashSet::new();
+    let mut summary = DedupeSummary::default();
+    for file in files {
+        let Some(remote) = remote_digests.get(&file.remote) else {
+            continue;
+        };
+        let local =
+            crate::hashing::hash_local_file(&file.local.to_string_lossy(), HashAlgorithm::Sha256);
+        if local.is_ok_and(|digest| &digest == remote) {
+            same.insert(file.local.clone());
+            summary.files += 1;
+            summary.bytes_saved += file.size;
+        }
+    }
+    (same, summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn batches_paths_into_hash_commands() {
+        let commands = hash_commands(&["/srv/a b", "/srv/it's"]);
+        assert_eq!(commands.len(), 1);
+        assert!(commands[0].contains("sha256sum -- '/srv/a b' '/srv/it'\\''s' 2>/dev/null"));
+
+        let long = "x".repeat(40 * 1024);
+        assert_eq!(hash_commands(&[&long, &long, &long]).len(), 3);
+        assert!(hash_commands(&[]).is_empty());
+    }
+
+    #[test]
+    fn parses_digest_lines() {
+        let stdout = format!(
+            "{d}  /srv/app.js\n{d} */srv/bin.dat\n\\{d}  /srv/odd\\nname\nsha256sum: /x: No such file\n",
+            d = ABC
+        );
+        let digests = parse_digests(stdout.as_bytes());
+        assert_eq!(digests.len(), 2);
+        assert_eq!(digests["/srv/app.js"], ABC);
+        assert_eq!(digests["/srv/bin.dat"], ABC);
+    }
+
+    #[test]
+    fn finds_unchanged_files() {
+        let dir = std::env::temp_dir().join(format!("zync-dedupe-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("same.txt"), "abc").unwrap();
+        std::fs::write(dir.join("sub/changed.txt"), "abcd").unwrap();
+        std::fs::write(dir.join("sub/new.txt"), "abc").unwrap();
+
+        let mut files = list_files(&dir, "/srv/site/");
+        files.sort_by(|a, b| a.remote.cmp(&b.remote));
+        let remotes: Vec<&str> = files.iter().map(|f| f.remote.as_str()).collect();
+        assert_eq!(
+            remotes,
+            [
+                "/srv/site/same.txt",
+                "/srv/site/sub/changed.txt",
+                "/srv/site/sub/new.txt"
+            ]
+        );
+
+        let remote_digests = HashMap::from([
+            ("/srv/site/same.txt".to_string(), ABC.to_string()),
+            ("/srv/site/sub/changed.txt".to_string(), ABC.to_string()),
+        ]);
+        let (same, summary) = unchanged(&files, &remote_digests);
+        assert_eq!(same, HashSet::from([dir.join("same.txt")]));
+        assert_eq!(
+            summary,
+            DedupeSummary {
+                files: 1,
+                bytes_saved: 3
+            }
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,310 @@
+//! Stream processors applied to file contents while they are transferred.
+//!
+//! A transfer declares an ordered list of processor names (e.g. `["gzip"]` to
+//! compress logs on download). Each file gets a fresh [`Pipeline`] built from the
+//! [`ProcessorChain`]; chunks flow through every stage before they hit the
+//! destination, so progress is reported in post-transform bytes. The registry
+//! starts with the built-in processors; plugins add theirs through
+//! [`crate::plugin_wasm::sync_processors`], named `<plugin id>/<name>`.
+
+use anyhow::{anyhow, Result};
+use flate2::write::{GzDecoder, GzEncoder};
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+/// One stage of a pipeline. Stages are stateful and live for a single file.
+pub trait TransferProcessor: Send {
+    /// Transforms the next chunk. May return fewer (or zero) bytes while buffering.
+    fn process(&mut self, chunk: &[u8]) -> Result<Vec<u8>>;
+    /// Flushes whatever is still buffered once the input is exhausted.
+    fn finish(&mut self) -> Result<Vec<u8>>;
+}
+
+pub trait ProcessorFactory: Send + Sync {
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn create(&self) -> Box<dyn TransferProcessor>;
+}
+
+struct GzipProcessor(Option<GzEncoder<Vec<u8>>>);
+
+impl TransferProcessor for GzipProcessor {
+    fn process(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        let encoder = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow!("gzip stream already finished"))?;
+        encoder.write_all(chunk)?;
+        Ok(std::mem::take(encoder.get_mut()))
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>> {
+        match self.0.take() {
+            Some(encoder) => Ok(encoder.finish()?),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+struct GunzipProcessor(Option<GzDecoder<Vec<u8>>>);
+
+impl TransferProcessor for GunzipProcessor {
+    fn process(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        let decoder = self
+            .0
+            .as_mut()
+            .ok_or_else(|| anyhow!("gunzip stream already finished"))?;
+        decoder
+            .write_all(chunk)
+            .map_err(|e| anyhow!("Not a valid gzip stream: {}", e))?;
+        Ok(std::mem::take(decoder.get_mut()))
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>> {
+        match self.0.take() {
+            Some(decoder) => decoder
+                .finish()
+                .map_err(|e| anyhow!("Truncated gzip stream: {}", e)),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+struct GzipFactory;
+
+impl ProcessorFactory for GzipFactory {
+    fn name(&self) -> &str {
+        "gzip"
+    }
+    fn description(&self) -> &str {
+        "Compress with gzip"
+    }
+    fn create(&self) -> Box<dyn TransferProcessor> {
+        Box::new(GzipProcessor(Some(GzEncoder::new(
+            Vec::new(),
+            Compression::default(),
+        ))))
+    }
+}
+
+struct GunzipFactory;
+
+impl ProcessorFactory for GunzipFactory {
+    fn name(&self) -> &str {
+        "gunzip"
+    }
+    fn description(&self) -> &str {
+        "Decompress gzip data"
+    }
+    fn create(&self) -> Box<dyn TransferProcessor> {
+        Box::new(GunzipProcessor(Some(GzDecoder::new(Vec::new()))))
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProcessorInfo {
+    pub name: String,
+    pub description: String,
+}
+
+pub struct ProcessorRegistry {
+    factories: RwLock<HashMap<String, Arc<dyn ProcessorFactory>>>,
+}
+
+impl ProcessorRegistry {
+    pub fn with_builtins() -> Self {
+        let registry = Self {
+            factories: RwLock::new(HashMap::new()),
+        };
+        registry.register(Arc::new(GzipFactory));
+        registry.register(Arc::new(GunzipFactory));
+        registry
+    }
+
+    /// Adds or replaces a processor under its name.
+    pub fn register(&self, factory: Arc<dyn ProcessorFactory>) {
+        if let Ok(mut factories) = self.factories.write() {
+            factories.insert(factory.name().to_string(), factory);
+        }
+    }
+
+    /// Drops every processor whose name `keep` rejects.
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) {
+        if let Ok(mut factories) = self.factories.write() {
+            factories.retain(|name, _| keep(name));
+        }
+    }
+
+    pub fn list(&self) -> Vec<ProcessorInfo> {
+        let mut infos: Vec<ProcessorInfo> = self
+            .factories
+            .read()
+            .map(|factories| {
+                factories
+                    .values()
+                    .map(|f| ProcessorInfo {
+                        name: f.name().to_string(),
+                        description: f.description().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Resolves processor names up front so unknown names fail before any bytes move.
+    pub fn chain(&self, names: &[String]) -> Result<ProcessorChain> {
+        let factories = self
+            .factories
+            .read()
+            .map_err(|_| anyhow!("Processor registry is poisoned"))?;
+        let stages = names
+            .iter()
+            .map(|name| {
+                factories
+                    .get(name.trim())
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Unknown transfer processor '{}'", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ProcessorChain { stages })
+    }
+}
+
+/// Validated, reusable list of processors for one transfer.
+#[derive(Clone, Default)]
+pub struct ProcessorChain {
+    stages: Vec<Arc<dyn ProcessorFactory>>,
+}
+
+impl ProcessorChain {
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Fresh per-file pipeline.
+    pub fn start(&self) -> Pipeline {
+        Pipeline {
+            stages: self.stages.iter().map(|f| f.create()).collect(),
+        }
+    }
+}
+
+pub struct Pipeline {
+    stages: Vec<Box<dyn TransferProcessor>>,
+}
+
+impl Pipeline {
+    pub fn process(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        let mut data = chunk.to_vec();
+        for stage in self.stages.iter_mut() {
+            data = stage.process(&data)?;
+        }
+        Ok(data)
+    }
+
+    /// Flushes each stage in order, pushing its tail through the stages after it.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        let mut carried = Vec::new();
+        for stage in self.stages.iter_mut() {
+            let mut data = if carried.is_empty() {
+                Vec::new()
+            } else {
+                stage.process(&carried)?
+            };
+            data.extend(stage.finish()?);
+            carried = data;
+        }
+        Ok(carried)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(pipeline: &mut Pipeline, input: &[u8], chunk: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for part in input.chunks(chunk) {
+            out.extend(pipeline.process(part).unwrap());
+        }
+        out.extend(pipeline.finish().unwrap());
+        out
+    }
+
+    #[test]
+    fn gzip_then_gunzip_round_trips() {
+        let registry = ProcessorRegistry::with_builtins();
+        let input: Vec<u8> = (0..200_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect();
+
+        let chain = registry
+            .chain(&["gzip".to_string(), "gunzip".to_string()])
+            .unwrap();
+        assert_eq!(run(&mut chain.start(), &input, 4096), input);
+    }
+
+    #[test]
+    fn gzip_output_is_a_gzip_stream() {
+        let registry = ProcessorRegistry::with_builtins();
+        let chain = registry.chain(&["gzip".to_string()]).unwrap();
+        let compressed = run(&mut chain.start(), b"hello hello hello hello", 5);
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+
+        let chain = registry.chain(&["gunzip".to_string()]).unwrap();
+        assert_eq!(
+            run(&mut chain.start(), &compressed, 3),
+            b"hello hello hello hello"
+        );
+    }
+
+    struct UpperFactory;
+
+    impl ProcessorFactory for UpperFactory {
+        fn name(&self) -> &str {
+            "com.example/upper"
+        }
+        fn description(&self) -> &str {
+            "Upper-case ASCII"
+        }
+        fn create(&self) -> Box<dyn TransferProcessor> {
+            struct Upper;
+            impl TransferProcessor for Upper {
+                fn process(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+                    Ok(chunk.to_ascii_uppercase())
+                }
+                fn finish(&mut self) -> Result<Vec<u8>> {
+                    Ok(Vec::new())
+                }
+            }
+            Box::new(Upper)
+        }
+    }
+
+    #[test]
+    fn registered_processors_chain_with_builtins() {
+        let registry = ProcessorRegistry::with_builtins();
+        registry.register(Arc::new(UpperFactory));
+        let chain = registry
+            .chain(&["com.example/upper".to_string(), "gzip".to_string()])
+            .unwrap();
+        let compressed = run(&mut chain.start(), b"quiet logs", 4);
+        let chain = registry.chain(&["gunzip".to_string()]).unwrap();
+        assert_eq!(run(&mut chain.start(), &compressed, 7), b"QUIET LOGS");
+
+        registry.retain(|name| !name.contains('/'));
+        assert!(registry.chain(&["com.example/upper".to_string()]).is_err());
+        assert_eq!(registry.list().len(), 2);
+    }
+
+    #[test]
+    fn unknown_processor_is_rejected() {
+        let registry = ProcessorRegistry::with_builtins();
+        assert!(registry.chain(&["webp".to_string()]).is_err());
+        assert!(registry.chain(&[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Chunk-level retries for SFTP transfers on flaky links.
+//!
+//! A transient failure (broken pipe, reset, timeout) on one chunk reopens the
+//! remote file, seeks back to the start of that chunk and carries on, up to
+//! [`MAX_CHUNK_RETRIES`] times per chunk, instead of failing the whole job.
+//! Permanent errors such as permission or quota failures are returned at once.
+
+use russh_sftp::client::fs::File;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::OpenFlags;
+use std::io::SeekFrom;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+pub const MAX_CHUNK_RETRIES: u32 = 3;
+/// Read size of [`spawn_remote_reader`]; large to keep high-latency links busy.
+const READ_CHUNK: usize = 4 * 1024 * 1024;
+
+const TRANSIENT_MARKERS: &[&str] = &[
+    "broken pipe",
+    "connection reset",
+    "connection aborted",
+    "connection lost",
+    "timed out",
+    "timeout",
+    "unexpected eof",
+];
+
+/// Whether an error looks like a dropped or stalled link rather than a refusal.
+pub fn is_transient(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Wait before retry `attempt` (1-based): 0.5s, 1s, 2s, ...
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_millis(500 << attempt.saturating_sub(1).min(6))
+}
+
+async fn reopen_at(
+    sftp: &SftpSession,
+    path: &str,
+    flags: OpenFlags,
+    offset: u64,
+) -> Result<File, String> {
+    let mut file = sftp
+        .open_with_flags(path, flags)
+        .await
+        .map_err(|e| format!("Failed to reopen remote file '{}': {}", path, e))?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .map_err(|e| format!("Failed to seek in remote file '{}': {}", path, e))?;
+    Ok(file)
+}
+
+/// After `error`, waits and reopens `path` positioned at `offset`. `attempts`
+/// counts retries already spent on the current chunk; once it passes
+/// [`MAX_CHUNK_RETRIES`], or for a permanent error, the error is returned.
+pub async fn recover(
+    sftp: &SftpSession,
+    path: &str,
+    flags: OpenFlags,
+    offset: u64,
+    attempts: &mut u32,
+    mut error: String,
+) -> Result<File, String> {
+    loop {
+        *attempts += 1;
+        if *attempts > MAX_CHUNK_RETRIES || !is_transient(&error) {
+            return Err(error);
+        }
+        crate::log_error!(
+            "[SFTP] {}; reopening '{}' at byte {} (retry {}/{})",
+            error,
+            path,
+            offset,
+            attempts,
+            MAX_CHUNK_RETRIES
+        );
+        tokio::time::sleep(backoff(*attempts)).await;
+        match reopen_at(sftp, path, flags, offset).await {
+            Ok(file) => return Ok(file),
+            Err(e) => error = e,
+        }
+    }
+}
+
+/// Writes `chunk` at `offset` of `path`, reopening `file` on transient failures.
+pub async fn write_chunk(
+    sftp: &SftpSession,
+    file: &mut File,
+    path: &str,
+    offset: u64,
+    chunk: &[u8],
+) -> Result<(), String> {
+    let mut attempts = 0;
+    loop {
+        match file.write_all(chunk).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                *file = recover(
+                    sftp,
+                    path,
+                    OpenFlags::WRITE,
+                    offset,
+                    &mut attempts,
+                    format!("SFTP write failed: {}", e),
+                )
+                .await?;
+            }
+        }
+    }
+}
+
+/// Reads `file` to the end on a background task. A read error is sent as the
+/// last item, so the caller can [`recover`] and start a new reader at its offset.
+pub fn spawn_remote_reader(mut file: File) -> mpsc::Receiver<Result<Vec<u8>, String>> {
+    let (tx, rx) = mpsc::channel::<Result<Vec<u8>, String>>(4);
+    tokio::spawn(async move {
+        loop {
+            let mut buffer = vec![0u8; READ_CHUNK];
+            match file.read(&mut buffer).await {
+                Ok(0) => break,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    if tx.send(Ok(buffer)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(Err(format!("SFTP read failed: {}", e))).await;
+                    break;
+                }
+            }
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_link_failures_are_retried() {
+        assert!(is_transient("SFTP write failed: Broken pipe (os error 32)"));
+        assert!(is_transient("SFTP read failed: Timeout"));
+        assert!(!is_transient("SFTP write failed: Permission denied"));
+        assert!(!is_transient("SFTP write failed: No space left on device"));
+    }
+
+    #[test]
+    fn backoff_doubles_per_attempt() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,621 @@
+//! Soft-delete for the file manager.
+//!
+//! Every backend uses the freedesktop.org trash layout: trashed items live in
+//! `files/<id>` and their origin is recorded in `info/<id>.trashinfo`.
+//!
+//! - Local Linux/BSD: the desktop trash (`$XDG_DATA_HOME/Trash`), so items show up
+//!   in the system file manager too.
+//! - Local macOS: items go to `~/.Trash`; restore metadata sits in a hidden
+//!   `.zync-trashinfo` folder next to them.
+//! - Local Windows: `~/.zync-trash` (the Recycle Bin needs shell APIs we don't link).
+//! - Remote: `~/.zync-trash` on the server, managed over SFTP.
+//!
+//! The info files zync writes carry an `X-Zync-Trashed` key, and the retention
+//! auto-purge only ever removes those, never what other programs trashed.
+
+use crate::fs::FileSystem;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const REMOTE_TRASH_DIR: &str = ".zync-trash";
+pub const DEFAULT_RETENTION_DAYS: u64 = 30;
+const TRASHINFO_EXT: &str = ".trashinfo";
+const OWNER_KEY: &str = "X-Zync-Trashed=true";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub name: String,
+    pub original_path: String,
+    /// Unix milliseconds.
+    pub deleted_at: u64,
+    pub is_dir: bool,
+    /// Trashed by zync rather than another program sharing the trash.
+    #[serde(skip)]
+    pub from_zync: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// --- .trashinfo encoding ---
+
+fn percent_encode_path(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+fn percent_decode_path(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let decoded = std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = decoded {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Days since 1970-01-01 -> (year, month, day), proleptic Gregorian.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (if month > 2 { month - 3 } else { month + 9 }) as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// `YYYY-MM-DDThh:mm:ss`. The spec asks for local time; we write UTC since
+/// the same trash may be read from machines in different zones.
+fn format_deletion_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+fn parse_deletion_date(value: &str) -> Option<u64> {
+    let (date, time) = value.trim().split_once('T')?;
+    let mut date_parts = date.split('-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    let mut time_parts = time.split(':');
+    let hour: u64 = time_parts.next()?.parse().ok()?;
+    let minute: u64 = time_parts.next()?.parse().ok()?;
+    let second: u64 = time_parts.next()?.get(..2)?.parse().ok()?;
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return None;
+    }
+    Some(days as u64 * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+pub fn encode_trash_info(original_path: &str, deleted_at_secs: u64) -> String {
+    format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n{}\n",
+        percent_encode_path(original_path),
+        format_deletion_date(deleted_at_secs),
+        OWNER_KEY
+    )
+}
+
+fn is_from_zync(content: &str) -> bool {
+    content.lines().any(|line| line.trim() == OWNER_KEY)
+}
+
+/// Returns `(original_path, deleted_at_secs)`.
+pub fn parse_trash_info(content: &str) -> Option<(String, u64)> {
+    let mut path = None;
+    let mut deleted_at = None;
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(percent_decode_path(value.trim()));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deleted_at = parse_deletion_date(value);
+        }
+    }
+    Some((path?, deleted_at.unwrap_or(0)))
+}
+
+/// Picks `name`, `name.2`, `name.3`, ... — the first id not already taken.
+fn unique_id(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(name) {
+        return name.to_string();
+    }
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{}.{}", name, counter);
+        if !taken(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+fn base_name(path: &str) -> Result<String> {
+    path.trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Cannot move '{}' to trash", path))
+}
+
+fn is_expired(deleted_at_secs: u64, older_than_secs: Option<u64>, now: u64) -> bool {
+    match older_than_secs {
+        None => true,
+        Some(age) => now.saturating_sub(deleted_at_secs) >= age,
+    }
+}
+
+// --- Local trash ---
+
+pub struct LocalTrash {
+    files_dir: PathBuf,
+    info_dir: PathBuf,
+}
+
+impl LocalTrash {
+    pub fn for_current_user() -> Result<Self> {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("Home directory not found"))?;
+
+        #[cfg(target_os = "macos")]
+        let (files_dir, info_dir) = {
+            let trash = home.join(".Trash");
+            (trash.clone(), trash.join(".zync-trashinfo"))
+        };
+
+        #[cfg(windows)]
+        let (files_dir, info_dir) = {
+            let trash = home.join(REMOTE_TRASH_DIR);
+            (trash.join("files"), trash.join("info"))
+        };
+
+        #[cfg(not(any(target_os = "macos", windows)))]
+        let (files_dir, info_dir) = {
+            let data_home = std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .filter(|p| p.is_absolute())
+                .unwrap_or_else(|| home.join(".local/share"));
+            let trash = data_home.join("Trash");
+            (trash.join("files"), trash.join("info"))
+        };
+
+        Ok(Self::at(files_dir, info_dir))
+    }
+
+    pub fn at(files_dir: PathBuf, info_dir: PathBuf) -> Self {
+        Self {
+            files_dir,
+            info_dir,
+        }
+    }
+
+    fn info_path(&self, id: &str) -> PathBuf {
+        self.info_dir.join(format!("{}{}", id, TRASHINFO_EXT))
+    }
+
+    pub fn trash(&self, path: &str) -> Result<TrashEntry> {
+        let source = Path::new(path);
+        let absolute = if source.is_absolute() {
+            source.to_path_buf()
+        } else {
+            std::env::current_dir()?.join(source)
+        };
+        let metadata = std::fs::symlink_metadata(&absolute)
+            .map_err(|e| anyhow!("Failed to read '{}': {}", path, e))?;
+        std::fs::create_dir_all(&self.files_dir)?;
+        std::fs::create_dir_all(&self.info_dir)?;
+
+        let name = base_name(path)?;
+        let id = unique_id(&name, |candidate| {
+            self.files_dir.join(candidate).exists() || self.info_path(candidate).exists()
+        });
+        let deleted_at = now_secs();
+        let original = absolute.to_string_lossy().to_string();
+        // Write the info file first so a crash never leaves an orphan with no origin.
+        crate::atomic_io::durable_replace(
+            &self.info_path(&id),
+            encode_trash_info(&original, deleted_at).as_bytes(),
+        )
+        .map_err(|e| anyhow!("Failed to write trash info: {}", e))?;
+        if let Err(e) = std::fs::rename(&absolute, self.files_dir.join(&id)) {
+            let _ = std::fs::remove_file(self.info_path(&id));
+            return Err(anyhow!(
+                "Failed to move '{}' to trash (it may be on another volume): {}",
+                path,
+                e
+            ));
+        }
+
+        Ok(TrashEntry {
+            id,
+            name,
+            original_path: original,
+            deleted_at: deleted_at * 1000,
+            is_dir: metadata.is_dir(),
+            from_zync: true,
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = Vec::new();
+        let dir = match std::fs::read_dir(&self.info_dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(anyhow!("Failed to read trash: {}", e)),
+        };
+        for entry in dir.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(id) = file_name.strip_suffix(TRASHINFO_EXT) else {
+                continue;
+            };
+            let Ok(content) = std::fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            let Some((original_path, deleted_at)) = parse_trash_info(&content) else {
+                continue;
+            };
+            let Ok(metadata) = std::fs::symlink_metadata(self.files_dir.join(id)) else {
+                continue;
+            };
+            entries.push(TrashEntry {
+                id: id.to_string(),
+                name: base_name(&original_path).unwrap_or_else(|_| id.to_string()),
+                original_path,
+                deleted_at: deleted_at * 1000,
+                is_dir: metadata.is_dir(),
+                from_zync: is_from_zync(&content),
+            });
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+        Ok(entries)
+    }
+
+    /// Moves an item back to where it came from and returns that path.
+    pub fn restore(&self, id: &str) -> Result<String> {
+        let content = std::fs::read_to_string(self.info_path(id))
+            .map_err(|_| anyhow!("Trash item '{}' not found", id))?;
+        let (original_path, _) =
+            parse_trash_info(&content).ok_or_else(|| anyhow!("Corrupt trash info for '{}'", id))?;
+        let target = Path::new(&original_path);
+        if std::fs::symlink_metadata(target).is_ok() {
+            return Err(anyhow!(
+                "Cannot restore: '{}' already exists",
+                original_path
+            ));
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(self.files_dir.join(id), target)
+            .map_err(|e| anyhow!("Failed to restore '{}': {}", original_path, e))?;
+        let _ = std::fs::remove_file(self.info_path(id));
+        Ok(original_path)
+    }
+
+    /// Permanently removes items; with `older_than_secs` only expired ones.
+    pub fn empty(&self, older_than_secs: Option<u64>) -> Result<usize> {
+        self.remove_where(|entry, now| is_expired(entry.deleted_at / 1000, older_than_secs, now))
+    }
+
+    /// Retention auto-purge: removes expired items zync trashed and leaves
+    /// everything else in a shared desktop trash alone.
+    pub fn purge_expired(&self, retention_secs: u64) -> Result<usize> {
+        self.remove_where(|entry, now| {
+            entry.from_zync && is_expired(entry.deleted_at / 1000, Some(retention_secs), now)
+        })
+    }
+
+    fn remove_where(&self, remove: impl Fn(&TrashEntry, u64) -> bool) -> Result<usize> {
+        let now = now_secs();
+        let mut removed = 0;
+        for entry in self.list()? {
+            if !remove(&entry, now) {
+                continue;
+            }
+            let item = self.files_dir.join(&entry.id);
+            let result = if entry.is_dir {
+                std::fs::remove_dir_all(&item)
+            } else {
+                std::fs::remove_file(&item)
+            };
+            if let Err(e) = result {
+                crate::log_error!("[FS] Failed to purge trash item '{}': {}", entry.id, e);
+                continue;
+            }
+            let _ = std::fs::remove_file(self.info_path(&entry.id));
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+// --- Remote trash ---
+
+pub struct RemoteTrash<'a> {
+    sftp: &'a russh_sftp::client::SftpSession,
+    files_dir: String,
+    info_dir: String,
+}
+
+impl<'a> RemoteTrash<'a> {
+    /// Resolves `~/.zync-trash` on the server (creating it on first use).
+    pub async fn open(sftp: &'a russh_sftp::client::SftpSession) -> Result<RemoteTrash<'a>> {
+        let home = sftp
+            .canonicalize(".")
+            .await
+            .map_err(|e| anyhow!("Failed to resolve remote home: {}", e))?;
+        let root = format!("{}/{}", home.trim_end_matches('/'), REMOTE_TRASH_DIR);
+        let trash = RemoteTrash {
+            sftp,
+            files_dir: format!("{}/files", root),
+            info_dir: format!("{}/info", root),
+        };
+        for dir in [&root, &trash.files_dir, &trash.info_dir] {
+            if !sftp.try_exists(dir.as_str()).await.unwrap_or(false) {
+                sftp.create_dir(dir.as_str())
+                    .await
+                    .map_err(|e| anyhow!("Failed to create '{}': {}", dir, e))?;
+            }
+        }
+        Ok(trash)
+    }
+
+    fn info_path(&self, id: &str) -> String {
+        format!("{}/{}{}", self.info_dir, id, TRASHINFO_EXT)
+    }
+
+    fn item_path(&self, id: &str) -> String {
+        format!("{}/{}", self.files_dir, id)
+    }
+
+    async fn taken_ids(&self) -> std::collections::HashSet<String> {
+        let mut taken = std::collections::HashSet::new();
+        for dir in [&self.files_dir, &self.info_dir] {
+            if let Ok(entries) = self.sftp.read_dir(dir.as_str()).await {
+                for entry in entries {
+                    let name = entry.file_name();
+                    taken.insert(name.strip_suffix(TRASHINFO_EXT).unwrap_or(&name).to_string());
+                }
+            }
+        }
+        taken
+    }
+
+    pub async fn trash(&self, path: &str) -> Result<TrashEntry> {
+        let attrs = self
+            .sftp
+            .symlink_metadata(path)
+            .await
+            .map_err(|e| anyhow!("Failed to stat '{}': {}", path, e))?;
+        let original_path = self
+            .sftp
+            .canonicalize(path)
+            .await
+            .unwrap_or_else(|_| path.to_string());
+        let name = base_name(&original_path)?;
+        let taken = self.taken_ids().await;
+        let id = unique_id(&name, |candidate| taken.contains(candidate));
+        let deleted_at = now_secs();
+
+        let info = encode_trash_info(&original_path, deleted_at);
+        FileSystem::new()
+            .write_remote(self.sftp, &self.info_path(&id), info.as_bytes())
+            .await?;
+        if let Err(e) = self.sftp.rename(path, self.item_path(&id)).await {
+            let _ = self.sftp.remove_file(self.info_path(&id)).await;
+            return Err(anyhow!(
+                "Failed to move '{}' to trash (it may be on another filesystem): {}",
+                path,
+                e
+            ));
+        }
+
+        Ok(TrashEntry {
+            id,
+            name,
+            original_path,
+            deleted_at: deleted_at * 1000,
+            is_dir: (attrs.permissions.unwrap_or(0) & 0o170000) == 0o040000,
+            from_zync: true,
+        })
+    }
+
+    pub async fn list(&self) -> Result<Vec<TrashEntry>> {
+        let mut entries = Vec::new();
+        let dir = self
+            .sftp
+            .read_dir(self.info_dir.as_str())
+            .await
+            .map_err(|e| anyhow!("Failed to read trash: {}", e))?;
+        for entry in dir {
+            let file_name = entry.file_name();
+            let Some(id) = file_name.strip_suffix(TRASHINFO_EXT) else {
+                continue;
+            };
+            let Ok(content) = self.sftp.read(self.info_path(id)).await else {
+                continue;
+            };
+            let content = String::from_utf8_lossy(&content);
+            let Some((original_path, deleted_at)) = parse_trash_info(&content) else {
+                continue;
+            };
+            let Ok(attrs) = self.sftp.symlink_metadata(self.item_path(id)).await else {
+                continue;
+            };
+            entries.push(TrashEntry {
+                id: id.to_string(),
+                name: base_name(&original_path).unwrap_or_else(|_| id.to_string()),
+                original_path,
+                deleted_at: deleted_at * 1000,
+                is_dir: (attrs.permissions.unwrap_or(0) & 0o170000) == 0o040000,
+                from_zync: is_from_zync(&content),
+            });
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+        Ok(entries)
+    }
+
+    pub async fn restore(&self, id: &str) -> Result<String> {
+        let content = self
+            .sftp
+            .read(self.info_path(id))
+            .await
+            .map_err(|_| anyhow!("Trash item '{}' not found", id))?;
+        let (original_path, _) = parse_trash_info(&String::from_utf8_lossy(&content))
+            .ok_or_else(|| anyhow!("Corrupt trash info for '{}'", id))?;
+        if self.sftp.try_exists(original_path.as_str()).await.unwrap_or(false) {
+            return Err(anyhow!(
+                "Cannot restore: '{}' already exists",
+                original_path
+            ));
+        }
+        self.sftp
+            .rename(self.item_path(id), original_path.as_str())
+            .await
+            .map_err(|e| anyhow!("Failed to restore '{}': {}", original_path, e))?;
+        let _ = self.sftp.remove_file(self.info_path(id)).await;
+        Ok(original_path)
+    }
+
+    pub async fn empty(&self, older_than_secs: Option<u64>) -> Result<usize> {
+        let now = now_secs();
+        let fs = FileSystem::new();
+        let mut removed = 0;
+        for entry in self.list().await? {
+            if !is_expired(entry.deleted_at / 1000, older_than_secs, now) {
+                continue;
+            }
+            if let Err(e) = fs.delete_remote(self.sftp, &self.item_path(&entry.id)).await {
+                crate::log_error!("[FS] Failed to purge remote trash item '{}': {}", entry.id, e);
+                continue;
+            }
+            let _ = self.sftp.remove_file(self.info_path(&entry.id)).await;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trash_info_round_trips_paths_and_dates() {
+        let info = encode_trash_info("/home/me/My Notes/a%b.txt", 1_700_000_000);
+        assert!(info.contains("Path=/home/me/My%20Notes/a%25b.txt"));
+        assert!(info.contains("DeletionDate=2023-11-14T22:13:20"));
+        let (path, secs) = parse_trash_info(&info).unwrap();
+        assert_eq!(path, "/home/me/My Notes/a%b.txt");
+        assert_eq!(secs, 1_700_000_000);
+    }
+
+    #[test]
+    fn unique_id_appends_counter() {
+        let taken = ["log.txt", "log.txt.2"];
+        assert_eq!(unique_id("log.txt", |c| taken.contains(&c)), "log.txt.3");
+        assert_eq!(unique_id("other", |c| taken.contains(&c)), "other");
+    }
+
+    #[test]
+    fn local_trash_moves_and_restores() {
+        let root = std::env::temp_dir().join(format!("zync-trash-{}", uuid::Uuid::new_v4()));
+        let work = root.join("work");
+        std::fs::create_dir_all(&work).unwrap();
+        let file = work.join("notes.txt");
+        std::fs::write(&file, b"keep me").unwrap();
+
+        let trash = LocalTrash::at(root.join("Trash/files"), root.join("Trash/info"));
+        let entry = trash.trash(&file.to_string_lossy()).unwrap();
+        assert!(!file.exists());
+        assert_eq!(trash.list().unwrap().len(), 1);
+
+        let restored = trash.restore(&entry.id).unwrap();
+        assert_eq!(restored, file.to_string_lossy());
+        assert_eq!(std::fs::read(&file).unwrap(), b"keep me");
+        assert!(trash.list().unwrap().is_empty());
+
+        trash.trash(&file.to_string_lossy()).unwrap();
+        assert_eq!(trash.empty(Some(3600)).unwrap(), 0);
+        assert_eq!(trash.empty(None).unwrap(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn auto_purge_leaves_other_programs_items_alone() {
+        let root = std::env::temp_dir().join(format!("zync-trash-{}", uuid::Uuid::new_v4()));
+        let trash = LocalTrash::at(root.join("Trash/files"), root.join("Trash/info"));
+        std::fs::create_dir_all(root.join("work")).unwrap();
+        let file = root.join("work/ours.txt");
+        std::fs::write(&file, b"ours").unwrap();
+        trash.trash(&file.to_string_lossy()).unwrap();
+
+        // Trashed by the desktop file manager: no zync key in its info file.
+        std::fs::write(root.join("Trash/files/theirs.txt"), b"theirs").unwrap();
+        std::fs::write(
+            root.join("Trash/info/theirs.txt.trashinfo"),
+            "[Trash Info]\nPath=/home/me/theirs.txt\nDeletionDate=2020-01-01T00:00:00\n",
+        )
+        .unwrap();
+
+        assert_eq!(trash.purge_expired(0).unwrap(), 1);
+        let left = trash.list().unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].id, "theirs.txt");
+        assert!(!left[0].from_zync);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,434 @@
+//! Output triggers: user-defined regex rules matched against terminal output.
+//!
+//! Rules live in `<data>/triggers.json`. `PtyManager` feeds every output chunk
+//! through [`TriggerEngine::output`], which strips escape sequences and matches
+//! each rule against the current line — including an unfinished one, so prompts
+//! like `Password:` fire before a newline arrives. A rule fires at most once per
+//! line and then waits out its cooldown for that terminal. Fires are queued to
+//! the dispatcher `AppState::new` starts (`commands::spawn_trigger_dispatcher`),
+//! which performs the actions: send text, notify, start a tunnel, run a snippet.
+
+use crate::session_log::ControlStripper;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+pub(crate) static TRIGGERS_MUTATION_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(1);
+/// Longest line kept for matching; older text on a runaway line is dropped.
+const MAX_LINE_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerRule {
+    pub id: String,
+    pub name: String,
+    pub pattern: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Only match terminals of this connection (`local` for local shells).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Minimum gap between two fires in the same terminal; defaults to 1s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_ms: Option<u64>,
+    pub actions: Vec<TriggerAction>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// `text` and `message` may reference capture groups (`$1`, `${name}`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TriggerAction {
+    /// Written to the terminal as if typed; include `\r` to press enter.
+    SendText { text: String },
+    /// Emitted as `trigger:notify`.
+    Notify {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        message: String,
+    },
+    StartTunnel {
+        #[serde(rename = "tunnelId")]
+        tunnel_id: String,
+    },
+    RunSnippet {
+        #[serde(rename = "snippetId")]
+        snippet_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TriggersData {
+    pub triggers: Vec<TriggerRule>,
+}
+
+/// A rule that matched, with capture groups already expanded into its actions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TriggerFire {
+    pub term_id: String,
+    pub connection_id: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub matched: String,
+    pub actions: Vec<TriggerAction>,
+}
+
+struct CompiledRule {
+    rule: TriggerRule,
+    regex: Regex,
+    cooldown: Duration,
+}
+
+pub fn compile(rule: &TriggerRule) -> Result<Regex, String> {
+    if rule.pattern.is_empty() {
+        return Err("Trigger pattern is empty".to_string());
+    }
+    RegexBuilder::new(&rule.pattern)
+        .case_insensitive(rule.case_insensitive)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|e| format!("Invalid trigger pattern: {}", e))
+}
+
+struct TerminalState {
+    stripper: ControlStripper,
+    line: String,
+    /// Rules that already fired on the current line.
+    fired: HashSet<String>,
+    last_fire: HashMap<String, Instant>,
+}
+
+impl TerminalState {
+    fn new() -> Self {
+        Self {
+            stripper: ControlStripper::new(),
+            line: String::new(),
+            fired: HashSet::new(),
+            last_fire: HashMap::new(),
+        }
+    }
+}
+
+/// Rules plus per-terminal matching state; cheap to clone into reader tasks.
+#[derive(Clone)]
+pub struct TriggerEngine {
+    rules: Arc<Mutex<Arc<Vec<CompiledRule>>>>,
+    terminals: Arc<Mutex<HashMap<String, TerminalState>>>,
+    disabled: Arc<Mutex<HashSet<String>>>,
+    tx: mpsc::UnboundedSender<TriggerFire>,
+    rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<TriggerFire>>>>,
+}
+
+impl Default for TriggerEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TriggerEngine {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            rules: Arc::default(),
+            terminals: Arc::default(),
+            disabled: Arc::default(),
+            tx,
+            rx: Arc::new(Mutex::new(Some(rx))),
+        }
+    }
+
+    /// The queue of fired rules; there is a single consumer, so this returns it once.
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<TriggerFire>> {
+        self.rx.lock().ok()?.take()
+    }
+
+    /// Replaces the active rule set. Rules that fail to compile are skipped.
+    pub fn set_rules(&self, rules: Vec<TriggerRule>) {
+        let compiled = rules
+            .into_iter()
+            .filter(|rule| rule.enabled && !rule.actions.is_empty())
+            .filter_map(|rule| match compile(&rule) {
+                Ok(regex) => Some(CompiledRule {
+                    cooldown: rule
+                        .cooldown_ms
+                        .map(Duration::from_millis)
+                        .unwrap_or(DEFAULT_COOLDOWN),
+                    regex,
+                    rule,
+                }),
+                Err(e) => {
+                    crate::log_error!("[TERM] Skipping trigger '{}': {}", rule.name, e);
+                    None
+                }
+            })
+            .collect();
+        if let Ok(mut rules) = self.rules.lock() {
+            *rules = Arc::new(compiled);
+        }
+    }
+
+    pub fn set_terminal_enabled(&self, term_id: &str, enabled: bool) {
+        if let Ok(mut disabled) = self.disabled.lock() {
+            if enabled {
+                disabled.remove(term_id);
+            } else {
+                disabled.insert(term_id.to_string());
+            }
+        }
+    }
+
+    pub fn is_terminal_enabled(&self, term_id: &str) -> bool {
+        self.disabled
+            .lock()
+            .map(|disabled| !disabled.contains(term_id))
+            .unwrap_or(true)
+    }
+
+    pub fn output(&self, term_id: &str, connection_id: &str, bytes: &[u8]) {
+        let rules = match self.rules.lock() {
+            Ok(rules) if !rules.is_empty() => rules.clone(),
+            _ => return,
+        };
+        if !self.is_terminal_enabled(term_id) {
+            return;
+        }
+        let Ok(mut terminals) = self.terminals.lock() else {
+            return;
+        };
+        let state = terminals
+            .entry(term_id.to_string())
+            .or_insert_with(TerminalState::new);
+
+        let mut plain = Vec::with_capacity(bytes.len());
+        state.stripper.strip(bytes, &mut plain);
+        let text = String::from_utf8_lossy(&plain);
+        let mut fires = Vec::new();
+        let mut lines = text.split('\n').peekable();
+        while let Some(segment) = lines.next() {
+            state.line.push_str(segment);
+            if state.line.len() > MAX_LINE_BYTES {
+                let mut cut = state.line.len() - MAX_LINE_BYTES;
+                while !state.line.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                state.line.drain(..cut);
+            }
+            let complete = lines.peek().is_some();
+            if complete || !state.line.is_empty() {
+                evaluate(&rules, state, term_id, connection_id, &mut fires);
+            }
+            if complete {
+                state.line.clear();
+                state.fired.clear();
+            }
+        }
+        drop(terminals);
+
+        for fire in fires {
+            let _ = self.tx.send(fire);
+        }
+    }
+
+    pub fn remove(&self, term_id: &str) {
+        if let Ok(mut terminals) = self.terminals.lock() {
+            terminals.remove(term_id);
+        }
+        if let Ok(mut disabled) = self.disabled.lock() {
+            disabled.remove(term_id);
+        }
+    }
+}
+
+fn evaluate(
+    rules: &[CompiledRule],
+    state: &mut TerminalState,
+    term_id: &str,
+    connection_id: &str,
+    fires: &mut Vec<TriggerFire>,
+) {
+    let now = Instant::now();
+    for compiled in rules {
+        let rule = &compiled.rule;
+        if rule
+            .connection_id
+            .as_deref()
+            .is_some_and(|id| id != connection_id)
+            || state.fired.contains(&rule.id)
+        {
+            continue;
+        }
+        let Some(captures) = compiled.regex.captures(&state.line) else {
+            continue;
+        };
+        state.fired.insert(rule.id.clone());
+        if state
+            .last_fire
+            .get(&rule.id)
+            .is_some_and(|last| now.duration_since(*last) < compiled.cooldown)
+        {
+            continue;
+        }
+        state.last_fire.insert(rule.id.clone(), now);
+
+        let expand = |template: &str| {
+            let mut out = String::new();
+            captures.expand(template, &mut out);
+            out
+        };
+        let actions = rule
+            .actions
+            .iter()
+            .map(|action| match action {
+                TriggerAction::SendText { text } => TriggerAction::SendText { text: expand(text) },
+                TriggerAction::Notify { title, message } => TriggerAction::Notify {
+                    title: title.as_deref().map(expand),
+                    message: expand(message),
+                },
+                other => other.clone(),
+            })
+            .collect();
+        fires.push(TriggerFire {
+            term_id: term_id.to_string(),
+            connection_id: connection_id.to_string(),
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            matched: captures[0].to_string(),
+            actions,
+        });
+    }
+}
+
+pub fn read_triggers(path: &Path) -> Result<Vec<TriggerRule>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let data: TriggersData = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    Ok(data.triggers)
+}
+
+pub fn write_triggers(path: &Path, triggers: Vec<TriggerRule>) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(&TriggersData { triggers }).map_err(|e| e.to_string())?;
+    crate::atomic_io::durable_replace(path, json.as_bytes())
+        .map_err(|e| format!("Failed to write triggers file: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, pattern: &str, actions: Vec<TriggerAction>) -> TriggerRule {
+        TriggerRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            pattern: pattern.to_string(),
+            enabled: true,
+            connection_id: None,
+            case_insensitive: false,
+            cooldown_ms: Some(0),
+            actions,
+        }
+    }
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<TriggerFire>) -> Vec<TriggerFire> {
+        let mut fires = Vec::new();
+        while let Ok(fire) = rx.try_recv() {
+            fires.push(fire);
+        }
+        fires
+    }
+
+    #[test]
+    fn fires_on_partial_lines_once_per_line_with_captures() {
+        let engine = TriggerEngine::new();
+        let mut rx = engine.take_receiver().unwrap();
+        engine.set_rules(vec![
+            rule(
+                "sudo",
+                r"\[sudo\] password for (\w+):",
+                vec![TriggerAction::Notify {
+                    title: None,
+                    message: "sudo wants $1's password".to_string(),
+                }],
+            ),
+            rule(
+                "deploy",
+                r"deploy (\S+) finished",
+                vec![TriggerAction::SendText {
+                    text: "tail -n 20 /var/log/$1.log\r".to_string(),
+                }],
+            ),
+        ]);
+
+        engine.output("t1", "c1", b"\x1b[1m[sudo] pass");
+        assert!(drain(&mut rx).is_empty());
+        engine.output("t1", "c1", b"word for ops: ");
+        // More output on the same line does not fire again.
+        engine.output("t1", "c1", b"*");
+        let fires = drain(&mut rx);
+        assert_eq!(fires.len(), 1);
+        assert_eq!(
+            fires[0].actions,
+            vec![TriggerAction::Notify {
+                title: None,
+                message: "sudo wants ops's password".to_string(),
+            }]
+        );
+
+        engine.output(
+            "t1",
+            "c1",
+            b"\r\ndeploy api finished\r\ndeploy web finished\r\n",
+        );
+        let fires = drain(&mut rx);
+        assert_eq!(fires.len(), 2);
+        assert_eq!(
+            fires[1].actions,
+            vec![TriggerAction::SendText {
+                text: "tail -n 20 /var/log/web.log\r".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn respects_scope_cooldown_and_terminal_switch() {
+        let engine = TriggerEngine::new();
+        let mut rx = engine.take_receiver().unwrap();
+        let mut scoped = rule(
+            "oom",
+            "out of memory",
+            vec![TriggerAction::StartTunnel {
+                tunnel_id: "x".to_string(),
+            }],
+        );
+        scoped.connection_id = Some("prod".to_string());
+        scoped.case_insensitive = true;
+        scoped.cooldown_ms = Some(60_000);
+        engine.set_rules(vec![scoped]);
+
+        engine.output("t1", "staging", b"Out Of Memory\n");
+        assert!(drain(&mut rx).is_empty());
+        engine.output("t2", "prod", b"Out Of Memory\nout of memory\n");
+        assert_eq!(drain(&mut rx).len(), 1);
+
+        engine.set_terminal_enabled("t3", false);
+        engine.output("t3", "prod", b"out of memory\n");
+        assert!(drain(&mut rx).is_empty());
+        engine.set_terminal_enabled("t3", true);
+        engine.output("t3", "prod", b"out of memory\n");
+        assert_eq!(drain(&mut rx).len(), 1);
+    }
+}
@@ -1,18 +1,55 @@
 use crate::commands::{get_data_dir, AppState};
 use super::manager::probe_ssh_session;
 use super::{remote_forward_map_key, tunnel_runtime_id};
+use super::metrics::{ConnectionRecord, TunnelStats};
+use super::exit_node::{self, ExitNode};
+use super::port_conflict::{self, PortConflict};
+use crate::state_sync::{self, ChangeKind, StateKind, TunnelSummary};
 use crate::types::{SavedTunnel, SavedTunnelsData};
+use crate::vault::at_rest;
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 
+/// A saved tunnel with the traffic of its current or most recent run.
 #[derive(Debug, Serialize, Clone)]
+pub struct TunnelListEntry {
+    #[serde(flatten)]
+    pub tunnel: SavedTunnel,
+    pub stats: TunnelStats,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct TunnelStatusChange {
     pub id: String,
     pub status: String,
     pub error: Option<String>,
+    /// Port a started local forward listens on; differs from the saved one when that was 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_port: Option<u16>,
+}
+
+/// A local forward started by `tunnel_start_local`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalForward {
+    pub runtime_id: String,
+    /// The OS-assigned port when the forward was requested on port 0.
+    pub local_port: u16,
+}
+
+/// Emits `tunnel:status-change` and the matching `state:changed` for other windows.
+pub(crate) fn emit_status_change(app: &AppHandle, change: TunnelStatusChange) {
+    let kind = match change.status.as_str() {
+        "active" => ChangeKind::Added,
+        "stopped" => ChangeKind::Removed,
+        _ => ChangeKind::Updated,
+    };
+    state_sync::notify(app, StateKind::Tunnel, &change.id, kind);
+    let _ = app.emit("tunnel:status-change", change);
 }
 
 fn connection_has_live_session(
@@ -41,7 +78,7 @@ pub(crate) async fn reconcile_stale_tunnel_runtime(
         return;
     }
 
-    let saved_data: SavedTunnelsData = match std::fs::read_to_string(&file_path)
+    let saved_data: SavedTunnelsData = match at_rest::read_to_string(&file_path)
         .ok()
         .and_then(|raw| serde_json::from_str(&raw).ok())
     {
@@ -95,12 +132,13 @@ pub(crate) async fn reconcile_stale_tunnel_runtime(
 
     for tunnel in stale_tunnels {
         let _ = state.tunnel_manager.stop_tunnel(None, &tunnel).await;
-        let _ = app.emit(
-            "tunnel:status-change",
+        emit_status_change(
+            app,
             TunnelStatusChange {
                 id: tunnel.id,
                 status: "stopped".to_string(),
                 error: None,
+                local_port: None,
             },
         );
     }
@@ -192,18 +230,21 @@ async fn apply_runtime_tunnel_status(
         )
     };
 
+    let degraded = state.tunnel_manager.degraded_tunnels().await;
     for tunnel in tunnels.iter_mut() {
         let has_session = session_alive_by_connection
             .get(&tunnel.connection_id)
             .copied()
             .unwrap_or(false);
         tunnel.status = Some(
-            if has_session
-                && tunnel_is_active_runtime(tunnel, &local_runtime_keys, &remote_runtime_keys)
+            if !has_session
+                || !tunnel_is_active_runtime(tunnel, &local_runtime_keys, &remote_runtime_keys)
             {
-                "active".to_string()
-            } else {
                 "stopped".to_string()
+            } else if degraded.contains(&tunnel.id) {
+                "degraded".to_string()
+            } else {
+                "active".to_string()
             },
         );
     }
@@ -224,8 +265,8 @@ pub(crate) async fn stop_tunnels_for_connections(
         return Ok(());
     }
 
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
     let connection_id_set: HashSet<&str> = connection_ids.iter().map(String::as_str).collect();
     let tunnels_for_connection: Vec<SavedTunnel> = saved_data
         .tunnels
@@ -265,12 +306,13 @@ pub(crate) async fn stop_tunnels_for_connections(
             Ok(()) => ("stopped".to_string(), None),
             Err(error) => ("error".to_string(), Some(error.to_string())),
         };
-        let _ = app.emit(
-            "tunnel:status-change",
+        emit_status_change(
+            app,
             TunnelStatusChange {
                 id: tunnel.id,
                 status,
                 error,
+                local_port: None,
             },
         );
     }
@@ -278,21 +320,53 @@ pub(crate) async fn stop_tunnels_for_connections(
     Ok(())
 }
 
+/// `ssh`-style forward spec for the audit trail, e.g. `-L 127.0.0.1:8080:db:5432`.
+fn forward_spec(kind: &str, bind: Option<&str>, port: u16, target: Option<(&str, u16)>) -> String {
+    let flag = match kind {
+        "local" => "-L",
+        "dynamic" => "-D",
+        _ => "-R",
+    };
+    let listen = match bind {
+        Some(bind) => format!("{}:{}", bind, port),
+        None => port.to_string(),
+    };
+    match target.filter(|_| kind == "local" || kind == "remote") {
+        Some((host, target_port)) => format!("{} {}:{}:{}", flag, listen, host, target_port),
+        None => format!("{} {}", flag, listen),
+    }
+}
+
+async fn audit_tunnel(app: &AppHandle, connection_id: &str, runtime_id: &str, spec: String) {
+    let event = crate::audit::AuditEvent::new(
+        connection_id,
+        crate::audit::AuditKind::Tunnel,
+        spec,
+        serde_json::json!({ "tunnelId": runtime_id }),
+    );
+    crate::commands::audit_record(app, event).await;
+}
+
+/// Starts an ad-hoc local forward. With `local_port` 0 the OS picks a free port;
+/// starting the same forward again returns the running one and its port.
 #[tauri::command]
 pub async fn tunnel_start_local(
+    app: AppHandle,
     connection_id: String,
     local_port: u16,
     remote_host: String,
     remote_port: u16,
     bind_address: Option<String>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<LocalForward, String> {
     let session = {
         let connections = state.connections.lock().await;
         connections
             .get(&connection_id)
             .and_then(|c| c.session.clone())
-            .ok_or_else(|| format!("Connection {} not found", connection_id))?
+            .ok_or_else(|| {
+                crate::redact::error(format!("Connection {} not found", connection_id))
+            })?
     };
 
     let bind_addr = bind_address.unwrap_or_else(|| "127.0.0.1".to_string());
@@ -308,19 +382,63 @@ pub async fn tunnel_start_local(
         .tunnel_manager
         .start_local_forwarding(
             session,
-            connection_id,
+            connection_id.clone(),
             runtime_id,
-            bind_addr,
+            bind_addr.clone(),
             local_port,
-            remote_host,
+            remote_host.clone(),
             remote_port,
         )
         .await;
-    res.map_err(|e| e.to_string())
+    let runtime_id = res.map_err(|e| port_conflict::command_error(&e))?;
+    let local_port = state.tunnel_manager.bound_port(&runtime_id, local_port);
+    let spec = forward_spec(
+        "local",
+        Some(&bind_addr),
+        local_port,
+        Some((&remote_host, remote_port)),
+    );
+    audit_tunnel(&app, &connection_id, &runtime_id, spec).await;
+
+    emit_status_change(
+        &app,
+        TunnelStatusChange {
+            id: runtime_id.clone(),
+            status: "active".to_string(),
+            error: None,
+            local_port: Some(local_port),
+        },
+    );
+    Ok(LocalForward {
+        runtime_id,
+        local_port,
+    })
+}
+
+/// Pre-flight check for a local forward's port: the conflict, if something
+/// else already listens there.
+#[tauri::command]
+pub async fn tunnel_check_port(
+    local_port: u16,
+    bind_address: Option<String>,
+) -> Result<Option<PortConflict>, String> {
+    let bind_addr = bind_address.unwrap_or_else(|| "127.0.0.1".to_string());
+    Ok(port_conflict::check(&bind_addr, local_port).await)
+}
+
+/// A free port after `local_port`, for offering an alternative.
+#[tauri::command]
+pub async fn tunnel_suggest_port(
+    local_port: u16,
+    bind_address: Option<String>,
+) -> Result<Option<u16>, String> {
+    let bind_addr = bind_address.unwrap_or_else(|| "127.0.0.1".to_string());
+    Ok(port_conflict::suggest_port(&bind_addr, local_port).await)
 }
 
 #[tauri::command]
 pub async fn tunnel_start_remote(
+    app: AppHandle,
     connection_id: String,
     remote_port: u16,
     local_host: String,
@@ -333,7 +451,9 @@ pub async fn tunnel_start_remote(
         connections
             .get(&connection_id)
             .and_then(|c| c.session.clone())
-            .ok_or_else(|| format!("Connection {} not found", connection_id))?
+            .ok_or_else(|| {
+                crate::redact::error(format!("Connection {} not found", connection_id))
+            })?
     };
 
     let bind_addr = bind_address.unwrap_or_else(|| "0.0.0.0".to_string());
@@ -345,6 +465,14 @@ pub async fn tunnel_start_remote(
         local_port
     );
 
+    let spec = forward_spec(
+        "remote",
+        Some(&bind_addr),
+        remote_port,
+        Some((&local_host, local_port)),
+    );
+    let audit_connection_id = connection_id.clone();
+
     let res: anyhow::Result<String> = state
         .tunnel_manager
         .start_remote_forwarding(
@@ -357,7 +485,148 @@ pub async fn tunnel_start_remote(
             local_port,
         )
         .await;
-    res.map_err(|e| e.to_string())
+    let runtime_id = res.map_err(crate::redact::error)?;
+    audit_tunnel(&app, &audit_connection_id, &runtime_id, spec).await;
+    Ok(runtime_id)
+}
+
+/// Starts a reverse SOCKS proxy (`ssh -R remote_port`): SOCKS5 clients on the
+/// server connect to `remote_port` and reach their destinations through this
+/// machine. Binds the server's loopback unless `bind_address` is given.
+#[tauri::command]
+pub async fn tunnel_start_remote_dynamic(
+    app: AppHandle,
+    connection_id: String,
+    remote_port: u16,
+    bind_address: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let session = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&connection_id)
+            .and_then(|c| c.session.clone())
+            .ok_or_else(|| {
+                crate::redact::error(format!("Connection {} not found", connection_id))
+            })?
+    };
+
+    let bind_addr = bind_address.unwrap_or_else(|| "127.0.0.1".to_string());
+    let runtime_id = format!("remote-dynamic:{}:{}", connection_id, remote_port);
+    let spec = forward_spec("remote-dynamic", Some(&bind_addr), remote_port, None);
+    let audit_connection_id = connection_id.clone();
+
+    let runtime_id = state
+        .tunnel_manager
+        .start_remote_dynamic_forwarding(session, connection_id, runtime_id, bind_addr, remote_port)
+        .await
+        .map_err(crate::redact::error)?;
+    audit_tunnel(&app, &audit_connection_id, &runtime_id, spec).await;
+    Ok(runtime_id)
+}
+
+/// Uses `connection_id`'s server as this machine's exit: a SOCKS forward on
+/// `socks_port` (1080 by default, 0 for any free port) plus a DNS forwarder
+/// to the server's resolver on `dns_port` (any free port by default).
+/// `nameserver` overrides the resolver read from the server's
+/// `/etc/resolv.conf`. Replaces any exit already running.
+#[tauri::command]
+pub async fn exit_node_start(
+    app: AppHandle,
+    connection_id: String,
+    socks_port: Option<u16>,
+    dns_port: Option<u16>,
+    nameserver: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ExitNode, String> {
+    let session = {
+        let connections = state.connections.lock().await;
+        connections
+            .get(&connection_id)
+            .and_then(|c| c.session.clone())
+            .ok_or_else(|| {
+                crate::redact::error(format!("Connection {} not found", connection_id))
+            })?
+    };
+
+    let nameserver = match nameserver.filter(|server| !server.trim().is_empty()) {
+        Some(server) => Some(server.trim().to_string()),
+        None => match crate::commands::exec_remote_capture(
+            &connection_id,
+            "cat /etc/resolv.conf",
+            &state,
+        )
+        .await
+        {
+            Ok((0, stdout, _)) => Some(exit_node::nameserver_or_fallback(
+                &String::from_utf8_lossy(&stdout),
+            )),
+            // No resolv.conf (e.g. a Windows server): SOCKS only.
+            Ok(_) => None,
+            Err(error) => {
+                crate::log_error!(
+                    "[TUNNEL] Exit resolver lookup on {} failed: {}",
+                    connection_id,
+                    error
+                );
+                None
+            }
+        },
+    };
+
+    let data_dir = get_data_dir(&app);
+    let exit = exit_node::start(
+        &state.tunnel_manager,
+        &data_dir,
+        session,
+        &connection_id,
+        socks_port.unwrap_or(exit_node::DEFAULT_SOCKS_PORT),
+        dns_port.unwrap_or(0),
+        nameserver,
+    )
+    .await
+    .map_err(|e| port_conflict::command_error(&e))?;
+
+    emit_status_change(
+        &app,
+        TunnelStatusChange {
+            id: exit_node::socks_runtime_id(&connection_id),
+            status: "active".to_string(),
+            error: None,
+            local_port: Some(exit.socks_port),
+        },
+    );
+    Ok(exit)
+}
+
+/// Tears down the running exit; returns it, or `None` if none was running.
+#[tauri::command]
+pub async fn exit_node_stop(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<ExitNode>, String> {
+    let exit = exit_node::stop(&state.tunnel_manager, &get_data_dir(&app)).await;
+    if let Some(exit) = &exit {
+        emit_exit_stopped(&app, exit);
+    }
+    Ok(exit)
+}
+
+#[tauri::command]
+pub async fn exit_node_status() -> Result<Option<ExitNode>, String> {
+    Ok(exit_node::active())
+}
+
+pub(crate) fn emit_exit_stopped(app: &AppHandle, exit: &ExitNode) {
+    emit_status_change(
+        app,
+        TunnelStatusChange {
+            id: exit_node::socks_runtime_id(&exit.connection_id),
+            status: "stopped".to_string(),
+            error: None,
+            local_port: None,
+        },
+    );
 }
 
 #[tauri::command]
@@ -371,8 +640,8 @@ pub async fn tunnel_stop(
     if !file_path.exists() {
         return Ok(());
     }
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
 
     let tunnel = saved_data
         .tunnels
@@ -387,7 +656,7 @@ pub async fn tunnel_stop(
             .and_then(|c| c.session.clone())
     };
 
-    println!(
+    crate::log_info!(
         "[TUNNEL CMD] Stopping tunnel: runtime_id={}",
         tunnel_runtime_id(&tunnel)
     );
@@ -397,26 +666,28 @@ pub async fn tunnel_stop(
         .await;
 
     if let Err(ref e) = res {
-        let _ = app.emit(
-            "tunnel:status-change",
+        emit_status_change(
+            &app,
             TunnelStatusChange {
                 id: id.clone(),
                 status: "error".to_string(),
                 error: Some(e.to_string()),
+                local_port: None,
             },
         );
     } else {
-        let _ = app.emit(
-            "tunnel:status-change",
+        emit_status_change(
+            &app,
             TunnelStatusChange {
                 id: id.clone(),
                 status: "stopped".to_string(),
                 error: None,
+                local_port: None,
             },
         );
     }
 
-    res.map_err(|e| e.to_string())
+    res.map_err(crate::redact::error)
 }
 
 #[tauri::command]
@@ -424,7 +695,7 @@ pub async fn tunnel_list(
     app: AppHandle,
     state: State<'_, AppState>,
     connection_id: String,
-) -> Result<Vec<SavedTunnel>, String> {
+) -> Result<Vec<TunnelListEntry>, String> {
     let data_dir = get_data_dir(&app);
     let file_path = data_dir.join("tunnels.json");
 
@@ -432,8 +703,8 @@ pub async fn tunnel_list(
         return Ok(vec![]);
     }
 
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
 
     let mut tunnels: Vec<SavedTunnel> = saved_data
         .tunnels
@@ -443,7 +714,104 @@ pub async fn tunnel_list(
 
     apply_runtime_tunnel_status(&app, &state, &mut tunnels).await;
 
-    Ok(tunnels)
+    Ok(tunnels
+        .into_iter()
+        .map(|tunnel| TunnelListEntry {
+            stats: state.tunnel_manager.stats(&tunnel),
+            tunnel,
+        })
+        .collect())
+}
+
+/// Bytes in/out, connection counts and last activity of a saved tunnel.
+#[tauri::command]
+pub async fn tunnel_stats(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<TunnelStats, String> {
+    let file_path = get_data_dir(&app).join("tunnels.json");
+    if !file_path.exists() {
+        return Err("Tunnels file not found".to_string());
+    }
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
+    let tunnel = saved_data
+        .tunnels
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Tunnel not found".to_string())?;
+
+    Ok(state.tunnel_manager.stats(&tunnel))
+}
+
+const DEFAULT_LOG_LIMIT: usize = 50;
+
+/// Finished connections of a saved tunnel's current or most recent run, newest first.
+#[tauri::command]
+pub async fn tunnel_logs(
+    app: AppHandle,
+    id: String,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ConnectionRecord>, String> {
+    let file_path = get_data_dir(&app).join("tunnels.json");
+    if !file_path.exists() {
+        return Err("Tunnels file not found".to_string());
+    }
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
+    let tunnel = saved_data
+        .tunnels
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or_else(|| "Tunnel not found".to_string())?;
+
+    Ok(state
+        .tunnel_manager
+        .connection_log(&tunnel, limit.unwrap_or(DEFAULT_LOG_LIMIT)))
+}
+
+/// Starts or stops emitting `tunnel:connection` for every finished forwarded
+/// connection. Off by default; meant for debugging flaky forwards.
+#[tauri::command]
+pub async fn tunnel_watch_connections(
+    app: AppHandle,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let manager = &state.tunnel_manager;
+    let mut watch = manager
+        .connection_watch
+        .lock()
+        .map_err(crate::redact::error)?;
+    if let Some(handle) = watch.take() {
+        if enabled && !handle.is_finished() {
+            *watch = Some(handle);
+            return Ok(());
+        }
+        handle.abort();
+    }
+    if !enabled {
+        return Ok(());
+    }
+
+    let mut events = manager.subscribe_connections();
+    let handle = tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let _ = app.emit("tunnel:connection", event);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    crate::log_error!("[TUNNEL] Dropped {} connection events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    *watch = Some(handle.abort_handle());
+    Ok(())
 }
 
 #[tauri::command]
@@ -470,18 +838,19 @@ fn tunnel_is_active_runtime(
 
 #[tauri::command]
 pub async fn tunnel_save(app: AppHandle, tunnel_val: serde_json::Value) -> Result<(), String> {
-    let mut tunnel: SavedTunnel = serde_json::from_value(tunnel_val).map_err(|e| e.to_string())?;
+    let mut tunnel: SavedTunnel =
+        serde_json::from_value(tunnel_val).map_err(crate::redact::error)?;
     let data_dir = get_data_dir(&app);
     if !data_dir.exists() {
-        std::fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&data_dir).map_err(crate::redact::error)?;
     }
     let file_path = data_dir.join("tunnels.json");
 
     let _guard = crate::sync::domain_tunnels::TUNNELS_MUTATION_LOCK
         .lock()
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
     let mut saved = crate::sync::domain_tunnels::load_saved_tunnels(&file_path)
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
 
     let now_ms = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -502,7 +871,7 @@ pub async fn tunnel_save(app: AppHandle, tunnel_val: serde_json::Value) -> Resul
     }
 
     crate::sync::domain_tunnels::write_saved_tunnels_atomic(&file_path, &saved)
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
 
     Ok(())
 }
@@ -518,14 +887,14 @@ pub async fn tunnel_delete(app: AppHandle, id: String) -> Result<(), String> {
 
     let _guard = crate::sync::domain_tunnels::TUNNELS_MUTATION_LOCK
         .lock()
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
     let mut saved = crate::sync::domain_tunnels::load_saved_tunnels(&file_path)
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
 
     saved.tunnels.retain(|t| t.id != id);
 
     crate::sync::domain_tunnels::write_saved_tunnels_atomic(&file_path, &saved)
-        .map_err(|error| error.to_string())?;
+        .map_err(crate::redact::error)?;
 
     Ok(())
 }
@@ -541,8 +910,8 @@ pub async fn tunnel_start(
     if !file_path.exists() {
         return Err("Tunnels file not found".to_string());
     }
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
 
     let tunnel = saved_data
         .tunnels
@@ -550,6 +919,48 @@ pub async fn tunnel_start(
         .find(|t| t.id == id)
         .ok_or_else(|| "Tunnel not found".to_string())?;
 
+    start_saved_tunnel(&app, &state, tunnel).await
+}
+
+/// Starts the connection's tunnels marked `auto_start`, right after it connects.
+/// Failures are reported through `tunnel:status-change` and never fail the connect.
+pub(crate) async fn start_auto_tunnels(app: &AppHandle, state: &AppState, connection_id: &str) {
+    let file_path = get_data_dir(app).join("tunnels.json");
+    if !file_path.exists() {
+        return;
+    }
+    let saved_data: SavedTunnelsData = match at_rest::read_to_string(&file_path)
+        .map_err(crate::redact::error)
+        .and_then(|raw| serde_json::from_str(&raw).map_err(crate::redact::error))
+    {
+        Ok(data) => data,
+        Err(e) => {
+            crate::log_error!("[TUNNEL] Failed to read tunnels for auto-start: {}", e);
+            return;
+        }
+    };
+
+    for tunnel in saved_data
+        .tunnels
+        .into_iter()
+        .filter(|t| t.connection_id == connection_id && t.auto_start.unwrap_or(false))
+    {
+        let name = tunnel.name.clone();
+        match start_saved_tunnel(app, state, tunnel).await {
+            Ok(_) => crate::log_info!("[TUNNEL] Auto-started '{}' for {}", name, connection_id),
+            Err(e) => crate::log_error!("[TUNNEL] Failed to auto-start '{}': {}", name, e),
+        }
+    }
+}
+
+/// Starts `tunnel` on its connection's session and emits `tunnel:status-change`.
+/// Starting a tunnel that is already running is a no-op reported as active.
+async fn start_saved_tunnel(
+    app: &AppHandle,
+    state: &AppState,
+    tunnel: SavedTunnel,
+) -> Result<String, String> {
+    let id = tunnel.id.clone();
     let session = {
         let connections = state.connections.lock().await;
         connections
@@ -563,79 +974,82 @@ pub async fn tunnel_start(
             })?
     };
 
-    let runtime_id = tunnel_runtime_id(&tunnel);
-    let res = if tunnel.tunnel_type == "dynamic" {
-        let bind_addr = tunnel
-            .bind_address
-            .clone()
-            .unwrap_or_else(|| "127.0.0.1".to_string());
-        state
-            .tunnel_manager
-            .start_dynamic_forwarding(
-                session,
-                tunnel.connection_id.clone(),
-                runtime_id,
-                bind_addr,
-                tunnel.local_port,
-            )
-            .await
-    } else if tunnel.tunnel_type == "local" {
-        let bind_addr = tunnel
-            .bind_address
-            .clone()
-            .unwrap_or_else(|| "127.0.0.1".to_string());
-        state
-            .tunnel_manager
-            .start_local_forwarding(
-                session,
-                tunnel.connection_id.clone(),
-                runtime_id,
-                bind_addr,
-                tunnel.local_port,
-                tunnel.remote_host.clone(),
-                tunnel.remote_port,
-            )
-            .await
-    } else {
-        let bind_addr = tunnel
-            .bind_address
-            .clone()
-            .unwrap_or_else(|| "0.0.0.0".to_string());
-        state
-            .tunnel_manager
-            .start_remote_forwarding(
-                session,
-                tunnel.connection_id.clone(),
-                runtime_id,
-                bind_addr,
-                tunnel.remote_port,
-                tunnel.remote_host.clone(),
-                tunnel.local_port,
-            )
-            .await
-    };
+    let res = state.tunnel_manager.start_tunnel(session, &tunnel).await;
+    if res.is_ok() {
+        state.tunnel_manager.supervise(&tunnel).await;
+    }
 
     if let Err(ref e) = res {
-        let _ = app.emit(
-            "tunnel:status-change",
+        emit_status_change(
+            app,
             TunnelStatusChange {
                 id: id.clone(),
                 status: "error".to_string(),
                 error: Some(e.to_string()),
+                local_port: None,
             },
         );
     } else {
-        let _ = app.emit(
-            "tunnel:status-change",
+        let local_port = state.tunnel_manager.local_port_of(&tunnel);
+        emit_status_change(
+            app,
             TunnelStatusChange {
                 id: id.clone(),
                 status: "active".to_string(),
                 error: None,
+                local_port,
             },
         );
+        let port = match tunnel.tunnel_type.as_str() {
+            "local" | "dynamic" => local_port.unwrap_or(tunnel.local_port),
+            _ => tunnel.remote_port,
+        };
+        let target_port = match tunnel.tunnel_type.as_str() {
+            "local" => tunnel.remote_port,
+            _ => tunnel.local_port,
+        };
+        let spec = forward_spec(
+            &tunnel.tunnel_type,
+            tunnel.bind_address.as_deref(),
+            port,
+            Some((&tunnel.remote_host, target_port)),
+        );
+        audit_tunnel(app, &tunnel.connection_id, &id, format!("{} ({})", tunnel.name, spec))
+            .await;
     }
 
-    res.map_err(|e| e.to_string())
+    res.map_err(|e| port_conflict::command_error(&e))
+}
+
+/// Running saved tunnels, plus ad-hoc forwards under their runtime id.
+pub(crate) async fn running_tunnels(app: &AppHandle, state: &AppState) -> Vec<TunnelSummary> {
+    let mut tunnels = at_rest::read_to_string(&get_data_dir(app).join("tunnels.json"))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<SavedTunnelsData>(&raw).ok())
+        .map(|data| data.tunnels)
+        .unwrap_or_default();
+    apply_runtime_tunnel_status(app, state, &mut tunnels).await;
+
+    let saved_runtime_ids: HashSet<String> = tunnels.iter().map(tunnel_runtime_id).collect();
+    let mut running: Vec<TunnelSummary> = tunnels
+        .into_iter()
+        .filter(|tunnel| tunnel.status.as_deref() != Some("stopped"))
+        .map(|tunnel| TunnelSummary {
+            id: tunnel.id,
+            status: tunnel.status.unwrap_or_default(),
+        })
+        .collect();
+    let local_listeners = state.tunnel_manager.local_listeners.lock().await;
+    running.extend(
+        local_listeners
+            .keys()
+            .filter(|runtime_id| !saved_runtime_ids.contains(*runtime_id))
+            .map(|runtime_id| TunnelSummary {
+                id: runtime_id.clone(),
+                status: "active".to_string(),
+            }),
+    );
+    running
 }
 
 #[tauri::command]
@@ -650,8 +1064,8 @@ pub async fn tunnel_get_all(
         return Ok(vec![]);
     }
 
-    let data = std::fs::read_to_string(file_path).map_err(|e| e.to_string())?;
-    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    let data = at_rest::read_to_string(&file_path).map_err(crate::redact::error)?;
+    let saved_data: SavedTunnelsData = serde_json::from_str(&data).map_err(crate::redact::error)?;
 
     let mut tunnels = saved_data.tunnels;
 
@@ -1,46 +1,119 @@
 //! Dynamic (SOCKS5) port forwarding — local proxy through an SSH session.
+//!
+//! Also the reverse direction (`ssh -R port` without a destination): the SOCKS
+//! client connects to a port on the server, arrives here over a forwarded
+//! channel, and its destination is resolved and dialed from this machine.
 
 use crate::ssh::Client;
+use crate::tunnels::metrics::{ConnectionGuard, CountingStream, TunnelCounters};
 use crate::tunnels::session_failure::{is_ssh_session_fatal_error, SessionFailureSender};
 use crate::tunnels::socks5::{
     self, connect_success_reply, error_reply, method_selection_reply, parse_connect_request,
     socks5_error_to_reply, Socks5Error, ATYP_DOMAIN, ATYP_IPV4, ATYP_IPV6, CMD_CONNECT, VERSION,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use russh::client::Handle;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::sync::{broadcast, Mutex};
 
 const SOCKS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Dialing a reverse SOCKS destination from this machine.
+const REVERSE_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub async fn handle_socks5_client(
     mut client: TcpStream,
     session: Arc<Mutex<Handle<Client>>>,
     connection_id: String,
+    counters: TunnelCounters,
     failure_tx: SessionFailureSender,
     stop_tx: broadcast::Sender<()>,
     mut cancel: broadcast::Receiver<()>,
 ) {
+    let source = client.peer_addr().ok().map(|addr| addr.to_string());
+    let connection = counters.open_connection(source);
     if let Err(error) = run_socks5_client(
         &mut client,
         session,
         &connection_id,
+        &connection,
         &failure_tx,
         &stop_tx,
         &mut cancel,
     )
     .await
     {
-        eprintln!("[TUNNEL][SOCKS] client handler error: {error}");
+        crate::log_error!("[TUNNEL][SOCKS] client handler error: {error}");
+        connection.fail(&error);
     }
 }
 
+/// Serves one SOCKS5 client of a remote dynamic forward over its forwarded channel.
+pub async fn handle_reverse_socks5_client<S>(mut client: S, connection: ConnectionGuard)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    if let Err(error) = run_reverse_socks5_client(&mut client, &connection).await {
+        crate::log_error!("[TUNNEL][SOCKS] reverse client error: {error}");
+        connection.fail(&error);
+    }
+}
+
+async fn run_reverse_socks5_client<S>(client: &mut S, connection: &ConnectionGuard) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Never fires: a reverse client ends when its channel closes.
+    let (_no_cancel, mut cancel) = broadcast::channel::<()>(1);
+    let handshake = tokio::time::timeout(SOCKS_HANDSHAKE_TIMEOUT, negotiate(client, &mut cancel));
+    let target = match handshake.await {
+        Ok(Ok(Some(target))) => target,
+        Ok(Ok(None)) | Err(_) => return Ok(()),
+        Ok(Err(error)) => return Err(error),
+    };
+
+    let dial = tokio::time::timeout(
+        REVERSE_CONNECT_TIMEOUT,
+        TcpStream::connect((target.host.as_str(), target.port)),
+    )
+    .await;
+    let stream = match dial {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(error)) => {
+            let _ = client
+                .write_all(&error_reply(socks5::io_error_to_reply(&error)))
+                .await;
+            return Err(anyhow!(
+                "connect to {}:{} failed: {error}",
+                target.host,
+                target.port
+            ));
+        }
+        Err(_) => {
+            let _ = client
+                .write_all(&error_reply(socks5::REP_HOST_UNREACHABLE))
+                .await;
+            return Err(anyhow!(
+                "connect to {}:{} timed out",
+                target.host,
+                target.port
+            ));
+        }
+    };
+
+    client.write_all(&connect_success_reply()).await?;
+    let mut stream = CountingStream::new(stream, connection);
+    tokio::io::copy_bidirectional(client, &mut stream)
+        .await
+        .map_err(|error| anyhow!("relay to {}:{} — {error}", target.host, target.port))?;
+    Ok(())
+}
+
 /// Returns `Ok(true)` when bytes were read, `Ok(false)` when cancelled.
-async fn read_exact_or_cancel(
-    client: &mut TcpStream,
+async fn read_exact_or_cancel<S: AsyncRead + Unpin>(
+    client: &mut S,
     buf: &mut [u8],
     cancel: &mut broadcast::Receiver<()>,
 ) -> Result<bool> {
@@ -57,36 +130,14 @@ async fn run_socks5_client(
     client: &mut TcpStream,
     session: Arc<Mutex<Handle<Client>>>,
     connection_id: &str,
+    connection: &ConnectionGuard,
     failure_tx: &SessionFailureSender,
     stop_tx: &broadcast::Sender<()>,
     cancel: &mut broadcast::Receiver<()>,
 ) -> Result<()> {
     let handshake = async {
-        let mut greeting = [0u8; 2];
-        if !read_exact_or_cancel(client, &mut greeting, cancel).await? {
+        let Some(target) = negotiate(client, cancel).await? else {
             return Ok(());
-        }
-
-        let nmethods = greeting[1] as usize;
-        let mut methods = vec![0u8; nmethods];
-        if !read_exact_or_cancel(client, &mut methods, cancel).await? {
-            return Ok(());
-        }
-
-        let mut full_greeting = greeting.to_vec();
-        full_greeting.extend_from_slice(&methods);
-        socks5::validate_client_greeting(&full_greeting)?;
-
-        client.write_all(&method_selection_reply()).await?;
-
-        let target = match read_connect_target(client, cancel).await {
-            Ok(target) => target,
-            Err(error) => {
-                let _ = client
-                    .write_all(&error_reply(socks5_error_to_reply(&error)))
-                    .await;
-                return Err(anyhow::Error::new(error));
-            }
         };
 
         let channel = {
@@ -116,7 +167,7 @@ async fn run_socks5_client(
                     .write_all(&error_reply(socks5::REP_GENERAL_FAILURE))
                     .await;
                 if is_ssh_session_fatal_error(&error) {
-                    println!(
+                    crate::log_info!(
                         "[TUNNEL][SOCKS] SSH session lost for {}; stopping tunnels",
                         connection_id
                     );
@@ -130,14 +181,16 @@ async fn run_socks5_client(
         client.write_all(&connect_success_reply()).await?;
 
         let mut stream = channel.into_stream();
+        let mut client = CountingStream::new(&mut *client, connection);
         tokio::select! {
-            result = tokio::io::copy_bidirectional(client, &mut stream) => {
+            result = tokio::io::copy_bidirectional(&mut client, &mut stream) => {
                 if let Err(error) = result {
-                    eprintln!(
+                    crate::log_error!(
                         "[TUNNEL][SOCKS] relay error to {}:{} — {error}",
                         target.host,
                         target.port
                     );
+                    connection.fail(&error);
                 }
             }
             _ = cancel.recv() => {}
@@ -152,8 +205,44 @@ async fn run_socks5_client(
     }
 }
 
-async fn read_connect_target(
-    client: &mut TcpStream,
+/// Greeting, method selection and CONNECT request. `None` when cancelled.
+async fn negotiate<S>(
+    client: &mut S,
+    cancel: &mut broadcast::Receiver<()>,
+) -> Result<Option<socks5::ConnectTarget>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut greeting = [0u8; 2];
+    if !read_exact_or_cancel(client, &mut greeting, cancel).await? {
+        return Ok(None);
+    }
+
+    let nmethods = greeting[1] as usize;
+    let mut methods = vec![0u8; nmethods];
+    if !read_exact_or_cancel(client, &mut methods, cancel).await? {
+        return Ok(None);
+    }
+
+    let mut full_greeting = greeting.to_vec();
+    full_greeting.extend_from_slice(&methods);
+    socks5::validate_client_greeting(&full_greeting)?;
+
+    client.write_all(&method_selection_reply()).await?;
+
+    match read_connect_target(client, cancel).await {
+        Ok(target) => Ok(Some(target)),
+        Err(error) => {
+            let _ = client
+                .write_all(&error_reply(socks5_error_to_reply(&error)))
+                .await;
+            Err(anyhow::Error::new(error))
+        }
+    }
+}
+
+async fn read_connect_target<S: AsyncRead + Unpin>(
+    client: &mut S,
     cancel: &mut broadcast::Receiver<()>,
 ) -> Result<socks5::ConnectTarget, Socks5Error> {
     let mut header = [0u8; 4];
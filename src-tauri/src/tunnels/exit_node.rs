@@ -0,0 +1,368 @@
+//! "Use this server as my exit": a temporary SOCKS + DNS profile over one
+//! SSH connection.
+//!
+//! Starting an exit opens a dynamic SOCKS forward on loopback and a local DNS
+//! forwarder that relays each UDP query as DNS-over-TCP through a
+//! `direct-tcpip` channel to the server's own resolver, so names resolve the
+//! way they do on the server. The proxy variables pointing at the forward are
+//! written to shell snippets in the data directory and set on every local
+//! terminal spawned while the exit is up. Only one exit is active at a time;
+//! stopping it closes both listeners and removes the snippets.
+
+use super::TunnelManager;
+use crate::ssh::Client;
+use anyhow::{anyhow, Context, Result};
+use russh::client::Handle;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UdpSocket;
+use tokio::sync::Mutex;
+
+/// SOCKS port used when the caller does not pick one.
+pub const DEFAULT_SOCKS_PORT: u16 = 1080;
+/// Resolver tried when the server's `/etc/resolv.conf` names none.
+const FALLBACK_NAMESERVER: &str = "127.0.0.53";
+/// How long one relayed DNS query may take end to end.
+const DNS_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest DNS message relayed (EDNS0 payloads stay well below this).
+const MAX_DNS_MESSAGE: usize = 4096;
+/// Hosts that must never go through the exit.
+const NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+/// The running exit, if any.
+static ACTIVE: LazyLock<std::sync::Mutex<Option<ExitNode>>> =
+    LazyLock::new(|| std::sync::Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitNode {
+    pub connection_id: String,
+    pub socks_port: u16,
+    /// `None` when no DNS forwarder could be started.
+    pub dns_port: Option<u16>,
+    /// Resolver on the server side that DNS queries are relayed to.
+    pub nameserver: Option<String>,
+    /// Proxy variables for programs that should use the exit.
+    pub env: BTreeMap<String, String>,
+    /// `source`-able POSIX shell snippet setting `env`.
+    pub shell_env_file: String,
+    /// PowerShell snippet setting `env`.
+    pub powershell_env_file: String,
+}
+
+pub fn socks_runtime_id(connection_id: &str) -> String {
+    format!("exit:{}", connection_id)
+}
+
+pub fn dns_runtime_id(connection_id: &str) -> String {
+    format!("exit-dns:{}", connection_id)
+}
+
+/// The active exit.
+pub fn active() -> Option<ExitNode> {
+    ACTIVE.lock().ok()?.clone()
+}
+
+/// Proxy variables of the active exit, for locally spawned processes.
+pub fn active_env() -> BTreeMap<String, String> {
+    active().map(|exit| exit.env).unwrap_or_default()
+}
+
+/// Proxy variables for a SOCKS forward on `127.0.0.1:socks_port`. `socks5h`
+/// makes clients hand host names to the proxy, so they resolve on the server.
+pub fn proxy_env(socks_port: u16) -> BTreeMap<String, String> {
+    let proxy = format!("socks5h://127.0.0.1:{}", socks_port);
+    let mut env = BTreeMap::new();
+    for key in ["ALL_PROXY", "HTTP_PROXY", "HTTPS_PROXY"] {
+        env.insert(key.to_string(), proxy.clone());
+        env.insert(key.to_lowercase(), proxy.clone());
+    }
+    env.insert("NO_PROXY".to_string(), NO_PROXY.to_string());
+    env.insert("no_proxy".to_string(), NO_PROXY.to_string());
+    env
+}
+
+pub fn posix_script(env: &BTreeMap<String, String>) -> String {
+    let mut script =
+        String::from("# Zync exit node: source this file to route traffic via the server.\n");
+    for (key, value) in env {
+        script.push_str(&format!(
+            "export {}='{}'\n",
+            key,
+            value.replace('\'', "'\\''")
+        ));
+    }
+    script.push_str(&format!(
+        "# Undo with: unset {}\n",
+        env.keys().cloned().collect::<Vec<_>>().join(" ")
+    ));
+    script
+}
+
+pub fn powershell_script(env: &BTreeMap<String, String>) -> String {
+    let mut script =
+        String::from("# Zync exit node: dot-source this file to route traffic via the server.\n");
+    // Environment names are case-insensitive on Windows.
+    for (key, value) in env.iter().filter(|(key, _)| key.to_uppercase() == **key) {
+        script.push_str(&format!("$env:{} = '{}'\n", key, value.replace('\'', "''")));
+    }
+    script
+}
+
+/// First `nameserver` of a `resolv.conf`.
+pub fn parse_nameserver(resolv_conf: &str) -> Option<String> {
+    resolv_conf.lines().find_map(|line| {
+        let line = line.split(['#', ';']).next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        (words.next() == Some("nameserver"))
+            .then(|| words.next())
+            .flatten()
+            // Scoped IPv6 resolvers (`fe80::1%eth0`) cannot be dialled by name.
+            .filter(|server| !server.contains('%'))
+            .map(str::to_string)
+    })
+}
+
+pub fn nameserver_or_fallback(resolv_conf: &str) -> String {
+    parse_nameserver(resolv_conf).unwrap_or_else(|| FALLBACK_NAMESERVER.to_string())
+}
+
+/// A DNS message framed for TCP: two-byte big-endian length, then the message.
+fn tcp_frame(message: &[u8]) -> Result<Vec<u8>> {
+    let length = u16::try_from(message.len()).map_err(|_| anyhow!("DNS message too large"))?;
+    let mut frame = Vec::with_capacity(message.len() + 2);
+    frame.extend_from_slice(&length.to_be_bytes());
+    frame.extend_from_slice(message);
+    Ok(frame)
+}
+
+/// Sends one query to `nameserver:53` over a fresh `direct-tcpip` channel.
+async fn relay_query(
+    session: &Arc<Mutex<Handle<Client>>>,
+    nameserver: &str,
+    query: &[u8],
+) -> Result<Vec<u8>> {
+    let channel = session
+        .lock()
+        .await
+        .channel_open_direct_tcpip(nameserver, 53, "127.0.0.1", 0)
+        .await
+        .context("DNS channel refused")?;
+    let mut stream = channel.into_stream();
+    stream.write_all(&tcp_frame(query)?).await?;
+    stream.flush().await?;
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length).await?;
+    let mut response = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.read_exact(&mut response).await?;
+    Ok(response)
+}
+
+/// Answers queries on `socket` until `stop` fires.
+async fn serve_dns(
+    socket: Arc<UdpSocket>,
+    session: Arc<Mutex<Handle<Client>>>,
+    nameserver: String,
+    mut stop: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut buf = vec![0u8; MAX_DNS_MESSAGE];
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => {
+                let Ok((len, peer)) = received else {
+                    continue;
+                };
+                let query = buf[..len].to_vec();
+                let socket = socket.clone();
+                let session = session.clone();
+                let nameserver = nameserver.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(
+                        DNS_QUERY_TIMEOUT,
+                        relay_query(&session, &nameserver, &query),
+                    )
+                    .await
+                    {
+                        Ok(Ok(response)) => {
+                            let _ = socket.send_to(&response, peer).await;
+                        }
+                        Ok(Err(error)) => crate::log_error!("[TUNNEL] Exit DNS query failed: {}", error),
+                        Err(_) => crate::log_error!("[TUNNEL] Exit DNS query to {} timed out", nameserver),
+                    }
+                });
+            }
+            _ = stop.recv() => break,
+        }
+    }
+}
+
+fn env_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("exit-node")
+}
+
+/// Starts the DNS forwarder on `127.0.0.1:dns_port` (0 picks a free port).
+async fn start_dns(
+    tunnel_manager: &TunnelManager,
+    session: Arc<Mutex<Handle<Client>>>,
+    connection_id: &str,
+    nameserver: String,
+    dns_port: u16,
+) -> Result<u16> {
+    let socket = UdpSocket::bind(("127.0.0.1", dns_port))
+        .await
+        .with_context(|| format!("Cannot listen for DNS on 127.0.0.1:{}", dns_port))?;
+    let port = socket.local_addr()?.port();
+    let (tx, rx) = tokio::sync::broadcast::channel(1);
+    let handle = tokio::spawn(serve_dns(Arc::new(socket), session, nameserver, rx));
+    tunnel_manager
+        .local_listeners
+        .lock()
+        .await
+        .insert(dns_runtime_id(connection_id), (handle.abort_handle(), tx));
+    Ok(port)
+}
+
+/// Starts the exit on `connection_id`, replacing any exit already running.
+/// `nameserver` is the server-side resolver, `None` to skip DNS forwarding.
+pub async fn start(
+    tunnel_manager: &TunnelManager,
+    data_dir: &Path,
+    session: Arc<Mutex<Handle<Client>>>,
+    connection_id: &str,
+    socks_port: u16,
+    dns_port: u16,
+    nameserver: Option<String>,
+) -> Result<ExitNode> {
+    stop(tunnel_manager, data_dir).await;
+
+    let socks_id = socks_runtime_id(connection_id);
+    tunnel_manager
+        .start_dynamic_forwarding(
+            session.clone(),
+            connection_id.to_string(),
+            socks_id.clone(),
+            "127.0.0.1".to_string(),
+            socks_port,
+        )
+        .await?;
+    let socks_port = tunnel_manager.bound_port(&socks_id, socks_port);
+
+    let dns_port = match &nameserver {
+        Some(server) => {
+            match start_dns(
+                tunnel_manager,
+                session,
+                connection_id,
+                server.clone(),
+                dns_port,
+            )
+            .await
+            {
+                Ok(port) => Some(port),
+                Err(error) => {
+                    crate::log_error!("[TUNNEL] Exit DNS forwarder not started: {}", error);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let env = proxy_env(socks_port);
+    let dir = env_dir(data_dir);
+    std::fs::create_dir_all(&dir)?;
+    let shell_env_file = dir.join("env.sh");
+    let powershell_env_file = dir.join("env.ps1");
+    std::fs::write(&shell_env_file, posix_script(&env))?;
+    std::fs::write(&powershell_env_file, powershell_script(&env))?;
+
+    let exit = ExitNode {
+        connection_id: connection_id.to_string(),
+        socks_port,
+        dns_port,
+        nameserver: dns_port.and(nameserver),
+        env,
+        shell_env_file: shell_env_file.to_string_lossy().to_string(),
+        powershell_env_file: powershell_env_file.to_string_lossy().to_string(),
+    };
+    crate::log_info!(
+        "[TUNNEL] Exit via {} on SOCKS port {} (DNS {:?})",
+        connection_id,
+        socks_port,
+        exit.dns_port
+    );
+    *ACTIVE.lock().unwrap() = Some(exit.clone());
+    Ok(exit)
+}
+
+/// Tears down the active exit; returns it, or `None` if none was running.
+pub async fn stop(tunnel_manager: &TunnelManager, data_dir: &Path) -> Option<ExitNode> {
+    let exit = ACTIVE.lock().unwrap().take()?;
+    for runtime_id in [
+        socks_runtime_id(&exit.connection_id),
+        dns_runtime_id(&exit.connection_id),
+    ] {
+        tunnel_manager.stop_listener(&runtime_id).await;
+    }
+    let _ = std::fs::remove_dir_all(env_dir(data_dir));
+    crate::log_info!("[TUNNEL] Exit via {} stopped", exit.connection_id);
+    Some(exit)
+}
+
+/// Tears down the exit if it runs over `connection_id`.
+pub async fn stop_for_connection(
+    tunnel_manager: &TunnelManager,
+    data_dir: &Path,
+    connection_id: &str,
+) -> Option<ExitNode> {
+    if active()?.connection_id != connection_id {
+        return None;
+    }
+    stop(tunnel_manager, data_dir).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proxy_env_resolves_names_on_the_server() {
+        let env = proxy_env(1080);
+        assert_eq!(env["ALL_PROXY"], "socks5h://127.0.0.1:1080");
+        assert_eq!(env["https_proxy"], "socks5h://127.0.0.1:1080");
+        assert_eq!(env["NO_PROXY"], NO_PROXY);
+        assert_eq!(env.len(), 8);
+    }
+
+    #[test]
+    fn scripts_set_every_variable() {
+        let env = proxy_env(1080);
+        let posix = posix_script(&env);
+        assert!(posix.contains("export all_proxy='socks5h://127.0.0.1:1080'\n"));
+        assert!(posix.contains("# Undo with: unset ALL_PROXY HTTPS_PROXY"));
+        let powershell = powershell_script(&env);
+        assert!(powershell.contains("$env:HTTP_PROXY = 'socks5h://127.0.0.1:1080'\n"));
+        assert!(!powershell.contains("$env:http_proxy"));
+    }
+
+    #[test]
+    fn reads_the_first_usable_nameserver() {
+        let conf = "# generated\nsearch lan\nnameserver fe80::1%eth0\nnameserver 10.0.0.2 # dhcp\nnameserver 1.1.1.1\n";
+        assert_eq!(parse_nameserver(conf).as_deref(), Some("10.0.0.2"));
+        assert_eq!(parse_nameserver("search lan\n"), None);
+        assert_eq!(nameserver_or_fallback(""), FALLBACK_NAMESERVER);
+    }
+
+    #[test]
+    fn frames_queries_for_tcp() {
+        assert_eq!(
+            tcp_frame(&[0xab, 0xcd, 0xef]).unwrap(),
+            [0, 3, 0xab, 0xcd, 0xef]
+        );
+        assert!(tcp_frame(&vec![0; 70_000]).is_err());
+    }
+}
@@ -1,17 +1,22 @@
 use crate::ssh::Client;
 use crate::tunnels::dynamic;
+use crate::tunnels::metrics::{
+    ConnectionEvent, ConnectionRecord, CountingStream, TunnelCounters, TunnelStats,
+};
+use crate::tunnels::port_conflict;
 use crate::tunnels::session_failure::{is_ssh_session_fatal_error, SessionFailureSender};
+use crate::tunnels::supervisor::{SupervisedTunnel, TunnelHealth};
 use crate::types::SavedTunnel;
 use anyhow::{anyhow, Result};
 use log::warn;
 use russh::client::Handle;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::net::TcpListener;
 use tokio::sync::Mutex;
 
 const SESSION_PROBE_INTERVAL_SECS: u64 = 15;
+const CONNECTION_EVENT_BUFFER: usize = 256;
 pub(crate) const SSH_SESSION_PROBE_TIMEOUT: Duration = Duration::from_secs(8);
 
 /// Bounded liveness probe — opens and closes a session channel without wedging on stalled transports.
@@ -26,6 +31,8 @@ pub(crate) async fn probe_ssh_session(session: &Arc<Mutex<Handle<Client>>>) -> b
                 let _ = channel.close().await;
                 true
             }
+            // A server at its session limit still answered, so the transport is alive.
+            Err(russh::Error::ChannelOpenFailure(_)) => true,
             Err(_) => false,
         }
     };
@@ -48,6 +55,13 @@ pub fn tunnel_runtime_id(tunnel: &SavedTunnel) -> String {
         );
     }
 
+    if tunnel.tunnel_type == REMOTE_DYNAMIC {
+        return format!(
+            "remote-dynamic:{}:{}",
+            tunnel.connection_id, tunnel.remote_port
+        );
+    }
+
     let remote_host = tunnel.remote_host.replace(':', "_");
     if tunnel.tunnel_type == "local" {
         format!(
@@ -62,10 +76,40 @@ pub fn tunnel_runtime_id(tunnel: &SavedTunnel) -> String {
     }
 }
 
+/// Tunnel type of a remote dynamic (reverse SOCKS) forward.
+pub const REMOTE_DYNAMIC: &str = "remote-dynamic";
+
+/// Where connections arriving on a remote forward are sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteTarget {
+    /// A fixed host and port, dialed from this machine.
+    Fixed(String, u16),
+    /// Each connection is a SOCKS5 client naming its own destination.
+    Socks,
+}
+
+impl std::fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(host, port) => write!(f, "{}:{}", host, port),
+            Self::Socks => f.write_str("SOCKS"),
+        }
+    }
+}
+
 fn uses_local_listener(tunnel_type: &str) -> bool {
     tunnel_type == "local" || tunnel_type == "dynamic"
 }
 
+/// Key of a tunnel's traffic counters: its runtime id, or the remote forward map key.
+fn metrics_key(tunnel: &SavedTunnel) -> String {
+    if uses_local_listener(&tunnel.tunnel_type) {
+        tunnel_runtime_id(tunnel)
+    } else {
+        remote_forward_map_key(&tunnel.connection_id, tunnel.remote_port)
+    }
+}
+
 /// Scoped key for remote forward lookup (per SSH connection).
 pub fn remote_forward_map_key(connection_id: &str, remote_port: u16) -> String {
     format!("{connection_id}:{remote_port}")
@@ -73,11 +117,21 @@ pub fn remote_forward_map_key(connection_id: &str, remote_port: u16) -> String {
 
 #[derive(Clone, Debug)]
 pub struct TunnelManager {
-    /// `{connection_id}:{remote_port}` -> (local_host, local_port, bind_address)
-    pub remote_forwards: Arc<Mutex<HashMap<String, (String, u16, String)>>>,
+    /// `{connection_id}:{remote_port}` -> (target, bind_address)
+    pub remote_forwards: Arc<Mutex<HashMap<String, (RemoteTarget, String)>>>,
     /// `tunnel_runtime_id` -> listener abort handle + cancel sender
     pub local_listeners:
         Arc<Mutex<HashMap<String, (tokio::task::AbortHandle, tokio::sync::broadcast::Sender<()>)>>>,
+    /// Saved tunnel id -> health tracked by `supervisor`, from start until stop.
+    pub(crate) supervised: Arc<Mutex<HashMap<String, SupervisedTunnel>>>,
+    /// Traffic counters keyed like `local_listeners` / `remote_forwards`; kept after stop.
+    metrics: Arc<std::sync::Mutex<HashMap<String, TunnelCounters>>>,
+    /// Runtime id of a local or dynamic forward started on port 0 -> the port the OS assigned.
+    allocated_ports: Arc<std::sync::Mutex<HashMap<String, u16>>>,
+    /// Every finished forwarded connection, across tunnels.
+    connection_events: tokio::sync::broadcast::Sender<ConnectionEvent>,
+    /// Task relaying `connection_events` to the UI while it asks for them.
+    pub(crate) connection_watch: Arc<std::sync::Mutex<Option<tokio::task::AbortHandle>>>,
     failure_tx: SessionFailureSender,
 }
 
@@ -86,10 +140,194 @@ impl TunnelManager {
         Self {
             remote_forwards: Arc::new(Mutex::new(HashMap::new())),
             local_listeners: Arc::new(Mutex::new(HashMap::new())),
+            supervised: Arc::new(Mutex::new(HashMap::new())),
+            metrics: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            allocated_ports: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            connection_events: tokio::sync::broadcast::channel(CONNECTION_EVENT_BUFFER).0,
+            connection_watch: Arc::new(std::sync::Mutex::new(None)),
             failure_tx,
         }
     }
 
+    /// Fresh counters for a tunnel that is starting.
+    fn reset_counters(&self, key: &str, runtime_id: &str) -> TunnelCounters {
+        let counters = TunnelCounters::new(runtime_id, self.connection_events.clone());
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics.insert(key.to_string(), counters.clone());
+        }
+        counters
+    }
+
+    /// Counters of the remote forward registered under `map_key`.
+    pub fn remote_forward_counters(&self, map_key: &str) -> Option<TunnelCounters> {
+        self.metrics.lock().ok()?.get(map_key).cloned()
+    }
+
+    /// Port a local forward actually listens on: the OS-assigned one when it
+    /// was started with port 0, otherwise `requested`.
+    pub fn bound_port(&self, runtime_id: &str, requested: u16) -> u16 {
+        self.allocated_ports
+            .lock()
+            .ok()
+            .and_then(|ports| ports.get(runtime_id).copied())
+            .unwrap_or(requested)
+    }
+
+    /// Listening port of a saved local forward, `None` for other tunnel types.
+    pub fn local_port_of(&self, tunnel: &SavedTunnel) -> Option<u16> {
+        (tunnel.tunnel_type == "local")
+            .then(|| self.bound_port(&tunnel_runtime_id(tunnel), tunnel.local_port))
+    }
+
+    /// Traffic of the tunnel's current or most recent run; zero if it never ran.
+    pub fn stats(&self, tunnel: &SavedTunnel) -> TunnelStats {
+        self.metrics
+            .lock()
+            .ok()
+            .and_then(|metrics| {
+                metrics
+                    .get(&metrics_key(tunnel))
+                    .map(TunnelCounters::snapshot)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Finished connections of the tunnel's current or most recent run, newest first.
+    pub fn connection_log(&self, tunnel: &SavedTunnel, limit: usize) -> Vec<ConnectionRecord> {
+        self.metrics
+            .lock()
+            .ok()
+            .and_then(|metrics| {
+                metrics
+                    .get(&metrics_key(tunnel))
+                    .map(|counters| counters.recent_connections(limit))
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe_connections(&self) -> tokio::sync::broadcast::Receiver<ConnectionEvent> {
+        self.connection_events.subscribe()
+    }
+
+    /// Starts the forward described by a saved tunnel.
+    pub async fn start_tunnel(
+        &self,
+        session: Arc<Mutex<Handle<Client>>>,
+        tunnel: &SavedTunnel,
+    ) -> Result<String> {
+        let runtime_id = tunnel_runtime_id(tunnel);
+        if tunnel.tunnel_type == "dynamic" {
+            let bind_addr = tunnel
+                .bind_address
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string());
+            self.start_dynamic_forwarding(
+                session,
+                tunnel.connection_id.clone(),
+                runtime_id,
+                bind_addr,
+                tunnel.local_port,
+            )
+            .await
+        } else if tunnel.tunnel_type == "local" {
+            let bind_addr = tunnel
+                .bind_address
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string());
+            self.start_local_forwarding(
+                session,
+                tunnel.connection_id.clone(),
+                runtime_id,
+                bind_addr,
+                tunnel.local_port,
+                tunnel.remote_host.clone(),
+                tunnel.remote_port,
+            )
+            .await
+        } else if tunnel.tunnel_type == REMOTE_DYNAMIC {
+            // Anyone reaching the port could use this machine's network, so it
+            // stays on the server's loopback unless a bind address is given.
+            let bind_addr = tunnel
+                .bind_address
+                .clone()
+                .unwrap_or_else(|| "127.0.0.1".to_string());
+            self.start_remote_dynamic_forwarding(
+                session,
+                tunnel.connection_id.clone(),
+                runtime_id,
+                bind_addr,
+                tunnel.remote_port,
+            )
+            .await
+        } else {
+            let bind_addr = tunnel
+                .bind_address
+                .clone()
+                .unwrap_or_else(|| "0.0.0.0".to_string());
+            self.start_remote_forwarding(
+                session,
+                tunnel.connection_id.clone(),
+                runtime_id,
+                bind_addr,
+                tunnel.remote_port,
+                tunnel.remote_host.clone(),
+                tunnel.local_port,
+            )
+            .await
+        }
+    }
+
+    /// Puts a started tunnel under supervision; `stop_tunnel` ends it.
+    pub async fn supervise(&self, tunnel: &SavedTunnel) {
+        self.supervised
+            .lock()
+            .await
+            .entry(tunnel.id.clone())
+            .or_insert_with(|| SupervisedTunnel::new(tunnel.clone()));
+    }
+
+    /// Ids of supervised tunnels whose forward or target is currently failing.
+    pub async fn degraded_tunnels(&self) -> HashSet<String> {
+        self.supervised
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, entry)| entry.health == TunnelHealth::Degraded)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Whether the tunnel's listener task is still running, or its remote forward still registered.
+    pub async fn is_runtime_alive(&self, tunnel: &SavedTunnel) -> bool {
+        if uses_local_listener(&tunnel.tunnel_type) {
+            self.local_listeners
+                .lock()
+                .await
+                .get(&tunnel_runtime_id(tunnel))
+                .is_some_and(|(handle, _)| !handle.is_finished())
+        } else {
+            self.remote_forwards
+                .lock()
+                .await
+                .contains_key(&remote_forward_map_key(
+                    &tunnel.connection_id,
+                    tunnel.remote_port,
+                ))
+        }
+    }
+
+    /// Forgets a finished listener so the tunnel can be started again.
+    pub async fn clear_dead_runtime(&self, tunnel: &SavedTunnel) {
+        let runtime_id = tunnel_runtime_id(tunnel);
+        let mut listeners = self.local_listeners.lock().await;
+        if listeners
+            .get(&runtime_id)
+            .is_some_and(|(handle, _)| handle.is_finished())
+        {
+            listeners.remove(&runtime_id);
+        }
+    }
+
     pub async fn start_local_forwarding(
         &self,
         session: Arc<Mutex<Handle<Client>>>,
@@ -103,7 +341,7 @@ impl TunnelManager {
         {
             let listeners = self.local_listeners.lock().await;
             if listeners.contains_key(&runtime_id) {
-                println!(
+                crate::log_info!(
                     "[TUNNEL] Tunnel {} already active, skipping start",
                     runtime_id
                 );
@@ -111,37 +349,24 @@ impl TunnelManager {
             }
         }
 
-        let listener = match TcpListener::bind(format!("{}:{}", bind_address, local_port)).await {
-            Ok(listener) => listener,
-            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
-                let process_info = find_process_using_port(local_port).await;
-                let suggested_port = find_next_available_port(local_port, 10).await;
-
-                let error_msg = if let Some(port) = suggested_port {
-                    format!(
-                        "Port {} is already in use{}. Port {} is available.",
-                        local_port,
-                        process_info.map(|p| format!(" {}", p)).unwrap_or_default(),
-                        port
-                    )
-                } else {
-                    format!(
-                        "Port {} is already in use{}. Please choose a different port.",
-                        local_port,
-                        process_info.map(|p| format!(" {}", p)).unwrap_or_default()
-                    )
-                };
-
-                return Err(anyhow!(error_msg));
+        let listener = port_conflict::bind_listener(&bind_address, local_port).await?;
+        let bound_port = listener.local_addr()?.port();
+        if local_port == 0 {
+            if let Ok(mut ports) = self.allocated_ports.lock() {
+                ports.insert(runtime_id.clone(), bound_port);
             }
-            Err(e) => return Err(e.into()),
-        };
+        }
         let session = session.clone();
         let failure_tx = self.failure_tx.clone();
+        let counters = self.reset_counters(&runtime_id, &runtime_id);
 
-        println!(
+        crate::log_info!(
             "[TUNNEL] Starting local forwarding {} on port {} to {}:{} (bind {})",
-            runtime_id, local_port, remote_host, remote_port, bind_address
+            runtime_id,
+            bound_port,
+            remote_host,
+            remote_port,
+            bind_address
         );
 
         let (tx, _rx) = tokio::sync::broadcast::channel(1);
@@ -157,23 +382,26 @@ impl TunnelManager {
                 let mut rx = tx.subscribe();
 
                 tokio::select! {
-                    Ok((mut incoming_stream, _)) = accept_fut => {
+                    Ok((incoming_stream, peer)) = accept_fut => {
                          let session = session.clone();
                          let remote_host = remote_host.clone();
+                         let counters = counters.clone();
                          let mut inner_rx = tx.subscribe();
                          let stop_tx = tx.clone();
                          let failure_tx = failure_tx.clone();
                          let connection_id = connection_id.clone();
 
                          tokio::spawn(async move {
+                            let connection = counters.open_connection(Some(peer.to_string()));
                             let channel = {
                                 let session_guard = session.lock().await;
                                 match session_guard.channel_open_direct_tcpip(remote_host, remote_port as u32, "127.0.0.1", 0).await {
                                      Ok(c) => Some(c),
                                      Err(e) => {
-                                         eprintln!("[TUNNEL] Failed to open direct-tcpip channel: {}", e);
+                                         crate::log_error!("[TUNNEL] Failed to open direct-tcpip channel: {}", e);
+                                         connection.fail(&e);
                                          if is_ssh_session_fatal_error(&e) {
-                                             println!(
+                                             crate::log_info!(
                                                  "[TUNNEL] SSH session lost for {}; stopping tunnels",
                                                  connection_id
                                              );
@@ -187,27 +415,29 @@ impl TunnelManager {
 
                             if let Some(channel) = channel {
                                  let mut stream = channel.into_stream();
+                                 let mut incoming_stream = CountingStream::new(incoming_stream, &connection);
 
                                  tokio::select! {
                                      res = tokio::io::copy_bidirectional(&mut incoming_stream, &mut stream) => {
                                          if let Err(e) = res {
-                                             println!("[TUNNEL] Error copying: {}", e);
+                                             crate::log_info!("[TUNNEL] Error copying: {}", e);
+                                             connection.fail(&e);
                                          }
                                      }
                                      _ = inner_rx.recv() => {
-                                         println!("[TUNNEL] Aborting active connection due to stop request");
+                                         crate::log_info!("[TUNNEL] Aborting active connection due to stop request");
                                      }
                                  }
                             }
                          });
                     }
                     _ = rx.recv() => {
-                        println!("[TUNNEL] Listener stopped via signal");
+                        crate::log_info!("[TUNNEL] Listener stopped via signal");
                         break;
                     }
                     _ = session_probe.tick() => {
                         if !probe_ssh_session(&session).await {
-                            println!(
+                            crate::log_info!(
                                 "[TUNNEL] SSH session probe failed for {}; stopping tunnels",
                                 connection_id
                             );
@@ -240,7 +470,7 @@ impl TunnelManager {
         {
             let listeners = self.local_listeners.lock().await;
             if listeners.contains_key(&runtime_id) {
-                println!(
+                crate::log_info!(
                     "[TUNNEL] Dynamic tunnel {} already active, skipping start",
                     runtime_id
                 );
@@ -248,41 +478,26 @@ impl TunnelManager {
             }
         }
 
-        let listener = match TcpListener::bind(format!("{}:{}", bind_address, local_port)).await {
-            Ok(listener) => listener,
-            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
-                let process_info = find_process_using_port(local_port).await;
-                let suggested_port = find_next_available_port(local_port, 10).await;
-
-                let error_msg = if let Some(port) = suggested_port {
-                    format!(
-                        "Port {} is already in use{}. Port {} is available.",
-                        local_port,
-                        process_info.map(|p| format!(" {}", p)).unwrap_or_default(),
-                        port
-                    )
-                } else {
-                    format!(
-                        "Port {} is already in use{}. Please choose a different port.",
-                        local_port,
-                        process_info.map(|p| format!(" {}", p)).unwrap_or_default()
-                    )
-                };
-
-                return Err(anyhow!(error_msg));
+        let listener = port_conflict::bind_listener(&bind_address, local_port).await?;
+        let bound_port = listener.local_addr()?.port();
+        if local_port == 0 {
+            if let Ok(mut ports) = self.allocated_ports.lock() {
+                ports.insert(runtime_id.clone(), bound_port);
             }
-            Err(e) => return Err(e.into()),
-        };
+        }
 
-        println!(
+        crate::log_info!(
             "[TUNNEL] Starting dynamic SOCKS {} on {}:{}",
-            runtime_id, bind_address, local_port
+            runtime_id,
+            bind_address,
+            bound_port
         );
 
         let (tx, _rx) = tokio::sync::broadcast::channel(1);
         let tx_for_store = tx.clone();
         let session = session.clone();
         let failure_tx = self.failure_tx.clone();
+        let counters = self.reset_counters(&runtime_id, &runtime_id);
 
         let handle = tokio::spawn(async move {
             let mut session_probe =
@@ -300,11 +515,13 @@ impl TunnelManager {
                         let stop_tx = tx.clone();
                         let failure_tx = failure_tx.clone();
                         let connection_id = connection_id.clone();
+                        let counters = counters.clone();
                         tokio::spawn(async move {
                             dynamic::handle_socks5_client(
                                 client_stream,
                                 session,
                                 connection_id,
+                                counters,
                                 failure_tx,
                                 stop_tx,
                                 client_rx,
@@ -313,12 +530,12 @@ impl TunnelManager {
                         });
                     }
                     _ = rx.recv() => {
-                        println!("[TUNNEL] Dynamic listener stopped via signal");
+                        crate::log_info!("[TUNNEL] Dynamic listener stopped via signal");
                         break;
                     }
                     _ = session_probe.tick() => {
                         if !probe_ssh_session(&session).await {
-                            println!(
+                            crate::log_info!(
                                 "[TUNNEL] SSH session probe failed for {}; stopping tunnels",
                                 connection_id
                             );
@@ -348,22 +565,61 @@ impl TunnelManager {
         remote_port: u16,
         local_host: String,
         local_port: u16,
+    ) -> Result<String> {
+        self.register_remote_forward(
+            session,
+            connection_id,
+            runtime_id,
+            bind_address,
+            remote_port,
+            RemoteTarget::Fixed(local_host, local_port),
+        )
+        .await
+    }
+
+    /// Remote dynamic forwarding (`ssh -R port`): the server listens on
+    /// `remote_port` and SOCKS5 clients connecting there are served from here.
+    pub async fn start_remote_dynamic_forwarding(
+        &self,
+        session: Arc<Mutex<Handle<Client>>>,
+        connection_id: String,
+        runtime_id: String,
+        bind_address: String,
+        remote_port: u16,
+    ) -> Result<String> {
+        self.register_remote_forward(
+            session,
+            connection_id,
+            runtime_id,
+            bind_address,
+            remote_port,
+            RemoteTarget::Socks,
+        )
+        .await
+    }
+
+    async fn register_remote_forward(
+        &self,
+        session: Arc<Mutex<Handle<Client>>>,
+        connection_id: String,
+        runtime_id: String,
+        bind_address: String,
+        remote_port: u16,
+        target: RemoteTarget,
     ) -> Result<String> {
         let map_key = remote_forward_map_key(&connection_id, remote_port);
         {
             let mut map = self.remote_forwards.lock().await;
             if map.contains_key(&map_key) {
-                println!(
+                crate::log_info!(
                     "[TUNNEL] Remote tunnel {} already active",
                     map_key
                 );
                 return Ok(runtime_id);
             }
-            map.insert(
-                map_key.clone(),
-                (local_host.clone(), local_port, bind_address.clone()),
-            );
+            map.insert(map_key.clone(), (target.clone(), bind_address.clone()));
         }
+        self.reset_counters(&map_key, &runtime_id);
 
         let res = {
             let mut session_handle = session.lock().await;
@@ -378,30 +634,73 @@ impl TunnelManager {
             return Err(anyhow!("Remote forwarding error: {}", e));
         }
 
-        println!(
-            "[TUNNEL] Remote forwarding {} enabled on remote port {} -> {}:{} (bind {})",
-            runtime_id, remote_port, local_host, local_port, bind_address
+        crate::log_info!(
+            "[TUNNEL] Remote forwarding {} enabled on remote port {} -> {} (bind {})",
+            runtime_id,
+            remote_port,
+            target,
+            bind_address
         );
 
         Ok(runtime_id)
     }
 
+    /// Stops every forward, saved or ad-hoc, without touching the SSH sessions;
+    /// returns how many were running. Remote forwards are only forgotten, so
+    /// callers close their sessions afterwards.
+    pub async fn stop_all(&self) -> usize {
+        self.supervised.lock().await.clear();
+        if let Ok(mut ports) = self.allocated_ports.lock() {
+            ports.clear();
+        }
+        let mut stopped = 0;
+        for (runtime_id, (handle, tx)) in self.local_listeners.lock().await.drain() {
+            let _ = tx.send(());
+            handle.abort();
+            crate::log_info!("[TUNNEL] Stop signal sent for {}", runtime_id);
+            stopped += 1;
+        }
+        let mut remote_forwards = self.remote_forwards.lock().await;
+        stopped += remote_forwards.len();
+        remote_forwards.clear();
+        stopped
+    }
+
+    /// Stops the local-side listener registered under `runtime_id`; returns
+    /// whether one was running.
+    pub async fn stop_listener(&self, runtime_id: &str) -> bool {
+        if let Ok(mut ports) = self.allocated_ports.lock() {
+            ports.remove(runtime_id);
+        }
+        let Some((handle, tx)) = self.local_listeners.lock().await.remove(runtime_id) else {
+            return false;
+        };
+        let _ = tx.send(());
+        handle.abort();
+        crate::log_info!("[TUNNEL] Stop signal sent for {}", runtime_id);
+        true
+    }
+
     pub async fn stop_tunnel(
         &self,
         session: Option<Arc<Mutex<Handle<Client>>>>,
         tunnel: &SavedTunnel,
     ) -> Result<()> {
         let runtime_id = tunnel_runtime_id(tunnel);
-        println!("[TUNNEL MANAGER] Stopping {}", runtime_id);
+        crate::log_info!("[TUNNEL MANAGER] Stopping {}", runtime_id);
+        self.supervised.lock().await.remove(&tunnel.id);
 
         if uses_local_listener(&tunnel.tunnel_type) {
             let mut listeners = self.local_listeners.lock().await;
+            if let Ok(mut ports) = self.allocated_ports.lock() {
+                ports.remove(&runtime_id);
+            }
             if let Some((handle, tx)) = listeners.remove(&runtime_id) {
                 let _ = tx.send(());
                 handle.abort();
-                println!("[TUNNEL] Stop signal sent for {}", runtime_id);
+                crate::log_info!("[TUNNEL] Stop signal sent for {}", runtime_id);
             } else {
-                println!(
+                crate::log_info!(
                     "[TUNNEL] Local-side tunnel {} not found in listeners",
                     runtime_id
                 );
@@ -413,7 +712,7 @@ impl TunnelManager {
                 remote_forwards_guard.get(&map_key).cloned()
             };
 
-            if let Some((_, _, saved_bind_address)) = found_entry {
+            if let Some((_, saved_bind_address)) = found_entry {
                 if let Some(session) = session {
                     let handle = session.lock().await;
                     let bind_addr = tunnel
@@ -427,12 +726,13 @@ impl TunnelManager {
                     if res.is_ok() {
                         let mut remote_forwards_guard = self.remote_forwards.lock().await;
                         remote_forwards_guard.remove(&map_key);
-                        println!(
+                        crate::log_info!(
                             "[TUNNEL] Cancelled remote forwarding {} (bind {})",
-                            map_key, bind_addr
+                            map_key,
+                            bind_addr
                         );
                     } else {
-                        println!(
+                        crate::log_info!(
                             "[TUNNEL ERROR] Failed to cancel remote forwarding {}: {:?}",
                             map_key,
                             res.err()
@@ -444,10 +744,15 @@ impl TunnelManager {
                 }
             } else if let Some(session) = session {
                 let handle = session.lock().await;
+                let default_bind = if tunnel.tunnel_type == REMOTE_DYNAMIC {
+                    "127.0.0.1"
+                } else {
+                    "0.0.0.0"
+                };
                 let bind_addr = tunnel
                     .bind_address
                     .clone()
-                    .unwrap_or_else(|| "0.0.0.0".to_string());
+                    .unwrap_or_else(|| default_bind.to_string());
                 let _ = handle
                     .cancel_tcpip_forward(bind_addr.clone(), tunnel.remote_port as u32)
                     .await;
@@ -461,104 +766,6 @@ impl TunnelManager {
     }
 }
 
-/// Attempts to find which process is using the specified port.
-async fn find_process_using_port(port: u16) -> Option<String> {
-    #[cfg(any(target_os = "linux", target_os = "macos"))]
-    {
-        use tokio::process::Command;
-
-        let output = Command::new("lsof")
-            .args(["-i", &format!(":{}", port), "-t", "-sTCP:LISTEN"])
-            .output()
-            .await
-            .ok()?;
-
-        if output.status.success() {
-            let pid_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if let Ok(pid) = pid_str.parse::<u32>() {
-                let name_output = Command::new("ps")
-                    .args(["-p", &pid.to_string(), "-o", "comm="])
-                    .output()
-                    .await
-                    .ok()?;
-
-                if name_output.status.success() {
-                    let process_name = String::from_utf8_lossy(&name_output.stdout)
-                        .trim()
-                        .to_string();
-                    if !process_name.is_empty() {
-                        return Some(format!("by '{}' (PID: {})", process_name, pid));
-                    }
-                }
-                return Some(format!("by PID {}", pid));
-            }
-        }
-        None
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        use tokio::process::Command;
-
-        let output = Command::new("netstat").args(["-ano"]).output().await.ok()?;
-
-        if output.status.success() {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            for line in output_str.lines() {
-                if line.contains(&format!(":{}", port)) && line.contains("LISTENING") {
-                    if let Some(pid_str) = line.split_whitespace().last() {
-                        if let Ok(pid) = pid_str.parse::<u32>() {
-                            let name_output = Command::new("tasklist")
-                                .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
-                                .output()
-                                .await
-                                .ok()?;
-
-                            if name_output.status.success() {
-                                let name_str = String::from_utf8_lossy(&name_output.stdout);
-                                if let Some(first_field) = name_str.split(',').next() {
-                                    let process_name = first_field.trim_matches('"').trim();
-                                    if !process_name.is_empty() {
-                                        return Some(format!(
-                                            "by '{}' (PID: {})",
-                                            process_name, pid
-                                        ));
-                                    }
-                                }
-                            }
-                            return Some(format!("by PID {}", pid));
-                        }
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
-    {
-        None
-    }
-}
-
-async fn find_next_available_port(start_port: u16, max_attempts: u8) -> Option<u16> {
-    for offset in 1..=max_attempts {
-        let candidate_port = start_port.saturating_add(offset.into());
-        if candidate_port == 0 || candidate_port == start_port {
-            continue;
-        }
-
-        match TcpListener::bind(format!("127.0.0.1:{}", candidate_port)).await {
-            Ok(listener) => {
-                drop(listener);
-                return Some(candidate_port);
-            }
-            Err(_) => continue,
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -614,4 +821,25 @@ mod tests {
             "dynamic:conn-d:8080:127.0.0.1"
         );
     }
+
+    #[test]
+    fn bound_port_prefers_os_assigned_port() {
+        let (failure_tx, _failure_rx) = tokio::sync::mpsc::unbounded_channel();
+        let manager = TunnelManager::new(failure_tx);
+        let mut t = sample_tunnel("local", "conn-e");
+        t.local_port = 0;
+        let runtime_id = tunnel_runtime_id(&t);
+        assert_eq!(manager.bound_port(&runtime_id, 0), 0);
+
+        manager
+            .allocated_ports
+            .lock()
+            .unwrap()
+            .insert(runtime_id.clone(), 49152);
+        assert_eq!(manager.bound_port(&runtime_id, 0), 49152);
+        assert_eq!(
+            manager.bound_port("local:conn-e:8080:127.0.0.1:5432", 8080),
+            8080
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,335 @@
+//! Per-tunnel traffic counters, updated as bytes move through a forward.
+//!
+//! Directions are seen from this machine: `bytes_out` is what the local side
+//! sent into the tunnel, `bytes_in` what came back out of it. Counters reset
+//! each time a tunnel starts.
+//!
+//! Each finished connection is also kept in a small per-tunnel log
+//! (`tunnel_logs`) and published on the manager's connection event channel,
+//! which the UI can subscribe to as `tunnel:connection` while debugging.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::broadcast;
+
+/// Finished connections kept per tunnel; older ones are dropped first.
+pub const CONNECTION_LOG_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TunnelStats {
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub active_connections: u32,
+    pub total_connections: u64,
+    /// Unix milliseconds of the last byte moved or connection opened.
+    pub last_activity: Option<u64>,
+}
+
+/// One forwarded connection, recorded when it closes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionRecord {
+    /// Unix milliseconds when the connection was accepted.
+    pub started_at: u64,
+    /// Address of the peer that opened it, when known.
+    pub source: Option<String>,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Payload of `tunnel:connection`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionEvent {
+    pub runtime_id: String,
+    #[serde(flatten)]
+    pub record: ConnectionRecord,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    active_connections: AtomicU32,
+    total_connections: AtomicU64,
+    last_activity: AtomicU64,
+    runtime_id: String,
+    log: Mutex<VecDeque<ConnectionRecord>>,
+    events: Option<broadcast::Sender<ConnectionEvent>>,
+}
+
+/// Shared counters of one running tunnel.
+#[derive(Debug, Clone, Default)]
+pub struct TunnelCounters(Arc<Counters>);
+
+impl TunnelCounters {
+    /// Counters of the tunnel `runtime_id`, publishing finished connections on `events`.
+    pub fn new(runtime_id: &str, events: broadcast::Sender<ConnectionEvent>) -> Self {
+        Self(Arc::new(Counters {
+            runtime_id: runtime_id.to_string(),
+            events: Some(events),
+            ..Default::default()
+        }))
+    }
+
+    pub fn snapshot(&self) -> TunnelStats {
+        let last_activity = self.0.last_activity.load(Ordering::Relaxed);
+        TunnelStats {
+            bytes_in: self.0.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.0.bytes_out.load(Ordering::Relaxed),
+            active_connections: self.0.active_connections.load(Ordering::Relaxed),
+            total_connections: self.0.total_connections.load(Ordering::Relaxed),
+            last_activity: (last_activity > 0).then_some(last_activity),
+        }
+    }
+
+    /// Most recent finished connections, newest first.
+    pub fn recent_connections(&self, limit: usize) -> Vec<ConnectionRecord> {
+        self.0
+            .log
+            .lock()
+            .map(|log| log.iter().rev().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Counts a new forwarded connection until the returned guard is dropped,
+    /// which also records it in the connection log.
+    pub fn open_connection(&self, source: Option<String>) -> ConnectionGuard {
+        self.0.active_connections.fetch_add(1, Ordering::Relaxed);
+        self.0.total_connections.fetch_add(1, Ordering::Relaxed);
+        self.touch();
+        ConnectionGuard {
+            counters: self.clone(),
+            tally: Arc::default(),
+            started: Instant::now(),
+            started_at: now_millis(),
+            source,
+            error: Mutex::new(None),
+        }
+    }
+
+    fn record(&self, record: ConnectionRecord) {
+        if let Ok(mut log) = self.0.log.lock() {
+            if log.len() == CONNECTION_LOG_CAPACITY {
+                log.pop_front();
+            }
+            log.push_back(record.clone());
+        }
+        if let Some(events) = &self.0.events {
+            // Nobody listening is the normal case.
+            let _ = events.send(ConnectionEvent {
+                runtime_id: self.0.runtime_id.clone(),
+                record,
+            });
+        }
+    }
+
+    fn add_in(&self, bytes: usize) {
+        self.0.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    fn add_out(&self, bytes: usize) {
+        self.0.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.touch();
+    }
+
+    fn touch(&self) {
+        self.0.last_activity.store(now_millis(), Ordering::Relaxed);
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Bytes of a single connection.
+#[derive(Debug, Default)]
+struct Tally {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+pub struct ConnectionGuard {
+    counters: TunnelCounters,
+    tally: Arc<Tally>,
+    started: Instant,
+    started_at: u64,
+    source: Option<String>,
+    error: Mutex<Option<String>>,
+}
+
+impl ConnectionGuard {
+    /// Notes why the connection failed; the first error is kept.
+    pub fn fail(&self, error: impl Display) {
+        if let Ok(mut slot) = self.error.lock() {
+            slot.get_or_insert_with(|| error.to_string());
+        }
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let counters = &self.counters.0;
+        counters.active_connections.fetch_sub(1, Ordering::Relaxed);
+        self.counters.record(ConnectionRecord {
+            started_at: self.started_at,
+            source: self.source.take(),
+            bytes_in: self.tally.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.tally.bytes_out.load(Ordering::Relaxed),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            error: self.error.lock().ok().and_then(|mut error| error.take()),
+        });
+    }
+}
+
+/// Wraps the local side of a forward: reads are counted as outgoing, writes as incoming.
+pub struct CountingStream<S> {
+    inner: S,
+    counters: TunnelCounters,
+    tally: Arc<Tally>,
+}
+
+impl<S> CountingStream<S> {
+    /// Counts traffic for both the tunnel and `connection`.
+    pub fn new(inner: S, connection: &ConnectionGuard) -> Self {
+        Self {
+            inner,
+            counters: connection.counters.clone(),
+            tally: connection.tally.clone(),
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read > 0 {
+            self.counters.add_out(read);
+            self.tally
+                .bytes_out
+                .fetch_add(read as u64, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            if written > 0 {
+                self.counters.add_in(written);
+                self.tally
+                    .bytes_in
+                    .fetch_add(written as u64, Ordering::Relaxed);
+            }
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn counts_bytes_and_connections() {
+        let counters = TunnelCounters::default();
+        assert_eq!(counters.snapshot(), TunnelStats::default());
+
+        let (local, mut peer) = tokio::io::duplex(64);
+        let guard = counters.open_connection(None);
+        let mut stream = CountingStream::new(local, &guard);
+
+        peer.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(b"ok").await.unwrap();
+
+        let stats = counters.snapshot();
+        assert_eq!((stats.bytes_out, stats.bytes_in), (5, 2));
+        assert_eq!(stats.active_connections, 1);
+        assert!(stats.last_activity.is_some());
+
+        drop(stream);
+        drop(guard);
+        let stats = counters.snapshot();
+        assert_eq!(stats.active_connections, 0);
+        assert_eq!(stats.total_connections, 1);
+    }
+
+    #[tokio::test]
+    async fn logs_finished_connections() {
+        let (events, mut rx) = broadcast::channel(4);
+        let counters = TunnelCounters::new("local:c1:8080:db:5432", events);
+
+        let guard = counters.open_connection(Some("127.0.0.1:50000".to_string()));
+        let (local, mut peer) = tokio::io::duplex(64);
+        let mut stream = CountingStream::new(local, &guard);
+        stream.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        peer.read_exact(&mut buf).await.unwrap();
+        guard.fail("connection reset");
+        guard.fail("ignored");
+        drop(guard);
+        drop(counters.open_connection(None));
+
+        let log = counters.recent_connections(10);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].source, None);
+        assert_eq!(log[1].source.as_deref(), Some("127.0.0.1:50000"));
+        assert_eq!((log[1].bytes_in, log[1].bytes_out), (4, 0));
+        assert_eq!(log[1].error.as_deref(), Some("connection reset"));
+        assert_eq!(counters.recent_connections(1), vec![log[0].clone()]);
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.runtime_id, "local:c1:8080:db:5432");
+        assert_eq!(event.record, log[1]);
+    }
+
+    #[test]
+    fn connection_log_is_bounded() {
+        let counters = TunnelCounters::default();
+        for _ in 0..CONNECTION_LOG_CAPACITY + 5 {
+            drop(counters.open_connection(None));
+        }
+        assert_eq!(
+            counters.recent_connections(usize::MAX).len(),
+            CONNECTION_LOG_CAPACITY
+        );
+    }
+}
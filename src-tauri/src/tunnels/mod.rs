@@ -4,10 +4,14 @@
 
 pub mod commands;
 pub mod dynamic;
+pub mod exit_node;
 pub mod manager;
+pub mod metrics;
+pub mod port_conflict;
 pub(crate) mod session_failure;
 pub(crate) mod socks5;
+pub(crate) mod supervisor;
 
 pub use manager::{remote_forward_map_key, tunnel_runtime_id, TunnelManager};
 
-pub(crate) use commands::stop_tunnels_for_connections;
\ No newline at end of file
+pub(crate) use commands::{running_tunnels, start_auto_tunnels, stop_tunnels_for_connections};
\ No newline at end of file
@@ -0,0 +1,244 @@
+//! Detecting local port conflicts before a forward binds, and suggesting free ports.
+//!
+//! A taken port surfaces as a [`PortConflict`]. Commands return it as
+//! `PORT_IN_USE:` followed by its JSON so the UI can offer an alternative port;
+//! its `Display` form is the plain message used in status events and logs.
+
+use serde::Serialize;
+use std::fmt;
+use tokio::net::TcpListener;
+
+/// Machine-readable prefix — must stay in sync with `PORT_IN_USE_PREFIX` in TS.
+pub const PORT_IN_USE_PREFIX: &str = "PORT_IN_USE:";
+/// Ports after the requested one tried by [`suggest_port`].
+const SUGGEST_ATTEMPTS: u16 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortConflict {
+    pub port: u16,
+    pub bind_address: String,
+    /// Process listening on the port, when the OS lets us find out.
+    pub owner: Option<PortOwner>,
+    pub suggested_port: Option<u16>,
+    pub message: String,
+}
+
+impl PortConflict {
+    async fn detect(bind_address: &str, port: u16) -> Self {
+        let owner = find_port_owner(port).await;
+        let suggested_port = suggest_port(bind_address, port).await;
+        let by = match &owner {
+            Some(PortOwner {
+                pid,
+                name: Some(name),
+            }) => format!(" by '{}' (PID: {})", name, pid),
+            Some(PortOwner { pid, name: None }) => format!(" by PID {}", pid),
+            None => String::new(),
+        };
+        let message = match suggested_port {
+            Some(free) => format!(
+                "Port {} is already in use{}. Port {} is available.",
+                port, by, free
+            ),
+            None => format!(
+                "Port {} is already in use{}. Please choose a different port.",
+                port, by
+            ),
+        };
+        Self {
+            port,
+            bind_address: bind_address.to_string(),
+            owner,
+            suggested_port,
+            message,
+        }
+    }
+
+    /// `PORT_IN_USE:{json}` for command results.
+    pub fn to_error_string(&self) -> String {
+        match serde_json::to_string(self) {
+            Ok(json) => format!("{}{}", PORT_IN_USE_PREFIX, json),
+            Err(_) => self.message.clone(),
+        }
+    }
+}
+
+impl fmt::Display for PortConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PortConflict {}
+
+/// Error string for a tunnel command: structured for port conflicts, plain otherwise.
+pub fn command_error(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<PortConflict>() {
+        Some(conflict) => conflict.to_error_string(),
+        None => error.to_string(),
+    }
+}
+
+/// Binds the listener of a local forward; a taken port becomes a [`PortConflict`].
+pub async fn bind_listener(bind_address: &str, port: u16) -> anyhow::Result<TcpListener> {
+    match TcpListener::bind(format!("{}:{}", bind_address, port)).await {
+        Ok(listener) => Ok(listener),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            Err(PortConflict::detect(bind_address, port).await.into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Pre-flight check: `Some` when `port` cannot be bound on `bind_address`
+/// because something else listens there. Port 0 never conflicts.
+pub async fn check(bind_address: &str, port: u16) -> Option<PortConflict> {
+    if port == 0 {
+        return None;
+    }
+    match TcpListener::bind(format!("{}:{}", bind_address, port)).await {
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            Some(PortConflict::detect(bind_address, port).await)
+        }
+        _ => None,
+    }
+}
+
+/// The first port after `port` that can be bound on `bind_address`.
+pub async fn suggest_port(bind_address: &str, port: u16) -> Option<u16> {
+    for offset in 1..=SUGGEST_ATTEMPTS {
+        let candidate = port.checked_add(offset)?;
+        if TcpListener::bind(format!("{}:{}", bind_address, candidate))
+            .await
+            .is_ok()
+        {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Attempts to find which process is listening on the specified port.
+async fn find_port_owner(port: u16) -> Option<PortOwner> {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        use tokio::process::Command;
+
+        let output = Command::new("lsof")
+            .args(["-i", &format!(":{}", port), "-t", "-sTCP:LISTEN"])
+            .output()
+            .await
+            .ok()?;
+
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // One PID per line; a port shared by several processes lists them all.
+            let pid_str = stdout.lines().next().unwrap_or_default().trim();
+            if let Ok(pid) = pid_str.parse::<u32>() {
+                let name_output = Command::new("ps")
+                    .args(["-p", &pid.to_string(), "-o", "comm="])
+                    .output()
+                    .await
+                    .ok()?;
+
+                if name_output.status.success() {
+                    let process_name = String::from_utf8_lossy(&name_output.stdout)
+                        .trim()
+                        .to_string();
+                    if !process_name.is_empty() {
+                        return Some(PortOwner {
+                            pid,
+                            name: Some(process_name),
+                        });
+                    }
+                }
+                return Some(PortOwner { pid, name: None });
+            }
+        }
+        None
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use tokio::process::Command;
+
+        let output = Command::new("netstat").args(["-ano"]).output().await.ok()?;
+
+        if output.status.success() {
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            for line in output_str.lines() {
+                if line.contains(&format!(":{}", port)) && line.contains("LISTENING") {
+                    if let Some(pid_str) = line.split_whitespace().last() {
+                        if let Ok(pid) = pid_str.parse::<u32>() {
+                            let name_output = Command::new("tasklist")
+                                .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+                                .output()
+                                .await
+                                .ok()?;
+
+                            if name_output.status.success() {
+                                let name_str = String::from_utf8_lossy(&name_output.stdout);
+                                if let Some(first_field) = name_str.split(',').next() {
+                                    let process_name = first_field.trim_matches('"').trim();
+                                    if !process_name.is_empty() {
+                                        return Some(PortOwner {
+                                            pid,
+                                            name: Some(process_name.to_string()),
+                                        });
+                                    }
+                                }
+                            }
+                            return Some(PortOwner { pid, name: None });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn detects_taken_port_and_suggests_another() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let conflict = check("127.0.0.1", port).await.expect("port is taken");
+        assert_eq!(conflict.port, port);
+        assert!(conflict
+            .message
+            .starts_with(&format!("Port {} is already in use", port)));
+        if let Some(free) = conflict.suggested_port {
+            assert!(free > port);
+        }
+
+        let error = bind_listener("127.0.0.1", port).await.unwrap_err();
+        let text = command_error(&error);
+        let json = text
+            .strip_prefix(PORT_IN_USE_PREFIX)
+            .expect("structured error");
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(value["port"], port);
+        assert_eq!(value["bindAddress"], "127.0.0.1");
+
+        drop(taken);
+        assert!(check("127.0.0.1", 0).await.is_none());
+    }
+}
@@ -2,6 +2,7 @@
 
 use super::commands::stop_tunnels_for_connections;
 use crate::commands::AppState;
+use crate::state_sync::{self, ChangeKind, StateKind};
 use std::collections::HashSet;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager};
@@ -53,6 +54,12 @@ pub fn spawn_session_failure_watcher(
                     "connection:transport-lost",
                     serde_json::json!({ "connectionId": connection_id }),
                 );
+                state_sync::notify(
+                    &app,
+                    StateKind::Connection,
+                    &connection_id,
+                    ChangeKind::Updated,
+                );
             }
 
             in_flight.lock().await.remove(&connection_id);
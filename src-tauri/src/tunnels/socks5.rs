@@ -14,6 +14,9 @@ pub const ATYP_DOMAIN: u8 = 0x03;
 pub const ATYP_IPV6: u8 = 0x04;
 pub const REP_SUCCEEDED: u8 = 0x00;
 pub const REP_GENERAL_FAILURE: u8 = 0x01;
+pub const REP_NETWORK_UNREACHABLE: u8 = 0x03;
+pub const REP_HOST_UNREACHABLE: u8 = 0x04;
+pub const REP_CONNECTION_REFUSED: u8 = 0x05;
 pub const REP_CMD_NOT_SUPPORTED: u8 = 0x07;
 pub const REP_ATYP_NOT_SUPPORTED: u8 = 0x08;
 
@@ -140,6 +143,16 @@ pub fn socks5_error_to_reply(error: &Socks5Error) -> u8 {
     }
 }
 
+/// Reply for a destination this side failed to connect to.
+pub fn io_error_to_reply(error: &std::io::Error) -> u8 {
+    match error.kind() {
+        std::io::ErrorKind::ConnectionRefused => REP_CONNECTION_REFUSED,
+        std::io::ErrorKind::NetworkUnreachable => REP_NETWORK_UNREACHABLE,
+        std::io::ErrorKind::HostUnreachable | std::io::ErrorKind::NotFound => REP_HOST_UNREACHABLE,
+        _ => REP_GENERAL_FAILURE,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -178,6 +191,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn maps_connect_errors_to_replies() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert_eq!(io_error_to_reply(&refused), REP_CONNECTION_REFUSED);
+        let other = std::io::Error::other("dns failure");
+        assert_eq!(io_error_to_reply(&other), REP_GENERAL_FAILURE);
+    }
+
     #[test]
     fn rejects_non_connect_command() {
         let msg = [0x05, 0x03, 0x00, 0x01, 127, 0, 0, 1, 0, 0x50];
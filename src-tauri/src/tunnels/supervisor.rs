@@ -0,0 +1,265 @@
+//! Background task: keep running tunnels healthy while their SSH session is up.
+//!
+//! Every started tunnel is supervised until it is stopped. A forward whose
+//! listener task died or whose remote forward disappeared is restarted with
+//! backoff, and the forward target is probed over TCP on each pass. Health
+//! transitions are emitted as `tunnel:status-change` with status `degraded` or
+//! `active`. A lost SSH session is left to the session failure watcher.
+
+use super::commands::{emit_status_change, TunnelStatusChange};
+use super::manager::TunnelManager;
+use crate::commands::AppState;
+use crate::ssh::Client;
+use crate::types::SavedTunnel;
+use russh::client::Handle;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(15);
+const TARGET_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunnelHealth {
+    Active,
+    Degraded,
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisedTunnel {
+    pub tunnel: SavedTunnel,
+    pub health: TunnelHealth,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl SupervisedTunnel {
+    pub fn new(tunnel: SavedTunnel) -> Self {
+        Self {
+            tunnel,
+            health: TunnelHealth::Active,
+            failures: 0,
+            retry_at: None,
+        }
+    }
+
+    /// Applies the outcome of one check; returns the new health when it changed.
+    fn record(&mut self, outcome: &Result<(), String>, now: Instant) -> Option<TunnelHealth> {
+        let health = match outcome {
+            Ok(()) => {
+                self.failures = 0;
+                self.retry_at = None;
+                TunnelHealth::Active
+            }
+            Err(_) => {
+                self.failures = self.failures.saturating_add(1);
+                self.retry_at = Some(now + retry_delay(self.failures));
+                TunnelHealth::Degraded
+            }
+        };
+        let changed = health != self.health;
+        self.health = health;
+        changed.then_some(health)
+    }
+}
+
+/// Wait before restart attempt `failures` (1-based): 2s, 4s, 8s, ... capped at a minute.
+pub fn retry_delay(failures: u32) -> Duration {
+    Duration::from_secs(2 << failures.saturating_sub(1).min(5)).min(MAX_RETRY_DELAY)
+}
+
+pub fn spawn_tunnel_supervisor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(SUPERVISE_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if let Some(state) = app.try_state::<AppState>() {
+                supervise_once(&app, &state).await;
+            }
+        }
+    });
+}
+
+async fn supervise_once(app: &AppHandle, state: &AppState) {
+    let entries: Vec<SupervisedTunnel> = state
+        .tunnel_manager
+        .supervised
+        .lock()
+        .await
+        .values()
+        .cloned()
+        .collect();
+
+    for entry in entries {
+        let session = {
+            let connections = state.connections.lock().await;
+            connections
+                .get(&entry.tunnel.connection_id)
+                .and_then(|c| c.session.clone())
+        };
+        let Some(session) = session else {
+            continue;
+        };
+        let Some(outcome) = check_tunnel(&state.tunnel_manager, session, &entry).await else {
+            continue;
+        };
+
+        let transition = {
+            let mut supervised = state.tunnel_manager.supervised.lock().await;
+            match supervised.get_mut(&entry.tunnel.id) {
+                Some(current) => current.record(&outcome, Instant::now()),
+                None => continue,
+            }
+        };
+        if let Some(health) = transition {
+            let (status, error) = match health {
+                TunnelHealth::Active => ("active", None),
+                TunnelHealth::Degraded => ("degraded", outcome.err()),
+            };
+            crate::log_info!(
+                "[TUNNEL] '{}' is now {}{}",
+                entry.tunnel.name,
+                status,
+                error
+                    .as_deref()
+                    .map(|e| format!(": {}", e))
+                    .unwrap_or_default()
+            );
+            emit_status_change(
+                app,
+                TunnelStatusChange {
+                    id: entry.tunnel.id.clone(),
+                    status: status.to_string(),
+                    error,
+                    local_port: state.tunnel_manager.local_port_of(&entry.tunnel),
+                },
+            );
+        }
+    }
+}
+
+/// Restarts a dead forward or probes the target of a live one. `None` while a
+/// restart is backing off or the tunnel was stopped in the meantime.
+async fn check_tunnel(
+    manager: &TunnelManager,
+    session: Arc<Mutex<Handle<Client>>>,
+    entry: &SupervisedTunnel,
+) -> Option<Result<(), String>> {
+    let tunnel = &entry.tunnel;
+    if manager.is_runtime_alive(tunnel).await {
+        return Some(probe_target(&session, tunnel).await);
+    }
+    if entry.retry_at.is_some_and(|at| Instant::now() < at)
+        || !manager.supervised.lock().await.contains_key(&tunnel.id)
+    {
+        return None;
+    }
+
+    crate::log_info!("[TUNNEL] '{}' forward is down; restarting", tunnel.name);
+    manager.clear_dead_runtime(tunnel).await;
+    Some(
+        manager
+            .start_tunnel(session, tunnel)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Restart failed: {}", e)),
+    )
+}
+
+/// Opens a TCP connection to the forward target: through the SSH server for
+/// local forwards, on this machine for remote forwards. Dynamic forwards have
+/// no fixed target.
+async fn probe_target(
+    session: &Arc<Mutex<Handle<Client>>>,
+    tunnel: &SavedTunnel,
+) -> Result<(), String> {
+    let target = match tunnel.tunnel_type.as_str() {
+        "local" => format!("{}:{}", tunnel.remote_host, tunnel.remote_port),
+        "remote" => format!("{}:{}", tunnel.remote_host, tunnel.local_port),
+        _ => return Ok(()),
+    };
+    let probe = async {
+        if tunnel.tunnel_type == "local" {
+            let channel = {
+                let guard = session.lock().await;
+                guard
+                    .channel_open_direct_tcpip(
+                        tunnel.remote_host.clone(),
+                        tunnel.remote_port as u32,
+                        "127.0.0.1",
+                        0,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?
+            };
+            let _ = channel.close().await;
+        } else {
+            tokio::net::TcpStream::connect(&target)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok::<(), String>(())
+    };
+    match tokio::time::timeout(TARGET_PROBE_TIMEOUT, probe).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => Err(format!("Target {} unreachable: {}", target, e)),
+        Err(_) => Err(format!(
+            "Target {} did not answer within {}s",
+            target,
+            TARGET_PROBE_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tunnel() -> SavedTunnel {
+        SavedTunnel {
+            id: "t1".to_string(),
+            connection_id: "conn".to_string(),
+            name: "db".to_string(),
+            tunnel_type: "local".to_string(),
+            local_port: 15432,
+            remote_host: "127.0.0.1".to_string(),
+            remote_port: 5432,
+            bind_address: None,
+            bind_to_any: None,
+            auto_start: None,
+            status: None,
+            original_port: None,
+            group: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn retry_delay_doubles_up_to_a_minute() {
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(2), Duration::from_secs(4));
+        assert_eq!(retry_delay(5), Duration::from_secs(32));
+        assert_eq!(retry_delay(6), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(40), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn only_health_transitions_are_reported() {
+        let now = Instant::now();
+        let mut entry = SupervisedTunnel::new(tunnel());
+        assert_eq!(entry.record(&Ok(()), now), None);
+
+        let failed = Err("refused".to_string());
+        assert_eq!(entry.record(&failed, now), Some(TunnelHealth::Degraded));
+        assert_eq!(entry.record(&failed, now), None);
+        assert_eq!(entry.retry_at, Some(now + Duration::from_secs(4)));
+
+        assert_eq!(entry.record(&Ok(()), now), Some(TunnelHealth::Active));
+        assert_eq!(entry.failures, 0);
+        assert_eq!(entry.retry_at, None);
+    }
+}
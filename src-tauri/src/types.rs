@@ -9,6 +9,182 @@ pub struct ConnectionConfig {
     pub username: String,
     pub auth_method: AuthMethod,
     pub jump_host: Option<Box<ConnectionConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<ResolutionOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_steps: Option<Vec<LoginStep>>,
+    /// Per-connection override of the global "log all terminal output" setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_logging: Option<bool>,
+    /// Keep remote shells alive in tmux/screen and reattach on reconnect.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_session: Option<PersistentSessionMode>,
+    /// Open terminals with mosh (UDP, roaming-tolerant) instead of an SSH shell channel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mosh: Option<MoshOptions>,
+    /// Backend scrollback cap for this connection's terminals, in MiB (overrides the global setting).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrollback_limit_mb: Option<u64>,
+    /// Hide login noise (MOTD, `Last login`, update notices) when a terminal opens, like `ssh -q`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_login: Option<bool>,
+    /// Offer only this connection's own key during public-key auth, never agent-held keys (ssh `IdentitiesOnly`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identities_only: Option<bool>,
+    /// Typed into every new terminal for this connection once the first prompt appears, e.g. `cd /srv/app && source env.sh`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_commands: Option<Vec<String>>,
+    /// Environment variables for this connection's shells (SSH `env` requests, falling back to `export`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<std::collections::HashMap<String, String>>,
+    /// TERM, locale and Backspace/Delete behaviour for this connection's terminals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalOptions>,
+    /// Socket options for the SSH connection itself: Nagle, OS keepalive, source binding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<TcpOptions>,
+    /// Session channels (shells, exec, SFTP) kept open at once; further requests wait. Defaults to OpenSSH's `MaxSessions` of 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_budget: Option<u32>,
+    /// Alternate routes tried in order when this endpoint cannot be reached (see `crate::failover`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallbacks: Option<Vec<FallbackEndpoint>>,
+}
+
+/// Another way to reach the same server, e.g. its VPN address or a route through a
+/// public bastion. Authentication and every other option come from the connection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FallbackEndpoint {
+    /// Shown in `ssh:connect-attempt` events; defaults to `host:port`.
+    pub label: Option<String>,
+    /// Defaults to the connection's host.
+    pub host: Option<String>,
+    /// Defaults to the connection's port.
+    pub port: Option<u16>,
+    /// Jump chain for this route. Unset connects directly, even when the connection
+    /// itself goes through a jump host.
+    pub jump_host: Option<Box<ConnectionConfig>>,
+    /// Saved connections name the jump host by id, like `jumpServerId`; the chain is
+    /// resolved into `jump_host` before connecting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_server_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamilyPreference {
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+/// Terminal multiplexer used to keep remote shells alive (see `crate::persistent_session`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PersistentSessionMode {
+    /// tmux when installed, else screen.
+    Auto,
+    Tmux,
+    Screen,
+}
+
+/// How `mosh-server` is started for connections using mosh (see `crate::mosh`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MoshOptions {
+    /// Remote `mosh-server` binary; defaults to the one on `PATH`.
+    pub server_path: Option<String>,
+    /// UDP port or range for the server, e.g. `60000:60010` (mosh `-p`).
+    pub port_range: Option<String>,
+    /// Locale exported to the server (mosh needs UTF-8); defaults to `LANG=en_US.UTF-8`.
+    pub locale: Option<String>,
+}
+
+/// Terminal type, locale and key behaviour for new terminals (see `crate::pty`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalOptions {
+    /// `TERM` value; defaults to `xterm-256color`.
+    pub term: Option<String>,
+    /// Exported as `LANG`, e.g. `en_US.UTF-8`.
+    pub lang: Option<String>,
+    /// Exported as `LC_ALL`, overriding every other locale category.
+    pub lc_all: Option<String>,
+    pub backspace: BackspaceKey,
+    pub delete: DeleteKey,
+}
+
+/// What the Backspace key sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackspaceKey {
+    /// `^?` (0x7f), the xterm default.
+    #[default]
+    Del,
+    /// `^H` (0x08), for hosts whose tty erase character is `^H`.
+    CtrlH,
+}
+
+/// What the Delete key sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeleteKey {
+    /// `ESC [ 3 ~`, the VT220 sequence.
+    #[default]
+    Escape,
+    /// `^?` (0x7f), usually paired with a `^H` Backspace.
+    Del,
+}
+
+/// Per-connection name resolution controls (see `crate::dns`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResolutionOptions {
+    /// Try addresses of this family first; the other family is still used as a fallback.
+    pub address_family: Option<AddressFamilyPreference>,
+    /// Query this DNS server directly instead of the system resolver (`ip` or `ip:port`).
+    pub dns_server: Option<String>,
+    /// Skip DNS entirely and dial this IP (hosts-file style alias override).
+    pub static_address: Option<String>,
+    /// Upper bound for the whole lookup; defaults to 10s.
+    pub timeout_ms: Option<u64>,
+}
+
+/// Options for the TCP socket a direct connection is made over (see
+/// `crate::tcp_options`). Hops reached through a jump host have no socket of
+/// their own, so only the jump host's options apply there.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TcpOptions {
+    /// Set `TCP_NODELAY` so keystrokes are not held back by Nagle's algorithm;
+    /// unset keeps the OS default.
+    pub nodelay: Option<bool>,
+    /// Enable OS-level TCP keepalive, probing after this many idle seconds.
+    pub keepalive_secs: Option<u64>,
+    /// Seconds between keepalive probes once they start; the OS default when unset.
+    pub keepalive_interval_secs: Option<u64>,
+    /// Local IP to connect from, for multi-homed machines.
+    pub bind_address: Option<String>,
+    /// Network interface to connect through, e.g. `wg0` (Linux only).
+    pub bind_interface: Option<String>,
+}
+
+/// A post-connect check or command (see `crate::login_steps`). Steps run in
+/// order before terminals or the file panel can use the connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginStep {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub command: String,
+    /// Per-step limit; defaults to 30s.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Log a failure and keep going instead of aborting the connection.
+    #[serde(default)]
+    pub continue_on_failure: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +219,9 @@ pub struct ConnectionResponse {
     pub message: String,
     pub term_id: Option<String>,
     pub detected_os: Option<String>,
+    /// Label of the fallback endpoint that connected; unset when the primary one did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
 }
 
 /// A reference to a vault item used as SSH credentials.
@@ -105,6 +284,49 @@ pub struct SavedConnection {
     pub pinned_features: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth_ref: Option<CredentialRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<ResolutionOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login_steps: Option<Vec<LoginStep>>,
+    /// Per-connection override of the global "log all terminal output" setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_logging: Option<bool>,
+    /// Keep remote shells alive in tmux/screen and reattach on reconnect.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_session: Option<PersistentSessionMode>,
+    /// Open terminals with mosh (UDP, roaming-tolerant) instead of an SSH shell channel.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mosh: Option<MoshOptions>,
+    /// Backend scrollback cap for this connection's terminals, in MiB (overrides the global setting).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scrollback_limit_mb: Option<u64>,
+    /// Hide login noise (MOTD, `Last login`, update notices) when a terminal opens, like `ssh -q`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_login: Option<bool>,
+    /// Offer only this connection's own key during public-key auth, never agent-held keys (ssh `IdentitiesOnly`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identities_only: Option<bool>,
+    /// Typed into every new terminal for this connection once the first prompt appears, e.g. `cd /srv/app && source env.sh`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_commands: Option<Vec<String>>,
+    /// Environment variables for this connection's shells (SSH `env` requests, falling back to `export`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<std::collections::HashMap<String, String>>,
+    /// TERM, locale and Backspace/Delete behaviour for this connection's terminals.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalOptions>,
+    /// Socket options for the SSH connection itself: Nagle, OS keepalive, source binding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<TcpOptions>,
+    /// Session channels (shells, exec, SFTP) kept open at once; further requests wait. Defaults to OpenSSH's `MaxSessions` of 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_budget: Option<u32>,
+    /// Accent colour shown next to the host in lists and tabs, e.g. `#ef4444`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Alternate routes tried in order when this endpoint cannot be reached.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallbacks: Option<Vec<FallbackEndpoint>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,12 +348,13 @@ pub struct SavedTunnel {
     pub connection_id: String,
     pub name: String,
     #[serde(rename = "type")]
-    pub tunnel_type: String, // "local", "remote", or "dynamic" (SOCKS)
+    pub tunnel_type: String, // "local", "remote", "dynamic" (SOCKS) or "remote-dynamic" (reverse SOCKS)
     pub local_port: u16,
     pub remote_host: String,
     pub remote_port: u16,
     pub bind_address: Option<String>,
     pub bind_to_any: Option<bool>,
+    /// Started by the backend as soon as `connection_id` connects.
     pub auto_start: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<String>,
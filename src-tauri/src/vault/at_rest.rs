@@ -0,0 +1,521 @@
+//! At-rest sealing for the JSON stores and the key directory.
+//!
+//! When sealing is on, `connections.json`, `tunnels.json` and every file under
+//! `keys/` are written as `MAGIC || nonce || ciphertext`. The key is derived
+//! from the vault's VEK and is held here only while the vault is unlocked.
+//! [`read_to_string`] passes plaintext files through unchanged, so turning
+//! sealing on or off never strands data that has not been converted yet. If
+//! no sealed file is read or written for `idle_lock_minutes` (background
+//! readers use [`peek_to_string`] and do not count), the vault locks again and
+//! the key is dropped.
+
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::vault::crypto::{derive_record_key, SecretKey};
+use crate::vault::error::VaultError;
+
+const MAGIC: &[u8] = b"ZYNCSEALED1\n";
+const NONCE_LEN: usize = 24;
+const KEY_INFO: &[u8] = b"zync:at-rest:v1";
+const SETTINGS_FILE: &str = "at_rest.json";
+const SEALED_FILES: [&str; 2] = ["connections.json", "tunnels.json"];
+const KEYS_DIR: &str = "keys";
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn default_idle_lock_minutes() -> u32 {
+    15
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtRestSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 0 keeps the vault unlocked until it is locked by hand.
+    #[serde(default = "default_idle_lock_minutes")]
+    pub idle_lock_minutes: u32,
+}
+
+impl Default for AtRestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_lock_minutes: default_idle_lock_minutes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtRestStatus {
+    #[serde(flatten)]
+    pub settings: AtRestSettings,
+    pub unlocked: bool,
+}
+
+struct AtRest {
+    settings: AtRestSettings,
+    key: Option<SecretKey>,
+    last_used: Instant,
+}
+
+static STATE: LazyLock<Mutex<AtRest>> = LazyLock::new(|| {
+    Mutex::new(AtRest {
+        settings: AtRestSettings::default(),
+        key: None,
+        last_used: Instant::now(),
+    })
+});
+
+fn state() -> MutexGuard<'static, AtRest> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn locked_error() -> io::Error {
+    io::Error::new(
+        ErrorKind::PermissionDenied,
+        "Saved data is encrypted and the vault is locked",
+    )
+}
+
+/// Binds ciphertext to the file it was written for, so sealed files cannot
+/// be swapped for each other.
+fn aad(path: &Path) -> Vec<u8> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().as_bytes().to_vec())
+        .unwrap_or_default()
+}
+
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn seal(key: &SecretKey, path: &Path, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    let aad = aad(path);
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| io::Error::other("Failed to encrypt saved data"))?;
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn unseal(key: &SecretKey, path: &Path, sealed: &[u8]) -> io::Result<Vec<u8>> {
+    let body = &sealed[MAGIC.len()..];
+    if body.len() < NONCE_LEN {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} is truncated", path.display()),
+        ));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    let aad = aad(path);
+    cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{} could not be decrypted with this vault", path.display()),
+            )
+        })
+}
+
+/// Loads the persisted settings; call once at startup before any store is read.
+pub fn init(data_dir: &Path) {
+    let settings = std::fs::read(data_dir.join(SETTINGS_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    state().settings = settings;
+}
+
+pub fn status() -> AtRestStatus {
+    let state = state();
+    AtRestStatus {
+        settings: state.settings.clone(),
+        unlocked: state.key.is_some(),
+    }
+}
+
+/// Called by [`crate::vault::store::VaultService`] whenever the VEK becomes available.
+pub(crate) fn unlock(vek: &SecretKey) {
+    if let Ok(key) = derive_record_key(vek, KEY_INFO) {
+        let mut state = state();
+        state.key = Some(key);
+        state.last_used = Instant::now();
+    }
+}
+
+pub(crate) fn forget_key() {
+    state().key = None;
+}
+
+fn open(path: &Path, activity: bool) -> io::Result<Vec<u8>> {
+    let bytes = std::fs::read(path)?;
+    if !is_sealed(&bytes) {
+        return Ok(bytes);
+    }
+    let mut state = state();
+    let key = state.key.as_ref().ok_or_else(locked_error)?;
+    let plaintext = unseal(key, path, &bytes)?;
+    if activity {
+        state.last_used = Instant::now();
+    }
+    Ok(plaintext)
+}
+
+fn into_string(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Reads a store file, decrypting it when it is sealed.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    into_string(open(path, true)?)
+}
+
+/// Like [`read_to_string`], but for background jobs: it does not count as
+/// activity, so a periodic reader cannot hold off the idle relock.
+pub fn peek_to_string(path: &Path) -> io::Result<String> {
+    into_string(open(path, false)?)
+}
+
+/// The bytes to store for `content`: sealed when sealing is on, unchanged
+/// otherwise. For writers that manage their own replace logic.
+pub fn encode(path: &Path, content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut state = state();
+    if !state.settings.enabled {
+        return Ok(content.to_vec());
+    }
+    let key = state.key.as_ref().ok_or_else(locked_error)?;
+    let sealed = seal(key, path, content)?;
+    state.last_used = Instant::now();
+    Ok(sealed)
+}
+
+/// Durably replaces a store file, sealing it when sealing is on.
+pub fn write(path: &Path, content: &[u8]) -> io::Result<()> {
+    crate::atomic_io::durable_replace(path, &encode(path, content)?)
+}
+
+/// Copies an outside file (a private key being imported) into the data
+/// directory, sealing it on the way when sealing is on. Returns the byte count
+/// like [`std::fs::copy`].
+pub fn copy_in(src: &Path, dest: &Path) -> io::Result<u64> {
+    let content = std::fs::read(src)?;
+    write(dest, &content)?;
+    Ok(content.len() as u64)
+}
+
+/// Seals a private key that a tool (`ssh-keygen`) just wrote in plaintext,
+/// when it sits in the data directory's `keys/` folder and sealing is on.
+/// Other paths are left alone, matching what [`configure`] converts.
+pub fn seal_new_key(data_dir: &Path, path: &Path) -> io::Result<()> {
+    seal_new_key_in(&mut state(), data_dir, path)
+}
+
+fn seal_new_key_in(state: &mut AtRest, data_dir: &Path, path: &Path) -> io::Result<()> {
+    if !state.settings.enabled || path.parent() != Some(data_dir.join(KEYS_DIR).as_path()) {
+        return Ok(());
+    }
+    let key = state.key.as_ref().ok_or_else(locked_error)?;
+    convert(key, path, true)?;
+    state.last_used = Instant::now();
+    Ok(())
+}
+
+/// A private key as a plaintext file, for tools that read keys from disk
+/// (`ssh-keygen`). A sealed key is decrypted into a `0600` temporary file that
+/// is removed on drop; a plaintext key is used where it is.
+pub struct PlaintextKey {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl PlaintextKey {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        if !is_sealed(&bytes) {
+            return Ok(Self {
+                path: path.to_path_buf(),
+                temporary: false,
+            });
+        }
+        let plaintext = open(path, true)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let key = Self {
+            path: std::env::temp_dir().join(format!("zync-key-{}", uuid::Uuid::new_v4())),
+            temporary: true,
+        };
+        options.open(&key.path)?.write_all(&plaintext)?;
+        Ok(key)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PlaintextKey {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn store_files(data_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = SEALED_FILES
+        .iter()
+        .map(|name| data_dir.join(name))
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(data_dir.join(KEYS_DIR)) {
+        files.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+                .map(|entry| entry.path()),
+        );
+    }
+    files
+}
+
+/// Seals or unseals one file in place, keeping its permissions (keys are 0600).
+fn convert(key: &SecretKey, path: &Path, to_sealed: bool) -> io::Result<()> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let converted = match (to_sealed, is_sealed(&bytes)) {
+        (true, false) => seal(key, path, &bytes)?,
+        (false, true) => unseal(key, path, &bytes)?,
+        _ => return Ok(()),
+    };
+    let permissions = std::fs::metadata(path)?.permissions();
+    crate::atomic_io::durable_replace(path, &converted)?;
+    std::fs::set_permissions(path, permissions)
+}
+
+/// Applies new settings. Switching sealing on or off needs the vault unlocked
+/// and rewrites every store file before the switch is persisted; a failure
+/// part-way leaves a mix of sealed and plain files, which [`read_to_string`]
+/// handles.
+pub fn configure(data_dir: &Path, settings: AtRestSettings) -> Result<AtRestStatus, VaultError> {
+    let mut state = state();
+    if settings.enabled != state.settings.enabled {
+        let key = state.key.clone().ok_or(VaultError::Locked)?;
+        for path in store_files(data_dir) {
+            convert(&key, &path, settings.enabled)
+                .map_err(|e| VaultError::InvalidData(format!("{}: {e}", path.display())))?;
+        }
+    }
+    let json = serde_json::to_vec_pretty(&settings)?;
+    crate::atomic_io::durable_replace(&data_dir.join(SETTINGS_FILE), &json)
+        .map_err(|e| VaultError::Storage(e.into()))?;
+    state.settings = settings;
+    state.last_used = Instant::now();
+    Ok(AtRestStatus {
+        settings: state.settings.clone(),
+        unlocked: state.key.is_some(),
+    })
+}
+
+fn idle_expired() -> bool {
+    let state = state();
+    let minutes = state.settings.idle_lock_minutes;
+    state.settings.enabled
+        && minutes > 0
+        && state.key.is_some()
+        && state.last_used.elapsed() >= Duration::from_secs(u64::from(minutes) * 60)
+}
+
+/// Locks the vault once sealed data has been idle for too long and emits
+/// `vault:locked` so the UI can ask for the passphrase again.
+pub fn spawn_idle_lock(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            if !idle_expired() {
+                continue;
+            }
+            let Some(vault) =
+                app.try_state::<tokio::sync::Mutex<crate::vault::store::VaultService>>()
+            else {
+                continue;
+            };
+            vault.lock().await.lock();
+            let _ = app.emit("vault:locked", "idle");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> SecretKey {
+        SecretKey::from_bytes([7u8; 32])
+    }
+
+    #[test]
+    fn seal_round_trips_and_binds_the_file_name() {
+        let path = Path::new("/data/connections.json");
+        let sealed = seal(&key(), path, b"{\"connections\":[]}").unwrap();
+        assert!(is_sealed(&sealed));
+        assert_eq!(
+            unseal(&key(), path, &sealed).unwrap(),
+            b"{\"connections\":[]}"
+        );
+        assert!(unseal(&key(), Path::new("/data/tunnels.json"), &sealed).is_err());
+        assert!(unseal(&SecretKey::from_bytes([8u8; 32]), path, &sealed).is_err());
+        assert!(unseal(&key(), path, MAGIC).is_err());
+    }
+
+    #[test]
+    fn convert_seals_and_restores_files_in_place() {
+        let dir = std::env::temp_dir().join(format!("zync-at-rest-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(KEYS_DIR)).unwrap();
+        std::fs::write(dir.join("connections.json"), b"{}").unwrap();
+        std::fs::write(dir.join(KEYS_DIR).join("id_ed25519"), b"PRIVATE").unwrap();
+
+        let files = store_files(&dir);
+        assert_eq!(files.len(), 3);
+        for path in &files {
+            convert(&key(), path, true).unwrap();
+        }
+        let sealed = std::fs::read(dir.join(KEYS_DIR).join("id_ed25519")).unwrap();
+        assert!(is_sealed(&sealed));
+        // Converting twice is a no-op.
+        convert(&key(), &dir.join("connections.json"), true).unwrap();
+
+        for path in &files {
+            convert(&key(), path, false).unwrap();
+        }
+        assert_eq!(std::fs::read(dir.join("connections.json")).unwrap(), b"{}");
+        assert_eq!(
+            std::fs::read(dir.join(KEYS_DIR).join("id_ed25519")).unwrap(),
+            b"PRIVATE"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sealed_keys_are_handed_out_as_private_temporary_files() {
+        let dir = std::env::temp_dir().join(format!("zync-at-rest-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("id_plain");
+        std::fs::write(&plain, b"PLAIN").unwrap();
+        let sealed = dir.join("id_sealed");
+        std::fs::write(&sealed, seal(&key(), &sealed, b"PRIVATE").unwrap()).unwrap();
+
+        assert_eq!(PlaintextKey::open(&plain).unwrap().path(), plain);
+
+        state().key = Some(key());
+        let copy = PlaintextKey::open(&sealed).unwrap();
+        let temp = copy.path().to_path_buf();
+        assert_ne!(temp, sealed);
+        assert_eq!(std::fs::read(&temp).unwrap(), b"PRIVATE");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&temp).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        drop(copy);
+        assert!(!temp.exists());
+
+        state().key = None;
+        assert!(PlaintextKey::open(&sealed).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn new_keys_in_the_key_directory_are_sealed_when_sealing_is_on() {
+        let dir = std::env::temp_dir().join(format!("zync-at-rest-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join(KEYS_DIR)).unwrap();
+        let inside = dir.join(KEYS_DIR).join("id_ed25519_rotated");
+        let outside = dir.join("id_ed25519_rotated");
+        for path in [&inside, &outside] {
+            std::fs::write(path, b"PRIVATE").unwrap();
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            }
+        }
+        let mut state = AtRest {
+            settings: AtRestSettings {
+                enabled: true,
+                ..AtRestSettings::default()
+            },
+            key: None,
+            last_used: Instant::now(),
+        };
+
+        assert!(seal_new_key_in(&mut state, &dir, &inside).is_err());
+        state.key = Some(key());
+        seal_new_key_in(&mut state, &dir, &inside).unwrap();
+        seal_new_key_in(&mut state, &dir, &outside).unwrap();
+
+        let sealed = std::fs::read(&inside).unwrap();
+        assert!(is_sealed(&sealed));
+        assert_eq!(unseal(&key(), &inside, &sealed).unwrap(), b"PRIVATE");
+        assert_eq!(std::fs::read(&outside).unwrap(), b"PRIVATE");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&inside).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        state.settings.enabled = false;
+        let plain = dir.join(KEYS_DIR).join("id_plain");
+        std::fs::write(&plain, b"PRIVATE").unwrap();
+        seal_new_key_in(&mut state, &dir, &plain).unwrap();
+        assert_eq!(std::fs::read(&plain).unwrap(), b"PRIVATE");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn settings_default_to_off_with_a_fifteen_minute_idle_lock() {
+        let settings: AtRestSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings, AtRestSettings::default());
+        assert!(!settings.enabled);
+        assert_eq!(settings.idle_lock_minutes, 15);
+    }
+}
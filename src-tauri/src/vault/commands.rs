@@ -7,6 +7,7 @@ use tokio::sync::Mutex;
 use zeroize::Zeroize;
 
 use crate::types::SavedData;
+use crate::vault::at_rest::{AtRestSettings, AtRestStatus};
 use crate::vault::credential::validate_secret_values_for_kind;
 use crate::vault::error::VaultError;
 use crate::vault::secure_to_vault::{SecureToVaultPreview, SecureToVaultResult};
@@ -433,6 +434,30 @@ pub async fn vault_secure_to_vault(
     crate::vault::secure_to_vault::secure(&data_dir, &guard).map_err(Into::into)
 }
 
+// ── At-rest sealing ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn vault_at_rest_status() -> VaultResult<AtRestStatus> {
+    Ok(crate::vault::at_rest::status())
+}
+
+/// Turns sealing of `connections.json`, `tunnels.json` and `keys/` on or off
+/// and sets the idle relock timeout. Needs the vault unlocked to convert files.
+#[tauri::command]
+pub async fn vault_at_rest_configure(
+    app: tauri::AppHandle,
+    vault: State<'_, Mutex<VaultService>>,
+    settings: AtRestSettings,
+) -> VaultResult<AtRestStatus> {
+    let data_dir = crate::commands::get_data_dir(&app);
+    // Same lock order as repair_connection_refs: vault first, then the file lock.
+    let _vault = vault.lock().await;
+    let _connections_guard = crate::commands::CONNECTIONS_MUTATION_LOCK
+        .lock()
+        .map_err(|e| VaultError::InvalidData(format!("lock connections file: {e}")))?;
+    crate::vault::at_rest::configure(&data_dir, settings).map_err(Into::into)
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VaultBackfillResult {
@@ -492,7 +517,7 @@ fn load_saved_connections(path: &std::path::Path) -> Result<SavedData, VaultErro
             folders: vec![],
         });
     }
-    let raw = std::fs::read_to_string(path)
+    let raw = crate::vault::at_rest::read_to_string(path)
         .map_err(|e| VaultError::InvalidData(format!("read connections file: {e}")))?;
     serde_json::from_str(&raw).map_err(VaultError::Serde)
 }
@@ -500,6 +525,8 @@ fn load_saved_connections(path: &std::path::Path) -> Result<SavedData, VaultErro
 fn save_saved_connections(path: &std::path::Path, saved: &SavedData) -> Result<(), VaultError> {
     use std::io::Write;
     let json = serde_json::to_string_pretty(saved).map_err(VaultError::Serde)?;
+    let json = crate::vault::at_rest::encode(path, json.as_bytes())
+        .map_err(|e| VaultError::InvalidData(format!("seal connections file: {e}")))?;
     let unique_suffix = uuid::Uuid::new_v4();
     let tmp = path.with_extension(format!("json.tmp.{unique_suffix}"));
     let mut f = std::fs::OpenOptions::new()
@@ -508,7 +535,7 @@ fn save_saved_connections(path: &std::path::Path, saved: &SavedData) -> Result<(
         .truncate(true)
         .open(&tmp)
         .map_err(|e| VaultError::InvalidData(format!("connections tmp write open: {e}")))?;
-    f.write_all(json.as_bytes())
+    f.write_all(&json)
         .map_err(|e| VaultError::InvalidData(format!("connections tmp write: {e}")))?;
     f.sync_all()
         .map_err(|e| VaultError::InvalidData(format!("connections tmp sync: {e}")))?;
@@ -128,7 +128,7 @@ pub fn secure(data_dir: &Path, vault: &VaultService) -> Result<SecureToVaultResu
                 skipped += 1;
                 continue;
             }
-            let key_content = match std::fs::read_to_string(key_path) {
+            let key_content = match crate::vault::at_rest::read_to_string(Path::new(key_path)) {
                 Ok(c) => c,
                 Err(_) => {
                     skipped += 1;
@@ -178,7 +178,8 @@ pub fn secure(data_dir: &Path, vault: &VaultService) -> Result<SecureToVaultResu
         });
     }
 
-    let original_json = std::fs::read_to_string(&connections_path).map_err(|e| {
+    // Backups keep the bytes as stored, sealed or not.
+    let original_json = std::fs::read(&connections_path).map_err(|e| {
         VaultError::InvalidData(format!("backup read failed ({connections_path:?}): {e}"))
     })?;
     if !backup_path.exists() {
@@ -330,7 +331,7 @@ fn load_connections(data_dir: &Path) -> Result<SavedData, VaultError> {
             folders: vec![],
         });
     }
-    let raw = std::fs::read_to_string(&path)
+    let raw = crate::vault::at_rest::read_to_string(&path)
         .map_err(|e| VaultError::InvalidData(format!("read connections.json: {e}")))?;
     serde_json::from_str(&raw).map_err(VaultError::Serde)
 }
@@ -345,7 +346,9 @@ fn atomic_write(path: &Path, content: &str) -> Result<(), VaultError> {
         .truncate(true)
         .open(&tmp)
         .map_err(|e| VaultError::InvalidData(format!("tmp write open: {e}")))?;
-    f.write_all(content.as_bytes())
+    let content = crate::vault::at_rest::encode(path, content.as_bytes())
+        .map_err(|e| VaultError::InvalidData(format!("seal: {e}")))?;
+    f.write_all(&content)
         .map_err(|e| VaultError::InvalidData(format!("tmp write: {e}")))?;
     f.sync_all()
         .map_err(|e| VaultError::InvalidData(format!("tmp sync: {e}")))?;
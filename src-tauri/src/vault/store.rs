@@ -214,6 +214,7 @@ impl VaultService {
             return Ok(false);
         }
 
+        self.publish_at_rest_key();
         Ok(true)
     }
 
@@ -377,6 +378,7 @@ impl VaultService {
         self.meta = Some(meta);
         self.suppress_session_cache_unlock = false;
         self.persist_session_cache_best_effort(remember_on_device);
+        self.publish_at_rest_key();
 
         self.build_unlocked_status()
     }
@@ -442,6 +444,7 @@ impl VaultService {
         }
 
         self.persist_session_cache_best_effort(remember_on_device);
+        self.publish_at_rest_key();
         self.build_unlocked_status()
     }
 
@@ -585,6 +588,14 @@ impl VaultService {
         self.vek = None;
         self.meta = None;
         self.suppress_session_cache_unlock = true;
+        super::at_rest::forget_key();
+    }
+
+    /// Hands the at-rest sealing key to [`super::at_rest`] after an unlock.
+    fn publish_at_rest_key(&self) {
+        if let Some(vek) = &self.vek {
+            super::at_rest::unlock(vek);
+        }
     }
 
     /// Returns the vault ID if the vault is unlocked (meta is cached).
@@ -1403,6 +1414,7 @@ impl VaultService {
         }
 
         self.persist_session_cache_best_effort(remember_on_device);
+        self.publish_at_rest_key();
         self.build_unlocked_status()
     }
 
@@ -1439,6 +1451,7 @@ impl VaultService {
         self.db = None;
         self.vek = None;
         self.meta = None;
+        super::at_rest::forget_key();
 
         let dest = self.vault_path();
         let tmp = dest.with_extension("redb.tmp-pre-import");
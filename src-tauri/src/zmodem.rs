@@ -0,0 +1,215 @@
+//! ZMODEM (`sz` / `rz`) detection in remote terminals.
+//!
+//! Rather than speaking ZMODEM, remote terminals watch for the opening hex
+//! header (`**\x18B00` ZRQINIT from `sz`, `**\x18B01` ZRINIT from `rz`), cancel
+//! the transfer so the binary frames never reach xterm.js, and emit
+//! `terminal:zmodem` with the files named on the command line (known when shell
+//! integration reported it) and the terminal's cwd. The frontend then shows a
+//! save/open dialog and moves the files over SFTP on the same connection.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// ZRQINIT / ZRINIT hex header prefix; the next byte tells them apart.
+const HEADER: &[u8] = b"**\x18B0";
+/// Eight CANs abort the peer; the backspaces erase them from a line-mode tty.
+pub const CANCEL: &[u8] = b"\x18\x18\x18\x18\x18\x18\x18\x18\x08\x08\x08\x08\x08\x08\x08\x08";
+/// How long output carrying ZMODEM framing is swallowed after a detection.
+const SUPPRESS_FOR: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZmodemDirection {
+    /// `sz`: the server sends, we save.
+    Download,
+    /// `rz`: the server waits for files from us.
+    Upload,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ZmodemScan {
+    /// Leading bytes of the chunk to pass to the terminal; the rest is dropped.
+    pub passthrough: usize,
+    pub started: Option<ZmodemDirection>,
+}
+
+#[derive(Debug, Default)]
+pub struct ZmodemDetector {
+    /// Last bytes of the previous chunk, for headers split across chunks.
+    tail: Vec<u8>,
+    suppress_until: Option<Instant>,
+}
+
+impl ZmodemDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feed(&mut self, data: &[u8]) -> ZmodemScan {
+        let now = Instant::now();
+        if self.suppress_until.is_some_and(|until| until > now) && data.contains(&0x18) {
+            self.tail.clear();
+            return ZmodemScan::default();
+        }
+
+        let mut window = std::mem::take(&mut self.tail);
+        let carried = window.len();
+        window.extend_from_slice(data);
+        let found = window.windows(HEADER.len() + 1).position(|w| {
+            w.starts_with(HEADER) && (w[HEADER.len()] == b'0' || w[HEADER.len()] == b'1')
+        });
+        match found {
+            Some(at) => {
+                let direction = if window[at + HEADER.len()] == b'0' {
+                    ZmodemDirection::Download
+                } else {
+                    ZmodemDirection::Upload
+                };
+                let repeat = self.suppress_until.is_some_and(|until| until > now);
+                self.suppress_until = Some(now + SUPPRESS_FOR);
+                ZmodemScan {
+                    passthrough: at.saturating_sub(carried),
+                    started: (!repeat).then_some(direction),
+                }
+            }
+            None => {
+                let keep = window.len().min(HEADER.len());
+                self.tail = window[window.len() - keep..].to_vec();
+                ZmodemScan {
+                    passthrough: data.len(),
+                    started: None,
+                }
+            }
+        }
+    }
+}
+
+/// Files named by a `sz` / `rz` command line; `None` if it is neither.
+pub fn transfer_files(command: &str) -> Option<(ZmodemDirection, Vec<String>)> {
+    let words = split_words(command);
+    let program = words.first()?.rsplit('/').next()?;
+    let direction = match program {
+        "sz" | "lsz" => ZmodemDirection::Download,
+        "rz" | "lrz" => ZmodemDirection::Upload,
+        _ => return None,
+    };
+    let files = match direction {
+        ZmodemDirection::Download => words[1..]
+            .iter()
+            .filter(|w| !w.starts_with('-'))
+            .cloned()
+            .collect(),
+        ZmodemDirection::Upload => Vec::new(),
+    };
+    Some((direction, files))
+}
+
+/// `file` made absolute against `cwd` (POSIX remote paths).
+pub fn resolve(cwd: Option<&str>, file: &str) -> String {
+    match cwd {
+        Some(cwd) if !file.starts_with('/') && !file.starts_with('~') => {
+            format!("{}/{}", cwd.trim_end_matches('/'), file)
+        }
+        _ => file.to_string(),
+    }
+}
+
+/// Whitespace split honouring single/double quotes and backslash escapes.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                    in_word = true;
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_headers_across_chunks() {
+        let mut detector = ZmodemDetector::new();
+        assert_eq!(
+            detector.feed(b"$ sz notes.txt\r\n**\x18"),
+            ZmodemScan {
+                passthrough: 19,
+                started: None
+            }
+        );
+        // The header straddles the chunks: nothing more of this one is shown.
+        assert_eq!(
+            detector.feed(b"B00000000000000\r\n"),
+            ZmodemScan {
+                passthrough: 0,
+                started: Some(ZmodemDirection::Download)
+            }
+        );
+        // Retransmits while the cancel lands are swallowed without a new event.
+        assert_eq!(
+            detector.feed(b"**\x18B00000000000000\r\n"),
+            ZmodemScan::default()
+        );
+
+        let mut detector = ZmodemDetector::new();
+        let scan = detector.feed(b"rz waiting to receive.**\x18B0100000023be50\r\n");
+        assert_eq!(scan.passthrough, 22);
+        assert_eq!(scan.started, Some(ZmodemDirection::Upload));
+        assert_eq!(
+            ZmodemDetector::new().feed(b"plain output **"),
+            ZmodemScan {
+                passthrough: 15,
+                started: None
+            }
+        );
+    }
+
+    #[test]
+    fn parses_transfer_commands() {
+        assert_eq!(
+            transfer_files("sz -e 'my report.pdf' logs/app.log"),
+            Some((
+                ZmodemDirection::Download,
+                vec!["my report.pdf".to_string(), "logs/app.log".to_string()]
+            ))
+        );
+        assert_eq!(
+            transfer_files("/usr/bin/rz -y"),
+            Some((ZmodemDirection::Upload, vec![]))
+        );
+        assert_eq!(transfer_files("ls sz"), None);
+        assert_eq!(resolve(Some("/home/me/"), "a.txt"), "/home/me/a.txt");
+        assert_eq!(resolve(Some("/home/me"), "/etc/hosts"), "/etc/hosts");
+        assert_eq!(resolve(None, "a.txt"), "a.txt");
+    }
+}
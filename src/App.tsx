@@ -10,6 +10,7 @@ import { ErrorBoundary } from './components/ErrorBoundary';
 import { PluginProvider } from './context/PluginContext';
 import { GlobalConfirmDialog } from './components/ui/GlobalConfirmDialog';
 import { GlobalVaultUnlockModal } from './components/vault/GlobalVaultUnlockModal';
+import { DangerousCommandDialog } from './components/terminal/DangerousCommandDialog';
 import * as RadixTooltip from '@radix-ui/react-tooltip';
 
 function AppContent() {
@@ -64,6 +65,7 @@ function App() {
                     <AppContent />
                     <GlobalConfirmDialog />
                     <GlobalVaultUnlockModal />
+                    <DangerousCommandDialog />
                 </RadixTooltip.Provider>
             </PluginProvider>
         </ErrorBoundary>
@@ -3,6 +3,7 @@ import {
   Copy,
   Download,
   FileArchive,
+  Fingerprint,
   FilePlus,
   FolderInput,
   Plus,
@@ -26,7 +27,7 @@ import { CopyToServerModal } from './file-manager/CopyToServerModal';
 import { FileGrid } from './file-manager/FileGrid';
 import { getCurrentDragSource } from '../lib/dragDrop';
 import { FileToolbar } from './file-manager/FileToolbar';
-import type { FileEntry } from './file-manager/types';
+import type { FileEntry, TextFile, TextFileFormat } from './file-manager/types';
 import { PropertiesPanel } from './file-manager/PropertiesPanel';
 import { ConflictModal, type ConflictAction } from './file-manager/ConflictModal';
 import { Button } from './ui/Button';
@@ -116,6 +117,7 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
   // Editor State
   const [editingFile, setEditingFile] = useState<FileEntry | null>(null);
   const [editorContent, setEditorContent] = useState('');
+  const [editorFormat, setEditorFormat] = useState<TextFileFormat | null>(null);
   const [editorProviderOverride, setEditorProviderOverride] = useState<string | null>(null);
 
   // Modal States
@@ -670,12 +672,13 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
     setIsFileLoading(true);
     try {
       const fullPath = currentPath === '/' ? `/${file.name}` : `${currentPath}/${file.name}`;
-      const content = await window.ipcRenderer.invoke('fs_read_file', {
+      const textFile: TextFile = await window.ipcRenderer.invoke('fs_read_file', {
         connectionId: activeConnectionId,
         path: fullPath,
       });
       setEditorProviderOverride(providerOverride ?? null);
-      setEditorContent(content);
+      setEditorContent(textFile.content);
+      setEditorFormat(textFile.format);
       setEditingFile(file);
     } catch (error: any) {
       if (handleConnectionError(activeConnectionId, error)) return;
@@ -738,6 +741,8 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
         connectionId: activeConnectionId,
         path: fullPath,
         content,
+        format: editorFormat,
+        snapshot: settings.editor?.snapshotOnSave ?? false,
       });
       setEditorContent(content);
       showToast('success', 'File saved');
@@ -746,7 +751,7 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
       showToast('error', `Failed to save file: ${error.message || String(error)}`);
       throw error;
     }
-  }, [activeConnectionId, editingFile, currentPath, handleConnectionError, showToast]);
+  }, [activeConnectionId, editingFile, editorFormat, currentPath, handleConnectionError, settings.editor?.snapshotOnSave, showToast]);
 
   const handleSelect = (filename: string, multi: boolean) => {
     if (!filename) {
@@ -919,6 +924,24 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
     }
   };
 
+  const handleChecksum = async (file: FileEntry, algo: 'sha256' | 'sha1' | 'md5') => {
+    if (!activeConnectionId) return;
+    setContextMenu(null);
+    const path = currentPath === '/' ? `/${file.name}` : `${currentPath}/${file.name}`;
+    try {
+      const result: { digest: string; method: 'server' | 'stream' } = await window.ipcRenderer.invoke('fs_checksum', {
+        connectionId: activeConnectionId,
+        path,
+        algo,
+      });
+      await navigator.clipboard.writeText(result.digest);
+      showToast('success', `${algo.toUpperCase()} of ${file.name} copied: ${result.digest}`);
+    } catch (error: any) {
+      if (handleConnectionError(activeConnectionId, error)) return;
+      showToast('error', `Checksum failed: ${error.message || String(error)}`);
+    }
+  };
+
   const handleDownloadAsZip = async () => {
     if (selectedFiles.length === 0 || !activeConnectionId) return;
     if (isZipping) return; // Prevent double-click opening two dialogs
@@ -1148,6 +1171,16 @@ export const FileManager = memo(function FileManager({ connectionId }: { connect
             label: `${option.value === (settings.editor?.defaultProvider ?? 'com.zync.editor.codemirror') ? '✓ ' : ''}${option.label}`,
             action: () => { void handleSetDefaultEditorProvider(option.value); },
           })),
+        } as ContextMenuItem, {
+          label: 'Checksum',
+          icon: <Fingerprint size={14} />,
+          children: (['sha256', 'sha1', 'md5'] as const).map((algo) => ({
+            label: algo.toUpperCase(),
+            action: () => {
+              if (!contextMenu?.file) return;
+              void handleChecksum(contextMenu.file, algo);
+            },
+          })),
         } as ContextMenuItem] : []),
         {
           label: 'Download as Archive (.tar.gz)',
@@ -2,14 +2,14 @@ import { memo, useCallback } from 'react';
 import { Copy, Play, ShieldCheck, ShieldAlert, AlertTriangle, Terminal, User } from 'lucide-react';
 import { AgentIcon } from './AgentIcon';
 import { cn } from '../../lib/utils';
-import type { AiDisplayEntry } from '../../ai/types/common';
+import type { AiDisplayEntry, RiskLevel } from '../../ai/types/common';
 import { AiSetupErrorCard } from './AiSetupErrorCard';
 import { useAppStore } from '../../store/useAppStore';
 import type { ProviderValue } from './providerCatalog';
 
 interface AiChatMessageProps {
     entry: AiDisplayEntry;
-    onRunCommand?: (command: string) => void;
+    onRunCommand?: (command: string, safety?: RiskLevel) => void;
 }
 
 const SAFETY_CONFIG = {
@@ -154,7 +154,7 @@ export const AiChatMessage = memo(function AiChatMessage({ entry, onRunCommand }
                                     {onRunCommand && (
                                         <div className="px-3 py-2 border-t border-app-border/20 bg-app-surface/40">
                                             <button
-                                                onClick={() => onRunCommand(result.command)}
+                                                onClick={() => onRunCommand(result.command, result.safety)}
                                                 className="group flex items-center gap-1.5 px-3 py-1.5 rounded-lg text-[11px] font-bold tracking-wide bg-app-accent/10 text-app-accent border border-app-accent/20 hover:bg-app-accent hover:text-white transition-all w-fit shadow-sm shadow-app-accent/5"
                                             >
                                                 <Play size={10} className="group-hover:text-white transition-colors" fill="currentColor" />
@@ -31,6 +31,7 @@ import {
     submitAskQuery,
 } from './sidebarSubmit';
 import { useAiSidebarResize } from './useAiSidebarResize';
+import type { RiskLevel } from '../../ai/types/common';
 
 // ──────────────────────────────────────────────────────────────────────────
 // Types & Constants
@@ -40,7 +41,7 @@ interface MessageListProps {
     connectionId: string | null;
     isLoading: boolean;
     streamingText: string;
-    onRunCommand: (command: string) => void;
+    onRunCommand: (command: string, safety?: RiskLevel) => void;
 }
 
 const MessageList = memo(function MessageList({
@@ -112,7 +113,7 @@ const MessageList = memo(function MessageList({
 interface AiSidebarProps {
     connectionId: string | null;
     activeTermId?: string | null;
-    onRunCommand?: (connectionId: string, command: string) => void;
+    onRunCommand?: (connectionId: string, command: string, safety?: RiskLevel) => void;
 }
 
 export function AiSidebar({ connectionId, activeTermId: activeTermIdProp, onRunCommand }: AiSidebarProps) {
@@ -242,8 +243,8 @@ export function AiSidebar({ connectionId, activeTermId: activeTermIdProp, onRunC
         ta.style.height = Math.min(ta.scrollHeight, 120) + 'px';
     }, []);
 
-    const handleRunCommand = useCallback((cmd: string) => {
-        if (connectionId && onRunCommand) onRunCommand(connectionId, cmd);
+    const handleRunCommand = useCallback((cmd: string, safety?: RiskLevel) => {
+        if (connectionId && onRunCommand) onRunCommand(connectionId, cmd, safety);
     }, [connectionId, onRunCommand]);
 
     const handleClearHistory = useCallback(() => {
@@ -6,3 +6,15 @@ export interface FileEntry {
   permissions: string; // Changed from rights object
   path: string;
 }
+
+/** How a file opened in the editor was stored; passed back on save. */
+export interface TextFileFormat {
+  encoding: 'utf-8' | 'utf-16le' | 'utf-16be' | 'latin-1';
+  bom: boolean;
+  lineEnding: 'lf' | 'crlf' | 'mixed';
+}
+
+export interface TextFile {
+  content: string;
+  format: TextFileFormat;
+}
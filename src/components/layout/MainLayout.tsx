@@ -26,11 +26,13 @@ import {
     cancelAllIdlePtySuspends,
     cancelIdlePtySuspend,
     resolveIdleHostPtySuspendDelayMs,
+    runAiCommand,
     scheduleIdlePtySuspend,
     shouldIdleSuspendConnection,
     terminalService,
 } from '../../lib/terminal';
 import { refreshAllCachedTerminalThemes } from '../terminal/terminalTheme';
+import type { RiskLevel } from '../../ai/types/common';
 import { registerTunnelTransportLostListener } from '../../features/tunnels/application/tunnelTransportLost';
 
 
@@ -895,11 +897,16 @@ export function MainLayout({ children }: { children: ReactNode }) {
         }
     }, []);
 
-    const handleAiRunCommand = useCallback((connectionId: string, command: string) => {
-        const normalizedCommand = command.endsWith('\r') ? command : `${command}\r`;
-        window.dispatchEvent(new CustomEvent('ssh-ui:run-command', {
-            detail: { connectionId, command: normalizedCommand },
-        }));
+    const handleAiRunCommand = useCallback((connectionId: string, command: string, safety?: RiskLevel) => {
+        const termId = useAppStore.getState().activeTerminalIds[connectionId];
+        if (termId) {
+            runAiCommand(termId, command, safety);
+        } else {
+            const normalizedCommand = command.endsWith('\r') ? command : `${command}\r`;
+            window.dispatchEvent(new CustomEvent('ssh-ui:run-command', {
+                detail: { connectionId, command: normalizedCommand, safety },
+            }));
+        }
 
         const currentTabId = useAppStore.getState().activeTabId;
         if (currentTabId) {
@@ -16,7 +16,7 @@ interface ImportSSHCommandModalProps {
 }
 
 interface ParsedTunnel {
-    type: 'local' | 'remote' | 'dynamic';
+    type: 'local' | 'remote' | 'dynamic' | 'remote-dynamic';
     localPort: number;
     remoteHost: string;
     remotePort: number;
@@ -204,12 +204,14 @@ export function ImportSSHCommandModal({
                                     <div key={i} className="flex items-center gap-3 p-2 rounded bg-app-surface/50 border border-app-border/20 text-xs text-app-muted">
                                         <div className="flex items-center gap-1.5 font-mono">
                                             <span className="text-app-text/90 font-bold">
-                                                {t.type === 'remote' ? t.remotePort : t.localPort}
+                                                {t.type === 'remote' || t.type === 'remote-dynamic' ? t.remotePort : t.localPort}
                                             </span>
                                             <span className="text-app-muted/50">→</span>
                                             <span className="text-app-text/90 font-bold">
                                                 {t.type === 'dynamic'
                                                     ? 'SOCKS (any host)'
+                                                    : t.type === 'remote-dynamic'
+                                                    ? 'SOCKS via this machine'
                                                     : t.type === 'local'
                                                         ? `${t.remoteHost}:${t.remotePort}`
                                                         : `localhost:${t.localPort}`}
@@ -222,7 +224,7 @@ export function ImportSSHCommandModal({
                                                     ? 'bg-purple-500/10 text-purple-400 border border-purple-500/20'
                                                     : 'bg-blue-500/10 text-blue-400 border border-blue-500/20'
                                             }`}>
-                                            {t.type === 'dynamic' ? 'socks' : t.type}
+                                            {t.type === 'dynamic' ? 'socks' : t.type === 'remote-dynamic' ? 'reverse socks' : t.type}
                                         </span>
                                     </div>
                                 ))}
@@ -87,12 +87,16 @@ const IMPORT_SOURCE_OPTIONS: Array<{ value: SshImportSourceType; label: string }
     { value: 'default_ssh', label: '~/.ssh/config' },
     { value: 'file', label: 'Custom SSH config file' },
     { value: 'text', label: 'Paste SSH config text' },
+    { value: 'putty', label: 'PuTTY saved sessions' },
+    { value: 'ansible', label: 'Ansible inventory (INI or YAML)' },
 ];
 
 const IMPORT_SOURCE_LABELS: Record<SshImportSourceType, string> = {
     default_ssh: '~/.ssh/config',
     file: 'Custom SSH config file',
     text: 'Pasted SSH config text',
+    putty: 'PuTTY saved sessions',
+    ansible: 'Ansible inventory',
 };
 
 const normalizeImportedConnectionPayload = (payload: ImportedConnectionPayload): Connection => ({
@@ -105,7 +109,8 @@ const normalizeImportedConnectionPayload = (payload: ImportedConnectionPayload):
     jumpServerId: payload.jumpServerId,
     status: 'disconnected',
     icon: 'Server',
-    tags: [],
+    tags: payload.tags ?? [],
+    folder: payload.folder,
 });
 
 const toImportedConnectionPayload = (connection: Connection): ImportedConnectionPayload => ({
@@ -116,6 +121,8 @@ const toImportedConnectionPayload = (connection: Connection): ImportedConnection
     port: connection.port,
     privateKeyPath: connection.privateKeyPath,
     jumpServerId: connection.jumpServerId,
+    tags: connection.tags,
+    folder: connection.folder,
 });
 
 const createDefaultDecisionMap = (
@@ -256,7 +263,7 @@ function ImportSourceBar({
     diagnostics,
 }: ImportSourceBarProps) {
     const isLoadDisabled = loading
-        || (sourceType === 'file' && !filePath.trim())
+        || ((sourceType === 'file' || sourceType === 'ansible') && !filePath.trim())
         || (sourceType === 'text' && !textContent.trim());
 
     if (!expanded) {
@@ -294,12 +301,14 @@ function ImportSourceBar({
                 </Button>
             </div>
 
-            {sourceType === 'file' && (
+            {(sourceType === 'file' || sourceType === 'ansible') && (
                 <div className="flex items-center gap-2">
                     <input
                         value={filePath}
                         onChange={(event) => onFilePathChange(event.target.value)}
-                        placeholder="Select SSH config file path..."
+                        placeholder={sourceType === 'ansible'
+                            ? 'Select Ansible inventory file path...'
+                            : 'Select SSH config file path...'}
                         className="h-8 flex-1 rounded-md border border-app-border bg-app-bg px-2 text-xs text-app-text outline-none focus:border-app-accent/60"
                     />
                     <Button size="sm" variant="ghost" onClick={onBrowseFile} disabled={loading}>
@@ -469,8 +478,8 @@ export function ImportSshModal({ isOpen, onClose, onImport, onImportReport }: Im
         setDecisions({});
         setSearchQuery('');
         setIsSourceBarExpanded(true);
-        if (nextSource === 'default_ssh') {
-            void loadConfigsFromSource('default_ssh', '', '', existingConnectionsOnOpenRef.current);
+        if (nextSource === 'default_ssh' || nextSource === 'putty') {
+            void loadConfigsFromSource(nextSource, '', '', existingConnectionsOnOpenRef.current);
         }
     };
 
@@ -12,6 +12,7 @@ const DESCRIPTIONS: Record<TunnelType, string> = {
     local: 'Reach a service behind the remote firewall as if it were running on your machine.',
     remote: 'Expose a port on the remote host that tunnels incoming connections back to you.',
     dynamic: 'Turn a local port into a SOCKS proxy that routes anywhere through the remote host.',
+    'remote-dynamic': 'Give the remote host a SOCKS proxy that reaches the network through your machine.',
 };
 
 interface TunnelTypeStepProps {
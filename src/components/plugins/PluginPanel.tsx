@@ -46,9 +46,9 @@ export function PluginPanel({ html, panelId, pluginId, connectionId }: PluginPan
             if (!type) return;
 
             if (type === 'zync:terminal:send') {
-                window.dispatchEvent(new CustomEvent('zync:terminal:send', { detail: { text: payload.text, connectionId } }));
+                window.dispatchEvent(new CustomEvent('zync:terminal:send', { detail: { text: payload.text, connectionId, pluginId } }));
             } else if (type === 'zync:terminal:opentab') {
-                window.dispatchEvent(new CustomEvent('ssh-ui:new-terminal-tab', { detail: { connectionId, command: payload.command } }));
+                window.dispatchEvent(new CustomEvent('ssh-ui:new-terminal-tab', { detail: { connectionId, command: payload.command, pluginId } }));
             } else if (type === 'zync:statusbar:set') {
                 window.dispatchEvent(new CustomEvent('zync:statusbar:set', { detail: payload }));
             } else if (type === 'zync:ui:notify') {
@@ -14,6 +14,9 @@ interface RegistryPlugin {
     description: string;
     author: string;
     downloadUrl: string;
+    sha256?: string;
+    installedVersion?: string;
+    updateAvailable?: boolean;
     thumbnailUrl?: string; // Optional
     icon?: string; // Lucide icon name
     mode?: 'dark' | 'light';
@@ -28,9 +31,6 @@ interface MarketplaceProps {
     onInstallSuccess?: () => void;
 }
 
-// Registry URL (Make this configurable later)
-const REGISTRY_URL = "https://raw.githubusercontent.com/zync-sh/zync-extensions/main/marketplace.json";
-
 // Icon Resolver Helper
 const IconResolver = ({ name, size = 16, className = "" }: { name?: string, size?: number, className?: string }) => {
     const icons: any = {
@@ -80,11 +80,9 @@ export function Marketplace({ onInstallSuccess }: MarketplaceProps) {
         setIsLoading(true);
         setError(null);
         try {
-            // Try fetching from real URL
-            const res = await fetch(REGISTRY_URL);
-            if (!res.ok) throw new Error('Failed to fetch registry');
-            const data = await res.json();
-            setRegistry(data.plugins || []);
+            // Fetched by the backend from settings.plugins.registryUrl
+            const plugins = await ipcRenderer.invoke('plugins:registryList', { refresh: true });
+            setRegistry(plugins || []);
         } catch (err) {
             console.error(err);
             // If fetch fails, we just show empty list since mock is empty now
@@ -99,11 +97,8 @@ export function Marketplace({ onInstallSuccess }: MarketplaceProps) {
     const handleInstall = async (plugin: RegistryPlugin) => {
         setInstallingId(plugin.id);
         try {
-            // 1. Install via Backend
-            // We use the downloadUrl. If it's a mock URL, this will fail in the backend unless we mock that too.
-            // For the mock "Oceanic", let's assume it might fail if the URL isn't real.
-            // But the flow is correct.
-            await ipcRenderer.invoke('plugins_install', { url: plugin.downloadUrl });
+            // Backend downloads, checks the registry checksum and replaces older versions
+            await ipcRenderer.invoke('plugins:installById', { id: plugin.id });
             await refreshInstalledPlugins();
         } catch (err: any) {
             console.error(err);
@@ -231,7 +226,7 @@ export function Marketplace({ onInstallSuccess }: MarketplaceProps) {
                                         {/* Action Button */}
                                         {installed ? (
                                             <div className="flex items-center gap-2">
-                                                {localVersion && plugin.version !== localVersion && (
+                                                {localVersion && plugin.updateAvailable && (
                                                     <button
                                                         onClick={() => handleInstall(plugin)}
                                                         disabled={processing}
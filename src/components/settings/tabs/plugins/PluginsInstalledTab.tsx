@@ -17,6 +17,8 @@ interface InstalledPlugin {
     manifest: PluginManifest;
     enabled: boolean;
     path: string;
+    /** Trusted publisher that signed the installed package */
+    publisher?: string | null;
 }
 
 interface PluginsInstalledTabProps {
@@ -201,6 +203,7 @@ export function PluginsInstalledTab({
                                         </div>
                                         <p className="text-[10px] text-[var(--color-app-muted)] mt-0.5 font-mono opacity-60 leading-none truncate">
                                             {plugin.manifest.id}
+                                            {plugin.publisher && ` · signed by ${plugin.publisher}`}
                                         </p>
                                     </div>
                                 </div>
@@ -0,0 +1,132 @@
+import { useEffect, useState } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { AlertTriangle } from 'lucide-react';
+import { Modal } from '../ui/Modal';
+import { Button } from '../ui/Button';
+import { Input } from '../ui/Input';
+import { useAppStore } from '../../store/useAppStore';
+
+interface DangerousChallenge {
+    challengeId: string;
+    termId: string;
+    /** The exact data that was held back from the terminal. */
+    command: string;
+    host: string;
+    expiresInMs: number;
+}
+
+/**
+ * Asks the user to re-type the target host before a dangerous command reaches a
+ * terminal. The backend holds the write back and emits `ai:dangerous-confirm`;
+ * once the host matches, the write is repeated with the single-use token.
+ */
+export function DangerousCommandDialog() {
+    const showToast = useAppStore(state => state.showToast);
+    const [challenge, setChallenge] = useState<DangerousChallenge | null>(null);
+    const [typedHost, setTypedHost] = useState('');
+    const [error, setError] = useState('');
+    const [isSubmitting, setIsSubmitting] = useState(false);
+
+    useEffect(() => {
+        const unlisten = listen<DangerousChallenge>('ai:dangerous-confirm', ({ payload }) => {
+            setChallenge(payload);
+            setTypedHost('');
+            setError('');
+        });
+        return () => {
+            unlisten.then(fn => fn());
+        };
+    }, []);
+
+    const close = () => {
+        setChallenge(null);
+        setTypedHost('');
+        setError('');
+    };
+
+    const handleSubmit = async (e: React.FormEvent) => {
+        e.preventDefault();
+        if (!challenge) return;
+        setIsSubmitting(true);
+        try {
+            const approvalToken: string = await window.ipcRenderer.invoke('ai:dangerousConfirm', {
+                challengeId: challenge.challengeId,
+                typedHost,
+            });
+            await window.ipcRenderer.invoke('terminal:write', {
+                termId: challenge.termId,
+                data: challenge.command,
+                approvalToken,
+            });
+            close();
+        } catch (err: unknown) {
+            const message = err instanceof Error ? err.message : String(err);
+            if (message.startsWith('Hostname did not match; type')) {
+                setError(message);
+            } else {
+                showToast('error', message);
+                close();
+            }
+        } finally {
+            setIsSubmitting(false);
+        }
+    };
+
+    return (
+        <Modal
+            isOpen={challenge !== null}
+            onClose={close}
+            title="Run dangerous command?"
+            width="max-w-md"
+        >
+            {challenge && (
+                <form onSubmit={handleSubmit} className="flex flex-col gap-4 py-2">
+                    <div className="flex items-start gap-4 px-1">
+                        <div className="w-10 h-10 shrink-0 rounded-full flex items-center justify-center shadow-xl ring-1 bg-red-500/10 border-red-500/30 text-red-400 ring-red-500/20">
+                            <AlertTriangle size={20} />
+                        </div>
+                        <p className="flex-1 pt-1 text-app-text/70 text-[12px] leading-relaxed font-medium">
+                            This command can destroy data on <span className="font-bold text-app-text">{challenge.host}</span>.
+                            Type the host name to run it.
+                        </p>
+                    </div>
+                    <pre className="max-h-40 overflow-auto rounded-lg bg-black/30 border border-app-border px-3 py-2 text-[12px] font-mono text-red-300 whitespace-pre-wrap break-all">
+                        {challenge.command.replace(/[\r\n]+$/, '')}
+                    </pre>
+                    <Input
+                        autoFocus
+                        value={typedHost}
+                        onChange={e => {
+                            setTypedHost(e.target.value);
+                            setError('');
+                        }}
+                        placeholder={challenge.host}
+                        error={error || undefined}
+                        spellCheck={false}
+                        autoComplete="off"
+                    />
+                    <div className="flex items-center gap-2 justify-end">
+                        <Button
+                            type="button"
+                            variant="ghost"
+                            onClick={close}
+                            className="px-4 h-8 font-bold text-[10px] uppercase tracking-widest hover:bg-white/[0.05]"
+                            disabled={isSubmitting}
+                        >
+                            Cancel
+                        </Button>
+                        <Button
+                            type="submit"
+                            variant="danger"
+                            isLoading={isSubmitting}
+                            disabled={!typedHost.trim()}
+                            className="px-6 h-8 font-bold text-[10px] uppercase tracking-widest min-w-[100px] bg-red-500 hover:bg-red-600 border-0 text-white shadow-lg shadow-red-500/20"
+                        >
+                            Run
+                        </Button>
+                    </div>
+                </form>
+            )}
+        </Modal>
+    );
+}
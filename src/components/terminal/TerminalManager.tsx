@@ -6,7 +6,7 @@ import { useShallow } from 'zustand/react/shallow';
 import { Terminal as TerminalIcon, Plus, X, Zap } from 'lucide-react';
 import { cn } from '../../lib/utils';
 import { once, type UnlistenFn } from '@tauri-apps/api/event';
-import { queueTerminalInput } from '../../lib/terminal';
+import { queueTerminalInput, runAiCommand, writePluginTerminalInput } from '../../lib/terminal';
 
 // TerminalTab interface is now in store/terminalSlice
 // export interface TerminalTab ... removed
@@ -72,17 +72,26 @@ export function TerminalManager({
     // Event Listener for external commands (Snippets)
     useEffect(() => {
         const handleRunCommand = (e: any) => {
-            const { connectionId: targetConnId, command } = e.detail;
+            const { connectionId: targetConnId, command, safety } = e.detail;
             if (targetConnId === activeConnectionId && activeTabIdRef.current) {
-                queueTerminalInput(activeTabIdRef.current, command);
+                // AI commands carry their safety rating and take the approval path.
+                if (safety) {
+                    runAiCommand(activeTabIdRef.current, command, safety);
+                } else {
+                    queueTerminalInput(activeTabIdRef.current, command);
+                }
             }
         };
 
         const handleTriggerNewTab = (e: any) => {
-            const { connectionId: targetConnId, command } = e.detail;
+            const { connectionId: targetConnId, command, pluginId } = e.detail;
             if (targetConnId === activeConnectionId && activeConnectionId) {
                 // `createTerminal` takes only `connectionId` and returns the newly created `termId`.
                 const newId = createTerminal(activeConnectionId);
+                // Commands from plugin panels need the plugin's terminal permission.
+                const sendCommand = (termId: string) => pluginId
+                    ? writePluginTerminalInput(pluginId, termId, command)
+                    : queueTerminalInput(termId, command);
 
                 // If a command was passed (e.g., from a plugin like PM2 logs), execute it in the new tab after it's ready
                 if (command) {
@@ -97,7 +106,7 @@ export function TerminalManager({
                             delete pendingReadyRef.current[newId];
 
                             console.warn(`[TerminalManager] terminal-ready-${newId} timed out, sending command anyway`);
-                            queueTerminalInput(newId, command);
+                            sendCommand(newId);
                         }, 5000)
                     };
                     pendingReadyRef.current[newId] = pendingObj;
@@ -108,7 +117,7 @@ export function TerminalManager({
                             obj.sent = true;
                             clearTimeout(obj.timeoutId);
                             delete pendingReadyRef.current[newId];
-                            queueTerminalInput(newId, command);
+                            sendCommand(newId);
                         }
                     }).then(unlisten => {
                         const obj = pendingReadyRef.current[newId];
@@ -140,10 +149,10 @@ export function TerminalManager({
         };
 
         const handlePluginTerminalSend = (e: any) => {
-            const { connectionId: targetConnId, text } = (e as CustomEvent).detail;
+            const { connectionId: targetConnId, text, pluginId } = (e as CustomEvent).detail;
             // If the plugin didn't provide a connectionId, or it matches ours, and we have an active terminal
-            if ((!targetConnId || targetConnId === activeConnectionId) && activeTabIdRef.current) {
-                queueTerminalInput(activeTabIdRef.current, text);
+            if ((!targetConnId || targetConnId === activeConnectionId) && activeTabIdRef.current && pluginId) {
+                writePluginTerminalInput(pluginId, activeTabIdRef.current, text);
             }
         };
 
@@ -10,7 +10,9 @@ import { AddTunnelModal } from '../modals/AddTunnelModal';
 import { ImportSSHCommandModal } from '../modals/ImportSSHCommandModal';
 import { TunnelCard, TunnelConfig } from './TunnelCard';
 import { getConnectionDisplayLabels } from '../../features/connections/domain/connectionDisplay';
+import { isTunnelRunning } from '../../features/tunnels/domain/tunnelTypes';
 import {
+    formatTunnelError,
     parsePortConflictError,
     tunnelWithSwappedPort,
 } from '../../features/tunnels/application/tunnelPortConflict';
@@ -161,7 +163,7 @@ export function GlobalTunnelList() {
         return a.localeCompare(b);
     });
 
-    const activeCount = allTunnels.filter(t => t.status === 'active').length;
+    const activeCount = allTunnels.filter(t => isTunnelRunning(t.status)).length;
     const serversCount = new Set(allTunnels.map(t => t.connectionId)).size;
 
     const handleToggleTunnel = async (tunnel: TunnelConfig) => {
@@ -172,7 +174,7 @@ export function GlobalTunnelList() {
         }
 
         try {
-            if (tunnel.status === 'active') {
+            if (isTunnelRunning(tunnel.status)) {
                 await stopTunnelConfig(tunnel, stopTunnel);
                 showToast('info', 'Forwarding stopped');
 
@@ -188,7 +190,7 @@ export function GlobalTunnelList() {
 
                 // Connection Cleanup Logic
                 setTimeout(() => {
-                    const remainingActiveForthost = allTunnels.filter(t => t.connectionId === tunnel.connectionId && t.status === 'active' && t.id !== tunnel.id).length;
+                    const remainingActiveForthost = allTunnels.filter(t => t.connectionId === tunnel.connectionId && isTunnelRunning(t.status) && t.id !== tunnel.id).length;
                     const hasActiveTabs = tabs.some(tab => tab.connectionId === tunnel.connectionId && (tab.view === 'terminal' || tab.view === 'files'));
                     const hasActiveTerminals = (terminals[tunnel.connectionId] || []).length > 0;
 
@@ -216,8 +218,7 @@ export function GlobalTunnelList() {
                 setPortSuggestion(conflict);
                 return;
             }
-            const errorMsg = error instanceof Error ? error.message : String(error);
-            showToast('error', `Action failed: ${errorMsg}`);
+            showToast('error', `Action failed: ${formatTunnelError(error)}`);
         }
     };
 
@@ -308,7 +309,7 @@ export function GlobalTunnelList() {
 
         // Sequential stop
         for (const tunnel of groupTunnels) {
-            if (tunnel.status === 'active') {
+            if (isTunnelRunning(tunnel.status)) {
                 try {
                     await stopTunnel(tunnel.id, tunnel.connectionId);
                     count++;
@@ -479,7 +480,7 @@ export function GlobalTunnelList() {
                         <div className="w-full space-y-6">
                                 {sortedGroupNames.map(groupName => {
                                     const ports = groupedTunnels[groupName];
-                                    const activeCount = ports.filter(t => t.status === 'active').length;
+                                    const activeCount = ports.filter(t => isTunnelRunning(t.status)).length;
 
                                     const isCollapsed = collapsedGroups.has(groupName);
 
@@ -11,13 +11,13 @@ import {
 } from 'lucide-react';
 import { cn } from '../../lib/utils';
 import { OSIcon } from '../icons/OSIcon';
-import { isDynamicTunnel, socks5Url } from '../../features/tunnels/domain/tunnelTypes';
+import { isDynamicTunnel, isTunnelRunning, socks5Url } from '../../features/tunnels/domain/tunnelTypes';
 import {
     formatTunnelFlow,
     TUNNEL_TYPE_META,
     tunnelCopyAddress,
 } from '../../features/tunnels/presentation/tunnelDisplay';
-import type { TunnelType } from '../../features/tunnels/domain/tunnelTypes';
+import type { TunnelStatus, TunnelType } from '../../features/tunnels/domain/tunnelTypes';
 
 export interface TunnelConfig {
     id: string;
@@ -29,7 +29,7 @@ export interface TunnelConfig {
     remotePort: number;
     bindToAny?: boolean;
     bindAddress?: string;
-    status: 'active' | 'error' | 'stopped';
+    status: TunnelStatus;
     autoStart?: boolean;
     group?: string;
     error?: string;
@@ -232,6 +232,14 @@ function TunnelMetaBadges({ tunnel }: { tunnel: TunnelConfig }) {
                     Auto
                 </span>
             )}
+            {tunnel.status === 'degraded' && (
+                <span
+                    className="rounded-full border border-amber-400/20 bg-amber-400/10 px-2 py-0.5 text-[9px] font-semibold text-amber-400"
+                    title={tunnel.error}
+                >
+                    Degraded
+                </span>
+            )}
         </>
     );
 }
@@ -314,7 +322,7 @@ export function TunnelCard({
     onOpenBrowser,
     onCopy,
 }: TunnelCardProps) {
-    const isActive = tunnel.status === 'active';
+    const isActive = isTunnelRunning(tunnel.status);
     const isDynamic = isDynamicTunnel(tunnel.type);
     const socksUrl = socks5Url(tunnel.bindAddress, tunnel.localPort);
     const copyText = tunnelCopyAddress(tunnel, socksUrl);
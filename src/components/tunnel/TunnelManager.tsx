@@ -10,8 +10,10 @@ import { Modal } from '../ui/Modal';
 import { TopbarDropdown } from '../ui/TopbarDropdown';
 import { TunnelCard, TunnelConfig } from './TunnelCard';
 import { getConnectionDisplayLabels } from '../../features/connections/domain/connectionDisplay';
+import { isTunnelRunning } from '../../features/tunnels/domain/tunnelTypes';
 
 import {
+  formatTunnelError,
   parsePortConflictError,
   tunnelWithSwappedPort,
 } from '../../features/tunnels/application/tunnelPortConflict';
@@ -113,7 +115,7 @@ export function TunnelManager({ connectionId }: { connectionId?: string }) {
 
   const handleToggleTunnel = async (tunnel: TunnelConfig) => {
     try {
-      if (tunnel.status === 'active') {
+      if (isTunnelRunning(tunnel.status)) {
         await stopTunnel(tunnel.id, tunnel.connectionId);
         showToast('info', 'Forwarding stopped');
 
@@ -136,8 +138,7 @@ export function TunnelManager({ connectionId }: { connectionId?: string }) {
         setPortSuggestion(conflict);
         return;
       }
-      const errorMsg = error instanceof Error ? error.message : String(error);
-      showToast('error', `Action failed: ${errorMsg}`);
+      showToast('error', `Action failed: ${formatTunnelError(error)}`);
     }
   };
 
@@ -210,7 +211,7 @@ export function TunnelManager({ connectionId }: { connectionId?: string }) {
 
     // Sequential stop
     for (const tunnel of groupTunnels) {
-      if (tunnel.status === 'active') {
+      if (isTunnelRunning(tunnel.status)) {
         try {
           await stopTunnel(tunnel.id, tunnel.connectionId);
           count++;
@@ -236,7 +237,7 @@ export function TunnelManager({ connectionId }: { connectionId?: string }) {
           <h2 className="text-sm font-bold tracking-tight text-app-text">Port Forwarding</h2>
           {tunnels.length > 0 && (
             <span className="text-[10px] text-app-muted/60 font-medium px-1.5 py-0.5 rounded-md bg-app-surface/50 border border-app-border/30">
-              {tunnels.filter(t => t.status === 'active').length} Active
+              {tunnels.filter(t => isTunnelRunning(t.status)).length} Active
             </span>
           )}
         </div>
@@ -367,7 +368,7 @@ export function TunnelManager({ connectionId }: { connectionId?: string }) {
 
               return sortedGroupNames.map(groupName => {
                 const groupTunnels = groups[groupName];
-                const activeCount = groupTunnels.filter(t => t.status === 'active').length;
+                const activeCount = groupTunnels.filter(t => isTunnelRunning(t.status)).length;
 
                 return (
                   <div key={groupName} className="space-y-3">
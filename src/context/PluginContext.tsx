@@ -29,11 +29,30 @@ export interface Plugin {
         iconsPath?: string;
         icons_path?: string;
         editor?: EditorProviderManifest;
+        permissions?: {
+            fs?: { path: string; write?: boolean }[];
+            network?: boolean;
+            exec?: boolean;
+            ssh?: boolean;
+            terminal?: boolean;
+        };
+        hooks?: string[];
+        settingsSchema?: Record<string, {
+            type: 'string' | 'number' | 'integer' | 'boolean' | 'array' | 'object';
+            default?: unknown;
+            title?: string;
+            description?: string;
+            enum?: unknown[];
+            minimum?: number;
+            maximum?: number;
+        }>;
     };
     script?: string;
     style?: string;
     editorHtml?: string;
     enabled: boolean;
+    /** Trusted publisher that signed the installed package (plugin_signing.rs) */
+    publisher?: string | null;
 }
 
 interface PluginCommand {
@@ -127,11 +146,29 @@ const zync = {
     },
 
     terminal: {
+        // Everything below needs the "terminal" permission in manifest.json
         send: (text) => {
             self.postMessage({ type: 'api:terminal:send', payload: { text } });
+        },
+        write: (termId, data) => zync.request('api:terminal:write', { termId, data }),
+        readBuffer: (termId, maxBytes) => zync.request('api:terminal:readBuffer', { termId, maxBytes }),
+        onOutput: (termId, callback) => {
+            zync.on('terminal:output:' + termId, callback);
+            return zync.request('api:terminal:subscribe', { termId });
+        },
+        offOutput: (termId) => {
+            delete zync.callbacks['terminal:output:' + termId];
+            return zync.request('api:terminal:unsubscribe', { termId });
         }
     },
 
+    // Need the "exec" (exec) and "ssh" (readFile, writeFile) permissions
+    ssh: {
+        exec: (connectionId, command, timeoutSecs) => zync.request('api:ssh:exec', { connectionId, command, timeoutSecs }),
+        readFile: (connectionId, path) => zync.request('api:sftp:read', { connectionId, path }),
+        writeFile: (connectionId, path, content) => zync.request('api:sftp:write', { connectionId, path, content }),
+    },
+
     statusBar: {
         set: (id, text) => {
             self.postMessage({ type: 'api:statusbar:set', payload: { id, text } });
@@ -160,6 +197,18 @@ const zync = {
         list: () => zync.request('api:plugins:load', {})
     },
 
+    // Values are checked against "settingsSchema" in manifest.json; set(values) updates
+    // only the keys given, and a null value resets a setting to its default
+    settings: {
+        get: () => zync.request('api:settings:get', {}),
+        set: (values) => zync.request('api:settings:set', { values })
+    },
+
+    // Only hooks listed under "hooks" in manifest.json are delivered
+    hooks: {
+        on: (name, callback) => zync.on('hook:' + name, callback)
+    },
+
     logger: {
         log: (msg) => {
             self.postMessage({ type: 'api:log', payload: msg });
@@ -190,6 +239,10 @@ self.onmessage = async (e) => {
     } else if (type === 'command:execute') {
         const handler = zync.commandHandlers[payload.id];
         if (handler) await handler();
+    } else if (type === 'terminal:output') {
+        zync.emit('terminal:output:' + payload.termId, payload.data);
+    } else if (type === 'hook') {
+        zync.emit('hook:' + payload.hook, payload);
     }
 };
 
@@ -310,7 +363,34 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:terminal:send':
                 const activeConnId = useAppStore.getState().activeConnectionId;
-                window.dispatchEvent(new CustomEvent('zync:terminal:send', { detail: { text: payload.text, connectionId: activeConnId } }));
+                window.dispatchEvent(new CustomEvent('zync:terminal:send', { detail: { text: payload.text, connectionId: activeConnId, pluginId } }));
+                break;
+            case 'api:terminal:write':
+            case 'api:terminal:readBuffer':
+            case 'api:terminal:subscribe':
+            case 'api:terminal:unsubscribe':
+            case 'api:ssh:exec':
+            case 'api:sftp:read':
+            case 'api:sftp:write':
+            case 'api:settings:get':
+            case 'api:settings:set':
+                try {
+                    const commandsByType: Record<string, string> = {
+                        'api:terminal:write': 'plugin_terminal_write',
+                        'api:terminal:readBuffer': 'plugin_terminal_read_buffer',
+                        'api:terminal:subscribe': 'plugin_terminal_subscribe',
+                        'api:terminal:unsubscribe': 'plugin_terminal_unsubscribe',
+                        'api:ssh:exec': 'plugin_ssh_exec',
+                        'api:sftp:read': 'plugin_sftp_read',
+                        'api:sftp:write': 'plugin_sftp_write',
+                        'api:settings:get': 'plugin_settings_get',
+                        'api:settings:set': 'plugin_settings_set',
+                    };
+                    const result = await ipcRenderer.invoke(commandsByType[type], { ...payload, pluginId });
+                    respond(pluginId, type, { requestId: payload.requestId, result: result ?? true });
+                } catch (e: any) {
+                    respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
+                }
                 break;
             case 'api:statusbar:set':
                 window.dispatchEvent(new CustomEvent('zync:statusbar:set', { detail: { id: payload.id, text: payload.text } }));
@@ -364,7 +444,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
             // File System Bridge
             case 'api:fs:read':
                 try {
-                    const content = await ipcRenderer.invoke('plugin_fs_read', { path: payload.path });
+                    const content = await ipcRenderer.invoke('plugin_fs_read', { pluginId, path: payload.path });
                     respond(pluginId, type, { requestId: payload.requestId, result: content });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -372,7 +452,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:fs:write':
                 try {
-                    await ipcRenderer.invoke('plugin_fs_write', { path: payload.path, content: payload.content });
+                    await ipcRenderer.invoke('plugin_fs_write', { pluginId, path: payload.path, content: payload.content });
                     respond(pluginId, type, { requestId: payload.requestId, result: true });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -380,7 +460,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:fs:list':
                 try {
-                    const entries = await ipcRenderer.invoke('plugin_fs_list', { path: payload.path });
+                    const entries = await ipcRenderer.invoke('plugin_fs_list', { pluginId, path: payload.path });
                     respond(pluginId, type, { requestId: payload.requestId, result: entries });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -388,7 +468,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:fs:exists':
                 try {
-                    const exists = await ipcRenderer.invoke('plugin_fs_exists', { path: payload.path });
+                    const exists = await ipcRenderer.invoke('plugin_fs_exists', { pluginId, path: payload.path });
                     respond(pluginId, type, { requestId: payload.requestId, result: exists });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -396,7 +476,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:fs:mkdir':
                 try {
-                    await ipcRenderer.invoke('plugin_fs_create_dir', { path: payload.path });
+                    await ipcRenderer.invoke('plugin_fs_create_dir', { pluginId, path: payload.path });
                     respond(pluginId, type, { requestId: payload.requestId, result: true });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -404,7 +484,7 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
                 break;
             case 'api:window:create':
                 try {
-                    await ipcRenderer.invoke('plugin_window_create', payload);
+                    await ipcRenderer.invoke('plugin_window_create', { ...payload, pluginId });
                     respond(pluginId, type, { requestId: payload.requestId, result: true });
                 } catch (e: any) {
                     respond(pluginId, type, { requestId: payload.requestId, error: e.toString() });
@@ -449,6 +529,58 @@ export const PluginProvider: React.FC<{ children: React.ReactNode }> = ({ childr
         return () => window.removeEventListener('zync:quick-pick-select', handleQuickPickSelect);
     }, []);
 
+    // Output of terminals plugins subscribed to (plugin_terminal_subscribe)
+    useEffect(() => {
+        return ipcRenderer.on('plugin-terminal:output', (_event: any, output: any) => {
+            for (const pluginId of output.pluginIds ?? []) {
+                workers.current.get(pluginId)?.postMessage({
+                    type: 'terminal:output',
+                    payload: { termId: output.termId, data: output.data }
+                });
+            }
+        });
+    }, []);
+
+    // Lifecycle hooks, delivered to the plugins that registered for them (plugin_hooks.rs)
+    useEffect(() => {
+        const hooks = ['connection-opened', 'terminal-created', 'transfer-completed', 'before-disconnect'];
+        const unlisteners = hooks.map((hook) =>
+            ipcRenderer.on(`plugin-hook:${hook}`, (_event: any, payload: any) => {
+                for (const pluginId of payload.pluginIds ?? []) {
+                    workers.current.get(pluginId)?.postMessage({ type: 'hook', payload });
+                }
+            })
+        );
+        return () => unlisteners.forEach((unlisten) => unlisten());
+    }, []);
+
+    // Backend asks once per capability a plugin declared; see plugin_permissions.rs
+    useEffect(() => {
+        return ipcRenderer.on('plugin-permission:request', (_event: any, request: any) => {
+            const scope = request.scope?.length ? `\n\n${request.scope.join('\n')}` : '';
+            const allowed = confirm(`Allow plugin "${request.pluginName}" to use ${request.capability}?${scope}`);
+            ipcRenderer.invoke('plugins:setPermission', {
+                pluginId: request.pluginId,
+                capability: request.capability,
+                allowed
+            }).catch((e: any) => console.error('[PluginContext] Failed to save plugin permission:', e));
+        });
+    }, []);
+
+    // Unsigned packages installed under plugins.unsignedPackages "warn" (plugin_signing.rs)
+    useEffect(() => {
+        return ipcRenderer.on('plugin-signature:unsigned', (_event: any, warning: any) => {
+            showToast('warning', `Plugin ${warning.pluginId} ${warning.reason}`);
+        });
+    }, [showToast]);
+
+    // Registry entries without a sha256 (plugin_registry.rs)
+    useEffect(() => {
+        return ipcRenderer.on('plugin-registry:unverified', (_event: any, warning: any) => {
+            showToast('warning', `Plugin ${warning.pluginId} ${warning.version} has no registry checksum and was installed unverified`);
+        });
+    }, [showToast]);
+
     return (
         <PluginContext.Provider value={{ plugins, editorProviders, loaded, commands, panels, executeCommand }}>
             {children}
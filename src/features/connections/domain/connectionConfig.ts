@@ -23,6 +23,13 @@ export type ConnectAuthMethod =
     | ConnectAuthMethodPrivateKey
     | ConnectAuthMethodVaultRef;
 
+export interface ConnectFallback {
+    label?: string;
+    host?: string;
+    port?: number;
+    jumpHost: ConnectConfig | null;
+}
+
 export interface ConnectConfig {
     id: string;
     name: string;
@@ -31,6 +38,8 @@ export interface ConnectConfig {
     username: string;
     auth_method: ConnectAuthMethod;
     jump_host: ConnectConfig | null;
+    /** Alternate routes the backend tries in order after the primary endpoint. */
+    fallbacks?: ConnectFallback[];
 }
 
 type ConnectionWithLegacyAuthFields = Connection & {
@@ -147,6 +156,36 @@ export const buildConnectConfigResult = (
         config.jump_host = jumpResult.config;
     }
 
+    if (connection.fallbacks?.length) {
+        const fallbacks: ConnectFallback[] = [];
+        for (const fallback of connection.fallbacks) {
+            let jumpHost: ConnectConfig | null = null;
+            if (fallback.jumpServerId) {
+                const jumpResult = buildConnectConfigResult(
+                    connections,
+                    fallback.jumpServerId,
+                    new Set(visited),
+                );
+                if (jumpResult.status === 'error') {
+                    // A stale fallback (e.g. its jump server was deleted) must not
+                    // take the primary route down with it.
+                    console.warn(
+                        `[connect] Skipping a fallback of ${connection.id}: jump host ${fallback.jumpServerId} failed (${jumpResult.reason})`,
+                    );
+                    continue;
+                }
+                jumpHost = jumpResult.config;
+            }
+            fallbacks.push({
+                label: normalizeOptionalText(fallback.label),
+                host: normalizeOptionalText(fallback.host),
+                port: fallback.port,
+                jumpHost,
+            });
+        }
+        config.fallbacks = fallbacks;
+    }
+
     return { status: 'ok', config };
 };
 
@@ -161,8 +200,10 @@ export const buildConnectConfig = (
 
 export const connectConfigUsesVaultAuth = (config: ConnectConfig): boolean => {
     if (config.auth_method.type === 'VaultRef') return true;
-    if (config.jump_host) return connectConfigUsesVaultAuth(config.jump_host);
-    return false;
+    if (config.jump_host && connectConfigUsesVaultAuth(config.jump_host)) return true;
+    return (config.fallbacks ?? []).some(
+        (fallback) => fallback.jumpHost !== null && connectConfigUsesVaultAuth(fallback.jumpHost),
+    );
 };
 
 export const connectionUsesVaultAuth = (
@@ -16,6 +16,18 @@ export interface CredentialRef {
     purpose: CredentialPurpose;
 }
 
+/** Another route to the same server, tried in order when the connection's own endpoint fails. */
+export interface ConnectionFallback {
+    /** Shown in connect progress; defaults to `host:port`. */
+    label?: string;
+    /** Defaults to the connection's host. */
+    host?: string;
+    /** Defaults to the connection's port. */
+    port?: number;
+    /** Jump host for this route; unset connects directly. */
+    jumpServerId?: string;
+}
+
 export interface Connection {
     id: string;
     name: string;
@@ -36,8 +48,11 @@ export interface Connection {
     tags?: string[];
     createdAt?: number;
     isFavorite?: boolean;
+    /** Accent colour for the host list, e.g. `#ef4444`. */
+    color?: string;
     pinnedFeatures?: string[];
     homePath?: string;
+    fallbacks?: ConnectionFallback[];
 }
 
 export interface Folder {
@@ -44,12 +44,16 @@ export interface ImportedConnectionPayload {
     jumpServerAlias?: string;
     jumpServerId?: string;
     aliases?: string[];
+    tags?: string[];
+    folder?: string;
 }
-export type SshImportSourceType = 'default_ssh' | 'file' | 'text';
+export type SshImportSourceType = 'default_ssh' | 'file' | 'text' | 'putty' | 'ansible';
 export type SshImportSourceRequest =
     | { sourceType: 'default_ssh' }
     | { sourceType: 'file'; path: string }
-    | { sourceType: 'text'; content: string };
+    | { sourceType: 'text'; content: string }
+    | { sourceType: 'putty' }
+    | { sourceType: 'ansible'; path?: string; content?: string };
 
 export const testConnectionIpc = async (config: ConnectionConfigPayload): Promise<string> =>
     window.ipcRenderer.invoke('ssh:test', config);
@@ -68,6 +72,209 @@ export const importSshConfigBySourceIpc = async (
 ): Promise<ImportedConnectionPayload[]> =>
     window.ipcRenderer.invoke('ssh:importConfigBySource', request);
 
+export type SshConfigExportTarget = 'include' | 'config';
+export interface SshConfigExportResult {
+    path: string;
+    hosts: string[];
+    includeAdded: boolean;
+}
+
+export const exportSshConfigIpc = async (
+    connectionIds: string[],
+    target: SshConfigExportTarget = 'include',
+): Promise<SshConfigExportResult> =>
+    window.ipcRenderer.invoke('ssh:exportConfig', { connectionIds, target });
+
+export interface AwsImportOptions {
+    runningOnly?: boolean;
+    usePrivateIp?: boolean;
+    username?: string;
+    keyDirectory?: string;
+    tagFilters?: string[];
+}
+
+export const importAwsInstancesIpc = async (
+    profile: string | undefined,
+    region: string | undefined,
+    options: AwsImportOptions = {},
+    refreshMinutes?: number,
+): Promise<ImportedConnectionPayload[]> =>
+    window.ipcRenderer.invoke('cloud:importAws', { profile, region, options, refreshMinutes });
+
+export interface GcpImportQuery {
+    provider: 'gcp';
+    project?: string;
+    zones?: string[];
+    runningOnly?: boolean;
+    usePrivateIp?: boolean;
+    username?: string;
+    labelFilters?: string[];
+}
+
+export interface AzureImportQuery {
+    provider: 'azure';
+    subscription?: string;
+    resourceGroup?: string;
+    runningOnly?: boolean;
+    usePrivateIp?: boolean;
+    username?: string;
+    tagFilters?: string[];
+}
+
+export type CloudImportQuery =
+    | ({ provider: 'aws'; profile?: string; region?: string } & AwsImportOptions)
+    | GcpImportQuery
+    | AzureImportQuery;
+
+export const importCloudInstancesIpc = async (
+    query: CloudImportQuery,
+    refreshMinutes?: number,
+): Promise<ImportedConnectionPayload[]> =>
+    window.ipcRenderer.invoke('cloud:import', { query, refreshMinutes });
+
+export interface TailscaleDiscoveryQuery {
+    onlineOnly?: boolean;
+    useIp?: boolean;
+    username?: string;
+    tags?: string[];
+}
+
+export interface ZerotierDiscoveryQuery {
+    networkIds?: string[];
+    apiToken?: string;
+    apiUrl?: string;
+    onlineOnly?: boolean;
+    username?: string;
+}
+
+export const discoverTailscaleIpc = async (
+    query?: TailscaleDiscoveryQuery,
+): Promise<ImportedConnectionPayload[]> =>
+    window.ipcRenderer.invoke('discover:tailscale', { query });
+
+export const discoverZerotierIpc = async (
+    query?: ZerotierDiscoveryQuery,
+): Promise<ImportedConnectionPayload[]> =>
+    window.ipcRenderer.invoke('discover:zerotier', { query });
+
+export interface LanDiscoveryOptions {
+    mdns?: boolean;
+    mdnsSeconds?: number;
+    /** IPv4 network to sweep, e.g. `192.168.1.0/24` (at most a /16). */
+    cidr?: string;
+    port?: number;
+    timeoutMs?: number;
+    concurrency?: number;
+}
+
+export interface DiscoveredLanHost {
+    discoveryId: string;
+    /** `address:port`; repeated events for the same id carry merged details. */
+    id: string;
+    address: string;
+    port: number;
+    name?: string;
+    hostname?: string;
+    banner?: string;
+    sources: Array<'mdns' | 'scan'>;
+}
+
+export interface LanDiscoverySummary {
+    discoveryId: string;
+    hosts: number;
+    errors: string[];
+}
+
+export const startLanDiscoveryIpc = async (options?: LanDiscoveryOptions): Promise<string> =>
+    window.ipcRenderer.invoke('discovery:start', { options });
+
+export const stopLanDiscoveryIpc = async (): Promise<boolean> =>
+    window.ipcRenderer.invoke('discovery:stop');
+
+export function onLanDiscoveryHost(listener: (host: DiscoveredLanHost) => void): () => void {
+    const handler = (_: unknown, host: DiscoveredLanHost) => listener(host);
+    window.ipcRenderer.on('discovery:host', handler);
+    return () => {
+        window.ipcRenderer.off('discovery:host', handler);
+    };
+}
+
+export function onLanDiscoveryDone(listener: (summary: LanDiscoverySummary) => void): () => void {
+    const handler = (_: unknown, summary: LanDiscoverySummary) => listener(summary);
+    window.ipcRenderer.on('discovery:done', handler);
+    return () => {
+        window.ipcRenderer.off('discovery:done', handler);
+    };
+}
+
+export type ConnectionHealthMode = 'tcp' | 'ssh';
+
+export interface ConnectionHealthSettings {
+    enabled: boolean;
+    /** `ssh` also waits for the server's SSH identification line. */
+    mode: ConnectionHealthMode;
+    intervalSecs: number;
+    timeoutMs: number;
+}
+
+export interface ConnectionHealth {
+    connectionId: string;
+    state: 'unknown' | 'up' | 'down';
+    latencyMs?: number;
+    checkedAt?: number;
+    address?: string;
+    banner?: string;
+    error?: string;
+}
+
+export const getConnectionsHealthIpc = async (): Promise<ConnectionHealth[]> =>
+    window.ipcRenderer.invoke('connections:health');
+
+export const getConnectionHealthSettingsIpc = async (): Promise<ConnectionHealthSettings> =>
+    window.ipcRenderer.invoke('connections:healthSettingsGet');
+
+export const saveConnectionHealthSettingsIpc = async (
+    settings: ConnectionHealthSettings,
+): Promise<ConnectionHealthSettings> =>
+    window.ipcRenderer.invoke('connections:healthSettingsSave', { settings });
+
+export function onConnectionHealth(listener: (health: ConnectionHealth) => void): () => void {
+    const handler = (_: unknown, health: ConnectionHealth) => listener(health);
+    window.ipcRenderer.on('connection:health', handler);
+    return () => {
+        window.ipcRenderer.off('connection:health', handler);
+    };
+}
+
+/** Per-host attempt limits; 0 turns a limit off. */
+export interface HostLimitSettings {
+    maxConcurrent: number;
+    maxPerMinute: number;
+    /** Doubles with each consecutive authentication failure. */
+    authFailureCooldownSecs: number;
+}
+
+export interface ConnectQueued {
+    connectionId: string;
+    host: string;
+    reason: 'concurrency' | 'rate-limit' | 'auth-cooldown';
+    waitMs?: number;
+}
+
+export const getHostLimitsIpc = async (): Promise<HostLimitSettings> =>
+    window.ipcRenderer.invoke('ssh:hostLimitsGet');
+
+export const saveHostLimitsIpc = async (settings: HostLimitSettings): Promise<HostLimitSettings> =>
+    window.ipcRenderer.invoke('ssh:hostLimitsSave', { settings });
+
+export function onConnectQueued(listener: (queued: ConnectQueued) => void): () => void {
+    const handler = (_: unknown, queued: ConnectQueued) => listener(queued);
+    window.ipcRenderer.on('ssh:connect-queued', handler);
+    return () => {
+        window.ipcRenderer.off('ssh:connect-queued', handler);
+    };
+}
+
 export const internalizeImportedConnectionsIpc = async (connections: ImportedConnectionPayload[]): Promise<ImportedConnectionPayload[]> =>
     window.ipcRenderer.invoke('ssh:internalize-connections', connections);
 
@@ -84,3 +291,64 @@ export const disconnectVaultBackedIpc = async (): Promise<string[]> =>
     window.ipcRenderer.invoke('ssh:disconnectVaultBacked');
 export const getRemoteCwdIpc = async (connectionId: string): Promise<string> =>
     window.ipcRenderer.invoke('fs:cwd', connectionId);
+
+/** A remote folder whose new files are downloaded automatically. */
+export interface InboxWatch {
+    id: string;
+    connectionId: string;
+    name: string;
+    remoteDir: string;
+    localDir: string;
+    /** Glob on the file name, e.g. `*.csv`. */
+    pattern?: string | null;
+    intervalSecs: number;
+    enabled: boolean;
+    /** Ignore files already present when the watch starts. */
+    skipExisting: boolean;
+}
+
+export interface InboxStatus {
+    watchId: string;
+    connectionId: string;
+    name: string;
+    state: 'idle' | 'watching' | 'error';
+    downloaded: number;
+    lastPolledAt?: number;
+    lastFile?: string;
+    error?: string;
+}
+
+export interface InboxDownload {
+    watchId: string;
+    connectionId: string;
+    remotePath: string;
+    localPath: string;
+    size: number;
+    /** An identical file was already in the local folder. */
+    duplicate: boolean;
+}
+
+export const listInboxWatchesIpc = async (): Promise<InboxWatch[]> =>
+    window.ipcRenderer.invoke('inbox:list');
+
+export const saveInboxWatchesIpc = async (watches: InboxWatch[]): Promise<void> =>
+    window.ipcRenderer.invoke('inbox:save', { watches });
+
+export const getInboxStatusIpc = async (): Promise<InboxStatus[]> =>
+    window.ipcRenderer.invoke('inbox:status');
+
+export function onInboxDownloaded(listener: (download: InboxDownload) => void): () => void {
+    const handler = (_: unknown, download: InboxDownload) => listener(download);
+    window.ipcRenderer.on('inbox:downloaded', handler);
+    return () => {
+        window.ipcRenderer.off('inbox:downloaded', handler);
+    };
+}
+
+export function onInboxStatus(listener: (status: InboxStatus) => void): () => void {
+    const handler = (_: unknown, status: InboxStatus) => listener(status);
+    window.ipcRenderer.on('inbox:status', handler);
+    return () => {
+        window.ipcRenderer.off('inbox:status', handler);
+    };
+}
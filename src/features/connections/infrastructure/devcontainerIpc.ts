@@ -0,0 +1,48 @@
+import type { FileEntry, TextFile, TextFileFormat } from '../../../components/file-manager/types';
+
+/** A dev container attached with `devcontainer:attach`. */
+export interface DevcontainerSession {
+    id: string;
+    connectionId: string;
+    name: string;
+    projectPath: string;
+    configPath: string;
+    containerId: string;
+    workspaceFolder: string;
+    remoteUser: string | null;
+    remoteEnv: Record<string, string>;
+}
+
+export const attachDevcontainerIpc = async (connectionId: string, path: string): Promise<DevcontainerSession> =>
+    window.ipcRenderer.invoke('devcontainer:attach', { connectionId, path });
+
+export const listDevcontainersIpc = async (): Promise<DevcontainerSession[]> =>
+    window.ipcRenderer.invoke('devcontainer:list');
+
+export const detachDevcontainerIpc = async (sessionId: string): Promise<boolean> =>
+    window.ipcRenderer.invoke('devcontainer:detach', { sessionId });
+
+export const devcontainerExecIpc = async (sessionId: string, command: string): Promise<string> =>
+    window.ipcRenderer.invoke('devcontainer:exec', { sessionId, command });
+
+export const devcontainerListDirIpc = async (sessionId: string, path: string): Promise<FileEntry[]> =>
+    window.ipcRenderer.invoke('devcontainer:listDir', { sessionId, path });
+
+export const devcontainerReadFileIpc = async (sessionId: string, path: string): Promise<TextFile> =>
+    window.ipcRenderer.invoke('devcontainer:readFile', { sessionId, path });
+
+export const devcontainerWriteFileIpc = async (
+    sessionId: string,
+    path: string,
+    content: string,
+    format?: TextFileFormat,
+): Promise<void> =>
+    window.ipcRenderer.invoke('devcontainer:writeFile', { sessionId, path, content, format });
+
+export const devcontainerCopyIpc = async (
+    sessionId: string,
+    serverPath: string,
+    containerPath: string,
+    intoContainer: boolean,
+): Promise<void> =>
+    window.ipcRenderer.invoke('devcontainer:copy', { sessionId, serverPath, containerPath, intoContainer });
@@ -0,0 +1,29 @@
+/** A running "use this server as my exit" profile (`exit_node_start`). */
+export interface ExitNode {
+    connectionId: string;
+    socksPort: number;
+    /** Local DNS forwarder port; null when DNS is not forwarded. */
+    dnsPort: number | null;
+    nameserver: string | null;
+    env: Record<string, string>;
+    shellEnvFile: string;
+    powershellEnvFile: string;
+}
+
+export interface ExitNodeOptions {
+    socksPort?: number;
+    dnsPort?: number;
+    nameserver?: string;
+}
+
+export const startExitNode = async (
+    connectionId: string,
+    options: ExitNodeOptions = {},
+): Promise<ExitNode> =>
+    window.ipcRenderer.invoke('tunnel:exitStart', { connectionId, ...options });
+
+export const stopExitNode = async (): Promise<ExitNode | null> =>
+    window.ipcRenderer.invoke('tunnel:exitStop');
+
+export const getExitNode = async (): Promise<ExitNode | null> =>
+    window.ipcRenderer.invoke('tunnel:exitStatus');
@@ -1,8 +1,38 @@
 import type { TunnelConfig } from '../../../components/tunnel/TunnelCard';
 
+/** Machine-readable prefix of port conflicts returned by tunnel commands (`PortConflict` in Rust). */
+export const PORT_IN_USE_PREFIX = 'PORT_IN_USE:';
+
 const PORT_CONFLICT_PATTERN =
     /Port (\d+) is already in use.*?Port (\d+) is available/;
 
+export type PortConflict = {
+    port: number;
+    bindAddress: string;
+    owner: { pid: number; name: string | null } | null;
+    suggestedPort: number | null;
+    message: string;
+};
+
+function errorMessage(error: unknown): string {
+    return error instanceof Error ? error.message : String(error ?? '');
+}
+
+export function parsePortConflict(error: unknown): PortConflict | null {
+    const message = errorMessage(error);
+    if (!message.startsWith(PORT_IN_USE_PREFIX)) return null;
+    try {
+        return JSON.parse(message.slice(PORT_IN_USE_PREFIX.length)) as PortConflict;
+    } catch {
+        return null;
+    }
+}
+
+/** User-facing text for a tunnel command error. */
+export function formatTunnelError(error: unknown): string {
+    return parsePortConflict(error)?.message ?? errorMessage(error);
+}
+
 export type PortConflictSuggestion = {
     tunnel: TunnelConfig;
     currentPort: number;
@@ -13,8 +43,12 @@ export function parsePortConflictError(
     error: unknown,
     tunnel: TunnelConfig,
 ): PortConflictSuggestion | null {
-    const message = error instanceof Error ? error.message : String(error ?? '');
-    const match = message.match(PORT_CONFLICT_PATTERN);
+    const conflict = parsePortConflict(error);
+    if (conflict) {
+        if (conflict.suggestedPort == null) return null;
+        return { tunnel, currentPort: conflict.port, suggestedPort: conflict.suggestedPort };
+    }
+    const match = errorMessage(error).match(PORT_CONFLICT_PATTERN);
     if (!match) return null;
 
     return {
@@ -4,6 +4,7 @@ import {
     type StartTunnelFn,
     type TunnelErrorLogger,
 } from '../../connections/application/tunnelAutoStartService.js';
+import { isTunnelRunning } from '../domain/tunnelTypes.js';
 
 const activeBeforeDisconnect = new Map<string, string[]>();
 
@@ -12,7 +13,7 @@ export function snapshotActiveTunnelsForReconnect(
     tunnels: Array<{ id: string; status?: string }>,
 ): void {
     const activeIds = tunnels
-        .filter((tunnel) => tunnel.status === 'active')
+        .filter((tunnel) => isTunnelRunning(tunnel.status))
         .map((tunnel) => tunnel.id);
 
     if (activeIds.length > 0) {
@@ -1,5 +1,12 @@
 /** Canonical tunnel type union — keep in sync with `SavedTunnel.tunnel_type` (Rust). */
-export type TunnelType = 'local' | 'remote' | 'dynamic';
+export type TunnelType = 'local' | 'remote' | 'dynamic' | 'remote-dynamic';
+
+/** Runtime status from `tunnel:status-change`; a `degraded` forward is still running. */
+export type TunnelStatus = 'active' | 'degraded' | 'error' | 'stopped';
+
+export function isTunnelRunning(status: string | undefined): boolean {
+    return status === 'active' || status === 'degraded';
+}
 
 /** Sentinel values persisted for dynamic (SOCKS) forwards — no fixed remote target. */
 export const DYNAMIC_REMOTE_HOST = '*';
@@ -11,7 +18,7 @@ export function isDynamicTunnel(type: TunnelType): boolean {
 
 /** Port field used for local-side binds (local + dynamic). */
 export function localBindPortKey(type: TunnelType): 'localPort' | 'remotePort' {
-    return type === 'remote' ? 'remotePort' : 'localPort';
+    return type === 'remote' || type === 'remote-dynamic' ? 'remotePort' : 'localPort';
 }
 
 export function defaultTunnelName(
@@ -23,6 +30,9 @@ export function defaultTunnelName(
     if (type === 'dynamic') {
         return `SOCKS ${localPort}`;
     }
+    if (type === 'remote-dynamic') {
+        return `Reverse SOCKS ${remotePort}`;
+    }
     if (type === 'local') {
         return `Local ${localPort} -> ${remoteHost}:${remotePort}`;
     }
@@ -4,6 +4,7 @@ export const TUNNEL_TYPE_META: Record<TunnelType, { label: string; flag: string
     local: { label: 'Local', flag: '-L' },
     remote: { label: 'Remote', flag: '-R' },
     dynamic: { label: 'Dynamic', flag: '-D' },
+    'remote-dynamic': { label: 'Reverse SOCKS', flag: '-R' },
 };
 
 export function tunnelHostDisplayLabel(hostLabel: string | undefined): string | null {
@@ -51,6 +52,16 @@ export function formatTunnelFlow(
         };
     }
 
+    if (tunnel.type === 'remote-dynamic') {
+        return {
+            source: String(tunnel.remotePort),
+            targetHost: 'SOCKS via this machine',
+            targetPort: null,
+            targetTagged: false,
+            inbound: true,
+        };
+    }
+
     if (tunnel.type === 'local') {
         const endpoint = formatTunnelServiceEndpoint(tunnel.remoteHost, tunnel.remotePort);
         return {
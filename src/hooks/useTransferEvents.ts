@@ -1,6 +1,7 @@
 import { useEffect } from 'react';
 import { listen } from '@tauri-apps/api/event';
 import { useAppStore } from '../store/useAppStore';
+import { formatBytes } from '../lib/utils';
 
 interface TransferProgressPayload {
     id: string;
@@ -11,6 +12,7 @@ interface TransferProgressPayload {
 interface TransferSuccessPayload {
     id: string;
     destination_connection_id: string;
+    unchanged?: { files: number; bytesSaved: number };
 }
 
 interface TransferErrorPayload {
@@ -23,6 +25,7 @@ export function useTransferEvents() {
     const completeTransfer = useAppStore(state => state.completeTransfer);
     const failTransfer = useAppStore(state => state.failTransfer);
     const refreshFiles = useAppStore(state => state.refreshFiles);
+    const showToast = useAppStore(state => state.showToast);
 
     useEffect(() => {
         const unlistenProgress = listen<TransferProgressPayload>('transfer-progress', (event) => {
@@ -35,8 +38,11 @@ export function useTransferEvents() {
         });
 
         const unlistenSuccess = listen<TransferSuccessPayload>('transfer-success', (event) => {
-            const { id, destination_connection_id } = event.payload;
+            const { id, destination_connection_id, unchanged } = event.payload;
             completeTransfer(id);
+            if (unchanged && unchanged.files > 0) {
+                showToast('info', `Skipped ${unchanged.files} unchanged file${unchanged.files === 1 ? '' : 's'} (${formatBytes(unchanged.bytesSaved)} saved)`);
+            }
             if (destination_connection_id) {
                 refreshFiles(destination_connection_id);
             }
@@ -52,5 +58,5 @@ export function useTransferEvents() {
             unlistenSuccess.then(f => f());
             unlistenError.then(f => f());
         };
-    }, [updateProgress, completeTransfer, failTransfer, refreshFiles]);
+    }, [updateProgress, completeTransfer, failTransfer, refreshFiles, showToast]);
 }
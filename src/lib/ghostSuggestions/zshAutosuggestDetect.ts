@@ -47,11 +47,11 @@ export function zshInitEnablesAutosuggestions(content: string): boolean {
 
 async function readZshInitFile(connectionId: string, path: string): Promise<string | null> {
   try {
-    const content = await window.ipcRenderer.invoke('fs_read_file', {
+    const file = await window.ipcRenderer.invoke('fs_read_file', {
       connectionId,
       path,
     });
-    return typeof content === 'string' ? content : null;
+    return typeof file?.content === 'string' ? file.content : null;
   } catch {
     return null;
   }
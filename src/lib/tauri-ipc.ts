@@ -143,10 +143,17 @@ const ipcRenderer = {
       'terminal:create': 'terminal_create',
       'terminal:close': 'terminal_close',
       'terminal:has-active-processes': 'terminal_has_active_processes',
+      'terminal:inputHistory': 'terminal_input_history',
+      'state:snapshot': 'state_snapshot',
+      'network:killSwitchGet': 'network_kill_switch_get',
+      'network:killSwitchSet': 'network_kill_switch_set',
       'connections:get': 'connections_get',
+      'connections:query': 'connections_query',
       'connections:save': 'connections_save',
       'connections:exportToFile': 'connections_export_to_file',
       'connections:importFromFile': 'connections_import_from_file',
+      'connections:exportBundle': 'connections_export',
+      'connections:importBundle': 'connections_import',
       'fs_list': 'fs_list',
       'fs_read_file': 'fs_read_file',
       'fs_write_file': 'fs_write_file',
@@ -156,14 +163,55 @@ const ipcRenderer = {
       'fs_delete': 'fs_delete',
       'fs_copy': 'fs_copy',
       'fs_exists': 'fs_exists',
+      'fs_versions': 'fs_versions',
+      'fs_version_read': 'fs_version_read',
+      'fs_version_restore': 'fs_version_restore',
+      'fs_chmod_bulk': 'fs_chmod_bulk',
+      'fs_checksum': 'fs_checksum',
       'tunnel:getAll': 'tunnel_get_all',
       'tunnel:startLocal': 'tunnel_start_local',
+      'tunnel:checkPort': 'tunnel_check_port',
+      'tunnel:suggestPort': 'tunnel_suggest_port',
+      'tunnel:logs': 'tunnel_logs',
+      'tunnel:watchConnections': 'tunnel_watch_connections',
       'tunnel:start_local': 'tunnel_start_local', // Add snake_case mapping
       'tunnel:startRemote': 'tunnel_start_remote',
       'tunnel:start_remote': 'tunnel_start_remote', // Add snake_case mapping
+      'tunnel:startRemoteDynamic': 'tunnel_start_remote_dynamic',
+      'tunnel:exitStart': 'exit_node_start',
+      'tunnel:exitStop': 'exit_node_stop',
+      'tunnel:exitStatus': 'exit_node_status',
       'tunnel:start': 'tunnel_start',
       'tunnel:stop': 'tunnel_stop',
       'ssh:exec': 'ssh_exec',
+      'ssh:exec-batch': 'ssh_exec_batch',
+      'snippets:run': 'snippets_run',
+      'snippets:importHistory': 'snippets_import_history',
+      'snippets:scheduleStatus': 'snippets_schedule_status',
+      'snippets:scheduleHistory': 'snippets_schedule_history',
+      'devcontainer:attach': 'devcontainer_attach',
+      'devcontainer:list': 'devcontainer_list',
+      'devcontainer:detach': 'devcontainer_detach',
+      'devcontainer:terminal': 'devcontainer_terminal',
+      'devcontainer:exec': 'devcontainer_exec',
+      'devcontainer:listDir': 'devcontainer_list_dir',
+      'devcontainer:readFile': 'devcontainer_read_file',
+      'devcontainer:writeFile': 'devcontainer_write_file',
+      'devcontainer:copy': 'devcontainer_copy',
+      'search:global': 'global_search',
+      'search:cancel': 'global_search_cancel',
+      'probes:list': 'probes_list',
+      'probes:save': 'probes_save',
+      'probes:status': 'probes_status',
+      'inbox:list': 'inbox_list',
+      'inbox:save': 'inbox_save',
+      'inbox:status': 'inbox_status',
+      'audit:query': 'audit_query',
+      'connections:health': 'connections_health',
+      'connections:healthSettingsGet': 'connections_health_settings_get',
+      'connections:healthSettingsSave': 'connections_health_settings_save',
+      'ssh:hostLimitsGet': 'ssh_host_limits_get',
+      'ssh:hostLimitsSave': 'ssh_host_limits_save',
       'ssh:test': 'ssh_test_connection',
 
       'ssh:extract-pem': 'ssh_extract_pem',
@@ -172,6 +220,16 @@ const ipcRenderer = {
       'ssh:importConfigFromFile': 'ssh_import_config_from_file',
       'ssh:importConfigFromText': 'ssh_import_config_from_text',
       'ssh:importConfigBySource': 'ssh_import_config_by_source',
+      'ssh:importPutty': 'import_putty_sessions',
+      'ssh:exportConfig': 'ssh_export_config',
+      'cloud:importAws': 'cloud_import_aws',
+      'cloud:import': 'cloud_import',
+      'discover:tailscale': 'discover_tailscale',
+      'discover:zerotier': 'discover_zerotier',
+      'discovery:start': 'discovery_start',
+      'discovery:stop': 'discovery_stop',
+      'cloud:sourcesList': 'cloud_sources_list',
+      'cloud:sourcesRemove': 'cloud_sources_remove',
       'ssh:readConfig': 'ssh_import_config',
       'ssh:internalize-connections': 'ssh_internalize_connections',
       'ssh:disconnectVaultBacked': 'ssh_disconnect_vault_backed',
@@ -199,9 +257,21 @@ const ipcRenderer = {
       'shell:getConnectionShells': 'shell_get_connection_shells',
       'plugins:load': 'plugins_load',
       'plugins:install_local': 'plugins_install_local',
+      'plugins:registryList': 'plugins_registry_list',
+      'plugins:registrySearch': 'plugins_registry_search',
+      'plugins:installById': 'plugins_install_by_id',
+      'plugins:wasmCall': 'plugin_wasm_call',
+      'plugins:getPermissions': 'plugins_permissions_get',
+      'plugins:setPermission': 'plugins_permissions_set',
+      'plugins:settingsGet': 'plugin_settings_get',
+      'plugins:settingsSet': 'plugin_settings_set',
       'app:getExeDir': 'app_get_exe_dir',
       'ai:translate': 'ai_translate',
       'ai:checkOllama': 'ai_check_ollama',
+      'ai:semanticSearch': 'semantic_search',
+      'ai:semanticIndexClear': 'semantic_index_clear',
+      'ai:terminalRun': 'ai_terminal_run',
+      'ai:dangerousConfirm': 'ai_dangerous_command_confirm',
     };
 
     const tauriCommand = channelMap[channel] || channel.replace(':', '_');
@@ -285,7 +355,8 @@ const ipcRenderer = {
 
       if (channel === 'update:check') {
         try {
-          const update = await check();
+          const proxy = await invoke<string | null>('network_proxy_url').catch(() => null);
+          const update = await check(proxy ? { proxy } : undefined);
           if (update?.available) {
             currentUpdate = update;
             return {
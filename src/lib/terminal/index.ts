@@ -90,6 +90,8 @@ export {
   flushPendingInput,
   handleTerminalReady,
   queueTerminalInput,
+  runAiCommand,
+  writePluginTerminalInput,
 } from './inputPipeline.js';
 
 export {
@@ -11,6 +11,7 @@ import {
 import { terminalCache } from './terminalCache.js';
 import { touchTerminalActivity } from './terminalActivity.js';
 import { clearIdleHostSuspendNotice } from './terminalIdleSuspendNotice.js';
+import type { RiskLevel } from '../../ai/types/common.js';
 
 const INPUT_BATCH_MS = 4;
 const INPUT_FLUSH_THRESHOLD = 64;
@@ -87,6 +88,35 @@ export function queueTerminalInput(termId: string | null | undefined, data: stri
   }
 }
 
+/**
+ * Runs an AI-suggested command in a terminal. The backend holds back dangerous
+ * ones (and any the model rated `dangerous`) until the host is confirmed in
+ * `DangerousCommandDialog`, so `APPROVAL_REQUIRED` is not an error here.
+ */
+export function runAiCommand(termId: string, command: string, safety?: RiskLevel): void {
+  window.ipcRenderer.invoke('ai:terminalRun', { termId, command, safety, execute: true })
+    .catch((error: unknown) => {
+      const message = String(error);
+      if (!message.startsWith('APPROVAL_REQUIRED')) {
+        useAppStore.getState().showToast('error', `Failed to run command: ${message}`);
+      }
+    });
+}
+
+/**
+ * Types `data` into a terminal for a plugin. Goes through `plugin_terminal_write`,
+ * which needs the plugin's `terminal` grant and applies the dangerous-command gate.
+ */
+export function writePluginTerminalInput(pluginId: string, termId: string, data: string): void {
+  window.ipcRenderer.invoke('plugin_terminal_write', { pluginId, termId, data })
+    .catch((error: unknown) => {
+      const message = String(error);
+      if (!message.startsWith('APPROVAL_REQUIRED')) {
+        console.warn(`[Plugins] ${pluginId} could not write to the terminal:`, message);
+      }
+    });
+}
+
 /** Called when `terminal-ready` arrives for the active generation. */
 export function handleTerminalReady(termId: string, generation: number): boolean {
   const cached = terminalCache.get(termId);
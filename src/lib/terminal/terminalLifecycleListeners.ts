@@ -20,6 +20,12 @@ export function terminalExitGenerationMatches(entry: NonNullable<ReturnType<type
 export interface TerminalLifecycleEvent {
   generation: number;
   exit_code?: number;
+  /** Signal name without the `SIG` prefix when the shell was killed. */
+  signal?: string;
+  message?: string;
+  /** Present on `terminal-exit-*`. */
+  reason?: 'exited' | 'signaled' | 'closed' | 'connectionLost';
+  connection_lost?: boolean;
 }
 
 /** Attaches generation-gated ready and exit listeners once per cached terminal. */
@@ -318,6 +318,7 @@ export const createConnectionSlice: StateCreator<AppStore, [], [], ConnectionSli
                             const existing = nextConnections[targetIndex];
                             const preservedMetadata: Partial<Connection> = {
                                 isFavorite: existing.isFavorite,
+                                color: existing.color,
                                 pinnedFeatures: existing.pinnedFeatures,
                                 icon: existing.icon,
                                 lastConnected: existing.lastConnected,
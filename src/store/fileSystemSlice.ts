@@ -310,7 +310,8 @@ export const createFileSystemSlice: StateCreator<AppStore, [], [], FileSystemSli
                     id: connectionId,
                     localPath,
                     remotePath,
-                    transferId // Pass transferId to backend
+                    transferId, // Pass transferId to backend
+                    skipUnchanged: true
                 }).catch((err: any) => {
                     console.error('Upload start failed:', err);
                     get().failTransfer(transferId, err.message || String(err));
@@ -24,6 +24,8 @@ export interface AppSettings {
     accentColor?: string | null;
     editor: {
         defaultProvider: string;
+        /** Keep a restorable copy of a remote file before each save (see `fs_versions`). */
+        snapshotOnSave?: boolean;
     };
     windowOpacity: number;
     enableVibrancy: boolean;
@@ -6,7 +6,26 @@ export interface Snippet {
     name: string;
     command: string;
     category?: string;
+    tags?: string[];
     connectionId?: string;
+    /** `/`-separated folder path, e.g. `deploy/k8s`. */
+    folder?: string;
+    /** Offered only on these connections (`local` for local terminals). */
+    connectionIds?: string[];
+    /** Offered only on hosts whose detected OS matches, e.g. `debian`, `rhel`, `linux`, `macos`. */
+    os?: string[];
+    /** Runs the snippet on a connection at set times. */
+    schedule?: SnippetSchedule;
+}
+
+export interface SnippetSchedule {
+    /** Five-field cron expression, or `@hourly`, `@daily`, `@weekly`, `@monthly`, `@yearly`. */
+    cron: string;
+    connectionId: string;
+    enabled?: boolean;
+    /** Zone the cron expression is written in, e.g. `-new Date().getTimezoneOffset()`. */
+    utcOffsetMinutes?: number;
+    timeoutSecs?: number;
 }
 
 export interface SnippetsSlice {
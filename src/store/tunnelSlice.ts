@@ -1,17 +1,19 @@
 import { StateCreator } from 'zustand';
 import type { AppStore } from './useAppStore';
+import { isTunnelRunning, type TunnelStatus } from '../features/tunnels/domain/tunnelTypes';
+import { formatTunnelError } from '../features/tunnels/application/tunnelPortConflict';
 
 export interface TunnelConfig {
     id: string;
     connectionId: string;
     name: string;
-    type: 'local' | 'remote' | 'dynamic';
+    type: 'local' | 'remote' | 'dynamic' | 'remote-dynamic';
     localPort: number;
     remoteHost: string;
     remotePort: number;
     bindToAny?: boolean;
     bindAddress?: string;
-    status: 'active' | 'error' | 'stopped';
+    status: TunnelStatus;
     autoStart?: boolean;
     error?: string;
     group?: string;
@@ -156,7 +158,7 @@ export const createTunnelSlice: StateCreator<AppStore, [], [], TunnelSlice> = (s
             // Status update will come from backend event
         } catch (error: any) {
             console.error('Failed to start tunnel:', error);
-            get().updateTunnelStatus(id, connectionId, 'error', error.message);
+            get().updateTunnelStatus(id, connectionId, 'error', formatTunnelError(error));
             throw error;
         }
     },
@@ -204,7 +206,7 @@ export const createTunnelSlice: StateCreator<AppStore, [], [], TunnelSlice> = (s
                 tunnels: {
                     ...state.tunnels,
                     [connectionId]: currentList.map((tunnel) =>
-                        tunnel.status === 'active'
+                        isTunnelRunning(tunnel.status)
                             ? { ...tunnel, status: 'stopped' as const, error: undefined }
                             : tunnel,
                     ),
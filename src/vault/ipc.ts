@@ -69,6 +69,16 @@ export interface SecureToVaultResult {
   backupPath?: string;
 }
 
+export interface AtRestSettings {
+  enabled: boolean;
+  /** 0 disables the idle relock. */
+  idleLockMinutes: number;
+}
+
+export interface AtRestStatus extends AtRestSettings {
+  unlocked: boolean;
+}
+
 export const vaultIpc = {
   status: (): Promise<VaultStatus> =>
     invoke('vault_status'),
@@ -145,6 +155,12 @@ export const vaultIpc = {
   importVault: (srcPath: string): Promise<VaultStatus> =>
     invoke('vault_import', { args: { src_path: srcPath } }),
 
+  atRestStatus: (): Promise<AtRestStatus> =>
+    invoke('vault_at_rest_status'),
+
+  atRestConfigure: (settings: AtRestSettings): Promise<AtRestStatus> =>
+    invoke('vault_at_rest_configure', { settings }),
+
   itemRevisionHistory: (itemId: string): Promise<RevisionMeta[]> =>
     invoke('vault_item_revision_history', { args: { item_id: itemId } }),
 
@@ -427,6 +427,30 @@ runTest('buildConnectConfig builds jump-host chain and rejects simple cycle', ()
   assert.equal(cyclicConfig, null);
 });
 
+runTest('buildConnectConfig resolves fallback routes and their jump hosts', () => {
+  const connections = [
+    {
+      id: 'db', name: 'DB', host: '10.0.0.5', port: 22, username: 'u', password: 'p', status: 'disconnected',
+      fallbacks: [
+        { label: ' VPN ', host: '100.64.0.5' },
+        { port: 2222, jumpServerId: 'bastion' },
+      ],
+    },
+    { id: 'bastion', name: 'Bastion', host: 'bastion', port: 22, username: 'u', password: 'p', status: 'disconnected' },
+  ];
+
+  const config = buildConnectConfig(connections, 'db');
+  assert.equal(config?.jump_host, null);
+  assert.deepEqual(config?.fallbacks?.[0], { label: 'VPN', host: '100.64.0.5', port: undefined, jumpHost: null });
+  assert.equal(config?.fallbacks?.[1].port, 2222);
+  assert.equal(config?.fallbacks?.[1].jumpHost?.id, 'bastion');
+
+  const missingJump = [{ ...connections[0], fallbacks: [{ jumpServerId: 'gone' }, { host: '100.64.0.5' }] }];
+  const withStaleFallback = buildConnectConfig(missingJump, 'db');
+  assert.equal(withStaleFallback?.host, '10.0.0.5');
+  assert.deepEqual(withStaleFallback?.fallbacks?.map((fallback) => fallback.host), ['100.64.0.5']);
+});
+
 runTest('buildConnectConfig includes stable credential id for vault auth', () => {
   const connections = [{
     id: 'vaulted',